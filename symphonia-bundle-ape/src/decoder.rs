@@ -0,0 +1,243 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::checksum::Crc32Le;
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_MONKEYS_AUDIO};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BufReader, Monitor, ReadBytes};
+use symphonia_core::support_codec;
+
+use log::warn;
+
+use super::entropy::{decode_value_3900, decode_value_3990, RangeDecoder, Rice};
+use super::filter::Filters;
+use super::header::{ApeCodecConfig, MAX_BLOCKS_PER_FRAME};
+
+/// Both channels of the frame are silent. For mono frames, any silence flag indicates silence.
+const FRAME_FLAG_STEREO_SILENCE: u32 = 0x3;
+/// The frame is mono, and should be duplicated to both channels.
+const FRAME_FLAG_PSEUDO_STEREO: u32 = 0x4;
+
+/// The oldest file version that can be decoded.
+const MIN_DECODABLE_VERSION: u16 = 3950;
+
+/// Monkey's Audio decoder.
+pub struct ApeDecoder {
+    params: CodecParameters,
+    config: ApeCodecConfig,
+    /// The number of bits per sample.
+    bits_per_sample: u32,
+    filters: Filters,
+    /// The decoded samples of each channel.
+    decoded: [Vec<i32>; 2],
+    buf: AudioBuffer<i32>,
+}
+
+impl ApeDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let n_blocks = packet.dur as usize;
+
+        if n_blocks > self.buf.capacity() {
+            return decode_error("ape: frame exceeds the maximum number of blocks");
+        }
+
+        let mut reader = BufReader::new(packet.buf());
+
+        // The CRC of the frame. If the most-significant bit is set, the frame flags follow.
+        let mut crc = reader.read_be_u32()?;
+        let mut frame_flags = 0;
+
+        if self.config.version > 3820 && crc & 0x8000_0000 != 0 {
+            crc &= !0x8000_0000;
+            frame_flags = reader.read_be_u32()?;
+        }
+
+        let mut dec = RangeDecoder::new(&packet.buf()[reader.pos() as usize..]);
+
+        let num_channels = self.buf.spec().channels.count();
+
+        let [y, x] = &mut self.decoded;
+
+        let y = &mut y[..n_blocks];
+        let x = &mut x[..n_blocks];
+
+        // Each frame is coded independently.
+        self.filters.reset();
+
+        let decode_value =
+            if self.config.version >= 3990 { decode_value_3990 } else { decode_value_3900 };
+
+        if num_channels == 1 || frame_flags & FRAME_FLAG_PSEUDO_STEREO != 0 {
+            if frame_flags & FRAME_FLAG_STEREO_SILENCE != 0 {
+                y.iter_mut().for_each(|s| *s = 0);
+            }
+            else {
+                let mut rice_y = Rice::default();
+
+                for s in y.iter_mut() {
+                    *s = decode_value(&mut dec, &mut rice_y);
+                }
+
+                self.filters.decode_mono(y);
+            }
+
+            // Pseudo-stereo frames are duplicated to both channels.
+            x.copy_from_slice(y);
+        }
+        else if frame_flags & FRAME_FLAG_STEREO_SILENCE == FRAME_FLAG_STEREO_SILENCE {
+            y.iter_mut().for_each(|s| *s = 0);
+            x.iter_mut().for_each(|s| *s = 0);
+        }
+        else {
+            let mut rice_y = Rice::default();
+            let mut rice_x = Rice::default();
+
+            for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+                *y = decode_value(&mut dec, &mut rice_y);
+                *x = decode_value(&mut dec, &mut rice_x);
+            }
+
+            self.filters.decode_stereo(y, x);
+
+            // Decorrelate the channels. Y is the difference of the right and left channels, while
+            // X is the left channel plus half the difference.
+            for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+                let left = x.wrapping_sub(*y / 2);
+                let right = left.wrapping_add(*y);
+
+                *y = left;
+                *x = right;
+            }
+        }
+
+        if dec.is_overread() {
+            return decode_error("ape: frame data is truncated");
+        }
+
+        // Verify the CRC of the frame. The CRC is calculated over the samples as they would be
+        // stored in a WAVE file.
+        let mut crc32 = Crc32Le::new(0xffff_ffff);
+
+        for i in 0..n_blocks {
+            for ch in self.decoded[..num_channels].iter() {
+                let sample = ch[i];
+
+                match self.bits_per_sample {
+                    8 => crc32.process_byte(sample.wrapping_add(0x80) as u8),
+                    16 => crc32.process_buf_bytes(&(sample as i16).to_le_bytes()),
+                    _ => crc32.process_buf_bytes(&sample.to_le_bytes()[..3]),
+                }
+            }
+        }
+
+        if !crc32.crc() >> 1 != crc {
+            warn!("ape: frame crc mismatch");
+        }
+
+        // Scale the samples to 32-bits.
+        let shift = 32 - self.bits_per_sample;
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(n_blocks));
+
+        for (ch, decoded) in self.decoded[..num_channels].iter().enumerate() {
+            for (out, &s) in self.buf.chan_mut(ch).iter_mut().zip(decoded.iter()) {
+                *out = s << shift;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for ApeDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports Monkey's Audio.
+        if params.codec != CODEC_TYPE_MONKEYS_AUDIO {
+            return unsupported_error("ape: invalid codec type");
+        }
+
+        // The extra data contains the file version, compression level, and format flags.
+        let config = match params.extra_data.as_ref() {
+            Some(buf) => ApeCodecConfig::read(buf)?,
+            _ => return unsupported_error("ape: missing extra data"),
+        };
+
+        if config.version < MIN_DECODABLE_VERSION {
+            return unsupported_error("ape: file versions prior to 3.95 are not supported");
+        }
+
+        let bits_per_sample = match params.bits_per_sample {
+            Some(bps @ (8 | 16 | 24)) => bps,
+            Some(_) => return unsupported_error("ape: unsupported bits per sample"),
+            None => return decode_error("ape: bits per sample is required"),
+        };
+
+        let channels = match params.channels.map(|channels| channels.count()) {
+            Some(1) => Channels::FRONT_LEFT,
+            Some(2) => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            Some(_) => return unsupported_error("ape: unsupported number of channels"),
+            None => return decode_error("ape: channels are required"),
+        };
+
+        let sample_rate = match params.sample_rate {
+            Some(sample_rate) => sample_rate,
+            None => return decode_error("ape: sample rate is required"),
+        };
+
+        let max_blocks = match params.max_frames_per_packet {
+            Some(max_blocks) if max_blocks <= u64::from(MAX_BLOCKS_PER_FRAME) => max_blocks,
+            Some(_) => return unsupported_error("ape: too many blocks per frame"),
+            None => u64::from(MAX_BLOCKS_PER_FRAME),
+        };
+
+        let spec = SignalSpec::new(sample_rate, channels);
+
+        Ok(ApeDecoder {
+            params: params.clone(),
+            config,
+            bits_per_sample,
+            filters: Filters::new(config.version, config.compression_level),
+            decoded: [vec![0; max_blocks as usize], vec![0; max_blocks as usize]],
+            buf: AudioBuffer::new(max_blocks, spec),
+        })
+    }
+
+    fn reset(&mut self) {
+        // Nothing to do. Each frame is decoded independently.
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_MONKEYS_AUDIO, "ape", "Monkey's Audio")]
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
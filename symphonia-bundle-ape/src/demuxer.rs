@@ -0,0 +1,344 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_MONKEYS_AUDIO};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::apev2;
+
+use log::{debug, warn};
+
+use super::header::{ApeHeader, APE_STREAM_MARKER};
+
+/// The maximum length of a frame.
+const MAX_FRAME_LEN: u64 = 64 * 1024 * 1024;
+
+/// The length of an ID3v1 tag.
+const ID3V1_TAG_LEN: u64 = 128;
+
+/// The location of a frame in the stream.
+struct FrameInfo {
+    /// The position of the 32-bit word containing the first byte of the frame.
+    pos: u64,
+    /// The length of the frame, in bytes, starting from `pos`.
+    len: u64,
+    /// The number of bytes to skip in the first word of the frame.
+    skip: usize,
+}
+
+/// Monkey's Audio (APE) format reader.
+///
+/// `ApeReader` implements a demuxer for the native Monkey's Audio file format. Each packet contains
+/// one frame. Since Monkey's Audio stores frames as a stream of 32-bit little-endian words, the
+/// packet data is converted to byte order, and starts at the first byte of the frame.
+pub struct ApeReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    frames: Vec<FrameInfo>,
+    blocks_per_frame: u32,
+    final_frame_blocks: u32,
+    next_frame: usize,
+}
+
+impl QueryDescriptor for ApeReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "ape",
+            "Monkey's Audio",
+            &["ape"],
+            &["audio/ape", "audio/x-ape"],
+            &[b"MAC "]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Reads the APE tag at the end of the stream, if present, and returns the position of the end of
+/// the audio data. The ID3v1 tag, if present, is skipped.
+fn read_tags(reader: &mut MediaSourceStream, metadata: &mut MetadataLog) -> Result<Option<u64>> {
+    let mut end = match reader.byte_len() {
+        Some(len) if reader.is_seekable() => len,
+        _ => return Ok(None),
+    };
+
+    if end >= ID3V1_TAG_LEN {
+        reader.seek(SeekFrom::Start(end - ID3V1_TAG_LEN))?;
+
+        if reader.read_triple_bytes()? == *b"TAG" {
+            end -= ID3V1_TAG_LEN;
+        }
+    }
+
+    let footer = match apev2::read_ape_tag_footer(reader, end)? {
+        Some(footer) => footer,
+        None => return Ok(Some(end)),
+    };
+
+    let mut builder = MetadataBuilder::new();
+
+    match apev2::read_ape_tag_items(reader, &footer, &mut builder) {
+        Ok(_) => metadata.push(builder.metadata()),
+        Err(err) => warn!("ape: ignoring invalid ape tag: {}", err),
+    }
+
+    Ok(Some(end - footer.tag_len()))
+}
+
+impl ApeReader {
+    /// Positions the reader at the given position in the stream.
+    fn seek_to(&mut self, pos: u64) -> Result<()> {
+        if self.reader.pos() == pos {
+            return Ok(());
+        }
+
+        // Frames may share a word, so the next frame may start slightly before the current
+        // position. Try to seek within the buffer first.
+        let buffered_pos = self.reader.seek_buffered(pos);
+
+        if buffered_pos != pos {
+            if self.reader.is_seekable() {
+                self.reader.seek(SeekFrom::Start(pos))?;
+            }
+            else if pos > buffered_pos {
+                self.reader.ignore_bytes(pos - buffered_pos)?;
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FormatReader for ApeReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // All positions are relative to the start of the stream marker.
+        let start_pos = source.pos();
+
+        let marker = source.read_quad_bytes()?;
+
+        if marker != APE_STREAM_MARKER {
+            return unsupported_error("ape: missing ape stream marker");
+        }
+
+        let header = ApeHeader::read(&mut source)?;
+
+        let first_frame_pos = start_pos + header.first_frame_pos;
+
+        // Read the tags at the end of the stream, and then return to the first frame.
+        let mut metadata = MetadataLog::default();
+
+        let data_end = read_tags(&mut source, &mut metadata)?;
+
+        if source.is_seekable() {
+            source.seek(SeekFrom::Start(first_frame_pos))?;
+        }
+        else if let Some(len) = first_frame_pos.checked_sub(source.pos()) {
+            source.ignore_bytes(len)?;
+        }
+        else {
+            return decode_error("ape: invalid first frame position");
+        }
+
+        // Calculate the location of each frame. The seek table contains the position of each
+        // frame, but frames are stored in 32-bit words, so a frame may start in the middle of a
+        // word.
+        let total_frames = header.seek_table.len();
+
+        let mut positions = Vec::with_capacity(total_frames);
+
+        positions.push(first_frame_pos);
+        positions.extend(header.seek_table[1..].iter().map(|&pos| start_pos + u64::from(pos)));
+
+        if positions.windows(2).any(|w| w[0] > w[1]) {
+            return decode_error("ape: invalid seek table");
+        }
+
+        let mut frames = Vec::with_capacity(total_frames);
+
+        for (i, &pos) in positions.iter().enumerate() {
+            let skip = (pos - first_frame_pos) & 0x3;
+
+            let len = match positions.get(i + 1) {
+                Some(&next_pos) => next_pos - pos,
+                None => {
+                    // The length of the final frame is the remainder of the audio data. If that is
+                    // not known, use an upper bound.
+                    let end =
+                        data_end.map(|end| end.saturating_sub(u64::from(header.wav_tail_len)));
+
+                    match end {
+                        Some(end) if end > pos => (end - pos) & !0x3,
+                        _ => 8 * u64::from(header.final_frame_blocks),
+                    }
+                }
+            };
+
+            frames.push(FrameInfo {
+                pos: pos - skip,
+                len: (len + skip + 3) & !0x3,
+                skip: skip as usize,
+            });
+        }
+
+        let channels = match header.channels {
+            1 => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        };
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_MONKEYS_AUDIO)
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_n_frames(header.n_frames())
+            .with_max_frames_per_packet(u64::from(header.blocks_per_frame))
+            .with_bits_per_sample(header.bits_per_sample)
+            .with_channels(channels)
+            .with_extra_data(header.config.to_extra_data());
+
+        Ok(ApeReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            frames,
+            blocks_per_frame: header.blocks_per_frame,
+            final_frame_blocks: header.final_frame_blocks,
+            next_frame: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let frame = match self.frames.get(self.next_frame) {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        if frame.len > MAX_FRAME_LEN {
+            return decode_error("ape: frame is too large");
+        }
+
+        let (pos, len, skip) = (frame.pos, frame.len as usize, frame.skip);
+
+        self.seek_to(pos)?;
+
+        // The final frame may be truncated.
+        let mut buf = vec![0; len];
+        let mut read = 0;
+
+        while read < len {
+            match self.reader.read(&mut buf[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+
+        if read <= skip {
+            return end_of_stream_error();
+        }
+
+        buf.truncate((read + 3) & !0x3);
+
+        // Convert the 32-bit little-endian words into a byte stream, and then skip to the start of
+        // the frame.
+        for word in buf.chunks_exact_mut(4) {
+            word.reverse();
+        }
+
+        buf.drain(..skip);
+
+        let ts = self.next_frame as u64 * u64::from(self.blocks_per_frame);
+
+        let dur = if self.next_frame + 1 == self.frames.len() {
+            self.final_frame_blocks
+        }
+        else {
+            self.blocks_per_frame
+        };
+
+        self.next_frame += 1;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, u64::from(dur), buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // All frames, except the last, have the same number of blocks. Therefore, the frame
+        // containing the desired timestamp can be found directly.
+        let frame_idx =
+            ((required_ts / u64::from(self.blocks_per_frame)) as usize).min(self.frames.len() - 1);
+
+        self.seek_to(self.frames[frame_idx].pos)?;
+
+        self.next_frame = frame_idx;
+
+        let actual_ts = frame_idx as u64 * u64::from(self.blocks_per_frame);
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts - actual_ts);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,245 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The range decoder, and the adaptive Rice-like entropy coding of residuals.
+
+const CODE_BITS: u32 = 32;
+const TOP_VALUE: u32 = 1 << (CODE_BITS - 1);
+const EXTRA_BITS: u32 = (CODE_BITS - 2) % 8 + 1;
+const BOTTOM_VALUE: u32 = TOP_VALUE >> 8;
+
+/// The number of symbols of the overflow model.
+const MODEL_ELEMENTS: u32 = 64;
+
+/// The cumulative frequencies of the overflow model for file versions prior to 3.98.
+#[rustfmt::skip]
+const COUNTS_3970: [u32; 22] = [
+        0, 14824, 28224, 39348, 47855, 53994, 58171, 60926,
+    62682, 63786, 64463, 64878, 65126, 65276, 65365, 65419,
+    65450, 65469, 65480, 65487, 65491, 65493,
+];
+
+/// The frequencies of the overflow model for file versions prior to 3.98.
+#[rustfmt::skip]
+const COUNTS_DIFF_3970: [u32; 21] = [
+    14824, 13400, 11124, 8507, 6139, 4177, 2755, 1756,
+     1104,   677,   415,  248,  150,   89,   54,   31,
+       19,    11,     7,    4,    2,
+];
+
+/// The cumulative frequencies of the overflow model for file versions 3.98 and later.
+#[rustfmt::skip]
+const COUNTS_3980: [u32; 22] = [
+        0, 19578, 36160, 48417, 56323, 60899, 63265, 64435,
+    64971, 65232, 65351, 65416, 65447, 65466, 65476, 65482,
+    65485, 65488, 65490, 65491, 65492, 65493,
+];
+
+/// The frequencies of the overflow model for file versions 3.98 and later.
+#[rustfmt::skip]
+const COUNTS_DIFF_3980: [u32; 21] = [
+    19578, 16582, 12257, 7906, 4576, 2366, 1170, 536,
+      261,   119,    65,   31,   19,   10,    6,   3,
+        3,     2,     1,    1,    1,
+];
+
+/// A range decoder.
+pub struct RangeDecoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    low: u32,
+    range: u32,
+    help: u32,
+    buffer: u32,
+    is_overread: bool,
+}
+
+impl<'a> RangeDecoder<'a> {
+    /// Instantiate a range decoder for the range coded data in `buf`.
+    pub fn new(buf: &'a [u8]) -> Self {
+        let mut dec =
+            RangeDecoder { buf, pos: 0, low: 0, range: 0, help: 0, buffer: 0, is_overread: false };
+
+        // The first byte is ignored.
+        dec.next_byte();
+
+        dec.buffer = dec.next_byte();
+        dec.low = dec.buffer >> (8 - EXTRA_BITS);
+        dec.range = 1 << EXTRA_BITS;
+
+        dec
+    }
+
+    /// Returns true if the decoder attempted to read past the end of the buffer.
+    pub fn is_overread(&self) -> bool {
+        self.is_overread
+    }
+
+    fn next_byte(&mut self) -> u32 {
+        match self.buf.get(self.pos) {
+            Some(&byte) => {
+                self.pos += 1;
+                u32::from(byte)
+            }
+            None => {
+                self.is_overread = true;
+                0
+            }
+        }
+    }
+
+    fn normalize(&mut self) {
+        while self.range <= BOTTOM_VALUE {
+            self.buffer = (self.buffer << 8) | self.next_byte();
+            self.low = (self.low << 8) | ((self.buffer >> 1) & 0xff);
+            self.range <<= 8;
+        }
+    }
+
+    fn decode_culfreq(&mut self, tot_f: u32) -> u32 {
+        self.normalize();
+        self.help = self.range / tot_f;
+        self.low / self.help
+    }
+
+    fn decode_culshift(&mut self, shift: u32) -> u32 {
+        self.normalize();
+        self.help = self.range >> shift;
+        self.low / self.help
+    }
+
+    fn update(&mut self, sy_f: u32, lt_f: u32) {
+        self.low = self.low.wrapping_sub(self.help.wrapping_mul(lt_f));
+        self.range = self.help.wrapping_mul(sy_f);
+    }
+
+    fn decode_bits(&mut self, n: u32) -> u32 {
+        let sym = self.decode_culshift(n);
+        self.update(1, sym);
+        sym
+    }
+
+    fn decode_symbol(&mut self, counts: &[u32; 22], counts_diff: &[u32; 21]) -> u32 {
+        let cf = self.decode_culshift(16);
+
+        // Symbols beyond the modelled range have a frequency of 1.
+        if cf > 65492 {
+            self.update(1, cf);
+
+            if cf > 65535 {
+                self.is_overread = true;
+            }
+
+            return (cf + MODEL_ELEMENTS - 1).wrapping_sub(65535);
+        }
+
+        let symbol = counts[1..].iter().position(|&count| count > cf).unwrap();
+
+        self.update(counts_diff[symbol], counts[symbol]);
+
+        symbol as u32
+    }
+}
+
+/// The adaptive state of the entropy coder for one channel.
+pub struct Rice {
+    k: u32,
+    ksum: u32,
+}
+
+impl Default for Rice {
+    fn default() -> Self {
+        Rice { k: 10, ksum: 16 << 10 }
+    }
+}
+
+impl Rice {
+    fn update(&mut self, x: u32) {
+        let lim = if self.k > 0 { 1 << (self.k + 4) } else { 0 };
+
+        self.ksum = self
+            .ksum
+            .wrapping_add(x.wrapping_add(1) / 2)
+            .wrapping_sub(self.ksum.wrapping_add(16) >> 5);
+
+        if self.ksum < lim {
+            self.k -= 1;
+        }
+        else if self.ksum >= 1 << (self.k + 5) && self.k < 24 {
+            self.k += 1;
+        }
+    }
+}
+
+/// Converts an unsigned residual to a signed residual.
+fn to_signed(x: u32) -> i32 {
+    ((x >> 1) ^ (x & 1).wrapping_sub(1)).wrapping_add(1) as i32
+}
+
+/// Decode a residual for file versions prior to 3.99.
+pub fn decode_value_3900(dec: &mut RangeDecoder<'_>, rice: &mut Rice) -> i32 {
+    let mut overflow = dec.decode_symbol(&COUNTS_3970, &COUNTS_DIFF_3970);
+
+    let k = if overflow == MODEL_ELEMENTS - 1 {
+        overflow = 0;
+        dec.decode_bits(5)
+    }
+    else {
+        rice.k.saturating_sub(1)
+    };
+
+    let mut x = if k <= 16 {
+        dec.decode_bits(k)
+    }
+    else {
+        let lo = dec.decode_bits(16);
+        lo | (dec.decode_bits(k - 16) << 16)
+    };
+
+    x = x.wrapping_add(overflow.wrapping_shl(k));
+
+    rice.update(x);
+
+    to_signed(x)
+}
+
+/// Decode a residual for file versions 3.99 and later.
+pub fn decode_value_3990(dec: &mut RangeDecoder<'_>, rice: &mut Rice) -> i32 {
+    let pivot = (rice.ksum >> 5).max(1);
+
+    let mut overflow = dec.decode_symbol(&COUNTS_3980, &COUNTS_DIFF_3980);
+
+    if overflow == MODEL_ELEMENTS - 1 {
+        overflow = dec.decode_bits(16) << 16;
+        overflow |= dec.decode_bits(16);
+    }
+
+    let base = if pivot < 0x10000 {
+        let base = dec.decode_culfreq(pivot);
+        dec.update(1, base);
+        base
+    }
+    else {
+        // The pivot is too large to be decoded at once, so decode the upper 16 bits and the
+        // remaining lower bits seperately.
+        let bits = 32 - (pivot >> 16).leading_zeros();
+
+        let base_hi = dec.decode_culfreq((pivot >> bits) + 1);
+        dec.update(1, base_hi);
+
+        let base_lo = dec.decode_culfreq(1 << bits);
+        dec.update(1, base_lo);
+
+        (base_hi << bits) + base_lo
+    };
+
+    let x = base.wrapping_add(overflow.wrapping_mul(pivot));
+
+    rice.update(x);
+
+    to_signed(x)
+}
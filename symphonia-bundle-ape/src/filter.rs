@@ -0,0 +1,366 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The neural network filters, and the adaptive predictor.
+
+/// The number of samples processed before the history buffers are rolled back.
+const HISTORY_SIZE: usize = 512;
+
+/// The number of samples of history required by the predictor.
+const PREDICTOR_SIZE: usize = 50;
+
+const PREDICTOR_ORDER: usize = 8;
+
+const Y_DELAY_A: usize = 18 + PREDICTOR_ORDER * 4;
+const Y_DELAY_B: usize = 18 + PREDICTOR_ORDER * 3;
+const X_DELAY_A: usize = 18 + PREDICTOR_ORDER * 2;
+const X_DELAY_B: usize = 18 + PREDICTOR_ORDER;
+
+const Y_ADAPT_COEFFS_A: usize = 18;
+const X_ADAPT_COEFFS_A: usize = 14;
+const Y_ADAPT_COEFFS_B: usize = 10;
+const X_ADAPT_COEFFS_B: usize = 5;
+
+/// The initial coefficients of stage 1 of the predictor.
+const INITIAL_COEFFS_A: [i32; 4] = [360, 317, -109, 98];
+
+/// The order and fractional bits of each neural network filter for each compression level.
+const FILTER_PARAMS: [&[(usize, u32)]; 5] = [
+    // Fast.
+    &[],
+    // Normal.
+    &[(16, 11)],
+    // High.
+    &[(64, 11)],
+    // Extra high.
+    &[(32, 10), (256, 13)],
+    // Insane.
+    &[(16, 11), (256, 13), (1024 + 256, 15)],
+];
+
+/// Returns -1 if `x` is positive, 1 if `x` is negative, and 0 otherwise.
+///
+/// Note that the sign is inverted.
+#[inline(always)]
+fn ape_sign(x: i32) -> i32 {
+    (x < 0) as i32 - (x > 0) as i32
+}
+
+/// A neural network filter.
+struct NnFilter {
+    order: usize,
+    frac_bits: u32,
+    /// The filter coefficients.
+    coeffs: Vec<i16>,
+    /// The history of the filter output, and the adaption values.
+    history: Vec<i16>,
+    /// The position in the history of the next output.
+    delay: usize,
+    /// The position in the history of the next adaption value.
+    adapt: usize,
+    /// The running average of the absolute value of the filter output.
+    avg: i32,
+}
+
+impl NnFilter {
+    fn new(order: usize, frac_bits: u32) -> Self {
+        NnFilter {
+            order,
+            frac_bits,
+            coeffs: vec![0; order],
+            history: vec![0; HISTORY_SIZE + 2 * order],
+            delay: 2 * order,
+            adapt: order,
+            avg: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.coeffs.iter_mut().for_each(|c| *c = 0);
+        self.history.iter_mut().for_each(|h| *h = 0);
+        self.delay = 2 * self.order;
+        self.adapt = self.order;
+        self.avg = 0;
+    }
+
+    fn apply(&mut self, version: u16, data: &mut [i32]) {
+        let order = self.order;
+
+        for sample in data.iter_mut() {
+            let input = *sample;
+
+            // Calculate the dot product of the coefficients and the previous outputs, while
+            // adapting the coefficients in the direction of the sign of the input.
+            let sign = ape_sign(input) as i16;
+
+            let hist = &self.history[self.delay - order..self.delay];
+            let adapt = &self.history[self.adapt - order..self.adapt];
+
+            let mut dot = 0i32;
+
+            for ((c, &h), &a) in self.coeffs.iter_mut().zip(hist).zip(adapt) {
+                dot = dot.wrapping_add(i32::from(*c) * i32::from(h));
+                *c = c.wrapping_add(sign.wrapping_mul(a));
+            }
+
+            let round = 1 << (self.frac_bits - 1);
+            let output = ((i64::from(dot) + round) >> self.frac_bits) as i32;
+            let output = output.wrapping_add(input);
+
+            *sample = output;
+
+            self.history[self.delay] =
+                output.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+            self.delay += 1;
+
+            let adapt = self.adapt;
+
+            if version >= 3980 {
+                // Scale the adaption value based on the magnitude of the output relative to the
+                // running average.
+                let abs = output.unsigned_abs();
+
+                self.history[adapt] = if abs != 0 {
+                    let avg = i64::from(self.avg);
+                    let abs = i64::from(abs);
+                    let scale = (abs > 3 * avg) as u32 + (abs > avg + avg / 3) as u32;
+
+                    (ape_sign(output) * (8 << scale)) as i16
+                }
+                else {
+                    0
+                };
+
+                self.avg = self.avg.wrapping_add(abs.wrapping_sub(self.avg as u32) as i32 / 16);
+
+                self.history[adapt - 1] >>= 1;
+                self.history[adapt - 2] >>= 1;
+                self.history[adapt - 8] >>= 1;
+            }
+            else {
+                self.history[adapt] =
+                    if output == 0 { 0 } else { (((output >> 28) & 8) - 4) as i16 };
+
+                self.history[adapt - 4] >>= 1;
+                self.history[adapt - 8] >>= 1;
+            }
+
+            self.adapt += 1;
+
+            // Roll back the history once it is full.
+            if self.delay == self.history.len() {
+                self.history.copy_within(self.delay - 2 * order..self.delay, 0);
+                self.delay = 2 * order;
+                self.adapt = order;
+            }
+        }
+    }
+}
+
+/// The adaptive predictor (stage 1 and 2 filters) of file versions 3.95 and later.
+struct Predictor {
+    history: Vec<i32>,
+    pos: usize,
+    coeffs_a: [[i32; 4]; 2],
+    coeffs_b: [[i32; 5]; 2],
+    filter_a: [i32; 2],
+    filter_b: [i32; 2],
+    last_a: [i32; 2],
+}
+
+impl Predictor {
+    fn new() -> Self {
+        Predictor {
+            history: vec![0; HISTORY_SIZE + PREDICTOR_SIZE],
+            pos: 0,
+            coeffs_a: [INITIAL_COEFFS_A; 2],
+            coeffs_b: [[0; 5]; 2],
+            filter_a: [0; 2],
+            filter_b: [0; 2],
+            last_a: [0; 2],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.iter_mut().for_each(|h| *h = 0);
+        self.pos = 0;
+        self.coeffs_a = [INITIAL_COEFFS_A; 2];
+        self.coeffs_b = [[0; 5]; 2];
+        self.filter_a = [0; 2];
+        self.filter_b = [0; 2];
+        self.last_a = [0; 2];
+    }
+
+    /// Advance the history by one sample, rolling it back once full.
+    fn advance(&mut self) {
+        self.pos += 1;
+
+        if self.pos == HISTORY_SIZE {
+            self.history.copy_within(self.pos..self.pos + PREDICTOR_SIZE, 0);
+            self.pos = 0;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_filter(
+        &mut self,
+        decoded: i32,
+        filter: usize,
+        delay_a: usize,
+        delay_b: usize,
+        adapt_a: usize,
+        adapt_b: usize,
+    ) -> i32 {
+        let buf = &mut self.history[self.pos..];
+
+        // Stage 2, part 1.
+        buf[delay_a] = self.last_a[filter];
+        buf[adapt_a] = ape_sign(buf[delay_a]);
+        buf[delay_a - 1] = buf[delay_a].wrapping_sub(buf[delay_a - 1]);
+        buf[adapt_a - 1] = ape_sign(buf[delay_a - 1]);
+
+        let coeffs_a = &mut self.coeffs_a[filter];
+
+        let pred_a = (0..4)
+            .fold(0i32, |acc, i| acc.wrapping_add(buf[delay_a - i].wrapping_mul(coeffs_a[i])));
+
+        // Stage 2, part 2. A scaled first-order filter of the other channel.
+        buf[delay_b] =
+            self.filter_a[filter ^ 1].wrapping_sub(self.filter_b[filter].wrapping_mul(31) >> 5);
+        buf[adapt_b] = ape_sign(buf[delay_b]);
+        buf[delay_b - 1] = buf[delay_b].wrapping_sub(buf[delay_b - 1]);
+        buf[adapt_b - 1] = ape_sign(buf[delay_b - 1]);
+
+        self.filter_b[filter] = self.filter_a[filter ^ 1];
+
+        let coeffs_b = &mut self.coeffs_b[filter];
+
+        let pred_b = (0..5)
+            .fold(0i32, |acc, i| acc.wrapping_add(buf[delay_b - i].wrapping_mul(coeffs_b[i])));
+
+        self.last_a[filter] = decoded.wrapping_add(pred_a.wrapping_add(pred_b >> 1) >> 10);
+
+        // Stage 1. A scaled first-order filter.
+        self.filter_a[filter] =
+            self.last_a[filter].wrapping_add(self.filter_a[filter].wrapping_mul(31) >> 5);
+
+        // Adapt the coefficients.
+        let sign = ape_sign(decoded);
+
+        for (i, c) in coeffs_a.iter_mut().enumerate() {
+            *c = c.wrapping_add(buf[adapt_a - i] * sign);
+        }
+
+        for (i, c) in coeffs_b.iter_mut().enumerate() {
+            *c = c.wrapping_add(buf[adapt_b - i] * sign);
+        }
+
+        self.filter_a[filter]
+    }
+
+    fn decode_stereo(&mut self, y: &mut [i32], x: &mut [i32]) {
+        for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+            *y =
+                self.update_filter(*y, 0, Y_DELAY_A, Y_DELAY_B, Y_ADAPT_COEFFS_A, Y_ADAPT_COEFFS_B);
+            *x =
+                self.update_filter(*x, 1, X_DELAY_A, X_DELAY_B, X_ADAPT_COEFFS_A, X_ADAPT_COEFFS_B);
+
+            self.advance();
+        }
+    }
+
+    fn decode_mono(&mut self, y: &mut [i32]) {
+        let mut current_a = self.last_a[0];
+
+        for y in y.iter_mut() {
+            let decoded = *y;
+
+            let buf = &mut self.history[self.pos..];
+
+            buf[Y_DELAY_A] = current_a;
+            buf[Y_DELAY_A - 1] = buf[Y_DELAY_A].wrapping_sub(buf[Y_DELAY_A - 1]);
+
+            let coeffs_a = &mut self.coeffs_a[0];
+
+            let pred_a = (0..4).fold(0i32, |acc, i| {
+                acc.wrapping_add(buf[Y_DELAY_A - i].wrapping_mul(coeffs_a[i]))
+            });
+
+            current_a = decoded.wrapping_add(pred_a >> 10);
+
+            buf[Y_ADAPT_COEFFS_A] = ape_sign(buf[Y_DELAY_A]);
+            buf[Y_ADAPT_COEFFS_A - 1] = ape_sign(buf[Y_DELAY_A - 1]);
+
+            let sign = ape_sign(decoded);
+
+            for (i, c) in coeffs_a.iter_mut().enumerate() {
+                *c = c.wrapping_add(buf[Y_ADAPT_COEFFS_A - i] * sign);
+            }
+
+            self.advance();
+
+            self.filter_a[0] = current_a.wrapping_add(self.filter_a[0].wrapping_mul(31) >> 5);
+
+            *y = self.filter_a[0];
+        }
+
+        self.last_a[0] = current_a;
+    }
+}
+
+/// The prediction filters of a frame.
+pub struct Filters {
+    version: u16,
+    /// The neural network filters for each channel, applied in order.
+    nn_filters: [Vec<NnFilter>; 2],
+    predictor: Predictor,
+}
+
+impl Filters {
+    /// Instantiate the filters for a file version and compression level. The compression level
+    /// must be a multiple of 1000 between 1000 and 5000.
+    pub fn new(version: u16, compression_level: u16) -> Self {
+        let params = FILTER_PARAMS[usize::from(compression_level / 1000 - 1)];
+
+        let nn_filters = [
+            params.iter().map(|&(order, frac_bits)| NnFilter::new(order, frac_bits)).collect(),
+            params.iter().map(|&(order, frac_bits)| NnFilter::new(order, frac_bits)).collect(),
+        ];
+
+        Filters { version, nn_filters, predictor: Predictor::new() }
+    }
+
+    /// Reset the filters to their initial state. Must be called at the start of every frame.
+    pub fn reset(&mut self) {
+        for filter in self.nn_filters.iter_mut().flatten() {
+            filter.reset();
+        }
+
+        self.predictor.reset();
+    }
+
+    /// Reconstruct the samples of a mono frame from the decoded residuals.
+    pub fn decode_mono(&mut self, y: &mut [i32]) {
+        for filter in self.nn_filters[0].iter_mut() {
+            filter.apply(self.version, y);
+        }
+
+        self.predictor.decode_mono(y);
+    }
+
+    /// Reconstruct the samples of a stereo frame from the decoded residuals.
+    pub fn decode_stereo(&mut self, y: &mut [i32], x: &mut [i32]) {
+        let [filters0, filters1] = &mut self.nn_filters;
+
+        for (filter0, filter1) in filters0.iter_mut().zip(filters1.iter_mut()) {
+            filter0.apply(self.version, y);
+            filter1.apply(self.version, x);
+        }
+
+        self.predictor.decode_stereo(y, x);
+    }
+}
@@ -0,0 +1,317 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+/// The Monkey's Audio stream marker: "MAC " in ASCII.
+pub const APE_STREAM_MARKER: [u8; 4] = *b"MAC ";
+
+/// The oldest supported file version.
+const MIN_VERSION: u16 = 3800;
+
+/// The newest supported file version.
+const MAX_VERSION: u16 = 3990;
+
+/// The maximum number of blocks (inter-channel samples) in a frame.
+pub const MAX_BLOCKS_PER_FRAME: u32 = 73728 * 16;
+
+/// The maximum number of frames.
+const MAX_FRAMES: u32 = 1 << 24;
+
+const FORMAT_FLAG_8_BIT: u16 = 0x1;
+const FORMAT_FLAG_HAS_PEAK_LEVEL: u16 = 0x4;
+const FORMAT_FLAG_24_BIT: u16 = 0x8;
+const FORMAT_FLAG_HAS_SEEK_ELEMENTS: u16 = 0x10;
+const FORMAT_FLAG_CREATE_WAV_HEADER: u16 = 0x20;
+
+/// The codec configuration shared between the demuxer and decoder.
+///
+/// The configuration is serialized as the codec extra data in the same 6 byte layout used by
+/// other implementations: the file version, compression level, and format flags as little-endian
+/// 16-bit integers.
+#[derive(Copy, Clone, Debug)]
+pub struct ApeCodecConfig {
+    /// The file version multiplied by 1000 (e.g., 3990 for version 3.99).
+    pub version: u16,
+    /// The compression level. One of 1000 (fast), 2000 (normal), 3000 (high), 4000 (extra high),
+    /// or 5000 (insane).
+    pub compression_level: u16,
+    /// The format flags.
+    pub format_flags: u16,
+}
+
+impl ApeCodecConfig {
+    /// Read the codec configuration from the codec extra data.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        if buf.len() != 6 {
+            return decode_error("ape: invalid extra data length");
+        }
+
+        let mut reader = BufReader::new(buf);
+
+        let config = ApeCodecConfig {
+            version: reader.read_u16()?,
+            compression_level: reader.read_u16()?,
+            format_flags: reader.read_u16()?,
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Serialize the codec configuration to codec extra data.
+    pub fn to_extra_data(self) -> Box<[u8]> {
+        let mut buf = Vec::with_capacity(6);
+
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        buf.extend_from_slice(&self.compression_level.to_le_bytes());
+        buf.extend_from_slice(&self.format_flags.to_le_bytes());
+
+        buf.into_boxed_slice()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.version < MIN_VERSION || self.version > MAX_VERSION {
+            return unsupported_error("ape: unsupported file version");
+        }
+
+        if self.compression_level % 1000 != 0
+            || self.compression_level < 1000
+            || self.compression_level > 5000
+        {
+            return unsupported_error("ape: unsupported compression level");
+        }
+
+        Ok(())
+    }
+}
+
+/// The stream header of a Monkey's Audio file, combining the descriptor, header, and seek table.
+pub struct ApeHeader {
+    pub config: ApeCodecConfig,
+    /// The number of blocks in each frame, except the last.
+    pub blocks_per_frame: u32,
+    /// The number of blocks in the last frame.
+    pub final_frame_blocks: u32,
+    /// The total number of frames.
+    pub total_frames: u32,
+    pub bits_per_sample: u32,
+    pub channels: u32,
+    pub sample_rate: u32,
+    /// The length of the WAVE file trailer that follows the audio data.
+    pub wav_tail_len: u32,
+    /// The position of the first frame relative to the start of the stream marker.
+    pub first_frame_pos: u64,
+    /// The position of each frame relative to the start of the stream marker.
+    pub seek_table: Vec<u32>,
+}
+
+impl ApeHeader {
+    /// Read the stream header. The reader must be positioned immediately after the stream marker.
+    /// Upon return, the reader is positioned at the end of the seek table.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<Self> {
+        let version = reader.read_u16()?;
+
+        if version < MIN_VERSION || version > MAX_VERSION {
+            return unsupported_error("ape: unsupported file version");
+        }
+
+        let mut header = if version >= 3980 {
+            Self::read_new_header(reader, version)?
+        }
+        else {
+            Self::read_old_header(reader, version)?
+        };
+
+        header.config.validate()?;
+
+        if header.total_frames == 0 || header.total_frames > MAX_FRAMES {
+            return decode_error("ape: invalid number of frames");
+        }
+
+        if header.blocks_per_frame == 0 || header.blocks_per_frame > MAX_BLOCKS_PER_FRAME {
+            return unsupported_error("ape: unsupported number of blocks per frame");
+        }
+
+        if header.final_frame_blocks > header.blocks_per_frame {
+            return decode_error("ape: invalid number of blocks in the final frame");
+        }
+
+        if header.channels == 0 || header.channels > 2 {
+            return unsupported_error("ape: unsupported number of channels");
+        }
+
+        if header.sample_rate == 0 {
+            return decode_error("ape: invalid sample rate");
+        }
+
+        if header.seek_table.len() < header.total_frames as usize {
+            return decode_error("ape: seek table is too short");
+        }
+
+        header.seek_table.truncate(header.total_frames as usize);
+
+        // The seek table must be sorted.
+        if header.seek_table.windows(2).any(|w| w[0] > w[1]) {
+            return decode_error("ape: invalid seek table");
+        }
+
+        Ok(header)
+    }
+
+    /// Read the descriptor and header of file versions 3.98 and later.
+    fn read_new_header<B: ReadBytes>(reader: &mut B, version: u16) -> Result<Self> {
+        // Descriptor.
+        let _padding = reader.read_u16()?;
+        let descriptor_len = reader.read_u32()?;
+        let header_len = reader.read_u32()?;
+        let seek_table_len = reader.read_u32()?;
+        let wav_header_len = reader.read_u32()?;
+        let _audio_data_len = reader.read_u32()?;
+        let _audio_data_len_high = reader.read_u32()?;
+        let wav_tail_len = reader.read_u32()?;
+
+        let mut _md5 = [0; 16];
+        reader.read_buf_exact(&mut _md5)?;
+
+        if descriptor_len < 52 || header_len < 24 {
+            return decode_error("ape: invalid descriptor");
+        }
+
+        reader.ignore_bytes(u64::from(descriptor_len - 52))?;
+
+        // Header.
+        let compression_level = reader.read_u16()?;
+        let format_flags = reader.read_u16()?;
+        let blocks_per_frame = reader.read_u32()?;
+        let final_frame_blocks = reader.read_u32()?;
+        let total_frames = reader.read_u32()?;
+        let bits_per_sample = u32::from(reader.read_u16()?);
+        let channels = u32::from(reader.read_u16()?);
+        let sample_rate = reader.read_u32()?;
+
+        reader.ignore_bytes(u64::from(header_len - 24))?;
+
+        let seek_table = read_seek_table(reader, seek_table_len, total_frames)?;
+
+        let first_frame_pos = u64::from(descriptor_len)
+            + u64::from(header_len)
+            + u64::from(seek_table_len)
+            + u64::from(wav_header_len);
+
+        Ok(ApeHeader {
+            config: ApeCodecConfig { version, compression_level, format_flags },
+            blocks_per_frame,
+            final_frame_blocks,
+            total_frames,
+            bits_per_sample,
+            channels,
+            sample_rate,
+            wav_tail_len,
+            first_frame_pos,
+            seek_table,
+        })
+    }
+
+    /// Read the header of file versions prior to 3.98.
+    fn read_old_header<B: ReadBytes>(reader: &mut B, version: u16) -> Result<Self> {
+        let compression_level = reader.read_u16()?;
+        let format_flags = reader.read_u16()?;
+        let channels = u32::from(reader.read_u16()?);
+        let sample_rate = reader.read_u32()?;
+        let wav_header_len = reader.read_u32()?;
+        let wav_tail_len = reader.read_u32()?;
+        let total_frames = reader.read_u32()?;
+        let final_frame_blocks = reader.read_u32()?;
+
+        let mut header_len = 32u32;
+
+        if format_flags & FORMAT_FLAG_HAS_PEAK_LEVEL != 0 {
+            let _peak_level = reader.read_u32()?;
+            header_len += 4;
+        }
+
+        let seek_table_len = if format_flags & FORMAT_FLAG_HAS_SEEK_ELEMENTS != 0 {
+            header_len += 4;
+            reader.read_u32()?.saturating_mul(4)
+        }
+        else {
+            total_frames.saturating_mul(4)
+        };
+
+        let bits_per_sample = if format_flags & FORMAT_FLAG_8_BIT != 0 {
+            8
+        }
+        else if format_flags & FORMAT_FLAG_24_BIT != 0 {
+            24
+        }
+        else {
+            16
+        };
+
+        let blocks_per_frame = if version >= 3950 {
+            73728 * 4
+        }
+        else if version >= 3900 || (version >= 3800 && compression_level >= 4000) {
+            73728
+        }
+        else {
+            9216
+        };
+
+        // The stored WAVE file header precedes the seek table.
+        if format_flags & FORMAT_FLAG_CREATE_WAV_HEADER == 0 {
+            reader.ignore_bytes(u64::from(wav_header_len))?;
+        }
+
+        let seek_table = read_seek_table(reader, seek_table_len, total_frames)?;
+
+        let mut first_frame_pos =
+            u64::from(header_len) + u64::from(seek_table_len) + u64::from(wav_header_len);
+
+        // Very old files have a table of bits following the seek table.
+        if version < 3810 {
+            first_frame_pos += u64::from(total_frames);
+        }
+
+        Ok(ApeHeader {
+            config: ApeCodecConfig { version, compression_level, format_flags },
+            blocks_per_frame,
+            final_frame_blocks,
+            total_frames,
+            bits_per_sample,
+            channels,
+            sample_rate,
+            wav_tail_len,
+            first_frame_pos,
+            seek_table,
+        })
+    }
+
+    /// The total number of blocks (inter-channel samples) in the stream.
+    pub fn n_frames(&self) -> u64 {
+        u64::from(self.total_frames - 1) * u64::from(self.blocks_per_frame)
+            + u64::from(self.final_frame_blocks)
+    }
+}
+
+fn read_seek_table<B: ReadBytes>(reader: &mut B, len: u32, total_frames: u32) -> Result<Vec<u32>> {
+    // Only the entries for the frames in the stream are read, the remainder is skipped.
+    let num_entries = (len / 4).min(total_frames.min(MAX_FRAMES));
+
+    let mut seek_table = Vec::with_capacity(num_entries as usize);
+
+    for _ in 0..num_entries {
+        seek_table.push(reader.read_u32()?);
+    }
+
+    reader.ignore_bytes(u64::from(len - 4 * num_entries))?;
+
+    Ok(seek_table)
+}
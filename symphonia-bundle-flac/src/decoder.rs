@@ -11,16 +11,17 @@ use std::num::Wrapping;
 
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
 use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::checksum::Crc16Ansi;
 use symphonia_core::codecs::{
     CodecDescriptor, CodecParameters, VerificationCheck, CODEC_TYPE_FLAC,
 };
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr};
+use symphonia_core::io::{BitReaderLtr, BufReader, Monitor, ReadBitsLtr};
 use symphonia_core::support_codec;
 use symphonia_core::units::TimeBase;
-use symphonia_core::util::bits::sign_extend_leq32_to_i32;
+use symphonia_core::util::bits::{sign_extend_leq32_to_i32, sign_extend_leq64_to_i64};
 use symphonia_utils_xiph::flac::metadata::StreamInfo;
 
 use log::{debug, log_enabled, warn};
@@ -80,22 +81,111 @@ fn decorrelate_right_side(right: &[i32], side: &mut [i32]) {
     }
 }
 
+// For 32-bit streams, the side channel is 33-bits wide. The following variants decorrelate a wide
+// side channel, and write the restored channel to `out`.
+
+fn decorrelate_left_wide_side(left: &[i32], side: &[i64], out: &mut [i32]) {
+    for ((o, l), s) in out.iter_mut().zip(left).zip(side) {
+        *o = (i64::from(*l) - *s) as i32;
+    }
+}
+
+fn decorrelate_mid_wide_side(mid: &mut [i32], side: &[i64], out: &mut [i32]) {
+    // See `decorrelate_mid_side` for details.
+    for ((m, o), s) in mid.iter_mut().zip(out.iter_mut()).zip(side) {
+        let mid = (i64::from(*m) << 1) | (*s & 1);
+        *m = ((mid + *s) >> 1) as i32;
+        *o = ((mid - *s) >> 1) as i32;
+    }
+}
+
+fn decorrelate_right_wide_side(right: &[i32], side: &[i64], out: &mut [i32]) {
+    for ((o, r), s) in out.iter_mut().zip(right).zip(side) {
+        *o = (*s + i64::from(*r)) as i32;
+    }
+}
+
+#[test]
+fn verify_decorrelate_wide_side() {
+    let left = [i32::MAX, i32::MIN, 0, -1];
+    let right = [i32::MIN, i32::MAX, -1, i32::MAX];
+
+    let side: Vec<i64> =
+        left.iter().zip(&right).map(|(&l, &r)| i64::from(l) - i64::from(r)).collect();
+    let mid: Vec<i32> = left
+        .iter()
+        .zip(&right)
+        .map(|(&l, &r)| ((i64::from(l) + i64::from(r)) >> 1) as i32)
+        .collect();
+
+    let mut out = [0; 4];
+    decorrelate_left_wide_side(&left, &side, &mut out);
+    assert_eq!(out, right);
+
+    decorrelate_right_wide_side(&right, &side, &mut out);
+    assert_eq!(out, left);
+
+    let mut mid = mid;
+    decorrelate_mid_wide_side(&mut mid, &side, &mut out);
+    assert_eq!(mid, left);
+    assert_eq!(out, right);
+}
+
+/// Statistics on the integrity of the frames decoded by the FLAC decoder.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DecodeStatistics {
+    /// The number of frames with a header CRC8 that did not match the expected CRC.
+    pub num_header_crc_errors: u64,
+    /// The number of frames with a frame CRC16 that did not match the expected CRC in the footer.
+    ///
+    /// The frame CRC16 is only checked if the demuxer did not already guarantee the integrity of
+    /// the packet data.
+    pub num_footer_crc_errors: u64,
+}
+
 /// Free Lossless Audio Codec (FLAC) decoder.
 pub struct FlacDecoder {
     params: CodecParameters,
     is_validating: bool,
+    /// If true, the frame CRC16 in the footer must be checked by the decoder.
+    is_checking_crc: bool,
+    stats: DecodeStatistics,
     validator: Validator,
     buf: AudioBuffer<i32>,
+    /// The side channel of a 32-bit stream.
+    wide_side: Vec<i64>,
 }
 
 impl FlacDecoder {
+    /// Gets statistics on the integrity of the frames decoded thus far.
+    pub fn statistics(&self) -> DecodeStatistics {
+        self.stats
+    }
+
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
         let mut reader = packet.as_buf_reader();
 
         // Synchronize to a frame and get the synchronization code.
         let sync = sync_frame(&mut reader)?;
 
-        let header = read_frame_header(&mut reader, sync)?;
+        let (header, is_crc_ok) = read_frame_header_unchecked(&mut reader, sync)?;
+
+        if !is_crc_ok && cfg!(not(fuzzing)) {
+            self.stats.num_header_crc_errors += 1;
+            return decode_error("flac: computed frame header CRC does not match expected CRC");
+        }
+
+        // If the demuxer does not guarantee the integrity of the packet, check the frame CRC16.
+        if self.is_checking_crc && !is_frame_crc_ok(packet.buf()) && cfg!(not(fuzzing)) {
+            self.stats.num_footer_crc_errors += 1;
+            return decode_error("flac: computed frame CRC does not match expected CRC");
+        }
+
+        // Stereo decorrelation is only possible for 2 channel streams, otherwise the number of
+        // channels in the frame must match the number of channels in the stream.
+        if header.channel_assignment.num_channels() as usize != self.buf.spec().channels.count() {
+            return decode_error("flac: frame channel assignment does not match the stream");
+        }
 
         // Use the bits per sample and sample rate as stated in the frame header, falling back to
         // the stream information if provided. If neither are available, return an error.
@@ -134,7 +224,10 @@ impl FlacDecoder {
                 }
                 // For Left/Side, Mid/Side, and Right/Side channel configurations, the Side
                 // (Difference) channel requires an extra bit per sample.
-                ChannelAssignment::LeftSide => {
+                //
+                // If the stream is 32-bit, the side channel will be 33-bit and must be read into a
+                // wider buffer.
+                ChannelAssignment::LeftSide if bits_per_sample < 32 => {
                     let (left, side) = self.buf.chan_pair_mut(0, 1);
 
                     read_subframe(&mut bs, bits_per_sample, left)?;
@@ -142,7 +235,7 @@ impl FlacDecoder {
 
                     decorrelate_left_side(left, side);
                 }
-                ChannelAssignment::MidSide => {
+                ChannelAssignment::MidSide if bits_per_sample < 32 => {
                     let (mid, side) = self.buf.chan_pair_mut(0, 1);
 
                     read_subframe(&mut bs, bits_per_sample, mid)?;
@@ -150,7 +243,7 @@ impl FlacDecoder {
 
                     decorrelate_mid_side(mid, side);
                 }
-                ChannelAssignment::RightSide => {
+                ChannelAssignment::RightSide if bits_per_sample < 32 => {
                     let (side, right) = self.buf.chan_pair_mut(0, 1);
 
                     read_subframe(&mut bs, bits_per_sample + 1, side)?;
@@ -158,6 +251,33 @@ impl FlacDecoder {
 
                     decorrelate_right_side(right, side);
                 }
+                ChannelAssignment::LeftSide => {
+                    let (left, right) = self.buf.chan_pair_mut(0, 1);
+                    let side = wide_side_buf(&mut self.wide_side, left.len());
+
+                    read_subframe(&mut bs, bits_per_sample, left)?;
+                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
+
+                    decorrelate_left_wide_side(left, side, right);
+                }
+                ChannelAssignment::MidSide => {
+                    let (mid, right) = self.buf.chan_pair_mut(0, 1);
+                    let side = wide_side_buf(&mut self.wide_side, mid.len());
+
+                    read_subframe(&mut bs, bits_per_sample, mid)?;
+                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
+
+                    decorrelate_mid_wide_side(mid, side, right);
+                }
+                ChannelAssignment::RightSide => {
+                    let (left, right) = self.buf.chan_pair_mut(0, 1);
+                    let side = wide_side_buf(&mut self.wide_side, right.len());
+
+                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
+                    read_subframe(&mut bs, bits_per_sample, right)?;
+
+                    decorrelate_right_wide_side(right, side, left);
+                }
             }
         }
 
@@ -194,6 +314,15 @@ impl Decoder for FlacDecoder {
         // Read the stream information block.
         let info = StreamInfo::read(&mut BufReader::new(extra_data))?;
 
+        // The stream information block only provides the number of channels, from which the
+        // default channel layout is derived. The demuxer may have a more specific channel layout
+        // (e.g., from a WAVEFORMATEXTENSIBLE_CHANNEL_MASK tag), so prefer it if it has the same
+        // number of channels.
+        let channels = match params.channels {
+            Some(channels) if channels.count() == info.channels.count() => channels,
+            _ => info.channels,
+        };
+
         // Clone the codec parameters so that the parameters can be supplemented and/or amended.
         let mut params = params.clone();
 
@@ -203,7 +332,7 @@ impl Decoder for FlacDecoder {
             .with_time_base(TimeBase::new(1, info.sample_rate))
             .with_bits_per_sample(info.bits_per_sample)
             .with_max_frames_per_packet(u64::from(info.block_len_max))
-            .with_channels(info.channels);
+            .with_channels(channels);
 
         if let Some(md5) = info.md5 {
             params.with_verification_code(VerificationCheck::Md5(md5));
@@ -213,19 +342,21 @@ impl Decoder for FlacDecoder {
             params.with_n_frames(n_frames);
         }
 
-        let spec = SignalSpec::new(info.sample_rate, info.channels);
+        let spec = SignalSpec::new(info.sample_rate, channels);
         let buf = AudioBuffer::new(u64::from(info.block_len_max), spec);
 
-        // TODO: Verify packet integrity if the demuxer is not.
-        // if !params.packet_data_integrity {
-        //     return unsupported_error("flac: packet integrity is required");
-        // }
+        // If the demuxer does not guarantee the integrity of the packet data, then the decoder must
+        // check the frame CRC16 itself.
+        let is_checking_crc = !params.packet_data_integrity;
 
         Ok(FlacDecoder {
             params,
             is_validating: options.verify,
+            is_checking_crc,
+            stats: Default::default(),
             validator: Default::default(),
             buf,
+            wide_side: Vec::new(),
         })
     }
 
@@ -290,8 +421,104 @@ impl Decoder for FlacDecoder {
     }
 }
 
+/// Checks if the CRC16 of the frame, excluding the footer, matches the CRC16 in the footer.
+fn is_frame_crc_ok(buf: &[u8]) -> bool {
+    if buf.len() < 2 {
+        return false;
+    }
+
+    let (frame, footer) = buf.split_at(buf.len() - 2);
+
+    let mut crc16 = Crc16Ansi::new(0);
+    crc16.process_buf_bytes(frame);
+
+    crc16.crc() == u16::from_be_bytes([footer[0], footer[1]])
+}
+
+/// Gets a buffer of `len` samples for a wide side channel.
+fn wide_side_buf(buf: &mut Vec<i64>, len: usize) -> &mut [i64] {
+    if buf.len() < len {
+        buf.resize(len, 0);
+    }
+    &mut buf[..len]
+}
+
 // Subframe business
 
+/// A decoded sub-frame sample. Sub-frames are decoded into 32-bit samples, except for the side
+/// channel of a 32-bit stream which requires 33-bits, and is therefore decoded into 64-bit samples.
+trait SubframeSample: Copy {
+    /// Reads a signed sample of `bps` bits.
+    fn read<B: ReadBitsLtr>(bs: &mut B, bps: u32) -> Result<Self>;
+
+    /// Converts a residual into a sample.
+    fn from_residual(residual: i32) -> Self;
+
+    /// Widens the sample.
+    fn to_i64(self) -> i64;
+
+    /// Adds a prediction to the sample. For valid streams, the sum will fit in the sample, but the
+    /// prediction itself may not, therefore the addition wraps.
+    fn add_prediction(self, predicted: i64) -> Self;
+
+    /// Shifts the sample left, wrapping on overflow.
+    fn wrapping_shl(self, shift: u32) -> Self;
+}
+
+impl SubframeSample for i32 {
+    #[inline(always)]
+    fn read<B: ReadBitsLtr>(bs: &mut B, bps: u32) -> Result<Self> {
+        Ok(sign_extend_leq32_to_i32(bs.read_bits_leq32(bps)?, bps))
+    }
+
+    #[inline(always)]
+    fn from_residual(residual: i32) -> Self {
+        residual
+    }
+
+    #[inline(always)]
+    fn to_i64(self) -> i64 {
+        i64::from(self)
+    }
+
+    #[inline(always)]
+    fn add_prediction(self, predicted: i64) -> Self {
+        self.wrapping_add(predicted as i32)
+    }
+
+    #[inline(always)]
+    fn wrapping_shl(self, shift: u32) -> Self {
+        i32::wrapping_shl(self, shift)
+    }
+}
+
+impl SubframeSample for i64 {
+    #[inline(always)]
+    fn read<B: ReadBitsLtr>(bs: &mut B, bps: u32) -> Result<Self> {
+        Ok(sign_extend_leq64_to_i64(bs.read_bits_leq64(bps)?, bps))
+    }
+
+    #[inline(always)]
+    fn from_residual(residual: i32) -> Self {
+        i64::from(residual)
+    }
+
+    #[inline(always)]
+    fn to_i64(self) -> i64 {
+        self
+    }
+
+    #[inline(always)]
+    fn add_prediction(self, predicted: i64) -> Self {
+        self.wrapping_add(predicted)
+    }
+
+    #[inline(always)]
+    fn wrapping_shl(self, shift: u32) -> Self {
+        i64::wrapping_shl(self, shift)
+    }
+}
+
 #[derive(Debug)]
 enum SubFrameType {
     Constant,
@@ -300,7 +527,11 @@ enum SubFrameType {
     Linear(u32),
 }
 
-fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) -> Result<()> {
+fn read_subframe<B: ReadBitsLtr, S: SubframeSample>(
+    bs: &mut B,
+    frame_bps: u32,
+    buf: &mut [S],
+) -> Result<()> {
     // First sub-frame bit must always 0.
     if bs.read_bool()? {
         return decode_error("flac: subframe padding is not 0");
@@ -331,6 +562,10 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
     // dropped bits per sample.
     let dropped_bps = if bs.read_bool()? { bs.read_unary_zeros()? + 1 } else { 0 };
 
+    if dropped_bps >= frame_bps {
+        return decode_error("flac: wasted bits per sample exceeds bits per sample");
+    }
+
     // The bits per sample stated in the frame header is for the decoded audio sub-block samples.
     // However, it is likely that the lower order bits of all the samples are simply 0. Therefore,
     // the encoder will truncate `dropped_bps` of lower order bits for every sample in a sub-block.
@@ -357,7 +592,7 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
 }
 
 #[inline(always)]
-fn samples_shl(shift: u32, buf: &mut [i32]) {
+fn samples_shl<S: SubframeSample>(shift: u32, buf: &mut [S]) {
     if shift > 0 {
         for sample in buf.iter_mut() {
             *sample = sample.wrapping_shl(shift);
@@ -365,8 +600,12 @@ fn samples_shl(shift: u32, buf: &mut [i32]) {
     }
 }
 
-fn decode_constant<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Result<()> {
-    let const_sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(bps)?, bps);
+fn decode_constant<B: ReadBitsLtr, S: SubframeSample>(
+    bs: &mut B,
+    bps: u32,
+    buf: &mut [S],
+) -> Result<()> {
+    let const_sample = S::read(bs, bps)?;
 
     for sample in buf.iter_mut() {
         *sample = const_sample;
@@ -375,19 +614,23 @@ fn decode_constant<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Res
     Ok(())
 }
 
-fn decode_verbatim<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Result<()> {
+fn decode_verbatim<B: ReadBitsLtr, S: SubframeSample>(
+    bs: &mut B,
+    bps: u32,
+    buf: &mut [S],
+) -> Result<()> {
     for sample in buf.iter_mut() {
-        *sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(bps)?, bps);
+        *sample = S::read(bs, bps)?;
     }
 
     Ok(())
 }
 
-fn decode_fixed_linear<B: ReadBitsLtr>(
+fn decode_fixed_linear<B: ReadBitsLtr, S: SubframeSample>(
     bs: &mut B,
     bps: u32,
     order: u32,
-    buf: &mut [i32],
+    buf: &mut [S],
 ) -> Result<()> {
     // The first `order` samples are encoded verbatim to warm-up the LPC decoder.
     decode_verbatim(bs, bps, &mut buf[..order as usize])?;
@@ -405,7 +648,12 @@ fn decode_fixed_linear<B: ReadBitsLtr>(
     Ok(())
 }
 
-fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i32]) -> Result<()> {
+fn decode_linear<B: ReadBitsLtr, S: SubframeSample>(
+    bs: &mut B,
+    bps: u32,
+    order: u32,
+    buf: &mut [S],
+) -> Result<()> {
     // The order of the Linear Predictor should be between 1 and 32.
     debug_assert!(order > 0 && order <= 32);
 
@@ -433,9 +681,14 @@ fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i3
 
         // Helper function to dispatch to a predictor with a maximum order of N.
         #[inline(always)]
-        fn lpc<const N: usize>(order: u32, coeffs: &[i32; 32], coeff_shift: i32, buf: &mut [i32]) {
+        fn lpc<const N: usize, S: SubframeSample>(
+            order: u32,
+            coeffs: &[i32; 32],
+            coeff_shift: i32,
+            buf: &mut [S],
+        ) {
             let coeffs_n = (&coeffs[32 - N..32]).try_into().unwrap();
-            lpc_predict::<N>(order as usize, coeffs_n, coeff_shift as u32, buf);
+            lpc_predict::<N, S>(order as usize, coeffs_n, coeff_shift as u32, buf);
         }
 
         // Pick the best length linear predictor to use based on the order. Most FLAC streams use
@@ -444,12 +697,12 @@ fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i3
         // then there will be wasted computations. On the other hand, it is not worth the code bloat
         // to specialize for every order <= 12.
         match order {
-            0..=4 => lpc::<4>(order, &qlp_coeffs, qlp_coeff_shift, buf),
-            5..=6 => lpc::<6>(order, &qlp_coeffs, qlp_coeff_shift, buf),
-            7..=8 => lpc::<8>(order, &qlp_coeffs, qlp_coeff_shift, buf),
-            9..=10 => lpc::<10>(order, &qlp_coeffs, qlp_coeff_shift, buf),
-            11..=12 => lpc::<12>(order, &qlp_coeffs, qlp_coeff_shift, buf),
-            _ => lpc::<32>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            0..=4 => lpc::<4, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            5..=6 => lpc::<6, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            7..=8 => lpc::<8, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            9..=10 => lpc::<10, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            11..=12 => lpc::<12, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
+            _ => lpc::<32, S>(order, &qlp_coeffs, qlp_coeff_shift, buf),
         };
     }
     else {
@@ -459,10 +712,10 @@ fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i3
     Ok(())
 }
 
-fn decode_residual<B: ReadBitsLtr>(
+fn decode_residual<B: ReadBitsLtr, S: SubframeSample>(
     bs: &mut B,
     n_prelude_samples: u32,
-    buf: &mut [i32],
+    buf: &mut [S],
 ) -> Result<()> {
     let method_enc = bs.read_bits_leq32(2)?;
 
@@ -523,10 +776,10 @@ fn decode_residual<B: ReadBitsLtr>(
     Ok(())
 }
 
-fn decode_rice_partition<B: ReadBitsLtr>(
+fn decode_rice_partition<B: ReadBitsLtr, S: SubframeSample>(
     bs: &mut B,
     param_bit_width: u32,
-    buf: &mut [i32],
+    buf: &mut [S],
 ) -> Result<()> {
     // Read the encoding parameter, generally the Rice parameter.
     let rice_param = bs.read_bits_leq32(param_bit_width)?;
@@ -542,7 +795,7 @@ fn decode_rice_partition<B: ReadBitsLtr>(
         for sample in buf.iter_mut() {
             let q = bs.read_unary_zeros()?;
             let r = bs.read_bits_leq32(rice_param)?;
-            *sample = rice_signed_to_i32((q << rice_param) | r);
+            *sample = S::from_residual(rice_signed_to_i32((q << rice_param) | r));
         }
     }
     else {
@@ -556,7 +809,9 @@ fn decode_rice_partition<B: ReadBitsLtr>(
 
         // Read each binary encoded residual and store in buffer.
         for sample in buf.iter_mut() {
-            *sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(residual_bits)?, residual_bits);
+            let residual =
+                sign_extend_leq32_to_i32(bs.read_bits_leq32(residual_bits)?, residual_bits);
+            *sample = S::from_residual(residual);
         }
     }
 
@@ -609,7 +864,7 @@ fn verify_rice_signed_to_i32() {
     assert_eq!(rice_signed_to_i32(u32::max_value()), -2_147_483_648);
 }
 
-fn fixed_predict(order: u32, buf: &mut [i32]) {
+fn fixed_predict<S: SubframeSample>(order: u32, buf: &mut [S]) {
     debug_assert!(order <= 4);
 
     // The Fixed Predictor is just a hard-coded version of the Linear Predictor up to order 4 and
@@ -623,35 +878,35 @@ fn fixed_predict(order: u32, buf: &mut [i32]) {
         // s(i) = 1*s(i),
         1 => {
             for i in 1..buf.len() {
-                buf[i] += buf[i - 1];
+                buf[i] = buf[i].add_prediction(buf[i - 1].to_i64());
             }
         }
         // A 2nd order predictor uses the polynomial: s(i) = 2*s(i-1) - 1*s(i-2).
         2 => {
             for i in 2..buf.len() {
-                let a = Wrapping(-1) * Wrapping(i64::from(buf[i - 2]));
-                let b = Wrapping(2) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b).0 as i32;
+                let a = Wrapping(-1) * Wrapping(buf[i - 2].to_i64());
+                let b = Wrapping(2) * Wrapping(buf[i - 1].to_i64());
+                buf[i] = buf[i].add_prediction((a + b).0);
             }
         }
         // A 3rd order predictor uses the polynomial: s(i) = 3*s(i-1) - 3*s(i-2) + 1*s(i-3).
         3 => {
             for i in 3..buf.len() {
-                let a = Wrapping(1) * Wrapping(i64::from(buf[i - 3]));
-                let b = Wrapping(-3) * Wrapping(i64::from(buf[i - 2]));
-                let c = Wrapping(3) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c).0 as i32;
+                let a = Wrapping(1) * Wrapping(buf[i - 3].to_i64());
+                let b = Wrapping(-3) * Wrapping(buf[i - 2].to_i64());
+                let c = Wrapping(3) * Wrapping(buf[i - 1].to_i64());
+                buf[i] = buf[i].add_prediction((a + b + c).0);
             }
         }
         // A 4th order predictor uses the polynomial:
         // s(i) = 4*s(i-1) - 6*s(i-2) + 4*s(i-3) - 1*s(i-4).
         4 => {
             for i in 4..buf.len() {
-                let a = Wrapping(-1) * Wrapping(i64::from(buf[i - 4]));
-                let b = Wrapping(4) * Wrapping(i64::from(buf[i - 3]));
-                let c = Wrapping(-6) * Wrapping(i64::from(buf[i - 2]));
-                let d = Wrapping(4) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c + d).0 as i32;
+                let a = Wrapping(-1) * Wrapping(buf[i - 4].to_i64());
+                let b = Wrapping(4) * Wrapping(buf[i - 3].to_i64());
+                let c = Wrapping(-6) * Wrapping(buf[i - 2].to_i64());
+                let d = Wrapping(4) * Wrapping(buf[i - 1].to_i64());
+                buf[i] = buf[i].add_prediction((a + b + c + d).0);
             }
         }
         _ => unreachable!(),
@@ -662,7 +917,12 @@ fn fixed_predict(order: u32, buf: &mut [i32]) {
 /// specified by `order`. Coefficients must be stored in reverse order in `coeffs` with the first
 /// coefficient at index 31. Coefficients at indices less than 31 - `order` must be 0.
 /// It is expected that the first `order` samples in `buf` are warm-up samples.
-fn lpc_predict<const N: usize>(order: usize, coeffs: &[i32; N], coeff_shift: u32, buf: &mut [i32]) {
+fn lpc_predict<const N: usize, S: SubframeSample>(
+    order: usize,
+    coeffs: &[i32; N],
+    coeff_shift: u32,
+    buf: &mut [S],
+) {
     // Order must be less than or equal to the number of coefficients.
     debug_assert!(order <= coeffs.len());
 
@@ -677,10 +937,10 @@ fn lpc_predict<const N: usize>(order: usize, coeffs: &[i32; N], coeff_shift: u32
         let predicted = coeffs[N - order..N]
             .iter()
             .zip(&buf[i - order..i])
-            .map(|(&c, &sample)| c as i64 * sample as i64)
+            .map(|(&c, &sample)| c as i64 * sample.to_i64())
             .sum::<i64>();
 
-        buf[i] += (predicted >> coeff_shift) as i32;
+        buf[i] = buf[i].add_prediction(predicted >> coeff_shift);
     }
 
     // If the pre-fill operation filled the entire sample buffer, return immediately.
@@ -693,9 +953,126 @@ fn lpc_predict<const N: usize>(order: usize, coeffs: &[i32; N], coeff_shift: u32
         let predicted = coeffs
             .iter()
             .zip(&buf[i - N..i])
-            .map(|(&c, &s)| i64::from(c) * i64::from(s))
+            .map(|(&c, &s)| i64::from(c) * s.to_i64())
             .sum::<i64>();
 
-        buf[i] += (predicted >> coeff_shift) as i32;
+        buf[i] = buf[i].add_prediction(predicted >> coeff_shift);
     }
 }
+
+#[test]
+fn verify_decode_multichannel() {
+    use symphonia_core::audio::Channels;
+    use symphonia_core::checksum::Crc8Ccitt;
+    use symphonia_core::codecs::CodecParameters;
+
+    // A stream information block for a 6 channel (5.1), 16-bit, 44.1kHz stream with 192 samples.
+    let mut info = vec![0x00, 0xc0, 0x00, 0xc0, 0, 0, 0, 0, 0, 0];
+    info.extend_from_slice(&((44_100u64 << 44) | (5 << 41) | (15 << 36) | 192).to_be_bytes());
+    info.extend_from_slice(&[0; 16]);
+
+    // Builds a frame with 192 samples per channel, and a constant subframe for each channel.
+    let build_frame = |channels_enc: u8, num_channels: i16| {
+        let mut frame = vec![0xff, 0xf8, 0x19, 0x58 | (channels_enc << 4), 0x00];
+
+        let mut crc8 = Crc8Ccitt::new(0);
+        crc8.process_buf_bytes(&frame);
+        frame.push(crc8.crc());
+
+        for i in 0..num_channels {
+            frame.push(0x00);
+            frame.extend_from_slice(&(100 * (i + 1)).to_be_bytes());
+        }
+
+        let mut crc16 = Crc16Ansi::new(0);
+        crc16.process_buf_bytes(&frame);
+        frame.extend_from_slice(&crc16.crc().to_be_bytes());
+
+        Packet::new_from_slice(0, 0, 192, &frame)
+    };
+
+    let mut params = CodecParameters::new();
+    params.for_codec(CODEC_TYPE_FLAC).with_extra_data(info.into_boxed_slice());
+
+    let mut decoder = FlacDecoder::try_new(&params, &Default::default()).unwrap();
+
+    let expected = Channels::FRONT_LEFT
+        | Channels::FRONT_RIGHT
+        | Channels::FRONT_CENTRE
+        | Channels::LFE1
+        | Channels::REAR_LEFT
+        | Channels::REAR_RIGHT;
+
+    assert_eq!(decoder.codec_params().channels, Some(expected));
+
+    match decoder.decode(&build_frame(0x5, 6)).unwrap() {
+        AudioBufferRef::S32(buf) => {
+            assert_eq!(buf.spec().channels, expected);
+            assert_eq!(buf.frames(), 192);
+
+            for i in 0..6 {
+                assert!(buf.chan(i).iter().all(|&s| s == (100 * (i as i32 + 1)) << 16));
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    // Stereo decorrelation is only valid for 2 channel streams.
+    assert!(decoder.decode(&build_frame(0x8, 2)).is_err());
+    // The number of channels must match the stream.
+    assert!(decoder.decode(&build_frame(0x7, 8)).is_err());
+}
+
+#[test]
+fn verify_decode_statistics() {
+    use symphonia_core::checksum::Crc8Ccitt;
+    use symphonia_core::codecs::CodecParameters;
+
+    // A stream information block for a mono, 16-bit, 44.1kHz stream with 192 samples.
+    let mut info = vec![0x00, 0xc0, 0x00, 0xc0, 0, 0, 0, 0, 0, 0];
+    info.extend_from_slice(&((44_100u64 << 44) | (15 << 36) | 192).to_be_bytes());
+    info.extend_from_slice(&[0; 16]);
+
+    // Builds a frame with 192 samples, and a constant subframe.
+    let build_frame = || {
+        let mut frame = vec![0xff, 0xf8, 0x19, 0x08, 0x00];
+
+        let mut crc8 = Crc8Ccitt::new(0);
+        crc8.process_buf_bytes(&frame);
+        frame.push(crc8.crc());
+
+        frame.extend_from_slice(&[0x00, 0x01, 0x00]);
+
+        let mut crc16 = Crc16Ansi::new(0);
+        crc16.process_buf_bytes(&frame);
+        frame.extend_from_slice(&crc16.crc().to_be_bytes());
+
+        frame
+    };
+
+    // The demuxer does not guarantee the integrity of the packet data, so the decoder must check
+    // the frame CRC16.
+    let mut params = CodecParameters::new();
+    params.for_codec(CODEC_TYPE_FLAC).with_extra_data(info.into_boxed_slice());
+
+    let mut decoder = FlacDecoder::try_new(&params, &Default::default()).unwrap();
+
+    assert!(decoder.decode(&Packet::new_from_slice(0, 0, 192, &build_frame())).is_ok());
+
+    // Corrupt the frame header CRC8.
+    let mut frame = build_frame();
+    frame[5] ^= 0xff;
+
+    assert!(decoder.decode(&Packet::new_from_slice(0, 0, 192, &frame)).is_err());
+
+    // Corrupt the constant subframe value, such that the frame CRC16 no longer matches.
+    let mut frame = build_frame();
+    frame[7] ^= 0xff;
+
+    assert!(decoder.decode(&Packet::new_from_slice(0, 0, 192, &frame)).is_err());
+
+    let stats = decoder.statistics();
+
+    assert_eq!(stats.num_header_crc_errors, 1);
+    assert_eq!(stats.num_footer_crc_errors, 1);
+}
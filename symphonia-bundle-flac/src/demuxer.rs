@@ -9,24 +9,30 @@ use std::io::{Seek, SeekFrom};
 
 use symphonia_core::support_format;
 
+use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, VerificationCheck, CODEC_TYPE_FLAC};
 use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::formats::util::{SeekIndex, SeekSearchResult};
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, Tag, Value};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 
 use symphonia_utils_xiph::flac::metadata::*;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
-use super::parser::PacketParser;
+use super::frame::{read_frame_header, BlockSequence, FrameHeader};
+use super::parser::{PacketParser, ReadStatistics};
 
 /// The FLAC start of stream marker: "fLaC" in ASCII.
 const FLAC_STREAM_MARKER: [u8; 4] = *b"fLaC";
 
 /// Free Lossless Audio Codec (FLAC) native frame reader.
+///
+/// Streams of bare FLAC frames, without the stream marker and metadata blocks, are also supported
+/// if the first frame header states the sample rate and bits per sample. Such streams are not
+/// detected by the probe, and the reader must be instantiated directly.
 pub struct FlacReader {
     reader: MediaSourceStream,
     metadata: MetadataLog,
@@ -78,13 +84,26 @@ impl FlacReader {
                 MetadataBlockType::VorbisComment => {
                     read_comment_block(&mut block_stream, &mut metadata_builder)?;
                 }
-                // Cuesheet blocks are parsed into Cues.
+                // Cuesheet blocks are parsed into Cues. An invalid cuesheet is not fatal since the
+                // audio is still playable.
                 MetadataBlockType::Cuesheet => {
-                    read_cuesheet_block(&mut block_stream, &mut cues)?;
+                    let mut block_cues = Vec::new();
+
+                    match read_cuesheet_block(
+                        &mut block_stream,
+                        &mut block_cues,
+                        &mut metadata_builder,
+                    ) {
+                        Ok(_) => cues.append(&mut block_cues),
+                        Err(err) => warn!("ignoring invalid cuesheet: {}", err),
+                    }
                 }
-                // Picture blocks are read as Visuals.
+                // Picture blocks are read as Visuals. An invalid picture is not fatal since other
+                // pictures, and the audio, are still usable.
                 MetadataBlockType::Picture => {
-                    read_picture_block(&mut block_stream, &mut metadata_builder)?;
+                    if let Err(err) = read_picture_block(&mut block_stream, &mut metadata_builder) {
+                        warn!("ignoring invalid picture: {}", err);
+                    }
                 }
                 // StreamInfo blocks are parsed into Streams.
                 MetadataBlockType::StreamInfo => {
@@ -117,9 +136,16 @@ impl FlacReader {
             }
         }
 
+        let metadata_rev = metadata_builder.metadata();
+
+        // The channel layout may be overridden by a tag.
+        if let Some(track) = tracks.first_mut() {
+            apply_channel_mask_tag(metadata_rev.tags(), &mut track.codec_params);
+        }
+
         // Commit any read metadata to the metadata log.
         let mut metadata = MetadataLog::default();
-        metadata.push(metadata_builder.metadata());
+        metadata.push(metadata_rev);
 
         // Synchronize the packet parser to the first audio frame.
         let _ = parser.resync(&mut reader)?;
@@ -130,6 +156,49 @@ impl FlacReader {
 
         Ok(FlacReader { reader, metadata, tracks, cues, index, first_frame_offset, parser })
     }
+
+    /// Uses the first frame header, instead of a stream information block, to populate the
+    /// `FlacReader`.
+    fn init_with_frame_header(mut reader: MediaSourceStream) -> Result<Self> {
+        let first_frame_offset = reader.pos();
+
+        // The stream must start with a frame header with a valid CRC.
+        let sync = reader.read_be_u16()?;
+
+        if sync & 0xfffe != 0xfff8 {
+            return unsupported_error("flac: missing flac stream marker");
+        }
+
+        let header = match read_frame_header(&mut reader, sync) {
+            Ok(header) => header,
+            Err(_) => return unsupported_error("flac: missing flac stream marker"),
+        };
+
+        // Rewind the reader back to the start of the frame.
+        reader.seek_buffered(first_frame_offset);
+
+        let mut tracks = Vec::new();
+        let mut parser = Default::default();
+
+        let extra_data = synthesize_stream_info_block(&header)?;
+
+        tracks.push(new_track(extra_data, &mut parser)?);
+
+        Ok(FlacReader {
+            reader,
+            metadata: Default::default(),
+            tracks,
+            cues: Vec::new(),
+            index: None,
+            first_frame_offset,
+            parser,
+        })
+    }
+
+    /// Gets statistics on the integrity of the frames read thus far.
+    pub fn statistics(&self) -> ReadStatistics {
+        self.parser.statistics()
+    }
 }
 
 impl QueryDescriptor for FlacReader {
@@ -154,7 +223,11 @@ impl FormatReader for FlacReader {
         let marker = source.read_quad_bytes()?;
 
         if marker != FLAC_STREAM_MARKER {
-            return unsupported_error("flac: missing flac stream marker");
+            // Some streams consist only of FLAC frames without the stream marker or any metadata
+            // blocks. If the stream starts with a valid frame header, read the stream as such.
+            source.seek_buffered_rev(4);
+
+            return Self::init_with_frame_header(source);
         }
 
         // Strictly speaking, the first metadata block must be a StreamInfo block. There is
@@ -319,6 +392,39 @@ impl FormatReader for FlacReader {
     }
 }
 
+/// If a WAVEFORMATEXTENSIBLE_CHANNEL_MASK tag is present, overrides the default channel layout of
+/// the stream with the stated channel mask.
+fn apply_channel_mask_tag(tags: &[Tag], codec_params: &mut CodecParameters) {
+    let tag = match tags
+        .iter()
+        .find(|tag| tag.key.eq_ignore_ascii_case("WAVEFORMATEXTENSIBLE_CHANNEL_MASK"))
+    {
+        Some(tag) => tag,
+        _ => return,
+    };
+
+    // The channel mask is usually written as a hexadecimal number.
+    let mask = match &tag.value {
+        Value::String(value) => {
+            let value = value.trim();
+
+            match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                _ => value.parse::<u32>().ok(),
+            }
+        }
+        _ => None,
+    };
+
+    // The channel mask must be valid and have the same number of channels as the stream.
+    match (mask.and_then(Channels::from_bits), codec_params.channels) {
+        (Some(channels), Some(default)) if channels.count() == default.count() => {
+            codec_params.with_channels(channels);
+        }
+        _ => warn!("ignoring invalid channel mask: {}", tag.value),
+    }
+}
+
 /// Reads a StreamInfo block and populates the reader with stream information.
 fn read_stream_info_block<B: ReadBytes + FiniteStream>(
     reader: &mut B,
@@ -337,39 +443,158 @@ fn read_stream_info_block<B: ReadBytes + FiniteStream>(
         // data on the codec parameters.
         let extra_data = reader.read_boxed_slice_exact(reader.byte_len() as usize)?;
 
-        // Parse the stream info block.
-        let info = StreamInfo::read(&mut BufReader::new(&extra_data))?;
+        // Add the track.
+        tracks.push(new_track(extra_data, parser)?);
+    }
+    else {
+        return decode_error("flac: found more than one stream info block");
+    }
+
+    Ok(())
+}
 
-        // Populate the codec parameters with the basic audio parameters of the track.
-        let mut codec_params = CodecParameters::new();
+/// Creates a track from a stream information block, and resets the packet parser for it.
+fn new_track(extra_data: Box<[u8]>, parser: &mut PacketParser) -> Result<Track> {
+    // Parse the stream info block.
+    let info = StreamInfo::read(&mut BufReader::new(&extra_data))?;
+
+    // Populate the codec parameters with the basic audio parameters of the track.
+    let mut codec_params = CodecParameters::new();
+
+    codec_params
+        .for_codec(CODEC_TYPE_FLAC)
+        .with_packet_data_integrity(true)
+        .with_extra_data(extra_data)
+        .with_sample_rate(info.sample_rate)
+        .with_time_base(TimeBase::new(1, info.sample_rate))
+        .with_bits_per_sample(info.bits_per_sample)
+        .with_channels(info.channels);
+
+    if let Some(md5) = info.md5 {
+        codec_params.with_verification_code(VerificationCheck::Md5(md5));
+    }
+
+    // Total samples per channel (the total number of frames) is optional.
+    if let Some(n_frames) = info.n_samples {
+        codec_params.with_n_frames(n_frames);
+    }
 
-        codec_params
-            .for_codec(CODEC_TYPE_FLAC)
-            .with_packet_data_integrity(true)
-            .with_extra_data(extra_data)
-            .with_sample_rate(info.sample_rate)
-            .with_time_base(TimeBase::new(1, info.sample_rate))
-            .with_bits_per_sample(info.bits_per_sample)
-            .with_channels(info.channels);
+    // Reset the packet parser.
+    parser.reset(info);
 
-        if let Some(md5) = info.md5 {
-            codec_params.with_verification_code(VerificationCheck::Md5(md5));
-        }
+    Ok(Track::new(0, codec_params))
+}
 
-        // Total samples per channel (the total number of frames) is optional.
-        if let Some(n_frames) = info.n_samples {
-            codec_params.with_n_frames(n_frames);
+/// Synthesizes a stream information block from the first frame header of a stream that does not
+/// have one.
+fn synthesize_stream_info_block(header: &FrameHeader) -> Result<Box<[u8]>> {
+    // The frame header may defer the sample rate and bits per sample to the stream information
+    // block. If so, the stream cannot be decoded.
+    let (sample_rate, bits_per_sample) = match (header.sample_rate, header.bits_per_sample) {
+        (Some(sample_rate), Some(bits_per_sample)) => (sample_rate, bits_per_sample),
+        _ => {
+            return unsupported_error("flac: stream parameters are not stated in the frame header")
+        }
+    };
+
+    // For a fixed block size stream, all blocks except the last have the same length. For a
+    // variable block size stream, the block length may be anything.
+    let (block_len_min, block_len_max) = match header.block_sequence {
+        BlockSequence::ByFrame(_) => {
+            let block_len = header.block_num_samples.max(16);
+            (block_len, block_len)
         }
+        BlockSequence::BySample(_) => (16, u16::MAX),
+    };
+
+    let num_channels = u64::from(header.channel_assignment.num_channels());
+
+    let mut buf = Vec::with_capacity(34);
+
+    buf.extend_from_slice(&block_len_min.to_be_bytes());
+    buf.extend_from_slice(&block_len_max.to_be_bytes());
+    // The minimum and maximum frame byte lengths are unknown.
+    buf.extend_from_slice(&[0; 6]);
+    // The sample rate, channels, and bits per sample. The total number of samples is unknown.
+    let params = (u64::from(sample_rate) << 44)
+        | ((num_channels - 1) << 41)
+        | (u64::from(bits_per_sample - 1) << 36);
+    buf.extend_from_slice(&params.to_be_bytes());
+    // The MD5 checksum is unknown.
+    buf.extend_from_slice(&[0; 16]);
+
+    Ok(buf.into_boxed_slice())
+}
 
-        // Reset the packet parser.
-        parser.reset(info);
+#[cfg(test)]
+mod tests {
+    use super::FlacReader;
+    use symphonia_core::checksum::{Crc16Ansi, Crc8Ccitt};
+    use symphonia_core::formats::FormatReader;
+    use symphonia_core::io::{MediaSourceStream, Monitor};
 
-        // Add the track.
-        tracks.push(Track::new(0, codec_params));
-    }
-    else {
-        return decode_error("flac: found more than one stream info block");
+    /// Builds a mono, 16-bit, 44.1kHz frame with 192 samples, and a constant subframe.
+    fn build_frame(frame_num: u8) -> Vec<u8> {
+        let mut frame = vec![0xff, 0xf8, 0x19, 0x08, frame_num];
+
+        let mut crc8 = Crc8Ccitt::new(0);
+        crc8.process_buf_bytes(&frame);
+        frame.push(crc8.crc());
+
+        frame.extend_from_slice(&[0x00, 0x01, frame_num]);
+
+        let mut crc16 = Crc16Ansi::new(0);
+        crc16.process_buf_bytes(&frame);
+        frame.extend_from_slice(&crc16.crc().to_be_bytes());
+
+        frame
     }
 
-    Ok(())
+    #[test]
+    fn verify_read_statistics() {
+        // The stream marker, and the stream information block for a mono, 16-bit, 44.1kHz stream
+        // with 10 frames of 192 samples.
+        let mut file = b"fLaC".to_vec();
+        file.extend_from_slice(&[0x80, 0x00, 0x00, 0x22]);
+        file.extend_from_slice(&[0x00, 0xc0, 0x00, 0xc0, 0, 0, 0, 0, 0, 0]);
+        file.extend_from_slice(&((44_100u64 << 44) | (15 << 36) | (10 * 192)).to_be_bytes());
+        file.extend_from_slice(&[0; 16]);
+
+        for i in 0..10 {
+            let mut frame = build_frame(i);
+
+            // Corrupt the constant subframe value of the 4th frame, such that its CRC16 no longer
+            // matches.
+            if i == 3 {
+                frame[7] ^= 0xff;
+            }
+
+            file.extend_from_slice(&frame);
+
+            // Follow the 7th frame with more junk than the maximum size of a frame, such that
+            // synchronization is lost.
+            if i == 6 {
+                file.resize(file.len() + 16 * 1024 * 1024 + 16, 0);
+            }
+        }
+
+        let source =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(file)), Default::default());
+
+        let mut reader = FlacReader::try_new(source, &Default::default()).unwrap();
+
+        let mut frame_nums = Vec::new();
+
+        while let Ok(packet) = reader.next_packet() {
+            frame_nums.push(packet.buf()[4]);
+        }
+
+        // The corrupt 4th frame is discarded, and the 7th frame is lost with the synchronization.
+        assert_eq!(frame_nums, [0, 1, 2, 4, 5, 7, 8, 9]);
+
+        let stats = reader.statistics();
+
+        assert_eq!(stats.num_crc_errors, 1);
+        assert_eq!(stats.num_resyncs, 1);
+    }
 }
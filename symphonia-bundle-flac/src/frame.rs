@@ -53,6 +53,18 @@ pub enum ChannelAssignment {
     RightSide,
 }
 
+impl ChannelAssignment {
+    /// Gets the number of channels coded in the frame.
+    pub fn num_channels(&self) -> u32 {
+        match self {
+            ChannelAssignment::Independant(num) => *num,
+            ChannelAssignment::LeftSide => 2,
+            ChannelAssignment::MidSide => 2,
+            ChannelAssignment::RightSide => 2,
+        }
+    }
+}
+
 pub struct FrameHeader {
     pub block_sequence: BlockSequence,
     pub block_num_samples: u16,
@@ -75,6 +87,21 @@ pub fn sync_frame<B: ReadBytes>(reader: &mut B) -> Result<u16> {
 }
 
 pub fn read_frame_header<B: ReadBytes>(reader: &mut B, sync: u16) -> Result<FrameHeader> {
+    let (header, is_crc_ok) = read_frame_header_unchecked(reader, sync)?;
+
+    if !is_crc_ok && cfg!(not(fuzzing)) {
+        return decode_error("flac: computed frame header CRC does not match expected CRC");
+    }
+
+    Ok(header)
+}
+
+/// Reads a frame header, but instead of returning an error if the frame header CRC check fails,
+/// returns the frame header and the result of the CRC check.
+pub fn read_frame_header_unchecked<B: ReadBytes>(
+    reader: &mut B,
+    sync: u16,
+) -> Result<(FrameHeader, bool)> {
     // The header is checksummed with a CRC8 hash. Include the sync code in this CRC.
     let mut crc8 = Crc8Ccitt::new(0);
     crc8.process_buf_bytes(&sync.to_be_bytes());
@@ -186,6 +213,7 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B, sync: u16) -> Result<Fram
         0x4 => Some(16),
         0x5 => Some(20),
         0x6 => Some(24),
+        0x7 => Some(32),
         _ => {
             return decode_error("flac: bits per sample set to reserved value");
         }
@@ -207,17 +235,15 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B, sync: u16) -> Result<Fram
     // Get expected CRC8 checksum from the header.
     let crc8_expected = reader_crc8.into_inner().read_u8()?;
 
-    if crc8_expected != crc8_computed && cfg!(not(fuzzing)) {
-        return decode_error("flac: computed frame header CRC does not match expected CRC");
-    }
-
-    Ok(FrameHeader {
+    let header = FrameHeader {
         block_sequence,
         block_num_samples,
         channel_assignment,
         bits_per_sample,
         sample_rate,
-    })
+    };
+
+    Ok((header, crc8_expected == crc8_computed))
 }
 
 /// A very quick check if the provided buffer is likely be a FLAC frame header.
@@ -253,7 +279,7 @@ pub fn is_likely_frame_header(buf: &[u8]) -> bool {
     }
 
     // Reserved sample size.
-    if (buf[3] & 0x0e == 0x6) || (buf[3] & 0x0e == 0x0e) {
+    if buf[3] & 0x0e == 0x6 {
         return false;
     }
 
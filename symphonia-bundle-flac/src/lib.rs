@@ -20,5 +20,6 @@ mod frame;
 mod parser;
 mod validate;
 
-pub use decoder::FlacDecoder;
+pub use decoder::{DecodeStatistics, FlacDecoder};
 pub use demuxer::FlacReader;
+pub use parser::ReadStatistics;
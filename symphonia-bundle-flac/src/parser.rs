@@ -65,6 +65,17 @@ pub struct SyncInfo {
     pub dur: u64,
 }
 
+/// Statistics on the integrity of the frames read by the packet parser.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ReadStatistics {
+    /// The number of fragments that were discarded because they could not be merged to form a
+    /// frame with a CRC16 that matched the expected CRC in the frame footer.
+    pub num_crc_errors: u64,
+    /// The number of times synchronization was lost and the reader had to resynchronize to the next
+    /// frame.
+    pub num_resyncs: u64,
+}
+
 /// A parsed packet.
 struct ParsedPacket {
     /// The packet data.
@@ -159,6 +170,8 @@ struct PacketBuilder {
     avg_size: Option<usize>,
     /// The last valid header,
     last_header: Option<FrameHeader>,
+    /// The number of fragments discarded due to a CRC mismatch.
+    num_crc_errors: u64,
 }
 
 impl PacketBuilder {
@@ -221,6 +234,7 @@ impl PacketBuilder {
 
             if prune {
                 self.frags.remove(0);
+                self.num_crc_errors += 1;
             }
         }
 
@@ -231,7 +245,10 @@ impl PacketBuilder {
 
     fn try_build(&mut self, stream_info: &StreamInfo, frag: Fragment) -> Option<ParsedPacket> {
         let (header, data) = if frag.crc_match {
-            // The fragment has a CRC that matches the expected CRC.
+            // The fragment has a CRC that matches the expected CRC. All existing fragments will be
+            // discarded.
+            self.num_crc_errors += self.frags.len() as u64;
+
             (frag.parse_header(), frag.data)
         }
         else {
@@ -245,7 +262,10 @@ impl PacketBuilder {
             let start = self.frags.iter_mut().position(|f| f.update(&frag));
 
             if let Some(i) = start {
-                // A range of fragments has been found that forms a packet.
+                // A range of fragments has been found that forms a packet. All fragments preceeding
+                // the range will be discarded.
+                self.num_crc_errors += i as u64;
+
                 let total_len = self.frags[i].state.total_len;
 
                 // debug!("merging {} fragments: total_len={}", self.frags.len() - i + 1, total_len);
@@ -293,6 +313,8 @@ pub struct PacketParser {
     fsma: MovingAverage<4>,
     /// Packet builder.
     builder: PacketBuilder,
+    /// The number of times synchronization was lost.
+    num_resyncs: u64,
 }
 
 impl PacketParser {
@@ -423,6 +445,7 @@ impl PacketParser {
 
             // If a fragment could not be read, synchronization was lost. Try to resync.
             warn!("synchronization lost");
+            self.num_resyncs += 1;
             let _ = self.resync(reader)?;
         }
     }
@@ -452,6 +475,14 @@ impl PacketParser {
         Ok(Packet::new_from_boxed_slice(0, parsed.sync.ts, parsed.sync.dur, parsed.buf))
     }
 
+    /// Gets statistics on the integrity of the frames read thus far.
+    pub fn statistics(&self) -> ReadStatistics {
+        ReadStatistics {
+            num_crc_errors: self.builder.num_crc_errors,
+            num_resyncs: self.num_resyncs,
+        }
+    }
+
     /// Resync the reader to the start of the next frame.
     pub fn resync<B>(&mut self, reader: &mut B) -> Result<SyncInfo>
     where
@@ -503,20 +534,14 @@ impl PacketParser {
 }
 
 fn calc_sync_info(stream_info: &StreamInfo, header: &FrameHeader) -> SyncInfo {
-    let is_fixed = stream_info.block_len_max == stream_info.block_len_min;
-
     let dur = u64::from(header.block_num_samples);
 
     let ts = match header.block_sequence {
         BlockSequence::BySample(sample) => sample,
-        BlockSequence::ByFrame(frame) if is_fixed => {
-            u64::from(frame) * u64::from(stream_info.block_len_min)
-        }
-        BlockSequence::ByFrame(frame) => {
-            // This should not happen in practice.
-            warn!("got a fixed size frame for a variable stream, the timestamp may be off");
-            u64::from(frame) * dur
-        }
+        // For a fixed block size stream, every block except the last has the maximum block length.
+        // The minimum block length may be smaller than the maximum if the encoder accounted for a
+        // shorter last block, therefore the maximum must be used here.
+        BlockSequence::ByFrame(frame) => u64::from(frame) * u64::from(stream_info.block_len_max),
     };
 
     SyncInfo { ts, dur }
@@ -558,12 +583,27 @@ fn strict_frame_header_check(
         _ => 0,
     };
 
-    // Sequence scoring: The fragment's blocking strategy is consistent with the stream
-    // information block, and the sequence number (frame number or sample number) is
-    // monotonic given the current state.
+    // Strategy scoring: The blocking strategy may not change within a stream. Without a previous
+    // header, the stream information block can only rule out the variable blocking strategy since a
+    // fixed block size stream may state a minimum block length smaller than the maximum if the last
+    // block is shorter.
+    let is_consistent = match (&header.block_sequence, last_header.map(|h| &h.block_sequence)) {
+        (BlockSequence::BySample(_), Some(BlockSequence::BySample(_))) => true,
+        (BlockSequence::ByFrame(_), Some(BlockSequence::ByFrame(_))) => true,
+        (BlockSequence::BySample(_), None) => !is_fixed,
+        (BlockSequence::ByFrame(_), None) => true,
+        _ => false,
+    };
+
+    if !is_consistent {
+        return false;
+    }
+
+    // Sequence scoring: The sequence number (frame number or sample number) is monotonic given the
+    // current state.
     let is_monotonic = match header.block_sequence {
-        BlockSequence::BySample(sample) => !is_fixed && (sample > last_seq || sample == 0),
-        BlockSequence::ByFrame(frame) => is_fixed && (u64::from(frame) > last_seq || frame == 0),
+        BlockSequence::BySample(sample) => sample > last_seq || sample == 0,
+        BlockSequence::ByFrame(frame) => u64::from(frame) > last_seq || frame == 0,
     };
 
     if !is_monotonic {
@@ -571,14 +611,7 @@ fn strict_frame_header_check(
     }
 
     // Channel assignments.
-    let num_frame_channels = match header.channel_assignment {
-        ChannelAssignment::Independant(num) => num,
-        ChannelAssignment::LeftSide => 2,
-        ChannelAssignment::MidSide => 2,
-        ChannelAssignment::RightSide => 2,
-    };
-
-    if num_frame_channels != stream_info.channels.count() as u32 {
+    if header.channel_assignment.num_channels() != stream_info.channels.count() as u32 {
         return false;
     }
 
@@ -106,6 +106,8 @@ pub struct FrameHeader {
     #[allow(dead_code)]
     pub has_padding: bool,
     pub has_crc: bool,
+    /// The frame uses the free bit-rate. The bit-rate and frame size are not stated in the header.
+    pub is_free_format: bool,
     pub frame_size: usize,
 }
 
@@ -8,7 +8,7 @@
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CodecType};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Error, Result};
 use symphonia_core::formats::Packet;
 use symphonia_core::io::FiniteStream;
 use symphonia_core::support_codec;
@@ -29,6 +29,8 @@ use crate::layer2;
 #[cfg(feature = "mp3")]
 use crate::layer3;
 
+use log::warn;
+
 enum State {
     #[cfg(feature = "mp1")]
     Layer1(layer1::Layer1),
@@ -57,13 +59,19 @@ pub struct MpaDecoder {
     params: CodecParameters,
     state: State,
     buf: AudioBuffer<f32>,
+    conceal_errors: bool,
 }
 
 impl MpaDecoder {
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
         let mut reader = packet.as_buf_reader();
 
-        let header = header::read_frame_header(&mut reader)?;
+        let mut header = header::read_frame_header(&mut reader)?;
+
+        // The size of a free bit-rate frame is not stated in the header, it is the packet size.
+        if header.is_free_format {
+            header::set_free_format_frame_size(&mut header, reader.bytes_available() as usize)?;
+        }
 
         // The packet should be the size stated in the header.
         if header.frame_size != reader.bytes_available() as usize {
@@ -99,6 +107,14 @@ impl MpaDecoder {
             }
             #[cfg(feature = "mp3")]
             State::Layer3(layer) if header.layer == MpegLayer::Layer3 => {
+                // When concealing errors, a frame failing its CRC check is treated as corrupt. The
+                // bit resevoir is discarded since the side information of the frame cannot be
+                // trusted.
+                if self.conceal_errors && !layer3::check_crc(packet.buf(), &header) {
+                    layer.resevoir.clear();
+                    return decode_error("mpa: crc check failed");
+                }
+
                 layer.decode(&mut reader, &header, &mut self.buf)?;
             }
             _ => return decode_error("mpa: invalid mpeg audio layer"),
@@ -108,10 +124,21 @@ impl MpaDecoder {
 
         Ok(())
     }
+
+    /// Conceals a packet that failed to decode by replacing it with silence.
+    fn conceal(&mut self, packet: &Packet) {
+        // The duration of a packet excludes any frames to be trimmed.
+        let n_frames =
+            packet.dur() as usize + packet.trim_start() as usize + packet.trim_end() as usize;
+
+        self.buf.clear();
+        self.buf.render_silence(Some(n_frames.min(self.buf.capacity())));
+        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+    }
 }
 
 impl Decoder for MpaDecoder {
-    fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
         // This decoder only supports MP1, MP2, and MP3.
         match params.codec {
             #[cfg(feature = "mp1")]
@@ -126,7 +153,12 @@ impl Decoder for MpaDecoder {
         // Create decoder state.
         let state = State::new(params.codec);
 
-        Ok(MpaDecoder { params: params.clone(), state, buf: AudioBuffer::unused() })
+        Ok(MpaDecoder {
+            params: params.clone(),
+            state,
+            buf: AudioBuffer::unused(),
+            conceal_errors: options.conceal_errors,
+        })
     }
 
     fn supported_codecs() -> &'static [CodecDescriptor] {
@@ -150,12 +182,19 @@ impl Decoder for MpaDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
-        if let Err(e) = self.decode_inner(packet) {
-            self.buf.clear();
-            Err(e)
-        }
-        else {
-            Ok(self.buf.as_audio_buffer_ref())
+        match self.decode_inner(packet) {
+            Ok(_) => Ok(self.buf.as_audio_buffer_ref()),
+            // A corrupt packet may be concealed, but only after the first packet was decoded since
+            // the signal specification is otherwise unknown.
+            Err(Error::DecodeError(err)) if self.conceal_errors && !self.buf.is_unused() => {
+                warn!("mpa: concealing decode error: {}", err);
+                self.conceal(packet);
+                Ok(self.buf.as_audio_buffer_ref())
+            }
+            Err(err) => {
+                self.buf.clear();
+                Err(err)
+            }
         }
     }
 
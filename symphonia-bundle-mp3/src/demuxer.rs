@@ -9,11 +9,16 @@ use symphonia_core::support_format;
 
 use symphonia_core::checksum::Crc16AnsiLe;
 use symphonia_core::codecs::CodecParameters;
-use symphonia_core::errors::{seek_error, Result, SeekErrorKind};
+use symphonia_core::errors::{
+    decode_error, end_of_stream_error, seek_error, Result, SeekErrorKind,
+};
 use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::util::{SeekIndex, SeekSearchResult};
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_metadata::id3v2::{self, read_id3v2_with_chapters, Chapter, Chapters};
+use symphonia_metadata::{apev2, id3v1, lyrics3};
 
 use crate::common::{FrameHeader, MpegLayer};
 use crate::header::{self, MAX_MPEG_FRAME_SIZE, MPEG_HEADER_LEN};
@@ -32,7 +37,16 @@ pub struct MpaReader {
     metadata: MetadataLog,
     options: FormatOptions,
     first_packet_pos: u64,
+    /// The position of the end of the audio data, if known. Tags may follow the audio data.
+    audio_end_pos: Option<u64>,
     next_packet_ts: u64,
+    /// If false, the next packet timestamp was estimated by a coarse seek.
+    is_next_packet_ts_exact: bool,
+    /// An index of the packets read so far with exact timestamps.
+    index: SeekIndex,
+    free_format_frame_len: Option<usize>,
+    seek_toc: Option<SeekToc>,
+    cbr: Option<CbrParams>,
 }
 
 impl QueryDescriptor for MpaReader {
@@ -42,7 +56,7 @@ impl QueryDescriptor for MpaReader {
             support_format!(
                 "mp1",
                 "MPEG Audio Layer 1 Native",
-                &["mp1"],
+                &["mp1", "m1a"],
                 &["audio/mpeg", "audio/mp1"],
                 &[
                     &[0xff, 0xfe], // MPEG 1 with CRC
@@ -57,7 +71,7 @@ impl QueryDescriptor for MpaReader {
             support_format!(
                 "mp2",
                 "MPEG Audio Layer 2 Native",
-                &["mp2"],
+                &["mp2", "m2a", "mpa"],
                 &["audio/mpeg", "audio/mp2"],
                 &[
                     &[0xff, 0xfc], // MPEG 1 with CRC
@@ -93,8 +107,34 @@ impl QueryDescriptor for MpaReader {
 
 impl FormatReader for MpaReader {
     fn try_new(mut source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
+        // The length of an unpadded free bit-rate frame, if the stream uses the free bit-rate.
+        let mut free_format_frame_len = None;
+
+        let mut metadata = MetadataLog::default();
+
+        // Tags may also be appended to the end of the stream, after the audio data. Since these
+        // tags are not required for playback, errors encountered while reading them are not fatal.
+        let audio_end_pos = if source.is_seekable() {
+            let start_pos = source.pos();
+
+            let audio_end_pos = match read_trailing_tags(&mut source, &mut metadata) {
+                Ok(audio_end_pos) => audio_end_pos,
+                Err(err) => {
+                    warn!("ignoring tags at the end of the stream: {}", err);
+                    source.byte_len()
+                }
+            };
+
+            source.seek(SeekFrom::Start(start_pos))?;
+
+            audio_end_pos
+        }
+        else {
+            None
+        };
+
         // Try to read the first MPEG frame.
-        let (header, packet) = read_mpeg_frame_strict(&mut source)?;
+        let (header, packet) = read_mpeg_frame_strict(&mut source, &mut free_format_frame_len)?;
 
         // Use the header to populate the codec parameters.
         let mut params = CodecParameters::new();
@@ -105,8 +145,16 @@ impl FormatReader for MpaReader {
             .with_time_base(TimeBase::new(1, header.sample_rate))
             .with_channels(header.channel_mode.channels());
 
+        let mut seek_toc = None;
+        let mut cbr = None;
+
         // Check if there is a Xing/Info tag contained in the first frame.
         if let Some(info_tag) = try_read_info_tag(&packet, &header) {
+            // The "Info" variant of the tag is written for CBR streams.
+            if info_tag.is_cbr {
+                cbr = Some(CbrParams::new(&header));
+            }
+
             // The LAME tag contains ReplayGain and padding information.
             let (delay, padding) = if let Some(lame_tag) = info_tag.lame {
                 params.with_delay(lame_tag.enc_delay).with_padding(lame_tag.enc_padding);
@@ -123,14 +171,31 @@ impl FormatReader for MpaReader {
 
                 let num_frames = u64::from(num_mpeg_frames) * header.duration();
 
-                // Adjust for gapless playback.
+                // Adjust for gapless playback. A malformed LAME tag may state more delay and
+                // padding than there are frames.
                 if options.enable_gapless {
-                    params.with_n_frames(num_frames - u64::from(delay) - u64::from(padding));
+                    let trim = u64::from(delay) + u64::from(padding);
+
+                    params.with_n_frames(num_frames.saturating_sub(trim));
                 }
                 else {
                     params.with_n_frames(num_frames);
                 }
             }
+
+            // The Xing/Info TOC is relative to the start of the frame containing the tag, and is
+            // scaled by the length of the stream from that point.
+            let base_pos = source.pos() - packet.len() as u64;
+
+            let num_bytes = info_tag
+                .num_bytes
+                .map(u64::from)
+                .or_else(|| audio_end_pos.map(|end| end.saturating_sub(base_pos)));
+
+            if let (Some(toc), Some(num_bytes)) = (info_tag.toc, num_bytes) {
+                debug!("using xing header toc for seeking");
+                seek_toc = Some(SeekToc::Xing { toc, base_pos, num_bytes });
+            }
         }
         else if let Some(vbri_tag) = try_read_vbri_tag(&packet, &header) {
             debug!("using vbri header for duration");
@@ -139,6 +204,24 @@ impl FormatReader for MpaReader {
 
             // Check if there is a VBRI tag.
             params.with_n_frames(num_frames);
+
+            // The VBRI TOC stores the length of each group of frames following the frame
+            // containing the tag. Convert the lengths into the absolute positions of each group.
+            if let Some(toc) = vbri_tag.toc {
+                debug!("using vbri header toc for seeking");
+
+                let mut positions = Vec::with_capacity(toc.entries.len());
+                let mut pos = source.pos();
+
+                for len in toc.entries {
+                    positions.push(pos);
+                    pos += len;
+                }
+
+                let ts_per_entry = u64::from(toc.frames_per_entry) * header.duration();
+
+                seek_toc = Some(SeekToc::Vbri { positions, ts_per_entry });
+            }
         }
         else {
             // The first frame was not a Xing/Info header, rewind back to the start of the frame so
@@ -149,29 +232,74 @@ impl FormatReader for MpaReader {
             if source.is_seekable() {
                 info!("estimating duration from bitrate, may be inaccurate for vbr files");
 
-                if let Some(n_mpeg_frames) = estimate_num_mpeg_frames(&mut source) {
+                if let Some((n_mpeg_frames, is_cbr)) =
+                    estimate_num_mpeg_frames(&mut source, audio_end_pos, &mut free_format_frame_len)
+                {
                     params.with_n_frames(n_mpeg_frames * header.duration());
+
+                    // The first frames of a VBR stream may coincidentally have the same bit-rate,
+                    // so also sample frames from throughout the rest of the stream.
+                    if is_cbr
+                        && is_bitrate_constant(
+                            &mut source,
+                            audio_end_pos,
+                            &header,
+                            &mut free_format_frame_len,
+                        )?
+                    {
+                        debug!("stream is cbr");
+                        cbr = Some(CbrParams::new(&header));
+                    }
                 }
             }
         }
 
         let first_packet_pos = source.pos();
 
+        // Podcasts commonly carry chapters in the ID3v2 tag at the start of the stream. This tag
+        // was consumed by the probe, so if possible, re-read it for the chapters and convert them
+        // into cues.
+        let cues = if source.is_seekable() {
+            let chapters = read_id3v2_chapters(&mut source)?;
+
+            // If gapless playback is disabled, the timeline starts with the encoder delay.
+            let delay = if options.enable_gapless { 0 } else { params.delay.unwrap_or(0) };
+
+            chapters_to_cues(chapters, header.sample_rate, delay)
+        }
+        else {
+            Vec::new()
+        };
+
         Ok(MpaReader {
             reader: source,
             tracks: vec![Track::new(0, params)],
-            cues: Vec::new(),
-            metadata: Default::default(),
+            cues,
+            metadata,
             options: *options,
             first_packet_pos,
+            audio_end_pos,
             next_packet_ts: 0,
+            is_next_packet_ts_exact: true,
+            index: SeekIndex::with_max_len(MAX_INDEX_LEN),
+            free_format_frame_len,
+            seek_toc,
+            cbr,
         })
     }
 
     fn next_packet(&mut self) -> Result<Packet> {
         let (header, packet) = loop {
+            // Do not attempt to read the tags that follow the audio data as MPEG frames.
+            if let Some(audio_end_pos) = self.audio_end_pos {
+                if self.reader.pos() >= audio_end_pos {
+                    return end_of_stream_error();
+                }
+            }
+
             // Read the next MPEG frame.
-            let (header, packet) = read_mpeg_frame(&mut self.reader)?;
+            let (header, packet) =
+                read_mpeg_frame(&mut self.reader, &mut self.free_format_frame_len)?;
 
             // Check if the packet contains a Xing, Info, or VBRI tag.
             if is_maybe_info_tag(&packet, &header) {
@@ -198,6 +326,11 @@ impl FormatReader for MpaReader {
 
         self.next_packet_ts += duration;
 
+        // Index the packet so that accurate seeks back to it do not require a rewind to the start
+        // of the stream.
+        let pos = self.reader.pos() - packet.len() as u64;
+        self.index_packet(ts, pos, duration);
+
         let mut packet = Packet::new_from_boxed_slice(0, ts, duration, packet.into_boxed_slice());
 
         if self.options.enable_gapless {
@@ -224,7 +357,6 @@ impl FormatReader for MpaReader {
     }
 
     fn seek(&mut self, mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
-        const MAX_REF_FRAMES: usize = 4;
         const REF_FRAMES_MASK: usize = MAX_REF_FRAMES - 1;
 
         // Get the timestamp of the desired audio frame.
@@ -273,9 +405,13 @@ impl FormatReader for MpaReader {
         //
         // In accurate seek mode, the underlying media source stream will not be seeked unless the
         // required timestamp is in the past, in which case the stream is seeked back to the start.
-        match mode {
-            SeekMode::Coarse if is_seekable => self.preseek_coarse(required_ts, delay)?,
-            SeekMode::Accurate => self.preseek_accurate(required_ts)?,
+        //
+        // However, if the stream is CBR, the position of any packet can be calculated directly. In
+        // this case, the underlying media source stream is seeked to it regardless of the mode.
+        match (mode, self.cbr) {
+            (_, Some(cbr)) if is_seekable => self.preseek_cbr(required_ts, cbr)?,
+            (SeekMode::Coarse, _) if is_seekable => self.preseek_coarse(required_ts, delay)?,
+            (SeekMode::Accurate, _) => self.preseek_accurate(required_ts)?,
             _ => (),
         };
 
@@ -290,7 +426,9 @@ impl FormatReader for MpaReader {
 
         loop {
             // Parse the next frame header.
-            let header = header::parse_frame_header(header::sync_frame(&mut self.reader)?)?;
+            let sync = header::sync_frame(&mut self.reader)?;
+            let header =
+                parse_frame_header(&mut self.reader, sync, &mut self.free_format_frame_len)?;
 
             // Position of the frame header.
             let pos = self.reader.pos() - std::mem::size_of::<u32>() as u64;
@@ -302,6 +440,8 @@ impl FormatReader for MpaReader {
             frames[n_parsed & REF_FRAMES_MASK] = FramePos { pos, ts: self.next_packet_ts };
             n_parsed += 1;
 
+            self.index_packet(self.next_packet_ts, pos, duration);
+
             // If the next frame's timestamp would exceed the desired timestamp, rewind back to the
             // start of this frame and end the search.
             if self.next_packet_ts + duration > required_ts {
@@ -393,9 +533,9 @@ impl MpaReader {
             0
         };
 
-        // Get the total byte length of the stream. It is not possible to seek without this.
-        let total_byte_len = match self.reader.byte_len() {
-            Some(byte_len) => byte_len,
+        // Get the position of the end of the audio data. It is not possible to seek without this.
+        let audio_end_pos = match self.audio_end_pos {
+            Some(audio_end_pos) => audio_end_pos,
             None => return seek_error(SeekErrorKind::Unseekable),
         };
 
@@ -406,8 +546,38 @@ impl MpaReader {
             None => return seek_error(SeekErrorKind::Unseekable),
         };
 
+        // If the stream has a table of contents, use it to find the position of the packet.
+        if let Some(seek_toc) = &self.seek_toc {
+            // As above, seek to a position slightly before the packet.
+            let seek_pos = seek_toc
+                .position_of(required_ts, duration)
+                .saturating_sub(MAX_MPEG_FRAME_SIZE)
+                .max(self.first_packet_pos);
+
+            self.reader.seek(SeekFrom::Start(seek_pos))?;
+
+            // Resync to the start of the next packet, then rewind back to it.
+            let (header, packet) =
+                read_mpeg_frame_strict(&mut self.reader, &mut self.free_format_frame_len)?;
+
+            self.reader.seek_buffered_rev(packet.len());
+
+            // Since the TOC is approximate, the timestamp must be derived from the position of
+            // the packet actually synced to. Round it to a multiple of the packet duration.
+            let ts = match self.reader.pos() {
+                pos if pos <= self.first_packet_pos => 0,
+                pos => seek_toc.timestamp_of(pos, duration),
+            };
+            let packet_dur = header.duration();
+
+            self.next_packet_ts = (ts / packet_dur) * packet_dur;
+            self.is_next_packet_ts_exact = false;
+
+            return Ok(());
+        }
+
         // Calculate the total size of the audio data.
-        let audio_byte_len = total_byte_len - self.first_packet_pos;
+        let audio_byte_len = audio_end_pos.saturating_sub(self.first_packet_pos).max(1);
 
         // Calculate, roughly, where the packet containing the required timestamp is in the media
         // source stream relative to the start of the audio data.
@@ -422,7 +592,8 @@ impl MpaReader {
         self.reader.seek(SeekFrom::Start(seek_pos))?;
 
         // Resync to the start of the next packet.
-        let (header, _) = read_mpeg_frame_strict(&mut self.reader)?;
+        let (header, _) =
+            read_mpeg_frame_strict(&mut self.reader, &mut self.free_format_frame_len)?;
 
         // Calculate, roughly, the timestamp of the packet based on the byte position after resync.
         let seeked_pos = self.reader.pos();
@@ -435,14 +606,70 @@ impl MpaReader {
         let packet_dur = header.duration();
 
         self.next_packet_ts = (ts / packet_dur) * packet_dur;
+        self.is_next_packet_ts_exact = false;
 
         Ok(())
     }
 
-    /// Seeks the media source stream back to the start of the first packet if the required
-    /// timestamp is in the past.
+    /// Seeks the media source stream to a few packets before the packet with the required timestamp
+    /// in a CBR stream.
+    fn preseek_cbr(&mut self, required_ts: u64, cbr: CbrParams) -> Result<()> {
+        // Seek to a few packets before the required packet so that the packets containing the
+        // main data of the required packet may be found.
+        let frame = (required_ts / cbr.frame_dur).saturating_sub(MAX_REF_FRAMES as u64);
+
+        self.reader.seek(SeekFrom::Start(self.first_packet_pos + cbr.frame_offset(frame)))?;
+
+        // Due to padding, the calculated position may be off by a few bytes. Resync to the start
+        // of the next packet, then rewind back to it.
+        let (_, packet) =
+            read_mpeg_frame_strict(&mut self.reader, &mut self.free_format_frame_len)?;
+
+        self.reader.seek_buffered_rev(packet.len());
+
+        // Calculate the timestamp of the packet from its actual position.
+        let offset = self.reader.pos().saturating_sub(self.first_packet_pos);
+
+        self.next_packet_ts = cbr.frame_at(offset) * cbr.frame_dur;
+        self.is_next_packet_ts_exact = true;
+
+        Ok(())
+    }
+
+    /// Seeks the media source stream to the nearest indexed packet a few packets before the packet
+    /// with the required timestamp, or back to the start of the first packet, if the required
+    /// timestamp is in the past or the timestamp of the next packet is not exact.
     fn preseek_accurate(&mut self, required_ts: u64) -> Result<()> {
-        if required_ts < self.next_packet_ts {
+        // Search the index for a packet a few packets before the required packet so that the
+        // packets containing the main data of the required packet may be found.
+        let search_ts = required_ts.saturating_sub(MAX_REF_FRAMES as u64 * 1152);
+
+        if let SeekSearchResult::Lower(point) | SeekSearchResult::Range(point, _) =
+            self.index.search(search_ts)
+        {
+            // Only seek to the indexed packet if reading forward from the current position would
+            // not reach the required packet sooner. Reading forward must also pass through the
+            // same reference packets that reading from the indexed packet would.
+            if search_ts < self.next_packet_ts
+                || point.frame_ts > self.next_packet_ts
+                || !self.is_next_packet_ts_exact
+            {
+                let seeked_pos = self.reader.seek(SeekFrom::Start(point.byte_offset))?;
+
+                if seeked_pos != point.byte_offset {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+
+                debug!("seeked to indexed packet with ts={} @ pos={}", point.frame_ts, seeked_pos);
+
+                self.next_packet_ts = point.frame_ts;
+                self.is_next_packet_ts_exact = true;
+            }
+
+            return Ok(());
+        }
+
+        if required_ts < self.next_packet_ts || !self.is_next_packet_ts_exact {
             let seeked_pos = self.reader.seek(SeekFrom::Start(self.first_packet_pos))?;
 
             // Since the elementary stream has no timestamp information, the position seeked
@@ -453,20 +680,260 @@ impl MpaReader {
 
             // Successfuly seeked to the start of the stream, reset the next packet timestamp.
             self.next_packet_ts = 0;
+            self.is_next_packet_ts_exact = true;
         }
 
         Ok(())
     }
+
+    /// Adds a packet to the index if its timestamp is exact.
+    fn index_packet(&mut self, ts: u64, pos: u64, duration: u64) {
+        if self.is_next_packet_ts_exact && self.cbr.is_none() {
+            self.index.insert(ts, pos, duration as u32);
+        }
+    }
+}
+
+/// Parses a MPEG frame header word that was just read from the stream.
+///
+/// The size of a free bit-rate frame is not stated in its header. Instead, the distance to the next
+/// frame header is measured for the first free bit-rate frame encountered, and then used for all
+/// subsequent frames.
+fn parse_frame_header(
+    reader: &mut MediaSourceStream,
+    sync: u32,
+    free_format_frame_len: &mut Option<usize>,
+) -> Result<FrameHeader> {
+    let mut header = header::parse_frame_header(sync)?;
+
+    if header.is_free_format {
+        let frame_len = match *free_format_frame_len {
+            Some(frame_len) => frame_len,
+            None => {
+                let frame_len = measure_free_format_frame_len(reader, &header)?;
+
+                debug!("free bit-rate frame length is {} bytes", frame_len);
+
+                *free_format_frame_len = Some(frame_len);
+                frame_len
+            }
+        };
+
+        let frame_size = frame_len + header::padding_len(&header) - MPEG_HEADER_LEN;
+
+        header::set_free_format_frame_size(&mut header, frame_size)?;
+    }
+
+    Ok(header)
+}
+
+/// Measures the length, in bytes, of an unpadded free bit-rate frame (including the header) by
+/// finding the header of the next frame. The stream must be positioned immediately after the
+/// header of the current frame, and will be returned to that position.
+fn measure_free_format_frame_len(
+    reader: &mut MediaSourceStream,
+    header: &FrameHeader,
+) -> Result<usize> {
+    // The largest free bit-rate frame is a MPEG2.5 layer 3 frame at 8kHz and 640kbit/s.
+    const MAX_FREE_FORMAT_FRAME_LEN: u64 = 5761;
+
+    let header_pos = reader.pos() - MPEG_HEADER_LEN as u64;
+
+    // A frame must at-least contain its header and side information.
+    let min_frame_len = (MPEG_HEADER_LEN + header.side_info_len()) as u64;
+
+    let mut sync = 0u32;
+    let mut frame_len = None;
+
+    while reader.pos() - header_pos <= MAX_FREE_FORMAT_FRAME_LEN + MPEG_HEADER_LEN as u64 {
+        sync = match reader.read_u8() {
+            Ok(byte) => (sync << 8) | u32::from(byte),
+            Err(_) => break,
+        };
+
+        // The next frame must also be a free bit-rate frame similar to the current frame.
+        let len = reader.pos() - header_pos - MPEG_HEADER_LEN as u64;
+
+        if len >= min_frame_len
+            && header::is_frame_header_word_synced(sync)
+            && header::check_header(sync)
+            && sync & 0xf000 == 0
+            && is_frame_header_similar(header, sync)
+        {
+            frame_len = Some(len as usize - header::padding_len(header));
+            break;
+        }
+    }
+
+    reader.seek_buffered(header_pos + MPEG_HEADER_LEN as u64);
+
+    match frame_len {
+        Some(frame_len) => Ok(frame_len),
+        None => decode_error("mpa: could not determine free bit-rate frame length"),
+    }
+}
+
+/// Reads the chapters from an ID3v2 tag at the start of a seekable stream, in playback order. The
+/// position of the stream is restored afterwards.
+fn read_id3v2_chapters(reader: &mut MediaSourceStream) -> Result<Vec<Chapter>> {
+    let mut chapters = Chapters::default();
+
+    let pos = reader.pos();
+
+    if pos == 0 {
+        return Ok(Vec::new());
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+
+    // The tag was already read successfully by the probe, or skipped as junk, so failing to read it
+    // again should not prevent playback.
+    let mut metadata = MetadataBuilder::new();
+
+    if let Err(err) = read_id3v2_with_chapters(reader, &mut metadata, &mut chapters) {
+        debug!("no chapters read from id3v2 tag: {}", err);
+        chapters = Chapters::default();
+    }
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    Ok(chapters.into_ordered())
+}
+
+/// Reads the APE tag, appended ID3v2 tag, and Lyrics3v2 tag, at the end of a seekable stream, if
+/// present, and returns the position of the end of the audio data. The ID3v1 tag, and the extended
+/// (TAG+) tag preceding it, are only read if the stream has no other tags, since they can only
+/// hold a subset of the information of other tags.
+fn read_trailing_tags(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<Option<u64>> {
+    let mut end = match reader.byte_len() {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+
+    let mut id3v1_pos = None;
+
+    if end >= id3v1::TAG_LEN {
+        reader.seek(SeekFrom::Start(end - id3v1::TAG_LEN))?;
+
+        if reader.read_triple_bytes()? == *b"TAG" {
+            end -= id3v1::TAG_LEN;
+            id3v1_pos = Some(end);
+        }
+    }
+
+    let mut extended = None;
+
+    if id3v1_pos.is_some() && end >= id3v1::EXTENDED_TAG_LEN {
+        reader.seek(SeekFrom::Start(end - id3v1::EXTENDED_TAG_LEN))?;
+
+        if let Ok(tag) = id3v1::read_extended_tag(reader) {
+            end -= id3v1::EXTENDED_TAG_LEN;
+            extended = Some(tag);
+        }
+    }
+
+    let mut has_tags = false;
+    let mut lyrics3_tags = Vec::new();
+
+    // The APE, ID3v2, and Lyrics3v2 tags may be appended in any order.
+    loop {
+        let mut builder = MetadataBuilder::new();
+
+        if let Some(footer) = apev2::read_ape_tag_footer(reader, end)? {
+            apev2::read_ape_tag_items(reader, &footer, &mut builder)?;
+            end -= footer.tag_len();
+        }
+        else if let Some(tag_len) = id3v2::find_appended_id3v2(reader, end)? {
+            id3v2::read_id3v2(reader, &mut builder)?;
+            end -= tag_len;
+        }
+        else if let Some(tag_len) = lyrics3::find_lyrics3v2(reader, end)? {
+            lyrics3::read_lyrics3v2(reader, tag_len, &mut builder)?;
+            end -= tag_len;
+
+            // The Lyrics3v2 tag extends the ID3v1 tag, so it is merged with the ID3v1 tag below.
+            lyrics3_tags.extend_from_slice(builder.metadata().tags());
+            continue;
+        }
+        else {
+            break;
+        }
+
+        metadata.push(builder.metadata());
+        has_tags = true;
+    }
+
+    let mut builder = MetadataBuilder::new();
+
+    if let Some(pos) = id3v1_pos {
+        // An ID3v2 tag at the start of the stream was read by the probe.
+        reader.seek(SeekFrom::Start(0))?;
+
+        if !has_tags && reader.read_triple_bytes()? != *b"ID3" {
+            let mut id3v1_builder = MetadataBuilder::new();
+
+            reader.seek(SeekFrom::Start(pos))?;
+            id3v1::read_id3v1_with_extended(reader, extended.as_ref(), &mut id3v1_builder)?;
+
+            // The fields of the Lyrics3v2 tag replace the equivalent, but truncated, fields of the
+            // ID3v1 tag.
+            for tag in id3v1_builder.metadata().tags() {
+                let is_replaced = tag.std_key.is_some()
+                    && lyrics3_tags.iter().any(|lyrics3_tag| lyrics3_tag.std_key == tag.std_key);
+
+                if !is_replaced {
+                    builder.add_tag(tag.clone());
+                }
+            }
+        }
+    }
+
+    for tag in lyrics3_tags {
+        builder.add_tag(tag);
+    }
+
+    let rev = builder.metadata();
+
+    if !rev.tags().is_empty() {
+        metadata.push(rev);
+    }
+
+    Ok(Some(end))
+}
+
+/// Converts ID3v2 chapters, in playback order, into cues. Chapter times are in milliseconds,
+/// whereas cue timestamps are in audio frames at the given sample rate, offset by `delay` frames.
+fn chapters_to_cues(chapters: Vec<Chapter>, sample_rate: u32, delay: u32) -> Vec<Cue> {
+    chapters
+        .into_iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            let start_ts = u64::from(chapter.start_ms) * u64::from(sample_rate) / 1000;
+
+            Cue {
+                index: index as u32,
+                start_ts: start_ts + u64::from(delay),
+                tags: chapter.tags,
+                points: Vec::new(),
+            }
+        })
+        .collect()
 }
 
 /// Reads a MPEG frame and returns the header and buffer.
-fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
+fn read_mpeg_frame(
+    reader: &mut MediaSourceStream,
+    free_format_frame_len: &mut Option<usize>,
+) -> Result<(FrameHeader, Vec<u8>)> {
     let (header, header_word) = loop {
         // Sync to the next frame header.
         let sync = header::sync_frame(reader)?;
 
         // Parse the frame header fully.
-        if let Ok(header) = header::parse_frame_header(sync) {
+        if let Ok(header) = parse_frame_header(reader, sync, free_format_frame_len) {
             break (header, sync);
         }
 
@@ -485,10 +952,13 @@ fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u
 }
 
 /// Reads a MPEG frame and checks if the next frame begins after the packet.
-fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
+fn read_mpeg_frame_strict(
+    reader: &mut MediaSourceStream,
+    free_format_frame_len: &mut Option<usize>,
+) -> Result<(FrameHeader, Vec<u8>)> {
     loop {
         // Read the next MPEG frame.
-        let (header, packet) = read_mpeg_frame(reader)?;
+        let (header, packet) = read_mpeg_frame(reader, free_format_frame_len)?;
 
         // Get the position before trying to read the next header.
         let pos = reader.pos();
@@ -506,6 +976,11 @@ fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader
                 // Seek back to the second byte of the rejected packet to prevent syncing to the
                 // same spot again.
                 reader.seek_buffered_rev(packet.len() + MPEG_HEADER_LEN - 1);
+
+                // A free bit-rate frame length measured from junk is also likely to be wrong.
+                if header.is_free_format {
+                    *free_format_frame_len = None;
+                }
                 continue;
             }
         }
@@ -532,6 +1007,130 @@ fn is_frame_header_similar(header: &FrameHeader, sync: u32) -> bool {
     false
 }
 
+/// The maximum number of previous frames that may be required to decode a frame.
+const MAX_REF_FRAMES: usize = 4;
+
+/// The maximum number of packets in the index of packets read so far.
+const MAX_INDEX_LEN: usize = 4096;
+
+/// The parameters of a CBR stream. Since every frame has the same bit-rate, the position of any
+/// frame can be calculated.
+#[derive(Copy, Clone)]
+struct CbrParams {
+    bitrate: u32,
+    sample_rate: u32,
+    frame_dur: u64,
+}
+
+impl CbrParams {
+    fn new(header: &FrameHeader) -> Self {
+        CbrParams {
+            bitrate: header.bitrate,
+            sample_rate: header.sample_rate,
+            frame_dur: header.duration(),
+        }
+    }
+
+    /// The average length of a frame in bits multiplied by the sample rate. Padding is what makes
+    /// the average frame length fractional.
+    fn scaled_frame_len(&self) -> u128 {
+        u128::from(self.bitrate) * u128::from(self.frame_dur)
+    }
+
+    /// Get the approximate offset in bytes of a frame relative to the first frame.
+    fn frame_offset(&self, frame: u64) -> u64 {
+        let scale = 8 * u128::from(self.sample_rate);
+        (u128::from(frame) * self.scaled_frame_len() / scale) as u64
+    }
+
+    /// Get the frame nearest to an offset in bytes relative to the first frame.
+    fn frame_at(&self, offset: u64) -> u64 {
+        let scaled_offset = 8 * u128::from(offset) * u128::from(self.sample_rate);
+        let scaled_frame_len = self.scaled_frame_len();
+
+        ((scaled_offset + scaled_frame_len / 2) / scaled_frame_len) as u64
+    }
+}
+
+/// A table of contents, read from a Xing/Info or VBRI tag, used to find the approximate position
+/// of a timestamp in a VBR stream without parsing every frame before it.
+enum SeekToc {
+    /// Entry `i` of a Xing/Info TOC is the position, as a fraction of `num_bytes` scaled to 256, at
+    /// which `i` percent of the total duration has elapsed.
+    Xing { toc: [u8; 100], base_pos: u64, num_bytes: u64 },
+    /// Each entry of a VBRI TOC is the position of a group of frames spanning `ts_per_entry`.
+    Vbri { positions: Vec<u64>, ts_per_entry: u64 },
+}
+
+impl SeekToc {
+    /// Get the approximate position of the required timestamp given the total duration of the
+    /// stream.
+    fn position_of(&self, required_ts: u64, duration: u64) -> u64 {
+        match self {
+            SeekToc::Xing { toc, base_pos, num_bytes } => {
+                if duration == 0 {
+                    return *base_pos;
+                }
+
+                // Interpolate between the entries surrounding the required percentage.
+                let percent = (100.0 * required_ts as f64 / duration as f64).clamp(0.0, 100.0);
+
+                let i = (percent as usize).min(99);
+                let a = f64::from(toc[i]);
+                let b = if i < 99 { f64::from(toc[i + 1]) } else { 256.0 };
+
+                let scaled = a + (b - a) * (percent - i as f64);
+
+                base_pos + (scaled * *num_bytes as f64 / 256.0) as u64
+            }
+            SeekToc::Vbri { positions, ts_per_entry } => {
+                let entry = match ts_per_entry {
+                    0 => 0,
+                    _ => ((required_ts / ts_per_entry) as usize).min(positions.len() - 1),
+                };
+
+                positions[entry]
+            }
+        }
+    }
+
+    /// Get the approximate timestamp at a position given the total duration of the stream.
+    fn timestamp_of(&self, pos: u64, duration: u64) -> u64 {
+        match self {
+            SeekToc::Xing { toc, base_pos, num_bytes } => {
+                if *num_bytes == 0 {
+                    return 0;
+                }
+
+                let scaled = 256.0 * pos.saturating_sub(*base_pos) as f64 / *num_bytes as f64;
+
+                // Find the last entry at or before the position, and interpolate between it and the
+                // following entry.
+                let i = toc.iter().rposition(|&entry| f64::from(entry) <= scaled).unwrap_or(0);
+                let a = f64::from(toc[i]);
+                let b = if i < 99 { f64::from(toc[i + 1]) } else { 256.0 };
+
+                let frac = if b > a { ((scaled - a) / (b - a)).clamp(0.0, 1.0) } else { 0.0 };
+
+                ((i as f64 + frac) * duration as f64 / 100.0) as u64
+            }
+            SeekToc::Vbri { positions, ts_per_entry } => {
+                let i = positions.iter().rposition(|&entry| entry <= pos).unwrap_or(0);
+
+                // Interpolate within the group of frames.
+                let offset = match positions.get(i + 1) {
+                    Some(&next) if next > positions[i] => {
+                        (pos - positions[i].min(pos)) * ts_per_entry / (next - positions[i])
+                    }
+                    _ => 0,
+                };
+
+                i as u64 * ts_per_entry + offset
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 struct FramePos {
     ts: u64,
@@ -557,8 +1156,13 @@ fn read_main_data_begin<B: ReadBytes>(reader: &mut B, header: &FrameHeader) -> R
     Ok(main_data_begin)
 }
 
-/// Estimates the total number of MPEG frames in the media source stream.
-fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
+/// Estimates the total number of MPEG frames in the media source stream. Also returns if all frames
+/// read to make the estimate had the same bit-rate, and therefore, if the stream is likely CBR.
+fn estimate_num_mpeg_frames(
+    reader: &mut MediaSourceStream,
+    audio_end_pos: Option<u64>,
+    free_format_frame_len: &mut Option<usize>,
+) -> Option<(u64, bool)> {
     const MAX_FRAMES: u32 = 16;
     const MAX_LEN: usize = 16 * 1024;
 
@@ -576,9 +1180,11 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
 
     let mut total_frame_len = 0;
     let mut total_frames = 0;
+    let mut bitrate = None;
+    let mut is_cbr = true;
 
-    let total_len = match reader.byte_len() {
-        Some(len) => len - start_pos,
+    let total_len = match audio_end_pos {
+        Some(end) if end > start_pos => end - start_pos,
         _ => return None,
     };
 
@@ -587,12 +1193,15 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
         let header_val = break_on_err!(reader.read_be_u32());
 
         // Parse the frame header.
-        let header = break_on_err!(header::parse_frame_header(header_val));
+        let header = break_on_err!(parse_frame_header(reader, header_val, free_format_frame_len));
 
         // Tabulate the size.
         total_frame_len += MPEG_HEADER_LEN + header.frame_size;
         total_frames += 1;
 
+        // Check the bit-rate is unchanged.
+        is_cbr &= *bitrate.get_or_insert(header.bitrate) == header.bitrate;
+
         // Ignore the frame body.
         break_on_err!(reader.ignore_bytes(header.frame_size as u64));
 
@@ -600,7 +1209,7 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
         // that, the total number of MPEG frames.
         if total_frames > MAX_FRAMES || total_frame_len > MAX_LEN {
             let avg_mpeg_frame_len = total_frame_len as f64 / total_frames as f64;
-            break Some((total_len as f64 / avg_mpeg_frame_len) as u64);
+            break Some(((total_len as f64 / avg_mpeg_frame_len) as u64, is_cbr));
         }
     };
 
@@ -610,6 +1219,42 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
     num_mpeg_frames
 }
 
+/// Checks if frames sampled from throughout the media source stream have the same bit-rate as the
+/// provided frame header. The stream is returned to its original position.
+fn is_bitrate_constant(
+    reader: &mut MediaSourceStream,
+    audio_end_pos: Option<u64>,
+    header: &FrameHeader,
+    free_format_frame_len: &mut Option<usize>,
+) -> Result<bool> {
+    const NUM_SAMPLES: u64 = 3;
+
+    let start_pos = reader.pos();
+
+    let total_len = match audio_end_pos {
+        Some(end) if end > start_pos => end - start_pos,
+        _ => return Ok(false),
+    };
+
+    let mut is_cbr = true;
+
+    for i in 1..=NUM_SAMPLES {
+        reader.seek(SeekFrom::Start(start_pos + i * total_len / (NUM_SAMPLES + 1)))?;
+
+        match read_mpeg_frame_strict(reader, free_format_frame_len) {
+            Ok((sample, _)) if sample.bitrate == header.bitrate => (),
+            _ => {
+                is_cbr = false;
+                break;
+            }
+        }
+    }
+
+    reader.seek(SeekFrom::Start(start_pos))?;
+
+    Ok(is_cbr)
+}
+
 const XING_TAG_ID: [u8; 4] = *b"Xing";
 const INFO_TAG_ID: [u8; 4] = *b"Info";
 
@@ -851,6 +1496,15 @@ const VBRI_TAG_ID: [u8; 4] = *b"VBRI";
 struct VbriTag {
     num_bytes: u32,
     num_mpeg_frames: u32,
+    toc: Option<VbriToc>,
+}
+
+/// The table of contents of a VBRI tag.
+struct VbriToc {
+    /// The length in bytes of each group of frames.
+    entries: Vec<u64>,
+    /// The number of frames in each group.
+    frames_per_entry: u16,
 }
 
 /// Try to read a VBRI tag from the provided MPEG frame.
@@ -892,7 +1546,36 @@ fn try_read_vbri_tag_inner(buf: &[u8], header: &FrameHeader) -> Result<Option<Vb
     let num_bytes = reader.read_be_u32()?;
     let num_mpeg_frames = reader.read_be_u32()?;
 
-    Ok(Some(VbriTag { num_bytes, num_mpeg_frames }))
+    // The TOC is optional, and may be truncated. If it cannot be read, it is ignored.
+    let toc = read_vbri_toc(&mut reader).ok().flatten();
+
+    Ok(Some(VbriTag { num_bytes, num_mpeg_frames, toc }))
+}
+
+fn read_vbri_toc(reader: &mut BufReader<'_>) -> Result<Option<VbriToc>> {
+    let num_entries = reader.read_be_u16()?;
+    let scale = reader.read_be_u16()?;
+    let entry_size = reader.read_be_u16()?;
+    let frames_per_entry = reader.read_be_u16()?;
+
+    if num_entries == 0 || frames_per_entry == 0 || !(1..=4).contains(&entry_size) {
+        return Ok(None);
+    }
+
+    let mut entries = Vec::with_capacity(usize::from(num_entries));
+
+    for _ in 0..num_entries {
+        let len = match entry_size {
+            1 => u32::from(reader.read_u8()?),
+            2 => u32::from(reader.read_be_u16()?),
+            3 => reader.read_be_u24()?,
+            _ => reader.read_be_u32()?,
+        };
+
+        entries.push(u64::from(len) * u64::from(scale));
+    }
+
+    Ok(Some(VbriToc { entries, frames_per_entry }))
 }
 
 /// Perform a fast check to see if the packet contains a VBRI tag. If this returns true, the
@@ -921,3 +1604,96 @@ fn is_maybe_vbri_tag(buf: &[u8], header: &FrameHeader) -> bool {
     // The bytes preceeding the VBRI tag (mostly the side information) should be all 0.
     !buf[MPEG_HEADER_LEN..VBRI_TAG_OFFSET].iter().any(|&b| b != 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MpaReader;
+    use symphonia_core::formats::{FormatReader, SeekMode, SeekTo};
+    use symphonia_core::io::{MediaSourceStream, MediaSourceStreamOptions};
+
+    /// Builds a MPEG1 layer 3, 44.1 kHz, mono frame that alternates between 128 kbit/s and
+    /// 160 kbit/s. The main data of every frame begins 500 bytes before its header.
+    fn mpeg_frame(i: usize) -> Vec<u8> {
+        let (bitrate_index, frame_len) = if i % 2 == 0 { (0x9, 417) } else { (0xa, 522) };
+
+        let mut frame = vec![0xff, 0xfb, bitrate_index << 4, 0xc0, 0xfa, 0x00];
+        frame.resize(frame_len, i as u8);
+        frame
+    }
+
+    #[test]
+    fn verify_accurate_seek_from_index() {
+        let file: Vec<u8> = (0..100).flat_map(mpeg_frame).collect();
+
+        let new_reader = || {
+            let data = std::io::Cursor::new(file.clone());
+            let source = MediaSourceStream::new(Box::new(data), Default::default());
+            MpaReader::try_new(source, &Default::default()).unwrap()
+        };
+
+        // Read the first 60 frames to populate the seek index of the indexed reader.
+        let mut indexed = new_reader();
+
+        for _ in 0..60 {
+            indexed.next_packet().unwrap();
+        }
+
+        for &ts in &[61 * 1152 + 5, 30 * 1152 + 100, 0, 2 * 1152, 60 * 1152, 75 * 1152 + 1151] {
+            let to = || SeekTo::TimeStamp { ts, track_id: 0 };
+
+            // A fresh reader must scan the stream linearly.
+            let mut linear = new_reader();
+
+            let indexed_seeked_to = indexed.seek(SeekMode::Accurate, to()).unwrap();
+            let linear_seeked_to = linear.seek(SeekMode::Accurate, to()).unwrap();
+
+            assert_eq!(indexed_seeked_to.actual_ts, linear_seeked_to.actual_ts);
+
+            let indexed_packet = indexed.next_packet().unwrap();
+            let linear_packet = linear.next_packet().unwrap();
+
+            assert_eq!(indexed_packet.ts(), linear_packet.ts());
+            assert_eq!(indexed_packet.buf(), linear_packet.buf());
+        }
+    }
+
+    #[test]
+    fn verify_read_with_min_buffer_len() {
+        // Junk between some frames requires the reader to resynchronize.
+        let mut file = Vec::new();
+
+        for i in 0..100 {
+            if i % 10 == 5 {
+                file.extend_from_slice(&[0x55; 3000]);
+            }
+            file.extend(mpeg_frame(i));
+        }
+
+        let read_all = |opts: MediaSourceStreamOptions| {
+            let data = std::io::Cursor::new(file.clone());
+            let source = MediaSourceStream::new(Box::new(data), opts);
+            let mut reader = MpaReader::try_new(source, &Default::default()).unwrap();
+
+            let mut packets = Vec::new();
+
+            while let Ok(packet) = reader.next_packet() {
+                packets.push((packet.ts(), packet.buf().to_vec()));
+            }
+
+            packets
+        };
+
+        // A large buffer, and the smallest buffer with the smallest read-ahead.
+        let large = MediaSourceStreamOptions { buffer_len: 1024 * 1024, ..Default::default() };
+        let small = MediaSourceStreamOptions {
+            buffer_len: 64 * 1024,
+            low_latency: true,
+            ..Default::default()
+        };
+
+        let expected = read_all(large);
+
+        assert_eq!(expected.len(), 100);
+        assert!(read_all(small) == expected);
+    }
+}
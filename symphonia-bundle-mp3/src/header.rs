@@ -5,7 +5,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use std::convert::TryFrom;
+
+use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
 
 use crate::common::*;
@@ -123,9 +125,9 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     };
 
     let bitrate = match ((header & 0xf000) >> 12, version, layer) {
-        // "Free" bit-rate. Note, this is NOT variable bit-rate and is not a mandatory feature of
-        // MP3 decoders.
-        (0b0000, _, _) => return unsupported_error("mpa: free bit-rate is not supported"),
+        // "Free" bit-rate. Note, this is NOT variable bit-rate. The bit-rate is fixed, but is not
+        // stated in the header. It must be derived from the distance between frames.
+        (0b0000, _, _) => 0,
         // Invalid bit-rate.
         (0b1111, _, _) => return decode_error("mpa: invalid bit-rate"),
         // MPEG 1 bit-rates.
@@ -197,26 +199,21 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
 
     let has_crc = header & 0x1_0000 == 0;
 
-    // Constants provided for size calculation in section ISO-11172 section 2.4.3.1.
-    let factor = match layer {
-        MpegLayer::Layer1 => 12,
-        MpegLayer::Layer2 => 144,
-        MpegLayer::Layer3 if version == MpegVersion::Mpeg1 => 144,
-        MpegLayer::Layer3 => 72,
-    };
+    let is_free_format = bitrate == 0;
 
-    // The header specifies the total frame size in "slots". For layers 2 & 3 a slot is 1 byte,
-    // however for layer 1 a slot is 4 bytes.
-    let slot_size = match layer {
-        MpegLayer::Layer1 => 4,
-        _ => 1,
-    };
+    // Calculate the frame size in bytes, excluding the header. The size of a free bit-rate frame is
+    // unknown until it is set with `set_free_format_frame_size`.
+    let frame_size = if !is_free_format {
+        let (factor, slot_size) = slot_params(version, layer);
 
-    // Calculate the total frame size in number of slots.
-    let frame_size_slots = (factor * bitrate / sample_rate) as usize + usize::from(has_padding);
+        // Calculate the total frame size in number of slots.
+        let frame_size_slots = (factor * bitrate / sample_rate) as usize + usize::from(has_padding);
 
-    // Calculate the frame size in bytes, excluding the header.
-    let frame_size = (frame_size_slots * slot_size) - 4;
+        (frame_size_slots * slot_size) - 4
+    }
+    else {
+        0
+    };
 
     Ok(FrameHeader {
         version,
@@ -230,10 +227,70 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
         is_original,
         has_padding,
         has_crc,
+        is_free_format,
         frame_size,
     })
 }
 
+/// Gets the frame size factor, and the size of a slot in bytes, for a MPEG version and layer.
+fn slot_params(version: MpegVersion, layer: MpegLayer) -> (u32, usize) {
+    // Constants provided for size calculation in section ISO-11172 section 2.4.3.1.
+    let factor = match layer {
+        MpegLayer::Layer1 => 12,
+        MpegLayer::Layer2 => 144,
+        MpegLayer::Layer3 if version == MpegVersion::Mpeg1 => 144,
+        MpegLayer::Layer3 => 72,
+    };
+
+    // The header specifies the total frame size in "slots". For layers 2 & 3 a slot is 1 byte,
+    // however for layer 1 a slot is 4 bytes.
+    let slot_size = match layer {
+        MpegLayer::Layer1 => 4,
+        _ => 1,
+    };
+
+    (factor, slot_size)
+}
+
+/// Returns the length in bytes of the padding slot of a frame, or 0 if the frame is not padded.
+pub fn padding_len(header: &FrameHeader) -> usize {
+    if header.has_padding {
+        slot_params(header.version, header.layer).1
+    }
+    else {
+        0
+    }
+}
+
+/// Sets the frame size, in bytes excluding the header, of a free bit-rate frame, and derives the
+/// bit-rate from it.
+pub fn set_free_format_frame_size(header: &mut FrameHeader, frame_size: usize) -> Result<()> {
+    debug_assert!(header.is_free_format);
+
+    let (factor, slot_size) = slot_params(header.version, header.layer);
+
+    // The frame must be large enough to contain the header and padding.
+    let frame_len = MPEG_HEADER_LEN + frame_size;
+
+    if frame_len < MPEG_HEADER_LEN + padding_len(header) || frame_len % slot_size != 0 {
+        return decode_error("mpa: invalid free bit-rate frame size");
+    }
+
+    // A corrupt or very large frame size may overflow the bit-rate calculation.
+    let bitrate = u32::try_from(frame_len / slot_size)
+        .ok()
+        .map(|frame_size_slots| frame_size_slots - u32::from(header.has_padding))
+        .and_then(|frame_size_slots| frame_size_slots.checked_mul(header.sample_rate));
+
+    header.bitrate = match bitrate {
+        Some(bitrate) => bitrate / factor,
+        None => return decode_error("mpa: free bit-rate frame size is too large"),
+    };
+    header.frame_size = frame_size;
+
+    Ok(())
+}
+
 /// Synchronize the stream to the start of the next MPEG audio frame header, then read and return
 /// the frame header or an error.
 #[inline]
@@ -249,3 +306,41 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B) -> Result<FrameHeader> {
 pub fn read_frame_header_word_no_sync<B: ReadBytes>(reader: &mut B) -> Result<u32> {
     Ok(reader.read_be_u32()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_frame_header, set_free_format_frame_size};
+    use crate::common::{MpegLayer, MpegVersion};
+
+    #[test]
+    fn verify_parse_mpeg2p5_frame_header() {
+        // MPEG2.5, layer 3, no CRC, 8kbit/s, mono.
+        let cases =
+            [(0xffe3_10c0, 11_025, 48), (0xffe3_14c0, 12_000, 44), (0xffe3_18c0, 8_000, 68)];
+
+        for &(word, sample_rate, frame_size) in cases.iter() {
+            let header = parse_frame_header(word).unwrap();
+
+            assert_eq!(header.version, MpegVersion::Mpeg2p5);
+            assert_eq!(header.layer, MpegLayer::Layer3);
+            assert_eq!(header.sample_rate, sample_rate);
+            assert_eq!(header.bitrate, 8_000);
+            assert_eq!(header.frame_size, frame_size);
+            assert_eq!(header.duration(), 576);
+        }
+    }
+
+    #[test]
+    fn verify_free_format_frame_size_overflow() {
+        // MPEG1, layer 3, no CRC, free bit-rate, 44.1kHz, mono.
+        let mut header = parse_frame_header(0xfffb_00c0).unwrap();
+
+        set_free_format_frame_size(&mut header, 413).unwrap();
+
+        assert_eq!(header.frame_size, 413);
+        assert_eq!(header.bitrate, 127_706);
+
+        // The bit-rate of this frame size does not fit in 32 bits.
+        assert!(set_free_format_frame_size(&mut header, 100_000_000).is_err());
+    }
+}
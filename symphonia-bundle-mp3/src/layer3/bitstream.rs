@@ -129,9 +129,8 @@ fn read_granule_channel_side_info<B: ReadBitsLtr>(
             // for region0. These bands are also always  [4, 4, 4, 4, 4, 4, 4, 4, 4, ...] regardless
             // of sample and thus sum to 36 samples.
             //
-            // In all cases, the region0_count is 36.
-            //
-            // TODO: This is not accurate for MPEG2.5 at 8kHz.
+            // In all cases, the region0_count is 36. MPEG2.5 is handled above since its bands
+            // differ at 8kHz.
             channel.region1_start = 36;
         }
         // If MPEG version 2 AND the block type is not Short...
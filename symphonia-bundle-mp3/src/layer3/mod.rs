@@ -8,8 +8,9 @@
 use std::fmt;
 
 use symphonia_core::audio::{AudioBuffer, Signal};
+use symphonia_core::checksum::Crc16Ansi;
 use symphonia_core::errors::{decode_error, Error, Result};
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::io::{BitReaderLtr, BufReader, Monitor, ReadBitsLtr, ReadBytes};
 
 mod bitstream;
 mod codebooks;
@@ -18,6 +19,7 @@ mod hybrid_synthesis;
 mod requantize;
 mod stereo;
 
+use crate::header::MPEG_HEADER_LEN;
 use crate::{common::*, synthesis};
 
 use common::BlockType;
@@ -251,6 +253,26 @@ impl fmt::Debug for GranuleChannel {
     }
 }
 
+/// Checks the CRC of a frame, if it is protected by one. The CRC covers the last two bytes of the
+/// frame header, and the side information.
+pub fn check_crc(buf: &[u8], header: &FrameHeader) -> bool {
+    if !header.has_crc {
+        return true;
+    }
+
+    let side_info_end = MPEG_HEADER_LEN + 2 + header.side_info_len();
+
+    if buf.len() < side_info_end {
+        return false;
+    }
+
+    let mut crc16 = Crc16Ansi::new(0xffff);
+    crc16.process_buf_bytes(&buf[2..MPEG_HEADER_LEN]);
+    crc16.process_buf_bytes(&buf[MPEG_HEADER_LEN + 2..side_info_end]);
+
+    crc16.crc() == u16::from_be_bytes([buf[MPEG_HEADER_LEN], buf[MPEG_HEADER_LEN + 1]])
+}
+
 pub struct Layer3 {
     pub samples: [[[f32; 576]; 2]; 2],
     pub overlap: [[[f32; 18]; 32]; 2],
@@ -387,8 +409,7 @@ impl Layer for Layer3 {
 
         let mut bs = BitReaderLtr::new(buf);
 
-        // Read side_info into the frame data.
-        // TODO: Use a MonitorStream to compute the CRC.
+        // Read side_info into the frame data. The CRC, if present, is checked by `check_crc`.
         let side_info_len = match bitstream::read_side_info(&mut bs, header, &mut frame_data) {
             Ok(len) => len,
             Err(e) => {
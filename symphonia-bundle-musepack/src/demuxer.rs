@@ -0,0 +1,538 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_MUSEPACK};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::util::trim_packet;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::apev2;
+
+use log::{debug, warn};
+
+use super::header::*;
+
+/// The maximum length of a SV8 packet.
+const MAX_PACKET_LEN: u64 = 16 * 1024 * 1024;
+
+/// The length of an ID3v1 tag.
+const ID3V1_TAG_LEN: u64 = 128;
+
+/// The framing of the audio data.
+enum Framing {
+    /// SV7 frames form a single stream of 32-bit little-endian words. Each frame starts with a
+    /// 20-bit length, and frames are not aligned to a word or byte boundary.
+    Sv7 {
+        /// The position of the first word of the audio data.
+        data_pos: u64,
+        /// The total number of frames.
+        n_frames: u64,
+    },
+    /// SV8 audio data is stored in packets, each containing a fixed number of frames.
+    Sv8,
+}
+
+/// Musepack (MPC) format reader.
+///
+/// `MpcReader` implements a demuxer for SV7 and SV8 Musepack streams.
+///
+/// For SV8 streams, each packet contains the payload of one audio packet. For SV7 streams, each
+/// packet contains one frame, excluding the frame length. Since SV7 frames are stored in a stream
+/// of 32-bit little-endian words, the packet data is converted to byte order. The first byte of
+/// the packet is the number of bits to skip in the remainder of the packet to reach the start of
+/// the frame.
+///
+/// The stream header, including the stream marker, is provided as the codec extra data.
+pub struct MpcReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    framing: Framing,
+    /// The position of each packet read so far. For SV7, this is the bit position of the frame
+    /// relative to the start of the audio data. For SV8, this is the byte position of the packet.
+    index: Vec<u64>,
+    /// The position of the next packet, in the same form as the index.
+    next_pos: u64,
+    /// The index of the next packet.
+    next_packet: usize,
+    /// The number of samples per channel in a packet.
+    packet_len: u64,
+    /// The total number of samples per channel output by the decoder.
+    total_len: u64,
+    delay: u32,
+    n_samples: u64,
+    enable_gapless: bool,
+}
+
+impl QueryDescriptor for MpcReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "mpc",
+            "Musepack",
+            &["mpc", "mp+", "mpp"],
+            &["audio/musepack", "audio/x-musepack"],
+            &[b"MPCK", b"MP+\x07", b"MP+\x17"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Reads the APE tag at the end of the stream, if present. The ID3v1 tag, if present, is skipped.
+fn read_tags(reader: &mut MediaSourceStream, metadata: &mut MetadataLog) -> Result<()> {
+    let mut end = match reader.byte_len() {
+        Some(len) if reader.is_seekable() => len,
+        _ => return Ok(()),
+    };
+
+    if end >= ID3V1_TAG_LEN {
+        reader.seek(SeekFrom::Start(end - ID3V1_TAG_LEN))?;
+
+        if reader.read_triple_bytes()? == *b"TAG" {
+            end -= ID3V1_TAG_LEN;
+        }
+    }
+
+    let footer = match apev2::read_ape_tag_footer(reader, end)? {
+        Some(footer) => footer,
+        None => return Ok(()),
+    };
+
+    let mut builder = MetadataBuilder::new();
+
+    match apev2::read_ape_tag_items(reader, &footer, &mut builder) {
+        Ok(_) => metadata.push(builder.metadata()),
+        Err(err) => warn!("mpc: ignoring invalid ape tag: {}", err),
+    }
+
+    Ok(())
+}
+
+/// Reads the header of a SV8 packet, and returns the packet key and the length of the payload.
+fn read_sv8_packet_header<B: ReadBytes>(reader: &mut B) -> Result<([u8; 2], u64)> {
+    let start = reader.pos();
+
+    let key = reader.read_double_bytes()?;
+
+    // Packet keys are two upper-case ASCII letters.
+    if !key.iter().all(u8::is_ascii_uppercase) {
+        return decode_error("mpc: invalid packet key");
+    }
+
+    // The packet size includes the packet header.
+    let size = read_size(reader)?;
+
+    match size.checked_sub(reader.pos() - start) {
+        Some(len) if len <= MAX_PACKET_LEN => Ok((key, len)),
+        Some(_) => decode_error("mpc: packet is too large"),
+        None => decode_error("mpc: invalid packet size"),
+    }
+}
+
+impl MpcReader {
+    /// Positions the reader at the given position in the stream.
+    fn seek_to(&mut self, pos: u64) -> Result<()> {
+        if self.reader.pos() == pos {
+            return Ok(());
+        }
+
+        // SV7 frames may share a word, so the next frame may start slightly before the current
+        // position. Try to seek within the buffer first.
+        let buffered_pos = self.reader.seek_buffered(pos);
+
+        if buffered_pos != pos {
+            if self.reader.is_seekable() {
+                self.reader.seek(SeekFrom::Start(pos))?;
+            }
+            else if pos > buffered_pos {
+                self.reader.ignore_bytes(pos - buffered_pos)?;
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads as many bytes as possible into the buffer, and returns the number of bytes read.
+    fn read_partial(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+
+        Ok(read)
+    }
+
+    /// Reads the length, in bits, of the SV7 frame at the given bit position.
+    fn read_sv7_frame_len(&mut self, data_pos: u64, bit_pos: u64) -> Result<u64> {
+        self.seek_to(data_pos + 4 * (bit_pos / 32))?;
+
+        // The 20-bit frame length may span two words.
+        let mut buf = [0; 8];
+
+        if self.read_partial(&mut buf)? < 4 {
+            return end_of_stream_error();
+        }
+
+        let words = (u64::from(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])) << 32)
+            | u64::from(u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]));
+
+        Ok((words << (bit_pos % 32)) >> 44)
+    }
+
+    /// Advances to the next packet without reading it, and returns the length of its payload. The
+    /// position of the packet is recorded in the index. Returns `None` at the end of the stream.
+    fn advance(&mut self) -> Result<Option<u64>> {
+        let (pos, len) = match self.framing {
+            Framing::Sv7 { data_pos, n_frames } => {
+                if self.next_packet as u64 >= n_frames {
+                    return Ok(None);
+                }
+
+                let pos = self.next_pos;
+                let len = self.read_sv7_frame_len(data_pos, pos)?;
+
+                self.next_pos = pos + 20 + len;
+
+                (pos, len)
+            }
+            Framing::Sv8 => loop {
+                let pos = self.next_pos;
+
+                self.seek_to(pos)?;
+
+                let (key, len) = read_sv8_packet_header(&mut self.reader)?;
+
+                self.next_pos = self.reader.pos() + len;
+
+                match &key {
+                    b"AP" => break (pos, len),
+                    b"SE" => {
+                        // Remain at the stream end packet.
+                        self.next_pos = pos;
+                        return Ok(None);
+                    }
+                    _ => (),
+                }
+            },
+        };
+
+        if self.next_packet == self.index.len() {
+            self.index.push(pos);
+        }
+
+        self.next_packet += 1;
+
+        Ok(Some(len))
+    }
+
+    /// Reads the payload of the packet at the given position.
+    fn read_payload(&mut self, pos: u64, len: u64) -> Result<Box<[u8]>> {
+        match self.framing {
+            Framing::Sv7 { data_pos, .. } => {
+                // Read all words containing the frame, excluding the frame length.
+                let start = pos + 20;
+                let end = start + len;
+
+                let first_word = start / 32;
+                let n_words = (end + 31) / 32 - first_word;
+
+                self.seek_to(data_pos + 4 * first_word)?;
+
+                let mut buf = vec![0; 1 + 4 * n_words as usize];
+
+                // The final frame may be truncated.
+                let read = self.read_partial(&mut buf[1..])?;
+
+                if read == 0 {
+                    return end_of_stream_error();
+                }
+
+                // Convert the 32-bit little-endian words into a byte stream.
+                for word in buf[1..].chunks_exact_mut(4) {
+                    word.reverse();
+                }
+
+                buf[0] = (start % 32) as u8;
+
+                Ok(buf.into_boxed_slice())
+            }
+            Framing::Sv8 => Ok(self.reader.read_boxed_slice_exact(len as usize)?),
+        }
+    }
+}
+
+impl FormatReader for MpcReader {
+    fn try_new(mut source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
+        let marker = source.read_quad_bytes()?;
+
+        let mut metadata = MetadataLog::default();
+
+        let (info, framing, first_pos, extra_data) = if marker == SV8_STREAM_MARKER {
+            let mut info = None;
+            let mut replaygain = None;
+            let mut extra_data = marker.to_vec();
+
+            // Read the packets preceding the first audio packet.
+            let first_pos = loop {
+                let pos = source.pos();
+
+                let (key, len) = read_sv8_packet_header(&mut source)?;
+
+                match &key {
+                    b"SH" => {
+                        let buf = source.read_boxed_slice_exact(len as usize)?;
+                        info = Some(StreamInfo::read_sv8(&buf)?);
+                        extra_data.extend_from_slice(&buf);
+                    }
+                    b"RG" => {
+                        let buf = source.read_boxed_slice_exact(len as usize)?;
+
+                        match ReplayGain::read_sv8(&buf) {
+                            Ok(rg) => replaygain = Some(rg),
+                            Err(err) => warn!("mpc: ignoring replaygain packet: {}", err),
+                        }
+                    }
+                    b"AP" => break pos,
+                    b"SE" => return decode_error("mpc: stream is empty"),
+                    _ => source.ignore_bytes(len)?,
+                }
+            };
+
+            let mut info = match info {
+                Some(info) => info,
+                None => return decode_error("mpc: missing stream header"),
+            };
+
+            if let Some(replaygain) = replaygain {
+                info.replaygain = replaygain;
+            }
+
+            (info, Framing::Sv8, first_pos, extra_data)
+        }
+        else if marker[..3] == SV7_STREAM_MARKER {
+            if marker[3] & 0xf != 7 {
+                return unsupported_error("mpc: unsupported stream version");
+            }
+
+            let buf = source.read_boxed_slice_exact(SV7_HEADER_LEN)?;
+
+            let info = StreamInfo::read_sv7(&buf)?;
+
+            // The audio data starts in the final word of the header, after the encoder version.
+            let data_pos = source.pos() - 4;
+
+            let framing =
+                Framing::Sv7 { data_pos, n_frames: u64::from(info.n_frames.unwrap_or_default()) };
+
+            let mut extra_data = marker.to_vec();
+            extra_data.extend_from_slice(&buf);
+
+            (info, framing, 8, extra_data)
+        }
+        else {
+            return unsupported_error("mpc: missing musepack stream marker");
+        };
+
+        debug!("mpc: {:?}", info);
+
+        // Read the tags at the end of the stream, and then return to the first packet.
+        let data_pos = match framing {
+            Framing::Sv7 { data_pos, .. } => data_pos,
+            Framing::Sv8 => first_pos,
+        };
+
+        read_tags(&mut source, &mut metadata)?;
+
+        if source.is_seekable() {
+            source.seek(SeekFrom::Start(data_pos))?;
+        }
+        else {
+            source.seek_buffered(data_pos);
+        }
+
+        let tags = info.replaygain.to_tags();
+
+        if !tags.is_empty() {
+            let mut builder = MetadataBuilder::new();
+
+            for tag in tags {
+                builder.add_tag(tag);
+            }
+
+            metadata.push(builder.metadata());
+        }
+
+        let packet_len = u64::from(info.frames_per_packet) * u64::from(MPC_FRAME_LEN);
+
+        // The decoder always outputs whole frames.
+        let frame_len = u64::from(MPC_FRAME_LEN);
+
+        let total_len = match info.n_frames {
+            Some(n_frames) => u64::from(n_frames) * frame_len,
+            None => (info.delay + info.n_samples + frame_len - 1) / frame_len * frame_len,
+        };
+
+        let delay = info.delay as u32;
+        let padding = total_len.saturating_sub(info.delay + info.n_samples) as u32;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_MUSEPACK)
+            .with_sample_rate(info.sample_rate)
+            .with_time_base(TimeBase::new(1, info.sample_rate))
+            .with_max_frames_per_packet(packet_len)
+            .with_channels(info.channels)
+            .with_delay(delay)
+            .with_padding(padding)
+            .with_extra_data(extra_data.into_boxed_slice());
+
+        if options.enable_gapless {
+            params.with_n_frames(info.n_samples);
+        }
+        else {
+            params.with_n_frames(total_len);
+        }
+
+        Ok(MpcReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            framing,
+            index: Vec::new(),
+            next_pos: first_pos,
+            next_packet: 0,
+            packet_len,
+            total_len,
+            delay,
+            n_samples: info.n_samples,
+            enable_gapless: options.enable_gapless,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let pos = self.next_pos;
+
+        let len = match self.advance()? {
+            Some(len) => len,
+            None => return end_of_stream_error(),
+        };
+
+        // For SV8, the reader is positioned at the payload.
+        let pos = match self.framing {
+            Framing::Sv7 { .. } => pos,
+            Framing::Sv8 => self.reader.pos(),
+        };
+
+        let buf = self.read_payload(pos, len)?;
+
+        let ts = (self.next_packet as u64 - 1) * self.packet_len;
+        let dur = self.packet_len.min(self.total_len.saturating_sub(ts));
+
+        let mut packet = Packet::new_from_boxed_slice(0, ts, dur, buf);
+
+        if self.enable_gapless {
+            trim_packet(&mut packet, self.delay, Some(self.n_samples));
+        }
+
+        Ok(packet)
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // The timestamp of the desired audio frame, including the delay.
+        let delay = if self.enable_gapless { u64::from(self.delay) } else { 0 };
+
+        let last_packet = (self.total_len.max(1) - 1) / self.packet_len;
+
+        let target = ((required_ts + delay) / self.packet_len).min(last_packet) as usize;
+
+        if target < self.index.len() {
+            self.next_pos = self.index[target];
+            self.next_packet = target;
+        }
+        else {
+            // Resume from the last known packet, and skip forward to the desired packet.
+            if let Some(&pos) = self.index.last() {
+                self.next_pos = pos;
+                self.next_packet = self.index.len() - 1;
+            }
+
+            while self.next_packet < target {
+                if self.advance()?.is_none() {
+                    return seek_error(SeekErrorKind::OutOfRange);
+                }
+            }
+        }
+
+        let actual_ts = (target as u64 * self.packet_len).saturating_sub(delay);
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,273 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::checksum::Crc32Le;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, Monitor, ReadBytes};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+/// The SV7 stream marker: "MP+" in ASCII, followed by the stream version.
+pub const SV7_STREAM_MARKER: [u8; 3] = *b"MP+";
+
+/// The SV8 stream marker: "MPCK" in ASCII.
+pub const SV8_STREAM_MARKER: [u8; 4] = *b"MPCK";
+
+/// The length of the SV7 stream header, excluding the stream marker.
+pub const SV7_HEADER_LEN: usize = 24;
+
+/// The number of samples per channel in a frame.
+pub const MPC_FRAME_LEN: u32 = 1152;
+
+/// The delay, in samples, of the synthesis filterbank.
+const DECODER_DELAY: u64 = 481;
+
+/// The reference loudness, in dB, that SV8 gains are relative to.
+const SV8_GAIN_REF: f32 = 64.82;
+
+/// Sample rates indexed by the sample rate index of a stream header.
+const SAMPLE_RATES: [u32; 4] = [44100, 48000, 37800, 32000];
+
+/// ReplayGain information stored in a stream header.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayGain {
+    /// The track gain in dB.
+    pub track_gain: Option<f32>,
+    /// The track peak, where 1.0 is full scale.
+    pub track_peak: Option<f32>,
+    /// The album gain in dB.
+    pub album_gain: Option<f32>,
+    /// The album peak, where 1.0 is full scale.
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Read the ReplayGain fields of a SV7 stream header. Gains are stored in hundredths of a dB,
+    /// and peaks as 16-bit sample values. A value of 0 indicates the field is not set.
+    fn from_sv7(track_gain: u16, track_peak: u16, album_gain: u16, album_peak: u16) -> Self {
+        let gain = |gain: u16| (gain != 0).then(|| f32::from(gain as i16) / 100.0);
+        let peak = |peak: u16| (peak != 0).then(|| f32::from(peak) / 32768.0);
+
+        ReplayGain {
+            track_gain: gain(track_gain),
+            track_peak: peak(track_peak),
+            album_gain: gain(album_gain),
+            album_peak: peak(album_peak),
+        }
+    }
+
+    /// Read a SV8 ReplayGain packet. Gains are stored as the loudness relative to the reference
+    /// loudness, and peaks as the level of a 16-bit sample value, both in 1/256ths of a dB. A value
+    /// of 0 indicates the field is not set.
+    pub fn read_sv8(buf: &[u8]) -> Result<Self> {
+        let mut reader = BufReader::new(buf);
+
+        // Only version 1 of the ReplayGain packet is defined.
+        if reader.read_u8()? != 1 {
+            return unsupported_error("mpc: unsupported replaygain version");
+        }
+
+        let gain = |gain: u16| (gain != 0).then(|| SV8_GAIN_REF - f32::from(gain) / 256.0);
+        let peak =
+            |peak: u16| (peak != 0).then(|| 10f32.powf(f32::from(peak) / (20.0 * 256.0)) / 32768.0);
+
+        Ok(ReplayGain {
+            track_gain: gain(reader.read_be_u16()?),
+            track_peak: peak(reader.read_be_u16()?),
+            album_gain: gain(reader.read_be_u16()?),
+            album_peak: peak(reader.read_be_u16()?),
+        })
+    }
+
+    /// Convert the ReplayGain information into tags.
+    pub fn to_tags(&self) -> Vec<Tag> {
+        let fields = [
+            (StandardTagKey::ReplayGainTrackGain, "REPLAYGAIN_TRACK_GAIN", self.track_gain, true),
+            (StandardTagKey::ReplayGainTrackPeak, "REPLAYGAIN_TRACK_PEAK", self.track_peak, false),
+            (StandardTagKey::ReplayGainAlbumGain, "REPLAYGAIN_ALBUM_GAIN", self.album_gain, true),
+            (StandardTagKey::ReplayGainAlbumPeak, "REPLAYGAIN_ALBUM_PEAK", self.album_peak, false),
+        ];
+
+        fields
+            .iter()
+            .filter_map(|&(std_key, key, value, is_gain)| {
+                let value = match value? {
+                    gain if is_gain => format!("{:.2} dB", gain),
+                    peak => format!("{:.6}", peak),
+                };
+
+                Some(Tag::new(Some(std_key), key, Value::from(value)))
+            })
+            .collect()
+    }
+}
+
+/// Information about a Musepack stream common to all stream versions.
+#[derive(Clone, Debug)]
+pub struct StreamInfo {
+    pub sample_rate: u32,
+    pub channels: Channels,
+    /// The number of frames in an audio packet.
+    pub frames_per_packet: u32,
+    /// The number of samples per channel, excluding the encoder delay.
+    pub n_samples: u64,
+    /// The number of samples per channel to discard from the start of the stream.
+    pub delay: u64,
+    /// The total number of frames, if known.
+    pub n_frames: Option<u32>,
+    pub replaygain: ReplayGain,
+}
+
+impl StreamInfo {
+    /// Read a SV7 stream header from a buffer of `SV7_HEADER_LEN` bytes following the stream
+    /// marker.
+    ///
+    /// The header is stored as 32-bit little-endian words that are read most-significant bit first.
+    pub fn read_sv7(buf: &[u8]) -> Result<Self> {
+        if buf.len() != SV7_HEADER_LEN {
+            return decode_error("mpc: invalid sv7 stream header length");
+        }
+
+        let mut words = [0; SV7_HEADER_LEN / 4];
+
+        for (word, bytes) in words.iter_mut().zip(buf.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let n_frames = words[0];
+
+        if (words[1] >> 31) != 0 {
+            return unsupported_error("mpc: intensity stereo is not supported");
+        }
+
+        let sample_rate = SAMPLE_RATES[((words[1] >> 16) & 0x3) as usize];
+
+        let replaygain = ReplayGain::from_sv7(
+            (words[2] >> 16) as u16,
+            words[2] as u16,
+            (words[3] >> 16) as u16,
+            words[3] as u16,
+        );
+
+        let is_true_gapless = (words[4] >> 31) != 0;
+        let last_frame_len = u64::from((words[4] >> 20) & 0x7ff);
+
+        if n_frames == 0 {
+            return decode_error("mpc: stream is empty");
+        }
+
+        // If the encoder stored the number of valid samples in the final frame, then the final
+        // frame is trimmed. Otherwise, only the decoder delay is trimmed.
+        let total = u64::from(n_frames) * u64::from(MPC_FRAME_LEN);
+
+        let n_samples = if is_true_gapless {
+            total - (u64::from(MPC_FRAME_LEN) - last_frame_len.min(u64::from(MPC_FRAME_LEN)))
+        }
+        else {
+            total.saturating_sub(DECODER_DELAY)
+        };
+
+        Ok(StreamInfo {
+            sample_rate,
+            channels: Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            frames_per_packet: 1,
+            n_samples,
+            delay: DECODER_DELAY,
+            n_frames: Some(n_frames),
+            replaygain,
+        })
+    }
+
+    /// Read a SV8 stream header packet.
+    pub fn read_sv8(buf: &[u8]) -> Result<Self> {
+        if buf.len() < 4 {
+            return decode_error("mpc: invalid sv8 stream header length");
+        }
+
+        let mut reader = BufReader::new(buf);
+
+        // The stream header starts with a CRC of the remainder of the packet.
+        let crc = reader.read_be_u32()?;
+
+        let mut crc32 = Crc32Le::new(0xffff_ffff);
+        crc32.process_buf_bytes(&buf[4..]);
+
+        if !crc32.crc() != crc {
+            return decode_error("mpc: computed stream header CRC does not match expected CRC");
+        }
+
+        let version = reader.read_u8()?;
+
+        if version != 8 {
+            return unsupported_error("mpc: unsupported stream version");
+        }
+
+        // The number of samples includes the silence at the start of the stream.
+        let n_samples = read_size(&mut reader)?;
+        let silence = read_size(&mut reader)?;
+
+        let flags = reader.read_be_u16()?;
+
+        let sample_rate = match SAMPLE_RATES.get(usize::from(flags >> 13)) {
+            Some(&sample_rate) => sample_rate,
+            None => return unsupported_error("mpc: unsupported sample rate"),
+        };
+
+        let num_channels = u32::from((flags >> 4) & 0xf) + 1;
+
+        let channels = match Channels::from_bits((1 << num_channels) - 1) {
+            Some(channels) => channels,
+            None => return unsupported_error("mpc: unsupported number of channels"),
+        };
+
+        // The number of frames per audio packet is a power of 4.
+        let frames_per_packet = 1 << (2 * (flags & 0x7));
+
+        Ok(StreamInfo {
+            sample_rate,
+            channels,
+            frames_per_packet,
+            n_samples: n_samples.saturating_sub(silence),
+            delay: DECODER_DELAY + silence,
+            n_frames: None,
+            replaygain: Default::default(),
+        })
+    }
+}
+
+/// Read a SV8 variable-length size. The size is stored as a big-endian sequence of 7-bit groups,
+/// where the most-significant bit of each byte indicates if another byte follows.
+pub fn read_size<B: ReadBytes>(reader: &mut B) -> Result<u64> {
+    let mut size = 0;
+
+    // A 64-bit size requires at most 10 bytes.
+    for _ in 0..10 {
+        let byte = reader.read_u8()?;
+
+        size = (size << 7) | u64::from(byte & 0x7f);
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+
+    decode_error("mpc: invalid size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_size() {
+        let mut reader = BufReader::new(&[0x05, 0x81, 0x00, 0xff, 0x7f]);
+
+        assert_eq!(read_size(&mut reader).unwrap(), 5);
+        assert_eq!(read_size(&mut reader).unwrap(), 128);
+        assert_eq!(read_size(&mut reader).unwrap(), 0x3fff);
+    }
+}
@@ -0,0 +1,136 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_SHORTEN};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::support_codec;
+
+use super::frame::{read_frame, State};
+use super::header::{SampleType, ShnHeader};
+
+/// The packet header flag indicating that the decoder state follows.
+pub const PACKET_FLAG_STATE: u8 = 0x80;
+
+/// Shorten (SHN) decoder.
+pub struct ShortenDecoder {
+    params: CodecParameters,
+    header: ShnHeader,
+    state: State,
+    /// The decoded samples of each channel.
+    samples: Vec<Vec<i32>>,
+    buf: AudioBuffer<i32>,
+}
+
+impl ShortenDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let mut reader = BufReader::new(packet.buf());
+
+        // The packet header contains the number of bits to skip to reach the first command, and
+        // optionally, the decoder state at the start of the packet.
+        let flags = reader.read_u8()?;
+
+        if flags & PACKET_FLAG_STATE != 0 {
+            self.state = State::read(&mut reader, &self.header)?;
+        }
+
+        let data = &packet.buf()[reader.pos() as usize..];
+
+        let mut bs = BitReaderLtr::new(data);
+        bs.ignore_bits(u32::from(flags & 0x7))?;
+
+        let n_frames =
+            match read_frame(&mut bs, &self.header, &mut self.state, Some(&mut self.samples))? {
+                Some(n_frames) => n_frames as usize,
+                None => return decode_error("shn: packet does not contain a frame"),
+            };
+
+        // Convert the samples to signed 32-bit samples.
+        let (shift, bias) = match self.header.sample_type {
+            SampleType::S8 => (24, 0),
+            SampleType::U8 => (24, 0x80),
+            SampleType::S16 => (16, 0),
+            SampleType::U16 => (16, 0x8000),
+        };
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(n_frames));
+
+        for (ch, samples) in self.samples.iter().enumerate() {
+            for (out, &s) in self.buf.chan_mut(ch).iter_mut().zip(samples) {
+                *out = s.wrapping_sub(bias).wrapping_shl(shift);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for ShortenDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports Shorten.
+        if params.codec != CODEC_TYPE_SHORTEN {
+            return unsupported_error("shn: invalid codec type");
+        }
+
+        // The extra data is the stream header.
+        let header = match params.extra_data.as_ref() {
+            Some(buf) => ShnHeader::read(&mut BitReaderLtr::new(buf))?,
+            _ => return unsupported_error("shn: missing extra data"),
+        };
+
+        let sample_rate = match params.sample_rate {
+            Some(sample_rate) => sample_rate,
+            None => return unsupported_error("shn: sample rate is required"),
+        };
+
+        let max_frames = u64::from(header.block_size);
+
+        let spec = SignalSpec::new(sample_rate, header.channels());
+
+        Ok(ShortenDecoder {
+            params: params.clone(),
+            state: State::new(&header),
+            samples: vec![vec![0; max_frames as usize]; header.num_channels as usize],
+            buf: AudioBuffer::new(max_frames, spec),
+            header,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.state = State::new(&self.header);
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_SHORTEN, "shorten", "Shorten")]
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
@@ -0,0 +1,526 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{self, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_SHORTEN};
+use symphonia_core::errors::{end_of_stream_error, seek_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use super::decoder::PACKET_FLAG_STATE;
+use super::frame::{read_frame, State};
+use super::header::{ReadCodes, ShnHeader};
+
+/// The sample rate assumed if the stream does not contain the header of a WAVE or AIFF file.
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+/// The length of the shntool seek table header.
+const SEEK_HEADER_LEN: u64 = 12;
+
+/// The length of the shntool seek table trailer.
+const SEEK_TRAILER_LEN: u64 = 12;
+
+/// The length of a shntool seek table entry.
+const SEEK_ENTRY_LEN: u64 = 80;
+
+/// The maximum number of seek table entries.
+const MAX_SEEK_ENTRIES: u64 = 1 << 20;
+
+/// The length of an ID3v1 tag.
+const ID3V1_TAG_LEN: u64 = 128;
+
+/// The interval, in samples, between seek points added while decoding to a seek position.
+const SEEK_POINT_INTERVAL: u64 = 25600;
+
+/// Reads bits, most-significant bit first, from a stream while retaining the bytes read.
+struct BitRecorder<'a, B: ReadBytes> {
+    reader: &'a mut B,
+    buf: Vec<u8>,
+    /// The number of unread bits in the last byte of `buf`.
+    n_bits_left: u32,
+    /// The total number of bits read.
+    n_bits_read: u64,
+}
+
+impl<'a, B: ReadBytes> BitRecorder<'a, B> {
+    /// Instantiate a new `BitRecorder` that skips `skip` bits of the first byte.
+    fn new(reader: &'a mut B, skip: u32) -> io::Result<Self> {
+        let mut bs = BitRecorder { reader, buf: Vec::new(), n_bits_left: 0, n_bits_read: 0 };
+
+        if skip > 0 {
+            bs.read_bits(skip)?;
+            bs.n_bits_read = 0;
+        }
+
+        Ok(bs)
+    }
+
+    #[inline(always)]
+    fn fetch(&mut self) -> io::Result<()> {
+        self.buf.push(self.reader.read_u8()?);
+        self.n_bits_left = 8;
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn cur(&self) -> u32 {
+        u32::from(self.buf[self.buf.len() - 1]) & ((1 << self.n_bits_left) - 1)
+    }
+}
+
+impl<'a, B: ReadBytes> ReadCodes for BitRecorder<'a, B> {
+    fn read_bits(&mut self, mut num_bits: u32) -> io::Result<u32> {
+        let mut value = 0u32;
+
+        self.n_bits_read += u64::from(num_bits);
+
+        while num_bits > 0 {
+            if self.n_bits_left == 0 {
+                self.fetch()?;
+            }
+
+            let n = num_bits.min(self.n_bits_left);
+            let bits = self.cur() >> (self.n_bits_left - n);
+
+            value = value.wrapping_shl(n) | bits;
+
+            self.n_bits_left -= n;
+            num_bits -= n;
+        }
+
+        Ok(value)
+    }
+
+    fn read_zeros(&mut self) -> io::Result<u32> {
+        let mut count = 0;
+
+        loop {
+            if self.n_bits_left == 0 {
+                self.fetch()?;
+            }
+
+            let cur = self.cur();
+
+            if cur != 0 {
+                // The number of leading 0 bits of the remaining bits of the current byte.
+                let zeros = cur.leading_zeros() - (32 - self.n_bits_left);
+
+                self.n_bits_left -= zeros + 1;
+                self.n_bits_read += u64::from(zeros + 1);
+
+                return Ok(count + zeros);
+            }
+
+            count += self.n_bits_left;
+            self.n_bits_read += u64::from(self.n_bits_left);
+            self.n_bits_left = 0;
+        }
+    }
+}
+
+/// A point in the stream at which decoding may start.
+#[derive(Clone)]
+struct SyncPoint {
+    /// The timestamp of the first sample of the frame.
+    ts: u64,
+    /// The position of the frame in bits.
+    bit_pos: u64,
+    /// The decoder state at the start of the frame.
+    state: State,
+}
+
+/// Shorten (SHN) format reader.
+///
+/// `ShortenReader` implements a demuxer for the Shorten file format. Each packet contains one
+/// frame, that is, one block of every channel, and any preceding non-audio commands.
+///
+/// Since a Shorten stream is a continuous bitstream, the first byte of a packet contains the
+/// number of bits to skip in the first byte of the frame data. If the `0x80` flag is set in that
+/// byte, then the serialized decoder state at the start of the frame follows. The decoder state is
+/// provided after a seek.
+pub struct ShortenReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    header: ShnHeader,
+    /// Points in the stream with a known decoder state, ordered by timestamp.
+    sync_points: Vec<SyncPoint>,
+    /// The state used to parse frames. Only the block size is maintained.
+    parse_state: State,
+    /// The decoder state to provide with the next packet.
+    pending_state: Option<State>,
+    /// The position of the next frame in bits.
+    next_bit_pos: u64,
+    next_ts: u64,
+}
+
+impl QueryDescriptor for ShortenReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("shn", "Shorten", &["shn"], &["audio/x-shorten"], &[b"ajkg"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Reads the seek table appended by shntool, if present. Positions are relative to `start_pos`.
+fn read_seek_table(
+    reader: &mut MediaSourceStream,
+    header: &ShnHeader,
+    start_pos: u64,
+) -> Result<Vec<SyncPoint>> {
+    let end = match reader.byte_len() {
+        Some(len) if reader.is_seekable() => len,
+        _ => return Ok(Vec::new()),
+    };
+
+    // The seek table is either at the end of the stream, or precedes an ID3v1 tag.
+    for &tag_len in &[0, ID3V1_TAG_LEN] {
+        let trailer_end = match end.checked_sub(tag_len) {
+            Some(trailer_end) if trailer_end >= start_pos + SEEK_TRAILER_LEN => trailer_end,
+            _ => continue,
+        };
+
+        reader.seek(SeekFrom::Start(trailer_end - SEEK_TRAILER_LEN))?;
+
+        // The trailer contains the length of the seek table, including the header and trailer.
+        let table_len = u64::from(reader.read_u32()?);
+
+        let mut sig = [0; 8];
+        reader.read_buf_exact(&mut sig)?;
+
+        if &sig != b"SHNAMPSK" {
+            continue;
+        }
+
+        let n_entries = match table_len.checked_sub(SEEK_HEADER_LEN + SEEK_TRAILER_LEN) {
+            Some(len) if trailer_end - start_pos >= table_len => len / SEEK_ENTRY_LEN,
+            _ => {
+                warn!("shn: invalid seek table length");
+                return Ok(Vec::new());
+            }
+        };
+
+        reader.seek(SeekFrom::Start(trailer_end - table_len))?;
+
+        if reader.read_quad_bytes()? != *b"SEEK" || n_entries > MAX_SEEK_ENTRIES {
+            warn!("shn: invalid seek table header");
+            return Ok(Vec::new());
+        }
+
+        let _version = reader.read_u32()?;
+        let _file_len = reader.read_u32()?;
+
+        // Entries contain the history and means of at most 2 channels, for the default number of
+        // history samples and mean blocks.
+        if header.num_channels > 2 || header.nwrap != 3 || header.nmean > 4 {
+            warn!("shn: seek table does not support this stream configuration");
+            return Ok(Vec::new());
+        }
+
+        let mut points = Vec::with_capacity(n_entries as usize);
+
+        for _ in 0..n_entries {
+            let mut entry = [0; SEEK_ENTRY_LEN as usize];
+            reader.read_buf_exact(&mut entry)?;
+
+            let mut entry = BufReader::new(&entry);
+
+            let ts = u64::from(entry.read_u32()?);
+            let _byte_pos = entry.read_u32()?;
+            let last_pos = u64::from(entry.read_u32()?);
+            let bytes_left = u64::from(entry.read_u16()?);
+            let _buf_offset = entry.read_u16()?;
+            let bits_left = u64::from(entry.read_u16()?);
+            let _bit_buf = entry.read_u32()?;
+            let bitshift = u32::from(entry.read_u16()?);
+
+            let mut state = State::new(header);
+            state.bitshift = bitshift;
+
+            let mut history = [[0; 3]; 2];
+            let mut means = [[0; 4]; 2];
+
+            for value in history.iter_mut().flatten().chain(means.iter_mut().flatten()) {
+                *value = entry.read_u32()? as i32;
+            }
+
+            // The history is stored most recent sample first.
+            for (ch, (h, m)) in state.history.iter_mut().zip(state.means.iter_mut()).enumerate() {
+                for (i, s) in h.iter_mut().rev().enumerate() {
+                    *s = history[ch][i];
+                }
+                for (i, s) in m.iter_mut().enumerate() {
+                    *s = means[ch][i];
+                }
+            }
+
+            // The entry stores the position of the shorten bitstream reader's input buffer. The
+            // next unread byte is the end of the buffer less the unread bytes, and the current
+            // word contains the unread bits preceding it.
+            let bit_pos = match (8 * (last_pos.saturating_sub(bytes_left))).checked_sub(bits_left) {
+                Some(bit_pos) if bitshift <= 32 => 8 * start_pos + bit_pos,
+                _ => {
+                    warn!("shn: invalid seek table entry");
+                    return Ok(Vec::new());
+                }
+            };
+
+            points.push(SyncPoint { ts, bit_pos, state });
+        }
+
+        if points.windows(2).any(|w| w[0].ts >= w[1].ts || w[0].bit_pos >= w[1].bit_pos) {
+            warn!("shn: seek table is not ordered");
+            return Ok(Vec::new());
+        }
+
+        debug!("shn: read seek table with {} entries", points.len());
+
+        return Ok(points);
+    }
+
+    Ok(Vec::new())
+}
+
+impl ShortenReader {
+    /// Positions the reader at the byte containing the given bit position.
+    fn seek_to_bit(&mut self, bit_pos: u64) -> Result<u32> {
+        let pos = bit_pos / 8;
+
+        if self.reader.pos() != pos {
+            // The next frame usually starts in the last byte of the previous frame. Try to seek
+            // within the buffer first.
+            let buffered_pos = self.reader.seek_buffered(pos);
+
+            if buffered_pos != pos {
+                if self.reader.is_seekable() {
+                    self.reader.seek(SeekFrom::Start(pos))?;
+                }
+                else if pos > buffered_pos {
+                    self.reader.ignore_bytes(pos - buffered_pos)?;
+                }
+                else {
+                    return seek_error(SeekErrorKind::ForwardOnly);
+                }
+            }
+        }
+
+        Ok((bit_pos % 8) as u32)
+    }
+
+    /// Add a sync point, if one does not already exist for the timestamp.
+    fn add_sync_point(&mut self, point: SyncPoint) {
+        if let Err(i) = self.sync_points.binary_search_by_key(&point.ts, |p| p.ts) {
+            self.sync_points.insert(i, point);
+        }
+    }
+}
+
+impl FormatReader for ShortenReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let start_pos = source.pos();
+
+        // Read the stream header, retaining it for the decoder.
+        let (header, extra_data, header_bits) = {
+            let mut bs = BitRecorder::new(&mut source, 0)?;
+            let header = ShnHeader::read(&mut bs)?;
+            (header, bs.buf, bs.n_bits_read)
+        };
+
+        debug!("shn: {:?}", header);
+
+        let first_bit_pos = 8 * start_pos + header_bits;
+
+        let mut sync_points =
+            vec![SyncPoint { ts: 0, bit_pos: first_bit_pos, state: State::new(&header) }];
+
+        // Read the seek table, and then return to the first frame.
+        let seek_table = read_seek_table(&mut source, &header, start_pos)?;
+
+        sync_points
+            .extend(seek_table.into_iter().filter(|p| p.ts > 0 && p.bit_pos > first_bit_pos));
+
+        if source.is_seekable() {
+            source.seek(SeekFrom::Start(first_bit_pos / 8))?;
+        }
+
+        let (sample_rate, n_frames) = match &header.file_info {
+            Some(info) => (info.sample_rate, info.n_frames),
+            None => {
+                warn!("shn: unknown sample rate, assuming {} Hz", DEFAULT_SAMPLE_RATE);
+                (DEFAULT_SAMPLE_RATE, None)
+            }
+        };
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_SHORTEN)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_max_frames_per_packet(u64::from(header.block_size))
+            .with_bits_per_sample(header.sample_type.bits_per_sample())
+            .with_channels(header.channels())
+            .with_extra_data(extra_data.into_boxed_slice());
+
+        if let Some(n_frames) = n_frames {
+            params.with_n_frames(n_frames);
+        }
+
+        Ok(ShortenReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            parse_state: State::new(&header),
+            header,
+            sync_points,
+            pending_state: None,
+            next_bit_pos: first_bit_pos,
+            next_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let skip = self.seek_to_bit(self.next_bit_pos)?;
+
+        let mut bs = BitRecorder::new(&mut self.reader, skip)?;
+
+        let dur = match read_frame(&mut bs, &self.header, &mut self.parse_state, None)? {
+            Some(dur) => u64::from(dur),
+            None => return end_of_stream_error(),
+        };
+
+        let n_bits_read = bs.n_bits_read;
+
+        let mut buf = Vec::with_capacity(bs.buf.len() + 1);
+
+        match self.pending_state.take() {
+            Some(state) => {
+                buf.push(PACKET_FLAG_STATE | skip as u8);
+                state.write(&mut buf);
+            }
+            None => buf.push(skip as u8),
+        }
+
+        buf.extend_from_slice(&bs.buf);
+
+        let ts = self.next_ts;
+
+        self.next_bit_pos += n_bits_read;
+        self.next_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Start from the last sync point preceding the desired timestamp.
+        let i = match self.sync_points.binary_search_by_key(&required_ts, |p| p.ts) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+
+        let SyncPoint { ts, bit_pos, state } = self.sync_points[i].clone();
+
+        let mut ts = ts;
+        let mut bit_pos = bit_pos;
+        let mut state = state;
+
+        // Decode frames until the frame containing the desired timestamp to recover the decoder
+        // state at the start of that frame.
+        let mut samples =
+            vec![vec![0; self.header.block_size as usize]; self.header.num_channels as usize];
+        let mut next_sync_ts = ts + SEEK_POINT_INTERVAL;
+
+        loop {
+            let skip = self.seek_to_bit(bit_pos)?;
+
+            let mut bs = BitRecorder::new(&mut self.reader, skip)?;
+
+            let mut next_state = state.clone();
+
+            let dur = match read_frame(&mut bs, &self.header, &mut next_state, Some(&mut samples))?
+            {
+                Some(dur) => u64::from(dur),
+                None => break,
+            };
+
+            if ts + dur > required_ts {
+                break;
+            }
+
+            bit_pos += bs.n_bits_read;
+            ts += dur;
+            state = next_state;
+
+            if ts >= next_sync_ts {
+                self.add_sync_point(SyncPoint { ts, bit_pos, state: state.clone() });
+                next_sync_ts = ts + SEEK_POINT_INTERVAL;
+            }
+        }
+
+        self.next_bit_pos = bit_pos;
+        self.next_ts = ts;
+        self.parse_state = state.clone();
+        self.pending_state = Some(state);
+
+        debug!("seeked to ts={} (delta={})", ts, required_ts - ts);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,321 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+use super::header::*;
+
+/// The coefficients of the fixed predictors.
+const FIXED_COEFFS: [[i32; 3]; 4] = [[0, 0, 0], [1, 0, 0], [2, -1, 0], [3, -3, 1]];
+
+/// The decoding state of a stream, carried from one frame to the next.
+#[derive(Clone, Debug)]
+pub struct State {
+    /// The current number of samples per channel in a block.
+    pub block_size: u32,
+    /// The number of low-order zero bits removed from each sample.
+    pub bitshift: u32,
+    /// The last `nwrap` samples of each channel, oldest first.
+    pub history: Vec<Vec<i32>>,
+    /// The means of the last `nmean` blocks of each channel, oldest first.
+    pub means: Vec<Vec<i32>>,
+}
+
+impl State {
+    /// Instantiate the initial state of a stream.
+    pub fn new(header: &ShnHeader) -> Self {
+        let num_channels = header.num_channels as usize;
+        let nmean = header.nmean.max(1) as usize;
+
+        State {
+            block_size: header.block_size,
+            bitshift: 0,
+            history: vec![vec![0; header.nwrap as usize]; num_channels],
+            means: vec![vec![header.sample_type.mean(); nmean]; num_channels],
+        }
+    }
+
+    /// Serialize the state.
+    pub fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.block_size.to_le_bytes());
+        buf.push(self.bitshift as u8);
+
+        for (history, means) in self.history.iter().zip(&self.means) {
+            for value in history.iter().chain(means) {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    /// Deserialize a state written by `write`.
+    pub fn read(reader: &mut BufReader<'_>, header: &ShnHeader) -> Result<Self> {
+        let mut state = State::new(header);
+
+        state.block_size = reader.read_u32()?;
+        state.bitshift = u32::from(reader.read_u8()?);
+
+        if state.block_size == 0 || state.block_size > header.block_size || state.bitshift > 32 {
+            return decode_error("shn: invalid decoder state");
+        }
+
+        for (history, means) in state.history.iter_mut().zip(state.means.iter_mut()) {
+            for value in history.iter_mut().chain(means.iter_mut()) {
+                *value = reader.read_u32()? as i32;
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+/// Scales the decoded samples by the bitshift.
+fn shift_samples(samples: &mut [i32], bitshift: u32) {
+    if bitshift >= 32 {
+        samples.iter_mut().for_each(|s| *s = 0);
+    }
+    else if bitshift > 0 {
+        samples.iter_mut().for_each(|s| *s = s.wrapping_shl(bitshift));
+    }
+}
+
+/// Read a block of one channel.
+///
+/// If `out` is `None`, the residuals are only parsed and the state is not updated. Otherwise, the
+/// block is decoded into `out`, which must be at least as long as the block.
+fn read_block<R: ReadCodes>(
+    bs: &mut R,
+    header: &ShnHeader,
+    state: &mut State,
+    ch: usize,
+    cmd: u32,
+    out: Option<&mut [i32]>,
+) -> Result<()> {
+    let n = state.block_size as usize;
+
+    let energy = if cmd != FN_ZERO {
+        let energy = bs.read_uvar(ENERGY_SIZE)?;
+
+        // Version 0 streams store the energy plus 1.
+        let energy = if header.version == 0 { energy.checked_sub(1) } else { Some(energy) };
+
+        match energy {
+            Some(energy) if energy < 32 => energy,
+            _ => return decode_error("shn: invalid residual energy"),
+        }
+    }
+    else {
+        0
+    };
+
+    let mut qlpc = [0; 3];
+    let mut qlpc_ext = Vec::new();
+
+    let (order, qshift) = match cmd {
+        FN_QLPC => {
+            let order = bs.read_uvar(LPC_ORDER_SIZE)?;
+
+            if order > header.nwrap {
+                return decode_error("shn: invalid lpc order");
+            }
+
+            let coeffs = if order as usize > qlpc.len() {
+                qlpc_ext.resize(order as usize, 0);
+                &mut qlpc_ext[..]
+            }
+            else {
+                &mut qlpc[..order as usize]
+            };
+
+            for coeff in coeffs.iter_mut() {
+                *coeff = bs.read_svar(LPC_QUANT)?;
+            }
+
+            (order as usize, LPC_QUANT)
+        }
+        FN_ZERO => (0, 0),
+        _ => (cmd as usize, 0),
+    };
+
+    let out = match out {
+        Some(out) => out,
+        None => {
+            // Only parse the residuals.
+            if cmd != FN_ZERO {
+                for _ in 0..n {
+                    bs.read_svar(energy)?;
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let coeffs: &[i32] = match cmd {
+        FN_QLPC if order > qlpc.len() => &qlpc_ext,
+        FN_QLPC => &qlpc[..order],
+        FN_ZERO => &[],
+        _ => &FIXED_COEFFS[cmd as usize][..order],
+    };
+
+    // Calculate the offset from the means of the previous blocks.
+    let nmean = header.nmean as i32;
+    let means = &state.means[ch];
+
+    let offset = if nmean == 0 {
+        means[0]
+    }
+    else {
+        let init = if header.version < 2 { 0 } else { nmean / 2 };
+        let sum = means.iter().fold(init, |sum, &mean| sum.wrapping_add(mean));
+        let offset = sum / nmean;
+
+        if header.version >= 2 && state.bitshift > 0 {
+            (offset >> (state.bitshift - 1)) >> 1
+        }
+        else {
+            offset
+        }
+    };
+
+    // The working buffer is the history of the channel followed by the samples of the block.
+    let nwrap = header.nwrap as usize;
+
+    let mut buf = Vec::with_capacity(nwrap + n);
+    buf.extend_from_slice(&state.history[ch]);
+    buf.resize(nwrap + n, 0);
+
+    if cmd == FN_ZERO {
+        // All samples are 0.
+    }
+    else {
+        // The LPC predictor operates on samples with the offset removed.
+        if cmd == FN_QLPC && offset != 0 {
+            for s in buf[nwrap - order..nwrap].iter_mut() {
+                *s = s.wrapping_sub(offset);
+            }
+        }
+
+        let init = match cmd {
+            _ if order == 0 => offset,
+            FN_QLPC => header.lpc_offset,
+            _ => 0,
+        };
+
+        for i in nwrap..nwrap + n {
+            let pred = coeffs
+                .iter()
+                .zip(buf[i - order..i].iter().rev())
+                .fold(init, |sum, (&c, &s)| sum.wrapping_add(c.wrapping_mul(s)));
+
+            buf[i] = bs.read_svar(energy)?.wrapping_add(pred >> qshift);
+        }
+
+        if cmd == FN_QLPC && offset != 0 {
+            for s in buf[nwrap..].iter_mut() {
+                *s = s.wrapping_add(offset);
+            }
+        }
+    }
+
+    let samples = &buf[nwrap..];
+
+    // Update the means with the mean of this block.
+    if nmean > 0 {
+        let init = if header.version < 2 { 0 } else { (n / 2) as i32 };
+        let sum = samples.iter().fold(init, |sum, &s| sum.wrapping_add(s));
+        let mean = sum / n as i32;
+
+        let mean = if header.version < 2 {
+            mean
+        }
+        else if state.bitshift < 32 {
+            mean.wrapping_shl(state.bitshift)
+        }
+        else {
+            0
+        };
+
+        let means = &mut state.means[ch];
+        means.rotate_left(1);
+        *means.last_mut().unwrap() = mean;
+    }
+
+    out[..n].copy_from_slice(samples);
+
+    shift_samples(&mut out[..n], state.bitshift);
+
+    // Retain the history for the next block.
+    state.history[ch].copy_from_slice(&buf[n..]);
+
+    Ok(())
+}
+
+/// Read commands until a block of every channel has been read, and return the number of samples
+/// per channel in the frame. Returns `None` if the end of the stream is reached.
+///
+/// If `out` is `None`, the frame is only parsed. Otherwise, the frame is decoded into `out`, which
+/// must contain one buffer per channel.
+pub fn read_frame<R: ReadCodes>(
+    bs: &mut R,
+    header: &ShnHeader,
+    state: &mut State,
+    mut out: Option<&mut [Vec<i32>]>,
+) -> Result<Option<u32>> {
+    let mut ch = 0;
+    let mut frame_len = 0;
+
+    loop {
+        match bs.read_uvar(FN_SIZE)? {
+            FN_QUIT => return Ok(None),
+            FN_BLOCKSIZE => {
+                // The block size may only change between frames, and cannot exceed the initial
+                // block size.
+                let k = 31 - state.block_size.leading_zeros();
+                let block_size = bs.read_ulong(header.version, k)?;
+
+                if block_size == 0 || block_size > header.block_size || ch != 0 {
+                    return decode_error("shn: invalid block size");
+                }
+
+                state.block_size = block_size;
+            }
+            FN_BITSHIFT => {
+                let bitshift = bs.read_uvar(BITSHIFT_SIZE)?;
+
+                if bitshift > 32 {
+                    return decode_error("shn: invalid bitshift");
+                }
+
+                state.bitshift = bitshift;
+            }
+            FN_VERBATIM => {
+                // Verbatim data, such as the trailer of the original file, is ignored.
+                let len = bs.read_uvar(VERBATIM_LEN_SIZE)?;
+
+                for _ in 0..len {
+                    bs.read_uvar(VERBATIM_BYTE_SIZE)?;
+                }
+            }
+            cmd @ FN_DIFF0..=FN_DIFF3 | cmd @ FN_QLPC | cmd @ FN_ZERO => {
+                if ch == 0 {
+                    frame_len = state.block_size;
+                }
+
+                let out = out.as_mut().map(|out| &mut out[ch][..]);
+
+                read_block(bs, header, state, ch, cmd, out)?;
+
+                ch += 1;
+
+                if ch == header.num_channels as usize {
+                    return Ok(Some(frame_len));
+                }
+            }
+            _ => return decode_error("shn: invalid command"),
+        }
+    }
+}
@@ -0,0 +1,388 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io;
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+
+use log::warn;
+
+/// The Shorten stream marker: "ajkg" in ASCII.
+pub const SHN_STREAM_MARKER: [u8; 4] = *b"ajkg";
+
+/// The maximum supported stream version.
+const MAX_VERSION: u8 = 3;
+
+/// The maximum number of channels.
+const MAX_CHANNELS: u32 = 8;
+
+/// The maximum block size.
+pub const MAX_BLOCK_SIZE: u32 = 65535;
+
+/// The maximum LPC order.
+const MAX_LPC_ORDER: u32 = 1024;
+
+/// The maximum number of blocks used to calculate the running mean.
+const MAX_MEAN_BLOCKS: u32 = 32768;
+
+/// The maximum length of the embedded file header.
+const MAX_HEADER_LEN: u32 = 64 * 1024;
+
+/// The default block size of version 0 streams.
+const DEFAULT_BLOCK_SIZE: u32 = 256;
+
+/// The minimum number of samples of history.
+const MIN_WRAP: u32 = 3;
+
+// Rice parameters of the various fields in the stream.
+pub const FN_SIZE: u32 = 2;
+pub const ENERGY_SIZE: u32 = 3;
+pub const BITSHIFT_SIZE: u32 = 2;
+pub const LPC_ORDER_SIZE: u32 = 2;
+pub const LPC_QUANT: u32 = 5;
+pub const VERBATIM_LEN_SIZE: u32 = 5;
+pub const VERBATIM_BYTE_SIZE: u32 = 8;
+const ULONG_SIZE: u32 = 2;
+const TYPE_SIZE: u32 = 4;
+const CHANNELS_SIZE: u32 = 0;
+const BLOCK_SIZE_SIZE: u32 = 8;
+const NSKIP_SIZE: u32 = 1;
+
+// Commands.
+pub const FN_DIFF0: u32 = 0;
+pub const FN_DIFF3: u32 = 3;
+pub const FN_QUIT: u32 = 4;
+pub const FN_BLOCKSIZE: u32 = 5;
+pub const FN_BITSHIFT: u32 = 6;
+pub const FN_QLPC: u32 = 7;
+pub const FN_ZERO: u32 = 8;
+pub const FN_VERBATIM: u32 = 9;
+
+/// Reads the variable-length codes used by Shorten.
+pub trait ReadCodes {
+    /// Read up-to 32 bits.
+    fn read_bits(&mut self, num_bits: u32) -> io::Result<u32>;
+
+    /// Read the number of 0 bits preceding a 1 bit.
+    fn read_zeros(&mut self) -> io::Result<u32>;
+
+    /// Read an unsigned Rice code with parameter `k`.
+    fn read_uvar(&mut self, k: u32) -> io::Result<u32> {
+        let high = self.read_zeros()?;
+        let low = if k > 0 { self.read_bits(k)? } else { 0 };
+        Ok(high.wrapping_shl(k) | low)
+    }
+
+    /// Read a signed Rice code with parameter `k`.
+    fn read_svar(&mut self, k: u32) -> io::Result<i32> {
+        let value = self.read_uvar(k + 1)?;
+        Ok(((value >> 1) as i32) ^ -((value & 1) as i32))
+    }
+
+    /// Read an unsigned integer of the stream header. Since version 1, the Rice parameter is coded
+    /// before the integer.
+    fn read_ulong(&mut self, version: u8, k: u32) -> io::Result<u32> {
+        let k = if version > 0 { self.read_uvar(ULONG_SIZE)? } else { k };
+
+        if k > 32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid rice parameter"));
+        }
+
+        self.read_uvar(k)
+    }
+}
+
+impl ReadCodes for BitReaderLtr<'_> {
+    #[inline(always)]
+    fn read_bits(&mut self, num_bits: u32) -> io::Result<u32> {
+        self.read_bits_leq32(num_bits)
+    }
+
+    #[inline(always)]
+    fn read_zeros(&mut self) -> io::Result<u32> {
+        self.read_unary_zeros()
+    }
+}
+
+/// The sample format of the audio data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SampleType {
+    S8,
+    U8,
+    S16,
+    U16,
+}
+
+impl SampleType {
+    pub fn bits_per_sample(&self) -> u32 {
+        match self {
+            SampleType::S8 | SampleType::U8 => 8,
+            SampleType::S16 | SampleType::U16 => 16,
+        }
+    }
+
+    /// The initial value of the running mean.
+    pub fn mean(&self) -> i32 {
+        match self {
+            SampleType::U8 => 0x80,
+            SampleType::U16 => 0x8000,
+            _ => 0,
+        }
+    }
+}
+
+/// The Shorten stream header.
+#[derive(Clone, Debug)]
+pub struct ShnHeader {
+    pub version: u8,
+    pub sample_type: SampleType,
+    pub num_channels: u32,
+    /// The initial, and maximum, number of samples per channel in a block.
+    pub block_size: u32,
+    /// The number of blocks used to calculate the running mean.
+    pub nmean: u32,
+    /// The number of samples of history required by the predictors.
+    pub nwrap: u32,
+    /// The rounding offset of the LPC predictor.
+    pub lpc_offset: i32,
+    /// The file header of the original file, if present.
+    pub file_info: Option<FileInfo>,
+}
+
+impl ShnHeader {
+    /// Read the stream header, including the stream marker, and the embedded file header.
+    pub fn read<R: ReadCodes>(bs: &mut R) -> Result<Self> {
+        let mut marker = [0; 4];
+
+        for byte in marker.iter_mut() {
+            *byte = bs.read_bits(8)? as u8;
+        }
+
+        if marker != SHN_STREAM_MARKER {
+            return unsupported_error("shn: missing shorten stream marker");
+        }
+
+        let version = bs.read_bits(8)? as u8;
+
+        if version > MAX_VERSION {
+            return unsupported_error("shn: unsupported stream version");
+        }
+
+        let sample_type = match bs.read_ulong(version, TYPE_SIZE)? {
+            1 => SampleType::S8,
+            2 => SampleType::U8,
+            3 | 5 => SampleType::S16,
+            4 | 6 => SampleType::U16,
+            _ => return unsupported_error("shn: unsupported sample type"),
+        };
+
+        let num_channels = bs.read_ulong(version, CHANNELS_SIZE)?;
+
+        if num_channels == 0 || num_channels > MAX_CHANNELS {
+            return unsupported_error("shn: unsupported number of channels");
+        }
+
+        let (block_size, max_lpc_order, nmean) = if version > 0 {
+            let block_size = bs.read_ulong(version, BLOCK_SIZE_SIZE)?;
+
+            if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+                return decode_error("shn: invalid block size");
+            }
+
+            let max_lpc_order = bs.read_ulong(version, LPC_ORDER_SIZE)?;
+
+            if max_lpc_order > MAX_LPC_ORDER {
+                return decode_error("shn: invalid maximum lpc order");
+            }
+
+            let nmean = bs.read_ulong(version, 0)?;
+
+            if nmean > MAX_MEAN_BLOCKS {
+                return decode_error("shn: invalid number of mean blocks");
+            }
+
+            let nskip = bs.read_ulong(version, NSKIP_SIZE)?;
+
+            for _ in 0..nskip {
+                bs.read_bits(8)?;
+            }
+
+            (block_size, max_lpc_order, nmean)
+        }
+        else {
+            (DEFAULT_BLOCK_SIZE, 0, 0)
+        };
+
+        // The stream starts with a verbatim copy of the header of the original file.
+        if bs.read_uvar(FN_SIZE)? != FN_VERBATIM {
+            return decode_error("shn: missing file header");
+        }
+
+        let len = bs.read_uvar(VERBATIM_LEN_SIZE)?;
+
+        if len > MAX_HEADER_LEN {
+            return decode_error("shn: file header is too large");
+        }
+
+        let mut buf = Vec::with_capacity(len as usize);
+
+        for _ in 0..len {
+            buf.push(bs.read_uvar(VERBATIM_BYTE_SIZE)? as u8);
+        }
+
+        let file_info = match FileInfo::read(&buf) {
+            Ok(file_info) => file_info,
+            Err(err) => {
+                warn!("shn: ignoring invalid file header: {}", err);
+                None
+            }
+        };
+
+        Ok(ShnHeader {
+            version,
+            sample_type,
+            num_channels,
+            block_size,
+            nmean,
+            nwrap: max_lpc_order.max(MIN_WRAP),
+            lpc_offset: if version > 1 { 1 << LPC_QUANT } else { 0 },
+            file_info,
+        })
+    }
+
+    /// Get the channels of the stream.
+    pub fn channels(&self) -> Channels {
+        // Channels are stored in the same order as a WAVE file without a channel mask.
+        Channels::from_bits((1 << self.num_channels) - 1).unwrap()
+    }
+}
+
+/// Information from the header of the original file.
+#[derive(Clone, Debug)]
+pub struct FileInfo {
+    pub sample_rate: u32,
+    /// The number of samples per channel, if known.
+    pub n_frames: Option<u64>,
+}
+
+impl FileInfo {
+    /// Read the header of a WAVE or AIFF file. Returns `None` if the format is not recognized.
+    fn read(buf: &[u8]) -> Result<Option<Self>> {
+        let mut reader = BufReader::new(buf);
+
+        match &reader.read_quad_bytes()? {
+            b"RIFF" => Self::read_wave(&mut reader).map(Some),
+            b"FORM" => Self::read_aiff(&mut reader).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn read_wave(reader: &mut BufReader<'_>) -> Result<Self> {
+        reader.read_u32()?;
+
+        if reader.read_quad_bytes()? != *b"WAVE" {
+            return decode_error("shn: invalid wave header");
+        }
+
+        let mut fmt = None;
+
+        // Read chunks until the data chunk, which is the final chunk of the header.
+        loop {
+            let id = reader.read_quad_bytes()?;
+            let len = reader.read_u32()?;
+
+            match &id {
+                b"fmt " => {
+                    if len < 16 {
+                        return decode_error("shn: invalid wave format chunk");
+                    }
+
+                    let _format = reader.read_u16()?;
+                    let _channels = reader.read_u16()?;
+                    let sample_rate = reader.read_u32()?;
+                    let _byte_rate = reader.read_u32()?;
+                    let block_align = reader.read_u16()?;
+
+                    reader.ignore_bytes(u64::from(len) - 14)?;
+
+                    fmt = Some((sample_rate, block_align));
+                }
+                b"data" => {
+                    return match fmt {
+                        Some((sample_rate, block_align)) if sample_rate > 0 => Ok(FileInfo {
+                            sample_rate,
+                            n_frames: (block_align > 0)
+                                .then(|| u64::from(len) / u64::from(block_align)),
+                        }),
+                        _ => decode_error("shn: invalid wave format chunk"),
+                    };
+                }
+                _ => reader.ignore_bytes(u64::from(len + (len & 1)))?,
+            }
+        }
+    }
+
+    fn read_aiff(reader: &mut BufReader<'_>) -> Result<Self> {
+        reader.read_be_u32()?;
+
+        match &reader.read_quad_bytes()? {
+            b"AIFF" | b"AIFC" => (),
+            _ => return decode_error("shn: invalid aiff header"),
+        }
+
+        loop {
+            let id = reader.read_quad_bytes()?;
+            let len = reader.read_be_u32()?;
+
+            if &id == b"COMM" {
+                if len < 18 {
+                    return decode_error("shn: invalid aiff common chunk");
+                }
+
+                let _channels = reader.read_be_u16()?;
+                let n_frames = reader.read_be_u32()?;
+                let _bits_per_sample = reader.read_be_u16()?;
+
+                // The sample rate is an 80-bit extended precision float.
+                let exp = reader.read_be_u16()? & 0x7fff;
+                let mant = reader.read_be_u64()?;
+
+                let shift = 16383 + 63 - i32::from(exp);
+
+                let sample_rate = match shift {
+                    0..=63 => (mant >> shift) as u32,
+                    _ => 0,
+                };
+
+                if sample_rate == 0 {
+                    return decode_error("shn: invalid aiff sample rate");
+                }
+
+                return Ok(FileInfo { sample_rate, n_frames: Some(u64::from(n_frames)) });
+            }
+
+            reader.ignore_bytes(u64::from(len + (len & 1)))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_codes() {
+        // uvar(2) = 5, svar(0) = -1, svar(1) = 3.
+        let mut bs = BitReaderLtr::new(&[0x5d, 0x80]);
+
+        assert_eq!(bs.read_uvar(2).unwrap(), 5);
+        assert_eq!(bs.read_svar(0).unwrap(), -1);
+        assert_eq!(bs.read_svar(1).unwrap(), 3);
+    }
+}
@@ -0,0 +1,467 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, VerificationCheck, CODEC_TYPE_TAK};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::apev2;
+
+use log::{debug, warn};
+
+use super::header::*;
+
+/// The length of an ID3v1 tag.
+const ID3V1_TAG_LEN: u64 = 128;
+
+/// The maximum length of a metadata block that is read into memory.
+const MAX_METADATA_BLOCK_LEN: usize = 1024;
+
+/// When seeking, the length of the byte range below which the stream is searched linearly.
+const LINEAR_SEEK_RANGE: u64 = 64 * 1024;
+
+/// TAK (Tom's lossless Audio Kompressor) format reader.
+///
+/// `TakReader` implements a demuxer for the TAK file format. Each packet contains one frame,
+/// including the frame header.
+///
+/// The stream information metadata block is provided as the codec extra data.
+pub struct TakReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    info: StreamInfo,
+    /// The position of the first frame.
+    first_frame_pos: u64,
+    /// The position of the end of the audio data, if known.
+    data_end: Option<u64>,
+}
+
+impl QueryDescriptor for TakReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("tak", "TAK", &["tak"], &["audio/x-tak"], &[b"tBaK"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Reads the APE tag at the end of the stream, if present. The ID3v1 tag, if present, is skipped.
+/// Returns the position of the end of the audio data.
+fn read_tags(reader: &mut MediaSourceStream, metadata: &mut MetadataLog) -> Result<Option<u64>> {
+    let mut end = match reader.byte_len() {
+        Some(len) if reader.is_seekable() => len,
+        _ => return Ok(None),
+    };
+
+    if end >= ID3V1_TAG_LEN {
+        reader.seek(SeekFrom::Start(end - ID3V1_TAG_LEN))?;
+
+        if reader.read_triple_bytes()? == *b"TAG" {
+            end -= ID3V1_TAG_LEN;
+        }
+    }
+
+    let footer = match apev2::read_ape_tag_footer(reader, end)? {
+        Some(footer) => footer,
+        None => return Ok(Some(end)),
+    };
+
+    let mut builder = MetadataBuilder::new();
+
+    match apev2::read_ape_tag_items(reader, &footer, &mut builder) {
+        Ok(_) => metadata.push(builder.metadata()),
+        Err(err) => warn!("tak: ignoring invalid ape tag: {}", err),
+    }
+
+    // The audio data ends at the tag header, if there is one.
+    Ok(Some(end - footer.tag_len()))
+}
+
+/// Reads a metadata block, excluding its CRC, and verifies the CRC.
+fn read_metadata_block<B: ReadBytes>(reader: &mut B, len: usize) -> Result<Box<[u8]>> {
+    if len <= CRC24_LEN || len > MAX_METADATA_BLOCK_LEN {
+        return decode_error("tak: invalid metadata block length");
+    }
+
+    let buf = reader.read_boxed_slice_exact(len)?;
+
+    if !check_crc24(&buf) {
+        return decode_error("tak: metadata block crc mismatch");
+    }
+
+    Ok(buf[..len - CRC24_LEN].into())
+}
+
+impl TakReader {
+    /// Reads as many bytes as possible into the buffer, and returns the number of bytes read.
+    fn read_partial(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+
+        Ok(read)
+    }
+
+    /// Tries to read a frame header at the given position, which must be the position of a frame
+    /// sync word that was just read. The reader is returned to the given position.
+    fn try_read_frame_header(&mut self, pos: u64) -> Result<Option<FrameHeader>> {
+        let mut buf = [0; MAX_FRAME_HEADER_LEN];
+
+        buf[..2].copy_from_slice(&FRAME_SYNC);
+
+        let len = 2 + self.read_partial(&mut buf[2..])?;
+
+        self.reader.seek_buffered(pos);
+
+        Ok(FrameHeader::read(&buf[..len]).ok())
+    }
+
+    /// Returns true if the reader is at the end of the audio data.
+    fn is_at_end(&self) -> bool {
+        matches!(self.data_end, Some(end) if self.reader.pos() >= end)
+    }
+
+    /// Finds the next frame header at, or after, the current position. The reader is positioned
+    /// at the start of the frame.
+    fn sync_frame(&mut self) -> Result<FrameHeader> {
+        let mut sync = 0u16;
+
+        loop {
+            if self.is_at_end() {
+                return end_of_stream_error();
+            }
+
+            sync = (sync << 8) | u16::from(self.reader.read_byte()?);
+
+            if sync.to_be_bytes() == FRAME_SYNC {
+                let pos = self.reader.pos() - 2;
+
+                if let Some(header) = self.try_read_frame_header(pos)? {
+                    return Ok(header);
+                }
+
+                // Continue after the sync word.
+                self.reader.seek_buffered(pos + 2);
+            }
+        }
+    }
+
+    /// Reads the frame starting at the current position. The frame ends at the header of the
+    /// following frame, or the end of the audio data.
+    fn read_frame(&mut self, header: &FrameHeader) -> Result<Vec<u8>> {
+        let mut buf = self.reader.read_boxed_slice_exact(header.len)?.into_vec();
+
+        // The final frame extends to the end of the audio data.
+        let is_last = header.last_frame_samples.is_some();
+
+        let mut sync = 0u16;
+
+        loop {
+            if self.is_at_end() {
+                break;
+            }
+
+            let byte = match self.reader.read_byte() {
+                Ok(byte) => byte,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(Error::IoError(err)),
+            };
+
+            buf.push(byte);
+
+            sync = (sync << 8) | u16::from(byte);
+
+            if !is_last && sync.to_be_bytes() == FRAME_SYNC {
+                let pos = self.reader.pos() - 2;
+
+                // The frame number of the following frame is usually one greater, but frames may
+                // be missing if the stream is damaged.
+                match self.try_read_frame_header(pos)? {
+                    Some(next) if next.frame_num > header.frame_num => {
+                        buf.truncate(buf.len() - 2);
+                        break;
+                    }
+                    _ => self.reader.seek_buffered(pos + 2),
+                };
+            }
+        }
+
+        Ok(buf)
+    }
+
+    /// Get the timestamp and duration of a frame.
+    fn frame_timing(&self, header: &FrameHeader) -> (u64, u64) {
+        let frame_samples = u64::from(self.info.frame_samples);
+
+        let ts = u64::from(header.frame_num) * frame_samples;
+
+        let dur = match header.last_frame_samples {
+            Some(samples) => u64::from(samples),
+            None => frame_samples,
+        };
+
+        match self.info.n_samples {
+            Some(n_samples) => (ts, dur.min(n_samples.saturating_sub(ts))),
+            None => (ts, dur),
+        }
+    }
+}
+
+impl FormatReader for TakReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        if source.read_quad_bytes()? != TAK_STREAM_MARKER {
+            return unsupported_error("tak: missing tak stream marker");
+        }
+
+        let mut info = None;
+        let mut extra_data = None;
+        let mut md5 = None;
+        let mut last_frame = None;
+
+        // Read metadata blocks until the end block.
+        loop {
+            let block_type = source.read_u8()? & 0x7f;
+            let len = source.read_u24()? as usize;
+
+            match block_type {
+                METADATA_END => break,
+                METADATA_STREAMINFO => {
+                    let buf = read_metadata_block(&mut source, len)?;
+
+                    info = Some(StreamInfo::read(&mut BitReaderRtl::new(&buf))?);
+                    extra_data = Some(buf);
+                }
+                METADATA_LAST_FRAME => {
+                    let buf = read_metadata_block(&mut source, len)?;
+
+                    let mut bs = BitReaderRtl::new(&buf);
+
+                    // The position of the final frame, relative to the first frame, and its
+                    // length.
+                    let pos = bs.read_bits_leq64(40)?;
+                    let len = bs.read_bits_leq32(24)?;
+
+                    last_frame = Some((pos, u64::from(len)));
+                }
+                METADATA_ENCODER => {
+                    let buf = read_metadata_block(&mut source, len)?;
+
+                    let version = BitReaderRtl::new(&buf).read_bits_leq32(24)?;
+
+                    debug!("tak: encoder version {:06x}", version);
+                }
+                METADATA_MD5 => {
+                    let buf = read_metadata_block(&mut source, len)?;
+
+                    if buf.len() == 16 {
+                        let mut value = [0; 16];
+                        value.copy_from_slice(&buf);
+                        md5 = Some(value);
+                    }
+                }
+                METADATA_SEEKTABLE => {
+                    // Seeking is performed by searching for frame headers instead.
+                    debug!("tak: ignoring seek table");
+                    source.ignore_bytes(len as u64)?;
+                }
+                _ => source.ignore_bytes(len as u64)?,
+            }
+        }
+
+        let first_frame_pos = source.pos();
+
+        let mut metadata = MetadataLog::default();
+
+        // Read the tags at the end of the stream, and then return to the first frame.
+        let stream_end = read_tags(&mut source, &mut metadata)?;
+
+        if source.is_seekable() {
+            source.seek(SeekFrom::Start(first_frame_pos))?;
+        }
+
+        let data_end = match last_frame {
+            Some((pos, len)) => Some(first_frame_pos + pos + len),
+            None => stream_end,
+        };
+
+        let mut reader = TakReader {
+            reader: source,
+            tracks: Vec::new(),
+            cues: Vec::new(),
+            metadata,
+            info: match info {
+                Some(info) => info,
+                None => return decode_error("tak: missing stream information"),
+            },
+            first_frame_pos,
+            data_end,
+        };
+
+        debug!("tak: {:?}", reader.info);
+
+        let info = &reader.info;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_TAK)
+            .with_sample_rate(info.sample_rate)
+            .with_time_base(TimeBase::new(1, info.sample_rate))
+            .with_bits_per_sample(info.bits_per_sample)
+            .with_bits_per_coded_sample(info.bits_per_sample)
+            .with_channels(info.channels())
+            .with_max_frames_per_packet(u64::from(info.frame_samples));
+
+        if let Some(n_samples) = info.n_samples {
+            params.with_n_frames(n_samples);
+        }
+
+        if let Some(md5) = md5 {
+            params.with_verification_code(VerificationCheck::Md5(md5));
+        }
+
+        if let Some(extra_data) = extra_data {
+            params.with_extra_data(extra_data);
+        }
+
+        reader.tracks.push(Track::new(0, params));
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let header = self.sync_frame()?;
+
+        let (ts, dur) = self.frame_timing(&header);
+
+        let buf = self.read_frame(&header)?;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        // Get the timestamp of the desired audio frame.
+        let ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // If the reader supports seeking, coarsely seek to the nearest frame with a timestamp
+        // lower than the desired timestamp using a binary search.
+        if self.reader.is_seekable() {
+            let mut start = self.first_frame_pos;
+
+            let mut end = match self.data_end {
+                Some(end) => end,
+                None => self.reader.seek(SeekFrom::End(0))?,
+            };
+
+            while end - start > LINEAR_SEEK_RANGE {
+                let mid = (start + end) / 2;
+
+                self.reader.seek(SeekFrom::Start(mid))?;
+
+                let header = match self.sync_frame() {
+                    Ok(header) => header,
+                    Err(Error::IoError(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                        end = mid;
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+
+                let (frame_ts, _) = self.frame_timing(&header);
+
+                if ts < frame_ts {
+                    end = mid;
+                }
+                else {
+                    start = mid;
+                }
+            }
+
+            self.reader.seek(SeekFrom::Start(start))?;
+        }
+
+        // Linearly search the stream frame-by-frame for the frame that contains the desired
+        // timestamp.
+        let actual_ts = loop {
+            let header = self.sync_frame()?;
+
+            let (frame_ts, dur) = self.frame_timing(&header);
+
+            if ts < frame_ts {
+                // Attempted to seek backwards on an unseekable stream.
+                if !self.reader.is_seekable() {
+                    return seek_error(SeekErrorKind::ForwardOnly);
+                }
+                break frame_ts;
+            }
+            else if ts < frame_ts + dur || header.last_frame_samples.is_some() {
+                break frame_ts;
+            }
+
+            self.read_frame(&header)?;
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, actual_ts as i64 - ts as i64);
+
+        Ok(SeekedTo { track_id: 0, required_ts: ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
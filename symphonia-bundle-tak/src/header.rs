@@ -0,0 +1,244 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::checksum::{Crc, CrcParams};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BitReaderRtl, FiniteBitStream, Monitor, ReadBitsRtl};
+
+/// The TAK stream marker: "tBaK" in ASCII.
+pub const TAK_STREAM_MARKER: [u8; 4] = *b"tBaK";
+
+/// The frame header sync word, as stored in the stream.
+pub const FRAME_SYNC: [u8; 2] = [0xff, 0xa0];
+
+/// The maximum length of a frame header, including the header CRC.
+pub const MAX_FRAME_HEADER_LEN: usize = 40;
+
+/// The length of a CRC24.
+pub const CRC24_LEN: usize = 3;
+
+// Metadata block types.
+pub const METADATA_END: u8 = 0;
+pub const METADATA_STREAMINFO: u8 = 1;
+pub const METADATA_SEEKTABLE: u8 = 2;
+pub const METADATA_ENCODER: u8 = 4;
+pub const METADATA_MD5: u8 = 6;
+pub const METADATA_LAST_FRAME: u8 = 7;
+
+// Frame header flags.
+const FRAME_FLAG_IS_LAST: u32 = 0x1;
+const FRAME_FLAG_HAS_INFO: u32 = 0x2;
+const FRAME_FLAG_HAS_METADATA: u32 = 0x4;
+
+/// The minimum sample rate.
+const MIN_SAMPLE_RATE: u32 = 6000;
+
+/// The minimum bits per sample.
+const MIN_BITS_PER_SAMPLE: u32 = 8;
+
+/// The maximum bits per sample.
+const MAX_BITS_PER_SAMPLE: u32 = 24;
+
+/// The maximum number of channels.
+const MAX_CHANNELS: u32 = 16;
+
+/// The maximum number of samples per channel in a frame.
+const MAX_FRAME_SAMPLES: u32 = 16384;
+
+/// Frame durations indexed by the frame size type. The first 4 are in units of 1/32 of a second,
+/// and the remainder are in samples.
+const FRAME_DURATIONS: [u32; 10] = [3, 4, 6, 8, 4096, 8192, 16384, 512, 1024, 2048];
+
+/// The number of frame size types measured in units of 1/32 of a second.
+const NUM_TIMED_FRAME_SIZES: usize = 4;
+
+/// Calculate the TAK CRC24 (CRC-24/OPENPGP) of the buffer.
+pub fn crc24(buf: &[u8]) -> u32 {
+    let params = CrcParams { width: 24, poly: 0x864cfb, reflected: false };
+
+    let mut crc = Crc::new(params, 0xb704ce);
+    crc.process_buf_bytes(buf);
+    crc.crc()
+}
+
+/// Verify the CRC24 that follows the data in the buffer.
+pub fn check_crc24(buf: &[u8]) -> bool {
+    if buf.len() < CRC24_LEN {
+        return false;
+    }
+
+    let (data, crc) = buf.split_at(buf.len() - CRC24_LEN);
+
+    crc24(data) == u32::from_le_bytes([crc[0], crc[1], crc[2], 0])
+}
+
+/// The stream information block.
+#[derive(Clone, Debug)]
+pub struct StreamInfo {
+    /// The total number of samples per channel, if known.
+    pub n_samples: Option<u64>,
+    pub sample_rate: u32,
+    pub bits_per_sample: u32,
+    pub num_channels: u32,
+    /// The channel layout, if stated.
+    pub channels: Option<Channels>,
+    /// The number of samples per channel in a frame, excluding the final frame.
+    pub frame_samples: u32,
+}
+
+impl StreamInfo {
+    /// Read the stream information from the metadata block, or a frame header.
+    pub fn read(bs: &mut BitReaderRtl<'_>) -> Result<Self> {
+        let _codec = bs.read_bits_leq32(6)?;
+        let _profile = bs.read_bits_leq32(4)?;
+
+        let frame_size_type = bs.read_bits_leq32(4)? as usize;
+        let n_samples = bs.read_bits_leq64(35)?;
+
+        let data_type = bs.read_bits_leq32(3)?;
+
+        let sample_rate = bs.read_bits_leq32(18)? + MIN_SAMPLE_RATE;
+        let bits_per_sample = bs.read_bits_leq32(5)? + MIN_BITS_PER_SAMPLE;
+        let num_channels = bs.read_bits_leq32(4)? + 1;
+
+        let channels = if bs.read_bool()? {
+            let _valid_bits = bs.read_bits_leq32(5)?;
+
+            let mut channels = Channels::empty();
+
+            // Each speaker position is the index of the corresponding bit of a WAVE channel
+            // mask, starting at 1.
+            for _ in 0..num_channels {
+                if let pos @ 1..=18 = bs.read_bits_leq32(6)? {
+                    channels |= Channels::from_bits_truncate(1 << (pos - 1));
+                }
+            }
+
+            (channels.count() == num_channels as usize).then(|| channels)
+        }
+        else {
+            None
+        };
+
+        // Only integer PCM is supported.
+        if data_type != 0 {
+            return unsupported_error("tak: unsupported sample data type");
+        }
+
+        if bits_per_sample > MAX_BITS_PER_SAMPLE {
+            return unsupported_error("tak: unsupported bits per sample");
+        }
+
+        if num_channels > MAX_CHANNELS {
+            return unsupported_error("tak: unsupported number of channels");
+        }
+
+        // The number of samples in a frame is either a fixed duration, or a fixed number of
+        // samples. In either case, a frame may not exceed a quarter second, nor 16384 samples.
+        let quarter_second = (sample_rate * FRAME_DURATIONS[3]) >> 5;
+
+        let (frame_samples, max_frame_samples) = match frame_size_type {
+            i if i < NUM_TIMED_FRAME_SIZES => {
+                ((sample_rate * FRAME_DURATIONS[i]) >> 5, MAX_FRAME_SAMPLES)
+            }
+            i if i < FRAME_DURATIONS.len() => (FRAME_DURATIONS[i], quarter_second),
+            _ => return decode_error("tak: invalid frame size type"),
+        };
+
+        if frame_samples == 0 || frame_samples > max_frame_samples {
+            return decode_error("tak: invalid frame size");
+        }
+
+        Ok(StreamInfo {
+            n_samples: (n_samples > 0).then(|| n_samples),
+            sample_rate,
+            bits_per_sample,
+            num_channels,
+            channels,
+            frame_samples,
+        })
+    }
+
+    /// Get the channels of the stream. If a channel layout was not stated, the channels are
+    /// assumed to be in the same order as a WAVE file without a channel mask.
+    pub fn channels(&self) -> Channels {
+        match self.channels {
+            Some(channels) => channels,
+            None => Channels::from_bits_truncate((1 << self.num_channels) - 1),
+        }
+    }
+}
+
+/// A frame header.
+#[derive(Clone, Debug)]
+pub struct FrameHeader {
+    /// The index of the frame.
+    pub frame_num: u32,
+    /// If this is the final frame, the number of samples per channel in it.
+    pub last_frame_samples: Option<u32>,
+    /// The length of the frame header, including the header CRC.
+    pub len: usize,
+}
+
+impl FrameHeader {
+    /// Read the frame header at the start of the buffer, and verify its CRC. The buffer may be
+    /// longer than the frame header.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let mut bs = BitReaderRtl::new(buf);
+
+        if bs.read_bits_leq32(16)? != u32::from(u16::from_le_bytes(FRAME_SYNC)) {
+            return decode_error("tak: missing frame sync");
+        }
+
+        let flags = bs.read_bits_leq32(3)?;
+        let frame_num = bs.read_bits_leq32(21)?;
+
+        let last_frame_samples = if flags & FRAME_FLAG_IS_LAST != 0 {
+            let samples = bs.read_bits_leq32(14)? + 1;
+            bs.ignore_bits(2)?;
+            Some(samples)
+        }
+        else {
+            None
+        };
+
+        // The stream information may be repeated in the frame header.
+        if flags & FRAME_FLAG_HAS_INFO != 0 {
+            StreamInfo::read(&mut bs)?;
+
+            if bs.read_bits_leq32(6)? != 0 {
+                bs.ignore_bits(25)?;
+            }
+
+            bs.realign();
+        }
+
+        if flags & FRAME_FLAG_HAS_METADATA != 0 {
+            return unsupported_error("tak: frame metadata is not supported");
+        }
+
+        let len = buf.len() - (bs.bits_left() / 8) as usize + CRC24_LEN;
+
+        if len > buf.len() || !check_crc24(&buf[..len]) {
+            return decode_error("tak: frame header crc mismatch");
+        }
+
+        Ok(FrameHeader { frame_num, last_frame_samples, len })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_crc24() {
+        // The CRC24 of "123456789".
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+    }
+}
@@ -0,0 +1,323 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_TTA};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderRtl, ReadBitsRtl};
+use symphonia_core::support_codec;
+
+use super::header::{tta_crc32, TtaHeader};
+
+/// The maximum Rice parameter.
+const MAX_RICE_PARAM: u32 = 25;
+
+/// Returns `1 << k`, saturating to `1 << 31`.
+#[inline(always)]
+fn shift_1(k: u32) -> u32 {
+    1 << k.min(31)
+}
+
+/// Returns `1 << (k + 4)`, saturating to `1 << 31`.
+#[inline(always)]
+fn shift_16(k: u32) -> u32 {
+    shift_1(k + 4)
+}
+
+/// The adaptive Rice coding state of a channel.
+struct Rice {
+    k0: u32,
+    k1: u32,
+    sum0: u32,
+    sum1: u32,
+}
+
+impl Default for Rice {
+    fn default() -> Self {
+        Rice { k0: 10, k1: 10, sum0: shift_16(10), sum1: shift_16(10) }
+    }
+}
+
+impl Rice {
+    /// Decode one residual.
+    fn decode(&mut self, bs: &mut BitReaderRtl<'_>) -> Result<i32> {
+        let mut unary = bs.read_unary_ones()?;
+
+        // A non-zero unary prefix selects the second Rice parameter.
+        let is_depth1 = unary > 0;
+
+        let k = if is_depth1 {
+            unary -= 1;
+            self.k1
+        }
+        else {
+            self.k0
+        };
+
+        if k > MAX_RICE_PARAM {
+            return decode_error("tta: invalid rice parameter");
+        }
+
+        let mut value =
+            if k > 0 { (unary << k).wrapping_add(bs.read_bits_leq32(k)?) } else { unary };
+
+        if is_depth1 {
+            self.sum1 = self.sum1.wrapping_add(value.wrapping_sub(self.sum1 >> 4));
+
+            if self.k1 > 0 && self.sum1 < shift_16(self.k1) {
+                self.k1 -= 1;
+            }
+            else if self.sum1 > shift_16(self.k1 + 1) {
+                self.k1 += 1;
+            }
+
+            value = value.wrapping_add(shift_1(self.k0));
+        }
+
+        self.sum0 = self.sum0.wrapping_add(value.wrapping_sub(self.sum0 >> 4));
+
+        if self.k0 > 0 && self.sum0 < shift_16(self.k0) {
+            self.k0 -= 1;
+        }
+        else if self.sum0 > shift_16(self.k0 + 1) {
+            self.k0 += 1;
+        }
+
+        // Convert to a signed value.
+        Ok(((value >> 1) ^ (value & 1).wrapping_sub(1)).wrapping_add(1) as i32)
+    }
+}
+
+/// The adaptive hybrid filter of a channel.
+struct Filter {
+    shift: u32,
+    round: i32,
+    error: i32,
+    qm: [i32; 8],
+    dx: [i32; 8],
+    dl: [i32; 8],
+}
+
+impl Filter {
+    fn new(shift: u32) -> Self {
+        Filter { shift, round: 1 << (shift - 1), error: 0, qm: [0; 8], dx: [0; 8], dl: [0; 8] }
+    }
+
+    fn apply(&mut self, input: i32) -> i32 {
+        // Adapt the coefficients in the direction of the previous error.
+        if self.error < 0 {
+            for (qm, &dx) in self.qm.iter_mut().zip(self.dx.iter()) {
+                *qm = qm.wrapping_sub(dx);
+            }
+        }
+        else if self.error > 0 {
+            for (qm, &dx) in self.qm.iter_mut().zip(self.dx.iter()) {
+                *qm = qm.wrapping_add(dx);
+            }
+        }
+
+        let sum = self
+            .qm
+            .iter()
+            .zip(self.dl.iter())
+            .fold(self.round, |sum, (&qm, &dl)| sum.wrapping_add(qm.wrapping_mul(dl)));
+
+        let dx = &mut self.dx;
+        let dl = &mut self.dl;
+
+        dx.copy_within(1..5, 0);
+        dl.copy_within(1..5, 0);
+
+        dx[4] = (dl[4] >> 30) | 1;
+        dx[5] = ((dl[5] >> 30) | 2) & !1;
+        dx[6] = ((dl[6] >> 30) | 2) & !1;
+        dx[7] = ((dl[7] >> 30) | 4) & !3;
+
+        self.error = input;
+
+        let output = input.wrapping_add(sum >> self.shift);
+
+        dl[4] = dl[5].wrapping_neg();
+        dl[5] = dl[6].wrapping_neg();
+        dl[6] = output.wrapping_sub(dl[7]);
+        dl[7] = output;
+        dl[5] = dl[5].wrapping_add(dl[6]);
+        dl[4] = dl[4].wrapping_add(dl[5]);
+
+        output
+    }
+}
+
+/// The decoding state of a channel.
+struct ChannelState {
+    rice: Rice,
+    filter: Filter,
+    prev: i32,
+}
+
+/// True Audio (TTA) decoder.
+pub struct TtaDecoder {
+    params: CodecParameters,
+    bits_per_sample: u32,
+    /// The shift of the hybrid filter.
+    filter_shift: u32,
+    /// The shift of the fixed first-order predictor.
+    pred_shift: u32,
+    /// The decoded samples, interleaved.
+    samples: Vec<i32>,
+    buf: AudioBuffer<i32>,
+}
+
+impl TtaDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let n_frames = packet.dur as usize;
+
+        if n_frames > self.buf.capacity() {
+            return decode_error("tta: frame exceeds the maximum number of samples");
+        }
+
+        let buf = packet.buf();
+
+        // The frame is followed by a CRC of the frame.
+        if buf.len() < 4 {
+            return decode_error("tta: frame is too short");
+        }
+
+        let (data, crc) = buf.split_at(buf.len() - 4);
+
+        if tta_crc32(data) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return decode_error("tta: computed frame CRC does not match expected CRC");
+        }
+
+        let num_channels = self.buf.spec().channels.count();
+
+        // Each frame is coded independently.
+        let mut states: Vec<ChannelState> = (0..num_channels)
+            .map(|_| ChannelState {
+                rice: Default::default(),
+                filter: Filter::new(self.filter_shift),
+                prev: 0,
+            })
+            .collect();
+
+        let mut bs = BitReaderRtl::new(data);
+
+        let samples = &mut self.samples[..n_frames * num_channels];
+
+        for block in samples.chunks_exact_mut(num_channels) {
+            for (sample, state) in block.iter_mut().zip(states.iter_mut()) {
+                let residual = state.rice.decode(&mut bs)?;
+
+                let filtered = state.filter.apply(residual);
+
+                // Fixed first-order prediction.
+                let pred =
+                    (i64::from(state.prev) * ((1 << self.pred_shift) - 1)) >> self.pred_shift;
+
+                *sample = filtered.wrapping_add(pred as i32);
+
+                state.prev = *sample;
+            }
+
+            // Undo the inter-channel decorrelation. Every channel, except the last, is coded as the
+            // difference of the following channel and itself. The last channel is offset by half
+            // of the preceding difference.
+            if num_channels > 1 {
+                let last = num_channels - 1;
+
+                block[last] = block[last].wrapping_add(block[last - 1] / 2);
+
+                for i in (0..last).rev() {
+                    block[i] = block[i + 1].wrapping_sub(block[i]);
+                }
+            }
+        }
+
+        // Scale the samples to 32-bits.
+        let shift = 32 - self.bits_per_sample;
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(n_frames));
+
+        for ch in 0..num_channels {
+            let out = self.buf.chan_mut(ch);
+
+            for (out, block) in out.iter_mut().zip(samples.chunks_exact(num_channels)) {
+                *out = block[ch] << shift;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for TtaDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports TTA.
+        if params.codec != CODEC_TYPE_TTA {
+            return unsupported_error("tta: invalid codec type");
+        }
+
+        // The extra data is the stream header.
+        let header = match params.extra_data.as_ref() {
+            Some(buf) => TtaHeader::read(buf)?,
+            _ => return unsupported_error("tta: missing extra data"),
+        };
+
+        let max_frames = u64::from(header.frame_len());
+
+        // The filter and predictor shifts depend on the number of bytes per sample.
+        let (filter_shift, pred_shift) = match (header.bits_per_sample + 7) / 8 {
+            1 => (10, 4),
+            2 => (9, 5),
+            _ => (10, 5),
+        };
+
+        let spec = SignalSpec::new(header.sample_rate, header.channels);
+
+        Ok(TtaDecoder {
+            params: params.clone(),
+            bits_per_sample: header.bits_per_sample,
+            filter_shift,
+            pred_shift,
+            samples: vec![0; max_frames as usize * header.channels.count()],
+            buf: AudioBuffer::new(max_frames, spec),
+        })
+    }
+
+    fn reset(&mut self) {
+        // Nothing to do. Each frame is decoded independently.
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_TTA, "tta", "True Audio")]
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
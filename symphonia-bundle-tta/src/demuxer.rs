@@ -0,0 +1,233 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_TTA};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+use super::header::{tta_crc32, TtaHeader, TTA_HEADER_LEN};
+
+/// The maximum number of frames.
+const MAX_FRAMES: u32 = 1 << 24;
+
+/// The maximum length of a frame.
+const MAX_FRAME_LEN: u64 = 64 * 1024 * 1024;
+
+/// The location of a frame in the stream.
+struct FrameInfo {
+    pos: u64,
+    len: u32,
+}
+
+/// True Audio (TTA) format reader.
+///
+/// `TtaReader` implements a demuxer for the TTA1 file format. Each packet contains one frame,
+/// including the frame CRC.
+pub struct TtaReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    frames: Vec<FrameInfo>,
+    frame_len: u32,
+    last_frame_len: u32,
+    next_frame: usize,
+}
+
+impl QueryDescriptor for TtaReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("tta", "True Audio", &["tta"], &["audio/tta", "audio/x-tta"], &[b"TTA1"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl TtaReader {
+    /// Positions the reader at the given position in the stream.
+    fn seek_to(&mut self, pos: u64) -> Result<()> {
+        let cur_pos = self.reader.pos();
+
+        if cur_pos != pos {
+            if self.reader.is_seekable() {
+                self.reader.seek(SeekFrom::Start(pos))?;
+            }
+            else if pos > cur_pos {
+                self.reader.ignore_bytes(pos - cur_pos)?;
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FormatReader for TtaReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut buf = [0; TTA_HEADER_LEN];
+        source.read_buf_exact(&mut buf)?;
+
+        let header = TtaHeader::read(&buf)?;
+
+        if header.n_samples == 0 {
+            return decode_error("tta: stream is empty");
+        }
+
+        // The seek table contains the length of each frame, followed by a CRC of the table.
+        let n_frames = header.n_frames();
+
+        if n_frames > MAX_FRAMES {
+            return unsupported_error("tta: too many frames");
+        }
+
+        let mut table = vec![0; 4 * n_frames as usize];
+        source.read_buf_exact(&mut table)?;
+
+        if tta_crc32(&table) != source.read_u32()? {
+            return decode_error("tta: computed seek table CRC does not match expected CRC");
+        }
+
+        // The first frame immediately follows the seek table.
+        let mut pos = source.pos();
+
+        let frames = table
+            .chunks_exact(4)
+            .map(|len| {
+                let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]);
+                let frame = FrameInfo { pos, len };
+                pos += u64::from(len);
+                frame
+            })
+            .collect();
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_TTA)
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_n_frames(u64::from(header.n_samples))
+            .with_max_frames_per_packet(u64::from(header.frame_len()))
+            .with_bits_per_sample(header.bits_per_sample)
+            .with_channels(header.channels)
+            .with_extra_data(Box::new(buf));
+
+        Ok(TtaReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            frames,
+            frame_len: header.frame_len(),
+            last_frame_len: header.last_frame_len(),
+            next_frame: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let frame = match self.frames.get(self.next_frame) {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        let (pos, len) = (frame.pos, u64::from(frame.len));
+
+        if len > MAX_FRAME_LEN {
+            return decode_error("tta: frame is too large");
+        }
+
+        // Frames are contiguous, so the reader should already be positioned at the frame.
+        self.seek_to(pos)?;
+
+        let buf = self.reader.read_boxed_slice_exact(len as usize)?;
+
+        let ts = self.next_frame as u64 * u64::from(self.frame_len);
+
+        let dur = if self.next_frame + 1 == self.frames.len() {
+            self.last_frame_len
+        }
+        else {
+            self.frame_len
+        };
+
+        self.next_frame += 1;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, u64::from(dur), buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // All frames, except the last, have the same duration. Therefore, the frame containing
+        // the desired timestamp can be found directly.
+        let frame_idx =
+            ((required_ts / u64::from(self.frame_len)) as usize).min(self.frames.len() - 1);
+
+        self.seek_to(self.frames[frame_idx].pos)?;
+
+        self.next_frame = frame_idx;
+
+        let actual_ts = frame_idx as u64 * u64::from(self.frame_len);
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts - actual_ts);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,120 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::checksum::Crc32Le;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, Monitor, ReadBytes};
+
+/// The TTA1 stream marker: "TTA1" in ASCII.
+pub const TTA_STREAM_MARKER: [u8; 4] = *b"TTA1";
+
+/// The length of the stream header, including the stream marker and CRC.
+pub const TTA_HEADER_LEN: usize = 22;
+
+/// The audio data is stored as integer PCM.
+const FORMAT_SIMPLE: u16 = 1;
+/// The audio data is encrypted with a password.
+const FORMAT_ENCRYPTED: u16 = 2;
+
+/// The maximum supported sample rate. This bounds the number of samples in a frame.
+const MAX_SAMPLE_RATE: u32 = 1_000_000;
+
+/// Calculates the CRC-32 of a buffer in the form stored in a TTA stream.
+pub fn tta_crc32(buf: &[u8]) -> u32 {
+    let mut crc32 = Crc32Le::new(0xffff_ffff);
+    crc32.process_buf_bytes(buf);
+    !crc32.crc()
+}
+
+/// The TTA1 stream header.
+///
+/// The stream header is also used as the codec extra data.
+#[derive(Clone, Debug)]
+pub struct TtaHeader {
+    pub channels: Channels,
+    pub bits_per_sample: u32,
+    pub sample_rate: u32,
+    /// The total number of samples per channel.
+    pub n_samples: u32,
+}
+
+impl TtaHeader {
+    /// Read and verify the stream header from a buffer of `TTA_HEADER_LEN` bytes, including the
+    /// stream marker.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        if buf.len() != TTA_HEADER_LEN {
+            return decode_error("tta: invalid stream header length");
+        }
+
+        let mut reader = BufReader::new(buf);
+
+        if reader.read_quad_bytes()? != TTA_STREAM_MARKER {
+            return unsupported_error("tta: missing tta1 stream marker");
+        }
+
+        let format = reader.read_u16()?;
+        let num_channels = reader.read_u16()?;
+        let bits_per_sample = u32::from(reader.read_u16()?);
+        let sample_rate = reader.read_u32()?;
+        let n_samples = reader.read_u32()?;
+        let crc = reader.read_u32()?;
+
+        if tta_crc32(&buf[..TTA_HEADER_LEN - 4]) != crc {
+            return decode_error("tta: computed stream header CRC does not match expected CRC");
+        }
+
+        match format {
+            FORMAT_SIMPLE => (),
+            FORMAT_ENCRYPTED => {
+                return unsupported_error("tta: encrypted streams are not supported")
+            }
+            _ => return unsupported_error("tta: unsupported format"),
+        }
+
+        // Channels are stored in the same order as a WAVE file without a channel mask.
+        let channels = match num_channels {
+            1..=32 => Channels::from_bits(((1u64 << num_channels) - 1) as u32),
+            _ => None,
+        };
+
+        let channels = match channels {
+            Some(channels) => channels,
+            None => return unsupported_error("tta: unsupported number of channels"),
+        };
+
+        if bits_per_sample < 8 || bits_per_sample > 24 {
+            return unsupported_error("tta: unsupported bits per sample");
+        }
+
+        if sample_rate == 0 || sample_rate > MAX_SAMPLE_RATE {
+            return unsupported_error("tta: unsupported sample rate");
+        }
+
+        Ok(TtaHeader { channels, bits_per_sample, sample_rate, n_samples })
+    }
+
+    /// The number of samples per channel in every frame, except the last.
+    pub fn frame_len(&self) -> u32 {
+        // Frames are 256/245 seconds long.
+        256 * self.sample_rate / 245
+    }
+
+    /// The number of samples per channel in the last frame.
+    pub fn last_frame_len(&self) -> u32 {
+        match self.n_samples % self.frame_len() {
+            0 => self.frame_len(),
+            len => len,
+        }
+    }
+
+    /// The total number of frames.
+    pub fn n_frames(&self) -> u32 {
+        let frame_len = self.frame_len();
+        self.n_samples / frame_len + u32::from(self.n_samples % frame_len != 0)
+    }
+}
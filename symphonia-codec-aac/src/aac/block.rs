@@ -0,0 +1,123 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::io::{BitReaderLtr, FiniteBitStream, ReadBitsLtr};
+
+use crate::aac::common::GASubbandInfo;
+use crate::aac::cpe::ChannelPair;
+use crate::aac::layout::ElementMap;
+use crate::common::M4AType;
+
+/// The bit ranges of a syntactic element in a raw data block.
+pub(crate) enum ElementRange {
+    /// A single channel, or low frequency effects, element.
+    Single { start: u64, end: u64 },
+    /// A channel pair element. The individual channel stream of the second channel starts at
+    /// `mid`.
+    Pair { start: u64, mid: u64, end: u64 },
+    /// A data stream, or program config, element.
+    Other { start: u64, end: u64 },
+}
+
+/// A parser for raw data blocks that finds the boundaries of the syntactic elements within them
+/// without decoding any audio.
+pub(crate) struct RawDataBlockParser {
+    m4atype: M4AType,
+    pair: ChannelPair,
+}
+
+impl RawDataBlockParser {
+    pub(crate) fn new(m4atype: M4AType, sample_rate: u32) -> Self {
+        RawDataBlockParser {
+            m4atype,
+            pair: ChannelPair::new(true, 0, GASubbandInfo::find(sample_rate)),
+        }
+    }
+
+    /// Parses a raw data block at the start of the buffer, calling `on_element` with the bit range
+    /// of each single channel, channel pair, data stream, and program config element. The range of
+    /// each element starts after its element ID. Returns the length of the raw data block in bits,
+    /// including the terminating element.
+    pub(crate) fn parse<F>(&mut self, buf: &[u8], mut on_element: F) -> Result<u64>
+    where
+        F: FnMut(ElementRange),
+    {
+        let mut bs = BitReaderLtr::new(buf);
+
+        let pos = |bs: &BitReaderLtr<'_>| 8 * buf.len() as u64 - bs.bits_left();
+
+        while bs.bits_left() > 3 {
+            let id = bs.read_bits_leq32(3)?;
+
+            let start = pos(&bs);
+
+            match id {
+                0 | 3 => {
+                    // ID_SCE, ID_LFE
+                    let _tag = bs.read_bits_leq32(4)?;
+                    self.pair.decode_ga_sce(&mut bs, self.m4atype)?;
+
+                    on_element(ElementRange::Single { start, end: pos(&bs) });
+                }
+                1 => {
+                    // ID_CPE
+                    let _tag = bs.read_bits_leq32(4)?;
+                    let common_window = self.pair.decode_ga_cpe_common(&mut bs)?;
+                    self.pair.decode_ga_cpe_channel(&mut bs, 0, self.m4atype, common_window)?;
+
+                    let mid = pos(&bs);
+
+                    self.pair.decode_ga_cpe_channel(&mut bs, 1, self.m4atype, common_window)?;
+
+                    on_element(ElementRange::Pair { start, mid, end: pos(&bs) });
+                }
+                2 => {
+                    // ID_CCE
+                    return unsupported_error("aac: coupling channel element");
+                }
+                4 => {
+                    // ID_DSE
+                    let _id = bs.read_bits_leq32(4)?;
+                    let align = bs.read_bool()?;
+                    let mut count = bs.read_bits_leq32(8)?;
+                    if count == 255 {
+                        count += bs.read_bits_leq32(8)?;
+                    }
+                    if align {
+                        bs.realign();
+                    }
+                    bs.ignore_bits(count * 8)?;
+
+                    on_element(ElementRange::Other { start, end: pos(&bs) });
+                }
+                5 => {
+                    // ID_PCE
+                    ElementMap::read_program_config(&mut bs)?;
+
+                    on_element(ElementRange::Other { start, end: pos(&bs) });
+                }
+                6 => {
+                    // ID_FIL
+                    let mut count = bs.read_bits_leq32(4)?;
+                    if count == 15 {
+                        count += bs.read_bits_leq32(8)?;
+                        count -= 1;
+                    }
+                    bs.ignore_bits(8 * count)?;
+                }
+                7 => {
+                    // ID_TERM
+                    break;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(pos(&bs))
+    }
+}
@@ -55,6 +55,19 @@ impl ChannelPair {
     }
 
     pub fn decode_ga_cpe<B: ReadBitsLtr>(&mut self, bs: &mut B, m4atype: M4AType) -> Result<()> {
+        let common_window = self.decode_ga_cpe_common(bs)?;
+
+        self.decode_ga_cpe_channel(bs, 0, m4atype, common_window)?;
+        self.decode_ga_cpe_channel(bs, 1, m4atype, common_window)?;
+
+        self.decode_joint_stereo(common_window);
+
+        Ok(())
+    }
+
+    /// Decodes the common ICS info and mid-side mask of a channel pair element, and returns if a
+    /// common window is used.
+    pub fn decode_ga_cpe_common<B: ReadBitsLtr>(&mut self, bs: &mut B) -> Result<bool> {
         let common_window = bs.read_bool()?;
 
         if common_window {
@@ -93,9 +106,22 @@ impl ChannelPair {
             self.ics1.info.copy_from_common(&self.ics0.info);
         }
 
-        self.ics0.decode(bs, &mut self.lcg, m4atype, common_window)?;
-        self.ics1.decode(bs, &mut self.lcg, m4atype, common_window)?;
+        Ok(common_window)
+    }
+
+    /// Decodes the individual channel stream of one channel of a channel pair element.
+    pub fn decode_ga_cpe_channel<B: ReadBitsLtr>(
+        &mut self,
+        bs: &mut B,
+        ch: usize,
+        m4atype: M4AType,
+        common_window: bool,
+    ) -> Result<()> {
+        let ics = if ch == 0 { &mut self.ics0 } else { &mut self.ics1 };
+        ics.decode(bs, &mut self.lcg, m4atype, common_window)
+    }
 
+    fn decode_joint_stereo(&mut self, common_window: bool) {
         // Joint-stereo decoding
         if common_window {
             let bands = self.ics0.get_bands();
@@ -145,8 +171,6 @@ impl ChannelPair {
                 }
             }
         }
-
-        Ok(())
     }
 
     pub fn synth_audio(
@@ -0,0 +1,96 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+
+use crate::aac::block::{ElementRange, RawDataBlockParser};
+use crate::common::M4AType;
+
+/// The number of protected bits at the start of a single channel element, or the first channel of
+/// a channel pair element.
+const PROTECTED_BITS_REG1: u64 = 192;
+/// The number of protected bits at the start of the second channel of a channel pair element.
+const PROTECTED_BITS_REG2: u64 = 128;
+
+/// An ADTS error check.
+///
+/// The ADTS CRC protects the ADTS header, and a region at the start of each syntactic element in
+/// the raw data block. Since the length of an individual channel stream is not signalled, the
+/// raw data block must be parsed to find these regions.
+pub(crate) struct AdtsErrorCheck {
+    parser: RawDataBlockParser,
+}
+
+impl AdtsErrorCheck {
+    pub(crate) fn new(m4atype: M4AType, sample_rate: u32) -> Self {
+        AdtsErrorCheck { parser: RawDataBlockParser::new(m4atype, sample_rate) }
+    }
+
+    /// Calculates the CRC of an ADTS frame given the ADTS header, excluding the CRC itself, and a
+    /// single raw data block.
+    pub(crate) fn calc_crc(&mut self, header: &[u8], buf: &[u8]) -> Result<u16> {
+        let mut crc = Crc::new();
+
+        crc.process_bits(header, 0, 8 * header.len() as u64, 0);
+
+        self.parser.parse(buf, |element| match element {
+            ElementRange::Single { start, end } => {
+                crc.process_bits(buf, start, end, PROTECTED_BITS_REG1);
+            }
+            ElementRange::Pair { start, mid, end } => {
+                // The first region spans both channels if the first channel is short.
+                crc.process_bits(buf, start, end, PROTECTED_BITS_REG1);
+                crc.process_bits(buf, mid, end, PROTECTED_BITS_REG2);
+            }
+            ElementRange::Other { start, end } => {
+                // Data stream and program config elements are fully protected.
+                crc.process_bits(buf, start, end, 0);
+            }
+        })?;
+
+        Ok(crc.crc())
+    }
+}
+
+/// The CRC-16 used by the ADTS error check (polynomial 0x8005, initial value 0xffff).
+struct Crc {
+    state: u16,
+}
+
+impl Crc {
+    fn new() -> Self {
+        Crc { state: 0xffff }
+    }
+
+    fn crc(&self) -> u16 {
+        self.state
+    }
+
+    fn process_bit(&mut self, bit: bool) {
+        let msb = self.state & 0x8000 != 0;
+
+        self.state <<= 1;
+
+        if msb != bit {
+            self.state ^= 0x8005;
+        }
+    }
+
+    /// Process the bits in the range `start..end` of the buffer. If `max_len` is not 0, at most
+    /// `max_len` bits are processed, and if the range is shorter, it is padded with 0 bits.
+    fn process_bits(&mut self, buf: &[u8], start: u64, end: u64, max_len: u64) {
+        let end = if max_len > 0 { end.min(start + max_len) } else { end };
+
+        for i in start..end {
+            self.process_bit(buf[(i >> 3) as usize] & (0x80 >> (i & 0x7)) != 0);
+        }
+
+        for _ in (end - start)..max_len {
+            self.process_bit(false);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::io::ReadBitsLtr;
+
+use crate::common::map_channels;
+
+/// The mapping of the channel elements in a raw data block to output channels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElementMap {
+    /// The output channels.
+    pub channels: Channels,
+    /// For each channel element, in the order they appear in a raw data block, if the element is a
+    /// channel pair, and the first output channel of the element.
+    elements: Vec<(bool, Channels)>,
+}
+
+impl ElementMap {
+    /// Get the element map for a number of channels signalled by a channel configuration.
+    pub fn from_num_channels(num_channels: usize) -> Option<Self> {
+        let sce = |ch| (false, ch);
+        let cpe = |ch| (true, ch);
+
+        let elements = match num_channels {
+            1 => vec![sce(Channels::FRONT_LEFT)],
+            2 => vec![cpe(Channels::FRONT_LEFT)],
+            3 => vec![sce(Channels::FRONT_CENTRE), cpe(Channels::FRONT_LEFT)],
+            4 => vec![
+                sce(Channels::FRONT_CENTRE),
+                cpe(Channels::FRONT_LEFT),
+                sce(Channels::REAR_CENTRE),
+            ],
+            5 => vec![
+                sce(Channels::FRONT_CENTRE),
+                cpe(Channels::FRONT_LEFT),
+                cpe(Channels::SIDE_LEFT),
+            ],
+            6 => vec![
+                sce(Channels::FRONT_CENTRE),
+                cpe(Channels::FRONT_LEFT),
+                cpe(Channels::SIDE_LEFT),
+                sce(Channels::LFE1),
+            ],
+            8 => vec![
+                sce(Channels::FRONT_CENTRE),
+                cpe(Channels::FRONT_LEFT),
+                cpe(Channels::FRONT_LEFT_WIDE),
+                cpe(Channels::SIDE_LEFT),
+                sce(Channels::LFE1),
+            ],
+            _ => return None,
+        };
+
+        Some(ElementMap { channels: map_channels(num_channels as u32)?, elements })
+    }
+
+    /// Read a program config element, and get its element map.
+    pub fn read_program_config<B: ReadBitsLtr>(bs: &mut B) -> Result<Self> {
+        let map = Self::read_program_config_channels(bs)?;
+
+        bs.realign();
+
+        let comment_field_bytes = bs.read_bits_leq32(8)?;
+        bs.ignore_bits(8 * comment_field_bytes)?;
+
+        Ok(map)
+    }
+
+    /// Read a program config element up-to, but excluding, the byte alignment and comment field,
+    /// and get its element map.
+    pub fn read_program_config_channels<B: ReadBitsLtr>(bs: &mut B) -> Result<Self> {
+        let _element_instance_tag = bs.read_bits_leq32(4)?;
+        let _object_type = bs.read_bits_leq32(2)?;
+        let _sampling_frequency_index = bs.read_bits_leq32(4)?;
+
+        let num_front_channel_elements = bs.read_bits_leq32(4)?;
+        let num_side_channel_elements = bs.read_bits_leq32(4)?;
+        let num_back_channel_elements = bs.read_bits_leq32(4)?;
+        let num_lfe_channel_elements = bs.read_bits_leq32(2)?;
+        let num_assoc_data_elements = bs.read_bits_leq32(3)?;
+        let num_valid_cc_elements = bs.read_bits_leq32(4)?;
+
+        // Mono, stereo, and matrix mixdowns.
+        if bs.read_bool()? {
+            let _mono_mixdown_element_number = bs.read_bits_leq32(4)?;
+        }
+        if bs.read_bool()? {
+            let _stereo_mixdown_element_number = bs.read_bits_leq32(4)?;
+        }
+        if bs.read_bool()? {
+            let _matrix_mixdown_idx = bs.read_bits_leq32(2)?;
+            let _pseudo_surround_enable = bs.read_bool()?;
+        }
+
+        let mut read_elements = |num| -> Result<Vec<bool>> {
+            (0..num)
+                .map(|_| {
+                    let is_cpe = bs.read_bool()?;
+                    let _tag_select = bs.read_bits_leq32(4)?;
+                    Ok(is_cpe)
+                })
+                .collect()
+        };
+
+        let front = read_elements(num_front_channel_elements)?;
+        let side = read_elements(num_side_channel_elements)?;
+        let back = read_elements(num_back_channel_elements)?;
+
+        for _ in 0..num_lfe_channel_elements {
+            let _lfe_element_tag_select = bs.read_bits_leq32(4)?;
+        }
+
+        for _ in 0..num_assoc_data_elements {
+            let _assoc_data_element_tag_select = bs.read_bits_leq32(4)?;
+        }
+
+        for _ in 0..num_valid_cc_elements {
+            let _cc_element_is_ind_sw = bs.read_bool()?;
+            let _valid_cc_element_tag_select = bs.read_bits_leq32(4)?;
+        }
+
+        // Assign output channels to the elements. The front elements are ordered from the centre
+        // outwards.
+        let num_front_pairs = front.iter().filter(|&&is_cpe| is_cpe).count();
+
+        let front_pairs: &[Channels] = match num_front_pairs {
+            0 => &[],
+            1 => &[Channels::FRONT_LEFT],
+            2 => &[Channels::FRONT_LEFT_CENTRE, Channels::FRONT_LEFT],
+            3 => &[Channels::FRONT_LEFT_CENTRE, Channels::FRONT_LEFT, Channels::FRONT_LEFT_WIDE],
+            _ => return unsupported_error("aac: too many front channel pairs"),
+        };
+
+        let mut map = ElementMap { channels: Channels::empty(), elements: Vec::new() };
+
+        let mut front_pairs = front_pairs.iter();
+
+        for &is_cpe in front.iter() {
+            match is_cpe {
+                true => map.push(true, *front_pairs.next().unwrap())?,
+                false => map.push(false, Channels::FRONT_CENTRE)?,
+            }
+        }
+
+        for &is_cpe in side.iter() {
+            match is_cpe {
+                true => map.push(true, Channels::SIDE_LEFT)?,
+                false => return unsupported_error("aac: side single channel element"),
+            }
+        }
+
+        for &is_cpe in back.iter() {
+            match is_cpe {
+                true => map.push(true, Channels::REAR_LEFT)?,
+                false => map.push(false, Channels::REAR_CENTRE)?,
+            }
+        }
+
+        for _ in 0..num_lfe_channel_elements {
+            map.push(false, Channels::LFE1)?;
+        }
+
+        if map.elements.is_empty() {
+            return unsupported_error("aac: program config without channels");
+        }
+
+        Ok(map)
+    }
+
+    fn push(&mut self, is_pair: bool, channel: Channels) -> Result<()> {
+        let channels = if is_pair {
+            channel | Channels::from_bits_truncate(channel.bits() << 1)
+        }
+        else {
+            channel
+        };
+
+        if self.channels.intersects(channels) {
+            return unsupported_error("aac: program config channel layout");
+        }
+
+        self.channels |= channels;
+        self.elements.push((is_pair, channel));
+
+        Ok(())
+    }
+
+    /// For the n-th channel element, get if it is a channel pair, and the index of its first output
+    /// channel in the audio buffer.
+    pub fn element(&self, n: usize) -> Option<(bool, usize)> {
+        self.elements.get(n).map(|&(is_pair, channel)| {
+            (is_pair, (self.channels.bits() & (channel.bits() - 1)).count_ones() as usize)
+        })
+    }
+}
@@ -11,37 +11,54 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::audio::SignalSpec;
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_AAC};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
 use symphonia_core::io::{BitReaderLtr, FiniteBitStream, ReadBitsLtr};
 use symphonia_core::support_codec;
 use symphonia_core::units::Duration;
 
+use log::warn;
+
+mod block;
 mod codebooks;
 mod common;
 mod cpe;
+mod crc;
 mod dsp;
 mod ics;
+mod layout;
 mod window;
 
 use crate::common::*;
 use common::*;
 
-struct M4AInfo {
+pub(crate) use block::RawDataBlockParser;
+pub(crate) use crc::AdtsErrorCheck;
+pub(crate) use layout::ElementMap;
+
+/// Extension payload type for SBR data.
+const EXT_SBR_DATA: u32 = 0xd;
+/// Extension payload type for SBR data with a CRC.
+const EXT_SBR_DATA_CRC: u32 = 0xe;
+
+pub(crate) struct M4AInfo {
     otype: M4AType,
-    srate: u32,
-    channels: usize,
-    samples: usize,
+    pub(crate) srate: u32,
+    pub(crate) channels: usize,
+    pub(crate) samples: usize,
     sbr_ps_info: Option<(u32, usize)>,
     sbr_present: bool,
     ps_present: bool,
+    /// The channel layout signalled by a program config element, if present.
+    pce: Option<ElementMap>,
 }
 
 impl M4AInfo {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             otype: M4AType::None,
             srate: 0,
@@ -50,9 +67,20 @@ impl M4AInfo {
             sbr_ps_info: Option::None,
             sbr_present: false,
             ps_present: false,
+            pce: None,
         }
     }
 
+    /// Get the element map for the channel configuration, or program config element.
+    fn element_map(&self) -> Option<ElementMap> {
+        self.pce.clone().or_else(|| ElementMap::from_num_channels(self.channels))
+    }
+
+    /// Get the output channels, if known.
+    pub(crate) fn channels(&self) -> Option<Channels> {
+        self.element_map().map(|map| map.channels)
+    }
+
     fn read_object_type<B: ReadBitsLtr>(bs: &mut B) -> Result<M4AType> {
         let otypeidx = match bs.read_bits_leq32(5)? {
             idx if idx < 31 => idx as usize,
@@ -71,10 +99,8 @@ impl M4AInfo {
     fn read_sampling_frequency<B: ReadBitsLtr>(bs: &mut B) -> Result<u32> {
         match bs.read_bits_leq32(4)? {
             idx if idx < 15 => Ok(AAC_SAMPLE_RATES[idx as usize]),
-            _ => {
-                let srate = (0xf << 20) & bs.read_bits_leq32(20)?;
-                Ok(srate)
-            }
+            // An escape value indicates the sample rate is explicitly stated.
+            _ => Ok(bs.read_bits_leq32(24)?),
         }
     }
 
@@ -88,26 +114,72 @@ impl M4AInfo {
         }
     }
 
+    /// Reads the SBR headers of a low delay SBR configuration.
+    fn read_ld_sbr_header<B: ReadBitsLtr>(bs: &mut B, channels: usize) -> Result<()> {
+        let num_sbr_header = match channels {
+            1 | 2 => 1,
+            3 => 2,
+            4..=6 => 3,
+            8 => 4,
+            _ => return unsupported_error("aac: low delay SBR channel configuration"),
+        };
+
+        for _ in 0..num_sbr_header {
+            // sbr_header
+            let _bs_amp_res = bs.read_bool()?;
+            let _bs_start_freq = bs.read_bits_leq32(4)?;
+            let _bs_stop_freq = bs.read_bits_leq32(4)?;
+            let _bs_xover_band = bs.read_bits_leq32(3)?;
+            let _bs_reserved = bs.read_bits_leq32(2)?;
+
+            let bs_header_extra_1 = bs.read_bool()?;
+            let bs_header_extra_2 = bs.read_bool()?;
+
+            if bs_header_extra_1 {
+                let _bs_freq_scale = bs.read_bits_leq32(2)?;
+                let _bs_alter_scale = bs.read_bool()?;
+                let _bs_noise_bands = bs.read_bits_leq32(2)?;
+            }
+
+            if bs_header_extra_2 {
+                let _bs_limiter_bands = bs.read_bits_leq32(2)?;
+                let _bs_limiter_gains = bs.read_bits_leq32(2)?;
+                let _bs_interpol_freq = bs.read_bool()?;
+                let _bs_smoothing_mode = bs.read_bool()?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn read(&mut self, buf: &[u8]) -> Result<()> {
-        let mut bs = BitReaderLtr::new(buf);
+        self.read_config(&mut BitReaderLtr::new(buf), true)
+    }
 
-        self.otype = Self::read_object_type(&mut bs)?;
-        self.srate = Self::read_sampling_frequency(&mut bs)?;
+    /// Reads an audio specific config from the bitstream. If `sync_extension` is true, and the
+    /// audio specific config is followed by a backwards-compatible extension, it is also read.
+    pub(crate) fn read_config<B: ReadBitsLtr + FiniteBitStream>(
+        &mut self,
+        bs: &mut B,
+        sync_extension: bool,
+    ) -> Result<()> {
+        self.otype = Self::read_object_type(bs)?;
+        self.srate = Self::read_sampling_frequency(bs)?;
 
         validate!(self.srate > 0);
 
-        self.channels = Self::read_channel_config(&mut bs)?;
+        self.channels = Self::read_channel_config(bs)?;
 
+        // Explicit, hierarchical, signalling of SBR and PS.
         if (self.otype == M4AType::Sbr) || (self.otype == M4AType::PS) {
-            let ext_srate = Self::read_sampling_frequency(&mut bs)?;
-            self.otype = Self::read_object_type(&mut bs)?;
+            self.sbr_present = true;
+            self.ps_present = self.otype == M4AType::PS;
 
-            let ext_chans = if self.otype == M4AType::ER_BSAC {
-                Self::read_channel_config(&mut bs)?
-            }
-            else {
-                0
-            };
+            let ext_srate = Self::read_sampling_frequency(bs)?;
+            self.otype = Self::read_object_type(bs)?;
+
+            let ext_chans =
+                if self.otype == M4AType::ER_BSAC { Self::read_channel_config(bs)? } else { 0 };
 
             self.sbr_ps_info = Some((ext_srate, ext_chans));
         }
@@ -127,7 +199,12 @@ impl M4AInfo {
                 // GASpecificConfig
                 let short_frame = bs.read_bool()?;
 
-                self.samples = if short_frame { 960 } else { 1024 };
+                self.samples = match (self.otype, short_frame) {
+                    (M4AType::ER_AAC_LD, true) => 480,
+                    (M4AType::ER_AAC_LD, false) => 512,
+                    (_, true) => 960,
+                    (_, false) => 1024,
+                };
 
                 let depends_on_core = bs.read_bool()?;
 
@@ -138,7 +215,9 @@ impl M4AInfo {
                 let extension_flag = bs.read_bool()?;
 
                 if self.channels == 0 {
-                    return unsupported_error("aac: program config element");
+                    let pce = ElementMap::read_program_config(bs)?;
+                    self.channels = pce.channels.count();
+                    self.pce = Some(pce);
                 }
 
                 if (self.otype == M4AType::Scalable) || (self.otype == M4AType::ER_AAC_Scalable) {
@@ -213,7 +292,48 @@ impl M4AInfo {
                 return unsupported_error("aac: SLS config");
             }
             M4AType::ER_AAC_ELD => {
-                return unsupported_error("aac: ELD config");
+                // ELDSpecificConfig
+                let short_frame = bs.read_bool()?;
+
+                self.samples = if short_frame { 480 } else { 512 };
+
+                let _section_data_resilience = bs.read_bool()?;
+                let _scalefactors_resilience = bs.read_bool()?;
+                let _spectral_data_resilience = bs.read_bool()?;
+
+                let ld_sbr_present = bs.read_bool()?;
+
+                if ld_sbr_present {
+                    self.sbr_present = true;
+
+                    let _ld_sbr_sampling_rate = bs.read_bool()?;
+                    let _ld_sbr_crc = bs.read_bool()?;
+
+                    Self::read_ld_sbr_header(bs, self.channels)?;
+                }
+
+                // Extensions are ignored.
+                loop {
+                    let eld_ext_type = bs.read_bits_leq32(4)?;
+
+                    // ELDEXT_TERM
+                    if eld_ext_type == 0 {
+                        break;
+                    }
+
+                    let mut eld_ext_len = bs.read_bits_leq32(4)?;
+
+                    if eld_ext_len == 15 {
+                        let eld_ext_len_add = bs.read_bits_leq32(8)?;
+                        eld_ext_len += eld_ext_len_add;
+
+                        if eld_ext_len_add == 255 {
+                            eld_ext_len += bs.read_bits_leq32(16)?;
+                        }
+                    }
+
+                    bs.ignore_bits(8 * eld_ext_len)?;
+                }
             }
             M4AType::SMRSimple | M4AType::SMRMain => {
                 return unsupported_error("aac: symbolic music config");
@@ -246,15 +366,18 @@ impl M4AInfo {
             _ => {}
         };
 
-        if self.sbr_ps_info.is_some() && (bs.bits_left() >= 16) {
+        // Explicit, backwards-compatible, signalling of SBR and PS. This is only present if SBR was
+        // not signalled hierarchically.
+        if sync_extension && self.sbr_ps_info.is_none() && (bs.bits_left() >= 16) {
             let sync = bs.read_bits_leq32(11)?;
 
             if sync == 0x2B7 {
-                let ext_otype = Self::read_object_type(&mut bs)?;
+                let ext_otype = Self::read_object_type(bs)?;
                 if ext_otype == M4AType::Sbr {
                     self.sbr_present = bs.read_bool()?;
                     if self.sbr_present {
-                        let _ext_srate = Self::read_sampling_frequency(&mut bs)?;
+                        let ext_srate = Self::read_sampling_frequency(bs)?;
+                        self.sbr_ps_info = Some((ext_srate, 0));
                         if bs.bits_left() >= 12 {
                             let sync = bs.read_bits_leq32(11)?;
                             if sync == 0x548 {
@@ -263,10 +386,11 @@ impl M4AInfo {
                         }
                     }
                 }
-                if ext_otype == M4AType::PS {
+                if ext_otype == M4AType::ER_BSAC {
                     self.sbr_present = bs.read_bool()?;
                     if self.sbr_present {
-                        let _ext_srate = Self::read_sampling_frequency(&mut bs)?;
+                        let ext_srate = Self::read_sampling_frequency(bs)?;
+                        self.sbr_ps_info = Some((ext_srate, 0));
                     }
                     let _ext_channels = bs.read_bits_leq32(4)?;
                 }
@@ -291,6 +415,11 @@ impl std::fmt::Display for M4AInfo {
 ///
 /// Implements a decoder for Advanced Audio Decoding Low-Complexity (AAC-LC) as defined in
 /// ISO/IEC 13818-7 and ISO/IEC 14496-3.
+///
+/// Spectral band replication (SBR) is not decoded. For a High-Efficiency AAC (HE-AAC) stream, only
+/// the AAC-LC core is decoded, at half of the sample rate of the stream.
+///
+/// The low delay object types, AAC-LD and AAC-ELD, are not supported.
 pub struct AacDecoder {
     // info: NACodecInfoRef,
     m4ainfo: M4AInfo,
@@ -299,10 +428,22 @@ pub struct AacDecoder {
     sbinfo: GASubbandInfo,
     params: CodecParameters,
     buf: AudioBuffer<f32>,
+    /// The mapping of channel elements to output channels.
+    elements: ElementMap,
+    /// If true, parametric stereo is signalled but not decoded. As a fallback, the mono core is
+    /// copied to both output channels.
+    is_ps_fallback: bool,
 }
 
 impl AacDecoder {
-    fn set_pair(&mut self, pair_no: usize, channel: usize, pair: bool) -> Result<()> {
+    fn set_pair(&mut self, pair_no: usize, pair: bool) -> Result<()> {
+        let (is_pair, channel) = match self.elements.element(pair_no) {
+            Some(element) => element,
+            None => return decode_error("aac: unexpected channel element"),
+        };
+
+        validate!(is_pair == pair);
+
         if self.pairs.len() <= pair_no {
             self.pairs.push(cpe::ChannelPair::new(pair, channel, self.sbinfo));
         }
@@ -310,13 +451,11 @@ impl AacDecoder {
             validate!(self.pairs[pair_no].channel == channel);
             validate!(self.pairs[pair_no].is_pair == pair);
         }
-        validate!(if pair { channel + 1 } else { channel } < self.m4ainfo.channels);
         Ok(())
     }
 
     fn decode_ga<B: ReadBitsLtr + FiniteBitStream>(&mut self, bs: &mut B) -> Result<()> {
         let mut cur_pair = 0;
-        let mut cur_ch = 0;
         while bs.bits_left() > 3 {
             let id = bs.read_bits_leq32(3)?;
 
@@ -324,18 +463,16 @@ impl AacDecoder {
                 0 => {
                     // ID_SCE
                     let _tag = bs.read_bits_leq32(4)?;
-                    self.set_pair(cur_pair, cur_ch, false)?;
+                    self.set_pair(cur_pair, false)?;
                     self.pairs[cur_pair].decode_ga_sce(bs, self.m4ainfo.otype)?;
                     cur_pair += 1;
-                    cur_ch += 1;
                 }
                 1 => {
                     // ID_CPE
                     let _tag = bs.read_bits_leq32(4)?;
-                    self.set_pair(cur_pair, cur_ch, true)?;
+                    self.set_pair(cur_pair, true)?;
                     self.pairs[cur_pair].decode_ga_cpe(bs, self.m4ainfo.otype)?;
                     cur_pair += 1;
-                    cur_ch += 2;
                 }
                 2 => {
                     // ID_CCE
@@ -344,10 +481,9 @@ impl AacDecoder {
                 3 => {
                     // ID_LFE
                     let _tag = bs.read_bits_leq32(4)?;
-                    self.set_pair(cur_pair, cur_ch, false)?;
+                    self.set_pair(cur_pair, false)?;
                     self.pairs[cur_pair].decode_ga_sce(bs, self.m4ainfo.otype)?;
                     cur_pair += 1;
-                    cur_ch += 1;
                 }
                 4 => {
                     // ID_DSE
@@ -364,7 +500,12 @@ impl AacDecoder {
                 }
                 5 => {
                     // ID_PCE
-                    return unsupported_error("aac: program config");
+                    let pce = ElementMap::read_program_config(bs)?;
+
+                    // The output channels cannot be changed once decoding has started.
+                    if pce != self.elements {
+                        warn!("aac: ignoring a program config element with a different layout");
+                    }
                 }
                 6 => {
                     // ID_FIL
@@ -373,9 +514,23 @@ impl AacDecoder {
                         count += bs.read_bits_leq32(8)? as usize;
                         count -= 1;
                     }
-                    for _ in 0..count {
+                    if count > 0 {
                         // ext payload
-                        bs.ignore_bits(8)?;
+                        let ext_type = bs.read_bits_leq32(4)?;
+
+                        // SBR may be implicitly signalled by the presence of SBR data in the
+                        // extension payload.
+                        if (ext_type == EXT_SBR_DATA || ext_type == EXT_SBR_DATA_CRC)
+                            && !self.m4ainfo.sbr_present
+                        {
+                            warn!(
+                                "aac: spectral band replication is not supported, \
+                                 decoding core only"
+                            );
+                            self.m4ainfo.sbr_present = true;
+                        }
+
+                        bs.ignore_bits(4 + 8 * (count as u32 - 1))?;
                     }
                 }
                 7 => {
@@ -389,6 +544,11 @@ impl AacDecoder {
         for pair in 0..cur_pair {
             self.pairs[pair].synth_audio(&mut self.dsp, &mut self.buf, rate_idx);
         }
+        // Fallback: parametric stereo is not decoded, so copy the mono core to both channels.
+        if self.is_ps_fallback {
+            let (left, right) = self.buf.chan_pair_mut(0, 1);
+            right.copy_from_slice(left);
+        }
         Ok(())
     }
 
@@ -412,6 +572,8 @@ impl AacDecoder {
             _ => return unsupported_error("aac: object type"),
         }
 
+        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+
         Ok(())
     }
 }
@@ -451,13 +613,49 @@ impl Decoder for AacDecoder {
             };
         }
 
+        // The audio specific config of the low delay object types is parsed, but decoding them
+        // requires the error resilient syntax and the low delay filterbank, which are not
+        // implemented.
+        if (m4ainfo.otype == M4AType::ER_AAC_LD) || (m4ainfo.otype == M4AType::ER_AAC_ELD) {
+            return unsupported_error("aac: low delay object types are not supported");
+        }
+
+        // Spectral band replication and parametric stereo are not supported, however, the AAC core
+        // can still be decoded.
+        if m4ainfo.sbr_present {
+            warn!("aac: spectral band replication is not supported, decoding core only");
+        }
+
         //print!("edata:"); for s in edata.iter() { print!(" {:02X}", *s);}println!("");
 
-        if (m4ainfo.otype != M4AType::Lc) || (m4ainfo.channels > 2) || (m4ainfo.samples != 1024) {
+        if (m4ainfo.otype != M4AType::Lc) || (m4ainfo.samples != 1024) {
             return unsupported_error("aac: aac too complex");
         }
 
-        let spec = SignalSpec::new(m4ainfo.srate, map_channels(m4ainfo.channels as u32).unwrap());
+        let elements = match m4ainfo.element_map() {
+            Some(elements) => elements,
+            None => return unsupported_error("aac: unsupported channel configuration"),
+        };
+
+        // A parametric stereo stream has a mono core, but is always output in stereo. Parametric
+        // stereo is not decoded, therefore, as a fallback, the mono core will be copied to both
+        // output channels.
+        let is_ps_fallback = m4ainfo.ps_present && m4ainfo.channels == 1;
+
+        if m4ainfo.ps_present {
+            warn!(
+                "aac: parametric stereo is not supported, copying the mono core to both channels"
+            );
+        }
+
+        let channels = if is_ps_fallback {
+            Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+        }
+        else {
+            elements.channels
+        };
+
+        let spec = SignalSpec::new(m4ainfo.srate, channels);
 
         let duration = m4ainfo.samples as Duration;
         let srate = m4ainfo.srate;
@@ -469,6 +667,8 @@ impl Decoder for AacDecoder {
             sbinfo: GASubbandInfo::find(srate),
             params: params.clone(),
             buf: AudioBuffer::new(duration, spec),
+            elements,
+            is_ps_fallback,
         })
     }
 
@@ -0,0 +1,373 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AAC};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, info, warn};
+
+use super::aac::{ElementMap, RawDataBlockParser};
+use super::common::{M4AType, AAC_SAMPLE_RATES, M4A_TYPES};
+
+const SAMPLES_PER_AAC_PACKET: u64 = 1024;
+
+/// The maximum length of the ADIF header that will be read.
+const MAX_HEADER_LEN: usize = 8 * 1024;
+
+/// The maximum number of bytes per channel in a raw data block.
+const MAX_BLOCK_LEN_PER_CHANNEL: usize = 6144 / 8;
+
+/// Audio Data Interchange Format (ADIF) format reader.
+///
+/// `AdifReader` implements a demuxer for ADIF (a single header followed by raw AAC data blocks).
+pub struct AdifReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+    parser: RawDataBlockParser,
+    /// The maximum length of a raw data block.
+    max_block_len: usize,
+}
+
+impl QueryDescriptor for AdifReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "adif",
+            "Audio Data Interchange Format (native AAC)",
+            &["aac", "adif"],
+            &["audio/aac"],
+            &[b"ADIF"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// The ADIF header.
+struct AdifHeader {
+    profile: M4AType,
+    sample_rate_idx: u32,
+    bit_rate: u32,
+    channels: ElementMap,
+    /// An audio specific config equivalent to the first program config element.
+    asc: Box<[u8]>,
+}
+
+impl AdifHeader {
+    fn read(buf: &[u8]) -> Result<(Self, usize)> {
+        let mut bs = BitReaderLtr::new(buf);
+
+        if bs.read_bits_leq32(32)? != u32::from_be_bytes(*b"ADIF") {
+            return unsupported_error("adif: missing adif marker");
+        }
+
+        let copyright_id_present = bs.read_bool()?;
+
+        if copyright_id_present {
+            bs.ignore_bits(72)?;
+        }
+
+        let _original_copy = bs.read_bool()?;
+        let _home = bs.read_bool()?;
+        let is_variable_rate = bs.read_bool()?;
+        let bit_rate = bs.read_bits_leq32(23)?;
+        let num_program_config_elements = bs.read_bits_leq32(4)? + 1;
+
+        if num_program_config_elements > 1 {
+            warn!("adif: only the first program config element is used");
+        }
+
+        let mut first = None;
+
+        for _ in 0..num_program_config_elements {
+            if !is_variable_rate {
+                let _adif_buffer_fullness = bs.read_bits_leq32(20)?;
+            }
+
+            let start = 8 * buf.len() as u64 - bs.bits_left();
+
+            let channels = ElementMap::read_program_config_channels(&mut bs)?;
+
+            let end = 8 * buf.len() as u64 - bs.bits_left();
+
+            bs.realign();
+
+            let comment_field_bytes = bs.read_bits_leq32(8)?;
+            bs.ignore_bits(8 * comment_field_bytes)?;
+
+            if first.is_none() {
+                first = Some((start, end, channels));
+            }
+        }
+
+        let (start, end, channels) = first.unwrap();
+
+        // Read the profile and sample rate of the program.
+        let mut bs_pce = BitReaderLtr::new(&buf[(start >> 3) as usize..]);
+        bs_pce.ignore_bits((start & 0x7) as u32 + 4)?;
+
+        let otype_idx = bs_pce.read_bits_leq32(2)? + 1;
+        let sample_rate_idx = bs_pce.read_bits_leq32(4)?;
+
+        if AAC_SAMPLE_RATES[sample_rate_idx as usize] == 0 {
+            return decode_error("adif: invalid sample rate");
+        }
+
+        // An equivalent audio specific config signals the program config element in the GA specific
+        // config. The byte alignment of the program config element must be rewritten since it is
+        // relative to the start of the audio specific config.
+        let mut asc = BitWriter::default();
+
+        asc.write_bits(otype_idx, 5);
+        asc.write_bits(sample_rate_idx, 4);
+        // Channel configuration 0, and GA specific config flags.
+        asc.write_bits(0, 4 + 3);
+
+        let mut bs_pce = BitReaderLtr::new(&buf[(start >> 3) as usize..]);
+        bs_pce.ignore_bits((start & 0x7) as u32)?;
+
+        for _ in start..end {
+            asc.write_bits(bs_pce.read_bit()?, 1);
+        }
+
+        asc.align();
+        // No comment field.
+        asc.write_bits(0, 8);
+
+        let header = AdifHeader {
+            profile: M4A_TYPES[otype_idx as usize],
+            sample_rate_idx,
+            bit_rate,
+            channels,
+            asc: asc.buf.into_boxed_slice(),
+        };
+
+        Ok((header, buf.len() - (bs.bits_left() >> 3) as usize))
+    }
+}
+
+/// A minimal bit writer for building an audio specific config.
+#[derive(Default)]
+struct BitWriter {
+    buf: Vec<u8>,
+    len: u64,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, num_bits: u32) {
+        for i in (0..num_bits).rev() {
+            if self.len & 0x7 == 0 {
+                self.buf.push(0);
+            }
+
+            if (value >> i) & 1 != 0 {
+                *self.buf.last_mut().unwrap() |= 0x80 >> (self.len & 0x7);
+            }
+
+            self.len += 1;
+        }
+    }
+
+    fn align(&mut self) {
+        self.len = (self.len + 7) & !0x7;
+    }
+}
+
+/// Reads as many bytes as possible into the buffer, stopping early only at the end of the stream.
+fn read_up_to(reader: &mut MediaSourceStream, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match reader.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    Ok(len)
+}
+
+impl AdifReader {
+    /// Reads the next raw data block.
+    fn read_block(&mut self) -> Result<Box<[u8]>> {
+        // Read as much of the largest possible raw data block as possible.
+        let mut buf = vec![0; self.max_block_len];
+        let len = read_up_to(&mut self.reader, &mut buf)?;
+
+        if len == 0 {
+            return end_of_stream_error();
+        }
+
+        buf.truncate(len);
+
+        // Since the length of a raw data block is not signalled, it must be parsed to find its end.
+        let block_len = ((self.parser.parse(&buf, |_| ())? + 7) >> 3) as usize;
+
+        // Rewind back to the end of the raw data block.
+        self.reader.seek_buffered_rev(len - block_len);
+
+        buf.truncate(block_len);
+
+        Ok(buf.into_boxed_slice())
+    }
+}
+
+impl FormatReader for AdifReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let start_pos = source.pos();
+
+        source.ensure_seekback_buffer(MAX_HEADER_LEN);
+
+        // The length of the header is not known until it is parsed, so read enough of the stream to
+        // contain it.
+        let mut buf = vec![0; MAX_HEADER_LEN];
+        let len = read_up_to(&mut source, &mut buf)?;
+
+        let (header, header_len) = AdifHeader::read(&buf[..len])?;
+
+        // Rewind back to the first raw data block.
+        source.seek_buffered(start_pos + header_len as u64);
+
+        let first_frame_pos = source.pos();
+
+        let sample_rate = AAC_SAMPLE_RATES[header.sample_rate_idx as usize];
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_channels(header.channels.channels)
+            .with_extra_data(header.asc);
+
+        // Estimate the duration from the bit rate.
+        if let Some(total_len) = source.byte_len() {
+            if header.bit_rate > 0 && total_len > first_frame_pos {
+                info!("estimating duration from bitrate, may be inaccurate for vbr files");
+
+                let total_bits = 8 * (total_len - first_frame_pos);
+                params.with_n_frames(
+                    total_bits * u64::from(sample_rate) / u64::from(header.bit_rate),
+                );
+            }
+        }
+
+        let max_block_len = MAX_BLOCK_LEN_PER_CHANNEL * header.channels.channels.count();
+
+        source.ensure_seekback_buffer(max_block_len);
+
+        Ok(AdifReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            first_frame_pos,
+            next_packet_ts: 0,
+            parser: RawDataBlockParser::new(header.profile, sample_rate),
+            max_block_len,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = self.read_block()?;
+
+        let ts = self.next_packet_ts;
+
+        self.next_packet_ts += SAMPLES_PER_AAC_PACKET;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, SAMPLES_PER_AAC_PACKET, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        // If the desired timestamp is less-than the next packet timestamp, attempt to seek
+        // to the start of the stream.
+        if required_ts < self.next_packet_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            if self.reader.is_seekable() {
+                let seeked_pos = self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+
+                // Since the raw data blocks have no timestamp information, the position seeked to
+                // must be exactly as requested.
+                if seeked_pos != self.first_frame_pos {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            // Successfuly seeked to the start of the stream, reset the next packet timestamp.
+            self.next_packet_ts = 0;
+        }
+
+        // Parse raw data blocks from the stream until the block containing the desired timestamp
+        // is reached.
+        while self.next_packet_ts + SAMPLES_PER_AAC_PACKET <= required_ts {
+            self.read_block()?;
+
+            // Increment the timestamp for the next packet.
+            self.next_packet_ts += SAMPLES_PER_AAC_PACKET;
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -10,20 +10,32 @@ use symphonia_core::support_format;
 
 use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AAC};
-use symphonia_core::errors::{decode_error, seek_error, Result, SeekErrorKind};
+use symphonia_core::errors::{decode_error, seek_error, Error, Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::util::{SeekIndex, SeekSearchResult};
 use symphonia_core::io::*;
 use symphonia_core::meta::{Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 
 use std::io::{Seek, SeekFrom};
 
+use super::aac::AdtsErrorCheck;
 use super::common::{map_channels, M4AType, AAC_SAMPLE_RATES, M4A_TYPES};
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
 const SAMPLES_PER_AAC_PACKET: u64 = 1024;
 
+/// The maximum number of frames in the index of frames read so far.
+const MAX_INDEX_LEN: usize = 4096;
+
+/// Statistics on the integrity of the frames read by the ADTS reader.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ReadStatistics {
+    /// The number of protected frames with a CRC that did not match the expected CRC.
+    pub num_crc_errors: u64,
+}
+
 /// Audio Data Transport Stream (ADTS) format reader.
 ///
 /// `AdtsReader` implements a demuxer for ADTS (AAC native frames).
@@ -34,6 +46,13 @@ pub struct AdtsReader {
     metadata: MetadataLog,
     first_frame_pos: u64,
     next_packet_ts: u64,
+    /// If false, the next packet timestamp was estimated by a coarse seek.
+    is_next_packet_ts_exact: bool,
+    /// An index of the frames read so far with exact timestamps.
+    index: SeekIndex,
+    /// The error check used to verify protected frames, if any were read.
+    error_check: Option<AdtsErrorCheck>,
+    stats: ReadStatistics,
 }
 
 impl QueryDescriptor for AdtsReader {
@@ -43,7 +62,7 @@ impl QueryDescriptor for AdtsReader {
             "Audio Data Transport Stream (native AAC)",
             &["aac"],
             &["audio/aac"],
-            &[&[0xff, 0xf1]]
+            &[&[0xff, 0xf0], &[0xff, 0xf1]]
         )]
     }
 
@@ -59,29 +78,38 @@ struct AdtsHeader {
     channels: Option<Channels>,
     sample_rate: u32,
     frame_len: usize,
+    /// The CRC of the frame, if the frame is protected.
+    crc: Option<u16>,
+    /// The header, excluding the CRC.
+    buf: [u8; AdtsHeader::SIZE],
 }
 
 impl AdtsHeader {
+    /// The size of the header without protection.
     const SIZE: usize = 7;
 
-    fn sync<B: ReadBytes>(reader: &mut B) -> Result<()> {
+    fn sync<B: ReadBytes>(reader: &mut B) -> Result<u16> {
         let mut sync = 0u16;
 
-        while sync != 0xfff1 {
+        // The last bit of the synchronization word is the protection absent flag.
+        while sync & 0xfffe != 0xfff0 {
             sync = (sync << 8) | u16::from(reader.read_u8()?);
         }
 
-        Ok(())
+        Ok(sync)
     }
 
     fn read<B: ReadBytes>(reader: &mut B) -> Result<Self> {
-        AdtsHeader::sync(reader)?;
+        let sync = AdtsHeader::sync(reader)?;
 
-        // The header may be 5 or 7 bytes (without or with protection).
-        let mut buf = [0u8; 7];
-        reader.read_buf_exact(&mut buf[..5])?;
+        let is_protected = sync & 0x1 == 0;
 
-        let mut bs = BitReaderLtr::new(&buf);
+        // The header is 7 or 9 bytes (without or with protection).
+        let mut buf = [0u8; AdtsHeader::SIZE];
+        buf[..2].copy_from_slice(&sync.to_be_bytes());
+        reader.read_buf_exact(&mut buf[2..])?;
+
+        let mut bs = BitReaderLtr::new(&buf[2..]);
 
         // Profile
         let profile = M4A_TYPES[bs.read_bits_leq32(2)? as usize + 1];
@@ -108,7 +136,9 @@ impl AdtsHeader {
         // Frame length = Header size (7) + AAC frame size
         let frame_len = bs.read_bits_leq32(13)? as usize;
 
-        if frame_len < AdtsHeader::SIZE {
+        let header_len = if is_protected { AdtsHeader::SIZE + 2 } else { AdtsHeader::SIZE };
+
+        if frame_len < header_len {
             return decode_error("adts: invalid adts frame length");
         }
 
@@ -120,7 +150,90 @@ impl AdtsHeader {
             return unsupported_error("adts: only 1 aac frame per adts packet is supported");
         }
 
-        Ok(AdtsHeader { profile, channels, sample_rate, frame_len: frame_len - AdtsHeader::SIZE })
+        let crc = if is_protected { Some(reader.read_be_u16()?) } else { None };
+
+        Ok(AdtsHeader {
+            profile,
+            channels,
+            sample_rate,
+            frame_len: frame_len - header_len,
+            crc,
+            buf,
+        })
+    }
+
+    /// Get the size of the header, including the CRC.
+    fn size(&self) -> usize {
+        if self.crc.is_some() {
+            AdtsHeader::SIZE + 2
+        }
+        else {
+            AdtsHeader::SIZE
+        }
+    }
+}
+
+impl AdtsReader {
+    /// Gets statistics on the integrity of the frames read thus far.
+    pub fn statistics(&self) -> ReadStatistics {
+        self.stats
+    }
+
+    /// Verifies the CRC of a frame, if it is protected. Frames that cannot be verified because
+    /// they contain unsupported elements are assumed to be valid.
+    fn check_crc(&mut self, header: &AdtsHeader, buf: &[u8]) -> bool {
+        let expected = match header.crc {
+            Some(crc) => crc,
+            _ => return true,
+        };
+
+        let error_check = self
+            .error_check
+            .get_or_insert_with(|| AdtsErrorCheck::new(header.profile, header.sample_rate));
+
+        match error_check.calc_crc(&header.buf, buf) {
+            Ok(crc) => crc == expected,
+            Err(Error::Unsupported(_)) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Seeks to the frame at the position extrapolated from the desired timestamp, and the
+    /// estimated number of frames in a stream of `data_len` bytes.
+    fn seek_coarse(&mut self, required_ts: u64, n_frames: u64, data_len: u64) -> Result<SeekedTo> {
+        // Extrapolate the position of the start of the frame containing the desired timestamp.
+        let ts = required_ts.min(n_frames) / SAMPLES_PER_AAC_PACKET * SAMPLES_PER_AAC_PACKET;
+        let offset = u128::from(ts) * u128::from(data_len) / u128::from(n_frames);
+
+        self.reader.seek(SeekFrom::Start(self.first_frame_pos + offset as u64))?;
+
+        // For variable bitrate streams, the position is unlikely to be the start of a frame, so
+        // synchronize to the next frame.
+        let pos = sync_frame(&mut self.reader)?;
+
+        // Extrapolate the timestamp of the frame from its position.
+        let offset = pos - self.first_frame_pos;
+        let frame_idx = u128::from(offset) * u128::from(n_frames)
+            / u128::from(data_len)
+            / u128::from(SAMPLES_PER_AAC_PACKET);
+
+        self.next_packet_ts = frame_idx as u64 * SAMPLES_PER_AAC_PACKET;
+        self.is_next_packet_ts_exact = false;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    /// Adds a frame to the index if its timestamp is exact.
+    fn index_frame(&mut self, ts: u64, pos: u64) {
+        if self.is_next_packet_ts_exact {
+            self.index.insert(ts, pos, SAMPLES_PER_AAC_PACKET as u32);
+        }
     }
 }
 
@@ -141,7 +254,7 @@ impl FormatReader for AdtsReader {
         }
 
         // Rewind back to the start of the frame.
-        source.seek_buffered_rev(AdtsHeader::SIZE);
+        source.seek_buffered_rev(header.size());
 
         let first_frame_pos = source.pos();
 
@@ -158,6 +271,10 @@ impl FormatReader for AdtsReader {
             metadata: Default::default(),
             first_frame_pos,
             next_packet_ts: 0,
+            is_next_packet_ts_exact: true,
+            index: SeekIndex::with_max_len(MAX_INDEX_LEN),
+            error_check: None,
+            stats: Default::default(),
         })
     }
 
@@ -171,12 +288,22 @@ impl FormatReader for AdtsReader {
 
         self.next_packet_ts += SAMPLES_PER_AAC_PACKET;
 
-        Ok(Packet::new_from_boxed_slice(
-            0,
-            ts,
-            SAMPLES_PER_AAC_PACKET,
-            self.reader.read_boxed_slice_exact(header.frame_len)?,
-        ))
+        // Index the frame so that accurate seeks back to it do not require a rewind to the
+        // start of the stream.
+        let pos = self.reader.pos() - header.size() as u64;
+        self.index_frame(ts, pos);
+
+        let buf = self.reader.read_boxed_slice_exact(header.frame_len)?;
+
+        // A corrupt frame is not returned. Since the frame was consumed, the next call will read
+        // the following frame.
+        if !self.check_crc(&header, &buf) {
+            warn!("adts: crc check failed for frame at ts={}", ts);
+            self.stats.num_crc_errors += 1;
+            return decode_error("adts: crc mismatch");
+        }
+
+        Ok(Packet::new_from_boxed_slice(0, ts, SAMPLES_PER_AAC_PACKET, buf))
     }
 
     fn metadata(&mut self) -> Metadata<'_> {
@@ -191,7 +318,7 @@ impl FormatReader for AdtsReader {
         &self.tracks
     }
 
-    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+    fn seek(&mut self, mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
         // Get the timestamp of the desired audio frame.
         let required_ts = match to {
             // Frame timestamp given.
@@ -211,9 +338,54 @@ impl FormatReader for AdtsReader {
 
         debug!("seeking to ts={}", required_ts);
 
-        // If the desired timestamp is less-than the next packet timestamp, attempt to seek
-        // to the start of the stream.
-        if required_ts < self.next_packet_ts {
+        // A coarse seek extrapolates the position of the desired frame from the estimated
+        // duration of the stream. However, if the desired frame is within the range of frames
+        // already indexed, an accurate seek is just as fast.
+        let is_indexed = matches!(self.index.search(required_ts), SeekSearchResult::Range(..));
+
+        if matches!(mode, SeekMode::Coarse) && self.reader.is_seekable() && !is_indexed {
+            let n_frames = self.tracks[0].codec_params.n_frames;
+
+            if let (Some(n_frames), Some(byte_len)) = (n_frames, self.reader.byte_len()) {
+                if n_frames > 0 && byte_len > self.first_frame_pos {
+                    return self.seek_coarse(
+                        required_ts,
+                        n_frames,
+                        byte_len - self.first_frame_pos,
+                    );
+                }
+            }
+        }
+
+        // If possible, seek to the nearest indexed frame before the desired frame if the desired
+        // timestamp is less-than the next packet timestamp, the indexed frame is after the next
+        // packet, or the next packet timestamp is not exact.
+        let indexed = match self.index.search(required_ts) {
+            SeekSearchResult::Lower(point) | SeekSearchResult::Range(point, _)
+                if self.reader.is_seekable() =>
+            {
+                Some(point)
+            }
+            _ => None,
+        };
+
+        let is_rewind_required = required_ts < self.next_packet_ts || !self.is_next_packet_ts_exact;
+
+        if let Some(point) =
+            indexed.filter(|p| is_rewind_required || p.frame_ts > self.next_packet_ts)
+        {
+            let seeked_pos = self.reader.seek(SeekFrom::Start(point.byte_offset))?;
+
+            if seeked_pos != point.byte_offset {
+                return seek_error(SeekErrorKind::Unseekable);
+            }
+
+            self.next_packet_ts = point.frame_ts;
+            self.is_next_packet_ts_exact = true;
+        }
+        // Otherwise, if the desired timestamp is less-than the next packet timestamp, or the next
+        // packet timestamp is not exact, attempt to seek to the start of the stream.
+        else if is_rewind_required {
             // If the reader is not seekable then only forward seeks are possible.
             if self.reader.is_seekable() {
                 let seeked_pos = self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
@@ -230,6 +402,7 @@ impl FormatReader for AdtsReader {
 
             // Successfuly seeked to the start of the stream, reset the next packet timestamp.
             self.next_packet_ts = 0;
+            self.is_next_packet_ts_exact = true;
         }
 
         // Parse frames from the stream until the frame containing the desired timestamp is
@@ -243,10 +416,13 @@ impl FormatReader for AdtsReader {
             // If the next frame's timestamp would exceed the desired timestamp, rewind back to the
             // start of this frame and end the search.
             if self.next_packet_ts + SAMPLES_PER_AAC_PACKET > required_ts {
-                self.reader.seek_buffered_rev(AdtsHeader::SIZE);
+                self.reader.seek_buffered_rev(header.size());
                 break;
             }
 
+            let pos = self.reader.pos() - header.size() as u64;
+            self.index_frame(self.next_packet_ts, pos);
+
             // Otherwise, ignore the frame body.
             self.reader.ignore_bytes(header.frame_len as u64)?;
 
@@ -268,6 +444,36 @@ impl FormatReader for AdtsReader {
     }
 }
 
+/// Synchronizes to the next frame, and returns its position. A frame is only considered valid if it
+/// is followed by another frame, or the end of the stream.
+fn sync_frame(reader: &mut MediaSourceStream) -> Result<u64> {
+    loop {
+        let header = match AdtsHeader::read(reader) {
+            Ok(header) => header,
+            Err(Error::DecodeError(_)) => continue,
+            Err(err) => return Err(err),
+        };
+
+        let pos = reader.pos() - header.size() as u64;
+
+        let is_valid = match reader.ignore_bytes(header.frame_len as u64) {
+            Ok(_) => match reader.read_be_u16() {
+                Ok(sync) => sync & 0xfffe == 0xfff0,
+                Err(_) => true,
+            },
+            Err(_) => true,
+        };
+
+        if is_valid {
+            reader.seek(SeekFrom::Start(pos))?;
+            return Ok(pos);
+        }
+
+        // Resume searching from the byte following the false synchronization word.
+        reader.seek(SeekFrom::Start(pos + 1))?;
+    }
+}
+
 fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<u64>> {
     let original_pos = source.pos();
     let total_len = match source.byte_len() {
@@ -296,7 +502,7 @@ fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<
             }
 
             parsed_n_frames += 1;
-            n_bytes += header.frame_len + AdtsHeader::SIZE;
+            n_bytes += header.frame_len + header.size();
         }
 
         let _ = source.seek_buffered(original_pos);
@@ -305,15 +511,22 @@ fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<
         // The number of points to sample within the stream.
         const NUM_SAMPLE_POINTS: u64 = 4;
 
-        let step = (total_len - original_pos) / NUM_SAMPLE_POINTS;
+        let step = (total_len / NUM_SAMPLE_POINTS).max(1);
 
         // Skip the first sample point (start of file) since it is an outlier.
-        for new_pos in (original_pos..total_len - step).step_by(step as usize).skip(1) {
+        for new_pos in
+            (original_pos..original_pos + total_len - step).step_by(step as usize).skip(1)
+        {
             let res = source.seek(SeekFrom::Start(new_pos));
             if res.is_err() {
                 break;
             }
 
+            // Synchronize to the first frame after the sample point.
+            if sync_frame(source).is_err() {
+                break;
+            }
+
             for _ in 0..=100 {
                 let header = match AdtsHeader::read(&mut source) {
                     Ok(header) => header,
@@ -325,7 +538,7 @@ fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<
                 }
 
                 parsed_n_frames += 1;
-                n_bytes += header.frame_len + AdtsHeader::SIZE;
+                n_bytes += header.frame_len + header.size();
             }
         }
 
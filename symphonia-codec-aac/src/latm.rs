@@ -0,0 +1,461 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AAC};
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use super::aac::M4AInfo;
+
+/// The LOAS AudioSyncStream synchronization word.
+const LOAS_SYNC_WORD: u16 = 0x2b7;
+
+/// Low Overhead Audio Stream (LOAS) format reader.
+///
+/// `LoasReader` implements a demuxer for AAC in the Low-overhead MPEG-4 Audio Transport Multiplex
+/// (LATM) carried by a LOAS AudioSyncStream. Only a single program with a single layer is
+/// supported.
+pub struct LoasReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+    /// The number of audio frames per AAC packet.
+    frames_per_packet: u64,
+    /// The last stream mux configuration.
+    config: Option<StreamMuxConfig>,
+    /// Packets read from the last AudioMuxElement but not yet returned.
+    packets: VecDeque<Box<[u8]>>,
+}
+
+impl QueryDescriptor for LoasReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "loas",
+            "Low Overhead Audio Stream (LATM AAC)",
+            &["loas", "latm"],
+            &["audio/aac-latm", "audio/mp4a-latm"],
+            // The syncword followed by the 5 most-significant bits of the frame length.
+            &[
+                &[0x56, 0xe0],
+                &[0x56, 0xe1],
+                &[0x56, 0xe2],
+                &[0x56, 0xe3],
+                &[0x56, 0xe4],
+                &[0x56, 0xe5],
+                &[0x56, 0xe6],
+                &[0x56, 0xe7],
+                &[0x56, 0xe8],
+                &[0x56, 0xe9],
+                &[0x56, 0xea],
+                &[0x56, 0xeb],
+                &[0x56, 0xec],
+                &[0x56, 0xed],
+                &[0x56, 0xee],
+                &[0x56, 0xef],
+                &[0x56, 0xf0],
+                &[0x56, 0xf1],
+                &[0x56, 0xf2],
+                &[0x56, 0xf3],
+                &[0x56, 0xf4],
+                &[0x56, 0xf5],
+                &[0x56, 0xf6],
+                &[0x56, 0xf7],
+                &[0x56, 0xf8],
+                &[0x56, 0xf9],
+                &[0x56, 0xfa],
+                &[0x56, 0xfb],
+                &[0x56, 0xfc],
+                &[0x56, 0xfd],
+                &[0x56, 0xfe],
+                &[0x56, 0xff],
+            ]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// A LATM StreamMuxConfig.
+struct StreamMuxConfig {
+    num_sub_frames: u32,
+    /// The AudioSpecificConfig of the only layer.
+    asc: Box<[u8]>,
+    sample_rate: u32,
+    channels: Option<Channels>,
+    frames_per_packet: u64,
+}
+
+impl StreamMuxConfig {
+    fn read(bs: &mut BitReaderLtr<'_>, buf: &[u8]) -> Result<Self> {
+        let audio_mux_version = bs.read_bit()?;
+
+        let audio_mux_version_a = if audio_mux_version == 1 { bs.read_bit()? } else { 0 };
+
+        if audio_mux_version_a != 0 {
+            return unsupported_error("latm: audio mux version a is not supported");
+        }
+
+        if audio_mux_version == 1 {
+            let _tara_buffer_fullness = latm_get_value(bs)?;
+        }
+
+        let all_streams_same_time_framing = bs.read_bool()?;
+        let num_sub_frames = bs.read_bits_leq32(6)?;
+        let num_program = bs.read_bits_leq32(4)? + 1;
+        let num_layer = bs.read_bits_leq32(3)? + 1;
+
+        if num_program > 1 || num_layer > 1 {
+            return unsupported_error("latm: multiple programs or layers are not supported");
+        }
+
+        if !all_streams_same_time_framing {
+            return unsupported_error(
+                "latm: streams with different time framing are not supported",
+            );
+        }
+
+        // The first layer of the first program always has an AudioSpecificConfig. If the audio mux
+        // version is 1, the length of the AudioSpecificConfig is provided, otherwise it must be
+        // parsed to find its length.
+        let asc_len = if audio_mux_version == 1 { Some(latm_get_value(bs)?) } else { None };
+
+        let asc_start = bit_pos(bs, buf);
+
+        let mut info = M4AInfo::new();
+        info.read_config(bs, false)?;
+
+        let asc_end = bit_pos(bs, buf);
+
+        if let Some(asc_len) = asc_len {
+            let read_len = asc_end - asc_start;
+
+            if u64::from(asc_len) < read_len {
+                return decode_error("latm: audio specific config exceeds the stated length");
+            }
+
+            bs.ignore_bits(asc_len - read_len as u32)?;
+        }
+
+        let asc = copy_bits(buf, asc_start, asc_end - asc_start)?;
+
+        let frame_length_type = bs.read_bits_leq32(3)?;
+
+        if frame_length_type != 0 {
+            return unsupported_error("latm: only variable length payloads are supported");
+        }
+
+        let _latm_buffer_fullness = bs.read_bits_leq32(8)?;
+
+        let other_data_present = bs.read_bool()?;
+
+        if other_data_present {
+            if audio_mux_version == 1 {
+                let _other_data_len_bits = latm_get_value(bs)?;
+            }
+            else {
+                loop {
+                    let other_data_len_esc = bs.read_bool()?;
+                    let _other_data_len_tmp = bs.read_bits_leq32(8)?;
+
+                    if !other_data_len_esc {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let crc_check_present = bs.read_bool()?;
+
+        if crc_check_present {
+            let _crc_check_sum = bs.read_bits_leq32(8)?;
+        }
+
+        debug!("latm: {}", info);
+
+        Ok(StreamMuxConfig {
+            num_sub_frames,
+            asc,
+            sample_rate: info.srate,
+            channels: info.channels(),
+            frames_per_packet: info.samples as u64,
+        })
+    }
+}
+
+/// Reads a LATM variable length value.
+fn latm_get_value(bs: &mut BitReaderLtr<'_>) -> Result<u32> {
+    let bytes_for_value = bs.read_bits_leq32(2)?;
+
+    let mut value = 0;
+
+    for _ in 0..=bytes_for_value {
+        value = (value << 8) | bs.read_bits_leq32(8)?;
+    }
+
+    Ok(value)
+}
+
+/// Gets the position of the bit reader, in bits, relative to the start of the buffer it is reading.
+fn bit_pos(bs: &BitReaderLtr<'_>, buf: &[u8]) -> u64 {
+    8 * buf.len() as u64 - bs.bits_left()
+}
+
+/// Copies `len` bits starting at bit `start` from the buffer into a new byte-aligned buffer.
+fn copy_bits(buf: &[u8], start: u64, len: u64) -> Result<Box<[u8]>> {
+    let mut bs = BitReaderLtr::new(&buf[(start >> 3) as usize..]);
+    bs.ignore_bits((start & 0x7) as u32)?;
+
+    let mut out = vec![0; ((len + 7) >> 3) as usize];
+
+    for (i, byte) in out.iter_mut().enumerate() {
+        let num_bits = (len - 8 * i as u64).min(8) as u32;
+        *byte = (bs.read_bits_leq32(num_bits)? << (8 - num_bits)) as u8;
+    }
+
+    Ok(out.into_boxed_slice())
+}
+
+/// Reads an AudioMuxElement, with an in-band StreamMuxConfig, and returns the payloads contained
+/// within.
+fn read_audio_mux_element(
+    buf: &[u8],
+    config: &mut Option<StreamMuxConfig>,
+) -> Result<Vec<Box<[u8]>>> {
+    let mut bs = BitReaderLtr::new(buf);
+
+    let use_same_stream_mux = bs.read_bool()?;
+
+    if !use_same_stream_mux {
+        let new_config = StreamMuxConfig::read(&mut bs, buf)?;
+
+        // Changing the codec configuration mid-stream is not supported.
+        if let Some(old_config) = config {
+            if old_config.asc != new_config.asc {
+                warn!("latm: ignoring a change in the audio specific config");
+            }
+        }
+
+        *config = Some(new_config);
+    }
+
+    let config = match config {
+        Some(config) => config,
+        _ => return decode_error("latm: missing stream mux config"),
+    };
+
+    let mut payloads = Vec::with_capacity(config.num_sub_frames as usize + 1);
+
+    for _ in 0..=config.num_sub_frames {
+        // PayloadLengthInfo
+        let mut mux_slot_len_bytes = 0;
+
+        loop {
+            let tmp = bs.read_bits_leq32(8)?;
+            mux_slot_len_bytes += tmp as usize;
+
+            if tmp != 255 {
+                break;
+            }
+        }
+
+        // PayloadMux
+        let mut payload = vec![0; mux_slot_len_bytes];
+
+        for byte in payload.iter_mut() {
+            *byte = bs.read_bits_leq32(8)? as u8;
+        }
+
+        payloads.push(payload.into_boxed_slice());
+    }
+
+    // Any other data, and the byte alignment, is ignored.
+    Ok(payloads)
+}
+
+/// Synchronizes to the next AudioSyncStream frame and returns its length.
+fn sync_frame<B: ReadBytes>(reader: &mut B) -> Result<usize> {
+    let mut sync = 0u16;
+
+    while sync >> 5 != LOAS_SYNC_WORD {
+        sync = (sync << 8) | u16::from(reader.read_u8()?);
+    }
+
+    Ok((usize::from(sync & 0x1f) << 8) | usize::from(reader.read_u8()?))
+}
+
+impl LoasReader {
+    /// Reads the next AudioSyncStream frame, and queues the packets contained within.
+    fn read_frame(&mut self) -> Result<()> {
+        let frame_len = sync_frame(&mut self.reader)?;
+
+        let buf = self.reader.read_boxed_slice_exact(frame_len)?;
+
+        self.packets.extend(read_audio_mux_element(&buf, &mut self.config)?);
+
+        Ok(())
+    }
+}
+
+impl FormatReader for LoasReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let first_frame_pos = source.pos();
+
+        // The first frame must contain a stream mux configuration.
+        let frame_len = sync_frame(&mut source)?;
+
+        let buf = source.read_boxed_slice_exact(frame_len)?;
+
+        let mut config = None;
+        let packets = read_audio_mux_element(&buf, &mut config)?.into();
+
+        let config = config.unwrap();
+
+        // Use the stream mux configuration to populate the codec parameters.
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_sample_rate(config.sample_rate)
+            .with_time_base(TimeBase::new(1, config.sample_rate))
+            .with_extra_data(config.asc.clone());
+
+        if let Some(channels) = config.channels {
+            params.with_channels(channels);
+        }
+
+        let frames_per_packet = config.frames_per_packet;
+
+        Ok(LoasReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            first_frame_pos,
+            next_packet_ts: 0,
+            frames_per_packet,
+            config: Some(config),
+            packets,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        while self.packets.is_empty() {
+            self.read_frame()?;
+        }
+
+        let buf = self.packets.pop_front().unwrap();
+
+        let ts = self.next_packet_ts;
+
+        self.next_packet_ts += self.frames_per_packet;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, self.frames_per_packet, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        // If the desired timestamp is less-than the next packet timestamp, attempt to seek
+        // to the start of the stream.
+        if required_ts < self.next_packet_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            if self.reader.is_seekable() {
+                let seeked_pos = self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+
+                // Since the elementary stream has no timestamp information, the position seeked
+                // to must be exactly as requested.
+                if seeked_pos != self.first_frame_pos {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            // Successfuly seeked to the start of the stream, reset the next packet timestamp and
+            // discard any queued packets.
+            self.next_packet_ts = 0;
+            self.packets.clear();
+        }
+
+        // Parse frames from the stream until the packet containing the desired timestamp is
+        // reached.
+        loop {
+            if self.packets.is_empty() {
+                self.read_frame()?;
+                continue;
+            }
+
+            // If the next packet's timestamp would exceed the desired timestamp, end the search.
+            if self.next_packet_ts + self.frames_per_packet > required_ts {
+                break;
+            }
+
+            // Otherwise, discard the packet.
+            self.packets.pop_front();
+
+            // Increment the timestamp for the next packet.
+            self.next_packet_ts += self.frames_per_packet;
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
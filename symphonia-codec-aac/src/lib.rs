@@ -17,8 +17,12 @@
 #![allow(clippy::needless_range_loop)]
 
 mod aac;
+mod adif;
 mod adts;
 mod common;
+mod latm;
 
 pub use aac::AacDecoder;
-pub use adts::AdtsReader;
+pub use adif::AdifReader;
+pub use adts::{AdtsReader, ReadStatistics};
+pub use latm::LoasReader;
@@ -1,7 +1,8 @@
-use symphonia_codec_aac::{AacDecoder, AdtsReader};
+use symphonia_codec_aac::{AacDecoder, AdifReader, AdtsReader, LoasReader};
+use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_AAC};
 use symphonia_core::errors;
-use symphonia_core::formats::{FormatOptions, FormatReader};
+use symphonia_core::formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo};
 use symphonia_core::io::MediaSourceStream;
 
 fn test_decode(data: Vec<u8>) -> symphonia_core::errors::Result<()> {
@@ -33,3 +34,201 @@ fn invalid_channels_aac() {
 
     assert!(matches!(err, errors::Error::Unsupported(_)));
 }
+
+#[test]
+fn loas_multiple_sub_frames() {
+    // Two AudioSyncStream frames, each with two sub-frames containing a silent mono AAC-LC frame.
+    // Only the first frame has a StreamMuxConfig.
+    let file = vec![
+        0x56, 0xe0, 0x11, 0x20, 0x80, 0x12, 0x08, 0x1f, 0xea, 0xa8, 0x20, 0x06, 0x40, 0x00, 0x38,
+        0x20, 0x06, 0x40, 0x00, 0x38, 0x56, 0xe0, 0x0b, 0x82, 0x00, 0x64, 0x00, 0x03, 0x82, 0x00,
+        0x64, 0x00, 0x03, 0x80,
+    ];
+
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(file)), Default::default());
+
+    let mut reader = LoasReader::try_new(source, &FormatOptions::default()).unwrap();
+
+    let params = &reader.tracks()[0].codec_params;
+
+    assert_eq!(params.sample_rate, Some(44100));
+    assert_eq!(params.extra_data.as_deref(), Some(&[0x12, 0x08][..]));
+
+    let mut decoder = AacDecoder::try_new(params, &DecoderOptions::default()).unwrap();
+
+    for i in 0..4 {
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.ts(), i * 1024);
+        assert_eq!(decoder.decode(&packet).unwrap().frames(), 1024);
+    }
+
+    assert!(matches!(reader.next_packet(), Err(errors::Error::IoError(_))));
+}
+
+#[test]
+fn adts_discard_corrupt_packets() {
+    // Three protected ADTS frames containing a silent stereo AAC-LC frame. The CRC of the second
+    // frame is invalid.
+    let file = vec![
+        0xff, 0xf0, 0x50, 0x80, 0x02, 0x1f, 0xfc, 0x4c, 0xda, 0x20, 0x64, 0x00, 0x01, 0x68, 0x00,
+        0x0e, 0xff, 0xf0, 0x50, 0x80, 0x02, 0x1f, 0xfc, 0x4c, 0xdb, 0x20, 0x64, 0x00, 0x01, 0x68,
+        0x00, 0x0e, 0xff, 0xf0, 0x50, 0x80, 0x02, 0x1f, 0xfc, 0x4c, 0xda, 0x20, 0x64, 0x00, 0x01,
+        0x68, 0x00, 0x0e,
+    ];
+
+    let data = std::io::Cursor::new(file);
+    let source = MediaSourceStream::new(Box::new(data), Default::default());
+
+    let mut reader = AdtsReader::try_new(source, &FormatOptions::default()).unwrap();
+
+    // The corrupt frame is reported as a decode error, and reading may continue after it.
+    assert_eq!(reader.next_packet().unwrap().ts(), 0);
+    assert!(matches!(reader.next_packet(), Err(errors::Error::DecodeError(_))));
+    assert_eq!(reader.next_packet().unwrap().ts(), 2048);
+    assert!(matches!(reader.next_packet(), Err(errors::Error::IoError(_))));
+
+    assert_eq!(reader.statistics().num_crc_errors, 1);
+}
+
+#[test]
+fn adts_coarse_seek() {
+    // A protected ADTS frame containing a silent stereo AAC-LC frame.
+    let frame = [
+        0xff, 0xf0, 0x50, 0x80, 0x02, 0x1f, 0xfc, 0x4c, 0xda, 0x20, 0x64, 0x00, 0x01, 0x68, 0x00,
+        0x0e,
+    ];
+
+    let file = frame.repeat(100);
+
+    let data = std::io::Cursor::new(file);
+    let source = MediaSourceStream::new(Box::new(data), Default::default());
+
+    let mut reader = AdtsReader::try_new(source, &Default::default()).unwrap();
+
+    assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(100 * 1024));
+
+    let to = SeekTo::TimeStamp { ts: 50 * 1024 + 100, track_id: 0 };
+    let seeked_to = reader.seek(SeekMode::Coarse, to).unwrap();
+
+    assert_eq!(seeked_to.actual_ts, 50 * 1024);
+    assert_eq!(reader.next_packet().unwrap().ts(), 50 * 1024);
+}
+
+/// Builds an unprotected ADTS frame with a 44.1 kHz AAC-LC header and a body of `len` bytes.
+fn adts_frame(len: usize, fill: u8) -> Vec<u8> {
+    let frame_len = 7 + len;
+
+    let mut frame = vec![
+        0xff,
+        0xf1,
+        0x50,
+        0x80 | (frame_len >> 11) as u8,
+        (frame_len >> 3) as u8,
+        ((frame_len & 0x7) << 5) as u8 | 0x1f,
+        0xfc,
+    ];
+
+    frame.resize(frame_len, fill);
+    frame
+}
+
+#[test]
+fn adts_accurate_seek_from_index() {
+    // ADTS frames of varying length so that frame positions cannot be interpolated.
+    let file: Vec<u8> = (0..100).flat_map(|i| adts_frame(8 + (i * 7) % 23, i as u8)).collect();
+
+    let new_reader = || {
+        let data = std::io::Cursor::new(file.clone());
+        let source = MediaSourceStream::new(Box::new(data), Default::default());
+        AdtsReader::try_new(source, &Default::default()).unwrap()
+    };
+
+    // Read the first 60 frames to populate the seek index of the indexed reader.
+    let mut indexed = new_reader();
+
+    for _ in 0..60 {
+        indexed.next_packet().unwrap();
+    }
+
+    for &ts in &[30 * 1024 + 100, 0, 59 * 1024, 75 * 1024 + 5, 10 * 1024, 99 * 1024 + 1023] {
+        let to = || SeekTo::TimeStamp { ts, track_id: 0 };
+
+        // A fresh reader must scan the stream linearly.
+        let mut linear = new_reader();
+
+        let indexed_seeked_to = indexed.seek(SeekMode::Accurate, to()).unwrap();
+        let linear_seeked_to = linear.seek(SeekMode::Accurate, to()).unwrap();
+
+        assert_eq!(indexed_seeked_to.actual_ts, ts - ts % 1024);
+        assert_eq!(indexed_seeked_to.actual_ts, linear_seeked_to.actual_ts);
+
+        let indexed_packet = indexed.next_packet().unwrap();
+        let linear_packet = linear.next_packet().unwrap();
+
+        assert_eq!(indexed_packet.ts(), linear_packet.ts());
+        assert_eq!(indexed_packet.buf(), linear_packet.buf());
+    }
+}
+
+#[test]
+fn program_config_5_1() {
+    // An audio specific config with a program config element for a 5.1 layout with surround
+    // channels at the back.
+    let asc = vec![0x11, 0x80, 0x04, 0xc8, 0x05, 0x00, 0x01, 0x08, 0x80, 0x00];
+
+    // A silent raw data block with a SCE, two CPEs, and a LFE.
+    let packet = vec![
+        0x00, 0xc8, 0x00, 0x01, 0x03, 0x20, 0x00, 0x0c, 0x80, 0x00, 0x11, 0x32, 0x00, 0x00, 0xc8,
+        0x00, 0x03, 0x06, 0x40, 0x00, 0x38,
+    ];
+
+    let mut params = CodecParameters::new();
+    params.for_codec(CODEC_TYPE_AAC).with_extra_data(asc.into_boxed_slice());
+
+    let mut decoder = AacDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+    let packet = Packet::new_from_slice(0, 0, 1024, &packet);
+    let buf = decoder.decode(&packet).unwrap();
+
+    assert_eq!(
+        buf.spec().channels,
+        Channels::FRONT_LEFT
+            | Channels::FRONT_RIGHT
+            | Channels::FRONT_CENTRE
+            | Channels::LFE1
+            | Channels::REAR_LEFT
+            | Channels::REAR_RIGHT
+    );
+    assert_eq!(buf.frames(), 1024);
+}
+
+#[test]
+fn adif_raw_data_blocks() {
+    // An ADIF header with a stereo program config element, followed by two silent raw data blocks.
+    // The first block contains a fill element.
+    let file = vec![
+        0x41, 0x44, 0x49, 0x46, 0x00, 0x01, 0x51, 0x80, 0x00, 0x00, 0x00, 0xa0, 0x80, 0x00, 0x04,
+        0x00, 0x03, 0x41, 0x42, 0x43, 0x20, 0x64, 0x00, 0x01, 0x68, 0x00, 0x0c, 0x40, 0x00, 0x1c,
+        0x20, 0x64, 0x00, 0x01, 0x68, 0x00, 0x0e,
+    ];
+
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(file)), Default::default());
+
+    let mut reader = AdifReader::try_new(source, &FormatOptions::default()).unwrap();
+
+    let params = &reader.tracks()[0].codec_params;
+
+    assert_eq!(params.sample_rate, Some(44100));
+    assert_eq!(params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+
+    let mut decoder = AacDecoder::try_new(params, &DecoderOptions::default()).unwrap();
+
+    for (ts, len) in [(0, 10), (1024, 7)] {
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.ts(), ts);
+        assert_eq!(packet.buf().len(), len);
+        assert_eq!(decoder.decode(&packet).unwrap().frames(), 1024);
+    }
+
+    assert!(matches!(reader.next_packet(), Err(errors::Error::IoError(_))));
+}
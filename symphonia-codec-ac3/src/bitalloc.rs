@@ -0,0 +1,252 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The parametric bit allocation routine of ATSC A/52 section 7.2.
+
+use crate::tables::*;
+
+/// The number of bit allocation bands.
+pub const NUM_BANDS: usize = 50;
+
+/// The maximum number of delta bit allocation segments.
+pub const MAX_DELTA_SEGMENTS: usize = 8;
+
+/// The bit allocation parameters shared by all channels.
+#[derive(Clone, Debug, Default)]
+pub struct BitAllocParams {
+    pub fscod: usize,
+    pub slow_decay: i32,
+    pub fast_decay: i32,
+    pub slow_gain: i32,
+    pub db_per_bit: i32,
+    pub floor: i32,
+}
+
+/// The delta bit allocation of a channel.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DeltaBitAlloc {
+    pub num_segments: usize,
+    pub offsets: [u8; MAX_DELTA_SEGMENTS],
+    pub lengths: [u8; MAX_DELTA_SEGMENTS],
+    pub values: [u8; MAX_DELTA_SEGMENTS],
+}
+
+/// The parameters of a channel for the masking curve calculation.
+#[derive(Copy, Clone, Debug)]
+pub struct MaskParams {
+    pub fast_gain: i32,
+    /// The initial fast and slow leak values. Only used by the coupling channel.
+    pub fast_leak: i32,
+    pub slow_leak: i32,
+}
+
+fn log_add(a: i32, b: i32) -> i32 {
+    let c = a - b;
+    let addr = (c.abs() >> 1).min(255) as usize;
+
+    if c >= 0 {
+        a + i32::from(LOG_ADD[addr])
+    }
+    else {
+        b + i32::from(LOG_ADD[addr])
+    }
+}
+
+fn calc_lowcomp(a: i32, b0: i32, b1: i32, band: usize) -> i32 {
+    if band < 7 {
+        if b0 + 256 == b1 {
+            384
+        }
+        else if b0 > b1 {
+            (a - 64).max(0)
+        }
+        else {
+            a
+        }
+    }
+    else if band < 20 {
+        if b0 + 256 == b1 {
+            320
+        }
+        else if b0 > b1 {
+            (a - 64).max(0)
+        }
+        else {
+            a
+        }
+    }
+    else {
+        (a - 128).max(0)
+    }
+}
+
+/// Calculate the power spectral density of each bin, and integrate it over each band.
+pub fn calc_psd(
+    exps: &[u8; 256],
+    start: usize,
+    end: usize,
+    psd: &mut [i32; 256],
+    band_psd: &mut [i32; NUM_BANDS],
+) {
+    for (psd, &exp) in psd[start..end].iter_mut().zip(&exps[start..end]) {
+        *psd = 3072 - (i32::from(exp) << 7);
+    }
+
+    let mut bin = start;
+    let mut band = usize::from(BIN_TO_BAND[start]);
+
+    loop {
+        let last = usize::from(BAND_START[band + 1]).min(end);
+
+        band_psd[band] = psd[bin];
+        bin += 1;
+
+        while bin < last {
+            band_psd[band] = log_add(band_psd[band], psd[bin]);
+            bin += 1;
+        }
+
+        band += 1;
+
+        if last >= end {
+            break;
+        }
+    }
+}
+
+/// Calculate the masking curve of each band.
+pub fn calc_mask(
+    params: &BitAllocParams,
+    chan: &MaskParams,
+    band_psd: &[i32; NUM_BANDS],
+    start: usize,
+    end: usize,
+    delta: Option<&DeltaBitAlloc>,
+    mask: &mut [i32; NUM_BANDS],
+) {
+    let band_start = usize::from(BIN_TO_BAND[start]);
+    let band_end = usize::from(BIN_TO_BAND[end - 1]) + 1;
+
+    let fast_gain = chan.fast_gain;
+
+    let mut excite = [0i32; NUM_BANDS];
+
+    let mut fast_leak = chan.fast_leak;
+    let mut slow_leak = chan.slow_leak;
+
+    let begin = if band_start == 0 {
+        // The low frequency compensation is not applied to the final band of the LFE channel.
+        let is_lfe_end = |band: usize| band_end == 7 && band == 6;
+
+        let mut lowcomp = calc_lowcomp(0, band_psd[0], band_psd[1], 0);
+        excite[0] = band_psd[0] - fast_gain - lowcomp;
+
+        lowcomp = calc_lowcomp(lowcomp, band_psd[1], band_psd[2], 1);
+        excite[1] = band_psd[1] - fast_gain - lowcomp;
+
+        let mut begin = 7;
+
+        for band in 2..7 {
+            if !is_lfe_end(band) {
+                lowcomp = calc_lowcomp(lowcomp, band_psd[band], band_psd[band + 1], band);
+            }
+
+            fast_leak = band_psd[band] - fast_gain;
+            slow_leak = band_psd[band] - params.slow_gain;
+            excite[band] = fast_leak - lowcomp;
+
+            if !is_lfe_end(band) && band_psd[band] <= band_psd[band + 1] {
+                begin = band + 1;
+                break;
+            }
+        }
+
+        for band in begin..band_end.min(22) {
+            if !is_lfe_end(band) {
+                lowcomp = calc_lowcomp(lowcomp, band_psd[band], band_psd[band + 1], band);
+            }
+
+            fast_leak = (fast_leak - params.fast_decay).max(band_psd[band] - fast_gain);
+            slow_leak = (slow_leak - params.slow_decay).max(band_psd[band] - params.slow_gain);
+            excite[band] = (fast_leak - lowcomp).max(slow_leak);
+        }
+
+        22
+    }
+    else {
+        band_start
+    };
+
+    for band in begin..band_end {
+        fast_leak = (fast_leak - params.fast_decay).max(band_psd[band] - fast_gain);
+        slow_leak = (slow_leak - params.slow_decay).max(band_psd[band] - params.slow_gain);
+        excite[band] = fast_leak.max(slow_leak);
+    }
+
+    for band in band_start..band_end {
+        if band_psd[band] < params.db_per_bit {
+            excite[band] += (params.db_per_bit - band_psd[band]) >> 2;
+        }
+
+        mask[band] = excite[band].max(i32::from(HEARING_THRESHOLD[band][params.fscod]));
+    }
+
+    // Apply the delta bit allocation.
+    if let Some(dba) = delta {
+        let mut band = 0;
+
+        for seg in 0..dba.num_segments {
+            band += usize::from(dba.offsets[seg]);
+
+            let value = i32::from(dba.values[seg]);
+            let delta = if value >= 4 { (value - 3) << 7 } else { (value - 4) << 7 };
+
+            for _ in 0..dba.lengths[seg] {
+                if band >= NUM_BANDS {
+                    break;
+                }
+
+                mask[band] += delta;
+                band += 1;
+            }
+        }
+    }
+}
+
+/// Calculate the bit allocation pointer of each bin.
+pub fn calc_bap(
+    params: &BitAllocParams,
+    mask: &[i32; NUM_BANDS],
+    psd: &[i32; 256],
+    start: usize,
+    end: usize,
+    snr_offset: i32,
+    bap: &mut [u8; 256],
+) {
+    let mut bin = start;
+    let mut band = usize::from(BIN_TO_BAND[start]);
+
+    loop {
+        let last = usize::from(BAND_START[band + 1]).min(end);
+
+        let mut m = (mask[band] - snr_offset - params.floor).max(0);
+        m &= 0x1fe0;
+        m += params.floor;
+
+        for (bap, &psd) in bap[bin..last].iter_mut().zip(&psd[bin..last]) {
+            let addr = ((psd - m) >> 5).clamp(0, 63) as usize;
+            *bap = BAP_TAB[addr];
+        }
+
+        bin = last;
+        band += 1;
+
+        if last >= end {
+            break;
+        }
+    }
+}
@@ -0,0 +1,1456 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{
+    AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec,
+};
+use symphonia_core::checksum::Crc16Ansi;
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_EAC3};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderLtr, Monitor, ReadBitsLtr};
+use symphonia_core::support_codec;
+
+use crate::bitalloc::*;
+use crate::dsp::Dsp;
+use crate::eac3::{self, AudioFrame};
+use crate::header::*;
+use crate::tables::*;
+
+/// The index of the coupling channel.
+pub const CPL_CH: usize = 0;
+
+/// The maximum number of channels, including the coupling channel.
+pub const MAX_CHANNELS: usize = 7;
+
+/// The maximum number of coupling bands.
+const MAX_CPL_BANDS: usize = 18;
+
+/// The maximum number of spectral extension bands.
+const MAX_SPX_BANDS: usize = 17;
+
+/// The end of the LFE channel.
+const LFE_END: usize = 7;
+
+/// The exponent strategy (`chexpstr`) indicating the exponents of the previous block are reused.
+pub const EXP_REUSE: u32 = 0;
+
+/// The SNR offset when the coarse and fine SNR offsets are both 0. No bits are allocated.
+const ZERO_SNR_OFFSET: i32 = -15 << 6;
+
+/// Delta bit allocation modes (`deltbae`).
+const DBA_REUSE: u32 = 0;
+const DBA_NEW: u32 = 1;
+const DBA_NONE: u32 = 2;
+
+/// The state of a channel that may persist between the audio blocks of a frame.
+#[derive(Clone)]
+struct Channel {
+    /// The first and last frequency bins.
+    start: usize,
+    end: usize,
+    exps: [u8; BLOCK_LEN],
+    bap: [u8; BLOCK_LEN],
+    coeffs: [f32; BLOCK_LEN],
+    block_switch: bool,
+    dither: bool,
+    in_cpl: bool,
+    cpl_coords: [f32; MAX_CPL_BANDS],
+    /// The coupling coordinates have been received since coupling was enabled.
+    cpl_coords_valid: bool,
+    in_spx: bool,
+    /// The spectral extension noise and signal blending factors, scaled by the spectral extension
+    /// coordinates.
+    spx_noise_blend: [f32; MAX_SPX_BANDS],
+    spx_signal_blend: [f32; MAX_SPX_BANDS],
+    /// The spectral extension coordinates have been received since spectral extension was enabled.
+    spx_coords_valid: bool,
+    fast_gain: i32,
+    snr_offset: i32,
+    delta: DeltaBitAlloc,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel {
+            start: 0,
+            end: 0,
+            exps: [0; BLOCK_LEN],
+            bap: [0; BLOCK_LEN],
+            coeffs: [0.0; BLOCK_LEN],
+            block_switch: false,
+            dither: false,
+            in_cpl: false,
+            cpl_coords: [0.0; MAX_CPL_BANDS],
+            cpl_coords_valid: false,
+            in_spx: false,
+            spx_noise_blend: [0.0; MAX_SPX_BANDS],
+            spx_signal_blend: [0.0; MAX_SPX_BANDS],
+            spx_coords_valid: false,
+            fast_gain: 0,
+            snr_offset: 0,
+            delta: Default::default(),
+        }
+    }
+}
+
+/// Buffered mantissas of the grouped quantizers. Groups are shared by all channels of an audio
+/// block.
+#[derive(Default)]
+struct MantissaGroups {
+    b1: [f32; 3],
+    b1_len: usize,
+    b2: [f32; 3],
+    b2_len: usize,
+    b4: [f32; 2],
+    b4_len: usize,
+}
+
+/// Dequantize a symmetric quantizer value.
+fn symmetric(q: u32, levels: u32) -> f32 {
+    (2 * q as i32 - (levels as i32 - 1)) as f32 / levels as f32
+}
+
+impl MantissaGroups {
+    fn read<B: ReadBitsLtr>(&mut self, bs: &mut B, bap: u8) -> Result<f32> {
+        let mantissa = match bap {
+            1 => {
+                if self.b1_len == 0 {
+                    let code = bs.read_bits_leq32(5)?;
+                    self.b1 = [
+                        symmetric(code % 3, 3),
+                        symmetric((code % 9) / 3, 3),
+                        symmetric(code / 9, 3),
+                    ];
+                    self.b1_len = 3;
+                }
+                self.b1_len -= 1;
+                self.b1[self.b1_len]
+            }
+            2 => {
+                if self.b2_len == 0 {
+                    let code = bs.read_bits_leq32(7)?;
+                    self.b2 = [
+                        symmetric(code % 5, 5),
+                        symmetric((code % 25) / 5, 5),
+                        symmetric(code / 25, 5),
+                    ];
+                    self.b2_len = 3;
+                }
+                self.b2_len -= 1;
+                self.b2[self.b2_len]
+            }
+            3 => symmetric(bs.read_bits_leq32(3)?, 7),
+            4 => {
+                if self.b4_len == 0 {
+                    let code = bs.read_bits_leq32(7)?;
+                    self.b4 = [symmetric(code % 11, 11), symmetric(code / 11, 11)];
+                    self.b4_len = 2;
+                }
+                self.b4_len -= 1;
+                self.b4[self.b4_len]
+            }
+            5 => symmetric(bs.read_bits_leq32(4)?, 15),
+            _ => {
+                // Asymmetric quantization, a two's complement fraction.
+                let bits = MANTISSA_BITS[usize::from(bap)];
+                let value = bs.read_bits_leq32_signed(bits)?;
+                value as f32 / (1 << (bits - 1)) as f32
+            }
+        };
+
+        Ok(mantissa)
+    }
+}
+
+/// Generates the pseudo-random values for dither and spectral extension noise.
+struct Random(u32);
+
+impl Random {
+    /// Generate a value in the range [-1, 1).
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+        ((self.0 >> 8) as f32 / (1 << 24) as f32 - 0.5) * 2.0
+    }
+
+    /// Generate a dither value in the range [-0.707, 0.707).
+    fn next_dither(&mut self) -> f32 {
+        self.next() * std::f32::consts::FRAC_1_SQRT_2
+    }
+}
+
+/// The state of the audio blocks of a frame.
+struct BlockState {
+    /// The frame is E-AC-3.
+    eac3: bool,
+    stream_type: StreamType,
+    /// The number of full bandwidth channels.
+    num_fbw: usize,
+    acmod: u32,
+    lfeon: bool,
+    frame: AudioFrame,
+    /// The channels. The coupling channel is first, followed by the full bandwidth channels in
+    /// coded order, and then the LFE channel.
+    chans: [Channel; MAX_CHANNELS],
+    cpl_in_use: bool,
+    cpl_band_struct: [bool; MAX_CPL_BANDS],
+    cpl_band_sizes: [usize; MAX_CPL_BANDS],
+    num_cpl_bands: usize,
+    phase_flags_in_use: bool,
+    phase_flags: [bool; MAX_CPL_BANDS],
+    /// The coupling leak parameters have not been received since coupling was enabled.
+    first_cpl_leak: bool,
+    spx_in_use: bool,
+    /// The first bin of the spectral extension copy region, and the first and last bins of the
+    /// extension region.
+    spx_copy_start: usize,
+    spx_start: usize,
+    spx_end: usize,
+    spx_band_struct: [bool; MAX_SPX_BANDS],
+    spx_band_sizes: [usize; MAX_SPX_BANDS],
+    num_spx_bands: usize,
+    num_rematrix_bands: usize,
+    rematrix_flags: [bool; 4],
+    dynamic_range: [f32; 2],
+    bit_alloc: BitAllocParams,
+    cpl_fast_leak: i32,
+    cpl_slow_leak: i32,
+}
+
+impl BlockState {
+    fn new() -> Self {
+        BlockState {
+            eac3: false,
+            stream_type: StreamType::Independent,
+            num_fbw: 0,
+            acmod: 0,
+            lfeon: false,
+            frame: AudioFrame::ac3(),
+            chans: Default::default(),
+            cpl_in_use: false,
+            cpl_band_struct: [false; MAX_CPL_BANDS],
+            cpl_band_sizes: [0; MAX_CPL_BANDS],
+            num_cpl_bands: 0,
+            phase_flags_in_use: false,
+            phase_flags: [false; MAX_CPL_BANDS],
+            first_cpl_leak: true,
+            spx_in_use: false,
+            spx_copy_start: 0,
+            spx_start: 0,
+            spx_end: 0,
+            spx_band_struct: [false; MAX_SPX_BANDS],
+            spx_band_sizes: [0; MAX_SPX_BANDS],
+            num_spx_bands: 0,
+            num_rematrix_bands: 0,
+            rematrix_flags: [false; 4],
+            dynamic_range: [1.0; 2],
+            bit_alloc: Default::default(),
+            cpl_fast_leak: 0,
+            cpl_slow_leak: 0,
+        }
+    }
+
+    /// Get the index of the LFE channel.
+    fn lfe_ch(&self) -> usize {
+        self.num_fbw + 1
+    }
+
+    /// Get the index of the last coded channel.
+    fn last_ch(&self) -> usize {
+        self.num_fbw + usize::from(self.lfeon)
+    }
+
+    /// Reset the state at the start of a frame.
+    fn start_frame(&mut self, info: &SyncInfo, frame: AudioFrame) {
+        self.eac3 = info.is_eac3();
+        self.stream_type = info.stream_type;
+        self.num_fbw = info.num_fbw_channels();
+        self.acmod = info.acmod;
+        self.lfeon = info.lfeon;
+        self.bit_alloc.fscod = info.fscod as usize;
+
+        for chan in self.chans.iter_mut() {
+            chan.cpl_coords_valid = false;
+            chan.spx_coords_valid = false;
+
+            if !frame.block_switch_syntax {
+                chan.block_switch = false;
+            }
+            if !frame.dither_flag_syntax {
+                chan.dither = true;
+            }
+            if frame.snr_offset_strategy == 0 {
+                chan.snr_offset = frame.snr_offset;
+            }
+        }
+
+        // The default bit allocation parameters of E-AC-3.
+        if !frame.bit_alloc_syntax {
+            self.bit_alloc.slow_decay = SLOW_DECAY[2];
+            self.bit_alloc.fast_decay = FAST_DECAY[1];
+            self.bit_alloc.slow_gain = SLOW_GAIN[1];
+            self.bit_alloc.db_per_bit = DB_PER_BIT[2];
+            self.bit_alloc.floor = FLOOR[7];
+        }
+
+        self.frame = frame;
+        self.first_cpl_leak = true;
+        self.spx_in_use = false;
+    }
+}
+
+/// Read the bit stream information following the synchronization information. Only the fields
+/// required to decode the audio blocks are retained.
+fn read_bsi<B: ReadBitsLtr>(bs: &mut B) -> Result<()> {
+    let _bsid = bs.read_bits_leq32(5)?;
+    let _bsmod = bs.read_bits_leq32(3)?;
+    let acmod = bs.read_bits_leq32(3)?;
+
+    // The downmix levels, and Dolby Surround mode, for the channels present.
+    if acmod & 0x1 != 0 && acmod != 0x1 {
+        let _cmixlev = bs.read_bits_leq32(2)?;
+    }
+    if acmod & 0x4 != 0 {
+        let _surmixlev = bs.read_bits_leq32(2)?;
+    }
+    if acmod == ACMOD_STEREO {
+        let _dsurmod = bs.read_bits_leq32(2)?;
+    }
+
+    let _lfeon = bs.read_bool()?;
+
+    // The dialogue normalization, compression, language, and production information. For dual
+    // mono, these are repeated for the second channel.
+    let num_programs = if acmod == ACMOD_DUAL_MONO { 2 } else { 1 };
+
+    for _ in 0..num_programs {
+        let _dialnorm = bs.read_bits_leq32(5)?;
+
+        if bs.read_bool()? {
+            let _compr = bs.read_bits_leq32(8)?;
+        }
+        if bs.read_bool()? {
+            let _langcod = bs.read_bits_leq32(8)?;
+        }
+        if bs.read_bool()? {
+            let _mixlevel = bs.read_bits_leq32(5)?;
+            let _roomtyp = bs.read_bits_leq32(2)?;
+        }
+    }
+
+    let _copyrightb = bs.read_bool()?;
+    let _origbs = bs.read_bool()?;
+
+    // Either time codes, or in the alternate syntax, the extended downmix information.
+    for _ in 0..2 {
+        if bs.read_bool()? {
+            bs.ignore_bits(14)?;
+        }
+    }
+
+    // Additional bit stream information.
+    if bs.read_bool()? {
+        let len = bs.read_bits_leq32(6)? + 1;
+        bs.ignore_bits(8 * len)?;
+    }
+
+    Ok(())
+}
+
+/// Read a band structure, and calculate the size of each band. Sub-bands are 12 bins wide, and
+/// each flag indicates if a sub-band is merged with the preceding band. Returns the number of
+/// bands.
+fn read_band_structure<B: ReadBitsLtr>(
+    bs: &mut B,
+    begin: usize,
+    end: usize,
+    band_struct: &mut [bool],
+    band_sizes: &mut [usize],
+) -> Result<usize> {
+    for flag in &mut band_struct[begin + 1..end] {
+        *flag = bs.read_bool()?;
+    }
+
+    Ok(band_sizes_from_structure(band_struct, begin, end, band_sizes))
+}
+
+/// Calculate the size of each band from a band structure. Returns the number of bands.
+fn band_sizes_from_structure(
+    band_struct: &[bool],
+    begin: usize,
+    end: usize,
+    band_sizes: &mut [usize],
+) -> usize {
+    let mut num_bands = 1;
+    band_sizes[0] = 12;
+
+    for &merged in &band_struct[begin + 1..end] {
+        if merged {
+            band_sizes[num_bands - 1] += 12;
+        }
+        else {
+            band_sizes[num_bands] = 12;
+            num_bands += 1;
+        }
+    }
+
+    num_bands
+}
+
+/// Read the grouped exponents of a channel. The first exponent is the absolute exponent.
+fn read_exponents<B: ReadBitsLtr>(
+    bs: &mut B,
+    strategy: u32,
+    abs_exp: u8,
+    num_groups: usize,
+    exps: &mut [u8],
+) -> Result<()> {
+    let group_size = 1 << (strategy - 1);
+
+    let mut exp = abs_exp;
+    let mut pos = 0;
+
+    for _ in 0..num_groups {
+        let code = bs.read_bits_leq32(7)?;
+
+        if code >= 125 {
+            return decode_error("ac3: invalid exponent group");
+        }
+
+        for diff in [code / 25, (code % 25) / 5, code % 5] {
+            exp = match (exp + diff as u8).checked_sub(2) {
+                Some(exp) if exp <= 24 => exp,
+                _ => return decode_error("ac3: exponent out of range"),
+            };
+
+            for _ in 0..group_size {
+                if pos < exps.len() {
+                    exps[pos] = exp;
+                }
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the delta bit allocation segments of a channel.
+fn read_delta<B: ReadBitsLtr>(bs: &mut B, delta: &mut DeltaBitAlloc) -> Result<()> {
+    delta.num_segments = bs.read_bits_leq32(3)? as usize + 1;
+
+    for seg in 0..delta.num_segments {
+        delta.offsets[seg] = bs.read_bits_leq32(5)? as u8;
+        delta.lengths[seg] = bs.read_bits_leq32(4)? as u8;
+        delta.values[seg] = bs.read_bits_leq32(3)? as u8;
+    }
+
+    Ok(())
+}
+
+/// Convert a dynamic range gain word to a linear gain.
+fn dynamic_range_gain(dynrng: u32) -> f32 {
+    // The upper 3 bits are a signed power-of-two exponent, and the lower 5 bits are the fractional
+    // part of a mantissa in the range [1, 2).
+    let exp = ((dynrng as u8 as i8) >> 5) as i32;
+    (1.0 + (dynrng & 0x1f) as f32 / 32.0) * 2f32.powi(exp)
+}
+
+/// AC-3 (Dolby Digital) and E-AC-3 (Dolby Digital Plus) decoder.
+///
+/// For E-AC-3, only the first program, independent substream 0 and its dependent substreams, is
+/// decoded.
+pub struct Ac3Decoder {
+    params: CodecParameters,
+    buf: AudioBuffer<f32>,
+    dsp: Dsp,
+    state: BlockState,
+    /// The delay line of each output channel, in coded order, of each substream of an access
+    /// unit.
+    delay: Vec<[[f32; BLOCK_LEN / 2]; MAX_CHANNELS - 1]>,
+    random: Random,
+}
+
+impl Ac3Decoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let buf = packet.buf();
+
+        // A packet may contain multiple frames, including the frames of other substreams.
+        let mut frames = Vec::new();
+        let mut pos = 0;
+
+        while pos < buf.len() {
+            if buf.len() - pos < SYNC_INFO_LEN {
+                return decode_error("ac3: packet too short");
+            }
+
+            let info = SyncInfo::read(&buf[pos..])?;
+
+            if buf.len() - pos < info.frame_len {
+                return decode_error("ac3: frame is truncated");
+            }
+
+            let frame = &buf[pos..pos + info.frame_len];
+
+            // The CRC of the entire frame, excluding the sync word, is 0 if the frame is valid.
+            let mut crc = Crc16Ansi::new(0);
+            crc.process_buf_bytes(&frame[2..]);
+
+            if crc.crc() != 0 {
+                return decode_error("ac3: crc mismatch");
+            }
+
+            frames.push((info, frame));
+            pos += info.frame_len;
+        }
+
+        // Group the frames of independent substream 0, and its dependent substreams, into access
+        // units. The frames of other programs are skipped.
+        let mut units: Vec<std::ops::Range<usize>> = Vec::new();
+        let mut in_program = false;
+
+        for (i, (info, _)) in frames.iter().enumerate() {
+            if info.stream_type != StreamType::Dependent {
+                in_program = info.substream_id == 0;
+
+                if in_program {
+                    units.push(i..i + 1);
+                }
+            }
+            else if let Some(unit) = units.last_mut().filter(|_| in_program) {
+                unit.end = i + 1;
+            }
+        }
+
+        if units.is_empty() {
+            return decode_error("ac3: packet has no independent substream");
+        }
+
+        let num_frames: usize = units.iter().map(|unit| frames[unit.start].0.duration()).sum();
+
+        if num_frames > FRAME_LEN {
+            return decode_error("ac3: too many audio blocks in packet");
+        }
+
+        self.buf.clear();
+
+        let mut offset = 0;
+
+        for unit in units {
+            let unit = &frames[unit];
+
+            // The location of each coded channel of each substream. Channels of dependent
+            // substreams replace those of the independent substream at the same location.
+            let locations = unit
+                .iter()
+                .map(|(info, frame)| info.channel_locations(frame))
+                .collect::<Result<Vec<_>>>()?;
+
+            let channels = locations.iter().flatten().fold(Channels::empty(), |acc, &ch| acc | ch);
+
+            let spec = SignalSpec::new(unit[0].0.sample_rate, channels);
+
+            // The audio buffer can only be created after the first frame is decoded.
+            if self.buf.is_unused() {
+                self.buf = AudioBuffer::new(FRAME_LEN as u64, spec);
+            }
+            else if self.buf.spec() != &spec {
+                return decode_error("ac3: invalid audio buffer signal spec for packet");
+            }
+
+            let duration = unit[0].0.duration();
+
+            self.buf.render_reserved(Some(duration));
+
+            if self.delay.len() < unit.len() {
+                self.delay.resize(unit.len(), [[0.0; BLOCK_LEN / 2]; MAX_CHANNELS - 1]);
+            }
+
+            for (sub, ((info, frame), locations)) in unit.iter().zip(&locations).enumerate() {
+                if info.duration() != duration {
+                    return decode_error("ac3: substream duration mismatch");
+                }
+
+                // Audio planes are ordered by channel bit position.
+                let planes: Vec<usize> = locations
+                    .iter()
+                    .map(|ch| (channels.bits() & (ch.bits() - 1)).count_ones() as usize)
+                    .collect();
+
+                self.decode_frame(info, frame, sub, &planes, offset)?;
+            }
+
+            offset += duration;
+        }
+
+        Ok(())
+    }
+
+    /// Decode a frame of substream `sub` of an access unit. Each coded channel is written to the
+    /// given audio plane, starting at `offset`.
+    fn decode_frame(
+        &mut self,
+        info: &SyncInfo,
+        frame: &[u8],
+        sub: usize,
+        planes: &[usize],
+        offset: usize,
+    ) -> Result<()> {
+        if info.fscod == 3 {
+            return unsupported_error("ac3: reduced sample rates are not supported");
+        }
+
+        let mut bs = BitReaderLtr::new(&frame[5..]);
+
+        let audio_frame = if info.is_eac3() {
+            eac3::read_bsi(&mut bs, info)?;
+            AudioFrame::read(&mut bs, info)?
+        }
+        else {
+            read_bsi(&mut bs)?;
+            AudioFrame::ac3()
+        };
+
+        self.state.start_frame(info, audio_frame);
+
+        for blk in 0..info.num_blocks {
+            self.read_audio_block(&mut bs, blk)?;
+            self.reconstruct_block();
+
+            // Transform each channel to the time domain.
+            let start = offset + blk * BLOCK_LEN;
+
+            for (i, &plane) in planes.iter().enumerate() {
+                let chan = &self.state.chans[i + 1];
+                let out = &mut self.buf.chan_mut(plane)[start..start + BLOCK_LEN];
+
+                self.dsp.synth(&chan.coeffs, chan.block_switch, &mut self.delay[sub][i], out);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the spectral extension strategy of an audio block.
+    fn read_spx_strategy(&mut self, bs: &mut BitReaderLtr<'_>, blk: usize) -> Result<()> {
+        let s = &mut self.state;
+
+        for chan in &mut s.chans[1..=s.num_fbw] {
+            chan.in_spx = s.acmod == ACMOD_MONO || bs.read_bool()?;
+        }
+
+        let spxstrtf = bs.read_bits_leq32(2)? as usize;
+
+        let begin = match bs.read_bits_leq32(3)? as usize {
+            spxbegf @ 0..=5 => spxbegf + 2,
+            spxbegf => 2 * spxbegf - 3,
+        };
+
+        let end = match bs.read_bits_leq32(3)? as usize {
+            spxendf @ 0..=2 => spxendf + 5,
+            spxendf => 2 * spxendf + 3,
+        };
+
+        s.spx_copy_start = spxstrtf * 12 + 25;
+        s.spx_start = begin * 12 + 25;
+        s.spx_end = end * 12 + 25;
+
+        if begin >= end || s.spx_copy_start >= s.spx_start {
+            return decode_error("ac3: invalid spectral extension range");
+        }
+
+        if blk == 0 {
+            s.spx_band_struct = DEFAULT_SPX_BAND_STRUCT;
+        }
+
+        s.num_spx_bands = if bs.read_bool()? {
+            read_band_structure(bs, begin, end, &mut s.spx_band_struct, &mut s.spx_band_sizes)?
+        }
+        else {
+            band_sizes_from_structure(&s.spx_band_struct, begin, end, &mut s.spx_band_sizes)
+        };
+
+        Ok(())
+    }
+
+    /// Read the spectral extension coordinates of an audio block.
+    fn read_spx_coords(&mut self, bs: &mut BitReaderLtr<'_>) -> Result<()> {
+        let s = &mut self.state;
+
+        for chan in &mut s.chans[1..=s.num_fbw] {
+            if !chan.in_spx {
+                chan.spx_coords_valid = false;
+                continue;
+            }
+
+            // The coordinates are always present in the first block using spectral extension.
+            if chan.spx_coords_valid && !bs.read_bool()? {
+                continue;
+            }
+
+            let blend = bs.read_bits_leq32(5)? as f32 / 32.0;
+            let master = 3 * bs.read_bits_leq32(2)? as i32;
+
+            let mut bin = s.spx_start;
+
+            for bnd in 0..s.num_spx_bands {
+                let size = s.spx_band_sizes[bnd];
+
+                // The proportion of noise increases with frequency.
+                let ratio = ((bin + size / 2) as f32 / s.spx_end as f32 - blend).clamp(0.0, 1.0);
+
+                bin += size;
+
+                let exp = bs.read_bits_leq32(4)? as i32;
+                let mant = bs.read_bits_leq32(2)? as f32;
+
+                let coord = if exp == 15 { mant / 4.0 } else { (mant + 4.0) / 8.0 };
+
+                // The coordinates are scaled by 32 to reconstruct the extension bands.
+                let coord = 32.0 * coord * 2f32.powi(-(exp + master));
+
+                // The noise has a variance of 1/3, and is scaled to unity.
+                chan.spx_noise_blend[bnd] = (3.0 * ratio).sqrt() * coord;
+                chan.spx_signal_blend[bnd] = (1.0 - ratio).sqrt() * coord;
+            }
+
+            chan.spx_coords_valid = true;
+        }
+
+        Ok(())
+    }
+
+    /// Read an audio block, and dequantize its transform coefficients.
+    fn read_audio_block(&mut self, bs: &mut BitReaderLtr<'_>, blk: usize) -> Result<()> {
+        let eac3 = self.state.eac3;
+
+        {
+            let s = &mut self.state;
+
+            if s.frame.block_switch_syntax {
+                for chan in &mut s.chans[1..=s.num_fbw] {
+                    chan.block_switch = bs.read_bool()?;
+                }
+            }
+
+            if s.frame.dither_flag_syntax {
+                for chan in &mut s.chans[1..=s.num_fbw] {
+                    chan.dither = bs.read_bool()?;
+                }
+            }
+
+            // Dynamic range control words. The second is for the second channel of a dual mono
+            // stream.
+            let num_dynrng = if s.acmod == ACMOD_DUAL_MONO { 2 } else { 1 };
+
+            for i in 0..num_dynrng {
+                if bs.read_bool()? {
+                    s.dynamic_range[i] = dynamic_range_gain(bs.read_bits_leq32(8)?);
+                }
+                else if blk == 0 {
+                    s.dynamic_range[i] = 1.0;
+                }
+            }
+        }
+
+        // Spectral extension strategy, and coordinates.
+        if eac3 && (blk == 0 || bs.read_bool()?) {
+            self.state.spx_in_use = bs.read_bool()?;
+
+            if self.state.spx_in_use {
+                self.read_spx_strategy(bs, blk)?;
+            }
+        }
+
+        if self.state.spx_in_use {
+            self.read_spx_coords(bs)?;
+        }
+        else {
+            for chan in self.state.chans.iter_mut() {
+                chan.in_spx = false;
+                chan.spx_coords_valid = false;
+            }
+        }
+
+        let s = &mut self.state;
+
+        let num_fbw = s.num_fbw;
+        let lfe_ch = s.lfe_ch();
+        let fbw = 1..=num_fbw;
+
+        // Coupling strategy. For E-AC-3, the presence of the strategy, and the use of coupling,
+        // are signalled for the entire frame.
+        let cpl_strategy = if eac3 { s.frame.cpl_strategy[blk] } else { bs.read_bool()? };
+
+        if cpl_strategy {
+            if !eac3 {
+                s.frame.cpl_in_use[blk] = bs.read_bool()?;
+            }
+
+            if s.frame.cpl_in_use[blk] {
+                if s.acmod < ACMOD_STEREO {
+                    return decode_error("ac3: coupling in a mono or dual mono stream");
+                }
+
+                if eac3 && bs.read_bool()? {
+                    return unsupported_error("ac3: enhanced coupling is not supported");
+                }
+
+                // Both channels of an E-AC-3 stereo stream are always coupled.
+                for ch in fbw.clone() {
+                    s.chans[ch].in_cpl = (eac3 && s.acmod == ACMOD_STEREO) || bs.read_bool()?;
+                }
+
+                s.phase_flags_in_use = s.acmod == ACMOD_STEREO && bs.read_bool()?;
+
+                let begin = bs.read_bits_leq32(4)? as usize;
+
+                // When spectral extension is in use, coupling ends where it begins.
+                let end = if s.spx_in_use {
+                    (s.spx_start - 37) / 12
+                }
+                else {
+                    bs.read_bits_leq32(4)? as usize + 3
+                };
+
+                if begin >= end {
+                    return decode_error("ac3: invalid coupling range");
+                }
+
+                s.chans[CPL_CH].start = begin * 12 + 37;
+                s.chans[CPL_CH].end = end * 12 + 37;
+
+                // Sub-bands may be merged into bands by the band structure. For E-AC-3, the
+                // structure is optional, and defaults to the previous, or default, structure.
+                if eac3 && blk == 0 {
+                    s.cpl_band_struct = DEFAULT_CPL_BAND_STRUCT;
+                }
+
+                s.num_cpl_bands = if !eac3 || bs.read_bool()? {
+                    read_band_structure(
+                        bs,
+                        begin,
+                        end,
+                        &mut s.cpl_band_struct,
+                        &mut s.cpl_band_sizes,
+                    )?
+                }
+                else {
+                    band_sizes_from_structure(&s.cpl_band_struct, begin, end, &mut s.cpl_band_sizes)
+                };
+            }
+            else {
+                for ch in fbw.clone() {
+                    s.chans[ch].in_cpl = false;
+                    s.chans[ch].cpl_coords_valid = false;
+                }
+
+                s.first_cpl_leak = true;
+            }
+        }
+        else if !eac3 {
+            if blk == 0 {
+                return decode_error("ac3: missing coupling strategy in first block");
+            }
+
+            s.frame.cpl_in_use[blk] = s.frame.cpl_in_use[blk - 1];
+        }
+
+        s.cpl_in_use = s.frame.cpl_in_use[blk];
+
+        // Coupling coordinates.
+        if s.cpl_in_use {
+            let mut any_coords = false;
+
+            for ch in fbw.clone() {
+                let chan = &mut s.chans[ch];
+
+                if !chan.in_cpl {
+                    chan.cpl_coords_valid = false;
+                    continue;
+                }
+
+                // For E-AC-3, the coordinates are always present in the first block using
+                // coupling.
+                if (eac3 && !chan.cpl_coords_valid) || bs.read_bool()? {
+                    any_coords = true;
+
+                    let master = 3 * bs.read_bits_leq32(2)? as i32;
+
+                    for bnd in 0..s.num_cpl_bands {
+                        let exp = bs.read_bits_leq32(4)? as i32;
+                        let mant = bs.read_bits_leq32(4)? as f32;
+
+                        let coord = if exp == 15 { mant / 16.0 } else { (mant + 16.0) / 32.0 };
+
+                        // The coordinates are scaled by 8 to reconstruct the channel.
+                        chan.cpl_coords[bnd] = 8.0 * coord * 2f32.powi(-(exp + master));
+                    }
+
+                    chan.cpl_coords_valid = true;
+                }
+                else if !chan.cpl_coords_valid {
+                    return decode_error("ac3: missing coupling coordinates");
+                }
+            }
+
+            if s.phase_flags_in_use {
+                if any_coords {
+                    for bnd in 0..s.num_cpl_bands {
+                        s.phase_flags[bnd] = bs.read_bool()?;
+                    }
+                }
+            }
+            else {
+                s.phase_flags = [false; MAX_CPL_BANDS];
+            }
+        }
+
+        // Rematrixing. For E-AC-3, the strategy is always present in the first block.
+        if s.acmod == ACMOD_STEREO {
+            if (eac3 && blk == 0) || bs.read_bool()? {
+                // If coupling, or spectral extension, begins at or below bin 61, there are fewer
+                // rematrixing bands.
+                s.num_rematrix_bands = if s.cpl_in_use {
+                    match s.chans[CPL_CH].start {
+                        37 => 2,
+                        49 | 61 => 3,
+                        _ => 4,
+                    }
+                }
+                else if s.spx_in_use && s.spx_start <= 61 {
+                    3
+                }
+                else {
+                    4
+                };
+
+                for flag in s.rematrix_flags.iter_mut().take(s.num_rematrix_bands) {
+                    *flag = bs.read_bool()?;
+                }
+            }
+            else if blk == 0 {
+                s.num_rematrix_bands = 0;
+            }
+        }
+
+        // Exponent strategies. For E-AC-3, these are signalled for the entire frame.
+        if !eac3 {
+            let strategies = &mut s.frame.exp_strategy[blk];
+
+            *strategies = [EXP_REUSE; MAX_CHANNELS];
+
+            if s.cpl_in_use {
+                strategies[CPL_CH] = bs.read_bits_leq32(2)?;
+            }
+
+            for ch in fbw.clone() {
+                strategies[ch] = bs.read_bits_leq32(2)?;
+            }
+
+            if s.lfeon {
+                strategies[lfe_ch] = bs.read_bits_leq32(1)?;
+            }
+        }
+
+        let exp_strategy = s.frame.exp_strategy[blk];
+
+        if blk == 0
+            && ((s.cpl_in_use && exp_strategy[CPL_CH] == EXP_REUSE)
+                || exp_strategy[1..=num_fbw].contains(&EXP_REUSE)
+                || (s.lfeon && exp_strategy[lfe_ch] == EXP_REUSE))
+        {
+            return decode_error("ac3: exponents must be present in the first block");
+        }
+
+        // Channel bandwidths.
+        for ch in fbw.clone() {
+            if s.chans[ch].in_cpl {
+                s.chans[ch].end = s.chans[CPL_CH].start;
+            }
+            else if s.chans[ch].in_spx {
+                s.chans[ch].end = s.spx_start;
+            }
+            else if exp_strategy[ch] != EXP_REUSE {
+                let chbwcod = bs.read_bits_leq32(6)? as usize;
+
+                if chbwcod > 60 {
+                    return decode_error("ac3: invalid channel bandwidth code");
+                }
+
+                s.chans[ch].end = chbwcod * 3 + 73;
+            }
+        }
+
+        // Exponents.
+        if s.cpl_in_use && exp_strategy[CPL_CH] != EXP_REUSE {
+            let strategy = exp_strategy[CPL_CH];
+            let cpl = &mut s.chans[CPL_CH];
+            let abs_exp = (bs.read_bits_leq32(4)? << 1) as u8;
+            let num_groups = (cpl.end - cpl.start) / (3 << (strategy - 1));
+            read_exponents(bs, strategy, abs_exp, num_groups, &mut cpl.exps[cpl.start..])?;
+        }
+
+        for ch in fbw.clone() {
+            if exp_strategy[ch] != EXP_REUSE {
+                let chan = &mut s.chans[ch];
+                let group_size = 3 << (exp_strategy[ch] - 1);
+                let num_groups = (chan.end - 1 + group_size - 3) / group_size;
+
+                chan.exps[0] = bs.read_bits_leq32(4)? as u8;
+                read_exponents(
+                    bs,
+                    exp_strategy[ch],
+                    chan.exps[0],
+                    num_groups,
+                    &mut chan.exps[1..],
+                )?;
+
+                let _gainrng = bs.read_bits_leq32(2)?;
+            }
+        }
+
+        if s.lfeon && exp_strategy[lfe_ch] != EXP_REUSE {
+            let lfe = &mut s.chans[lfe_ch];
+            lfe.end = LFE_END;
+            lfe.exps[0] = bs.read_bits_leq32(4)? as u8;
+            read_exponents(bs, 1, lfe.exps[0], 2, &mut lfe.exps[1..LFE_END])?;
+        }
+
+        let first = if s.cpl_in_use { CPL_CH } else { 1 };
+        let last = s.last_ch();
+
+        // Bit allocation parametric information.
+        if s.frame.bit_alloc_syntax {
+            if bs.read_bool()? {
+                let params = &mut s.bit_alloc;
+                params.slow_decay = SLOW_DECAY[bs.read_bits_leq32(2)? as usize];
+                params.fast_decay = FAST_DECAY[bs.read_bits_leq32(2)? as usize];
+                params.slow_gain = SLOW_GAIN[bs.read_bits_leq32(2)? as usize];
+                params.db_per_bit = DB_PER_BIT[bs.read_bits_leq32(2)? as usize];
+                params.floor = FLOOR[bs.read_bits_leq32(3)? as usize];
+            }
+            else if blk == 0 {
+                return decode_error("ac3: missing bit allocation information in first block");
+            }
+        }
+
+        // Signal-to-noise ratio offsets, and for AC-3, the fast gains. For E-AC-3, these may only
+        // be present in the first block.
+        if !eac3 || blk == 0 {
+            if s.frame.snr_offset_strategy != 0 && bs.read_bool()? {
+                let coarse = (bs.read_bits_leq32(6)? as i32 - 15) << 4;
+
+                let mut snr_offset = 0;
+
+                for ch in first..=last {
+                    // With strategy 1, a single fine offset applies to all channels.
+                    if ch == first || s.frame.snr_offset_strategy == 2 {
+                        snr_offset = (coarse + bs.read_bits_leq32(4)? as i32) << 2;
+                    }
+
+                    s.chans[ch].snr_offset = snr_offset;
+
+                    if !eac3 {
+                        s.chans[ch].fast_gain = FAST_GAIN[bs.read_bits_leq32(3)? as usize];
+                    }
+                }
+            }
+            else if !eac3 && blk == 0 {
+                return decode_error("ac3: missing snr offsets in first block");
+            }
+        }
+
+        // Fast gains.
+        if s.frame.fast_gain_syntax && bs.read_bool()? {
+            for chan in &mut s.chans[first..=last] {
+                chan.fast_gain = FAST_GAIN[bs.read_bits_leq32(3)? as usize];
+            }
+        }
+        else if eac3 && blk == 0 {
+            for chan in &mut s.chans[first..=last] {
+                chan.fast_gain = FAST_GAIN[4];
+            }
+        }
+
+        // The SNR offset of the original AC-3 stream, for converters.
+        if eac3 && s.stream_type == StreamType::Independent && bs.read_bool()? {
+            let _convsnroffst = bs.read_bits_leq32(10)?;
+        }
+
+        // Coupling leak initialization. For E-AC-3, it is always present in the first block using
+        // coupling.
+        if s.cpl_in_use {
+            if (eac3 && s.first_cpl_leak) || bs.read_bool()? {
+                s.cpl_fast_leak = ((bs.read_bits_leq32(3)? as i32) << 8) + 768;
+                s.cpl_slow_leak = ((bs.read_bits_leq32(3)? as i32) << 8) + 768;
+            }
+            else if blk == 0 {
+                return decode_error("ac3: missing coupling leak in first block");
+            }
+
+            s.first_cpl_leak = false;
+        }
+
+        // Delta bit allocation.
+        if s.frame.dba_syntax && bs.read_bool()? {
+            let mut modes = [DBA_REUSE; MAX_CHANNELS];
+
+            for mode in &mut modes[first..=num_fbw] {
+                *mode = bs.read_bits_leq32(2)?;
+            }
+
+            for (chan, &mode) in s.chans[first..=num_fbw].iter_mut().zip(&modes[first..=num_fbw]) {
+                match mode {
+                    DBA_REUSE => (),
+                    DBA_NEW => read_delta(bs, &mut chan.delta)?,
+                    DBA_NONE => chan.delta.num_segments = 0,
+                    _ => return decode_error("ac3: reserved delta bit allocation mode"),
+                }
+            }
+        }
+        else if blk == 0 {
+            for chan in s.chans.iter_mut() {
+                chan.delta.num_segments = 0;
+            }
+        }
+
+        // Unused data.
+        if s.frame.skip_syntax && bs.read_bool()? {
+            let len = bs.read_bits_leq32(9)?;
+            bs.ignore_bits(8 * len)?;
+        }
+
+        // Calculate the bit allocation of each channel.
+        for ch in first..=last {
+            let is_lfe = ch == lfe_ch;
+
+            let chan = &mut s.chans[ch];
+
+            if ch == CPL_CH {
+                chan.dither = true;
+            }
+            else {
+                chan.start = 0;
+            }
+
+            if chan.snr_offset == ZERO_SNR_OFFSET {
+                chan.bap = [0; BLOCK_LEN];
+                continue;
+            }
+
+            let (fast_leak, slow_leak) =
+                if ch == CPL_CH { (s.cpl_fast_leak, s.cpl_slow_leak) } else { (0, 0) };
+
+            let mask_params = MaskParams { fast_gain: chan.fast_gain, fast_leak, slow_leak };
+
+            let mut psd = [0; BLOCK_LEN];
+            let mut band_psd = [0; NUM_BANDS];
+            let mut mask = [0; NUM_BANDS];
+
+            let delta = if is_lfe { None } else { Some(&chan.delta) };
+
+            calc_psd(&chan.exps, chan.start, chan.end, &mut psd, &mut band_psd);
+            calc_mask(
+                &s.bit_alloc,
+                &mask_params,
+                &band_psd,
+                chan.start,
+                chan.end,
+                delta,
+                &mut mask,
+            );
+            calc_bap(
+                &s.bit_alloc,
+                &mask,
+                &psd,
+                chan.start,
+                chan.end,
+                chan.snr_offset,
+                &mut chan.bap,
+            );
+        }
+
+        // Mantissas. The mantissas of the coupling channel follow those of the first coupled
+        // channel.
+        let mut groups = MantissaGroups::default();
+        let mut got_cpl = false;
+
+        for ch in 1..=last {
+            self.read_mantissas(bs, &mut groups, ch)?;
+
+            if self.state.cpl_in_use && self.state.chans[ch].in_cpl && !got_cpl {
+                self.read_mantissas(bs, &mut groups, CPL_CH)?;
+                got_cpl = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the mantissas of a channel, and calculate its transform coefficients.
+    fn read_mantissas(
+        &mut self,
+        bs: &mut BitReaderLtr<'_>,
+        groups: &mut MantissaGroups,
+        ch: usize,
+    ) -> Result<()> {
+        let chan = &mut self.state.chans[ch];
+
+        for bin in chan.start..chan.end {
+            let bap = chan.bap[bin];
+
+            let mantissa = if bap == 0 {
+                if chan.dither {
+                    self.random.next_dither()
+                }
+                else {
+                    0.0
+                }
+            }
+            else {
+                groups.read(bs, bap)?
+            };
+
+            chan.coeffs[bin] = mantissa * 2f32.powi(-i32::from(chan.exps[bin]));
+        }
+
+        Ok(())
+    }
+
+    /// Decouple, rematrix, scale, and extend the transform coefficients of an audio block.
+    fn reconstruct_block(&mut self) {
+        let s = &mut self.state;
+
+        let (cpl, chans) = s.chans.split_at_mut(1);
+        let cpl = &cpl[0];
+
+        let num_chans = s.num_fbw + usize::from(s.lfeon);
+
+        for (i, chan) in chans.iter_mut().enumerate().take(num_chans) {
+            let ch = i + 1;
+
+            let end = if s.cpl_in_use && chan.in_cpl {
+                let mut bin = cpl.start;
+
+                for bnd in 0..s.num_cpl_bands {
+                    let mut coord = chan.cpl_coords[bnd];
+
+                    if ch == 2 && s.phase_flags[bnd] {
+                        coord = -coord;
+                    }
+
+                    for bin in bin..bin + s.cpl_band_sizes[bnd] {
+                        chan.coeffs[bin] = if chan.dither || cpl.bap[bin] != 0 {
+                            cpl.coeffs[bin] * coord
+                        }
+                        else {
+                            0.0
+                        };
+                    }
+
+                    bin += s.cpl_band_sizes[bnd];
+                }
+
+                cpl.end
+            }
+            else {
+                chan.end
+            };
+
+            chan.coeffs[end..].iter_mut().for_each(|c| *c = 0.0);
+        }
+
+        // Rematrixing of the left and right channels.
+        if s.acmod == ACMOD_STEREO {
+            let end = chans[0].end.min(chans[1].end);
+
+            let (left, right) = chans.split_at_mut(1);
+            let (left, right) = (&mut left[0].coeffs, &mut right[0].coeffs);
+
+            for bnd in 0..s.num_rematrix_bands {
+                if !s.rematrix_flags[bnd] {
+                    continue;
+                }
+
+                let start = REMATRIX_BANDS[bnd];
+                let end = REMATRIX_BANDS[bnd + 1].min(end);
+
+                for bin in start..end {
+                    let (l, r) = (left[bin], right[bin]);
+                    left[bin] = l + r;
+                    right[bin] = l - r;
+                }
+            }
+        }
+
+        // Dynamic range compression.
+        for (i, chan) in chans.iter_mut().enumerate().take(num_chans) {
+            let gain = if s.acmod == ACMOD_DUAL_MONO && i == 1 {
+                s.dynamic_range[1]
+            }
+            else {
+                s.dynamic_range[0]
+            };
+
+            chan.coeffs.iter_mut().for_each(|c| *c *= gain);
+        }
+
+        if s.spx_in_use {
+            self.extend_spectrum();
+        }
+    }
+
+    /// Synthesize the extension bands of the channels using spectral extension by translating the
+    /// coefficients of the copy region, and blending them with noise.
+    fn extend_spectrum(&mut self) {
+        let s = &mut self.state;
+
+        let (copy_start, spx_start) = (s.spx_copy_start, s.spx_start);
+        let band_sizes = &s.spx_band_sizes[..s.num_spx_bands];
+
+        // Split the translation into sections. The copy region wraps back to its start when it is
+        // exhausted, or when the next band would not fit. A notch filter may be applied at each
+        // wrap point, and at the start of the extension region.
+        let mut wraps = [false; MAX_SPX_BANDS];
+        let mut sections = Vec::with_capacity(2 * MAX_SPX_BANDS + 1);
+
+        wraps[0] = true;
+
+        let mut bin = copy_start;
+
+        for (bnd, &size) in band_sizes.iter().enumerate() {
+            if bin + size > spx_start {
+                sections.push(bin - copy_start);
+                bin = copy_start;
+                wraps[bnd] = true;
+            }
+
+            let mut i = 0;
+
+            while i < size {
+                if bin == spx_start {
+                    sections.push(bin - copy_start);
+                    bin = copy_start;
+                }
+
+                let len = (size - i).min(spx_start - bin);
+                bin += len;
+                i += len;
+            }
+        }
+
+        sections.push(bin - copy_start);
+
+        for ch in 1..=s.num_fbw {
+            let chan = &mut s.chans[ch];
+
+            if !chan.in_spx {
+                continue;
+            }
+
+            let coeffs = &mut chan.coeffs;
+
+            let mut dst = spx_start;
+
+            for &len in &sections {
+                coeffs.copy_within(copy_start..copy_start + len, dst);
+                dst += len;
+            }
+
+            // The RMS energy of each band of translated coefficients.
+            let mut rms = [0.0; MAX_SPX_BANDS];
+            let mut bin = spx_start;
+
+            for (rms, &size) in rms.iter_mut().zip(band_sizes) {
+                let energy: f32 = coeffs[bin..bin + size].iter().map(|c| c * c).sum();
+                *rms = (energy / size as f32).sqrt();
+                bin += size;
+            }
+
+            // Attenuate the coefficients around the wrap points.
+            if let Some(code) = s.frame.spx_atten[ch] {
+                let atten = |i: u32| 2f32.powf(-(((i + 1) * (code + 1)) as f32) / 15.0);
+                let gains = [atten(0), atten(1), atten(2), atten(1), atten(0)];
+
+                let mut bin = spx_start - 2;
+
+                for (&wrap, &size) in wraps.iter().zip(band_sizes) {
+                    if wrap {
+                        for (c, gain) in coeffs[bin..bin + 5].iter_mut().zip(gains) {
+                            *c *= gain;
+                        }
+                    }
+                    bin += size;
+                }
+            }
+
+            // Scale, and blend with noise.
+            let mut bin = spx_start;
+
+            for (bnd, &size) in band_sizes.iter().enumerate() {
+                let noise_scale = chan.spx_noise_blend[bnd] * rms[bnd];
+                let signal_scale = chan.spx_signal_blend[bnd];
+
+                for c in &mut coeffs[bin..bin + size] {
+                    *c = *c * signal_scale + noise_scale * self.random.next();
+                }
+
+                bin += size;
+            }
+        }
+    }
+}
+
+impl Decoder for Ac3Decoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports AC-3 and E-AC-3.
+        if params.codec != CODEC_TYPE_EAC3 {
+            return unsupported_error("ac3: invalid codec type");
+        }
+
+        Ok(Ac3Decoder {
+            params: params.clone(),
+            buf: AudioBuffer::unused(),
+            dsp: Dsp::new(),
+            state: BlockState::new(),
+            delay: Vec::new(),
+            random: Random(1),
+        })
+    }
+
+    fn reset(&mut self) {
+        self.delay.clear();
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_EAC3, "ac3", "Dolby Digital (AC-3, E-AC-3)")]
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
@@ -0,0 +1,323 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_EAC3};
+use symphonia_core::errors::{seek_error, Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use crate::header::{StreamType, SyncInfo, SYNC_INFO_LEN, SYNC_WORD};
+
+/// The number of bytes to start searching before the estimated position of a frame when seeking.
+/// At 44.1 kHz, the length of a frame varies by one word, so the estimate may be slightly late.
+const SEEK_BACKOFF: u64 = 8;
+
+/// Dolby Digital (AC-3) and Dolby Digital Plus (E-AC-3) elementary stream format reader.
+///
+/// `Ac3Reader` implements a demuxer for a stream of AC-3 or E-AC-3 synchronization frames. Each
+/// packet is an access unit containing a frame of independent substream 0, and the frames of any
+/// other substreams that follow it.
+pub struct Ac3Reader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    /// The average length of an access unit in bytes.
+    avg_frame_len: f64,
+    /// The number of samples per channel in an access unit.
+    frame_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for Ac3Reader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "ac3",
+            "Dolby Digital (AC-3, E-AC-3)",
+            &["ac3", "ec3", "eac3"],
+            &["audio/ac3", "audio/eac3"],
+            &[&[0x0b, 0x77]]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Synchronizes to, and reads, the synchronization information of the next frame. The reader is
+/// positioned after the synchronization information.
+fn read_sync_info<B: ReadBytes + SeekBuffered>(reader: &mut B) -> Result<(SyncInfo, [u8; 7])> {
+    let mut sync = 0u16;
+
+    loop {
+        while sync != SYNC_WORD {
+            sync = (sync << 8) | u16::from(reader.read_u8()?);
+        }
+
+        let mut buf = [0; SYNC_INFO_LEN];
+        buf[..2].copy_from_slice(&sync.to_be_bytes());
+        reader.read_buf_exact(&mut buf[2..])?;
+
+        match SyncInfo::read(&buf) {
+            Ok(info) => return Ok((info, buf)),
+            Err(Error::DecodeError(_)) => {
+                // Resume searching from the byte following the false synchronization word.
+                reader.seek_buffered_rev(SYNC_INFO_LEN - 1);
+                sync = 0;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Synchronizes to, and reads, the next frame.
+fn read_frame(reader: &mut MediaSourceStream) -> Result<(SyncInfo, Vec<u8>)> {
+    let (info, header) = read_sync_info(reader)?;
+
+    // The packet is the entire frame, including the synchronization information.
+    let mut buf = vec![0; info.frame_len];
+    buf[..SYNC_INFO_LEN].copy_from_slice(&header);
+    reader.read_buf_exact(&mut buf[SYNC_INFO_LEN..])?;
+
+    Ok((info, buf))
+}
+
+/// Synchronizes to, and reads, the next frame that is followed by another frame, or the end of the
+/// stream.
+fn read_frame_strict(reader: &mut MediaSourceStream) -> Result<(SyncInfo, Vec<u8>)> {
+    loop {
+        let (info, buf) = read_frame(reader)?;
+
+        // Get the position before trying to read the next synchronization word.
+        let pos = reader.pos();
+
+        // If the read fails then the stream may have ended, and this check cannot be performed.
+        if let Ok(sync) = reader.read_be_u16() {
+            if sync != SYNC_WORD {
+                warn!("skipping junk at {} bytes", pos - buf.len() as u64);
+
+                // Seek back to the second byte of the rejected frame to prevent synchronizing to
+                // the same spot again.
+                reader.seek_buffered_rev(buf.len() + 1);
+                continue;
+            }
+        }
+
+        // Jump back to the position before the next synchronization word was read.
+        reader.seek_buffered(pos);
+
+        break Ok((info, buf));
+    }
+}
+
+/// Synchronizes to, and reads, the next access unit. Frames preceding the first frame of the access
+/// unit are skipped. If `strict` is true, the first frame must be followed by another frame, or the
+/// end of the stream.
+fn read_access_unit(reader: &mut MediaSourceStream, strict: bool) -> Result<(SyncInfo, Vec<u8>)> {
+    let (info, mut buf) = loop {
+        let (info, buf) = if strict { read_frame_strict(reader)? } else { read_frame(reader)? };
+
+        if info.starts_access_unit() {
+            break (info, buf);
+        }
+
+        debug!("skipping frame of substream {} outside of an access unit", info.substream_id);
+    };
+
+    // Append the frames of the other substreams until the start of the next access unit.
+    loop {
+        let pos = reader.pos();
+
+        let mut header = [0; SYNC_INFO_LEN];
+
+        let is_substream = match reader.read_buf_exact(&mut header) {
+            Ok(_) => SyncInfo::read(&header).ok().filter(|next| !next.starts_access_unit()),
+            Err(_) => None,
+        };
+
+        match is_substream {
+            Some(next) => {
+                let start = buf.len();
+                buf.resize(start + next.frame_len, 0);
+                buf[start..start + SYNC_INFO_LEN].copy_from_slice(&header);
+                reader.read_buf_exact(&mut buf[start + SYNC_INFO_LEN..])?;
+            }
+            None => {
+                reader.seek_buffered(pos);
+                break;
+            }
+        }
+    }
+
+    Ok((info, buf))
+}
+
+impl FormatReader for Ac3Reader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let (info, buf) = read_access_unit(&mut source, true)?;
+
+        // Rewind back to the start of the access unit.
+        source.seek_buffered_rev(buf.len());
+
+        let first_frame_pos = source.pos();
+
+        // The channels of the stream are the channels of all substreams of the access unit. Each
+        // access unit has a constant duration, and a bit rate that is constant within the stream.
+        let mut channels = Channels::empty();
+        let mut avg_frame_len = 0.0;
+        let mut pos = 0;
+
+        while pos < buf.len() {
+            let frame = &buf[pos..];
+            let sub = SyncInfo::read(frame)?;
+
+            if sub.starts_access_unit() {
+                channels |= sub.channels();
+            }
+            else if sub.stream_type == StreamType::Dependent {
+                for location in sub.channel_locations(frame)? {
+                    channels |= location;
+                }
+            }
+
+            avg_frame_len += sub.avg_frame_len();
+            pos += sub.frame_len;
+        }
+
+        let frame_dur = info.duration() as u64;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_EAC3)
+            .with_sample_rate(info.sample_rate)
+            .with_time_base(TimeBase::new(1, info.sample_rate))
+            .with_channels(channels)
+            .with_max_frames_per_packet(frame_dur)
+            .with_frames_per_block(frame_dur);
+
+        if let Some(byte_len) = source.byte_len() {
+            let n_frames = ((byte_len - first_frame_pos) as f64 / avg_frame_len).round() as u64;
+            params.with_n_frames(n_frames * frame_dur);
+        }
+
+        Ok(Ac3Reader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            first_frame_pos,
+            avg_frame_len,
+            frame_dur,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (info, buf) = read_access_unit(&mut self.reader, false)?;
+
+        let ts = self.next_packet_ts;
+        let dur = info.duration() as u64;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = self.tracks[0].codec_params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        let frame_idx = required_ts / self.frame_dur;
+
+        if self.reader.is_seekable() {
+            // Since the bit rate is constant, the position of the frame can be calculated.
+            let offset = (frame_idx as f64 * self.avg_frame_len) as u64;
+            let pos = self.first_frame_pos + offset.saturating_sub(SEEK_BACKOFF);
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            let (_, buf) = read_access_unit(&mut self.reader, true)?;
+
+            // Rewind back to the start of the access unit.
+            self.reader.seek_buffered_rev(buf.len());
+
+            let pos = self.reader.pos();
+
+            // Determine the index of the access unit from its position.
+            let offset = (pos - self.first_frame_pos) as f64;
+            self.next_packet_ts = (offset / self.avg_frame_len).round() as u64 * self.frame_dur;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            if required_ts < self.next_packet_ts {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            while self.next_packet_ts + self.frame_dur <= required_ts {
+                let (info, _) = read_access_unit(&mut self.reader, false)?;
+                self.next_packet_ts += info.duration() as u64;
+            }
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,126 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::dsp::mdct::Imdct;
+
+use crate::header::BLOCK_LEN;
+
+/// The Kaiser-Bessel derived window alpha.
+const WINDOW_ALPHA: f64 = 5.0;
+
+/// The synthesis filter bank.
+pub struct Dsp {
+    /// The first half of the 512-point window.
+    window: [f32; BLOCK_LEN],
+    imdct_long: Imdct,
+    imdct_short: Imdct,
+    long_buf: [f32; 2 * BLOCK_LEN],
+    short_buf: [f32; BLOCK_LEN],
+    /// The middle half of the transform output.
+    mid: [f32; BLOCK_LEN],
+}
+
+impl Dsp {
+    pub fn new() -> Self {
+        // The A/52 transforms are scaled by -2.
+        Dsp {
+            window: kbd_window(),
+            imdct_long: Imdct::new_scaled(BLOCK_LEN, -2.0),
+            imdct_short: Imdct::new_scaled(BLOCK_LEN / 2, -2.0),
+            long_buf: [0.0; 2 * BLOCK_LEN],
+            short_buf: [0.0; BLOCK_LEN],
+            mid: [0.0; BLOCK_LEN],
+        }
+    }
+
+    /// Transform the coefficients of one channel of an audio block, overlap-add the result with
+    /// the delay line, and write the block to `out`.
+    ///
+    /// If `is_short` is true, the coefficients are two interleaved 256-point transforms.
+    pub fn synth(
+        &mut self,
+        coeffs: &[f32; BLOCK_LEN],
+        is_short: bool,
+        delay: &mut [f32; BLOCK_LEN / 2],
+        out: &mut [f32],
+    ) {
+        const N2: usize = BLOCK_LEN / 2;
+        const N4: usize = BLOCK_LEN / 4;
+
+        // Each transform is reduced to the middle half of its output, the remainder of which is
+        // implied by symmetry.
+        if is_short {
+            let mut short = [0.0; N2];
+
+            for (i, half) in self.mid.chunks_exact_mut(N2).enumerate() {
+                for (s, &c) in short.iter_mut().zip(coeffs.iter().skip(i).step_by(2)) {
+                    *s = c;
+                }
+
+                self.imdct_short.imdct(&short, &mut self.short_buf);
+                half.copy_from_slice(&self.short_buf[N4..N4 + N2]);
+            }
+        }
+        else {
+            self.imdct_long.imdct(coeffs, &mut self.long_buf);
+            self.mid.copy_from_slice(&self.long_buf[N2..N2 + BLOCK_LEN]);
+        }
+
+        // Window and overlap-add the first half with the delay line.
+        let (first, second) = self.mid.split_at(N2);
+
+        for i in 0..N2 {
+            let j = BLOCK_LEN - 1 - i;
+
+            let d = delay[i];
+            let m = first[N2 - 1 - i];
+            let wi = self.window[i];
+            let wj = self.window[j];
+
+            out[i] = d * wj - m * wi;
+            out[j] = d * wi + m * wj;
+        }
+
+        // The second half is overlapped with the next block.
+        delay.copy_from_slice(second);
+    }
+}
+
+/// Generate the first half of the Kaiser-Bessel derived window.
+fn kbd_window() -> [f32; BLOCK_LEN] {
+    let n = BLOCK_LEN;
+    let alpha2 = (WINDOW_ALPHA * std::f64::consts::PI / n as f64).powi(2);
+
+    let mut window = [0.0; BLOCK_LEN];
+    let mut kernel = [0.0f64; BLOCK_LEN];
+
+    let mut sum = 0.0;
+
+    for (i, k) in kernel.iter_mut().enumerate() {
+        sum += bessel_i0((i * (n - i)) as f64 * alpha2);
+        *k = sum;
+    }
+
+    sum += 1.0;
+
+    for (w, &k) in window.iter_mut().zip(kernel.iter()) {
+        *w = (k / sum).sqrt() as f32;
+    }
+
+    window
+}
+
+fn bessel_i0(x: f64) -> f64 {
+    let mut val = 1.0;
+
+    for n in (1..64).rev() {
+        val *= x / f64::from(n * n);
+        val += 1.0;
+    }
+
+    val
+}
@@ -0,0 +1,314 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The E-AC-3 (Dolby Digital Plus) frame syntax of ATSC A/52 annex E.
+
+use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::io::ReadBitsLtr;
+
+use crate::decoder::{CPL_CH, EXP_REUSE, MAX_CHANNELS};
+use crate::header::*;
+use crate::tables::FRAME_EXP_STRATEGIES;
+
+/// The frame-level syntax of the audio blocks (`audfrm`). For AC-3 frames, this is fixed, and the
+/// strategies are read from each audio block instead.
+pub struct AudioFrame {
+    /// The exponent strategy of each channel of each block.
+    pub exp_strategy: [[u32; MAX_CHANNELS]; NUM_BLOCKS],
+    /// A coupling strategy is present in the block (`cplstre`).
+    pub cpl_strategy: [bool; NUM_BLOCKS],
+    /// Coupling is in use in the block (`cplinu`).
+    pub cpl_in_use: [bool; NUM_BLOCKS],
+    pub block_switch_syntax: bool,
+    pub dither_flag_syntax: bool,
+    pub bit_alloc_syntax: bool,
+    pub fast_gain_syntax: bool,
+    pub dba_syntax: bool,
+    pub skip_syntax: bool,
+    /// The SNR offset strategy (`snroffststr`). If 0, `snr_offset` applies to all channels of all
+    /// blocks.
+    pub snr_offset_strategy: u32,
+    pub snr_offset: i32,
+    /// The spectral extension attenuation code of each channel, if attenuation is enabled.
+    pub spx_atten: [Option<u32>; MAX_CHANNELS],
+}
+
+impl AudioFrame {
+    /// Get the frame-level syntax of an AC-3 frame.
+    pub fn ac3() -> Self {
+        AudioFrame {
+            exp_strategy: [[EXP_REUSE; MAX_CHANNELS]; NUM_BLOCKS],
+            cpl_strategy: [false; NUM_BLOCKS],
+            cpl_in_use: [false; NUM_BLOCKS],
+            block_switch_syntax: true,
+            dither_flag_syntax: true,
+            bit_alloc_syntax: true,
+            fast_gain_syntax: false,
+            dba_syntax: true,
+            skip_syntax: true,
+            snr_offset_strategy: 2,
+            snr_offset: 0,
+            spx_atten: [None; MAX_CHANNELS],
+        }
+    }
+
+    /// Read the frame-level syntax of an E-AC-3 frame.
+    pub fn read<B: ReadBitsLtr>(bs: &mut B, info: &SyncInfo) -> Result<Self> {
+        let num_blocks = info.num_blocks;
+        let num_fbw = info.num_fbw_channels();
+        let last_ch = num_fbw + usize::from(info.lfeon);
+
+        // Frames with less than 6 blocks always use the AC-3 style exponent strategy syntax, and
+        // never use the adaptive hybrid transform.
+        let (exp_strategy_syntax, aht_syntax) = if num_blocks == NUM_BLOCKS {
+            (bs.read_bool()?, bs.read_bool()?)
+        }
+        else {
+            (true, false)
+        };
+
+        let mut frame = AudioFrame::ac3();
+
+        frame.snr_offset_strategy = bs.read_bits_leq32(2)?;
+
+        let transient_proc_syntax = bs.read_bool()?;
+
+        frame.block_switch_syntax = bs.read_bool()?;
+        frame.dither_flag_syntax = bs.read_bool()?;
+        frame.bit_alloc_syntax = bs.read_bool()?;
+        frame.fast_gain_syntax = bs.read_bool()?;
+        frame.dba_syntax = bs.read_bool()?;
+        frame.skip_syntax = bs.read_bool()?;
+
+        let spx_atten_syntax = bs.read_bool()?;
+
+        // Coupling strategy, and use, of each block.
+        if info.acmod >= ACMOD_STEREO {
+            for blk in 0..num_blocks {
+                frame.cpl_strategy[blk] = blk == 0 || bs.read_bool()?;
+
+                frame.cpl_in_use[blk] = if frame.cpl_strategy[blk] {
+                    bs.read_bool()?
+                }
+                else {
+                    frame.cpl_in_use[blk - 1]
+                };
+            }
+        }
+
+        let num_cpl_blocks = frame.cpl_in_use.iter().filter(|&&cpl| cpl).count();
+
+        // Exponent strategies, either for each block, or a combination for the entire frame.
+        if exp_strategy_syntax {
+            for blk in 0..num_blocks {
+                let first = if frame.cpl_in_use[blk] { CPL_CH } else { 1 };
+
+                for ch in first..=num_fbw {
+                    frame.exp_strategy[blk][ch] = bs.read_bits_leq32(2)?;
+                }
+            }
+        }
+        else {
+            let first = if num_cpl_blocks > 0 { CPL_CH } else { 1 };
+
+            for ch in first..=num_fbw {
+                let strategies = &FRAME_EXP_STRATEGIES[bs.read_bits_leq32(5)? as usize];
+
+                for (blk_strategies, &strategy) in frame.exp_strategy.iter_mut().zip(strategies) {
+                    blk_strategies[ch] = strategy;
+                }
+            }
+        }
+
+        if info.lfeon {
+            for blk in 0..num_blocks {
+                frame.exp_strategy[blk][num_fbw + 1] = bs.read_bits_leq32(1)?;
+            }
+        }
+
+        // The exponent strategies of the original AC-3 stream, for converters.
+        if info.stream_type == StreamType::Independent
+            && (num_blocks == NUM_BLOCKS || bs.read_bool()?)
+        {
+            bs.ignore_bits(5 * num_fbw as u32)?;
+        }
+
+        // The adaptive hybrid transform may only be used by channels with exponents in the first
+        // block only, and by the coupling channel if coupling is used with a single strategy.
+        if aht_syntax {
+            let first = if num_cpl_blocks == NUM_BLOCKS { CPL_CH } else { 1 };
+
+            for ch in first..=last_ch {
+                let eligible = (1..NUM_BLOCKS).all(|blk| {
+                    frame.exp_strategy[blk][ch] == EXP_REUSE
+                        && (ch != CPL_CH || !frame.cpl_strategy[blk])
+                });
+
+                if eligible && bs.read_bool()? {
+                    return unsupported_error("ac3: adaptive hybrid transform is not supported");
+                }
+            }
+        }
+
+        if frame.snr_offset_strategy == 0 {
+            let csnroffst = bs.read_bits_leq32(6)? as i32;
+            let fsnroffst = bs.read_bits_leq32(4)? as i32;
+            frame.snr_offset = (((csnroffst - 15) << 4) + fsnroffst) << 2;
+        }
+
+        // Transient pre-noise processing is an optional post-processing step that is not
+        // performed.
+        if transient_proc_syntax {
+            for _ in 0..num_fbw {
+                if bs.read_bool()? {
+                    let _transprocloc = bs.read_bits_leq32(10)?;
+                    let _transproclen = bs.read_bits_leq32(8)?;
+                }
+            }
+        }
+
+        if spx_atten_syntax {
+            for ch in 1..=num_fbw {
+                if bs.read_bool()? {
+                    frame.spx_atten[ch] = Some(bs.read_bits_leq32(5)?);
+                }
+            }
+        }
+
+        // The block start information is only useful for seeking to a block within the frame.
+        if num_blocks > 1 && bs.read_bool()? {
+            let num_words = info.frame_len / 2;
+            let bits = 4 + usize::BITS - (num_words - 1).leading_zeros();
+            bs.ignore_bits((num_blocks as u32 - 1) * bits)?;
+        }
+
+        Ok(frame)
+    }
+}
+
+/// Read the E-AC-3 bit stream information following the synchronization information. None of the
+/// fields are required to decode the audio blocks.
+pub fn read_bsi<B: ReadBitsLtr>(bs: &mut B, info: &SyncInfo) -> Result<()> {
+    let _bsid = bs.read_bits_leq32(5)?;
+
+    // The dialogue normalization and compression gain are repeated for dual mono.
+    let num_programs = if info.acmod == ACMOD_DUAL_MONO { 2 } else { 1 };
+
+    for _ in 0..num_programs {
+        let _dialnorm = bs.read_bits_leq32(5)?;
+
+        if bs.read_bool()? {
+            let _compr = bs.read_bits_leq32(8)?;
+        }
+    }
+
+    if info.stream_type == StreamType::Dependent && bs.read_bool()? {
+        let _chanmap = bs.read_bits_leq32(16)?;
+    }
+
+    // Mixing metadata.
+    if bs.read_bool()? {
+        if info.acmod > ACMOD_STEREO {
+            let _dmixmod = bs.read_bits_leq32(2)?;
+
+            // The Lt/Rt and Lo/Ro downmix levels of the centre and surround channels.
+            if info.acmod & 0x1 != 0 {
+                bs.ignore_bits(6)?;
+            }
+            if info.acmod & 0x4 != 0 {
+                bs.ignore_bits(6)?;
+            }
+        }
+
+        if info.lfeon && bs.read_bool()? {
+            let _lfemixlevcod = bs.read_bits_leq32(5)?;
+        }
+
+        if info.stream_type == StreamType::Independent {
+            // Program, and external program, scale factors.
+            for _ in 0..num_programs + 1 {
+                if bs.read_bool()? {
+                    bs.ignore_bits(6)?;
+                }
+            }
+
+            match bs.read_bits_leq32(2)? {
+                1 => bs.ignore_bits(5)?,
+                2 => bs.ignore_bits(12)?,
+                3 => {
+                    let len = bs.read_bits_leq32(5)? + 2;
+                    bs.ignore_bits(8 * len)?;
+                }
+                _ => (),
+            }
+
+            // Panning information for mono and dual mono.
+            if info.acmod < ACMOD_STEREO {
+                for _ in 0..num_programs {
+                    if bs.read_bool()? {
+                        bs.ignore_bits(14)?;
+                    }
+                }
+            }
+
+            // Mixing configuration of the frame, or of each block.
+            if bs.read_bool()? {
+                for _ in 0..info.num_blocks {
+                    if info.num_blocks == 1 || bs.read_bool()? {
+                        bs.ignore_bits(5)?;
+                    }
+                }
+            }
+        }
+    }
+
+    // Informational metadata.
+    if bs.read_bool()? {
+        let _bsmod = bs.read_bits_leq32(3)?;
+        let _copyrightb = bs.read_bool()?;
+        let _origbs = bs.read_bool()?;
+
+        if info.acmod == ACMOD_STEREO {
+            let _dsurmod = bs.read_bits_leq32(2)?;
+            let _dheadphonmod = bs.read_bits_leq32(2)?;
+        }
+        if info.acmod >= 6 {
+            let _dsurexmod = bs.read_bits_leq32(2)?;
+        }
+
+        for _ in 0..num_programs {
+            if bs.read_bool()? {
+                let _mixlevel = bs.read_bits_leq32(5)?;
+                let _roomtyp = bs.read_bits_leq32(2)?;
+                let _adconvtyp = bs.read_bool()?;
+            }
+        }
+
+        if info.fscod < 3 {
+            let _sourcefscod = bs.read_bool()?;
+        }
+    }
+
+    if info.stream_type == StreamType::Independent && info.num_blocks != NUM_BLOCKS {
+        let _convsync = bs.read_bool()?;
+    }
+
+    // The frame size code of the original AC-3 stream, for converters.
+    if info.stream_type == StreamType::Converted
+        && (info.num_blocks == NUM_BLOCKS || bs.read_bool()?)
+    {
+        let _frmsizecod = bs.read_bits_leq32(6)?;
+    }
+
+    // Additional bit stream information.
+    if bs.read_bool()? {
+        let len = bs.read_bits_leq32(6)? + 1;
+        bs.ignore_bits(8 * len)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,440 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+
+use crate::tables::{BIT_RATES, FRAME_SIZES, SAMPLE_RATES};
+
+/// The synchronization word at the start of every frame.
+pub const SYNC_WORD: u16 = 0x0b77;
+
+/// The number of bytes required to read the synchronization information, and the start of the bit
+/// stream information up-to and including `lfeon`.
+pub const SYNC_INFO_LEN: usize = 7;
+
+/// The maximum number of audio blocks in a frame.
+pub const NUM_BLOCKS: usize = 6;
+
+/// The number of samples per channel in an audio block.
+pub const BLOCK_LEN: usize = 256;
+
+/// The maximum number of samples per channel in a frame.
+pub const FRAME_LEN: usize = NUM_BLOCKS * BLOCK_LEN;
+
+/// The maximum `bsid` of an AC-3 bit stream.
+const MAX_AC3_BSID: u32 = 8;
+
+/// The range of `bsid` of an E-AC-3 bit stream.
+const EAC3_BSIDS: std::ops::RangeInclusive<u32> = 11..=16;
+
+/// The number of audio blocks in an E-AC-3 frame, indexed by `numblkscod`.
+const EAC3_NUM_BLOCKS: [usize; 4] = [1, 2, 3, 6];
+
+/// Audio coding mode (`acmod`) for dual mono (1+1).
+pub const ACMOD_DUAL_MONO: u32 = 0;
+
+/// Audio coding mode (`acmod`) for mono (1/0).
+pub const ACMOD_MONO: u32 = 1;
+
+/// Audio coding mode (`acmod`) for stereo (2/0).
+pub const ACMOD_STEREO: u32 = 2;
+
+/// The channel locations of an E-AC-3 custom channel map (`chanmap`), from the most significant
+/// bit. Locations with two channels are coded left then right.
+const CUSTOM_CHANNEL_LOCATIONS: [&[Channels]; 16] = [
+    &[Channels::FRONT_LEFT],
+    &[Channels::FRONT_CENTRE],
+    &[Channels::FRONT_RIGHT],
+    &[Channels::SIDE_LEFT],
+    &[Channels::SIDE_RIGHT],
+    &[Channels::FRONT_LEFT_CENTRE, Channels::FRONT_RIGHT_CENTRE],
+    &[Channels::REAR_LEFT, Channels::REAR_RIGHT],
+    &[Channels::REAR_CENTRE],
+    &[Channels::TOP_CENTRE],
+    // The left and right surround direct channels have no equivalent.
+    &[],
+    &[Channels::FRONT_LEFT_WIDE, Channels::FRONT_RIGHT_WIDE],
+    &[Channels::FRONT_LEFT_HIGH, Channels::FRONT_RIGHT_HIGH],
+    &[Channels::FRONT_CENTRE_HIGH],
+    &[Channels::TOP_REAR_LEFT, Channels::TOP_REAR_RIGHT],
+    &[Channels::LFE2],
+    &[Channels::LFE1],
+];
+
+/// The type of an E-AC-3 substream (`strmtyp`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamType {
+    /// An independent substream. AC-3 frames are always independent.
+    Independent,
+    /// A dependent substream, adding or replacing channels of the preceding independent
+    /// substream.
+    Dependent,
+    /// An independent substream converted from AC-3.
+    Converted,
+}
+
+/// The synchronization information, and the parts of the bit stream information needed to
+/// describe the stream.
+#[derive(Copy, Clone, Debug)]
+pub struct SyncInfo {
+    /// The sample rate code. A code of 3 indicates a reduced E-AC-3 sample rate.
+    pub fscod: u32,
+    pub sample_rate: u32,
+    /// The bit rate in kbps.
+    pub bit_rate: u32,
+    /// The length of the frame in bytes, including the synchronization information.
+    pub frame_len: usize,
+    /// The bit stream identification.
+    pub bsid: u32,
+    pub stream_type: StreamType,
+    /// The substream identification. Always 0 for AC-3.
+    pub substream_id: u32,
+    /// The number of audio blocks in the frame.
+    pub num_blocks: usize,
+    /// The audio coding mode.
+    pub acmod: u32,
+    /// The LFE channel is present.
+    pub lfeon: bool,
+}
+
+impl SyncInfo {
+    /// Read the synchronization information from the start of a frame. The buffer must be atleast
+    /// `SYNC_INFO_LEN` bytes long.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let mut bs = BitReaderLtr::new(buf);
+
+        if bs.read_bits_leq32(16)? != u32::from(SYNC_WORD) {
+            return decode_error("ac3: missing sync word");
+        }
+
+        // The bit stream identification is at the same position in AC-3 and E-AC-3 frames, and
+        // determines the syntax of the remaining fields.
+        let bsid = match buf.get(5) {
+            Some(&byte) => u32::from(byte >> 3),
+            None => return decode_error("ac3: frame is truncated"),
+        };
+
+        if EAC3_BSIDS.contains(&bsid) {
+            return Self::read_eac3(&mut bs, bsid);
+        }
+
+        if bsid > MAX_AC3_BSID {
+            return unsupported_error("ac3: unsupported bit stream id");
+        }
+
+        // The CRC of the first 5/8ths of the frame.
+        let _crc1 = bs.read_bits_leq32(16)?;
+
+        let fscod = bs.read_bits_leq32(2)?;
+        let frmsizecod = bs.read_bits_leq32(6)? as usize;
+
+        let sample_rate = match SAMPLE_RATES.get(fscod as usize) {
+            Some(&sample_rate) => sample_rate,
+            None => return decode_error("ac3: reserved sample rate"),
+        };
+
+        let frame_len = match FRAME_SIZES.get(frmsizecod) {
+            Some(sizes) => 2 * usize::from(sizes[fscod as usize]),
+            None => return decode_error("ac3: invalid frame size code"),
+        };
+
+        let bit_rate = BIT_RATES[frmsizecod >> 1];
+
+        let _bsid = bs.read_bits_leq32(5)?;
+        let _bsmod = bs.read_bits_leq32(3)?;
+        let acmod = bs.read_bits_leq32(3)?;
+
+        // Skip the mix levels and surround mode, the presence of which depend on the audio coding
+        // mode.
+        if acmod & 0x1 != 0 && acmod != 0x1 {
+            bs.ignore_bits(2)?;
+        }
+        if acmod & 0x4 != 0 {
+            bs.ignore_bits(2)?;
+        }
+        if acmod == ACMOD_STEREO {
+            bs.ignore_bits(2)?;
+        }
+
+        let lfeon = bs.read_bool()?;
+
+        Ok(SyncInfo {
+            fscod,
+            sample_rate,
+            bit_rate,
+            frame_len,
+            bsid,
+            stream_type: StreamType::Independent,
+            substream_id: 0,
+            num_blocks: NUM_BLOCKS,
+            acmod,
+            lfeon,
+        })
+    }
+
+    /// Read the synchronization information of an E-AC-3 frame following the synchronization
+    /// word.
+    fn read_eac3(bs: &mut BitReaderLtr<'_>, bsid: u32) -> Result<Self> {
+        let stream_type = match bs.read_bits_leq32(2)? {
+            0 => StreamType::Independent,
+            1 => StreamType::Dependent,
+            2 => StreamType::Converted,
+            _ => return decode_error("ac3: reserved stream type"),
+        };
+
+        let substream_id = bs.read_bits_leq32(3)?;
+
+        let frame_len = 2 * (bs.read_bits_leq32(11)? as usize + 1);
+
+        if frame_len < SYNC_INFO_LEN {
+            return decode_error("ac3: invalid frame size");
+        }
+
+        let fscod = bs.read_bits_leq32(2)?;
+
+        // A sample rate code of 3 signals a reduced sample rate in place of the number of blocks.
+        let (sample_rate, num_blocks) = if fscod == 3 {
+            match SAMPLE_RATES.get(bs.read_bits_leq32(2)? as usize) {
+                Some(&sample_rate) => (sample_rate / 2, NUM_BLOCKS),
+                None => return decode_error("ac3: reserved sample rate"),
+            }
+        }
+        else {
+            let numblkscod = bs.read_bits_leq32(2)? as usize;
+            (SAMPLE_RATES[fscod as usize], EAC3_NUM_BLOCKS[numblkscod])
+        };
+
+        let acmod = bs.read_bits_leq32(3)?;
+        let lfeon = bs.read_bool()?;
+
+        let bit_rate = (8 * frame_len as u64 * u64::from(sample_rate)
+            / (1000 * (num_blocks * BLOCK_LEN) as u64)) as u32;
+
+        Ok(SyncInfo {
+            fscod,
+            sample_rate,
+            bit_rate,
+            frame_len,
+            bsid,
+            stream_type,
+            substream_id,
+            num_blocks,
+            acmod,
+            lfeon,
+        })
+    }
+
+    /// Returns true if the frame is E-AC-3.
+    pub fn is_eac3(&self) -> bool {
+        self.bsid > MAX_AC3_BSID
+    }
+
+    /// Returns true if the frame belongs to independent substream 0. Such a frame starts an access
+    /// unit, and is followed by the frames of any other substreams covering the same period.
+    pub fn starts_access_unit(&self) -> bool {
+        self.stream_type != StreamType::Dependent && self.substream_id == 0
+    }
+
+    /// Get the number of samples per channel in the frame.
+    pub fn duration(&self) -> usize {
+        self.num_blocks * BLOCK_LEN
+    }
+
+    /// Get the average length of a frame of the stream in bytes.
+    pub fn avg_frame_len(&self) -> f64 {
+        if self.is_eac3() {
+            self.frame_len as f64
+        }
+        else {
+            // At 44.1 kHz, the length of an AC-3 frame varies to maintain the bit rate.
+            f64::from(self.bit_rate) * 1000.0 / 8.0 * self.duration() as f64
+                / f64::from(self.sample_rate)
+        }
+    }
+
+    /// Get the number of full bandwidth channels.
+    pub fn num_fbw_channels(&self) -> usize {
+        self.coded_channels().len()
+    }
+
+    /// Get the full bandwidth channels in the order they are coded.
+    fn coded_channels(&self) -> &'static [Channels] {
+        match self.acmod {
+            1 => &[Channels::FRONT_LEFT],
+            0 | 2 => &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT],
+            3 => &[Channels::FRONT_LEFT, Channels::FRONT_CENTRE, Channels::FRONT_RIGHT],
+            4 => &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::REAR_CENTRE],
+            5 => &[
+                Channels::FRONT_LEFT,
+                Channels::FRONT_CENTRE,
+                Channels::FRONT_RIGHT,
+                Channels::REAR_CENTRE,
+            ],
+            6 => &[
+                Channels::FRONT_LEFT,
+                Channels::FRONT_RIGHT,
+                Channels::SIDE_LEFT,
+                Channels::SIDE_RIGHT,
+            ],
+            _ => &[
+                Channels::FRONT_LEFT,
+                Channels::FRONT_CENTRE,
+                Channels::FRONT_RIGHT,
+                Channels::SIDE_LEFT,
+                Channels::SIDE_RIGHT,
+            ],
+        }
+    }
+
+    /// Get the channels of the stream.
+    pub fn channels(&self) -> Channels {
+        let channels = self.coded_channels().iter().fold(Channels::empty(), |acc, &ch| acc | ch);
+
+        if self.lfeon {
+            channels | Channels::LFE1
+        }
+        else {
+            channels
+        }
+    }
+
+    /// Get the location of each coded channel, in the order the channels are coded. The LFE
+    /// channel, if present, is last. The frame is required to read the custom channel map of a
+    /// dependent substream.
+    pub fn channel_locations(&self, frame: &[u8]) -> Result<Vec<Channels>> {
+        let mut locations = self.coded_channels().to_vec();
+
+        if self.lfeon {
+            locations.push(Channels::LFE1);
+        }
+
+        if self.stream_type == StreamType::Dependent {
+            if let Some(chanmap) = read_channel_map(frame, self.acmod)? {
+                let mut custom = Vec::new();
+
+                for (i, &chans) in CUSTOM_CHANNEL_LOCATIONS.iter().enumerate() {
+                    if chanmap & (0x8000 >> i) == 0 {
+                        continue;
+                    }
+
+                    // A flagged location with no equivalent cannot be represented.
+                    if chans.is_empty() {
+                        return unsupported_error("ac3: unsupported channel location");
+                    }
+
+                    custom.extend_from_slice(chans);
+                }
+
+                if custom.len() != locations.len() {
+                    return decode_error("ac3: channel map does not match the coded channels");
+                }
+
+                locations = custom;
+            }
+        }
+
+        Ok(locations)
+    }
+}
+
+/// Read the custom channel map (`chanmap`), if present, from the bit stream information of a
+/// dependent E-AC-3 frame.
+fn read_channel_map(frame: &[u8], acmod: u32) -> Result<Option<u16>> {
+    let mut bs = BitReaderLtr::new(frame.get(5..).unwrap_or_default());
+
+    let _bsid = bs.read_bits_leq32(5)?;
+
+    // The dialogue normalization and compression gain are repeated for dual mono.
+    let num_programs = if acmod == ACMOD_DUAL_MONO { 2 } else { 1 };
+
+    for _ in 0..num_programs {
+        let _dialnorm = bs.read_bits_leq32(5)?;
+
+        if bs.read_bool()? {
+            let _compr = bs.read_bits_leq32(8)?;
+        }
+    }
+
+    if bs.read_bool()? {
+        Ok(Some(bs.read_bits_leq32(16)? as u16))
+    }
+    else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_sync_info() {
+        // 44.1 kHz mono, with a padded 128 kbps frame.
+        let info = SyncInfo::read(&[0x0b, 0x77, 0x00, 0x00, 0x51, 0x40, 0x2d]).unwrap();
+
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.bit_rate, 128);
+        assert_eq!(info.frame_len, 558);
+        assert_eq!(info.channels(), Channels::FRONT_LEFT);
+
+        // 48 kHz 3/2 with LFE, at 640 kbps.
+        let info = SyncInfo::read(&[0x0b, 0x77, 0x00, 0x00, 0x24, 0x40, 0xe1]).unwrap();
+
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.frame_len, 2560);
+        assert_eq!(info.num_fbw_channels(), 5);
+        assert_eq!(
+            info.channel_locations(&[]).unwrap(),
+            [
+                Channels::FRONT_LEFT,
+                Channels::FRONT_CENTRE,
+                Channels::FRONT_RIGHT,
+                Channels::SIDE_LEFT,
+                Channels::SIDE_RIGHT,
+                Channels::LFE1,
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_read_eac3_sync_info() {
+        // 48 kHz 3/2 with LFE, independent substream 0, with 6 blocks in a 1536 byte frame.
+        let info = SyncInfo::read(&[0x0b, 0x77, 0x02, 0xff, 0x3f, 0x80, 0x00]).unwrap();
+
+        assert!(info.is_eac3());
+        assert!(info.starts_access_unit());
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.bit_rate, 384);
+        assert_eq!(info.frame_len, 1536);
+        assert_eq!(info.num_blocks, 6);
+        assert_eq!(
+            info.channels(),
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+        );
+
+        // 48 kHz 2/0 dependent substream 0, with a custom channel map of the rear surround
+        // channels.
+        let frame = [0x0b, 0x77, 0x40, 0x7f, 0x34, 0x80, 0x10, 0x20, 0x00, 0x00];
+        let info = SyncInfo::read(&frame).unwrap();
+
+        assert!(!info.starts_access_unit());
+        assert_eq!(info.stream_type, StreamType::Dependent);
+        assert_eq!(info.frame_len, 256);
+        assert_eq!(info.num_blocks, 6);
+        assert_eq!(
+            info.channel_locations(&frame).unwrap(),
+            [Channels::REAR_LEFT, Channels::REAR_RIGHT]
+        );
+    }
+}
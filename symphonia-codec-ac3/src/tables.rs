@@ -0,0 +1,186 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// Sample rates indexed by `fscod`.
+pub const SAMPLE_RATES: [u32; 3] = [48000, 44100, 32000];
+
+/// Bit rates, in kbps, indexed by `frmsizecod / 2`.
+pub const BIT_RATES: [u32; 19] =
+    [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640];
+
+/// Frame sizes, in 16-bit words, indexed by `frmsizecod` and then `fscod`.
+#[rustfmt::skip]
+pub const FRAME_SIZES: [[u16; 3]; 38] = [
+    [64, 69, 96], [64, 70, 96], [80, 87, 120], [80, 88, 120],
+    [96, 104, 144], [96, 105, 144], [112, 121, 168], [112, 122, 168],
+    [128, 139, 192], [128, 140, 192], [160, 174, 240], [160, 175, 240],
+    [192, 208, 288], [192, 209, 288], [224, 243, 336], [224, 244, 336],
+    [256, 278, 384], [256, 279, 384], [320, 348, 480], [320, 349, 480],
+    [384, 417, 576], [384, 418, 576], [448, 487, 672], [448, 488, 672],
+    [512, 557, 768], [512, 558, 768], [640, 696, 960], [640, 697, 960],
+    [768, 835, 1152], [768, 836, 1152], [896, 975, 1344], [896, 976, 1344],
+    [1024, 1114, 1536], [1024, 1115, 1536], [1152, 1253, 1728], [1152, 1254, 1728],
+    [1280, 1393, 1920], [1280, 1394, 1920],
+];
+
+/// The first frequency bin of each bit allocation band, and the end of the last band.
+#[rustfmt::skip]
+pub const BAND_START: [u8; 51] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 31, 34, 37,
+    40, 43, 46, 49, 55, 61, 67, 73, 79, 85, 97, 109, 121, 133, 157, 181,
+    205, 229, 253,
+];
+
+/// The bit allocation band of each frequency bin.
+#[rustfmt::skip]
+pub const BIN_TO_BAND: [u8; 256] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 28, 28, 29,
+    29, 29, 30, 30, 30, 31, 31, 31, 32, 32, 32, 33, 33, 33, 34, 34,
+    34, 35, 35, 35, 35, 35, 35, 36, 36, 36, 36, 36, 36, 37, 37, 37,
+    37, 37, 37, 38, 38, 38, 38, 38, 38, 39, 39, 39, 39, 39, 39, 40,
+    40, 40, 40, 40, 40, 41, 41, 41, 41, 41, 41, 41, 41, 41, 41, 41,
+    41, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 43, 43, 43,
+    43, 43, 43, 43, 43, 43, 43, 43, 43, 44, 44, 44, 44, 44, 44, 44,
+    44, 44, 44, 44, 44, 45, 45, 45, 45, 45, 45, 45, 45, 45, 45, 45,
+    45, 45, 45, 45, 45, 45, 45, 45, 45, 45, 45, 45, 45, 46, 46, 46,
+    46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46, 46,
+    46, 46, 46, 46, 46, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47,
+    47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 48, 48, 48,
+    48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48,
+    48, 48, 48, 48, 48, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49,
+    49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49, 49,
+];
+
+/// The log-addition table.
+#[rustfmt::skip]
+pub const LOG_ADD: [u16; 256] = [
+    0x0040, 0x003f, 0x003e, 0x003d, 0x003c, 0x003b, 0x003a, 0x0039,
+    0x0038, 0x0037, 0x0036, 0x0035, 0x0034, 0x0034, 0x0033, 0x0032,
+    0x0031, 0x0030, 0x002f, 0x002f, 0x002e, 0x002d, 0x002c, 0x002c,
+    0x002b, 0x002a, 0x0029, 0x0029, 0x0028, 0x0027, 0x0026, 0x0026,
+    0x0025, 0x0024, 0x0024, 0x0023, 0x0023, 0x0022, 0x0021, 0x0021,
+    0x0020, 0x0020, 0x001f, 0x001e, 0x001e, 0x001d, 0x001d, 0x001c,
+    0x001c, 0x001b, 0x001b, 0x001a, 0x001a, 0x0019, 0x0019, 0x0018,
+    0x0018, 0x0017, 0x0017, 0x0016, 0x0016, 0x0015, 0x0015, 0x0015,
+    0x0014, 0x0014, 0x0013, 0x0013, 0x0013, 0x0012, 0x0012, 0x0012,
+    0x0011, 0x0011, 0x0011, 0x0010, 0x0010, 0x0010, 0x000f, 0x000f,
+    0x000f, 0x000e, 0x000e, 0x000e, 0x000d, 0x000d, 0x000d, 0x000d,
+    0x000c, 0x000c, 0x000c, 0x000c, 0x000b, 0x000b, 0x000b, 0x000b,
+    0x000a, 0x000a, 0x000a, 0x000a, 0x000a, 0x0009, 0x0009, 0x0009,
+    0x0009, 0x0009, 0x0008, 0x0008, 0x0008, 0x0008, 0x0008, 0x0008,
+    0x0007, 0x0007, 0x0007, 0x0007, 0x0007, 0x0007, 0x0006, 0x0006,
+    0x0006, 0x0006, 0x0006, 0x0006, 0x0006, 0x0006, 0x0005, 0x0005,
+    0x0005, 0x0005, 0x0005, 0x0005, 0x0005, 0x0005, 0x0004, 0x0004,
+    0x0004, 0x0004, 0x0004, 0x0004, 0x0004, 0x0004, 0x0004, 0x0004,
+    0x0004, 0x0003, 0x0003, 0x0003, 0x0003, 0x0003, 0x0003, 0x0003,
+    0x0003, 0x0003, 0x0003, 0x0003, 0x0003, 0x0003, 0x0003, 0x0002,
+    0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002,
+    0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002, 0x0002,
+    0x0002, 0x0002, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001,
+    0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001,
+    0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001,
+    0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001, 0x0001,
+    0x0001, 0x0001, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+];
+
+/// The hearing threshold of each bit allocation band, indexed by band and then `fscod`.
+#[rustfmt::skip]
+pub const HEARING_THRESHOLD: [[u16; 3]; 50] = [
+    [0x04d0, 0x04f0, 0x0580], [0x04d0, 0x04f0, 0x0580], [0x0440, 0x0460, 0x04b0],
+    [0x0400, 0x0410, 0x0450], [0x03e0, 0x03e0, 0x0420], [0x03c0, 0x03d0, 0x03f0],
+    [0x03b0, 0x03c0, 0x03e0], [0x03b0, 0x03b0, 0x03d0], [0x03a0, 0x03b0, 0x03c0],
+    [0x03a0, 0x03a0, 0x03b0], [0x03a0, 0x03a0, 0x03b0], [0x03a0, 0x03a0, 0x03b0],
+    [0x03a0, 0x03a0, 0x03a0], [0x0390, 0x03a0, 0x03a0], [0x0390, 0x0390, 0x03a0],
+    [0x0390, 0x0390, 0x03a0], [0x0380, 0x0390, 0x03a0], [0x0380, 0x0380, 0x03a0],
+    [0x0370, 0x0380, 0x03a0], [0x0370, 0x0380, 0x03a0], [0x0360, 0x0370, 0x0390],
+    [0x0360, 0x0370, 0x0390], [0x0350, 0x0360, 0x0390], [0x0350, 0x0360, 0x0390],
+    [0x0340, 0x0350, 0x0380], [0x0340, 0x0350, 0x0380], [0x0330, 0x0340, 0x0380],
+    [0x0320, 0x0340, 0x0370], [0x0310, 0x0320, 0x0360], [0x0300, 0x0310, 0x0350],
+    [0x02f0, 0x0300, 0x0340], [0x02f0, 0x02f0, 0x0330], [0x02f0, 0x02f0, 0x0320],
+    [0x02f0, 0x02f0, 0x0310], [0x0300, 0x02f0, 0x0300], [0x0310, 0x0300, 0x02f0],
+    [0x0340, 0x0320, 0x02f0], [0x0390, 0x0350, 0x02f0], [0x03e0, 0x0390, 0x0300],
+    [0x0420, 0x03e0, 0x0310], [0x0460, 0x0420, 0x0330], [0x0490, 0x0450, 0x0350],
+    [0x04a0, 0x04a0, 0x03c0], [0x0460, 0x0490, 0x0410], [0x0440, 0x0460, 0x0470],
+    [0x0440, 0x0440, 0x04a0], [0x0520, 0x0480, 0x0460], [0x0800, 0x0630, 0x0440],
+    [0x0840, 0x0840, 0x0450], [0x0840, 0x0840, 0x04e0],
+];
+
+/// The bit allocation pointer table.
+#[rustfmt::skip]
+pub const BAP_TAB: [u8; 64] = [
+    0, 1, 1, 1, 1, 1, 2, 2, 3, 3, 3, 4, 4, 5, 5, 6,
+    6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 8, 9, 9, 9, 9, 10,
+    10, 10, 10, 11, 11, 11, 11, 12, 12, 12, 12, 13, 13, 13, 13, 14,
+    14, 14, 14, 14, 14, 14, 14, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+];
+
+/// Slow decay, indexed by `sdcycod`.
+pub const SLOW_DECAY: [i32; 4] = [0x0f, 0x11, 0x13, 0x15];
+
+/// Fast decay, indexed by `fdcycod`.
+pub const FAST_DECAY: [i32; 4] = [0x3f, 0x53, 0x67, 0x7b];
+
+/// Slow gain, indexed by `sgaincod`.
+pub const SLOW_GAIN: [i32; 4] = [0x540, 0x4d8, 0x478, 0x410];
+
+/// dB per bit, indexed by `dbpbcod`.
+pub const DB_PER_BIT: [i32; 4] = [0x000, 0x700, 0x900, 0xb00];
+
+/// Masking floor, indexed by `floorcod`.
+pub const FLOOR: [i32; 8] = [0x2f0, 0x2b0, 0x270, 0x230, 0x1f0, 0x170, 0x0f0, -0x800];
+
+/// Fast gain, indexed by `fgaincod`.
+pub const FAST_GAIN: [i32; 8] = [0x080, 0x100, 0x180, 0x200, 0x280, 0x300, 0x380, 0x400];
+
+/// The first frequency bin of each rematrixing band, and the end of the last band.
+pub const REMATRIX_BANDS: [usize; 5] = [13, 25, 37, 61, 253];
+
+/// The number of bits of each mantissa, indexed by bit allocation pointers 6 to 15.
+pub const MANTISSA_BITS: [u32; 16] = [0, 0, 0, 3, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12, 14, 16];
+
+/// Exponent strategies of each audio block, indexed by the E-AC-3 frame exponent strategy code
+/// (`frmcplexpstr` or `frmchexpstr`).
+#[rustfmt::skip]
+pub const FRAME_EXP_STRATEGIES: [[u32; 6]; 32] = [
+    [1, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 3],
+    [1, 0, 0, 0, 2, 0], [1, 0, 0, 0, 3, 3],
+    [2, 0, 0, 2, 0, 0], [2, 0, 0, 2, 0, 3],
+    [2, 0, 0, 3, 2, 0], [2, 0, 0, 3, 3, 3],
+    [2, 0, 1, 0, 0, 0], [2, 0, 2, 0, 0, 3],
+    [2, 0, 2, 0, 2, 0], [2, 0, 2, 0, 3, 3],
+    [2, 0, 3, 2, 0, 0], [2, 0, 3, 2, 0, 3],
+    [2, 0, 3, 3, 2, 0], [2, 0, 3, 3, 3, 3],
+    [3, 1, 0, 0, 0, 0], [3, 1, 0, 0, 0, 3],
+    [3, 2, 0, 0, 2, 0], [3, 2, 0, 0, 3, 3],
+    [3, 2, 0, 2, 0, 0], [3, 2, 0, 2, 0, 3],
+    [3, 2, 0, 3, 2, 0], [3, 2, 0, 3, 3, 3],
+    [3, 3, 1, 0, 0, 0], [3, 3, 2, 0, 0, 3],
+    [3, 3, 2, 0, 2, 0], [3, 3, 2, 0, 3, 3],
+    [3, 3, 3, 2, 0, 0], [3, 3, 3, 2, 0, 3],
+    [3, 3, 3, 3, 2, 0], [3, 3, 3, 3, 3, 3],
+];
+
+/// The default E-AC-3 coupling band structure, indexed by sub-band.
+#[rustfmt::skip]
+pub const DEFAULT_CPL_BAND_STRUCT: [bool; 18] = [
+    false, false, false, false, false, false, false, false, true,
+    false, true, true, false, true, true, true, true, true,
+];
+
+/// The default spectral extension band structure, indexed by sub-band.
+#[rustfmt::skip]
+pub const DEFAULT_SPX_BAND_STRUCT: [bool; 17] = [
+    false, false, false, false, false, false, false, false, true,
+    false, true, false, true, false, true, true, true,
+];
@@ -0,0 +1,272 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ITU-T G.722 is specified with bit-exact fixed-point arithmetic, which is followed here. Only
+//! the 64 kbit/s mode, where each byte codes a 6 bit lower sub-band sample and a 2 bit higher
+//! sub-band sample, is supported.
+
+use symphonia_core::util::clamp::clamp_i16;
+
+use crate::common::from_i16_shift;
+
+/// The log scale factor increments of the lower sub-band, indexed by the 4 bit code magnitude.
+const WL: [i32; 8] = [-60, -30, 58, 172, 334, 538, 1198, 3042];
+
+/// The magnitudes of the 4 bit lower sub-band codes.
+const RL42: [usize; 16] = [0, 7, 6, 5, 4, 3, 2, 1, 7, 6, 5, 4, 3, 2, 1, 0];
+
+/// The log scale factor increments of the higher sub-band, indexed by the code magnitude.
+const WH: [i32; 3] = [0, -214, 798];
+
+/// The magnitudes of the higher sub-band codes.
+const RH2: [usize; 4] = [2, 1, 2, 1];
+
+/// The inverse log scale factor table.
+#[rustfmt::skip]
+const ILB: [i32; 32] = [
+    2048, 2093, 2139, 2186, 2233, 2282, 2332, 2383,
+    2435, 2489, 2543, 2599, 2656, 2714, 2774, 2834,
+    2896, 2960, 3025, 3091, 3158, 3228, 3298, 3371,
+    3444, 3520, 3597, 3676, 3756, 3838, 3922, 4008,
+];
+
+/// The inverse quantizer of the 2 bit higher sub-band codes.
+const QM2: [i32; 4] = [-7408, -1616, 7408, 1616];
+
+/// The inverse quantizer of the 4 bit lower sub-band codes, used for adaptation.
+#[rustfmt::skip]
+const QM4: [i32; 16] = [
+    0, -20456, -12896, -8968, -6288, -4240, -2584, -1200,
+    20456, 12896, 8968, 6288, 4240, 2584, 1200, 0,
+];
+
+/// The inverse quantizer of the 6 bit lower sub-band codes.
+#[rustfmt::skip]
+const QM6: [i32; 64] = [
+    -136, -136, -136, -136, -24808, -21904, -19008, -16704,
+    -14984, -13512, -12280, -11192, -10232, -9360, -8576, -7856,
+    -7192, -6576, -6000, -5456, -4944, -4464, -4008, -3576,
+    -3168, -2776, -2400, -2032, -1688, -1360, -1040, -728,
+    24808, 21904, 19008, 16704, 14984, 13512, 12280, 11192,
+    10232, 9360, 8576, 7856, 7192, 6576, 6000, 5456,
+    4944, 4464, 4008, 3576, 3168, 2776, 2400, 2032,
+    1688, 1360, 1040, 728, 432, 136, -432, -136,
+];
+
+/// The coefficients of the receive quadrature mirror filter.
+const QMF_COEFFS: [i32; 12] = [3, -11, 12, 32, -210, 951, 3876, -805, 362, -156, 53, -11];
+
+#[inline(always)]
+fn sat(x: i32) -> i32 {
+    i32::from(clamp_i16(x))
+}
+
+/// The adaptive predictor and quantizer state of a sub-band.
+#[derive(Default)]
+struct Band {
+    /// The predicted signal.
+    s: i32,
+    /// The pole section output.
+    sp: i32,
+    /// The zero section output.
+    sz: i32,
+    /// The reconstructed signal.
+    r: [i32; 3],
+    /// The pole section coefficients.
+    a: [i32; 3],
+    /// The partially reconstructed signal.
+    p: [i32; 3],
+    /// The quantized difference signal.
+    d: [i32; 7],
+    /// The zero section coefficients.
+    b: [i32; 7],
+    /// The log scale factor.
+    nb: i32,
+    /// The scale factor.
+    det: i32,
+}
+
+impl Band {
+    fn new(det: i32) -> Self {
+        Band { det, ..Default::default() }
+    }
+
+    /// Adapt the log scale factor by `wd`, and update the scale factor.
+    fn adapt_scale(&mut self, wd: i32, max_nb: i32, shift: i32) {
+        self.nb = (((self.nb * 127) >> 7) + wd).clamp(0, max_nb);
+
+        let wd1 = ILB[((self.nb >> 6) & 31) as usize];
+        let wd2 = shift - (self.nb >> 11);
+
+        self.det = (if wd2 < 0 { wd1 << -wd2 } else { wd1 >> wd2 }) << 2;
+    }
+
+    /// Update the adaptive predictor with the quantized difference signal, `dx`.
+    fn predict(&mut self, dx: i32) {
+        let p0 = sat(self.sz + dx);
+
+        self.d[0] = dx;
+        self.r[0] = sat(self.s + dx);
+        self.p[0] = p0;
+
+        // Update the second pole coefficient.
+        let sg = [p0 >> 15, self.p[1] >> 15, self.p[2] >> 15];
+
+        let wd1 = sat(self.a[1] << 2);
+        let wd2 = if sg[0] == sg[1] { -wd1 } else { wd1 }.min(32767);
+        let wd3 = (wd2 >> 7) + if sg[0] == sg[2] { 128 } else { -128 };
+
+        let a2 = (wd3 + ((self.a[2] * 32512) >> 15)).clamp(-12288, 12288);
+
+        // Update the first pole coefficient.
+        let wd1 = if sg[0] == sg[1] { 192 } else { -192 };
+        let wd2 = (self.a[1] * 32640) >> 15;
+        let wd3 = sat(15360 - a2);
+
+        let a1 = sat(wd1 + wd2).clamp(-wd3, wd3);
+
+        // Update the zero coefficients.
+        let wd1 = if dx == 0 { 0 } else { 128 };
+        let sg0 = dx >> 15;
+
+        for i in 1..7 {
+            let wd2 = if self.d[i] >> 15 == sg0 { wd1 } else { -wd1 };
+            let wd3 = (self.b[i] * 32640) >> 15;
+            self.b[i] = sat(wd2 + wd3);
+        }
+
+        // Delay.
+        self.d.copy_within(0..6, 1);
+
+        self.r[2] = self.r[1];
+        self.r[1] = self.r[0];
+        self.p[2] = self.p[1];
+        self.p[1] = self.p[0];
+        self.a[2] = a2;
+        self.a[1] = a1;
+
+        // Compute the pole and zero section outputs, and the prediction.
+        let wd1 = (self.a[1] * sat(self.r[1] + self.r[1])) >> 15;
+        let wd2 = (self.a[2] * sat(self.r[2] + self.r[2])) >> 15;
+
+        self.sp = sat(wd1 + wd2);
+
+        let mut sz = 0;
+
+        for i in (1..7).rev() {
+            sz += (self.b[i] * sat(self.d[i] + self.d[i])) >> 15;
+        }
+
+        self.sz = sat(sz);
+        self.s = sat(self.sp + self.sz);
+    }
+}
+
+/// The state of a G.722 decoder for one channel.
+pub(crate) struct G722Decoder {
+    low: Band,
+    high: Band,
+    /// The receive quadrature mirror filter history.
+    x: [i32; 24],
+}
+
+impl Default for G722Decoder {
+    fn default() -> Self {
+        G722Decoder { low: Band::new(32), high: Band::new(8), x: [0; 24] }
+    }
+}
+
+impl G722Decoder {
+    /// Reset the decoder to its initial state.
+    pub fn reset(&mut self) {
+        *self = Default::default();
+    }
+
+    /// Decode a code into two samples.
+    pub fn decode(&mut self, code: u8, out: &mut [i32]) {
+        let ilow = usize::from(code & 0x3f);
+        let ihigh = usize::from(code >> 6);
+
+        // Lower sub-band. The output uses the 6 bit code, while the adaptation only uses the 4
+        // most-significant bits.
+        let low = &mut self.low;
+
+        let wd2 = (low.det * QM6[ilow]) >> 15;
+        let rlow = (low.s + wd2).clamp(-16384, 16383);
+
+        let ril = ilow >> 2;
+        let dlow = (low.det * QM4[ril]) >> 15;
+
+        low.adapt_scale(WL[RL42[ril]], 18432, 8);
+        low.predict(dlow);
+
+        // Higher sub-band.
+        let high = &mut self.high;
+
+        let dhigh = (high.det * QM2[ihigh]) >> 15;
+        let rhigh = (dhigh + high.s).clamp(-16384, 16383);
+
+        high.adapt_scale(WH[RH2[ihigh]], 22528, 10);
+        high.predict(dhigh);
+
+        // Receive quadrature mirror filter.
+        self.x.copy_within(2.., 0);
+        self.x[22] = rlow + rhigh;
+        self.x[23] = rlow - rhigh;
+
+        let mut xout1 = 0;
+        let mut xout2 = 0;
+
+        for (i, &coeff) in QMF_COEFFS.iter().enumerate() {
+            xout2 += self.x[2 * i] * coeff;
+            xout1 += self.x[2 * i + 1] * QMF_COEFFS[11 - i];
+        }
+
+        out[0] = from_i16_shift!(clamp_i16(xout1 >> 11));
+        out[1] = from_i16_shift!(clamp_i16(xout2 >> 11));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::G722Decoder;
+
+    #[test]
+    fn verify_decode() {
+        // The expected output was generated by a separate implementation of the fixed-point
+        // reference decoder.
+        #[rustfmt::skip]
+        const CODES: [u8; 32] = [
+            198, 126, 129, 107, 75, 251, 226, 251, 84, 246, 189, 223, 124, 28, 225, 135,
+            1, 191, 49, 222, 86, 114, 15, 71, 103, 102, 135, 89, 170, 136, 60, 89,
+        ];
+
+        #[rustfmt::skip]
+        const SAMPLES: [i16; 64] = [
+            -1, 0, 0, -1, -1, 1, -1, -4, 1, 7, -9, -37, -36, -10, 1, -10,
+            7, 36, 32, -46, -79, -43, 56, 137, 152, 67, -47, -135, -90, 9, 62, 31,
+            -20, -62, -10, 76, -36, -224, 129, 640, 216, -885, -1022, -140, 249, -116, 6, 606,
+            714, 23, -616, -657, -163, 409, 612, -466,
+            -2157, -2711, -1519, 1908, 8335, 11174, 1900, -13141,
+        ];
+
+        let mut decoder = G722Decoder::default();
+        let mut out = [0; 2];
+
+        for (&code, expected) in CODES.iter().zip(SAMPLES.chunks_exact(2)) {
+            decoder.decode(code, &mut out);
+
+            assert_eq!(out, [i32::from(expected[0]) << 16, i32::from(expected[1]) << 16]);
+        }
+
+        // After a reset, decoding must restart from the initial state.
+        decoder.reset();
+        decoder.decode(CODES[0], &mut out);
+
+        assert_eq!(out, [i32::from(SAMPLES[0]) << 16, i32::from(SAMPLES[1]) << 16]);
+    }
+}
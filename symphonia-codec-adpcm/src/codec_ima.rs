@@ -31,7 +31,8 @@ const IMA_STEP_TABLE: [i32; 89] = [
 ];
 
 /// `AdpcmImaBlockStatus` contains values to decode a block
-struct AdpcmImaBlockStatus {
+#[derive(Default)]
+pub(crate) struct AdpcmImaBlockStatus {
     predictor: i32,
     step_index: i32,
 }
@@ -60,6 +61,28 @@ impl AdpcmImaBlockStatus {
         self.step_index = (self.step_index + IMA_INDEX_TABLE[nibble as usize]).clamp(0, 88);
         from_i16_shift!(self.predictor)
     }
+
+    /// Expand a nibble as QuickTime does. Unlike `expand_nibble`, the difference is accumulated
+    /// from the bits of the nibble, which rounds differently.
+    fn expand_nibble_qt(&mut self, byte: u8, nibble: Nibble) -> i32 {
+        let nibble = nibble.get_nibble(byte);
+        let step = IMA_STEP_TABLE[self.step_index as usize];
+        let mut diff = step >> 3;
+        if nibble & 0x04 != 0 {
+            diff += step;
+        }
+        if nibble & 0x02 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 0x01 != 0 {
+            diff += step >> 2;
+        }
+        let predictor =
+            if nibble & 0x08 != 0 { self.predictor - diff } else { self.predictor + diff };
+        self.predictor = clamp_i16(predictor) as i32;
+        self.step_index = (self.step_index + IMA_INDEX_TABLE[nibble as usize]).clamp(0, 88);
+        from_i16_shift!(self.predictor)
+    }
 }
 
 pub(crate) fn decode_mono<B: ReadBytes>(
@@ -78,25 +101,97 @@ pub(crate) fn decode_mono<B: ReadBytes>(
     Ok(())
 }
 
-pub(crate) fn decode_stereo<B: ReadBytes>(
+pub(crate) fn decode_multichannel<B: ReadBytes>(
     stream: &mut B,
-    buffers: [&mut [i32]; 2],
+    buffers: &mut [&mut [i32]],
     frames_per_block: usize,
 ) -> Result<()> {
-    let data_bytes_per_channel = frames_per_block - 1;
-    let mut status =
-        [AdpcmImaBlockStatus::read_preamble(stream)?, AdpcmImaBlockStatus::read_preamble(stream)?];
-    buffers[0][0] = from_i16_shift!(status[0].predictor);
-    buffers[1][0] = from_i16_shift!(status[1].predictor);
-    for index in 0..data_bytes_per_channel {
-        let channel = (index / 4) & 1;
-        let offset = (index / 8) * 8;
-        let byte = index % 4;
+    let mut status = Vec::with_capacity(buffers.len());
+
+    // Each channel has its own preamble, and the preamble sample is the first sample of the block.
+    for buffer in buffers.iter_mut() {
+        let channel_status = AdpcmImaBlockStatus::read_preamble(stream)?;
+        buffer[0] = from_i16_shift!(channel_status.predictor);
+        status.push(channel_status);
+    }
+
+    // The remaining samples are interleaved in groups of 8 samples (4 bytes) per channel.
+    for offset in (1..frames_per_block).step_by(8) {
+        for (buffer, status) in buffers.iter_mut().zip(status.iter_mut()) {
+            for byte in 0..4 {
+                let nibbles = stream.read_u8()?;
+                buffer[offset + byte * 2] = status.expand_nibble(nibbles, Nibble::Lower);
+                buffer[offset + byte * 2 + 1] = status.expand_nibble(nibbles, Nibble::Upper);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decode the block of one channel of IMA ADPCM as used by QuickTime. Unlike IMA ADPCM WAV, the
+/// status continues from the previous block of the channel.
+pub(crate) fn decode_qt<B: ReadBytes>(
+    stream: &mut B,
+    buffer: &mut [i32],
+    status: &mut AdpcmImaBlockStatus,
+) -> Result<()> {
+    // The preamble contains the 9 most-significant bits of the predictor, and the step index.
+    let preamble = stream.read_be_u16()?;
+    let predictor = u16_to_i32!(preamble & !0x7f);
+    let step_index = i32::from(preamble & 0x7f);
+    if step_index > 88 {
+        return decode_error("adpcm (ima qt): invalid step index");
+    }
+    // If the preamble is consistent with the status at the end of the previous block, keep the
+    // previous predictor since it is more precise.
+    if step_index != status.step_index || (predictor - status.predictor).abs() > 0x7f {
+        status.predictor = predictor;
+        status.step_index = step_index;
+    }
+    for samples in buffer.chunks_exact_mut(2) {
         let nibbles = stream.read_u8()?;
-        buffers[channel][1 + offset + byte * 2] =
-            status[channel].expand_nibble(nibbles, Nibble::Lower);
-        buffers[channel][1 + offset + byte * 2 + 1] =
-            status[channel].expand_nibble(nibbles, Nibble::Upper);
+        samples[0] = status.expand_nibble_qt(nibbles, Nibble::Lower);
+        samples[1] = status.expand_nibble_qt(nibbles, Nibble::Upper);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+
+    use super::{decode_qt, AdpcmImaBlockStatus};
+
+    fn decode_qt_block(block: &[u8], status: &mut AdpcmImaBlockStatus) -> Vec<i32> {
+        let mut buffer = vec![0; 2 * (block.len() - 2)];
+        decode_qt(&mut BufReader::new(block), &mut buffer, status).unwrap();
+        buffer.iter().map(|&sample| sample >> 16).collect()
+    }
+
+    #[test]
+    fn verify_decode_qt() {
+        let mut status = Default::default();
+
+        // The preamble sets a predictor of 0x1200, and a step index of 52.
+        let samples = decode_qt_block(&[0x12, 0x34, 0x07, 0x8f, 0x3c, 0xa5], &mut status);
+
+        assert_eq!(samples, [6595, 6879, 3006, 2453, -2076, 2184, 8272, 4220]);
+
+        // The preamble is consistent with the status at the end of the previous block, so the
+        // more precise predictor of 4220 is kept. The output saturates.
+        let samples = decode_qt_block(&[0x10, 0x46, 0x21, 0x43, 0x65, 0x87], &mut status);
+
+        assert_eq!(samples, [6429, 9777, 14037, 19018, 26384, 32767, 32767, 29043]);
+
+        // The preamble is inconsistent, so the predictor and step index are reset to -4096 and 5.
+        let samples = decode_qt_block(&[0xf0, 0x05, 0x77, 0x00, 0xff, 0x18], &mut status);
+
+        assert_eq!(samples, [-4074, -4028, -4022, -4016, -4099, -4280, -4306, -4236]);
+
+        // A step index greater than 88 is invalid.
+        let mut buffer = [0; 2];
+        assert!(
+            decode_qt(&mut BufReader::new(&[0x00, 0x59, 0x00]), &mut buffer, &mut status).is_err()
+        );
+    }
+}
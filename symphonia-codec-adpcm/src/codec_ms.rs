@@ -5,7 +5,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::ReadBytes;
 use symphonia_core::util::clamp::clamp_i16;
 
@@ -20,14 +20,54 @@ const MS_ADAPTATION_TABLE: [i32; 16] = [
 const MS_ADAPT_COEFFS1: [i32; 7] = [256, 512, 0, 192, 240, 460, 392];
 const MS_ADAPT_COEFFS2: [i32; 7] = [0, -256, 0, 64, 0, -208, -232];
 
+/// The maximum number of predictor coefficient pairs an encoder may define.
+const MAX_COEFFS: usize = 256;
+
 const DELTA_MIN: i32 = 16;
 
-macro_rules! check_block_predictor {
-    ($block_predictor:ident, $max:expr) => {
-        if $block_predictor > $max {
-            return unsupported_error("adpcm: block predictor exceeds range");
+/// `AdpcmMsCoeffs` is the table of predictor coefficient pairs used by a Microsoft ADPCM stream.
+pub(crate) struct AdpcmMsCoeffs {
+    coeffs: Vec<(i32, i32)>,
+}
+
+impl AdpcmMsCoeffs {
+    /// Read the coefficient table from the Microsoft ADPCM format extension (ADPCMWAVEFORMAT).
+    ///
+    /// The extension consists of the number of frames per block, the number of coefficient pairs,
+    /// and then the coefficient pairs. The first 7 pairs must always be the standard pairs, but an
+    /// encoder may define up-to 256 pairs in total.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<Self> {
+        let _frames_per_block = reader.read_u16()?;
+        let n_coeffs = usize::from(reader.read_u16()?);
+
+        if n_coeffs < MS_ADAPT_COEFFS1.len() || n_coeffs > MAX_COEFFS {
+            return decode_error("adpcm (ms): invalid number of coefficients");
         }
-    };
+
+        let mut coeffs = Vec::with_capacity(n_coeffs);
+
+        for _ in 0..n_coeffs {
+            let coeff1 = u16_to_i32!(reader.read_u16()?);
+            let coeff2 = u16_to_i32!(reader.read_u16()?);
+            coeffs.push((coeff1, coeff2));
+        }
+
+        Ok(AdpcmMsCoeffs { coeffs })
+    }
+
+    fn get(&self, block_predictor: usize) -> Result<(i32, i32)> {
+        match self.coeffs.get(block_predictor) {
+            Some(&coeffs) => Ok(coeffs),
+            None => unsupported_error("adpcm: block predictor exceeds range"),
+        }
+    }
+}
+
+impl Default for AdpcmMsCoeffs {
+    fn default() -> Self {
+        let coeffs = MS_ADAPT_COEFFS1.iter().copied().zip(MS_ADAPT_COEFFS2.iter().copied());
+        AdpcmMsCoeffs { coeffs: coeffs.collect() }
+    }
 }
 
 pub fn signed_nibble(nibble: u8) -> i8 {
@@ -49,12 +89,11 @@ struct AdpcmMsBlockStatus {
 }
 
 impl AdpcmMsBlockStatus {
-    fn read_mono_preamble<B: ReadBytes>(stream: &mut B) -> Result<Self> {
-        let block_predictor = stream.read_byte()? as usize;
-        check_block_predictor!(block_predictor, 6);
+    fn read_mono_preamble<B: ReadBytes>(stream: &mut B, coeffs: &AdpcmMsCoeffs) -> Result<Self> {
+        let (coeff1, coeff2) = coeffs.get(stream.read_byte()? as usize)?;
         let status = Self {
-            coeff1: MS_ADAPT_COEFFS1[block_predictor],
-            coeff2: MS_ADAPT_COEFFS2[block_predictor],
+            coeff1,
+            coeff2,
             delta: u16_to_i32!(stream.read_u16()?),
             sample1: u16_to_i32!(stream.read_u16()?),
             sample2: u16_to_i32!(stream.read_u16()?),
@@ -62,11 +101,12 @@ impl AdpcmMsBlockStatus {
         Ok(status)
     }
 
-    fn read_stereo_preamble<B: ReadBytes>(stream: &mut B) -> Result<(Self, Self)> {
-        let left_block_predictor = stream.read_byte()? as usize;
-        check_block_predictor!(left_block_predictor, 6);
-        let right_block_predictor = stream.read_byte()? as usize;
-        check_block_predictor!(right_block_predictor, 6);
+    fn read_stereo_preamble<B: ReadBytes>(
+        stream: &mut B,
+        coeffs: &AdpcmMsCoeffs,
+    ) -> Result<(Self, Self)> {
+        let (left_coeff1, left_coeff2) = coeffs.get(stream.read_byte()? as usize)?;
+        let (right_coeff1, right_coeff2) = coeffs.get(stream.read_byte()? as usize)?;
         let left_delta = u16_to_i32!(stream.read_u16()?);
         let right_delta = u16_to_i32!(stream.read_u16()?);
         let left_sample1 = u16_to_i32!(stream.read_u16()?);
@@ -75,15 +115,15 @@ impl AdpcmMsBlockStatus {
         let right_sample2 = u16_to_i32!(stream.read_u16()?);
         Ok((
             Self {
-                coeff1: MS_ADAPT_COEFFS1[left_block_predictor],
-                coeff2: MS_ADAPT_COEFFS2[left_block_predictor],
+                coeff1: left_coeff1,
+                coeff2: left_coeff2,
                 delta: left_delta,
                 sample1: left_sample1,
                 sample2: left_sample2,
             },
             Self {
-                coeff1: MS_ADAPT_COEFFS1[right_block_predictor],
-                coeff2: MS_ADAPT_COEFFS2[right_block_predictor],
+                coeff1: right_coeff1,
+                coeff2: right_coeff2,
                 delta: right_delta,
                 sample1: right_sample1,
                 sample2: right_sample2,
@@ -108,8 +148,9 @@ pub(crate) fn decode_mono<B: ReadBytes>(
     stream: &mut B,
     buffer: &mut [i32],
     frames_per_block: usize,
+    coeffs: &AdpcmMsCoeffs,
 ) -> Result<()> {
-    let mut status = AdpcmMsBlockStatus::read_mono_preamble(stream)?;
+    let mut status = AdpcmMsBlockStatus::read_mono_preamble(stream, coeffs)?;
     buffer[0] = from_i16_shift!(status.sample2);
     buffer[1] = from_i16_shift!(status.sample1);
     for byte in 1..(frames_per_block / 2) {
@@ -124,8 +165,10 @@ pub(crate) fn decode_stereo<B: ReadBytes>(
     stream: &mut B,
     buffers: [&mut [i32]; 2],
     frames_per_block: usize,
+    coeffs: &AdpcmMsCoeffs,
 ) -> Result<()> {
-    let (mut left_status, mut right_status) = AdpcmMsBlockStatus::read_stereo_preamble(stream)?;
+    let (mut left_status, mut right_status) =
+        AdpcmMsBlockStatus::read_stereo_preamble(stream, coeffs)?;
     buffers[0][0] = from_i16_shift!(left_status.sample2);
     buffers[0][1] = from_i16_shift!(left_status.sample1);
     buffers[1][0] = from_i16_shift!(right_status.sample2);
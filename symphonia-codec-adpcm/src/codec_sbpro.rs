@@ -0,0 +1,172 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::ReadBytes;
+
+use crate::common::from_i16_shift;
+
+/// `AdpcmSbProStatus` contains the values to decode a channel. Creative 8-bit ADPCM has no
+/// preambles, so the status continues between packets.
+#[derive(Default)]
+pub(crate) struct AdpcmSbProStatus {
+    predictor: i32,
+    step: u32,
+}
+
+impl AdpcmSbProStatus {
+    /// Start from a reference byte, an unsigned 8-bit sample.
+    fn set_reference(&mut self, byte: u8) -> i32 {
+        self.predictor = (i32::from(byte) - 0x80) << 7;
+        self.step = 0;
+        from_i16_shift!(self.predictor)
+    }
+
+    /// Expand a code of `size` bits, the most-significant of which is the sign.
+    fn expand_code(&mut self, code: u8, size: u32, shift: u32) -> i32 {
+        let sign = code & (1 << (size - 1)) != 0;
+        let delta = code & ((1 << (size - 1)) - 1);
+        let diff = i32::from(delta) << (7 + self.step + shift);
+        let predictor = if sign { self.predictor - diff } else { self.predictor + diff };
+        // The predictor is limited to the range of an 8-bit sample.
+        self.predictor = predictor.clamp(-128 << 7, 127 << 7);
+        if u32::from(delta) >= 2 * size - 3 && self.step < 3 {
+            self.step += 1;
+        }
+        else if delta == 0 && self.step > 0 {
+            self.step -= 1;
+        }
+        from_i16_shift!(self.predictor)
+    }
+}
+
+/// Get the number of samples coded in a byte for the given number of bits per sample. The 2.6-bit
+/// variant is given as 3 bits.
+pub(crate) fn samples_per_byte(bits: u32) -> usize {
+    match bits {
+        4 => 2,
+        3 => 3,
+        _ => 4,
+    }
+}
+
+/// Decode interleaved samples into `buffers`. If `has_reference` is set, the first frame is a
+/// reference byte per channel.
+pub(crate) fn decode<B: ReadBytes>(
+    stream: &mut B,
+    buffers: &mut [&mut [i32]],
+    bits: u32,
+    has_reference: bool,
+    status: &mut [AdpcmSbProStatus],
+) -> Result<()> {
+    let channel_count = buffers.len();
+
+    let mut first_frame = 0;
+    if has_reference {
+        for (buffer, status) in buffers.iter_mut().zip(status.iter_mut()) {
+            buffer[0] = status.set_reference(stream.read_u8()?);
+        }
+        first_frame = 1;
+    }
+
+    let sample_count = channel_count * (buffers[0].len() - first_frame);
+    let byte_count = (sample_count + samples_per_byte(bits) - 1) / samples_per_byte(bits);
+
+    // Samples are coded from the most-significant bits of each byte, alternating channels.
+    let mut sample = 0;
+    let mut expand = |code: u8, size: u32, shift: u32| {
+        if sample < sample_count {
+            let ch = sample % channel_count;
+            let frame = first_frame + sample / channel_count;
+            buffers[ch][frame] = status[ch].expand_code(code, size, shift);
+            sample += 1;
+        }
+    };
+
+    for _ in 0..byte_count {
+        let byte = stream.read_u8()?;
+        match bits {
+            4 => {
+                expand(byte >> 4, 4, 0);
+                expand(byte & 0xf, 4, 0);
+            }
+            3 => {
+                expand(byte >> 5, 3, 0);
+                expand((byte >> 2) & 0x7, 3, 0);
+                expand(byte & 0x3, 2, 0);
+            }
+            _ => {
+                expand(byte >> 6, 2, 2);
+                expand((byte >> 4) & 0x3, 2, 2);
+                expand((byte >> 2) & 0x3, 2, 2);
+                expand(byte & 0x3, 2, 2);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+
+    use super::{decode, AdpcmSbProStatus};
+
+    fn decode_mono(data: &[u8], bits: u32, has_reference: bool, len: usize) -> Vec<i32> {
+        let mut status = [AdpcmSbProStatus::default()];
+        let mut buffer = vec![0; len];
+
+        decode(&mut BufReader::new(data), &mut [&mut buffer], bits, has_reference, &mut status)
+            .unwrap();
+
+        to_i16(&buffer)
+    }
+
+    fn to_i16(buffer: &[i32]) -> Vec<i32> {
+        buffer.iter().map(|&sample| sample >> 16).collect()
+    }
+
+    #[test]
+    fn verify_decode_4bit() {
+        // A reference byte, followed by 4 bit codes.
+        let samples = decode_mono(&[0x90, 0x77, 0x70, 0x0f, 0xf8], 4, true, 9);
+
+        assert_eq!(samples, [2048, 2944, 4736, 8320, 8320, 8320, 6528, 2944, 2944]);
+
+        // Stereo, where the channels alternate after a reference byte per channel.
+        let mut status = [AdpcmSbProStatus::default(), AdpcmSbProStatus::default()];
+        let mut left = [0; 4];
+        let mut right = [0; 4];
+
+        let data = [0x80, 0x40, 0x7f, 0x1e, 0x00];
+
+        decode(&mut BufReader::new(&data), &mut [&mut left, &mut right], 4, true, &mut status)
+            .unwrap();
+
+        assert_eq!(to_i16(&left), [0, 896, 1152, 1152]);
+        assert_eq!(to_i16(&right), [-8192, -9088, -10624, -10624]);
+    }
+
+    #[test]
+    fn verify_decode_3bit() {
+        // Each byte codes two 3 bit codes, and one 2 bit code.
+        let samples = decode_mono(&[0x60, 0x6d, 0xb7, 0xff], 3, true, 7);
+
+        assert_eq!(samples, [-4096, -3712, -2944, -2432, -3456, -4480, -5504]);
+    }
+
+    #[test]
+    fn verify_decode_2bit() {
+        // Without a reference byte, decoding starts from the current status.
+        let samples = decode_mono(&[0x55, 0xff, 0x03], 2, false, 12);
+
+        assert_eq!(
+            samples,
+            [512, 1536, 3584, 7680, 3584, -512, -4608, -8704, -8704, -8704, -8704, -9216]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::ReadBytes;
+use symphonia_core::util::clamp::clamp_i16;
+
+use crate::common::{from_i16_shift, Nibble};
+
+#[rustfmt::skip]
+const YAMAHA_DIFF_TABLE: [i32; 16] = [
+    1, 3, 5, 7, 9, 11, 13, 15,
+    -1, -3, -5, -7, -9, -11, -13, -15,
+];
+
+#[rustfmt::skip]
+const YAMAHA_STEP_SCALE_TABLE: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614,
+    230, 230, 230, 230, 307, 409, 512, 614,
+];
+
+const MIN_STEP: i32 = 127;
+const MAX_STEP: i32 = 24576;
+
+/// `AdpcmYamahaStatus` contains the values to decode a channel. Yamaha ADPCM has no preambles, so
+/// the status continues between packets.
+pub(crate) struct AdpcmYamahaStatus {
+    predictor: i32,
+    step: i32,
+}
+
+impl Default for AdpcmYamahaStatus {
+    fn default() -> Self {
+        Self { predictor: 0, step: MIN_STEP }
+    }
+}
+
+impl AdpcmYamahaStatus {
+    fn expand_nibble(&mut self, byte: u8, nibble: Nibble) -> i32 {
+        let nibble = nibble.get_nibble(byte) as usize;
+        let predictor = self.predictor + (self.step * YAMAHA_DIFF_TABLE[nibble]) / 8;
+        self.predictor = clamp_i16(predictor) as i32;
+        self.step = ((self.step * YAMAHA_STEP_SCALE_TABLE[nibble]) >> 8).clamp(MIN_STEP, MAX_STEP);
+        from_i16_shift!(self.predictor)
+    }
+}
+
+pub(crate) fn decode_mono<B: ReadBytes>(
+    stream: &mut B,
+    buffer: &mut [i32],
+    status: &mut AdpcmYamahaStatus,
+) -> Result<()> {
+    for samples in buffer.chunks_exact_mut(2) {
+        let nibbles = stream.read_u8()?;
+        samples[0] = status.expand_nibble(nibbles, Nibble::Lower);
+        samples[1] = status.expand_nibble(nibbles, Nibble::Upper);
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_stereo<B: ReadBytes>(
+    stream: &mut B,
+    buffers: [&mut [i32]; 2],
+    status: &mut [AdpcmYamahaStatus],
+) -> Result<()> {
+    let [left, right] = buffers;
+    for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+        let nibbles = stream.read_u8()?;
+        *left = status[0].expand_nibble(nibbles, Nibble::Lower);
+        *right = status[1].expand_nibble(nibbles, Nibble::Upper);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+
+    use super::{decode_mono, decode_stereo, AdpcmYamahaStatus};
+
+    fn to_i16(buffer: &[i32]) -> Vec<i32> {
+        buffer.iter().map(|&sample| sample >> 16).collect()
+    }
+
+    #[test]
+    fn verify_decode_mono() {
+        let mut status = AdpcmYamahaStatus::default();
+        let mut buffer = [0; 16];
+
+        let data = [0x77, 0x77, 0x77, 0x7f, 0x88, 0x08, 0x31, 0xf0];
+
+        decode_mono(&mut BufReader::new(&data), &mut buffer, &mut status).unwrap();
+
+        assert_eq!(
+            to_i16(&buffer),
+            [
+                238, 808, 2174, 5451, 13311, 32162, -13049, 32767, 29695, 26935, 24456, 26683,
+                32687, 32767, 32767, 10999
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_decode_stereo() {
+        let mut status = [AdpcmYamahaStatus::default(), AdpcmYamahaStatus::default()];
+        let mut left = [0; 4];
+        let mut right = [0; 4];
+
+        // The lower nibble is the left channel, and the upper nibble is the right channel.
+        let data = [0x70, 0x07, 0x9c, 0x4b];
+
+        decode_stereo(&mut BufReader::new(&data), [&mut left, &mut right], &mut status).unwrap();
+
+        assert_eq!(to_i16(&left), [15, 253, -89, -407]);
+        assert_eq!(to_i16(&right), [238, 276, 174, 449]);
+    }
+}
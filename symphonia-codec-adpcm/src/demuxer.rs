@@ -0,0 +1,171 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_ADPCM_G722};
+use symphonia_core::errors::{end_of_stream_error, seek_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+/// The sample rate of G.722.
+const SAMPLE_RATE: u32 = 16000;
+
+/// The number of codes read per packet. Each code decodes to two samples.
+const MAX_CODES_PER_PACKET: usize = 1024;
+
+/// Raw G.722 file format reader.
+///
+/// `G722Reader` implements a demuxer for headerless files of mono 64 kbit/s G.722 codes, commonly
+/// given the `.g722` extension.
+///
+/// Since these files have no file signature, `G722Reader` has no probe markers and is not found by
+/// probing. It must be instantiated directly when the file is known to be raw G.722, for example,
+/// by its extension.
+pub struct G722Reader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    data_start_pos: u64,
+    data_end_pos: Option<u64>,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for G722Reader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("g722", "Raw G.722", &["g722"], &["audio/G722"], &[])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for G722Reader {
+    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let data_start_pos = source.pos();
+        let data_end_pos = source.byte_len();
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_ADPCM_G722)
+            .with_sample_rate(SAMPLE_RATE)
+            .with_time_base(TimeBase::new(1, SAMPLE_RATE))
+            .with_channels(Channels::FRONT_LEFT)
+            .with_bits_per_coded_sample(4)
+            .with_max_frames_per_packet(2 * MAX_CODES_PER_PACKET as u64)
+            .with_frames_per_block(2);
+
+        if let Some(data_end_pos) = data_end_pos {
+            params.with_n_frames(2 * (data_end_pos - data_start_pos));
+        }
+
+        Ok(G722Reader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            data_start_pos,
+            data_end_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        // Do not read past the end of the stream, if known, so the final packet may be shorter.
+        let len = match self.data_end_pos {
+            Some(end) => {
+                let remaining = end.saturating_sub(self.reader.pos());
+
+                if remaining == 0 {
+                    return end_of_stream_error();
+                }
+
+                remaining.min(MAX_CODES_PER_PACKET as u64) as usize
+            }
+            None => MAX_CODES_PER_PACKET,
+        };
+
+        let buf = self.reader.read_boxed_slice_exact(len)?;
+
+        let ts = self.next_packet_ts;
+        let dur = 2 * len as u64;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => TimeBase::new(1, SAMPLE_RATE).calc_timestamp(time),
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = self.tracks[0].codec_params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Every code decodes to two samples.
+        let code_idx = required_ts / 2;
+
+        if self.reader.is_seekable() {
+            self.reader.seek(SeekFrom::Start(self.data_start_pos + code_idx))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let cur_code_idx = self.next_packet_ts / 2;
+
+            if code_idx < cur_code_idx {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes(code_idx - cur_code_idx)?;
+        }
+
+        self.next_packet_ts = 2 * code_idx;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -19,40 +19,55 @@ use symphonia_core::support_codec;
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CodecType};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::codecs::{CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS};
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::codecs::{
+    CODEC_TYPE_ADPCM_G722, CODEC_TYPE_ADPCM_IMA_QT, CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS,
+    CODEC_TYPE_ADPCM_SBPRO_2, CODEC_TYPE_ADPCM_SBPRO_3, CODEC_TYPE_ADPCM_SBPRO_4,
+    CODEC_TYPE_ADPCM_YAMAHA,
+};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
-use symphonia_core::io::ReadBytes;
+use symphonia_core::io::BufReader;
 
+mod codec_g722;
 mod codec_ima;
 mod codec_ms;
+mod codec_sbpro;
+mod codec_yamaha;
 mod common;
+mod demuxer;
+
+pub use demuxer::G722Reader;
+
+use codec_g722::G722Decoder;
+use codec_ima::AdpcmImaBlockStatus;
+use codec_ms::AdpcmMsCoeffs;
+use codec_sbpro::AdpcmSbProStatus;
+use codec_yamaha::AdpcmYamahaStatus;
+
+/// The number of frames in a block of IMA ADPCM as used by QuickTime.
+const IMA_QT_FRAMES_PER_BLOCK: u64 = 64;
 
 fn is_supported_adpcm_codec(codec_type: CodecType) -> bool {
-    matches!(codec_type, CODEC_TYPE_ADPCM_MS | CODEC_TYPE_ADPCM_IMA_WAV)
+    matches!(
+        codec_type,
+        CODEC_TYPE_ADPCM_MS
+            | CODEC_TYPE_ADPCM_IMA_WAV
+            | CODEC_TYPE_ADPCM_IMA_QT
+            | CODEC_TYPE_ADPCM_G722
+            | CODEC_TYPE_ADPCM_YAMAHA
+            | CODEC_TYPE_ADPCM_SBPRO_4
+            | CODEC_TYPE_ADPCM_SBPRO_3
+            | CODEC_TYPE_ADPCM_SBPRO_2
+    )
 }
 
 enum InnerDecoder {
-    AdpcmMs,
+    AdpcmMs { coeffs: AdpcmMsCoeffs },
     AdpcmIma,
-}
-
-impl InnerDecoder {
-    fn decode_mono_fn<B: ReadBytes>(&self) -> impl Fn(&mut B, &mut [i32], usize) -> Result<()> {
-        match *self {
-            InnerDecoder::AdpcmMs => codec_ms::decode_mono,
-            InnerDecoder::AdpcmIma => codec_ima::decode_mono,
-        }
-    }
-
-    fn decode_stereo_fn<B: ReadBytes>(
-        &self,
-    ) -> impl Fn(&mut B, [&mut [i32]; 2], usize) -> Result<()> {
-        match *self {
-            InnerDecoder::AdpcmMs => codec_ms::decode_stereo,
-            InnerDecoder::AdpcmIma => codec_ima::decode_stereo,
-        }
-    }
+    AdpcmImaQt { status: Vec<AdpcmImaBlockStatus> },
+    G722 { decoders: Vec<G722Decoder> },
+    AdpcmYamaha { status: Vec<AdpcmYamahaStatus> },
+    AdpcmSbPro { bits: u32, status: Vec<AdpcmSbProStatus> },
 }
 
 /// Adaptive Differential Pulse Code Modulation (ADPCM) decoder.
@@ -74,31 +89,109 @@ impl AdpcmDecoder {
         self.buf.render_reserved(Some(block_count * frames_per_block));
 
         let channel_count = self.buf.spec().channels.count();
-        match channel_count {
-            1 => {
+        match (&mut self.inner_decoder, channel_count) {
+            (InnerDecoder::AdpcmMs { coeffs }, 1) => {
                 let buffer = self.buf.chan_mut(0);
-                let decode_mono = self.inner_decoder.decode_mono_fn();
                 for block_id in 0..block_count {
                     let offset = frames_per_block * block_id;
                     let buffer_range = offset..(offset + frames_per_block);
                     let buffer = &mut buffer[buffer_range];
-                    decode_mono(&mut stream, buffer, frames_per_block)?;
+                    codec_ms::decode_mono(&mut stream, buffer, frames_per_block, coeffs)?;
                 }
             }
-            2 => {
+            (InnerDecoder::AdpcmMs { coeffs }, 2) => {
                 let buffers = self.buf.chan_pair_mut(0, 1);
-                let decode_stereo = self.inner_decoder.decode_stereo_fn();
                 for block_id in 0..block_count {
                     let offset = frames_per_block * block_id;
                     let buffer_range = offset..(offset + frames_per_block);
                     let buffers =
                         [&mut buffers.0[buffer_range.clone()], &mut buffers.1[buffer_range]];
-                    decode_stereo(&mut stream, buffers, frames_per_block)?;
+                    codec_ms::decode_stereo(&mut stream, buffers, frames_per_block, coeffs)?;
+                }
+            }
+            (InnerDecoder::AdpcmIma, 1) => {
+                let buffer = self.buf.chan_mut(0);
+                for block_id in 0..block_count {
+                    let offset = frames_per_block * block_id;
+                    let buffer_range = offset..(offset + frames_per_block);
+                    let buffer = &mut buffer[buffer_range];
+                    codec_ima::decode_mono(&mut stream, buffer, frames_per_block)?;
+                }
+            }
+            (InnerDecoder::AdpcmIma, _) => {
+                let mut planes = self.buf.planes_mut();
+                for block_id in 0..block_count {
+                    let offset = frames_per_block * block_id;
+                    let mut buffers: Vec<&mut [i32]> = planes
+                        .planes()
+                        .iter_mut()
+                        .map(|plane| &mut plane[offset..offset + frames_per_block])
+                        .collect();
+                    codec_ima::decode_multichannel(&mut stream, &mut buffers, frames_per_block)?;
+                }
+            }
+            (InnerDecoder::AdpcmImaQt { status }, _) => {
+                // Each block contains the blocks of each channel in sequence.
+                let mut planes = self.buf.planes_mut();
+                for block_id in 0..block_count {
+                    let offset = frames_per_block * block_id;
+                    for (plane, status) in planes.planes().iter_mut().zip(status.iter_mut()) {
+                        let buffer = &mut plane[offset..offset + frames_per_block];
+                        codec_ima::decode_qt(&mut stream, buffer, status)?;
+                    }
+                }
+            }
+            (InnerDecoder::G722 { decoders }, _) => {
+                // Each block contains one code per channel, and each code decodes to two samples.
+                let mut planes = self.buf.planes_mut();
+                let blocks = packet.buf().chunks_exact(channel_count).take(block_count);
+
+                for (block_id, codes) in blocks.enumerate() {
+                    let offset = frames_per_block * block_id;
+
+                    for ((plane, decoder), &code) in
+                        planes.planes().iter_mut().zip(decoders.iter_mut()).zip(codes)
+                    {
+                        decoder.decode(code, &mut plane[offset..offset + frames_per_block]);
+                    }
+                }
+            }
+            (InnerDecoder::AdpcmYamaha { status }, 1) => {
+                let buffer = self.buf.chan_mut(0);
+                codec_yamaha::decode_mono(&mut stream, buffer, &mut status[0])?;
+            }
+            (InnerDecoder::AdpcmYamaha { status }, 2) => {
+                let buffers = self.buf.chan_pair_mut(0, 1);
+                codec_yamaha::decode_stereo(&mut stream, [buffers.0, buffers.1], status)?;
+            }
+            (InnerDecoder::AdpcmSbPro { bits, status }, _) => {
+                // The first packet of a sound starts with a reference byte per channel. Such a
+                // packet is detected by its duration, which is then one frame longer than the
+                // duration of the coded samples alone.
+                let len = packet.buf().len();
+                let frames = block_count * frames_per_block;
+                let samples_per_byte = codec_sbpro::samples_per_byte(*bits);
+
+                let has_reference = if frames == len * samples_per_byte / channel_count {
+                    false
+                }
+                else if len >= channel_count
+                    && frames == 1 + (len - channel_count) * samples_per_byte / channel_count
+                {
+                    true
                 }
+                else {
+                    return decode_error("adpcm (sbpro): packet duration is invalid");
+                };
+
+                let mut planes = self.buf.planes_mut();
+                codec_sbpro::decode(&mut stream, planes.planes(), *bits, has_reference, status)?;
             }
             _ => unreachable!(),
         }
 
+        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+
         Ok(())
     }
 }
@@ -134,9 +227,89 @@ impl Decoder for AdpcmDecoder {
             return unsupported_error("adpcm: channels or channel_layout is required");
         };
 
+        let frames_per_block = params.frames_per_block.unwrap();
+
         let inner_decoder = match params.codec {
-            CODEC_TYPE_ADPCM_MS => InnerDecoder::AdpcmMs,
-            CODEC_TYPE_ADPCM_IMA_WAV => InnerDecoder::AdpcmIma,
+            CODEC_TYPE_ADPCM_MS => {
+                // Microsoft ADPCM is only defined for mono and stereo.
+                if spec.channels.count() > 2 {
+                    return unsupported_error("adpcm (ms): too many channels");
+                }
+
+                // If the format provided the encoder's coefficient table, use it. Otherwise, use
+                // the standard coefficients.
+                let coeffs = match &params.extra_data {
+                    Some(extra_data) => AdpcmMsCoeffs::read(&mut BufReader::new(extra_data))?,
+                    None => Default::default(),
+                };
+
+                InnerDecoder::AdpcmMs { coeffs }
+            }
+            CODEC_TYPE_ADPCM_IMA_WAV => {
+                // For more than one channel, the samples of each channel are interleaved in
+                // groups of 8 samples following the preambles.
+                if spec.channels.count() > 1 && (frames_per_block - 1) % 8 != 0 {
+                    return unsupported_error("adpcm (ima): invalid frames per block");
+                }
+
+                InnerDecoder::AdpcmIma
+            }
+            CODEC_TYPE_ADPCM_IMA_QT => {
+                // Each block of a channel is 34 bytes and decodes to exactly 64 samples.
+                if frames_per_block != IMA_QT_FRAMES_PER_BLOCK {
+                    return unsupported_error("adpcm (ima qt): invalid frames per block");
+                }
+
+                let status = (0..spec.channels.count()).map(|_| Default::default()).collect();
+
+                InnerDecoder::AdpcmImaQt { status }
+            }
+            CODEC_TYPE_ADPCM_G722 => {
+                // Each code decodes to exactly two samples.
+                if frames_per_block != 2 {
+                    return unsupported_error("adpcm (g722): invalid frames per block");
+                }
+
+                let decoders = (0..spec.channels.count()).map(|_| Default::default()).collect();
+
+                InnerDecoder::G722 { decoders }
+            }
+            CODEC_TYPE_ADPCM_YAMAHA => {
+                // Yamaha ADPCM is only defined for mono and stereo. Each block is one byte.
+                if spec.channels.count() > 2 {
+                    return unsupported_error("adpcm (yamaha): too many channels");
+                }
+
+                if frames_per_block != 2 / spec.channels.count() as u64 {
+                    return unsupported_error("adpcm (yamaha): invalid frames per block");
+                }
+
+                let status = (0..spec.channels.count()).map(|_| Default::default()).collect();
+
+                InnerDecoder::AdpcmYamaha { status }
+            }
+            CODEC_TYPE_ADPCM_SBPRO_4 | CODEC_TYPE_ADPCM_SBPRO_3 | CODEC_TYPE_ADPCM_SBPRO_2 => {
+                let bits = match params.codec {
+                    CODEC_TYPE_ADPCM_SBPRO_4 => 4,
+                    CODEC_TYPE_ADPCM_SBPRO_3 => 3,
+                    _ => 2,
+                };
+
+                // Creative 8-bit ADPCM is only defined for mono and stereo, and the 2.6-bit
+                // variant only for mono.
+                if spec.channels.count() > 2 || (bits == 3 && spec.channels.count() > 1) {
+                    return unsupported_error("adpcm (sbpro): too many channels");
+                }
+
+                // The samples are not coded in blocks.
+                if frames_per_block != 1 {
+                    return unsupported_error("adpcm (sbpro): invalid frames per block");
+                }
+
+                let status = (0..spec.channels.count()).map(|_| Default::default()).collect();
+
+                InnerDecoder::AdpcmSbPro { bits, status }
+            }
             _ => return unsupported_error("adpcm: codec is unsupported"),
         };
 
@@ -151,11 +324,40 @@ impl Decoder for AdpcmDecoder {
         &[
             support_codec!(CODEC_TYPE_ADPCM_MS, "adpcm_ms", "Microsoft ADPCM"),
             support_codec!(CODEC_TYPE_ADPCM_IMA_WAV, "adpcm_ima_wav", "ADPCM IMA WAV"),
+            support_codec!(CODEC_TYPE_ADPCM_IMA_QT, "adpcm_ima_qt", "ADPCM IMA QuickTime"),
+            support_codec!(CODEC_TYPE_ADPCM_G722, "adpcm_g722", "G.722 ADPCM"),
+            support_codec!(CODEC_TYPE_ADPCM_YAMAHA, "adpcm_yamaha", "Yamaha ADPCM"),
+            support_codec!(
+                CODEC_TYPE_ADPCM_SBPRO_4,
+                "adpcm_sbpro_4",
+                "Creative 8-bit ADPCM (4-bit)"
+            ),
+            support_codec!(
+                CODEC_TYPE_ADPCM_SBPRO_3,
+                "adpcm_sbpro_3",
+                "Creative 8-bit ADPCM (2.6-bit)"
+            ),
+            support_codec!(
+                CODEC_TYPE_ADPCM_SBPRO_2,
+                "adpcm_sbpro_2",
+                "Creative 8-bit ADPCM (2-bit)"
+            ),
         ]
     }
 
     fn reset(&mut self) {
-        // No state is stored between packets, therefore do nothing.
+        // Only the codecs without a preamble per block store state between packets.
+        match &mut self.inner_decoder {
+            InnerDecoder::AdpcmImaQt { status } => status.fill_with(Default::default),
+            InnerDecoder::G722 { decoders } => {
+                for decoder in decoders.iter_mut() {
+                    decoder.reset();
+                }
+            }
+            InnerDecoder::AdpcmYamaha { status } => status.fill_with(Default::default),
+            InnerDecoder::AdpcmSbPro { status, .. } => status.fill_with(Default::default),
+            _ => (),
+        }
     }
 
     fn codec_params(&self) -> &CodecParameters {
@@ -71,19 +71,31 @@ enum ChannelLayout {
     Mpeg7p1B,
 }
 
+/// A channel element, and the Symphonia `AudioBuffer` channel indicies it is decoded into.
+#[derive(Copy, Clone, Debug)]
+enum ChannelElement {
+    /// A Single Channel Element (SCE), or LFE Channel Element (LFE).
+    Single(u8),
+    /// A Channel Pair Element (CPE).
+    Pair(u8, u8),
+}
+
 impl ChannelLayout {
-    /// Given the current ALAC channel layout, this function will return a mappings of an ALAC
-    /// channel number (the index into the array) to a Symphonia `AudioBuffer` channel index.
-    fn channel_map(&self) -> [u8; 8] {
+    /// Given the current ALAC channel layout, this function will return the channel elements, in
+    /// the order they must appear in a frame, and the Symphonia `AudioBuffer` channel indicies
+    /// they are decoded into.
+    fn elements(&self) -> &'static [ChannelElement] {
+        use ChannelElement::{Pair, Single};
+
         match self {
-            ChannelLayout::Mono => [0, 0, 0, 0, 0, 0, 0, 0],
-            ChannelLayout::Stereo => [0, 1, 0, 0, 0, 0, 0, 0],
-            ChannelLayout::Mpeg3p0B => [2, 0, 1, 0, 0, 0, 0, 0],
-            ChannelLayout::Mpeg4p0B => [2, 0, 1, 3, 0, 0, 0, 0],
-            ChannelLayout::Mpeg5p0D => [2, 0, 1, 3, 4, 0, 0, 0],
-            ChannelLayout::Mpeg5p1D => [2, 0, 1, 4, 5, 3, 0, 0],
-            ChannelLayout::Aac6p1 => [2, 0, 1, 5, 6, 4, 3, 0],
-            ChannelLayout::Mpeg7p1B => [2, 4, 5, 0, 1, 6, 7, 3],
+            ChannelLayout::Mono => &[Single(0)],
+            ChannelLayout::Stereo => &[Pair(0, 1)],
+            ChannelLayout::Mpeg3p0B => &[Single(2), Pair(0, 1)],
+            ChannelLayout::Mpeg4p0B => &[Single(2), Pair(0, 1), Single(3)],
+            ChannelLayout::Mpeg5p0D => &[Single(2), Pair(0, 1), Pair(3, 4)],
+            ChannelLayout::Mpeg5p1D => &[Single(2), Pair(0, 1), Pair(4, 5), Single(3)],
+            ChannelLayout::Aac6p1 => &[Single(2), Pair(0, 1), Pair(5, 6), Single(4), Single(3)],
+            ChannelLayout::Mpeg7p1B => &[Single(2), Pair(4, 5), Pair(0, 1), Pair(6, 7), Single(3)],
         }
     }
 
@@ -159,8 +171,9 @@ struct MagicCookie {
 
 impl MagicCookie {
     fn try_read<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result<MagicCookie> {
-        // The magic cookie is either 24 or 48 bytes long.
-        if reader.byte_len() != 24 && reader.byte_len() != 48 {
+        // The magic cookie is either 24 or 48 bytes long, but may be followed by additional atoms
+        // that should be ignored.
+        if reader.byte_len() < 24 {
             return unsupported_error("alac: invalid magic cookie size");
         }
 
@@ -184,8 +197,8 @@ impl MagicCookie {
             return unsupported_error("alac: not compatible with alac version 0");
         }
 
-        // A bit-depth greater than 32 is not allowed.
-        if config.bit_depth > 32 {
+        // A bit-depth of 0, or greater than 32, is not allowed.
+        if config.bit_depth == 0 || config.bit_depth > 32 {
             return decode_error("alac: invalid bit depth");
         }
 
@@ -195,19 +208,18 @@ impl MagicCookie {
             return unsupported_error("alac: more than 8 channels");
         }
 
-        // If the magic cookie is 48 bytes, the channel layout is explictly set, otherwise select a
-        // channel layout from the number of channels.
-        config.channel_layout = if reader.byte_len() == 48 {
-            // The first field is the size of the channel layout info. This should always be 24.
-            if reader.read_be_u32()? != 24 {
-                return decode_error("alac: invalid channel layout info size");
-            }
+        // If the magic cookie is followed by the channel layout info, the channel layout is
+        // explicitly set, otherwise select a channel layout from the number of channels. The
+        // channel layout info starts with its size, which is always 24, and the ascii string
+        // "chan". Any other trailing atom is ignored.
+        let has_layout_info = reader.bytes_available() >= 24 && {
+            let size = reader.read_be_u32()?;
+            let id = reader.read_quad_bytes()?;
 
-            // The channel layout info identifier should be the ascii string "chan".
-            if reader.read_quad_bytes()? != *b"chan" {
-                return decode_error("alac: invalid channel layout info id");
-            }
+            size == 24 && id == *b"chan"
+        };
 
+        config.channel_layout = if has_layout_info {
             // The channel layout info version must be 0.
             if reader.read_be_u32()? != 0 {
                 return decode_error("alac: invalid channel layout info version");
@@ -414,12 +426,12 @@ impl ElementChannel {
                 .iter()
                 .rev()
                 .zip(&out[i - order..i])
-                .map(|(&coeff, &s)| coeff.wrapping_mul(s - past0))
+                .map(|(&coeff, &s)| coeff.wrapping_mul(s.wrapping_sub(past0)))
                 .fold(0i32, |sum, s| sum.wrapping_add(s));
 
             // Rewrite `1 << (self.shift - 1)` as `(1 << self.shift) >> 1` to prevent overflowing
             // when shift is 0.
-            let val = (sum + ((1 << self.shift) >> 1)) >> self.shift;
+            let val = sum.wrapping_add((1 << self.shift) >> 1) >> self.shift;
             out[i] = clip_msbs(out[i].wrapping_add(past0).wrapping_add(val), num_clip_bits);
 
             // Adjust the coefficients if the initial value of the residual was not 0.
@@ -431,12 +443,14 @@ impl ElementChannel {
                 if res > 0 {
                     // Positive residual case.
                     for (j, (coeff, &sample)) in iter {
-                        let val = past0 - sample;
+                        let val = past0.wrapping_sub(sample);
                         let sign = val.signum();
 
                         *coeff -= sign;
 
-                        res -= (1 + j as i32) * ((sign * val) >> self.shift);
+                        res = res.wrapping_sub(
+                            (1 + j as i32).wrapping_mul(sign.wrapping_mul(val) >> self.shift),
+                        );
 
                         if res <= 0 {
                             break;
@@ -446,12 +460,14 @@ impl ElementChannel {
                 else {
                     // Negative residual case.
                     for (j, (coeff, &sample)) in iter {
-                        let val = past0 - sample;
+                        let val = past0.wrapping_sub(sample);
                         let sign = val.signum();
 
                         *coeff += sign;
 
-                        res -= (1 + j as i32) * ((-sign * val) >> self.shift);
+                        res = res.wrapping_sub(
+                            (1 + j as i32).wrapping_mul((-sign).wrapping_mul(val) >> self.shift),
+                        );
 
                         if res >= 0 {
                             break;
@@ -482,9 +498,8 @@ impl AlacDecoder {
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
         let mut bs = BitReaderLtr::new(packet.buf());
 
-        let channel_map = self.config.channel_layout.channel_map();
-        let num_channels = self.config.num_channels as usize;
-        let mut next_channel = 0;
+        let elements = self.config.channel_layout.elements();
+        let mut next_element = 0;
         let mut num_frames = 0;
 
         // Fill the audio buffer with silence.
@@ -496,24 +511,27 @@ impl AlacDecoder {
 
             match tag {
                 ALAC_ELEM_TAG_SCE | ALAC_ELEM_TAG_LFE => {
-                    let out0 = self.buf.chan_mut(channel_map[next_channel] as usize);
+                    // The element must be a single channel element in the channel layout.
+                    let ch = match elements[next_element] {
+                        ChannelElement::Single(ch) => ch,
+                        _ => return decode_error("alac: unexpected single channel element"),
+                    };
+
+                    let out0 = self.buf.chan_mut(ch as usize);
 
                     num_frames =
                         decode_sce_or_cpe(&self.config, &mut bs, &mut self.tail_bits, out0, None)?;
 
-                    next_channel += 1;
+                    next_element += 1;
                 }
                 ALAC_ELEM_TAG_CPE => {
-                    // There may only be one channel left in the output buffer, do not attempt to
-                    // decode in this case.
-                    if next_channel + 2 > num_channels {
-                        break;
-                    }
+                    // The element must be a channel pair element in the channel layout.
+                    let (ch0, ch1) = match elements[next_element] {
+                        ChannelElement::Pair(ch0, ch1) => (ch0, ch1),
+                        _ => return decode_error("alac: unexpected channel pair element"),
+                    };
 
-                    let (out0, out1) = self.buf.chan_pair_mut(
-                        channel_map[next_channel + 0] as usize,
-                        channel_map[next_channel + 1] as usize,
-                    );
+                    let (out0, out1) = self.buf.chan_pair_mut(ch0 as usize, ch1 as usize);
 
                     num_frames = decode_sce_or_cpe(
                         &self.config,
@@ -523,7 +541,7 @@ impl AlacDecoder {
                         Some(out1),
                     )?;
 
-                    next_channel += 2;
+                    next_element += 1;
                 }
                 ALAC_ELEM_TAG_DSE => {
                     let _tag = bs.read_bits_leq32(4)?;
@@ -558,8 +576,8 @@ impl AlacDecoder {
                 _ => unreachable!(),
             }
 
-            // Exit if all channels are decoded.
-            if next_channel >= num_channels {
+            // Exit if all channel elements are decoded.
+            if next_element >= elements.len() {
                 break;
             }
         }
@@ -575,6 +593,13 @@ impl AlacDecoder {
             self.buf.transform(|sample| sample << shift);
         }
 
+        // The trim at the end of a packet is relative to its nominal duration. The final packet
+        // may code fewer frames than that, in which case less of it is trimmed.
+        let missing_frames = (packet.block_dur() as usize).saturating_sub(num_frames);
+        let trim_end = (packet.trim_end() as usize).saturating_sub(missing_frames);
+
+        self.buf.trim(packet.trim_start() as usize, trim_end);
+
         Ok(())
     }
 }
@@ -588,7 +613,7 @@ impl Decoder for AlacDecoder {
 
         // Read the config (magic cookie).
         let config = if let Some(extra_data) = &params.extra_data {
-            MagicCookie::try_read(&mut BufReader::new(extra_data))?
+            MagicCookie::try_read(&mut BufReader::new(strip_cookie_atoms(extra_data)))?
         }
         else {
             return unsupported_error("alac: missing extra data");
@@ -633,6 +658,25 @@ impl Decoder for AlacDecoder {
     }
 }
 
+/// Strips the atoms that may precede the magic cookie.
+///
+/// Older encoders, and some containers (e.g., CAF), store the magic cookie as a sequence of atoms:
+/// an optional format ('frma') atom, followed by an 'alac' atom containing the magic cookie. The
+/// magic cookie may then be followed by a channel layout ('chan') atom, and a terminator atom.
+fn strip_cookie_atoms(mut buf: &[u8]) -> &[u8] {
+    // The format atom is 12 bytes: the atom size, the atom type, and the format type ('alac').
+    if buf.len() >= 12 && &buf[4..8] == b"frma" {
+        buf = &buf[12..];
+    }
+
+    // The 'alac' atom header is 12 bytes: the atom size, the atom type, and the version and flags.
+    if buf.len() >= 12 && &buf[4..8] == b"alac" {
+        buf = &buf[12..];
+    }
+
+    buf
+}
+
 /// Reads and decodes a SCE or CPE (if the second output channel not `None`).
 fn decode_sce_or_cpe<B: ReadBitsLtr>(
     config: &MagicCookie,
@@ -662,6 +706,12 @@ fn decode_sce_or_cpe<B: ReadBitsLtr>(
         return decode_error("alac: invalid shift value");
     }
 
+    // The number of predicted bits must not exceed 32-bits. Only 32-bit channel pairs without a
+    // shift could exceed this limit, but these are never produced by an encoder.
+    if !is_uncompressed && config.bit_depth - shift + u8::from(is_cpe) > 32 {
+        return decode_error("alac: invalid shift value for bit depth");
+    }
+
     // If this is a partial frame, then read the frame length from the element,
     // otherwise use the frame length in the configuration.
     let num_samples =
@@ -811,11 +861,356 @@ fn clip_msbs(val: i32, num: u32) -> i32 {
 }
 
 /// Decorrelates a mid-side channel pair.
+///
+/// For high bit-depths, the intermediate values may exceed 32-bits. Like the reference decoder,
+/// these must wrap.
 fn decorrelate_mid_side(out0: &mut [i32], out1: &mut [i32], weight: i32, shift: u8) {
     assert!(out0.len() == out1.len());
 
     for (s0, s1) in out0.iter_mut().zip(out1.iter_mut()) {
-        *s0 = *s0 + *s1 - ((*s1 * weight) >> shift);
-        *s1 = *s0 - *s1;
+        *s0 = s0.wrapping_add(*s1).wrapping_sub(s1.wrapping_mul(weight) >> shift);
+        *s1 = s0.wrapping_sub(*s1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::audio::{AudioBufferRef, Channels, Signal};
+    use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_ALAC};
+    use symphonia_core::errors::Error;
+    use symphonia_core::formats::Packet;
+    use symphonia_core::io::BufReader;
+
+    use super::{strip_cookie_atoms, AlacDecoder, MagicCookie};
+    use super::{ALAC_ELEM_TAG_CPE, ALAC_ELEM_TAG_END, ALAC_ELEM_TAG_LFE, ALAC_ELEM_TAG_SCE};
+
+    const FRAME_LEN: usize = 4;
+
+    /// A minimal bit writer for building ALAC frames.
+    #[derive(Default)]
+    struct BitWriter {
+        buf: Vec<u8>,
+        len: u64,
+    }
+
+    impl BitWriter {
+        fn write_bits(&mut self, value: u32, num_bits: u32) {
+            for i in (0..num_bits).rev() {
+                if self.len & 0x7 == 0 {
+                    self.buf.push(0);
+                }
+
+                if (value >> i) & 1 != 0 {
+                    *self.buf.last_mut().unwrap() |= 0x80 >> (self.len & 0x7);
+                }
+
+                self.len += 1;
+            }
+        }
+
+        /// Write the element header of a full frame.
+        fn write_element_header(&mut self, tag: u32, is_uncompressed: bool) {
+            self.write_bits(tag, 3);
+            // Element instance tag, and unused header bits.
+            self.write_bits(0, 4 + 12);
+            // Partial frame flag, and shift.
+            self.write_bits(0, 1 + 2);
+            self.write_bits(u32::from(is_uncompressed), 1);
+        }
+
+        /// Write an uncompressed SCE or CPE element.
+        fn write_uncompressed(&mut self, tag: u32, bit_depth: u32, channels: &[[i32; FRAME_LEN]]) {
+            self.write_element_header(tag, true);
+
+            for i in 0..FRAME_LEN {
+                for samples in channels {
+                    self.write_bits(samples[i] as u32 & (!0 >> (32 - bit_depth)), bit_depth);
+                }
+            }
+        }
+
+        /// Write a compressed CPE element without prediction or mid-side coding, where every
+        /// residual is escaped.
+        fn write_escaped_cpe(&mut self, pred_bits: u32, channels: &[[i32; FRAME_LEN]; 2]) {
+            self.write_element_header(ALAC_ELEM_TAG_CPE, false);
+
+            // Mid-side shift and weight.
+            self.write_bits(0, 8 + 8);
+
+            // Mode, shift, rice modifier, and a predictor order of 0, for each channel.
+            for _ in channels {
+                self.write_bits(0, 4 + 4 + 3 + 5);
+            }
+
+            for samples in channels {
+                for &sample in samples {
+                    // An escaped residual is a prefix of 9 ones, followed by the residual.
+                    self.write_bits(0x1ff, 9);
+                    self.write_bits(((sample << 1) ^ (sample >> 31)) as u32, pred_bits);
+                }
+            }
+        }
+    }
+
+    fn make_cookie(num_channels: u8, bit_depth: u8) -> Vec<u8> {
+        let mut cookie = Vec::new();
+        cookie.extend_from_slice(&(FRAME_LEN as u32).to_be_bytes());
+        // Compatible version, bit depth, pb, mb, kb, and number of channels. A pb of 0, and a mb
+        // of 255, keeps the rice parameter constant and prevents runs of zeros.
+        cookie.extend_from_slice(&[0, bit_depth, 0, 255, 14, num_channels]);
+        // Maximum run, maximum frame bytes, average bit rate, and sample rate.
+        cookie.extend_from_slice(&255u16.to_be_bytes());
+        cookie.extend_from_slice(&0u32.to_be_bytes());
+        cookie.extend_from_slice(&0u32.to_be_bytes());
+        cookie.extend_from_slice(&44100u32.to_be_bytes());
+        cookie
+    }
+
+    fn make_layout_info(tag: u32) -> Vec<u8> {
+        let mut info = Vec::new();
+        info.extend_from_slice(&24u32.to_be_bytes());
+        info.extend_from_slice(b"chan");
+        // Version, channel layout tag, and two reserved fields.
+        info.extend_from_slice(&0u32.to_be_bytes());
+        info.extend_from_slice(&tag.to_be_bytes());
+        info.extend_from_slice(&[0; 8]);
+        info
+    }
+
+    fn read_cookie(buf: &[u8]) -> Result<MagicCookie, Error> {
+        MagicCookie::try_read(&mut BufReader::new(strip_cookie_atoms(buf)))
+    }
+
+    fn make_decoder(num_channels: u8, bit_depth: u8) -> AlacDecoder {
+        let cookie = make_cookie(num_channels, bit_depth);
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_ALAC).with_extra_data(cookie.into_boxed_slice());
+
+        AlacDecoder::try_new(&params, &DecoderOptions::default()).unwrap()
+    }
+
+    fn decode(decoder: &mut AlacDecoder, bw: BitWriter) -> Result<Vec<Vec<i32>>, Error> {
+        let packet =
+            Packet::new_from_boxed_slice(0, 0, FRAME_LEN as u64, bw.buf.into_boxed_slice());
+
+        match decoder.decode(&packet)? {
+            AudioBufferRef::S32(buf) => {
+                Ok((0..buf.spec().channels.count()).map(|ch| buf.chan(ch).to_vec()).collect())
+            }
+            _ => panic!("expected s32 samples"),
+        }
+    }
+
+    fn shifted(samples: &[i32; FRAME_LEN], bit_depth: u32) -> Vec<i32> {
+        samples.iter().map(|&s| s << (32 - bit_depth)).collect()
+    }
+
+    #[test]
+    fn verify_decode_multichannel() {
+        let mut decoder = make_decoder(6, 16);
+
+        let left = [1000, -1001, 1002, -1003];
+        let right = [2000, -2001, 2002, -2003];
+        let centre = [3000, -3001, 3002, -3003];
+        let lfe = [4000, -4001, 4002, -4003];
+        let side_left = [-32768, 32767, 0, -1];
+        let side_right = [5000, -5001, 5002, -5003];
+
+        // A 5.1 frame is coded as centre, front pair, side pair, and then the LFE.
+        let mut bw = BitWriter::default();
+        bw.write_uncompressed(ALAC_ELEM_TAG_SCE, 16, &[centre]);
+        bw.write_uncompressed(ALAC_ELEM_TAG_CPE, 16, &[left, right]);
+        bw.write_uncompressed(ALAC_ELEM_TAG_CPE, 16, &[side_left, side_right]);
+        bw.write_uncompressed(ALAC_ELEM_TAG_LFE, 16, &[lfe]);
+        bw.write_bits(ALAC_ELEM_TAG_END, 3);
+
+        // Symphonia orders the channels as front left, front right, centre, LFE, side left, and
+        // side right.
+        let expected = [left, right, centre, lfe, side_left, side_right];
+
+        let decoded = decode(&mut decoder, bw).unwrap();
+
+        assert_eq!(decoded.len(), expected.len());
+
+        for (decoded, expected) in decoded.iter().zip(&expected) {
+            assert_eq!(decoded, &shifted(expected, 16));
+        }
+    }
+
+    #[test]
+    fn verify_decode_24bit() {
+        let mut decoder = make_decoder(2, 24);
+
+        let left = [0x7f_ffff, -0x80_0000, 0x12_3456, -1];
+        let right = [-0x65_4321, 0, 0x7f_fffe, -0x7f_ffff];
+
+        // An uncompressed frame.
+        let mut bw = BitWriter::default();
+        bw.write_uncompressed(ALAC_ELEM_TAG_CPE, 24, &[left, right]);
+        bw.write_bits(ALAC_ELEM_TAG_END, 3);
+
+        let decoded = decode(&mut decoder, bw).unwrap();
+
+        assert_eq!(decoded[0], shifted(&left, 24));
+        assert_eq!(decoded[1], shifted(&right, 24));
+
+        // A compressed frame. Each channel of a pair is predicted with one extra bit.
+        let mut bw = BitWriter::default();
+        bw.write_escaped_cpe(25, &[left, right]);
+        bw.write_bits(ALAC_ELEM_TAG_END, 3);
+
+        let decoded = decode(&mut decoder, bw).unwrap();
+
+        assert_eq!(decoded[0], shifted(&left, 24));
+        assert_eq!(decoded[1], shifted(&right, 24));
+    }
+
+    #[test]
+    fn verify_decode_mismatched_elements() {
+        let samples = [0, 1, 2, 3];
+
+        // A stereo frame must be coded as a channel pair, not a single channel.
+        let mut decoder = make_decoder(2, 16);
+
+        let mut bw = BitWriter::default();
+        bw.write_uncompressed(ALAC_ELEM_TAG_SCE, 16, &[samples]);
+        bw.write_uncompressed(ALAC_ELEM_TAG_SCE, 16, &[samples]);
+        bw.write_bits(ALAC_ELEM_TAG_END, 3);
+
+        match decode(&mut decoder, bw) {
+            Err(Error::DecodeError(msg)) => {
+                assert_eq!(msg, "alac: unexpected single channel element")
+            }
+            _ => panic!("expected a decode error"),
+        }
+
+        // A 3 channel frame must be coded as the centre, and then the front pair.
+        let mut decoder = make_decoder(3, 16);
+
+        let mut bw = BitWriter::default();
+        bw.write_uncompressed(ALAC_ELEM_TAG_CPE, 16, &[samples, samples]);
+        bw.write_uncompressed(ALAC_ELEM_TAG_SCE, 16, &[samples]);
+        bw.write_bits(ALAC_ELEM_TAG_END, 3);
+
+        match decode(&mut decoder, bw) {
+            Err(Error::DecodeError(msg)) => {
+                assert_eq!(msg, "alac: unexpected channel pair element")
+            }
+            _ => panic!("expected a decode error"),
+        }
+    }
+
+    #[test]
+    fn verify_read_bare_cookie() {
+        // Without the channel layout info, the channel layout is selected by the number of
+        // channels.
+        let cookie = read_cookie(&make_cookie(3, 24)).unwrap();
+
+        assert_eq!(cookie.frame_length, FRAME_LEN as u32);
+        assert_eq!(cookie.bit_depth, 24);
+        assert_eq!(cookie.num_channels, 3);
+        assert_eq!(cookie.sample_rate, 44100);
+        assert_eq!(
+            cookie.channel_layout.channels(),
+            Channels::FRONT_CENTRE | Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+        );
+
+        // The magic cookie must be at least 24 bytes.
+        assert!(read_cookie(&make_cookie(3, 24)[..23]).is_err());
+    }
+
+    #[test]
+    fn verify_read_cookie_with_layout_info() {
+        // A 4 channel MPEG 4.0 B layout.
+        let mut buf = make_cookie(4, 16);
+        buf.extend(make_layout_info(0x74_0004));
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(
+            cookie.channel_layout.channels(),
+            Channels::FRONT_CENTRE
+                | Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::REAR_CENTRE
+        );
+
+        // The number of channels must match the channel layout.
+        let mut buf = make_cookie(3, 16);
+        buf.extend(make_layout_info(0x74_0004));
+
+        assert!(read_cookie(&buf).is_err());
+    }
+
+    #[test]
+    fn verify_read_cookie_with_trailing_atoms() {
+        // A terminator atom.
+        let terminator = [0, 0, 0, 8, 0, 0, 0, 0];
+
+        // A 24 byte trailing atom that is not the channel layout info must be ignored.
+        let mut buf = make_cookie(2, 16);
+        buf.extend_from_slice(&24u32.to_be_bytes());
+        buf.extend_from_slice(b"free");
+        buf.extend_from_slice(&[0; 16]);
+        buf.extend_from_slice(&terminator);
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(cookie.channel_layout.channels(), Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        // A terminator atom alone must be ignored.
+        let mut buf = make_cookie(1, 16);
+        buf.extend_from_slice(&terminator);
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(cookie.channel_layout.channels(), Channels::FRONT_LEFT);
+
+        // The channel layout info followed by a terminator atom.
+        let mut buf = make_cookie(6, 16);
+        buf.extend(make_layout_info(0x7c_0006));
+        buf.extend_from_slice(&terminator);
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(cookie.channel_layout.channels().count(), 6);
+        assert!(cookie.channel_layout.channels().contains(Channels::LFE1));
+    }
+
+    #[test]
+    fn verify_read_atom_wrapped_cookie() {
+        let cookie = make_cookie(2, 16);
+
+        // The 'alac' atom, containing the magic cookie.
+        let mut alac = Vec::new();
+        alac.extend_from_slice(&(12 + cookie.len() as u32).to_be_bytes());
+        alac.extend_from_slice(b"alac");
+        alac.extend_from_slice(&[0; 4]);
+        alac.extend_from_slice(&cookie);
+
+        // The 'alac' atom, followed by the channel layout info, and a terminator atom.
+        let mut buf = alac.clone();
+        buf.extend(make_layout_info(0x65_0002));
+        buf.extend_from_slice(&[0, 0, 0, 8, 0, 0, 0, 0]);
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(cookie.num_channels, 2);
+        assert_eq!(cookie.sample_rate, 44100);
+        assert_eq!(cookie.channel_layout.channels(), Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        // The format atom, followed by the 'alac' atom.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&12u32.to_be_bytes());
+        buf.extend_from_slice(b"frma");
+        buf.extend_from_slice(b"alac");
+        buf.extend_from_slice(&alac);
+
+        let cookie = read_cookie(&buf).unwrap();
+
+        assert_eq!(cookie.num_channels, 2);
+        assert_eq!(cookie.sample_rate, 44100);
+        assert_eq!(cookie.channel_layout.channels(), Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
     }
 }
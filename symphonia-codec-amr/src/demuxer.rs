@@ -0,0 +1,289 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{ErrorKind, Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AMR_NB, CODEC_TYPE_AMR_WB};
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+/// The maximum length of a file signature, including the terminating newline.
+const MAX_MAGIC_LEN: usize = 15;
+
+/// The length of the speech data of an AMR-NB frame, indexed by the frame type. Frame types
+/// without speech data have a length of 0.
+const NB_FRAME_LEN: [usize; 16] = [12, 13, 15, 17, 19, 20, 26, 31, 5, 0, 0, 0, 0, 0, 0, 0];
+
+/// The length of the speech data of an AMR-WB frame, indexed by the frame type.
+const WB_FRAME_LEN: [usize; 16] = [17, 23, 32, 36, 40, 46, 50, 58, 60, 5, 0, 0, 0, 0, 0, 0];
+
+/// The number of samples per channel in a frame of AMR-NB.
+const NB_FRAME_DUR: u64 = 160;
+
+/// The number of samples per channel in a frame of AMR-WB.
+const WB_FRAME_DUR: u64 = 320;
+
+/// Adaptive Multi-Rate (AMR) file format reader.
+///
+/// `AmrReader` implements a demuxer for the AMR-NB and AMR-WB storage format of RFC 4867, including
+/// the multi-channel variants. Each packet is a frame-block containing one frame, with its header,
+/// per channel.
+pub struct AmrReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    wideband: bool,
+    num_channels: usize,
+    first_frame_pos: u64,
+    /// The number of samples per channel in a frame-block.
+    frame_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for AmrReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "amr",
+            "Adaptive Multi-Rate",
+            &["amr", "awb"],
+            &["audio/amr", "audio/amr-wb"],
+            &[b"#!AMR\n", b"#!AMR-WB\n", b"#!AMR_MC1.0\n", b"#!AMR-WB_MC1.0\n"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Get the channels of a multi-channel file. The channel orders are those of RFC 3551.
+fn channels(num_channels: usize) -> Result<Channels> {
+    let channels = match num_channels {
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE,
+        4 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_CENTRE
+                | Channels::FRONT_RIGHT
+                | Channels::REAR_CENTRE
+        }
+        6 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_LEFT_CENTRE
+                | Channels::FRONT_CENTRE
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_RIGHT_CENTRE
+                | Channels::REAR_CENTRE
+        }
+        _ => return unsupported_error("amr: unsupported number of channels"),
+    };
+
+    Ok(channels)
+}
+
+/// Reads a frame-block of one frame per channel.
+fn read_frame_block<B: ReadBytes>(
+    reader: &mut B,
+    wideband: bool,
+    num_channels: usize,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+
+    for _ in 0..num_channels {
+        let header = reader.read_u8()?;
+
+        // The padding bits of the frame header must be 0.
+        if header & 0x83 != 0 {
+            return decode_error("amr: invalid frame header");
+        }
+
+        let frame_type = usize::from(header >> 3);
+
+        let len = if wideband { WB_FRAME_LEN[frame_type] } else { NB_FRAME_LEN[frame_type] };
+
+        buf.push(header);
+
+        let start = buf.len();
+        buf.resize(start + len, 0);
+        reader.read_buf_exact(&mut buf[start..])?;
+    }
+
+    Ok(buf)
+}
+
+impl FormatReader for AmrReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // The file signature is terminated by a newline.
+        let mut magic = Vec::with_capacity(MAX_MAGIC_LEN);
+
+        while magic.len() < MAX_MAGIC_LEN {
+            let byte = source.read_u8()?;
+            magic.push(byte);
+
+            if byte == b'\n' {
+                break;
+            }
+        }
+
+        let (wideband, multichannel) = match magic.as_slice() {
+            b"#!AMR\n" => (false, false),
+            b"#!AMR-WB\n" => (true, false),
+            b"#!AMR_MC1.0\n" => (false, true),
+            b"#!AMR-WB_MC1.0\n" => (true, true),
+            _ => return unsupported_error("amr: missing file signature"),
+        };
+
+        // Multi-channel files have a channel description following the signature.
+        let num_channels = if multichannel {
+            match source.read_be_u32()? & 0xf {
+                0 => return decode_error("amr: invalid number of channels"),
+                num_channels => num_channels as usize,
+            }
+        }
+        else {
+            1
+        };
+
+        let first_frame_pos = source.pos();
+
+        let (codec, sample_rate, frame_dur) = if wideband {
+            (CODEC_TYPE_AMR_WB, 16000, WB_FRAME_DUR)
+        }
+        else {
+            (CODEC_TYPE_AMR_NB, 8000, NB_FRAME_DUR)
+        };
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(codec)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_channels(channels(num_channels)?)
+            .with_max_frames_per_packet(frame_dur)
+            .with_frames_per_block(frame_dur);
+
+        Ok(AmrReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            wideband,
+            num_channels,
+            first_frame_pos,
+            frame_dur,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = read_frame_block(&mut self.reader, self.wideband, self.num_channels)?;
+
+        let ts = self.next_packet_ts;
+
+        self.next_packet_ts += self.frame_dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, self.frame_dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        // The length of a frame depends on its frame type, so the stream must be scanned. Backward
+        // seeks scan from the first frame-block.
+        if required_ts < self.next_packet_ts {
+            if !self.reader.is_seekable() {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+            self.next_packet_ts = 0;
+        }
+
+        while self.next_packet_ts + self.frame_dur <= required_ts {
+            match read_frame_block(&mut self.reader, self.wideband, self.num_channels) {
+                Ok(_) => self.next_packet_ts += self.frame_dur,
+                Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return seek_error(SeekErrorKind::OutOfRange);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+
+    use super::read_frame_block;
+
+    #[test]
+    fn verify_read_frame_block() {
+        // A 12.2 kbit/s speech frame, followed by a no data frame, of a 2 channel AMR-NB file.
+        let mut data = vec![0x3c];
+        data.extend_from_slice(&[0x55; 31]);
+        data.push(0x7c);
+
+        let buf = read_frame_block(&mut BufReader::new(&data), false, 2).unwrap();
+        assert_eq!(buf, data);
+
+        // The padding bits must be 0.
+        assert!(read_frame_block(&mut BufReader::new(&[0x3d]), false, 1).is_err());
+    }
+}
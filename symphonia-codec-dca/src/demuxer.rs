@@ -0,0 +1,352 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_DCA};
+use symphonia_core::errors::{seek_error, Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use crate::header::*;
+
+/// The number of bytes to start searching before the estimated position of a frame when seeking.
+const SEEK_BACKOFF: u64 = 16;
+
+/// DTS Coherent Acoustics (DCA) elementary stream format reader.
+///
+/// `DcaReader` implements a demuxer for a stream of DTS core frames in any of the 16-bit or 14-bit
+/// packings. Each packet is a core frame, converted to 16-bit big-endian words, followed by any
+/// DTS-HD extension substream frames that follow it.
+pub struct DcaReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    packing: Packing,
+    first_frame_pos: u64,
+    /// The average length of a packet in bytes of the source stream.
+    avg_frame_len: f64,
+    /// The number of samples per channel in a packet.
+    frame_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for DcaReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "dca",
+            "DTS Coherent Acoustics",
+            &["dts"],
+            &["audio/vnd.dts", "audio/vnd.dts.hd"],
+            &[
+                &[0x7f, 0xfe, 0x80, 0x01],
+                &[0xfe, 0x7f, 0x01, 0x80],
+                &[0x1f, 0xff, 0xe8, 0x00],
+                &[0xff, 0x1f, 0x00, 0xe8]
+            ]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Synchronizes to, and reads, the header of the next core frame. If `packing` is provided, only
+/// frames with that packing are accepted. Returns the position of the frame, and the normalized
+/// header. The reader is positioned after the packed header.
+fn read_header<B: ReadBytes + SeekBuffered>(
+    reader: &mut B,
+    packing: Option<Packing>,
+) -> Result<(u64, Packing, FrameHeader)> {
+    let mut sync = [0; SYNC_LEN];
+
+    loop {
+        sync.rotate_left(1);
+        sync[SYNC_LEN - 1] = reader.read_u8()?;
+
+        let found = match Packing::detect(&sync) {
+            Some(found) if packing.map_or(true, |packing| packing == found) => found,
+            _ => continue,
+        };
+
+        let packed_len = found.packed_len(HEADER_LEN);
+
+        let mut packed = vec![0; packed_len];
+        packed[..SYNC_LEN].copy_from_slice(&sync);
+        reader.read_buf_exact(&mut packed[SYNC_LEN..])?;
+
+        let mut buf = Vec::with_capacity(HEADER_LEN);
+        found.normalize(&packed, HEADER_LEN, &mut buf);
+
+        match FrameHeader::read(&buf) {
+            Ok(header) => return Ok((reader.pos() - packed_len as u64, found, header)),
+            Err(Error::DecodeError(_)) => {
+                // Resume searching from the byte following the false synchronization word.
+                reader.seek_buffered_rev(packed_len - 1);
+                sync = [0; SYNC_LEN];
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Synchronizes to, and reads, the next core frame. Returns the position of the frame, and the
+/// normalized frame.
+fn read_frame(
+    reader: &mut MediaSourceStream,
+    packing: Option<Packing>,
+) -> Result<(u64, Packing, FrameHeader, Vec<u8>)> {
+    let (pos, packing, header) = read_header(reader, packing)?;
+
+    // The packet is the entire frame, including the header.
+    let packed_len = packing.packed_len(header.frame_len);
+
+    let mut packed = vec![0; packed_len];
+    reader.seek_buffered(pos);
+    reader.read_buf_exact(&mut packed)?;
+
+    let mut buf = Vec::with_capacity(header.frame_len);
+    packing.normalize(&packed, header.frame_len, &mut buf);
+
+    Ok((pos, packing, header, buf))
+}
+
+/// Returns true if the bytes are the start of a core frame, or an extension substream frame, with
+/// the given packing.
+fn is_sync(packing: Packing, bytes: [u8; 4]) -> bool {
+    match packing {
+        Packing::Be16 => matches!(u32::from_be_bytes(bytes), SYNC_WORD | EXSS_SYNC_WORD),
+        Packing::Le16 => {
+            let word = u32::from_be_bytes([bytes[1], bytes[0], bytes[3], bytes[2]]);
+            matches!(word, SYNC_WORD | EXSS_SYNC_WORD)
+        }
+        Packing::Be14 => bytes == [0x1f, 0xff, 0xe8, 0x00],
+        Packing::Le14 => bytes == [0xff, 0x1f, 0x00, 0xe8],
+    }
+}
+
+/// Synchronizes to, and reads, the next core frame that is followed by another frame, or the end of
+/// the stream.
+fn read_frame_strict(
+    reader: &mut MediaSourceStream,
+    packing: Option<Packing>,
+) -> Result<(u64, Packing, FrameHeader, Vec<u8>)> {
+    loop {
+        let (start, found, header, buf) = read_frame(reader, packing)?;
+
+        // Get the position before trying to read the next synchronization word.
+        let pos = reader.pos();
+
+        // If the read fails then the stream may have ended, and this check cannot be performed.
+        let mut next = [0; 4];
+
+        if reader.read_buf_exact(&mut next).is_ok() && !is_sync(found, next) {
+            warn!("skipping junk at {} bytes", start);
+
+            // Seek back to the second byte of the rejected frame to prevent synchronizing to the
+            // same spot again.
+            reader.seek_buffered(start + 1);
+            continue;
+        }
+
+        // Jump back to the position before the next synchronization word was read.
+        reader.seek_buffered(pos);
+
+        break Ok((start, found, header, buf));
+    }
+}
+
+/// Synchronizes to, and reads, the next packet. The packet is a core frame followed by any
+/// extension substream frames. If `strict` is true, the core frame must be followed by another
+/// frame, or the end of the stream. Returns the position of the packet.
+fn read_packet(
+    reader: &mut MediaSourceStream,
+    packing: Option<Packing>,
+    strict: bool,
+) -> Result<(u64, Packing, FrameHeader, Vec<u8>)> {
+    let (start, packing, header, mut buf) =
+        if strict { read_frame_strict(reader, packing)? } else { read_frame(reader, packing)? };
+
+    // Extension substreams are only carried by 16-bit streams.
+    if packing == Packing::Be14 || packing == Packing::Le14 {
+        return Ok((start, packing, header, buf));
+    }
+
+    loop {
+        let pos = reader.pos();
+
+        let mut packed = vec![0; packing.packed_len(EXSS_HEADER_LEN)];
+
+        let len = match reader.read_buf_exact(&mut packed) {
+            Ok(_) => {
+                let mut exss = Vec::with_capacity(EXSS_HEADER_LEN);
+                packing.normalize(&packed, EXSS_HEADER_LEN, &mut exss);
+                read_exss_len(&exss).ok()
+            }
+            Err(_) => None,
+        };
+
+        reader.seek_buffered(pos);
+
+        match len {
+            Some(len) => {
+                let mut packed = vec![0; packing.packed_len(len)];
+                reader.read_buf_exact(&mut packed)?;
+                packing.normalize(&packed, len, &mut buf);
+            }
+            None => break,
+        }
+    }
+
+    Ok((start, packing, header, buf))
+}
+
+impl FormatReader for DcaReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let (first_frame_pos, packing, header, _) = read_packet(&mut source, None, true)?;
+
+        // The average packet length is the length of the first packet in the source stream. Each
+        // packet has a constant duration.
+        let avg_frame_len = (source.pos() - first_frame_pos) as f64;
+
+        // Rewind back to the start of the packet.
+        source.seek_buffered(first_frame_pos);
+
+        let frame_dur = header.duration() as u64;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_DCA)
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_max_frames_per_packet(frame_dur)
+            .with_frames_per_block(frame_dur);
+
+        if let Some(channels) = header.channels() {
+            params.with_channels(channels);
+        }
+
+        if let Some(byte_len) = source.byte_len() {
+            let n_frames = ((byte_len - first_frame_pos) as f64 / avg_frame_len).round() as u64;
+            params.with_n_frames(n_frames * frame_dur);
+        }
+
+        debug!("detected {:?} packing", packing);
+
+        Ok(DcaReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            packing,
+            first_frame_pos,
+            avg_frame_len,
+            frame_dur,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (_, _, header, buf) = read_packet(&mut self.reader, Some(self.packing), false)?;
+
+        let ts = self.next_packet_ts;
+        let dur = header.duration() as u64;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = self.tracks[0].codec_params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        let frame_idx = required_ts / self.frame_dur;
+
+        if self.reader.is_seekable() {
+            // Assuming the bit rate is constant, the position of the frame can be calculated.
+            let offset = (frame_idx as f64 * self.avg_frame_len) as u64;
+            let pos = self.first_frame_pos + offset.saturating_sub(SEEK_BACKOFF);
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            let (pos, _, _, _) = read_packet(&mut self.reader, Some(self.packing), true)?;
+
+            // Rewind back to the start of the packet.
+            self.reader.seek_buffered(pos);
+
+            // Determine the index of the packet from its position.
+            let offset = (pos - self.first_frame_pos) as f64;
+            self.next_packet_ts = (offset / self.avg_frame_len).round() as u64 * self.frame_dur;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            if required_ts < self.next_packet_ts {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            while self.next_packet_ts + self.frame_dur <= required_ts {
+                let (_, _, header, _) = read_packet(&mut self.reader, Some(self.packing), false)?;
+                self.next_packet_ts += header.duration() as u64;
+            }
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,298 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+
+/// The synchronization word of a core frame in a 16-bit big-endian stream.
+pub const SYNC_WORD: u32 = 0x7ffe_8001;
+
+/// The synchronization word of a DTS-HD extension substream in a 16-bit big-endian stream.
+pub const EXSS_SYNC_WORD: u32 = 0x6458_2025;
+
+/// The number of bytes required to detect the packing of a stream.
+pub const SYNC_LEN: usize = 6;
+
+/// The number of bytes of a normalized core frame header required to describe the stream.
+pub const HEADER_LEN: usize = 12;
+
+/// The number of bytes of a normalized extension substream header required to read its length.
+pub const EXSS_HEADER_LEN: usize = 10;
+
+/// The number of samples per channel in a PCM block.
+const PCM_BLOCK_LEN: usize = 32;
+
+/// The minimum length of a core frame in bytes.
+const MIN_FRAME_LEN: usize = 96;
+
+/// The minimum number of PCM blocks in a core frame.
+const MIN_PCM_BLOCKS: usize = 6;
+
+/// Core sample rates, indexed by `SFREQ`. A rate of 0 is invalid.
+const SAMPLE_RATES: [u32; 16] =
+    [0, 8000, 16000, 32000, 0, 0, 11025, 22050, 44100, 0, 0, 12000, 24000, 48000, 0, 0];
+
+/// The packing of the words of a stream. A 14-bit stream stores 14 bits of the bit stream in each
+/// 16-bit word, and is typically found on audio CDs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Packing {
+    Be16,
+    Le16,
+    Be14,
+    Le14,
+}
+
+impl Packing {
+    /// Detect the packing of a stream from the bytes at the start of a core frame.
+    pub fn detect(buf: &[u8; SYNC_LEN]) -> Option<Packing> {
+        let sync = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        let next = u16::from_be_bytes([buf[4], buf[5]]);
+
+        // In 14-bit streams, the synchronization word is followed by 4 more bits of the sync.
+        match sync {
+            SYNC_WORD => Some(Packing::Be16),
+            0xfe7f_0180 => Some(Packing::Le16),
+            0x1fff_e800 if next & 0xfff0 == 0x07f0 => Some(Packing::Be14),
+            0xff1f_00e8 if next & 0xf0ff == 0xf007 => Some(Packing::Le14),
+            _ => None,
+        }
+    }
+
+    /// Get the number of packed bytes occupied by the given number of bit stream bytes.
+    pub fn packed_len(&self, len: usize) -> usize {
+        match self {
+            Packing::Be16 => len,
+            // Little-endian words are always complete.
+            Packing::Le16 => len + (len & 1),
+            Packing::Be14 | Packing::Le14 => 2 * ((8 * len + 13) / 14),
+        }
+    }
+
+    /// Convert packed bytes to big-endian 16-bit words, and append `len` bytes to `out`.
+    pub fn normalize(&self, packed: &[u8], len: usize, out: &mut Vec<u8>) {
+        let word = |chunk: &[u8]| match self {
+            Packing::Be16 | Packing::Be14 => u16::from_be_bytes([chunk[0], chunk[1]]),
+            Packing::Le16 | Packing::Le14 => u16::from_le_bytes([chunk[0], chunk[1]]),
+        };
+
+        match self {
+            Packing::Be16 => out.extend_from_slice(&packed[..len]),
+            Packing::Le16 => {
+                let start = out.len();
+
+                for chunk in packed.chunks_exact(2) {
+                    out.extend_from_slice(&word(chunk).to_be_bytes());
+                }
+
+                out.truncate(start + len);
+            }
+            Packing::Be14 | Packing::Le14 => {
+                let end = out.len() + len;
+
+                // The upper 2 bits of each word are a sign extension, and are discarded.
+                let mut acc = 0u32;
+                let mut bits = 0;
+
+                for chunk in packed.chunks_exact(2) {
+                    acc = (acc << 14) | u32::from(word(chunk) & 0x3fff);
+                    bits += 14;
+
+                    while bits >= 8 && out.len() < end {
+                        bits -= 8;
+                        out.push((acc >> bits) as u8);
+                    }
+
+                    acc &= (1 << bits) - 1;
+                }
+            }
+        }
+    }
+}
+
+/// The parts of a core frame header needed to describe the stream.
+#[derive(Copy, Clone, Debug)]
+pub struct FrameHeader {
+    /// The number of PCM blocks of 32 samples per channel.
+    pub num_blocks: usize,
+    /// The length of the frame in bytes of the bit stream.
+    pub frame_len: usize,
+    /// The audio channel arrangement (`AMODE`).
+    pub amode: u32,
+    pub sample_rate: u32,
+    /// The stream has a low frequency effects channel.
+    pub lfe: bool,
+}
+
+impl FrameHeader {
+    /// Read the header of a normalized core frame.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let mut bs = BitReaderLtr::new(buf);
+
+        if bs.read_bits_leq32(32)? != SYNC_WORD {
+            return decode_error("dca: missing sync word");
+        }
+
+        let _ftype = bs.read_bool()?;
+        let _short = bs.read_bits_leq32(5)?;
+        let _cpf = bs.read_bool()?;
+
+        let num_blocks = bs.read_bits_leq32(7)? as usize + 1;
+
+        if num_blocks < MIN_PCM_BLOCKS {
+            return decode_error("dca: invalid number of pcm blocks");
+        }
+
+        let frame_len = bs.read_bits_leq32(14)? as usize + 1;
+
+        if frame_len < MIN_FRAME_LEN {
+            return decode_error("dca: invalid frame size");
+        }
+
+        let amode = bs.read_bits_leq32(6)?;
+
+        let sample_rate = match SAMPLE_RATES[bs.read_bits_leq32(4)? as usize] {
+            0 => return decode_error("dca: invalid sample rate"),
+            rate => rate,
+        };
+
+        // The bit rate, and the mixing, dynamic range, time stamp, auxiliary data, HDCD, and
+        // extension flags.
+        bs.ignore_bits(15)?;
+
+        let lfe = match bs.read_bits_leq32(2)? {
+            0 => false,
+            1 | 2 => true,
+            _ => return decode_error("dca: invalid lfe flag"),
+        };
+
+        Ok(FrameHeader { num_blocks, frame_len, amode, sample_rate, lfe })
+    }
+
+    /// Get the number of samples per channel in the frame.
+    pub fn duration(&self) -> usize {
+        self.num_blocks * PCM_BLOCK_LEN
+    }
+
+    /// Get the channels of the stream, if the channel arrangement is known.
+    pub fn channels(&self) -> Option<Channels> {
+        let channels = match self.amode {
+            0 => Channels::FRONT_CENTRE,
+            // Dual mono, stereo, sum and difference, and matrix encoded stereo.
+            1..=4 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            5 => Channels::FRONT_LEFT | Channels::FRONT_CENTRE | Channels::FRONT_RIGHT,
+            6 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::REAR_CENTRE,
+            7 => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_CENTRE
+                    | Channels::FRONT_RIGHT
+                    | Channels::REAR_CENTRE
+            }
+            8 => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_RIGHT
+                    | Channels::SIDE_LEFT
+                    | Channels::SIDE_RIGHT
+            }
+            9 => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_CENTRE
+                    | Channels::FRONT_RIGHT
+                    | Channels::SIDE_LEFT
+                    | Channels::SIDE_RIGHT
+            }
+            // Arrangements with more than 6 channels, or user defined arrangements.
+            _ => return None,
+        };
+
+        if self.lfe {
+            Some(channels | Channels::LFE1)
+        }
+        else {
+            Some(channels)
+        }
+    }
+}
+
+/// Read the length of a normalized DTS-HD extension substream frame.
+pub fn read_exss_len(buf: &[u8]) -> Result<usize> {
+    let mut bs = BitReaderLtr::new(buf);
+
+    if bs.read_bits_leq32(32)? != EXSS_SYNC_WORD {
+        return decode_error("dca: missing extension substream sync word");
+    }
+
+    let _user_defined = bs.read_bits_leq32(8)?;
+    let _index = bs.read_bits_leq32(2)?;
+
+    // A wide header has larger header and frame size fields.
+    let (header_bits, size_bits) = if bs.read_bool()? { (12, 20) } else { (8, 16) };
+
+    let header_len = bs.read_bits_leq32(header_bits)? as usize + 1;
+    let frame_len = bs.read_bits_leq32(size_bits)? as usize + 1;
+
+    if frame_len < header_len {
+        return decode_error("dca: invalid extension substream size");
+    }
+
+    Ok(frame_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 5.1, 48 kHz core frame header of 512 PCM samples.
+    const HEADER: [u8; HEADER_LEN] =
+        [0x7f, 0xfe, 0x80, 0x01, 0xfc, 0x3c, 0x7f, 0xf2, 0x77, 0x00, 0x0c, 0x00];
+
+    #[test]
+    fn verify_read_frame_header() {
+        let header = FrameHeader::read(&HEADER).unwrap();
+
+        assert_eq!(header.num_blocks, 16);
+        assert_eq!(header.duration(), 512);
+        assert_eq!(header.frame_len, 2048);
+        assert_eq!(header.sample_rate, 48000);
+        assert_eq!(
+            header.channels(),
+            Some(
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_CENTRE
+                    | Channels::FRONT_RIGHT
+                    | Channels::SIDE_LEFT
+                    | Channels::SIDE_RIGHT
+                    | Channels::LFE1
+            )
+        );
+    }
+
+    #[test]
+    fn verify_normalize_14_bit() {
+        // Pack the header into 14-bit little-endian words.
+        let mut bits = HEADER.iter().fold(0u128, |acc, &b| (acc << 8) | u128::from(b));
+        bits <<= 2;
+
+        let mut packed = Vec::new();
+
+        for i in (0..7).rev() {
+            let word = ((bits >> (14 * i)) & 0x3fff) as u16;
+            // Sign extend the 14-bit word.
+            let word = if word & 0x2000 != 0 { word | 0xc000 } else { word };
+            packed.extend_from_slice(&word.to_le_bytes());
+        }
+
+        let sync = [packed[0], packed[1], packed[2], packed[3], packed[4], packed[5]];
+        assert_eq!(Packing::detect(&sync), Some(Packing::Le14));
+        assert_eq!(Packing::Le14.packed_len(HEADER_LEN), packed.len());
+
+        let mut normalized = Vec::new();
+        Packing::Le14.normalize(&packed, HEADER_LEN, &mut normalized);
+
+        assert_eq!(normalized, HEADER);
+    }
+}
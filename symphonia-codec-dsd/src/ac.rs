@@ -0,0 +1,50 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::ReadBitsLtr;
+
+/// The binary arithmetic decoder of DST. The interval is maintained with 12 bits of precision, and
+/// probabilities are given in units of 1/256.
+pub struct ArithmeticDecoder {
+    a: u32,
+    c: u32,
+}
+
+impl ArithmeticDecoder {
+    pub fn new<B: ReadBitsLtr>(bs: &mut B) -> Result<Self> {
+        Ok(ArithmeticDecoder { a: 4095, c: bs.read_bits_leq32(12)? })
+    }
+
+    /// Decode a bit, where `p` is the probability that the bit is 0.
+    #[inline(always)]
+    pub fn decode_bit<B: ReadBitsLtr>(&mut self, bs: &mut B, p: u32) -> Result<u32> {
+        // The interval of a 0 bit is approximately `p / 256` of the current interval.
+        let q = ((self.a >> 8) | ((self.a >> 7) & 1)) * p;
+        let a_q = self.a - q;
+
+        let bit = if self.c < a_q {
+            self.a = a_q;
+            1
+        }
+        else {
+            self.c -= a_q;
+            self.a = q;
+            0
+        };
+
+        // Renormalize the interval such that its most-significant bit is set.
+        if self.a < 2048 {
+            let shift = self.a.leading_zeros() - 20;
+
+            self.a <<= shift;
+            self.c = (self.c << shift) | bs.read_bits_leq32(shift)?;
+        }
+
+        Ok(bit)
+    }
+}
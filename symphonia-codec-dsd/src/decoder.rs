@@ -0,0 +1,218 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::AudioBufferRef;
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_DST};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+use symphonia_core::support_codec;
+
+use crate::ac::ArithmeticDecoder;
+use crate::frame::{FrameHeader, MAX_CHANNELS};
+use crate::output::Output;
+
+/// The DSD sample rates DST is defined for, 64, 128, and 256 times 44.1 kHz.
+const SAMPLE_RATES: [u32; 3] = [2_822_400, 5_644_800, 11_289_600];
+
+/// The number of DST frames per second.
+const FRAMES_PER_SECOND: u32 = 75;
+
+/// The number of zero bytes appended to a frame. The arithmetic decoder reads slightly past the
+/// end of the coded data.
+const PADDING_LEN: usize = 8;
+
+/// The initial history of the prediction filters, an alternating pattern of bits.
+const HISTORY_INIT: u128 = 0xaaaa_aaaa_aaaa_aaaa_aaaa_aaaa_aaaa_aaaa;
+
+/// Direct Stream Transfer (DST) decoder.
+///
+/// DST is the lossless compression of 1-bit DSD audio used by SACD and DSDIFF. Each packet is a
+/// single DST frame of 1/75th of a second. The sample rate of the codec parameters must be the DSD
+/// sample rate.
+///
+/// By default, the decoded audio is raw DSD. Each `u8` sample contains 8 successive DSD bits, the
+/// earliest bit in the most-significant bit, and the sample rate is 1/8th of the DSD sample rate.
+///
+/// If [`DecoderOptions::dsd_over_pcm`] is set, the decoded audio is DSD over PCM (DoP) instead.
+/// Each `i32` sample contains a 24-bit DoP sample, 16 DSD bits following the DoP marker, in its
+/// most-significant bits, and the sample rate is 1/16th of the DSD sample rate. The DoP samples
+/// must be output to a DoP capable DAC bit-perfect.
+pub struct DstDecoder {
+    params: CodecParameters,
+    /// The number of DSD bits per channel in a frame.
+    frame_len: usize,
+    /// The frame being decoded, padded with zeros.
+    data: Vec<u8>,
+    /// The decoded DSD bits of each channel, packed into bytes.
+    dsd: Vec<Vec<u8>>,
+    output: Output,
+}
+
+impl DstDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let buf = packet.buf();
+
+        if buf.is_empty() {
+            return decode_error("dst: frame is empty");
+        }
+
+        self.data.clear();
+        self.data.extend_from_slice(buf);
+        self.data.resize(buf.len() + PADDING_LEN, 0);
+
+        let mut bs = BitReaderLtr::new(&self.data);
+
+        if bs.read_bool()? {
+            decode_dst_frame(&mut bs, self.frame_len, &mut self.dsd)?;
+        }
+        else {
+            // The remainder of the first byte is reserved.
+            if bs.read_bits_leq32(7)? & 0x3f != 0 {
+                return decode_error("dst: invalid uncoded frame");
+            }
+
+            // An uncoded frame contains the DSD bytes of each channel interleaved.
+            let num_channels = self.dsd.len();
+
+            if buf.len() - 1 < num_channels * self.frame_len / 8 {
+                return decode_error("dst: uncoded frame is truncated");
+            }
+
+            for (ch, dsd) in self.dsd.iter_mut().enumerate() {
+                for (byte, &coded) in dsd.iter_mut().zip(buf[1 + ch..].iter().step_by(num_channels))
+                {
+                    *byte = coded;
+                }
+            }
+        }
+
+        self.output.render(&self.dsd, self.frame_len / 8);
+
+        Ok(())
+    }
+}
+
+/// Decode a DST coded frame following the coded frame flag.
+fn decode_dst_frame<B: ReadBitsLtr>(
+    bs: &mut B,
+    frame_len: usize,
+    dsd: &mut [Vec<u8>],
+) -> Result<()> {
+    let num_channels = dsd.len();
+
+    let header = FrameHeader::read(bs, num_channels)?;
+
+    // The arithmetic coded data is preceded by a reserved bit.
+    if bs.read_bool()? {
+        return decode_error("dst: invalid arithmetic coded data");
+    }
+
+    let mut ac = ArithmeticDecoder::new(bs)?;
+
+    // The first coded bit is reserved.
+    ac.decode_bit(bs, header.x_bit_prob)?;
+
+    for dsd in dsd.iter_mut() {
+        dsd.fill(0);
+    }
+
+    let mut history = [HISTORY_INIT; MAX_CHANNELS];
+
+    for i in 0..frame_len {
+        for (ch, dsd) in dsd.iter_mut().enumerate() {
+            let filter = &header.filters[header.filter_map[ch]];
+
+            let prediction = filter.predict(history[ch]);
+
+            // Until the filter history is full, the bits may be coded with a probability of one
+            // half. Otherwise, the probability depends on the magnitude of the prediction.
+            let p = if header.half_prob[ch] && i < filter.len {
+                128
+            }
+            else {
+                let probs = &header.probs[header.prob_map[ch]];
+                let idx = usize::from(prediction.unsigned_abs() >> 3);
+                probs[idx.min(probs.len() - 1)]
+            };
+
+            // The residual is 1 if the bit is the sign of the prediction.
+            let residual = ac.decode_bit(bs, p)?;
+            let bit = ((prediction >> 15) as u32 ^ residual) & 1;
+
+            dsd[i >> 3] |= (bit as u8) << (7 - (i & 0x7));
+
+            history[ch] = (history[ch] << 1) | u128::from(bit);
+        }
+    }
+
+    Ok(())
+}
+
+impl Decoder for DstDecoder {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_DST {
+            return unsupported_error("dst: invalid codec type");
+        }
+
+        let rate = match params.sample_rate {
+            Some(rate) if SAMPLE_RATES.contains(&rate) => rate,
+            Some(_) => return unsupported_error("dst: unsupported sample rate"),
+            _ => return unsupported_error("dst: sample rate is required"),
+        };
+
+        let channels = match params.channels {
+            Some(channels) if channels.count() <= MAX_CHANNELS => channels,
+            Some(_) => return unsupported_error("dst: too many channels"),
+            _ => return unsupported_error("dst: channels are required"),
+        };
+
+        let frame_len = (rate / FRAMES_PER_SECOND) as usize;
+
+        let output = Output::new(rate, channels, frame_len / 8, options.dsd_over_pcm);
+
+        Ok(DstDecoder {
+            params: params.clone(),
+            frame_len,
+            data: Vec::new(),
+            dsd: vec![vec![0; frame_len / 8]; channels.count()],
+            output,
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_DST, "dst", "Direct Stream Transfer")]
+    }
+
+    fn reset(&mut self) {
+        // DST frames are independent, only the DoP markers continue between frames.
+        self.output.reset();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.output.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.last_decoded())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.output.as_audio_buffer_ref()
+    }
+}
@@ -0,0 +1,292 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::ReadBitsLtr;
+
+/// The maximum number of channels.
+pub const MAX_CHANNELS: usize = 6;
+
+/// The coefficients used to predict the coded prediction filter coefficients, indexed by the
+/// coding method.
+const FILTER_PRED_COEFFS: [[i64; 3]; 3] = [[-8, 0, 0], [-16, 8, 0], [-9, -5, 6]];
+
+/// The coefficients used to predict the coded probability table entries, indexed by the coding
+/// method.
+const PROB_PRED_COEFFS: [[i64; 3]; 3] = [[-8, 0, 0], [-16, 8, 0], [-24, 24, -8]];
+
+/// The prediction filter of an element, expanded into lookup tables. Each table gives the
+/// contribution of 8 successive history bits to the prediction.
+pub struct Filter {
+    /// The number of filter coefficients.
+    pub len: usize,
+    pub tables: Vec<[i16; 256]>,
+}
+
+impl Filter {
+    fn new(coeffs: &[i32]) -> Self {
+        let tables = coeffs
+            .chunks(8)
+            .map(|coeffs| {
+                let mut table = [0; 256];
+
+                for (history, entry) in table.iter_mut().enumerate() {
+                    // A set history bit contributes the coefficient, a cleared bit its negation.
+                    let sum: i32 = coeffs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &c)| {
+                            if history & (1 << i) != 0 {
+                                c
+                            }
+                            else {
+                                -c
+                            }
+                        })
+                        .sum();
+
+                    *entry = sum as i16;
+                }
+
+                table
+            })
+            .collect();
+
+        Filter { len: coeffs.len(), tables }
+    }
+
+    /// Compute the prediction of the next bit from the history of bits. The most recent bit is the
+    /// least-significant bit of the history.
+    #[inline(always)]
+    pub fn predict(&self, history: u128) -> i16 {
+        let mut sum = 0i32;
+
+        for (i, table) in self.tables.iter().enumerate() {
+            sum += i32::from(table[((history >> (8 * i)) & 0xff) as usize]);
+        }
+
+        // The sum of the filter coefficients wraps to 16 bits.
+        sum as i16
+    }
+}
+
+/// A DST frame header.
+pub struct FrameHeader {
+    /// The prediction filter of each filter element.
+    pub filters: Vec<Filter>,
+    /// The probability table of each probability table element.
+    pub probs: Vec<Vec<u32>>,
+    /// The filter element of each channel.
+    pub filter_map: [usize; MAX_CHANNELS],
+    /// The probability table element of each channel.
+    pub prob_map: [usize; MAX_CHANNELS],
+    /// If set for a channel, the bits that precede the full filter history are coded with a
+    /// probability of one half.
+    pub half_prob: [bool; MAX_CHANNELS],
+    /// The probability of the reserved first coded bit.
+    pub x_bit_prob: u32,
+}
+
+impl FrameHeader {
+    /// Read the header of a DST coded frame following the coded frame flag.
+    pub fn read<B: ReadBitsLtr>(bs: &mut B, num_channels: usize) -> Result<FrameHeader> {
+        // Only a single segment per channel is supported. This is the case for all known encoders.
+        let same_segmentation = bs.read_bool()?;
+        let same_segments_for_all_channels = bs.read_bool()?;
+        let end_of_channel_segmentation = bs.read_bool()?;
+
+        if !same_segmentation || !same_segments_for_all_channels || !end_of_channel_segmentation {
+            return unsupported_error("dst: segmented frames are not supported");
+        }
+
+        let same_mapping = bs.read_bool()?;
+
+        let mut filter_map = [0; MAX_CHANNELS];
+        let mut prob_map = [0; MAX_CHANNELS];
+
+        let num_filters = read_map(bs, &mut filter_map[..num_channels])?;
+
+        let num_probs = if same_mapping {
+            prob_map = filter_map;
+            num_filters
+        }
+        else {
+            read_map(bs, &mut prob_map[..num_channels])?
+        };
+
+        let mut half_prob = [false; MAX_CHANNELS];
+
+        for half_prob in half_prob[..num_channels].iter_mut() {
+            *half_prob = bs.read_bool()?;
+        }
+
+        let mut filters = Vec::with_capacity(num_filters);
+        let mut x_bit_prob = 0;
+
+        for i in 0..num_filters {
+            let coeffs = read_table(bs, &FILTER_PRED_COEFFS, 7, 9, true, 0)?;
+
+            // The probability of the reserved first coded bit is derived from the first
+            // coefficient of the first filter.
+            if i == 0 {
+                x_bit_prob = u32::from(((coeffs[0] & 0x7f) as u8).reverse_bits() >> 1) + 1;
+            }
+
+            filters.push(Filter::new(&coeffs));
+        }
+
+        let mut probs = Vec::with_capacity(num_probs);
+
+        for _ in 0..num_probs {
+            let entries = read_table(bs, &PROB_PRED_COEFFS, 6, 7, false, 1)?;
+            probs.push(entries.iter().map(|&p| p as u32).collect());
+        }
+
+        Ok(FrameHeader { filters, probs, filter_map, prob_map, half_prob, x_bit_prob })
+    }
+}
+
+/// Read the mapping of channels to elements, and return the number of elements.
+fn read_map<B: ReadBitsLtr>(bs: &mut B, map: &mut [usize]) -> Result<usize> {
+    let mut num_elements = 1;
+
+    // If the flag is set, all channels use the first element.
+    if bs.read_bool()? {
+        return Ok(num_elements);
+    }
+
+    // The first channel always uses the first element. Each subsequent channel either uses an
+    // existing element, or the next new element.
+    for element in map.iter_mut().skip(1) {
+        let bits = usize::BITS - num_elements.leading_zeros();
+
+        *element = bs.read_bits_leq32(bits)? as usize;
+
+        if *element == num_elements {
+            num_elements += 1;
+        }
+        else if *element > num_elements {
+            return decode_error("dst: invalid element mapping");
+        }
+    }
+
+    Ok(num_elements)
+}
+
+/// Read a signed Rice code.
+fn read_rice_signed<B: ReadBitsLtr>(bs: &mut B, k: u32) -> Result<i64> {
+    let msbs = i64::from(bs.read_unary_zeros()?);
+    let lsbs = i64::from(bs.read_bits_leq32(k)?);
+
+    let value = (msbs << k) | lsbs;
+
+    if value != 0 && bs.read_bool()? {
+        Ok(-value)
+    }
+    else {
+        Ok(value)
+    }
+}
+
+/// Read the prediction filter coefficients, or probability table entries, of an element. These
+/// are either stored as-is, or predicted from the preceding values with a Rice coded residual.
+fn read_table<B: ReadBitsLtr>(
+    bs: &mut B,
+    pred_coeffs: &[[i64; 3]; 3],
+    len_bits: u32,
+    value_bits: u32,
+    is_signed: bool,
+    offset: i32,
+) -> Result<Vec<i32>> {
+    let len = bs.read_bits_leq32(len_bits)? as usize + 1;
+
+    let read_value = |bs: &mut B| -> Result<i32> {
+        let value = match is_signed {
+            true => bs.read_bits_leq32_signed(value_bits)?,
+            false => bs.read_bits_leq32(value_bits)? as i32,
+        };
+        Ok(value + offset)
+    };
+
+    let mut values = Vec::with_capacity(len);
+
+    if !bs.read_bool()? {
+        for _ in 0..len {
+            values.push(read_value(bs)?);
+        }
+
+        return Ok(values);
+    }
+
+    let method = bs.read_bits_leq32(2)? as usize;
+
+    if method == 3 {
+        return decode_error("dst: invalid table coding method");
+    }
+
+    let order = method + 1;
+
+    for _ in 0..order {
+        values.push(read_value(bs)?);
+    }
+
+    let k = bs.read_bits_leq32(3)?;
+
+    let (min, max) = match is_signed {
+        true => (-(1 << (value_bits - 1)), (1 << (value_bits - 1)) - 1),
+        false => (offset, offset + (1 << value_bits) - 1),
+    };
+
+    for j in order..len {
+        let pred: i64 = pred_coeffs[method]
+            .iter()
+            .take(method + 1)
+            .enumerate()
+            .map(|(i, &c)| c * i64::from(values[j - i - 1]))
+            .sum();
+
+        // The prediction is rounded towards the nearest integer after scaling by 1/8.
+        let residual = read_rice_signed(bs, k)?;
+
+        let value = if pred >= 0 { residual - (pred + 4) / 8 } else { residual + (-pred + 3) / 8 };
+
+        if value < i64::from(min) || value > i64::from(max) {
+            return decode_error("dst: table value out of range");
+        }
+
+        values.push(value as i32);
+    }
+
+    // The initial values are always stored, even if there are fewer values.
+    values.truncate(len);
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BitReaderLtr;
+
+    #[test]
+    fn verify_read_table() {
+        // The same filter coefficients stored as-is, and predicted with the second coding method.
+        let stored = [0x08, 0x05, 0x7b, 0x03, 0xc0, 0x5f, 0xc8];
+        let coded = [0x09, 0x41, 0x5e, 0xc4, 0x00, 0x00, 0x10, 0x00, 0x00, 0x78, 0xa0];
+
+        for buf in [&stored[..], &coded[..]] {
+            let mut bs = BitReaderLtr::new(buf);
+            let coeffs = read_table(&mut bs, &FILTER_PRED_COEFFS, 7, 9, true, 0).unwrap();
+            assert_eq!(coeffs, [10, -20, 30, 5, -7]);
+        }
+
+        // Probability table entries predicted with the third coding method.
+        let mut bs = BitReaderLtr::new(&[0x17, 0x7f, 0xc6, 0xfd, 0x19, 0xc0, 0x40]);
+        let probs = read_table(&mut bs, &PROB_PRED_COEFFS, 6, 7, false, 1).unwrap();
+        assert_eq!(probs, [128, 100, 64, 30, 1, 1]);
+    }
+}
@@ -0,0 +1,91 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::SignalSpec;
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+
+/// The DSD over PCM markers. Successive samples alternate between the markers.
+const DOP_MARKERS: [u32; 2] = [0x05, 0xfa];
+
+/// The decoded audio of a DSD decoder.
+pub enum Output {
+    /// Raw DSD.
+    Dsd(AudioBuffer<u8>),
+    /// DSD over PCM, and the index of the marker of the next sample.
+    Dop { buf: AudioBuffer<i32>, marker: usize },
+}
+
+impl Output {
+    /// Instantiate the output for the DSD sample rate `rate`, and at most `max_len` bytes of DSD
+    /// per channel.
+    pub fn new(rate: u32, channels: Channels, max_len: usize, dsd_over_pcm: bool) -> Self {
+        if dsd_over_pcm {
+            let spec = SignalSpec::new(rate / 16, channels);
+            Output::Dop { buf: AudioBuffer::new(max_len as u64 / 2, spec), marker: 0 }
+        }
+        else {
+            let spec = SignalSpec::new(rate / 8, channels);
+            Output::Dsd(AudioBuffer::new(max_len as u64, spec))
+        }
+    }
+
+    /// Render the first `len` bytes of DSD of each channel. Each byte contains 8 successive DSD
+    /// bits, the earliest bit in the most-significant bit.
+    pub fn render(&mut self, dsd: &[Vec<u8>], len: usize) {
+        match self {
+            Output::Dsd(out) => {
+                out.clear();
+                out.render_reserved(Some(len));
+
+                for (ch, dsd) in dsd.iter().enumerate() {
+                    out.chan_mut(ch).copy_from_slice(&dsd[..len]);
+                }
+            }
+            Output::Dop { buf: out, marker } => {
+                let dur = len / 2;
+
+                out.clear();
+                out.render_reserved(Some(dur));
+
+                for (ch, dsd) in dsd.iter().enumerate() {
+                    for (i, (sample, bytes)) in
+                        out.chan_mut(ch).iter_mut().zip(dsd.chunks_exact(2)).enumerate()
+                    {
+                        let dop = (DOP_MARKERS[(*marker + i) & 1] << 16)
+                            | (u32::from(bytes[0]) << 8)
+                            | u32::from(bytes[1]);
+
+                        *sample = (dop << 8) as i32;
+                    }
+                }
+
+                *marker = (*marker + dur) & 1;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Output::Dsd(buf) => buf.clear(),
+            Output::Dop { buf, .. } => buf.clear(),
+        }
+    }
+
+    /// Restart the DoP markers.
+    pub fn reset(&mut self) {
+        if let Output::Dop { marker, .. } = self {
+            *marker = 0;
+        }
+    }
+
+    pub fn as_audio_buffer_ref(&self) -> AudioBufferRef<'_> {
+        match self {
+            Output::Dsd(buf) => buf.as_audio_buffer_ref(),
+            Output::Dop { buf, .. } => buf.as_audio_buffer_ref(),
+        }
+    }
+}
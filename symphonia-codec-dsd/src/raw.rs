@@ -0,0 +1,226 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::AudioBufferRef;
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::codecs::{CODEC_TYPE_DSD_LSBF, CODEC_TYPE_DSD_LSBF_PLANAR};
+use symphonia_core::codecs::{CODEC_TYPE_DSD_MSBF, CODEC_TYPE_DSD_MSBF_PLANAR};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::support_codec;
+
+use crate::output::Output;
+
+/// The largest supported packet duration in DSD samples. This bounds the buffers allocated for
+/// untrusted codec parameters. All known containers use far smaller packets.
+const MAX_FRAMES_PER_PACKET: u64 = 1 << 20;
+
+/// Raw DSD decoder.
+///
+/// Each packet contains the DSD bytes of every channel, either interleaved or planar. The bits of
+/// each byte are ordered either least- or most-significant bit first. The sample rate of the codec
+/// parameters must be the DSD sample rate, and the packet duration is in DSD samples.
+///
+/// The decoded audio is the same as that of [`DstDecoder`](crate::DstDecoder).
+pub struct DsdDecoder {
+    params: CodecParameters,
+    /// The maximum number of DSD bytes per channel in a packet.
+    max_len: usize,
+    is_planar: bool,
+    is_lsb_first: bool,
+    /// The DSD bytes of each channel, the earliest bit in the most-significant bit.
+    dsd: Vec<Vec<u8>>,
+    output: Output,
+}
+
+impl DsdDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let buf = packet.buf();
+
+        let num_channels = self.dsd.len();
+        let channel_len = buf.len() / num_channels;
+
+        // The final packet of a stream may be padded.
+        let len = channel_len.min(((packet.dur() + 7) / 8) as usize);
+
+        if len > self.max_len {
+            return decode_error("dsd: packet is too large");
+        }
+
+        for (ch, dsd) in self.dsd.iter_mut().enumerate() {
+            if self.is_planar {
+                dsd[..len].copy_from_slice(&buf[ch * channel_len..ch * channel_len + len]);
+            }
+            else {
+                for (byte, &coded) in
+                    dsd[..len].iter_mut().zip(buf[ch..].iter().step_by(num_channels))
+                {
+                    *byte = coded;
+                }
+            }
+
+            if self.is_lsb_first {
+                for byte in dsd[..len].iter_mut() {
+                    *byte = byte.reverse_bits();
+                }
+            }
+        }
+
+        self.output.render(&self.dsd, len);
+
+        Ok(())
+    }
+}
+
+impl Decoder for DsdDecoder {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        let (is_planar, is_lsb_first) = match params.codec {
+            CODEC_TYPE_DSD_LSBF => (false, true),
+            CODEC_TYPE_DSD_LSBF_PLANAR => (true, true),
+            CODEC_TYPE_DSD_MSBF => (false, false),
+            CODEC_TYPE_DSD_MSBF_PLANAR => (true, false),
+            _ => return unsupported_error("dsd: invalid codec type"),
+        };
+
+        let rate = match params.sample_rate {
+            Some(rate) => rate,
+            _ => return unsupported_error("dsd: sample rate is required"),
+        };
+
+        let channels = match params.channels {
+            Some(channels) => channels,
+            _ => return unsupported_error("dsd: channels are required"),
+        };
+
+        let max_len = match params.max_frames_per_packet {
+            Some(frames) if frames <= MAX_FRAMES_PER_PACKET => ((frames + 7) / 8) as usize,
+            Some(_) => return unsupported_error("dsd: maximum frames per packet is too large"),
+            _ => return unsupported_error("dsd: maximum frames per packet is required"),
+        };
+
+        Ok(DsdDecoder {
+            params: params.clone(),
+            max_len,
+            is_planar,
+            is_lsb_first,
+            dsd: vec![vec![0; max_len]; channels.count()],
+            output: Output::new(rate, channels, max_len, options.dsd_over_pcm),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[
+            support_codec!(CODEC_TYPE_DSD_LSBF, "dsd_lsbf", "DSD LSB-first Interleaved"),
+            support_codec!(CODEC_TYPE_DSD_LSBF_PLANAR, "dsd_lsbf_planar", "DSD LSB-first Planar"),
+            support_codec!(CODEC_TYPE_DSD_MSBF, "dsd_msbf", "DSD MSB-first Interleaved"),
+            support_codec!(CODEC_TYPE_DSD_MSBF_PLANAR, "dsd_msbf_planar", "DSD MSB-first Planar"),
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.output.reset();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.output.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.last_decoded())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.output.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::audio::{AudioBufferRef, Channels, Signal};
+    use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions};
+    use symphonia_core::codecs::{CODEC_TYPE_DSD_LSBF, CODEC_TYPE_DSD_MSBF_PLANAR};
+    use symphonia_core::formats::Packet;
+
+    use super::DsdDecoder;
+
+    fn make_params(codec: symphonia_core::codecs::CodecType) -> CodecParameters {
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(codec)
+            .with_sample_rate(2_822_400)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+            .with_max_frames_per_packet(32);
+        params
+    }
+
+    #[test]
+    fn verify_decode_dsd() {
+        let params = make_params(CODEC_TYPE_DSD_LSBF);
+        let mut decoder = DsdDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        // Interleaved and least-significant bit first.
+        let packet =
+            Packet::new_from_slice(0, 0, 32, &[0x01, 0x80, 0x0f, 0xf0, 0x03, 0x00, 0xaa, 0x55]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::U8(buf) => {
+                assert_eq!(buf.spec().rate, 2_822_400 / 8);
+                assert_eq!(buf.chan(0), &[0x80, 0xf0, 0xc0, 0x55]);
+                assert_eq!(buf.chan(1), &[0x01, 0x0f, 0x00, 0xaa]);
+            }
+            _ => panic!("expected u8 samples"),
+        }
+    }
+
+    #[test]
+    fn verify_max_frames_per_packet() {
+        let mut params = make_params(CODEC_TYPE_DSD_LSBF);
+
+        // A 1 GiB block must be rejected before any buffers are allocated.
+        params.with_max_frames_per_packet(8 << 30);
+        assert!(DsdDecoder::try_new(&params, &DecoderOptions::default()).is_err());
+    }
+
+    #[test]
+    fn verify_decode_dsd_over_pcm() {
+        let params = make_params(CODEC_TYPE_DSD_MSBF_PLANAR);
+
+        let options = DecoderOptions { dsd_over_pcm: true, ..Default::default() };
+
+        let mut decoder = DsdDecoder::try_new(&params, &options).unwrap();
+
+        // Planar and most-significant bit first.
+        let packet =
+            Packet::new_from_slice(0, 0, 32, &[0x01, 0x02, 0x03, 0x04, 0x11, 0x12, 0x13, 0x14]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::S32(buf) => {
+                assert_eq!(buf.spec().rate, 2_822_400 / 16);
+                assert_eq!(buf.chan(0), &[0x0501_0200, 0xfa03_0400u32 as i32]);
+                assert_eq!(buf.chan(1), &[0x0511_1200, 0xfa13_1400u32 as i32]);
+            }
+            _ => panic!("expected s32 samples"),
+        }
+
+        // The markers continue to alternate in the next packet.
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::S32(buf) => assert_eq!(buf.chan(0)[0], 0x0501_0200),
+            _ => panic!("expected s32 samples"),
+        }
+    }
+}
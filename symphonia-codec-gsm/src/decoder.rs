@@ -0,0 +1,134 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_GSM, CODEC_TYPE_GSM_MS};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::support_codec;
+
+use crate::dsp::Synthesis;
+use crate::frame::{Frame, FRAME_DUR, FRAME_LEN, MS_BLOCK_LEN};
+
+/// GSM 06.10 Full Rate decoder.
+///
+/// Packets of `CODEC_TYPE_GSM` contain one or more frames in the standard packing of 33 bytes.
+/// Packets of `CODEC_TYPE_GSM_MS` contain one or more blocks of two frames in the Microsoft
+/// packing of 65 bytes, as found in WAVE files.
+pub struct GsmDecoder {
+    params: CodecParameters,
+    synthesis: Synthesis,
+    buf: AudioBuffer<i16>,
+}
+
+impl GsmDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let data = packet.buf();
+
+        let (block_len, frames_per_block) = match self.params.codec {
+            CODEC_TYPE_GSM_MS => (MS_BLOCK_LEN, 2),
+            _ => (FRAME_LEN, 1),
+        };
+
+        if data.len() % block_len != 0 {
+            return decode_error("gsm: packet is not a whole number of blocks");
+        }
+
+        let num_frames = frames_per_block * (data.len() / block_len);
+
+        if num_frames * FRAME_DUR > self.buf.capacity() {
+            return decode_error("gsm: packet is too long");
+        }
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(num_frames * FRAME_DUR));
+
+        let out = self.buf.chan_mut(0);
+
+        for (block, out) in
+            data.chunks_exact(block_len).zip(out.chunks_exact_mut(frames_per_block * FRAME_DUR))
+        {
+            if block_len == MS_BLOCK_LEN {
+                let frames = Frame::read_ms_block(block)?;
+
+                for (frame, out) in frames.iter().zip(out.chunks_exact_mut(FRAME_DUR)) {
+                    self.synthesis.decode(frame, out);
+                }
+            }
+            else {
+                self.synthesis.decode(&Frame::read_standard(block)?, out);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for GsmDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_GSM && params.codec != CODEC_TYPE_GSM_MS {
+            return unsupported_error("gsm: invalid codec type");
+        }
+
+        // GSM 06.10 is only defined for mono.
+        if let Some(channels) = params.channels {
+            if channels.count() != 1 {
+                return unsupported_error("gsm: only mono is supported");
+            }
+        }
+
+        // The sample rate is nominally 8 kHz, but some files declare another, so prefer the one
+        // provided, if any.
+        let rate = params.sample_rate.unwrap_or(8000);
+
+        let frames = match params.max_frames_per_packet {
+            Some(frames) => frames,
+            _ => return unsupported_error("gsm: maximum frames per packet is required"),
+        };
+
+        Ok(GsmDecoder {
+            params: params.clone(),
+            synthesis: Default::default(),
+            buf: AudioBuffer::new(frames, SignalSpec::new(rate, Channels::FRONT_LEFT)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[
+            support_codec!(CODEC_TYPE_GSM, "gsm", "GSM 06.10 Full Rate"),
+            support_codec!(CODEC_TYPE_GSM_MS, "gsm_ms", "GSM 06.10 Full Rate (Microsoft)"),
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.synthesis.reset();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
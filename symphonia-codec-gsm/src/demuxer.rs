@@ -0,0 +1,162 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_GSM};
+use symphonia_core::errors::{seek_error, unsupported_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+use crate::frame::{FRAME_DUR, FRAME_LEN};
+
+/// The sample rate of GSM 06.10.
+const SAMPLE_RATE: u32 = 8000;
+
+/// Raw GSM 06.10 file format reader.
+///
+/// `GsmReader` implements a demuxer for headerless files of GSM 06.10 frames in the standard
+/// packing, commonly given the `.gsm` extension. Each packet is a single frame.
+///
+/// Since these files have no file signature, `GsmReader` has no probe markers and is not found by
+/// probing. It must be instantiated directly when the file is known to be raw GSM, for example, by
+/// its extension.
+pub struct GsmReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for GsmReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("gsm", "Raw GSM 06.10", &["gsm"], &["audio/x-gsm"], &[])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for GsmReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let first_frame_pos = source.pos();
+
+        // Check the signature of the first frame.
+        let byte = source.read_u8()?;
+
+        if byte >> 4 != 0xd {
+            return unsupported_error("gsm: missing frame signature");
+        }
+
+        source.seek_buffered(first_frame_pos);
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_GSM)
+            .with_sample_rate(SAMPLE_RATE)
+            .with_time_base(TimeBase::new(1, SAMPLE_RATE))
+            .with_channels(Channels::FRONT_LEFT)
+            .with_max_frames_per_packet(FRAME_DUR as u64)
+            .with_frames_per_block(FRAME_DUR as u64);
+
+        if let Some(byte_len) = source.byte_len() {
+            let n_frames = (byte_len - first_frame_pos) / FRAME_LEN as u64;
+            params.with_n_frames(n_frames * FRAME_DUR as u64);
+        }
+
+        Ok(GsmReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            first_frame_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = self.reader.read_boxed_slice_exact(FRAME_LEN)?;
+
+        let ts = self.next_packet_ts;
+
+        self.next_packet_ts += FRAME_DUR as u64;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, FRAME_DUR as u64, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => TimeBase::new(1, SAMPLE_RATE).calc_timestamp(time),
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = self.tracks[0].codec_params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Every frame has the same length and duration.
+        let frame_idx = required_ts / FRAME_DUR as u64;
+
+        if self.reader.is_seekable() {
+            let pos = self.first_frame_pos + frame_idx * FRAME_LEN as u64;
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let cur_frame_idx = self.next_packet_ts / FRAME_DUR as u64;
+
+            if frame_idx < cur_frame_idx {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes((frame_idx - cur_frame_idx) * FRAME_LEN as u64)?;
+        }
+
+        self.next_packet_ts = frame_idx * FRAME_DUR as u64;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,289 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The GSM 06.10 synthesis is specified with bit-exact 16-bit fixed-point arithmetic, which is
+//! followed here.
+
+use crate::frame::{Frame, SubFrame, FRAME_DUR};
+
+/// The number of samples in a sub-frame.
+const SUB_FRAME_DUR: usize = 40;
+
+/// The maximum long-term prediction lag, and the length of the reconstructed residual history.
+const MAX_LAG: usize = 120;
+
+/// The long-term prediction gains, indexed by `bc`.
+const QLB: [i16; 4] = [3277, 11469, 21299, 32767];
+
+/// The normalized mantissas of the block amplitude.
+const FAC: [i16; 8] = [18431, 20479, 22527, 24575, 26623, 28671, 30719, 32767];
+
+/// The offsets (`MIC`), biases (`B`), and inverted scales (`INVA`) used to decode the log-area
+/// ratios.
+const LAR_MIC: [i16; 8] = [-32, -32, -16, -16, -8, -8, -4, -4];
+const LAR_B: [i16; 8] = [0, 0, 2048, -2560, 94, -1792, -341, -1144];
+const LAR_INVA: [i16; 8] = [13107, 13107, 13107, 13107, 19223, 17476, 31454, 29708];
+
+#[inline(always)]
+fn add(a: i16, b: i16) -> i16 {
+    a.saturating_add(b)
+}
+
+#[inline(always)]
+fn sub(a: i16, b: i16) -> i16 {
+    a.saturating_sub(b)
+}
+
+/// Multiply two Q15 values with rounding.
+#[inline(always)]
+fn mult_r(a: i16, b: i16) -> i16 {
+    if a == i16::MIN && b == i16::MIN {
+        i16::MAX
+    }
+    else {
+        ((i32::from(a) * i32::from(b) + 16384) >> 15) as i16
+    }
+}
+
+/// Arithmetic shift right, or left if `n` is negative.
+fn asr(a: i16, n: i16) -> i16 {
+    if n >= 16 {
+        if a < 0 {
+            -1
+        }
+        else {
+            0
+        }
+    }
+    else if n <= -16 {
+        0
+    }
+    else if n < 0 {
+        a << -n
+    }
+    else {
+        a >> n
+    }
+}
+
+/// Arithmetic shift left, or right if `n` is negative.
+fn asl(a: i16, n: i16) -> i16 {
+    if n >= 16 {
+        0
+    }
+    else if n <= -16 {
+        if a < 0 {
+            -1
+        }
+        else {
+            0
+        }
+    }
+    else if n < 0 {
+        asr(a, -n)
+    }
+    else {
+        a << n
+    }
+}
+
+/// Decode the coded log-area ratios.
+fn decode_lar(larc: &[i16; 8], larpp: &mut [i16; 8]) {
+    for i in 0..8 {
+        // Adding MIC restores the sign of the coded log-area ratio.
+        let temp = add(larc[i], LAR_MIC[i]) << 10;
+        let temp = sub(temp, LAR_B[i] << 1);
+        let temp = mult_r(LAR_INVA[i], temp);
+        larpp[i] = add(temp, temp);
+    }
+}
+
+/// Convert interpolated log-area ratios to reflection coefficients, in place.
+fn lar_to_rp(lar: &mut [i16; 8]) {
+    for lar in lar.iter_mut() {
+        let temp = if *lar == i16::MIN { i16::MAX } else { lar.abs() };
+
+        let rp = if temp < 11059 {
+            temp << 1
+        }
+        else if temp < 20070 {
+            temp + 11059
+        }
+        else {
+            add(temp >> 2, 26112)
+        };
+
+        *lar = if *lar < 0 { -rp } else { rp };
+    }
+}
+
+/// Decode the regular pulse excitation of a sub-frame.
+fn decode_rpe(sub_frame: &SubFrame, erp: &mut [i16; SUB_FRAME_DUR]) {
+    // Get the exponent and mantissa of the block amplitude.
+    let xmaxc = sub_frame.xmaxc;
+
+    let mut exp = if xmaxc > 15 { (xmaxc >> 3) - 1 } else { 0 };
+    let mut mant = xmaxc - (exp << 3);
+
+    if mant == 0 {
+        exp = -4;
+        mant = 7;
+    }
+    else {
+        while mant <= 7 {
+            mant = (mant << 1) | 1;
+            exp -= 1;
+        }
+        mant -= 8;
+    }
+
+    // Inverse quantize the pulses.
+    let temp1 = FAC[mant as usize];
+    let temp2 = sub(6, exp);
+    let temp3 = asl(1, sub(temp2, 1));
+
+    erp.fill(0);
+
+    for (i, &xmc) in sub_frame.xmc.iter().enumerate() {
+        // Restore the sign of the 3 bit pulse, and scale it to 16 bits.
+        let temp = ((xmc << 1) - 7) << 12;
+        let temp = add(mult_r(temp1, temp), temp3);
+
+        erp[sub_frame.mc as usize + 3 * i] = asr(temp, temp2);
+    }
+}
+
+/// The state of the GSM 06.10 synthesis.
+pub struct Synthesis {
+    /// The reconstructed short-term residual of the previous 120 samples, followed by that of the
+    /// current sub-frame.
+    drp: [i16; MAX_LAG + SUB_FRAME_DUR],
+    /// The decoded log-area ratios of the previous and current frames.
+    larpp: [[i16; 8]; 2],
+    /// The index of the log-area ratios of the previous frame in `larpp`.
+    prev: usize,
+    /// The long-term prediction lag of the previous sub-frame.
+    nrp: i16,
+    /// The short-term synthesis filter state.
+    v: [i16; 9],
+    /// The de-emphasis filter state.
+    msr: i16,
+}
+
+impl Default for Synthesis {
+    fn default() -> Self {
+        Synthesis {
+            drp: [0; MAX_LAG + SUB_FRAME_DUR],
+            larpp: [[0; 8]; 2],
+            prev: 0,
+            nrp: 40,
+            v: [0; 9],
+            msr: 0,
+        }
+    }
+}
+
+impl Synthesis {
+    /// Reset the synthesis to its initial state.
+    pub fn reset(&mut self) {
+        *self = Default::default();
+    }
+
+    /// Apply the long-term synthesis filter to the excitation of a sub-frame, and append the
+    /// reconstructed residual to `wt`.
+    fn long_term_synthesis(
+        &mut self,
+        nc: i16,
+        bc: i16,
+        erp: &[i16; SUB_FRAME_DUR],
+        wt: &mut [i16],
+    ) {
+        // An out-of-range lag repeats the previous lag.
+        let nr = if nc < 40 || nc > 120 { self.nrp } else { nc };
+        self.nrp = nr;
+
+        let brp = QLB[bc as usize];
+
+        for (k, &e) in erp.iter().enumerate() {
+            let drpp = mult_r(brp, self.drp[MAX_LAG + k - nr as usize]);
+            self.drp[MAX_LAG + k] = add(e, drpp);
+        }
+
+        wt.copy_from_slice(&self.drp[MAX_LAG..]);
+
+        // Shift the history.
+        self.drp.copy_within(SUB_FRAME_DUR.., 0);
+    }
+
+    /// Apply the short-term synthesis filter with the reflection coefficients `rrp` to `wt`.
+    fn short_term_synthesis(&mut self, rrp: &[i16; 8], wt: &[i16], out: &mut [i16]) {
+        let v = &mut self.v;
+
+        for (&w, out) in wt.iter().zip(out.iter_mut()) {
+            let mut sri = w;
+
+            for i in (0..8).rev() {
+                sri = sub(sri, mult_r(rrp[i], v[i]));
+                v[i + 1] = add(v[i], mult_r(rrp[i], sri));
+            }
+
+            v[0] = sri;
+            *out = sri;
+        }
+    }
+
+    /// Decode a frame of 160 samples.
+    pub fn decode(&mut self, frame: &Frame, out: &mut [i16]) {
+        let mut wt = [0; FRAME_DUR];
+        let mut erp = [0; SUB_FRAME_DUR];
+
+        for (sub_frame, wt) in frame.sub_frames.iter().zip(wt.chunks_exact_mut(SUB_FRAME_DUR)) {
+            decode_rpe(sub_frame, &mut erp);
+            self.long_term_synthesis(sub_frame.nc, sub_frame.bc, &erp, wt);
+        }
+
+        // Decode the log-area ratios of the current frame.
+        let prev = self.larpp[self.prev];
+        let cur = &mut self.larpp[self.prev ^ 1];
+        decode_lar(&frame.larc, cur);
+        let cur = *cur;
+
+        self.prev ^= 1;
+
+        // The log-area ratios are interpolated with those of the previous frame over the first
+        // 40 samples.
+        let mut lar = [0; 8];
+
+        for i in 0..8 {
+            lar[i] = add(add(prev[i] >> 2, cur[i] >> 2), prev[i] >> 1);
+        }
+        lar_to_rp(&mut lar);
+        self.short_term_synthesis(&lar, &wt[..13], &mut out[..13]);
+
+        for i in 0..8 {
+            lar[i] = add(prev[i] >> 1, cur[i] >> 1);
+        }
+        lar_to_rp(&mut lar);
+        self.short_term_synthesis(&lar, &wt[13..27], &mut out[13..27]);
+
+        for i in 0..8 {
+            lar[i] = add(add(prev[i] >> 2, cur[i] >> 2), cur[i] >> 1);
+        }
+        lar_to_rp(&mut lar);
+        self.short_term_synthesis(&lar, &wt[27..40], &mut out[27..40]);
+
+        lar = cur;
+        lar_to_rp(&mut lar);
+        self.short_term_synthesis(&lar, &wt[40..], &mut out[40..FRAME_DUR]);
+
+        // De-emphasis, and truncation to 13 bits.
+        for s in out[..FRAME_DUR].iter_mut() {
+            self.msr = add(*s, mult_r(self.msr, 28180));
+            *s = add(self.msr, self.msr) & !7;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BitReaderLtr, BitReaderRtl, ReadBitsLtr, ReadBitsRtl};
+
+/// The length of a frame in the standard packing.
+pub const FRAME_LEN: usize = 33;
+
+/// The length of a block of two frames in the Microsoft packing.
+pub const MS_BLOCK_LEN: usize = 65;
+
+/// The number of samples in a frame.
+pub const FRAME_DUR: usize = 160;
+
+/// The signature in the upper 4 bits of a frame in the standard packing.
+const FRAME_MAGIC: u32 = 0xd;
+
+/// The number of bits of each log-area ratio.
+const LARC_BITS: [u32; 8] = [6, 6, 5, 5, 4, 4, 3, 3];
+
+/// The coded parameters of a sub-frame of 40 samples.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubFrame {
+    /// The long-term prediction lag (`Nc`).
+    pub nc: i16,
+    /// The long-term prediction gain (`bc`).
+    pub bc: i16,
+    /// The regular pulse excitation grid position (`Mc`).
+    pub mc: i16,
+    /// The block amplitude (`xmaxc`).
+    pub xmaxc: i16,
+    /// The regular pulse excitation pulses (`xMc`).
+    pub xmc: [i16; 13],
+}
+
+/// The coded parameters of a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    /// The log-area ratios (`LARc`).
+    pub larc: [i16; 8],
+    pub sub_frames: [SubFrame; 4],
+}
+
+impl Frame {
+    /// Read the parameters of a frame. The packings only differ in the order of the bits, so the
+    /// bits of each parameter are read with `read_bits`.
+    fn read<F: FnMut(u32) -> Result<u32>>(mut read_bits: F) -> Result<Frame> {
+        let mut frame: Frame = Default::default();
+
+        for (larc, &bits) in frame.larc.iter_mut().zip(&LARC_BITS) {
+            *larc = read_bits(bits)? as i16;
+        }
+
+        for sub_frame in frame.sub_frames.iter_mut() {
+            sub_frame.nc = read_bits(7)? as i16;
+            sub_frame.bc = read_bits(2)? as i16;
+            sub_frame.mc = read_bits(2)? as i16;
+            sub_frame.xmaxc = read_bits(6)? as i16;
+
+            for xmc in sub_frame.xmc.iter_mut() {
+                *xmc = read_bits(3)? as i16;
+            }
+        }
+
+        Ok(frame)
+    }
+
+    /// Read a frame in the standard packing of 33 bytes. The bits are packed most-significant bit
+    /// first, following a 4 bit signature.
+    pub fn read_standard(buf: &[u8]) -> Result<Frame> {
+        let mut bs = BitReaderLtr::new(&buf[..FRAME_LEN]);
+
+        if bs.read_bits_leq32(4)? != FRAME_MAGIC {
+            return decode_error("gsm: invalid frame signature");
+        }
+
+        Frame::read(|bits| Ok(bs.read_bits_leq32(bits)?))
+    }
+
+    /// Read a block of two frames in the Microsoft packing of 65 bytes. The bits are packed
+    /// least-significant bit first, and the frames have no signature.
+    pub fn read_ms_block(buf: &[u8]) -> Result<[Frame; 2]> {
+        let mut bs = BitReaderRtl::new(&buf[..MS_BLOCK_LEN]);
+
+        let first = Frame::read(|bits| Ok(bs.read_bits_leq32(bits)?))?;
+        let second = Frame::read(|bits| Ok(bs.read_bits_leq32(bits)?))?;
+
+        Ok([first, second])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the parameters of a test frame. Every parameter is given a distinct value.
+    fn test_frame(seed: u32) -> Frame {
+        let mut value = seed;
+
+        Frame::read(|bits| {
+            value = value.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            Ok((value >> 16) & ((1 << bits) - 1))
+        })
+        .unwrap()
+    }
+
+    /// Pack a sequence of parameters, most-significant bit first if `ltr` is true, or
+    /// least-significant bit first otherwise.
+    fn pack(frames: &[Frame], ltr: bool, prefix: &[(u32, u32)]) -> Vec<u8> {
+        let mut fields = prefix.to_vec();
+
+        for frame in frames {
+            for (&larc, &bits) in frame.larc.iter().zip(&LARC_BITS) {
+                fields.push((larc as u32, bits));
+            }
+            for sub_frame in &frame.sub_frames {
+                fields.push((sub_frame.nc as u32, 7));
+                fields.push((sub_frame.bc as u32, 2));
+                fields.push((sub_frame.mc as u32, 2));
+                fields.push((sub_frame.xmaxc as u32, 6));
+                fields.extend(sub_frame.xmc.iter().map(|&xmc| (xmc as u32, 3)));
+            }
+        }
+
+        let mut buf = Vec::new();
+        let mut bit = 0;
+
+        for (value, bits) in fields {
+            for i in 0..bits {
+                if bit % 8 == 0 {
+                    buf.push(0);
+                }
+
+                let (value_bit, pos) = if ltr { (bits - 1 - i, 7 - bit % 8) } else { (i, bit % 8) };
+
+                *buf.last_mut().unwrap() |= (((value >> value_bit) & 1) as u8) << pos;
+                bit += 1;
+            }
+        }
+
+        buf
+    }
+
+    #[test]
+    fn verify_read_standard() {
+        let frame = test_frame(1);
+        let buf = pack(&[frame], true, &[(FRAME_MAGIC, 4)]);
+
+        assert_eq!(buf.len(), FRAME_LEN);
+        assert_eq!(Frame::read_standard(&buf).unwrap(), frame);
+
+        // The signature must be present.
+        let buf = pack(&[frame], true, &[(0xc, 4)]);
+        assert!(Frame::read_standard(&buf).is_err());
+    }
+
+    #[test]
+    fn verify_read_ms_block() {
+        let frames = [test_frame(1), test_frame(2)];
+        let buf = pack(&frames, false, &[]);
+
+        assert_eq!(buf.len(), MS_BLOCK_LEN);
+        assert_eq!(Frame::read_ms_block(&buf).unwrap(), frames);
+    }
+}
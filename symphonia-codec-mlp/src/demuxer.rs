@@ -0,0 +1,308 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{ErrorKind, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_MLP, CODEC_TYPE_TRUEHD};
+use symphonia_core::errors::{decode_error, seek_error, Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use crate::header::*;
+
+/// MLP and Dolby TrueHD elementary stream format reader.
+///
+/// `MlpReader` implements a demuxer for a stream of MLP or TrueHD access units. Each packet is a
+/// single access unit. A decoder may only start at an access unit with a major sync, so seeking
+/// scans the stream for the nearest such access unit.
+pub struct MlpReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    /// The most recently read major sync.
+    sync: MajorSync,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for MlpReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "mlp",
+            "MLP / Dolby TrueHD",
+            &["thd", "mlp", "truehd"],
+            &["audio/vnd.dolby.mlp", "audio/true-hd"],
+            &[&[0xf8, 0x72, 0x6f, 0xba], &[0xf8, 0x72, 0x6f, 0xbb]]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Synchronizes to, and reads, the next access unit with a major sync. The reader is positioned
+/// after the access unit.
+fn read_major_sync_unit(reader: &mut MediaSourceStream) -> Result<(MajorSync, Vec<u8>)> {
+    let mut sync = 0u32;
+
+    loop {
+        while sync != TRUEHD_SYNC && sync != MLP_SYNC {
+            sync = (sync << 8) | u32::from(reader.read_u8()?);
+        }
+
+        sync = 0;
+
+        let resume = reader.pos();
+
+        // The access unit header precedes the major sync. If it can't be read, then skip the
+        // major sync.
+        match resume.checked_sub((AU_HEADER_LEN + 4) as u64) {
+            Some(start) if reader.seek_buffered(start) == start => (),
+            _ => {
+                reader.seek_buffered(resume);
+                continue;
+            }
+        }
+
+        let mut header = [0; AU_HEADER_LEN + MAJOR_SYNC_LEN];
+        reader.read_buf_exact(&mut header)?;
+
+        let major = match MajorSync::read(&header[AU_HEADER_LEN..]) {
+            Ok(major) => major,
+            Err(Error::DecodeError(_)) => {
+                reader.seek_buffered(resume);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let len = access_unit_len(&header);
+
+        if len >= AU_HEADER_LEN + major.len {
+            let mut buf = vec![0; len];
+            buf[..header.len()].copy_from_slice(&header);
+            reader.read_buf_exact(&mut buf[header.len()..])?;
+
+            if verify_check_nibble(&buf, major.len, major.num_substreams) {
+                return Ok((major, buf));
+            }
+        }
+
+        // Resume searching from the byte following the false major sync.
+        reader.seek_buffered(resume);
+    }
+}
+
+/// Reads the next access unit, updating the major sync if the access unit has one.
+fn read_access_unit<B: ReadBytes>(reader: &mut B, sync: &mut MajorSync) -> Result<Vec<u8>> {
+    let mut header = [0; AU_HEADER_LEN];
+    reader.read_buf_exact(&mut header)?;
+
+    let len = access_unit_len(&header);
+
+    if len < AU_HEADER_LEN {
+        return decode_error("mlp: invalid access unit length");
+    }
+
+    let mut buf = vec![0; len];
+    buf[..AU_HEADER_LEN].copy_from_slice(&header);
+    reader.read_buf_exact(&mut buf[AU_HEADER_LEN..])?;
+
+    let major_sync_len = if has_major_sync(&buf) {
+        if len < AU_HEADER_LEN + MAJOR_SYNC_LEN {
+            return decode_error("mlp: invalid access unit length");
+        }
+
+        *sync = MajorSync::read(&buf[AU_HEADER_LEN..])?;
+        sync.len
+    }
+    else {
+        0
+    };
+
+    if !verify_check_nibble(&buf, major_sync_len, sync.num_substreams) {
+        return decode_error("mlp: access unit check nibble mismatch");
+    }
+
+    Ok(buf)
+}
+
+impl FormatReader for MlpReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let (sync, buf) = read_major_sync_unit(&mut source)?;
+
+        // Rewind back to the start of the access unit.
+        source.seek_buffered_rev(buf.len());
+
+        let first_frame_pos = source.pos();
+
+        let codec = match sync.stream_type {
+            StreamType::Mlp => CODEC_TYPE_MLP,
+            StreamType::TrueHd => CODEC_TYPE_TRUEHD,
+        };
+
+        let au_len = sync.au_len as u64;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(codec)
+            .with_sample_rate(sync.sample_rate)
+            .with_time_base(TimeBase::new(1, sync.sample_rate))
+            .with_channels(sync.channels)
+            .with_max_frames_per_packet(au_len)
+            .with_frames_per_block(au_len);
+
+        if let Some(bits_per_sample) = sync.bits_per_sample {
+            params.with_bits_per_sample(bits_per_sample);
+        }
+
+        Ok(MlpReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            sync,
+            first_frame_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = match read_access_unit(&mut self.reader, &mut self.sync) {
+            Ok(buf) => buf,
+            Err(Error::DecodeError(err)) => {
+                // Decoding can only resume from the next major sync.
+                warn!("{}, resynchronizing", err);
+
+                let (sync, buf) = read_major_sync_unit(&mut self.reader)?;
+                self.sync = sync;
+                buf
+            }
+            Err(err) => return Err(err),
+        };
+
+        let ts = self.next_packet_ts;
+        let dur = self.sync.au_len as u64;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        let seekable = self.reader.is_seekable();
+
+        // The stream has no index, so backward seeks must scan from the first access unit.
+        if required_ts < self.next_packet_ts {
+            if !seekable {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+            self.next_packet_ts = 0;
+        }
+
+        // Find the last access unit with a major sync before the required timestamp. If the
+        // stream is not seekable, or no such access unit was scanned, then use the first one after
+        // the required timestamp instead.
+        let mut found = None;
+
+        loop {
+            let pos = self.reader.pos();
+            let ts = self.next_packet_ts;
+
+            let buf = match read_access_unit(&mut self.reader, &mut self.sync) {
+                Ok(buf) => buf,
+                Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                    if found.is_some() && required_ts < self.next_packet_ts {
+                        break;
+                    }
+                    return seek_error(SeekErrorKind::OutOfRange);
+                }
+                Err(err) => return Err(err),
+            };
+
+            self.next_packet_ts += self.sync.au_len as u64;
+
+            if has_major_sync(&buf) {
+                if ts < required_ts {
+                    if seekable {
+                        found = Some((pos, ts));
+                    }
+                }
+                else {
+                    if found.is_none() || ts == required_ts {
+                        found = Some((pos, ts));
+                    }
+                    break;
+                }
+            }
+        }
+
+        if let Some((pos, ts)) = found {
+            if seekable {
+                self.reader.seek(SeekFrom::Start(pos))?;
+            }
+            else {
+                self.reader.seek_buffered(pos);
+            }
+
+            self.next_packet_ts = ts;
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,323 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, Result};
+
+/// The format synchronization word of a Dolby TrueHD major sync.
+pub const TRUEHD_SYNC: u32 = 0xf872_6fba;
+
+/// The format synchronization word of an MLP major sync.
+pub const MLP_SYNC: u32 = 0xf872_6fbb;
+
+/// The signature following the format information of a major sync.
+const SIGNATURE: u16 = 0xb752;
+
+/// The length of an access unit header in bytes.
+pub const AU_HEADER_LEN: usize = 4;
+
+/// The minimum length of a major sync in bytes.
+pub const MAJOR_SYNC_LEN: usize = 28;
+
+/// The number of samples per channel in an access unit at the base rate of 44.1 or 48 kHz.
+const BASE_AU_LEN: usize = 40;
+
+/// The maximum number of substreams of a stream.
+const MAX_SUBSTREAMS: usize = 4;
+
+/// MLP sample sizes, indexed by the quantization word length code. A size of 0 is invalid.
+const MLP_QUANTS: [u32; 16] = [16, 20, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// MLP channel arrangements.
+const MLP_CHANNELS: [&[Channels]; 21] = [
+    &[Channels::FRONT_CENTRE],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::REAR_CENTRE],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::REAR_LEFT, Channels::REAR_RIGHT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::LFE1],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::REAR_CENTRE, Channels::LFE1],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::REAR_CENTRE],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+    ],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::LFE1],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_CENTRE,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::REAR_CENTRE],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+    ],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::LFE1],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_CENTRE,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_RIGHT,
+        Channels::FRONT_CENTRE,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+];
+
+/// Dolby TrueHD channel assignments, indexed by the bit of the channel assignment field.
+const TRUEHD_CHANNELS: [&[Channels]; 13] = [
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT],
+    &[Channels::FRONT_CENTRE],
+    &[Channels::LFE1],
+    &[Channels::SIDE_LEFT, Channels::SIDE_RIGHT],
+    &[Channels::FRONT_LEFT_HIGH, Channels::FRONT_RIGHT_HIGH],
+    &[Channels::FRONT_LEFT_CENTRE, Channels::FRONT_RIGHT_CENTRE],
+    &[Channels::REAR_LEFT, Channels::REAR_RIGHT],
+    &[Channels::REAR_CENTRE],
+    &[Channels::TOP_CENTRE],
+    // The left and right surround direct channels have no equivalent.
+    &[],
+    &[Channels::FRONT_LEFT_WIDE, Channels::FRONT_RIGHT_WIDE],
+    &[Channels::FRONT_CENTRE_HIGH],
+    &[Channels::LFE2],
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamType {
+    Mlp,
+    TrueHd,
+}
+
+/// The parts of a major sync needed to frame, and describe, the stream.
+#[derive(Copy, Clone, Debug)]
+pub struct MajorSync {
+    pub stream_type: StreamType,
+    pub sample_rate: u32,
+    /// The sample size of the first channel group, if known.
+    pub bits_per_sample: Option<u32>,
+    pub channels: Channels,
+    pub num_substreams: usize,
+    /// The number of samples per channel in an access unit.
+    pub au_len: usize,
+    /// The length of the major sync in bytes.
+    pub len: usize,
+}
+
+impl MajorSync {
+    /// Read a major sync. The buffer must start with the format synchronization word, and be at
+    /// least `MAJOR_SYNC_LEN` bytes long.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let stream_type = match u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) {
+            TRUEHD_SYNC => StreamType::TrueHd,
+            MLP_SYNC => StreamType::Mlp,
+            _ => return decode_error("mlp: missing major sync"),
+        };
+
+        if u16::from_be_bytes([buf[8], buf[9]]) != SIGNATURE {
+            return decode_error("mlp: invalid major sync signature");
+        }
+
+        let info = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+
+        let (rate, bits_per_sample, channels) = match stream_type {
+            StreamType::Mlp => {
+                let bits_per_sample = match MLP_QUANTS[(info >> 28) as usize] {
+                    0 => return decode_error("mlp: invalid quantization word length"),
+                    bits => bits,
+                };
+
+                let channels = match MLP_CHANNELS.get((info & 0x1f) as usize) {
+                    Some(locations) => {
+                        locations.iter().fold(Channels::empty(), |acc, &ch| acc | ch)
+                    }
+                    None => return decode_error("mlp: invalid channel arrangement"),
+                };
+
+                ((info >> 20) & 0xf, Some(bits_per_sample), channels)
+            }
+            StreamType::TrueHd => {
+                // The 8 channel presentation, if present, is a superset of the 6 channel
+                // presentation.
+                let assignment = match info & 0x1fff {
+                    0 => (info >> 15) & 0x1f,
+                    assignment => assignment,
+                };
+
+                let channels = TRUEHD_CHANNELS
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| assignment & (1 << i) != 0)
+                    .flat_map(|(_, locations)| locations.iter())
+                    .fold(Channels::empty(), |acc, &ch| acc | ch);
+
+                if channels.is_empty() {
+                    return decode_error("mlp: invalid channel assignment");
+                }
+
+                (info >> 28, None, channels)
+            }
+        };
+
+        // Rates are multiples of 44.1 or 48 kHz, up-to 4 times the base rate.
+        let sample_rate = match rate {
+            0..=2 => 48000 << rate,
+            8..=10 => 44100 << (rate & 0x7),
+            _ => return decode_error("mlp: invalid sample rate"),
+        };
+
+        let num_substreams = usize::from(buf[16] >> 4);
+
+        if num_substreams == 0 || num_substreams > MAX_SUBSTREAMS {
+            return decode_error("mlp: invalid number of substreams");
+        }
+
+        // A TrueHD major sync may be extended.
+        let len = if stream_type == StreamType::TrueHd && buf[25] & 0x1 != 0 {
+            MAJOR_SYNC_LEN + 2 + 2 * usize::from(buf[26] >> 4)
+        }
+        else {
+            MAJOR_SYNC_LEN
+        };
+
+        Ok(MajorSync {
+            stream_type,
+            sample_rate,
+            bits_per_sample,
+            channels,
+            num_substreams,
+            au_len: BASE_AU_LEN << (rate & 0x7),
+            len,
+        })
+    }
+}
+
+/// Get the length of an access unit in bytes from its header.
+pub fn access_unit_len(header: &[u8]) -> usize {
+    2 * usize::from(u16::from_be_bytes([header[0], header[1]]) & 0xfff)
+}
+
+/// Returns true if the access unit starts with a major sync.
+pub fn has_major_sync(au: &[u8]) -> bool {
+    match au.get(AU_HEADER_LEN..AU_HEADER_LEN + 4) {
+        Some(sync) => {
+            let sync = u32::from_be_bytes([sync[0], sync[1], sync[2], sync[3]]);
+            sync == TRUEHD_SYNC || sync == MLP_SYNC
+        }
+        None => false,
+    }
+}
+
+/// Verify the check nibble of an access unit. The check nibble covers the access unit header, and
+/// the substream directory following the major sync, if present.
+pub fn verify_check_nibble(au: &[u8], major_sync_len: usize, num_substreams: usize) -> bool {
+    let mut parity = au[..AU_HEADER_LEN].iter().fold(0, |acc, &b| acc ^ b);
+
+    let mut pos = AU_HEADER_LEN + major_sync_len;
+
+    for _ in 0..num_substreams {
+        // Each substream directory entry may be followed by an extra word.
+        let len = match au.get(pos) {
+            Some(&flags) if flags & 0x80 != 0 => 4,
+            Some(_) => 2,
+            None => return false,
+        };
+
+        match au.get(pos..pos + len) {
+            Some(entry) => parity = entry.iter().fold(parity, |acc, &b| acc ^ b),
+            None => return false,
+        }
+
+        pos += len;
+    }
+
+    ((parity >> 4) ^ parity) & 0xf == 0xf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_truehd_major_sync() {
+        // A 48 kHz, 7.1 channel, 3 substream major sync.
+        let buf = [
+            0xf8, 0x72, 0x6f, 0xba, 0x00, 0x0f, 0x40, 0x4f, 0xb7, 0x52, 0x00, 0x00, 0x00, 0x00,
+            0x80, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let sync = MajorSync::read(&buf).unwrap();
+
+        assert_eq!(sync.stream_type, StreamType::TrueHd);
+        assert_eq!(sync.sample_rate, 48000);
+        assert_eq!(sync.num_substreams, 3);
+        assert_eq!(sync.au_len, 40);
+        assert_eq!(sync.len, MAJOR_SYNC_LEN);
+        assert_eq!(
+            sync.channels,
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+        );
+    }
+}
@@ -204,10 +204,10 @@ const XLAW_QUANT_MASK: u8 = 0x0f;
 const XLAW_SEG_MASK: u8 = 0x70;
 const XLAW_SEG_SHIFT: u32 = 4;
 
-fn alaw_to_linear(mut a_val: u8) -> i16 {
+const fn alaw_to_linear(mut a_val: u8) -> i16 {
     a_val ^= 0x55;
 
-    let mut t = i16::from((a_val & XLAW_QUANT_MASK) << 4);
+    let mut t = ((a_val & XLAW_QUANT_MASK) << 4) as i16;
     let seg = (a_val & XLAW_SEG_MASK) >> XLAW_SEG_SHIFT;
 
     match seg {
@@ -224,7 +224,7 @@ fn alaw_to_linear(mut a_val: u8) -> i16 {
     }
 }
 
-fn mulaw_to_linear(mut mu_val: u8) -> i16 {
+const fn mulaw_to_linear(mut mu_val: u8) -> i16 {
     const BIAS: i16 = 0x84;
 
     // Complement to obtain normal u-law value.
@@ -232,7 +232,7 @@ fn mulaw_to_linear(mut mu_val: u8) -> i16 {
 
     // Extract and bias the quantization bits. Then shift up by the segment number and subtract out
     // the bias.
-    let mut t = i16::from((mu_val & XLAW_QUANT_MASK) << 3) + BIAS;
+    let mut t = ((mu_val & XLAW_QUANT_MASK) << 3) as i16 + BIAS;
     t <<= (mu_val & XLAW_SEG_MASK) >> XLAW_SEG_SHIFT;
 
     if mu_val & 0x80 == 0x80 {
@@ -243,6 +243,32 @@ fn mulaw_to_linear(mut mu_val: u8) -> i16 {
     }
 }
 
+const fn build_alaw_table() -> [i16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = alaw_to_linear(i as u8);
+        i += 1;
+    }
+    table
+}
+
+const fn build_mulaw_table() -> [i16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = mulaw_to_linear(i as u8);
+        i += 1;
+    }
+    table
+}
+
+/// A-law to 16-bit linear PCM expansion table, computed at compile time.
+static ALAW_TO_LINEAR: [i16; 256] = build_alaw_table();
+
+/// Mu-law to 16-bit linear PCM expansion table, computed at compile time.
+static MULAW_TO_LINEAR: [i16; 256] = build_mulaw_table();
+
 fn is_supported_pcm_codec(codec_type: CodecType) -> bool {
     matches!(
         codec_type,
@@ -336,10 +362,18 @@ impl PcmDecoder {
                 read_pcm_floating!(self.buf, F64, reader.read_be_f64()?)
             }
             CODEC_TYPE_PCM_ALAW => {
-                read_pcm_transfer_func!(self.buf, S16, alaw_to_linear(reader.read_u8()?))
+                read_pcm_transfer_func!(
+                    self.buf,
+                    S16,
+                    ALAW_TO_LINEAR[usize::from(reader.read_u8()?)]
+                )
             }
             CODEC_TYPE_PCM_MULAW => {
-                read_pcm_transfer_func!(self.buf, S16, mulaw_to_linear(reader.read_u8()?))
+                read_pcm_transfer_func!(
+                    self.buf,
+                    S16,
+                    MULAW_TO_LINEAR[usize::from(reader.read_u8()?)]
+                )
             }
             // CODEC_TYPE_PCM_S32LE_PLANAR =>
             // CODEC_TYPE_PCM_S32BE_PLANAR =>
@@ -651,3 +685,28 @@ impl Decoder for PcmDecoder {
         self.buf.as_audio_buffer_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ALAW_TO_LINEAR, MULAW_TO_LINEAR};
+
+    #[test]
+    fn verify_alaw_table() {
+        // Smallest magnitudes.
+        assert_eq!(ALAW_TO_LINEAR[0xd5], 8);
+        assert_eq!(ALAW_TO_LINEAR[0x55], -8);
+        // Largest magnitudes.
+        assert_eq!(ALAW_TO_LINEAR[0xaa], 32256);
+        assert_eq!(ALAW_TO_LINEAR[0x2a], -32256);
+    }
+
+    #[test]
+    fn verify_mulaw_table() {
+        // Both zero codes.
+        assert_eq!(MULAW_TO_LINEAR[0xff], 0);
+        assert_eq!(MULAW_TO_LINEAR[0x7f], 0);
+        // Largest magnitudes.
+        assert_eq!(MULAW_TO_LINEAR[0x80], 32124);
+        assert_eq!(MULAW_TO_LINEAR[0x00], -32124);
+    }
+}
@@ -0,0 +1,133 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_SBC};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::support_codec;
+
+use crate::dsp::Synthesis;
+use crate::frame::{Frame, MAX_BLOCKS, MAX_CHANNELS, MAX_SUBBANDS};
+
+/// Low Complexity Subband Coding (SBC) decoder.
+///
+/// Packets contain one or more SBC or mSBC frames, as found in raw SBC files and A2DP media
+/// payloads. All frames must have the same number of channels.
+pub struct SbcDecoder {
+    params: CodecParameters,
+    synthesis: Vec<Synthesis>,
+    buf: AudioBuffer<f32>,
+}
+
+impl SbcDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let mut data = packet.buf();
+
+        self.buf.clear();
+
+        while !data.is_empty() {
+            let frame = Frame::read(data)?;
+            let header = &frame.header;
+
+            if header.num_channels() != self.synthesis.len() {
+                return decode_error("sbc: number of channels changed");
+            }
+
+            let start = self.buf.frames();
+            let dur = header.duration();
+
+            if start + dur > self.buf.capacity() {
+                return decode_error("sbc: packet is too long");
+            }
+
+            self.buf.render_reserved(Some(dur));
+
+            for (ch, synthesis) in self.synthesis.iter_mut().enumerate() {
+                // The filter bank must be rebuilt if the number of subbands changed.
+                if synthesis.subbands() != header.subbands {
+                    *synthesis = Synthesis::new(header.subbands);
+                }
+
+                let out = &mut self.buf.chan_mut(ch)[start..];
+
+                for (block, out) in
+                    frame.samples[..header.blocks].iter().zip(out.chunks_exact_mut(header.subbands))
+                {
+                    synthesis.synthesize(&block[ch][..header.subbands], out);
+                }
+            }
+
+            data = &data[header.frame_len()..];
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for SbcDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_SBC {
+            return unsupported_error("sbc: invalid codec type");
+        }
+
+        let rate = match params.sample_rate {
+            Some(rate) => rate,
+            _ => return unsupported_error("sbc: sample rate is required"),
+        };
+
+        let channels = match params.channels {
+            Some(channels) if channels.count() <= MAX_CHANNELS => channels,
+            Some(_) => return unsupported_error("sbc: only mono and stereo are supported"),
+            _ => return unsupported_error("sbc: channels are required"),
+        };
+
+        let frames = params.max_frames_per_packet.unwrap_or((MAX_BLOCKS * MAX_SUBBANDS) as u64);
+
+        // The number of subbands is not known until the first frame is read.
+        let synthesis = (0..channels.count()).map(|_| Synthesis::new(MAX_SUBBANDS)).collect();
+
+        Ok(SbcDecoder {
+            params: params.clone(),
+            synthesis,
+            buf: AudioBuffer::new(frames, SignalSpec::new(rate, channels)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_SBC, "sbc", "Low Complexity Subband Coding")]
+    }
+
+    fn reset(&mut self) {
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.reset();
+        }
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
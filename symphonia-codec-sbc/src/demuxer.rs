@@ -0,0 +1,207 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{ErrorKind, Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_SBC};
+use symphonia_core::errors::{seek_error, Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+use crate::frame::{FrameHeader, HEADER_LEN, MSBC_SYNC_WORD, SYNC_WORD};
+
+/// Raw SBC file format reader.
+///
+/// `SbcReader` implements a demuxer for headerless streams of SBC or mSBC frames, such as captured
+/// Bluetooth A2DP or HFP audio, commonly given the `.sbc` or `.msbc` extension. Junk between frames
+/// is skipped by synchronizing to the next frame with a valid CRC. Each packet is a single frame.
+///
+/// Since these files have no file signature, `SbcReader` has no probe markers and is not found by
+/// probing. It must be instantiated directly when the file is known to be raw SBC, for example, by
+/// its extension.
+pub struct SbcReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for SbcReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("sbc", "Raw SBC", &["sbc", "msbc"], &["audio/sbc"], &[])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Synchronizes to, and reads, the next frame. Returns the position and header of the frame, and
+/// the frame.
+fn read_frame<B: ReadBytes + SeekBuffered>(reader: &mut B) -> Result<(u64, FrameHeader, Vec<u8>)> {
+    loop {
+        let sync = reader.read_u8()?;
+
+        if sync != SYNC_WORD && sync != MSBC_SYNC_WORD {
+            continue;
+        }
+
+        let pos = reader.pos() - 1;
+
+        let mut buf = vec![sync; HEADER_LEN];
+        reader.read_buf_exact(&mut buf[1..])?;
+
+        let header = match FrameHeader::read(&[buf[0], buf[1], buf[2], buf[3]]) {
+            Ok(header) => header,
+            Err(Error::DecodeError(_)) => {
+                // Resume searching from the byte following the false synchronization word.
+                reader.seek_buffered(pos + 1);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        buf.resize(header.frame_len(), 0);
+        reader.read_buf_exact(&mut buf[HEADER_LEN..])?;
+
+        if !header.verify_crc(&buf) {
+            reader.seek_buffered(pos + 1);
+            continue;
+        }
+
+        return Ok((pos, header, buf));
+    }
+}
+
+impl FormatReader for SbcReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let (first_frame_pos, header, _) = read_frame(&mut source)?;
+
+        // Rewind back to the start of the frame.
+        source.seek_buffered(first_frame_pos);
+
+        let frame_dur = header.duration() as u64;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(CODEC_TYPE_SBC)
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_channels(header.channels())
+            .with_max_frames_per_packet(frame_dur);
+
+        // The bitpool may change between frames, so the number of frames is only an estimate.
+        if let Some(byte_len) = source.byte_len() {
+            let n_frames = (byte_len - first_frame_pos) / header.frame_len() as u64;
+            params.with_n_frames(n_frames * frame_dur);
+        }
+
+        Ok(SbcReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            first_frame_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (_, header, buf) = read_frame(&mut self.reader)?;
+
+        let ts = self.next_packet_ts;
+        let dur = header.duration() as u64;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        // The length of a frame depends on its bitpool, so the stream must be scanned. Backward
+        // seeks scan from the first frame.
+        if required_ts < self.next_packet_ts {
+            if !self.reader.is_seekable() {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+            self.next_packet_ts = 0;
+        }
+
+        loop {
+            let (pos, header, _) = match read_frame(&mut self.reader) {
+                Ok(frame) => frame,
+                Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return seek_error(SeekErrorKind::OutOfRange);
+                }
+                Err(err) => return Err(err),
+            };
+
+            let dur = header.duration() as u64;
+
+            if self.next_packet_ts + dur > required_ts {
+                // Rewind back to the start of the frame containing the required timestamp.
+                self.reader.seek_buffered(pos);
+                break;
+            }
+
+            self.next_packet_ts += dur;
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,121 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f64::consts::PI;
+
+use crate::frame::MAX_SUBBANDS;
+
+/// The prototype filter of the 4 subband filter bank.
+#[rustfmt::skip]
+const PROTO_4_40: [f32; 40] = [
+    0.00000000E+00, 5.36548976E-04, 1.49188357E-03, 2.73370904E-03,
+    3.83720193E-03, 3.89205149E-03, 1.86581691E-03, -3.06012286E-03,
+    1.09137620E-02, 2.04385087E-02, 2.88757392E-02, 3.21939290E-02,
+    2.58767811E-02, 6.13245186E-03, -2.88217274E-02, -7.76463494E-02,
+    1.35593274E-01, 1.94987841E-01, 2.46636662E-01, 2.81828203E-01,
+    2.94315332E-01, 2.81828203E-01, 2.46636662E-01, 1.94987841E-01,
+    -1.35593274E-01, -7.76463494E-02, -2.88217274E-02, 6.13245186E-03,
+    2.58767811E-02, 3.21939290E-02, 2.88757392E-02, 2.04385087E-02,
+    -1.09137620E-02, -3.06012286E-03, 1.86581691E-03, 3.89205149E-03,
+    3.83720193E-03, 2.73370904E-03, 1.49188357E-03, 5.36548976E-04,
+];
+
+/// The prototype filter of the 8 subband filter bank.
+#[rustfmt::skip]
+const PROTO_8_80: [f32; 80] = [
+    0.00000000E+00, 1.56575398E-04, 3.43256425E-04, 5.54620202E-04,
+    8.23919506E-04, 1.13992507E-03, 1.47640169E-03, 1.78371725E-03,
+    2.01182542E-03, 2.10371989E-03, 1.99454554E-03, 1.61656283E-03,
+    9.02154502E-04, -1.78805361E-04, -1.64973098E-03, -3.49717454E-03,
+    5.65949473E-03, 8.02941163E-03, 1.04584443E-02, 1.27472335E-02,
+    1.46525263E-02, 1.59045603E-02, 1.62208471E-02, 1.53184106E-02,
+    1.29371806E-02, 8.85757540E-03, 2.92408442E-03, -4.91578024E-03,
+    -1.46404076E-02, -2.61098752E-02, -3.90751381E-02, -5.31873032E-02,
+    6.79989431E-02, 8.29847578E-02, 9.75753918E-02, 1.11196689E-01,
+    1.23264548E-01, 1.33264415E-01, 1.40753505E-01, 1.45389847E-01,
+    1.46955068E-01, 1.45389847E-01, 1.40753505E-01, 1.33264415E-01,
+    1.23264548E-01, 1.11196689E-01, 9.75753918E-02, 8.29847578E-02,
+    -6.79989431E-02, -5.31873032E-02, -3.90751381E-02, -2.61098752E-02,
+    -1.46404076E-02, -4.91578024E-03, 2.92408442E-03, 8.85757540E-03,
+    1.29371806E-02, 1.53184106E-02, 1.62208471E-02, 1.59045603E-02,
+    1.46525263E-02, 1.27472335E-02, 1.04584443E-02, 8.02941163E-03,
+    -5.65949473E-03, -3.49717454E-03, -1.64973098E-03, -1.78805361E-04,
+    9.02154502E-04, 1.61656283E-03, 1.99454554E-03, 2.10371989E-03,
+    2.01182542E-03, 1.78371725E-03, 1.47640169E-03, 1.13992507E-03,
+    8.23919506E-04, 5.54620202E-04, 3.43256425E-04, 1.56575398E-04,
+];
+
+/// The state of the synthesis filter bank of a channel.
+pub struct Synthesis {
+    subbands: usize,
+    /// The matrixing coefficients, `N[k][i] = cos((i + 0.5) * (k + M / 2) * PI / M)`.
+    matrix: Vec<f32>,
+    /// The window coefficients.
+    window: Vec<f32>,
+    /// The matrixed subband samples of the previous 10 blocks.
+    v: [f32; 20 * MAX_SUBBANDS],
+}
+
+impl Synthesis {
+    pub fn new(subbands: usize) -> Self {
+        let m = subbands;
+
+        let mut matrix = Vec::with_capacity(2 * m * m);
+
+        for k in 0..2 * m {
+            for i in 0..m {
+                let phase = (i as f64 + 0.5) * (k as f64 + (m / 2) as f64) * PI / m as f64;
+                matrix.push(phase.cos() as f32);
+            }
+        }
+
+        let proto: &[f32] = if subbands == 4 { &PROTO_4_40 } else { &PROTO_8_80 };
+
+        // The window is the prototype filter scaled by -M. The output is additionally scaled from
+        // the 16-bit range of the subband samples to [-1.0, 1.0).
+        let scale = -(m as f32) / 32768.0;
+        let window = proto.iter().map(|&c| scale * c).collect();
+
+        Synthesis { subbands, matrix, window, v: [0.0; 20 * MAX_SUBBANDS] }
+    }
+
+    /// Get the number of subbands of the filter bank.
+    pub fn subbands(&self) -> usize {
+        self.subbands
+    }
+
+    /// Reset the filter bank to its initial state.
+    pub fn reset(&mut self) {
+        self.v = [0.0; 20 * MAX_SUBBANDS];
+    }
+
+    /// Synthesize the audio samples of a block from its subband samples.
+    pub fn synthesize(&mut self, samples: &[f32], out: &mut [f32]) {
+        let m = self.subbands;
+        let v = &mut self.v[..20 * m];
+
+        // Shift the previous blocks.
+        v.copy_within(..18 * m, 2 * m);
+
+        // Matrixing.
+        for (v, n) in v[..2 * m].iter_mut().zip(self.matrix.chunks_exact(m)) {
+            *v = n.iter().zip(samples).map(|(&n, &s)| n * s).sum();
+        }
+
+        // Windowing. Each group of 4M matrixed samples contributes its first and last quarter.
+        for (j, out) in out[..m].iter_mut().enumerate() {
+            let mut sum = 0.0;
+
+            for i in 0..5 {
+                sum += v[4 * m * i + j] * self.window[2 * m * i + j];
+                sum += v[4 * m * i + 3 * m + j] * self.window[2 * m * i + m + j];
+            }
+
+            *out = sum;
+        }
+    }
+}
@@ -0,0 +1,474 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+
+/// The synchronization word of an SBC frame.
+pub const SYNC_WORD: u8 = 0x9c;
+
+/// The synchronization word of an mSBC frame. mSBC is the fixed configuration of SBC used by the
+/// Bluetooth Hands-Free Profile for wideband speech.
+pub const MSBC_SYNC_WORD: u8 = 0xad;
+
+/// The length of the frame header, including the CRC.
+pub const HEADER_LEN: usize = 4;
+
+/// The maximum number of blocks in a frame.
+pub const MAX_BLOCKS: usize = 16;
+
+/// The maximum number of subbands.
+pub const MAX_SUBBANDS: usize = 8;
+
+/// The maximum number of channels.
+pub const MAX_CHANNELS: usize = 2;
+
+/// Sample rates, indexed by the sampling frequency field.
+const SAMPLE_RATES: [u32; 4] = [16000, 32000, 44100, 48000];
+
+/// The loudness offsets of the bit allocation for 4 and 8 subbands, indexed by the sampling
+/// frequency field and the subband.
+const OFFSET4: [[i32; 4]; 4] = [[-1, 0, 0, 0], [-2, 0, 0, 1], [-2, 0, 0, 1], [-2, 0, 0, 1]];
+const OFFSET8: [[i32; 8]; 4] = [
+    [-2, 0, 0, 0, 0, 0, 0, 1],
+    [-3, 0, 0, 0, 0, 0, 1, 2],
+    [-4, 0, 0, 0, 0, 0, 1, 2],
+    [-4, 0, 0, 0, 0, 0, 1, 2],
+];
+
+/// The CRC-8 generator polynomial, `x^8 + x^4 + x^3 + x^2 + 1`.
+const CRC_POLY: u8 = 0x1d;
+
+/// The initial value of the CRC.
+const CRC_INIT: u8 = 0x0f;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChannelMode {
+    Mono,
+    DualChannel,
+    Stereo,
+    JointStereo,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Allocation {
+    Loudness,
+    Snr,
+}
+
+/// An SBC frame header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// The sampling frequency field.
+    freq: usize,
+    pub sample_rate: u32,
+    pub blocks: usize,
+    pub mode: ChannelMode,
+    pub allocation: Allocation,
+    pub subbands: usize,
+    pub bitpool: u32,
+    pub crc: u8,
+}
+
+impl FrameHeader {
+    /// Read a frame header. mSBC frame headers are accepted, and describe the fixed mSBC
+    /// configuration.
+    pub fn read(buf: &[u8; HEADER_LEN]) -> Result<FrameHeader> {
+        let header = match buf[0] {
+            SYNC_WORD => {
+                let freq = usize::from(buf[1] >> 6);
+
+                let mode = match (buf[1] >> 2) & 0x3 {
+                    0 => ChannelMode::Mono,
+                    1 => ChannelMode::DualChannel,
+                    2 => ChannelMode::Stereo,
+                    _ => ChannelMode::JointStereo,
+                };
+
+                let allocation =
+                    if buf[1] & 0x2 == 0 { Allocation::Loudness } else { Allocation::Snr };
+
+                FrameHeader {
+                    freq,
+                    sample_rate: SAMPLE_RATES[freq],
+                    blocks: 4 * (usize::from((buf[1] >> 4) & 0x3) + 1),
+                    mode,
+                    allocation,
+                    subbands: if buf[1] & 0x1 == 0 { 4 } else { 8 },
+                    bitpool: u32::from(buf[2]),
+                    crc: buf[3],
+                }
+            }
+            MSBC_SYNC_WORD => {
+                // The configuration of mSBC is fixed, and the header fields are reserved.
+                if buf[1] != 0 || buf[2] != 0 {
+                    return decode_error("sbc: invalid msbc frame header");
+                }
+
+                FrameHeader {
+                    freq: 0,
+                    sample_rate: 16000,
+                    blocks: 15,
+                    mode: ChannelMode::Mono,
+                    allocation: Allocation::Loudness,
+                    subbands: 8,
+                    bitpool: 26,
+                    crc: buf[3],
+                }
+            }
+            _ => return decode_error("sbc: missing frame sync word"),
+        };
+
+        // The bit pool is limited to the number of bits the bit allocation can distribute.
+        let max_bitpool = match header.mode {
+            ChannelMode::Mono | ChannelMode::DualChannel => 16 * header.subbands,
+            ChannelMode::Stereo | ChannelMode::JointStereo => 32 * header.subbands,
+        };
+
+        if header.bitpool < 2 || header.bitpool as usize > max_bitpool {
+            return decode_error("sbc: invalid bitpool");
+        }
+
+        Ok(header)
+    }
+
+    /// Get the number of channels.
+    pub fn num_channels(&self) -> usize {
+        match self.mode {
+            ChannelMode::Mono => 1,
+            _ => 2,
+        }
+    }
+
+    /// Get the channels.
+    pub fn channels(&self) -> Channels {
+        match self.mode {
+            ChannelMode::Mono => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        }
+    }
+
+    /// Get the number of samples per channel in the frame.
+    pub fn duration(&self) -> usize {
+        self.blocks * self.subbands
+    }
+
+    /// Get the number of joint stereo flag bits following the header.
+    fn join_bits(&self) -> usize {
+        if self.mode == ChannelMode::JointStereo {
+            self.subbands
+        }
+        else {
+            0
+        }
+    }
+
+    /// Get the length of the frame in bytes, including the header.
+    pub fn frame_len(&self) -> usize {
+        let bitpool = self.bitpool as usize;
+
+        let sample_bits = match self.mode {
+            ChannelMode::Mono | ChannelMode::DualChannel => {
+                self.blocks * self.num_channels() * bitpool
+            }
+            ChannelMode::Stereo | ChannelMode::JointStereo => self.blocks * bitpool,
+        };
+
+        let scale_factor_bits = 4 * self.subbands * self.num_channels();
+
+        HEADER_LEN + (scale_factor_bits + self.join_bits() + sample_bits + 7) / 8
+    }
+
+    /// Verify the CRC of a frame. The CRC covers the header, excluding the synchronization word,
+    /// followed by the joint stereo flags and scale factors.
+    pub fn verify_crc(&self, buf: &[u8]) -> bool {
+        let num_bits = self.join_bits() + 4 * self.subbands * self.num_channels();
+
+        let mut crc = crc8(CRC_INIT, buf[1], 8);
+        crc = crc8(crc, buf[2], 8);
+
+        for (i, &byte) in buf[HEADER_LEN..].iter().take((num_bits + 7) / 8).enumerate() {
+            crc = crc8(crc, byte, (num_bits - 8 * i).min(8) as u32);
+        }
+
+        crc == self.crc
+    }
+}
+
+/// Update the CRC with the leading `num_bits` bits of `byte`.
+fn crc8(mut crc: u8, byte: u8, num_bits: u32) -> u8 {
+    for i in 0..num_bits {
+        let bit = (byte >> (7 - i)) & 1;
+        let feedback = (crc >> 7) ^ bit;
+
+        crc <<= 1;
+
+        if feedback != 0 {
+            crc ^= CRC_POLY;
+        }
+    }
+
+    crc
+}
+
+/// Allocate the bit pool to the subbands of the given channels. In the stereo modes, the bit pool
+/// is shared by both channels, otherwise, each channel has its own bit pool.
+fn allocate_bits(
+    header: &FrameHeader,
+    channels: &[usize],
+    scale_factors: &[[u8; MAX_SUBBANDS]; MAX_CHANNELS],
+    bits: &mut [[u8; MAX_SUBBANDS]; MAX_CHANNELS],
+) {
+    let subbands = header.subbands;
+    let bitpool = header.bitpool as i32;
+
+    // Calculate the number of bits each subband needs.
+    let mut bitneed = [[0i32; MAX_SUBBANDS]; MAX_CHANNELS];
+    let mut max_bitneed = 0;
+
+    for &ch in channels {
+        for sb in 0..subbands {
+            let scale_factor = i32::from(scale_factors[ch][sb]);
+
+            let need = match header.allocation {
+                Allocation::Snr => scale_factor,
+                Allocation::Loudness if scale_factor == 0 => -5,
+                Allocation::Loudness => {
+                    let offset = match subbands {
+                        4 => OFFSET4[header.freq][sb],
+                        _ => OFFSET8[header.freq][sb],
+                    };
+
+                    let loudness = scale_factor - offset;
+
+                    if loudness > 0 {
+                        loudness / 2
+                    }
+                    else {
+                        loudness
+                    }
+                }
+            };
+
+            bitneed[ch][sb] = need;
+            max_bitneed = max_bitneed.max(need);
+        }
+    }
+
+    // Find the bit slice at which the bit pool is exhausted.
+    let mut bitcount = 0;
+    let mut slicecount = 0;
+    let mut bitslice = max_bitneed + 1;
+
+    loop {
+        bitslice -= 1;
+        bitcount += slicecount;
+        slicecount = 0;
+
+        for &ch in channels {
+            for &need in &bitneed[ch][..subbands] {
+                if need > bitslice + 1 && need < bitslice + 16 {
+                    slicecount += 1;
+                }
+                else if need == bitslice + 1 {
+                    slicecount += 2;
+                }
+            }
+        }
+
+        if bitcount + slicecount >= bitpool {
+            break;
+        }
+    }
+
+    if bitcount + slicecount == bitpool {
+        bitcount += slicecount;
+        bitslice -= 1;
+    }
+
+    for &ch in channels {
+        for (bits, &need) in bits[ch][..subbands].iter_mut().zip(&bitneed[ch]) {
+            *bits = if need < bitslice + 2 { 0 } else { (need - bitslice).min(16) as u8 };
+        }
+    }
+
+    // Distribute the remaining bits, alternating between the channels of each subband.
+    let order = || (0..subbands).flat_map(|sb| channels.iter().map(move |&ch| (ch, sb)));
+
+    for (ch, sb) in order() {
+        if bitcount >= bitpool {
+            return;
+        }
+
+        if bits[ch][sb] >= 2 && bits[ch][sb] < 16 {
+            bits[ch][sb] += 1;
+            bitcount += 1;
+        }
+        else if bitneed[ch][sb] == bitslice + 1 && bitpool > bitcount + 1 {
+            bits[ch][sb] = 2;
+            bitcount += 2;
+        }
+    }
+
+    for (ch, sb) in order() {
+        if bitcount >= bitpool {
+            return;
+        }
+
+        if bits[ch][sb] < 16 {
+            bits[ch][sb] += 1;
+            bitcount += 1;
+        }
+    }
+}
+
+/// A decoded SBC frame.
+pub struct Frame {
+    pub header: FrameHeader,
+    /// The reconstructed subband samples, indexed by block, channel, and subband.
+    pub samples: [[[f32; MAX_SUBBANDS]; MAX_CHANNELS]; MAX_BLOCKS],
+}
+
+impl Frame {
+    /// Read a frame, and reconstruct its subband samples.
+    pub fn read(buf: &[u8]) -> Result<Frame> {
+        if buf.len() < HEADER_LEN {
+            return decode_error("sbc: frame is truncated");
+        }
+
+        let header = FrameHeader::read(&[buf[0], buf[1], buf[2], buf[3]])?;
+
+        let frame_len = header.frame_len();
+
+        if buf.len() < frame_len {
+            return decode_error("sbc: frame is truncated");
+        }
+
+        if !header.verify_crc(buf) {
+            return decode_error("sbc: crc mismatch");
+        }
+
+        let num_channels = header.num_channels();
+        let subbands = header.subbands;
+
+        let mut bs = BitReaderLtr::new(&buf[HEADER_LEN..frame_len]);
+
+        // The flag of the last subband is reserved.
+        let mut join = [false; MAX_SUBBANDS];
+
+        if header.mode == ChannelMode::JointStereo {
+            for flag in join[..subbands].iter_mut() {
+                *flag = bs.read_bool()?;
+            }
+
+            join[subbands - 1] = false;
+        }
+
+        let mut scale_factors = [[0; MAX_SUBBANDS]; MAX_CHANNELS];
+
+        for scale_factors in scale_factors[..num_channels].iter_mut() {
+            for scale_factor in scale_factors[..subbands].iter_mut() {
+                *scale_factor = bs.read_bits_leq32(4)? as u8;
+            }
+        }
+
+        let mut bits = [[0; MAX_SUBBANDS]; MAX_CHANNELS];
+
+        match header.mode {
+            ChannelMode::Mono => allocate_bits(&header, &[0], &scale_factors, &mut bits),
+            ChannelMode::DualChannel => {
+                allocate_bits(&header, &[0], &scale_factors, &mut bits);
+                allocate_bits(&header, &[1], &scale_factors, &mut bits);
+            }
+            _ => allocate_bits(&header, &[0, 1], &scale_factors, &mut bits),
+        }
+
+        let mut samples = [[[0.0; MAX_SUBBANDS]; MAX_CHANNELS]; MAX_BLOCKS];
+
+        for block in samples[..header.blocks].iter_mut() {
+            for (ch, block) in block[..num_channels].iter_mut().enumerate() {
+                for (sb, sample) in block[..subbands].iter_mut().enumerate() {
+                    let num_bits = u32::from(bits[ch][sb]);
+
+                    if num_bits == 0 {
+                        continue;
+                    }
+
+                    let value = bs.read_bits_leq32(num_bits)?;
+
+                    let levels = ((1u32 << num_bits) - 1) as f32;
+                    let scale = (1u32 << (scale_factors[ch][sb] + 1)) as f32;
+
+                    *sample = scale * ((2 * value + 1) as f32 / levels - 1.0);
+                }
+            }
+        }
+
+        // Joint stereo subbands are coded as the sum and difference of the channels.
+        for (sb, _) in join.iter().enumerate().filter(|(_, &join)| join) {
+            for block in samples[..header.blocks].iter_mut() {
+                let mid = block[0][sb];
+                let side = block[1][sb];
+
+                block[0][sb] = mid + side;
+                block[1][sb] = mid - side;
+            }
+        }
+
+        Ok(Frame { header, samples })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_frame_header() {
+        // 44.1 kHz, 16 blocks, joint stereo, loudness, 8 subbands, and a bitpool of 53. This is
+        // the high quality configuration recommended for A2DP.
+        let header = FrameHeader::read(&[0x9c, 0xbd, 0x35, 0x00]).unwrap();
+
+        assert_eq!(header.sample_rate, 44100);
+        assert_eq!(header.blocks, 16);
+        assert_eq!(header.mode, ChannelMode::JointStereo);
+        assert_eq!(header.allocation, Allocation::Loudness);
+        assert_eq!(header.subbands, 8);
+        assert_eq!(header.bitpool, 53);
+        assert_eq!(header.frame_len(), 119);
+
+        let header = FrameHeader::read(&[0xad, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(header.frame_len(), 57);
+
+        // The bitpool of a mono stream with 4 subbands may not exceed 64.
+        assert!(FrameHeader::read(&[0x9c, 0x00, 0x41, 0x00]).is_err());
+    }
+
+    #[test]
+    fn verify_read_msbc_silence() {
+        // The mSBC frame of silence given by the Hands-Free Profile specification.
+        let mut buf = vec![0xad, 0x00, 0x00, 0xc5, 0x00, 0x00, 0x00, 0x00];
+
+        for _ in 0..3 {
+            buf.extend_from_slice(&[
+                0x77, 0x6d, 0xb6, 0xdd, 0xdb, 0x6d, 0xb7, 0x76, 0xdb, 0x6d, 0xdd, 0xb6, 0xdb,
+            ]);
+        }
+
+        buf.extend_from_slice(&[0x77, 0x6d, 0xb6, 0xdd, 0xdb, 0x6d, 0xb7, 0x76, 0xdb, 0x6c]);
+
+        let frame = Frame::read(&buf).unwrap();
+
+        assert_eq!(buf.len(), frame.header.frame_len());
+        assert!(frame.samples.iter().flatten().flatten().all(|&s| s == 0.0));
+
+        // The CRC must match.
+        buf[3] = 0xc4;
+        assert!(Frame::read(&buf).is_err());
+    }
+}
@@ -219,6 +219,12 @@ impl Floor for Floor0 {
             let floor_book_idx_bits = ilog(u32::from(self.setup.floor0_number_of_books));
             let floor_book_idx = io_try_or_ret!(bs.read_bits_leq32(floor_book_idx_bits)) as usize;
 
+            // If the index exceeds the floor's codebook list, the floor is undecodable and treated
+            // as unused.
+            if floor_book_idx >= usize::from(self.setup.floor0_number_of_books) {
+                return Ok(());
+            }
+
             // Get the actual codebook index from the floor's codebook list.
             let codebook_idx = self.setup.floor0_book_list[floor_book_idx] as usize;
 
@@ -239,6 +245,10 @@ impl Floor for Floor0 {
                 // Read and obtain the VQ vector from the codebook.
                 let vq = try_or_ret!(codebook.read_vq(bs));
 
+                if vq.is_empty() {
+                    return decode_error("vorbis: floor0, invalid vq vector");
+                }
+
                 // The VQ vector may be much larger (up-to 65535 scalars) than the remaining number
                 // of coefficients (up-to 255 scalars). Cap the amount of coefficients to be
                 // processed.
@@ -250,9 +260,9 @@ impl Floor for Floor0 {
                     *c = last + vq;
                 }
 
-                // Store the value of the last coefficient in the coefficient vector for the next
-                // iteration.
-                last = self.coeffs[i - 1];
+                // Store the value of the last scalar in the VQ vector, after adding the last
+                // coefficient, for the next iteration.
+                last += vq[vq.len() - 1];
             }
 
             // Pre-compute the 2 times the cosine of all coefficients.
@@ -64,17 +64,104 @@ pub struct VorbisDecoder {
     dsp: Dsp,
     /// Output buffer.
     buf: AudioBuffer<f32>,
+    /// An identification header received mid-stream, and its packet, pending a setup header.
+    pending_ident: Option<(IdentHeader, Box<[u8]>)>,
 }
 
 impl VorbisDecoder {
+    fn from_headers(params: &CodecParameters, ident: IdentHeader, setup: Setup) -> Result<Self> {
+        // Initialize static DSP data.
+        let windows = Windows::new(1 << ident.bs0_exp, 1 << ident.bs1_exp);
+
+        // Initialize dynamic DSP for each channel.
+        let dsp_channels =
+            (0..ident.n_channels).map(|_| DspChannel::new(ident.bs0_exp, ident.bs1_exp)).collect();
+
+        // Map the channels
+        let channels = match vorbis_channels_to_channels(ident.n_channels) {
+            Some(channels) => channels,
+            _ => return unsupported_error("vorbis: unknown channel map (fix me)"),
+        };
+
+        // Initialize the output buffer.
+        let spec = SignalSpec::new(ident.sample_rate, channels);
+
+        let imdct_short = Imdct::new((1 << ident.bs0_exp) >> 1);
+        let imdct_long = Imdct::new((1 << ident.bs1_exp) >> 1);
+
+        // TODO: Should this be half the block size?
+        let duration = 1u64 << ident.bs1_exp;
+
+        let dsp =
+            Dsp { windows, channels: dsp_channels, imdct_short, imdct_long, lapping_state: None };
+
+        Ok(VorbisDecoder {
+            params: params.clone(),
+            ident,
+            codebooks: setup.codebooks,
+            floors: setup.floors,
+            residues: setup.residues,
+            modes: setup.modes,
+            mappings: setup.mappings,
+            dsp,
+            buf: AudioBuffer::new(duration, spec),
+            pending_ident: None,
+        })
+    }
+
+    /// Reads a header packet received mid-stream. A new identification and setup header sequence
+    /// (e.g., at the start of a chained stream) reconfigures the decoder.
+    fn read_header_packet(&mut self, buf: &[u8]) -> Result<()> {
+        // No audio is produced for a header packet.
+        self.buf.clear();
+
+        match buf[0] {
+            VORBIS_PACKET_TYPE_IDENTIFICATION => {
+                let ident = read_ident_header(&mut BufReader::new(buf))?;
+
+                self.pending_ident = Some((ident, Box::from(buf)));
+            }
+            VORBIS_PACKET_TYPE_COMMENT => (),
+            VORBIS_PACKET_TYPE_SETUP => {
+                let (ident, ident_buf) = match self.pending_ident.take() {
+                    Some(pending) => pending,
+                    _ => return decode_error("vorbis: setup header without identification header"),
+                };
+
+                let setup = read_setup(&mut BufReader::new(buf), &ident)?;
+
+                debug!(
+                    "vorbis: reconfiguring for sample_rate={}, n_channels={}",
+                    ident.sample_rate, ident.n_channels
+                );
+
+                // Update the codec parameters to reflect the new headers.
+                let mut params = self.params.clone();
+
+                params
+                    .with_sample_rate(ident.sample_rate)
+                    .with_extra_data([&ident_buf[..], buf].concat().into_boxed_slice());
+
+                if let Some(channels) = vorbis_channels_to_channels(ident.n_channels) {
+                    params.with_channels(channels);
+                }
+
+                *self = VorbisDecoder::from_headers(&params, ident, setup)?;
+            }
+            _ => return decode_error("vorbis: not an audio packet"),
+        }
+
+        Ok(())
+    }
+
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
         let mut bs = BitReaderRtl::new(packet.buf());
 
         // Section 4.3.1 - Packet Type, Mode, and Window Decode
 
-        // First bit must be 0 to indicate audio packet.
+        // First bit must be 0 to indicate audio packet. Otherwise, the packet is a header packet.
         if bs.read_bool()? {
-            return decode_error("vorbis: not an audio packet");
+            return self.read_header_packet(packet.buf());
         }
 
         let num_modes = self.modes.len() - 1;
@@ -287,42 +374,7 @@ impl Decoder for VorbisDecoder {
         // Read setup data.
         let setup = read_setup(&mut reader, &ident)?;
 
-        // Initialize static DSP data.
-        let windows = Windows::new(1 << ident.bs0_exp, 1 << ident.bs1_exp);
-
-        // Initialize dynamic DSP for each channel.
-        let dsp_channels =
-            (0..ident.n_channels).map(|_| DspChannel::new(ident.bs0_exp, ident.bs1_exp)).collect();
-
-        // Map the channels
-        let channels = match vorbis_channels_to_channels(ident.n_channels) {
-            Some(channels) => channels,
-            _ => return unsupported_error("vorbis: unknown channel map (fix me)"),
-        };
-
-        // Initialize the output buffer.
-        let spec = SignalSpec::new(ident.sample_rate, channels);
-
-        let imdct_short = Imdct::new((1 << ident.bs0_exp) >> 1);
-        let imdct_long = Imdct::new((1 << ident.bs1_exp) >> 1);
-
-        // TODO: Should this be half the block size?
-        let duration = 1u64 << ident.bs1_exp;
-
-        let dsp =
-            Dsp { windows, channels: dsp_channels, imdct_short, imdct_long, lapping_state: None };
-
-        Ok(VorbisDecoder {
-            params: params.clone(),
-            ident,
-            codebooks: setup.codebooks,
-            floors: setup.floors,
-            residues: setup.residues,
-            modes: setup.modes,
-            mappings: setup.mappings,
-            dsp,
-            buf: AudioBuffer::new(duration, spec),
-        })
+        VorbisDecoder::from_headers(params, ident, setup)
     }
 
     fn reset(&mut self) {
@@ -366,6 +418,8 @@ struct IdentHeader {
 
 /// The packet type for an identification header.
 const VORBIS_PACKET_TYPE_IDENTIFICATION: u8 = 1;
+/// The packet type for a comment header.
+const VORBIS_PACKET_TYPE_COMMENT: u8 = 3;
 /// The packet type for a setup header.
 const VORBIS_PACKET_TYPE_SETUP: u8 = 5;
 
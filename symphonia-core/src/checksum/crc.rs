@@ -0,0 +1,138 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::io::Monitor;
+
+/// `CrcParams` describes a CRC algorithm with an arbitrary polynomial.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CrcParams {
+    /// The width of the CRC in bits. Must be between 8 and 32, inclusive.
+    pub width: u32,
+    /// The polynomial, excluding the implicit highest order term, in normal (non-reflected) form.
+    pub poly: u32,
+    /// If true, the bytes are processed least-significant bit first, and the CRC is reflected
+    /// (RefIn = RefOut = true). Otherwise, the bytes are processed most-significant bit first.
+    pub reflected: bool,
+}
+
+/// `Crc` implements the CRC algorithm described by a `CrcParams`, for any polynomial of up-to 32
+/// bits.
+///
+/// A lookup table is generated for the polynomial upon instantiation. For the common algorithms
+/// implemented by the other CRC types of this module, prefer those types, since they are faster.
+///
+/// Like the other CRC types, no final XOR is applied to the computed CRC (XorOut = false).
+#[derive(Clone)]
+pub struct Crc {
+    table: Box<[u32; 256]>,
+    params: CrcParams,
+    state: u32,
+}
+
+impl Crc {
+    /// Instantiate a `Crc` instance for the CRC algorithm with an initial state.
+    ///
+    /// For a reflected CRC, the initial state must also be reflected.
+    pub fn new(params: CrcParams, state: u32) -> Self {
+        assert!(params.width >= 8 && params.width <= 32);
+
+        let mask = Self::mask(params.width);
+
+        let mut table = Box::new([0; 256]);
+
+        if params.reflected {
+            let poly = params.poly.reverse_bits() >> (32 - params.width);
+
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut crc = i as u32;
+
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+                }
+
+                *entry = crc;
+            }
+        }
+        else {
+            let top_bit = 1 << (params.width - 1);
+
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut crc = (i as u32) << (params.width - 8);
+
+                for _ in 0..8 {
+                    crc = if crc & top_bit != 0 { (crc << 1) ^ params.poly } else { crc << 1 };
+                }
+
+                *entry = crc & mask;
+            }
+        }
+
+        Crc { table, params, state: state & mask }
+    }
+
+    /// Gets the parameters of the CRC algorithm.
+    pub fn params(&self) -> &CrcParams {
+        &self.params
+    }
+
+    /// Returns the computed CRC.
+    pub fn crc(&self) -> u32 {
+        self.state
+    }
+
+    fn mask(width: u32) -> u32 {
+        u32::MAX >> (32 - width)
+    }
+}
+
+impl Monitor for Crc {
+    #[inline(always)]
+    fn process_byte(&mut self, byte: u8) {
+        self.state = if self.params.reflected {
+            (self.state >> 8) ^ self.table[usize::from(self.state as u8 ^ byte)]
+        }
+        else {
+            let idx = (self.state >> (self.params.width - 8)) as u8 ^ byte;
+            ((self.state << 8) ^ self.table[usize::from(idx)]) & Self::mask(self.params.width)
+        };
+    }
+
+    fn process_buf_bytes(&mut self, buf: &[u8]) {
+        for byte in buf.iter() {
+            self.process_byte(*byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc, CrcParams, Monitor};
+
+    fn check(width: u32, poly: u32, reflected: bool, init: u32) -> u32 {
+        let mut crc = Crc::new(CrcParams { width, poly, reflected }, init);
+        crc.process_buf_bytes(b"123456789");
+        crc.crc()
+    }
+
+    #[test]
+    fn verify_crc() {
+        // CRC-8/SMBUS, equivalent to Crc8Ccitt.
+        assert_eq!(check(8, 0x07, false, 0), 0xf4);
+        // CRC-16/ARC, equivalent to Crc16AnsiLe.
+        assert_eq!(check(16, 0x8005, true, 0), 0xbb3d);
+        // CRC-16/IBM-3740.
+        assert_eq!(check(16, 0x1021, false, 0xffff), 0x29b1);
+        // CRC-24/OPENPGP.
+        assert_eq!(check(24, 0x864cfb, false, 0xb704ce), 0x21cf02);
+        // CRC-32/MPEG-2, equivalent to Crc32.
+        assert_eq!(check(32, 0x04c11db7, false, 0xffffffff), 0x0376e6e7);
+        // CRC-32/ISO-HDLC, equivalent to Crc32Le.
+        assert_eq!(!check(32, 0x04c11db7, true, 0xffffffff), 0xcbf43926);
+        // CRC-32C.
+        assert_eq!(!check(32, 0x1edc6f41, true, 0xffffffff), 0xe3069283);
+    }
+}
@@ -177,6 +177,26 @@ pub const CODEC_TYPE_ADPCM_MS: CodecType = CodecType(0x203);
 pub const CODEC_TYPE_ADPCM_IMA_WAV: CodecType = CodecType(0x204);
 /// ADPCM IMA QuickTime
 pub const CODEC_TYPE_ADPCM_IMA_QT: CodecType = CodecType(0x205);
+/// Yamaha ADPCM
+pub const CODEC_TYPE_ADPCM_YAMAHA: CodecType = CodecType(0x206);
+/// Creative 8-bit ADPCM, 4 bits per sample
+pub const CODEC_TYPE_ADPCM_SBPRO_4: CodecType = CodecType(0x207);
+/// Creative 8-bit ADPCM, 2.6 bits per sample
+pub const CODEC_TYPE_ADPCM_SBPRO_3: CodecType = CodecType(0x208);
+/// Creative 8-bit ADPCM, 2 bits per sample
+pub const CODEC_TYPE_ADPCM_SBPRO_2: CodecType = CodecType(0x209);
+
+// Uncompressed DSD audio codecs
+//------------------------------
+
+/// DSD 1-bit, least-significant bit first, interleaved bytes
+pub const CODEC_TYPE_DSD_LSBF: CodecType = CodecType(0x300);
+/// DSD 1-bit, least-significant bit first, planar bytes
+pub const CODEC_TYPE_DSD_LSBF_PLANAR: CodecType = CodecType(0x301);
+/// DSD 1-bit, most-significant bit first, interleaved bytes
+pub const CODEC_TYPE_DSD_MSBF: CodecType = CodecType(0x302);
+/// DSD 1-bit, most-significant bit first, planar bytes
+pub const CODEC_TYPE_DSD_MSBF_PLANAR: CodecType = CodecType(0x303);
 
 // Compressed lossy audio codecs
 //------------------------------
@@ -213,6 +233,20 @@ pub const CODEC_TYPE_AC4: CodecType = CodecType(0x100d);
 pub const CODEC_TYPE_DCA: CodecType = CodecType(0x100e);
 /// Windows Media Audio
 pub const CODEC_TYPE_WMA: CodecType = CodecType(0x100f);
+/// Adaptive Multi-Rate Narrowband (AMR-NB)
+pub const CODEC_TYPE_AMR_NB: CodecType = CodecType(0x1010);
+/// Adaptive Multi-Rate Wideband (AMR-WB)
+pub const CODEC_TYPE_AMR_WB: CodecType = CodecType(0x1011);
+/// GSM 06.10 Full Rate
+pub const CODEC_TYPE_GSM: CodecType = CodecType(0x1012);
+/// GSM 06.10 Full Rate, Microsoft packing
+pub const CODEC_TYPE_GSM_MS: CodecType = CodecType(0x1013);
+/// Low Complexity Subband Coding (SBC)
+pub const CODEC_TYPE_SBC: CodecType = CodecType(0x1014);
+/// RealAudio Cook (RealAudio G2)
+pub const CODEC_TYPE_COOK: CodecType = CodecType(0x1015);
+/// RealAudio Sipro (ACELP.net)
+pub const CODEC_TYPE_SIPR: CodecType = CodecType(0x1016);
 
 // Compressed lossless audio codecs
 //---------------------------------
@@ -227,6 +261,18 @@ pub const CODEC_TYPE_MONKEYS_AUDIO: CodecType = CodecType(0x2002);
 pub const CODEC_TYPE_ALAC: CodecType = CodecType(0x2003);
 /// True Audio (TTA)
 pub const CODEC_TYPE_TTA: CodecType = CodecType(0x2004);
+/// Shorten (SHN)
+pub const CODEC_TYPE_SHORTEN: CodecType = CodecType(0x2005);
+/// Tom's lossless Audio Kompressor (TAK)
+pub const CODEC_TYPE_TAK: CodecType = CodecType(0x2006);
+/// Meridian Lossless Packing (MLP)
+pub const CODEC_TYPE_MLP: CodecType = CodecType(0x2007);
+/// Dolby TrueHD
+pub const CODEC_TYPE_TRUEHD: CodecType = CodecType(0x2008);
+/// Direct Stream Transfer (DST)
+pub const CODEC_TYPE_DST: CodecType = CodecType(0x2009);
+/// RealAudio Lossless Format (RALF)
+pub const CODEC_TYPE_RALF: CodecType = CodecType(0x200a);
 
 /// A method and expected value to perform verification on the decoded audio.
 #[derive(Copy, Clone, Debug)]
@@ -453,6 +499,12 @@ pub struct FinalizeResult {
 pub struct DecoderOptions {
     /// The decoded audio should be verified if possible during the decode process.
     pub verify: bool,
+    /// If supported by the decoder, errors in corrupt packets should be concealed by producing
+    /// substitute audio (e.g., silence) instead of returning a decode error.
+    pub conceal_errors: bool,
+    /// If supported by the decoder, DSD audio should be output as DSD over PCM (DoP) instead of
+    /// raw DSD.
+    pub dsd_over_pcm: bool,
 }
 
 /// A `Decoder` implements a codec's decode algorithm. It consumes `Packet`s and produces
@@ -353,9 +353,20 @@ pub mod util {
     ///
     /// A `SeekIndex` does not require complete coverage of the entire media stream. However, the
     /// better the coverage, the smaller the manual search range the `SeekIndex` will return.
+    ///
+    /// A `SeekIndex` may be bounded to a maximum number of `SeekPoint`s. This allows a
+    /// `FormatReader` to build an index incrementally while packets are read, without the index
+    /// growing with the length of the media stream. When a bounded index is full, every other
+    /// `SeekPoint` is discarded, and `SeekPoint`s appended afterwards are spaced at least as far
+    /// apart as the remaining `SeekPoint`s.
     #[derive(Default)]
     pub struct SeekIndex {
         points: Vec<SeekPoint>,
+        /// The maximum number of `SeekPoint`s in the index, if bounded.
+        max_len: Option<usize>,
+        /// The minimum timestamp distance from the last `SeekPoint` for a `SeekPoint` to be
+        /// appended to the index.
+        min_spacing: u64,
     }
 
     /// `SeekSearchResult` is the return value for a search on a `SeekIndex`. It returns a range of
@@ -383,10 +394,44 @@ pub mod util {
     impl SeekIndex {
         /// Create an empty `SeekIndex`
         pub fn new() -> SeekIndex {
-            SeekIndex { points: Vec::new() }
+            SeekIndex { points: Vec::new(), max_len: None, min_spacing: 0 }
+        }
+
+        /// Create an empty `SeekIndex` that is bounded to a maximum of `max_len` `SeekPoint`s.
+        ///
+        /// Panics if `max_len` is less-than 2.
+        pub fn with_max_len(max_len: usize) -> SeekIndex {
+            assert!(max_len >= 2, "a seek index must store atleast 2 seek points");
+
+            SeekIndex { points: Vec::new(), max_len: Some(max_len), min_spacing: 0 }
+        }
+
+        /// Gets the number of `SeekPoint`s in the index.
+        pub fn len(&self) -> usize {
+            self.points.len()
+        }
+
+        /// Returns true if the index contains no `SeekPoint`s.
+        pub fn is_empty(&self) -> bool {
+            self.points.is_empty()
+        }
+
+        /// Gets the `SeekPoint` with the greatest timestamp, if the index is not empty.
+        pub fn last(&self) -> Option<&SeekPoint> {
+            self.points.last()
+        }
+
+        /// Remove all `SeekPoint`s from the index.
+        pub fn clear(&mut self) {
+            self.points.clear();
+            self.min_spacing = 0;
         }
 
         /// Insert a `SeekPoint` into the index.
+        ///
+        /// A `SeekPoint` with the same timestamp as a `SeekPoint` already in the index is ignored.
+        /// If the index is bounded, a `SeekPoint` closer to its neighbours than the current spacing
+        /// of the index is also ignored.
         pub fn insert(&mut self, ts: u64, byte_offset: u64, n_frames: u32) {
             // Create the seek point.
             let seek_point = SeekPoint::new(ts, byte_offset, n_frames);
@@ -395,22 +440,65 @@ pub mod util {
             let last_ts = self.points.last().map_or(u64::MAX, |p| p.frame_ts);
 
             // If the seek point has a timestamp greater-than the last entry in the index, then
-            // simply append it to the index.
+            // simply append it to the index. However, to keep the spacing of a bounded index even,
+            // the seek point is ignored if it is too close to the last entry.
             if ts > last_ts {
+                if ts - last_ts < self.min_spacing {
+                    return;
+                }
+
                 self.points.push(seek_point)
             }
             else if ts < last_ts {
-                // If the seek point has a timestamp less-than the last entry in the index, then the
-                // insertion point must be found. This case should rarely occur.
+                // If the seek point has a timestamp less-than the last entry in the index, or the
+                // index is empty, then the insertion point must be found. This case should rarely
+                // occur.
 
                 // TODO: Use when Rust 1.52 is stable.
                 // let i = self.points.partition_point(|p| p.frame_ts < ts);
 
                 let i =
-                    self.points.iter().position(|p| p.frame_ts > ts).unwrap_or(self.points.len());
+                    self.points.iter().position(|p| p.frame_ts >= ts).unwrap_or(self.points.len());
+
+                // Ignore seek points that are already in the index, or, to keep the spacing of a
+                // bounded index even, too close to their neighbours.
+                let is_too_close =
+                    |p: &SeekPoint| p.frame_ts.max(ts) - p.frame_ts.min(ts) < self.min_spacing;
+
+                if self.points.get(i).map_or(false, |p| p.frame_ts == ts || is_too_close(p))
+                    || i > 0 && is_too_close(&self.points[i - 1])
+                {
+                    return;
+                }
 
                 self.points.insert(i, seek_point);
             }
+
+            // If the index is bounded and has become too long, decimate it.
+            if let Some(max_len) = self.max_len {
+                if self.points.len() > max_len {
+                    self.decimate();
+                }
+            }
+        }
+
+        /// Discard every other `SeekPoint`, keeping the first and last `SeekPoint`s, and update
+        /// the minimum spacing to the average spacing of the remaining `SeekPoint`s.
+        fn decimate(&mut self) {
+            let last = self.points.len() - 1;
+
+            let mut i = 0;
+
+            self.points.retain(|_| {
+                let keep = i % 2 == 0 || i == last;
+                i += 1;
+                keep
+            });
+
+            let first_ts = self.points[0].frame_ts;
+            let last_ts = self.points[self.points.len() - 1].frame_ts;
+
+            self.min_spacing = (last_ts - first_ts) / (self.points.len() as u64 - 1);
         }
 
         /// Search the index to find a bounded range of bytes wherein the specified frame timestamp
@@ -454,6 +542,30 @@ pub mod util {
             // The index is empty, the stream must be searched manually.
             SeekSearchResult::Stream
         }
+
+        /// Estimate the byte offset of the specified frame timestamp by linearly interpolating
+        /// between the `SeekPoint`s surrounding it. Returns `None` if the timestamp is not covered
+        /// by the index.
+        ///
+        /// The estimate is approximate, and generally not the start of a packet.
+        pub fn interpolate(&self, frame_ts: u64) -> Option<u64> {
+            match self.search(frame_ts) {
+                SeekSearchResult::Range(lower, upper) => {
+                    let ts_delta = u128::from(frame_ts - lower.frame_ts);
+                    let ts_span = u128::from(upper.frame_ts - lower.frame_ts);
+
+                    // Byte offsets should increase with the timestamps, but guard against an
+                    // index that is not monotonic.
+                    let byte_span = u128::from(upper.byte_offset.saturating_sub(lower.byte_offset));
+
+                    Some(lower.byte_offset + (ts_delta * byte_span / ts_span) as u64)
+                }
+                SeekSearchResult::Lower(point) if point.frame_ts == frame_ts => {
+                    Some(point.byte_offset)
+                }
+                _ => None,
+            }
+        }
     }
 
     /// Given a `Packet`, the encoder delay in frames, and the number of non-delay or padding
@@ -511,5 +623,43 @@ pub mod util {
                 SeekSearchResult::Range(SeekPoint::new(320, 0, 100), SeekPoint::new(421, 0, 10))
             );
         }
+
+        #[test]
+        fn verify_seek_index_bounded() {
+            let mut index = SeekIndex::with_max_len(4);
+
+            for i in 0..5 {
+                index.insert(100 * i, 1000 * i, 100);
+            }
+
+            // The index overflowed, so every other seek point was discarded, except the last.
+            assert_eq!(index.len(), 3);
+            assert_eq!(index.last(), Some(&SeekPoint::new(400, 4000, 100)));
+
+            // Seek points closer than the spacing of the remaining seek points are ignored.
+            index.insert(500, 5000, 100);
+            assert_eq!(index.len(), 3);
+
+            index.insert(600, 6000, 100);
+            assert_eq!(index.len(), 4);
+
+            // Duplicate, and too closely spaced, seek points are also ignored when inserted.
+            index.insert(200, 2000, 100);
+            index.insert(300, 3000, 100);
+            assert_eq!(index.len(), 4);
+
+            assert_eq!(
+                index.search(450),
+                SeekSearchResult::Range(
+                    SeekPoint::new(400, 4000, 100),
+                    SeekPoint::new(600, 6000, 100)
+                )
+            );
+
+            assert_eq!(index.interpolate(450), Some(4500));
+            assert_eq!(index.interpolate(600), Some(6000));
+            assert_eq!(index.interpolate(700), None);
+            assert_eq!(SeekIndex::new().interpolate(0), None);
+        }
     }
 }
@@ -19,14 +19,32 @@ fn end_of_stream_error<T>() -> io::Result<T> {
 }
 
 /// `MediaSourceStreamOptions` specifies the buffering behaviour of a `MediaSourceStream`.
+#[derive(Copy, Clone, Debug)]
 pub struct MediaSourceStreamOptions {
-    /// The maximum buffer size. Must be a power of 2. Must be > 32kB.
+    /// The initial buffer size. Must be a power of 2. Must be > 32kB.
     pub buffer_len: usize,
+    /// The maximum size the buffer may grow to when a format reader requests a larger seekback
+    /// buffer. If `None`, the buffer may grow as required. Must be a power of 2, and >=
+    /// `buffer_len` if provided.
+    ///
+    /// Note: If the buffer can't grow large enough, less seekback than requested is guaranteed.
+    pub max_buffer_len: Option<usize>,
+    /// The minimum number of previously read bytes that are guaranteed to be available for
+    /// seekback. If required, the initial buffer is enlarged to provide this guarantee.
+    pub seekback_len: usize,
+    /// If true, the read-ahead length does not grow past its initial length of 1kB. This minimizes
+    /// the amount of data buffered ahead of the reader, at the cost of more reads of the source.
+    pub low_latency: bool,
 }
 
 impl Default for MediaSourceStreamOptions {
     fn default() -> Self {
-        MediaSourceStreamOptions { buffer_len: 64 * 1024 }
+        MediaSourceStreamOptions {
+            buffer_len: 64 * 1024,
+            max_buffer_len: None,
+            seekback_len: 0,
+            low_latency: false,
+        }
     }
 }
 
@@ -49,6 +67,9 @@ impl Default for MediaSourceStreamOptions {
 /// length buffer cache. By default, the buffer caches allows backtracking by up-to the minimum of
 /// either `buffer_len - 32kB` or the total number of bytes read since instantiation or the last
 /// buffer cache invalidation. Note that regular a `seek()` will invalidate the buffer cache.
+///
+/// The buffer size, seekback guarantee, and read-ahead behaviour may be configured with
+/// `MediaSourceStreamOptions`.
 pub struct MediaSourceStream {
     /// The source reader.
     inner: Box<dyn MediaSource>,
@@ -62,6 +83,10 @@ pub struct MediaSourceStream {
     write_pos: usize,
     /// The current block size for a new read.
     read_block_len: usize,
+    /// The maximum block size for a new read.
+    max_block_len: usize,
+    /// The maximum length of the ring buffer, if limited.
+    max_ring_len: Option<usize>,
     /// Absolute position of the inner stream.
     abs_pos: u64,
     /// Relative position of the inner stream from the last seek or 0. This is a count of bytes
@@ -78,16 +103,33 @@ impl MediaSourceStream {
         assert!(options.buffer_len.count_ones() == 1);
         assert!(options.buffer_len > Self::MAX_BLOCK_LEN);
 
-        MediaSourceStream {
+        if let Some(max_buffer_len) = options.max_buffer_len {
+            assert!(max_buffer_len.count_ones() == 1);
+            assert!(max_buffer_len >= options.buffer_len);
+        }
+
+        // In low-latency mode, the read-ahead length never grows past its initial length.
+        let max_block_len =
+            if options.low_latency { Self::MIN_BLOCK_LEN } else { Self::MAX_BLOCK_LEN };
+
+        let mut mss = MediaSourceStream {
             inner: source,
             ring: vec![0; options.buffer_len].into_boxed_slice(),
             ring_mask: options.buffer_len - 1,
             read_pos: 0,
             write_pos: 0,
             read_block_len: Self::MIN_BLOCK_LEN,
+            max_block_len,
+            max_ring_len: options.max_buffer_len,
             abs_pos: 0,
             rel_pos: 0,
+        };
+
+        if options.seekback_len > 0 {
+            mss.ensure_seekback_buffer(options.seekback_len);
         }
+
+        mss
     }
 
     /// Returns if the buffer has been exhausted This is a marginally more efficient way of checking
@@ -129,7 +171,7 @@ impl MediaSourceStream {
 
             // Grow the read block length exponentially to reduce the overhead of buffering on
             // consecutive seeks.
-            self.read_block_len = cmp::min(self.read_block_len << 1, Self::MAX_BLOCK_LEN);
+            self.read_block_len = cmp::min(self.read_block_len << 1, self.max_block_len);
         }
 
         Ok(())
@@ -379,10 +421,15 @@ impl SeekBuffered for MediaSourceStream {
     fn ensure_seekback_buffer(&mut self, len: usize) {
         let ring_len = self.ring.len();
 
-        // A fetch can overwrite a maximum of MAX_BLOCK_LEN bytes in the ring. Therefore, for there
-        // to always be `len` bytes available for seekback, the ring must be len + MAX_BLOCK_LEN in
-        // length. Round-up to the next power-of-2 as that is an invariant of the ring.
-        let new_ring_len = (Self::MAX_BLOCK_LEN + len).next_power_of_two();
+        // A fetch can overwrite a maximum of `max_block_len` bytes in the ring. Therefore, for
+        // there to always be `len` bytes available for seekback, the ring must be len +
+        // `max_block_len` in length. Round-up to the next power-of-2 as that is an invariant of the
+        // ring. The ring may not grow beyond its maximum length, if limited.
+        let mut new_ring_len = (self.max_block_len + len).next_power_of_two();
+
+        if let Some(max_ring_len) = self.max_ring_len {
+            new_ring_len = cmp::min(new_ring_len, max_ring_len);
+        }
 
         // Only grow the ring if necessary.
         if ring_len < new_ring_len {
@@ -467,7 +514,7 @@ impl SeekBuffered for MediaSourceStream {
 
 #[cfg(test)]
 mod tests {
-    use super::{MediaSourceStream, ReadBytes, SeekBuffered};
+    use super::{MediaSourceStream, MediaSourceStreamOptions, ReadBytes, SeekBuffered};
     use std::io::{Cursor, Read};
 
     /// Generate a random vector of bytes of the specified length using a PRNG.
@@ -572,6 +619,46 @@ mod tests {
         assert_eq!(upper, mss.read_byte().unwrap());
     }
 
+    #[test]
+    fn verify_mss_options() {
+        let data = generate_random_bytes(512 * 1024);
+
+        // A minimum length, low-latency, buffer with a seekback guarantee larger than the buffer.
+        let opts = MediaSourceStreamOptions {
+            buffer_len: 64 * 1024,
+            max_buffer_len: Some(256 * 1024),
+            seekback_len: 96 * 1024,
+            low_latency: true,
+        };
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(data.clone())), opts);
+
+        // The read-ahead never exceeds the minimum read block length.
+        mss.read_byte().unwrap();
+        assert_eq!(mss.unread_buffer_len(), 1024 - 1);
+
+        mss.ignore_bytes(160 * 1024 - 1).unwrap();
+        assert!(mss.unread_buffer_len() <= 1024);
+
+        // The seekback guarantee is honoured.
+        assert_eq!(mss.seek_buffered_rel(-96 * 1024), 64 * 1024);
+        assert_eq!(mss.read_byte().unwrap(), data[64 * 1024]);
+
+        // The buffer does not grow beyond its maximum length.
+        mss.ensure_seekback_buffer(512 * 1024);
+        mss.ignore_bytes(256 * 1024).unwrap();
+        assert_eq!(mss.read_buffer_len(), 256 * 1024 - mss.unread_buffer_len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn verify_mss_min_buffer_len() {
+        // The buffer must be larger than the maximum read block length.
+        let opts = MediaSourceStreamOptions { buffer_len: 32 * 1024, ..Default::default() };
+
+        MediaSourceStream::new(Box::new(Cursor::new(vec![0; 1024])), opts);
+    }
+
     #[test]
     fn verify_reading_be() {
         let data = generate_random_bytes(1024 * 1024);
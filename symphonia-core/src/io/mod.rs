@@ -27,12 +27,16 @@ mod buf_reader;
 mod media_source_stream;
 mod monitor_stream;
 mod scoped_stream;
+#[cfg(feature = "segmented-source")]
+mod segmented_source;
 
 pub use bit::*;
 pub use buf_reader::BufReader;
 pub use media_source_stream::{MediaSourceStream, MediaSourceStreamOptions};
 pub use monitor_stream::{Monitor, MonitorStream};
-pub use scoped_stream::ScopedStream;
+pub use scoped_stream::{is_out_of_bounds_error, OutOfBoundsError, ScopedStream};
+#[cfg(feature = "segmented-source")]
+pub use segmented_source::{Segment, SegmentProvider, SegmentedSource, SegmentedSourceHandle};
 
 /// `MediaSource` is a composite trait of [`std::io::Read`] and [`std::io::Seek`]. A source *must*
 /// implement this trait to be used by [`MediaSourceStream`].
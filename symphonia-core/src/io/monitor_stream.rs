@@ -5,6 +5,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::cmp;
 use std::io;
 
 use super::ReadBytes;
@@ -38,8 +39,25 @@ pub trait Monitor {
     fn process_buf_bytes(&mut self, buf: &[u8]);
 }
 
+/// A pair of `Monitor`s observes the same operations. This allows, for example, a CRC and a MD5
+/// checksum to be computed over the same bytes.
+impl<M0: Monitor, M1: Monitor> Monitor for (M0, M1) {
+    #[inline(always)]
+    fn process_byte(&mut self, byte: u8) {
+        self.0.process_byte(byte);
+        self.1.process_byte(byte);
+    }
+
+    fn process_buf_bytes(&mut self, buf: &[u8]) {
+        self.0.process_buf_bytes(buf);
+        self.1.process_buf_bytes(buf);
+    }
+}
+
 /// A `MonitorStream` is a passive stream that observes all operations performed on the inner
 /// stream and forwards an immutable reference of the result to a [`Monitor`].
+///
+/// The monitor observes exactly the bytes consumed from the inner stream, including ignored bytes.
 pub struct MonitorStream<B: ReadBytes, M: Monitor> {
     inner: B,
     monitor: M,
@@ -123,8 +141,17 @@ impl<B: ReadBytes, M: Monitor> ReadBytes for MonitorStream<B, M> {
         Ok(result)
     }
 
-    fn ignore_bytes(&mut self, count: u64) -> io::Result<()> {
-        self.inner.ignore_bytes(count)
+    fn ignore_bytes(&mut self, mut count: u64) -> io::Result<()> {
+        // Ignored bytes must still be observed by the monitor, so read them instead.
+        let mut buf = [0; 1024];
+
+        while count > 0 {
+            let len = cmp::min(count, buf.len() as u64) as usize;
+            self.read_buf_exact(&mut buf[..len])?;
+            count -= len as u64;
+        }
+
+        Ok(())
     }
 
     #[inline(always)]
@@ -132,3 +159,34 @@ impl<B: ReadBytes, M: Monitor> ReadBytes for MonitorStream<B, M> {
         self.inner.pos()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Monitor, MonitorStream, ReadBytes};
+    use crate::checksum::{Crc32, Md5};
+    use crate::io::BufReader;
+
+    #[test]
+    fn verify_monitor_stream() {
+        let buf = b"abcdefghijklmnopqrstuvwxyz123456789";
+
+        let mut expected_crc = Crc32::new(0xffffffff);
+        expected_crc.process_buf_bytes(buf);
+
+        let mut expected_md5 = Md5::default();
+        expected_md5.process_buf_bytes(buf);
+
+        // Consume the buffer using reads and ignores.
+        let monitor = (Crc32::new(0xffffffff), Md5::default());
+        let mut reader = MonitorStream::new(BufReader::new(buf), monitor);
+
+        reader.read_quad_bytes().unwrap();
+        reader.ignore_bytes(10).unwrap();
+        reader.read_buf_exact(&mut [0; 21]).unwrap();
+
+        let (crc, md5) = reader.monitor();
+
+        assert_eq!(crc.crc(), expected_crc.crc());
+        assert_eq!(md5.md5(), expected_md5.md5());
+    }
+}
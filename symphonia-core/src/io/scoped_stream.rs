@@ -6,16 +6,44 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::cmp;
+use std::error;
+use std::fmt;
 use std::io;
 
 use super::{FiniteStream, ReadBytes, SeekBuffered};
 
+/// `OutOfBoundsError` is the error returned when attempting to read past the end of a
+/// `ScopedStream`.
+///
+/// The error is wrapped in an `io::Error` of kind `UnexpectedEof` such that reaching the end of a
+/// scope is still handled like reaching the end of the stream. Use [`is_out_of_bounds_error`] to
+/// distinguish it from the inner stream actually ending.
+#[derive(Debug)]
+pub struct OutOfBoundsError;
+
+impl fmt::Display for OutOfBoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of bounds")
+    }
+}
+
+impl error::Error for OutOfBoundsError {}
+
+/// Returns true if the error was caused by attempting to read past the end of a `ScopedStream`.
+pub fn is_out_of_bounds_error(err: &io::Error) -> bool {
+    err.get_ref().map_or(false, |err| err.is::<OutOfBoundsError>())
+}
+
 #[inline(always)]
 fn out_of_bounds_error<T>() -> io::Result<T> {
-    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "out of bounds"))
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, OutOfBoundsError))
 }
 
 /// A `ScopedStream` restricts the number of bytes that may be read to an upper limit.
+///
+/// A read that would exceed the upper limit fails with an [`OutOfBoundsError`] without consuming
+/// any bytes. `ScopedStream`s may be nested using [`ScopedStream::scope`], in which case reading
+/// from the nested `ScopedStream` also counts towards the limit of its parent.
 pub struct ScopedStream<B: ReadBytes> {
     inner: B,
     start: u64,
@@ -40,6 +68,19 @@ impl<B: ReadBytes> ScopedStream<B> {
         &mut self.inner
     }
 
+    /// Instantiates a new `ScopedStream`, nested within this `ScopedStream`, with an upper limit on
+    /// the number of bytes that can be read starting from the current position.
+    ///
+    /// If the nested `ScopedStream` would exceed the bytes available in this `ScopedStream`, an
+    /// [`OutOfBoundsError`] is returned.
+    pub fn scope(&mut self, len: u64) -> io::Result<ScopedStream<&mut Self>> {
+        if len > self.bytes_available() {
+            return out_of_bounds_error();
+        }
+
+        Ok(ScopedStream::new(self, len))
+    }
+
     /// Ignores the remainder of the `ScopedStream`.
     pub fn ignore(&mut self) -> io::Result<()> {
         self.inner.ignore_bytes(self.len - self.read)
@@ -53,16 +94,19 @@ impl<B: ReadBytes> ScopedStream<B> {
 
 impl<B: ReadBytes> FiniteStream for ScopedStream<B> {
     /// Returns the length of the the `ScopedStream`.
+    #[inline(always)]
     fn byte_len(&self) -> u64 {
         self.len
     }
 
     /// Returns the number of bytes read.
+    #[inline(always)]
     fn bytes_read(&self) -> u64 {
         self.read
     }
 
     /// Returns the number of bytes available to read.
+    #[inline(always)]
     fn bytes_available(&self) -> u64 {
         self.len - self.read
     }
@@ -110,6 +154,11 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
     }
 
     fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Like the end of a stream, return an error if no bytes can be read.
+        if self.len == self.read && !buf.is_empty() {
+            return out_of_bounds_error();
+        }
+
         // Limit read_buf() to the remainder of the scoped bytes if buf has a greater length.
         let scoped_len = cmp::min(self.len - self.read, buf.len() as u64) as usize;
         let result = self.inner.read_buf(&mut buf[0..scoped_len])?;
@@ -177,7 +226,9 @@ impl<B: ReadBytes + SeekBuffered> SeekBuffered for ScopedStream<B> {
     #[inline(always)]
     fn seek_buffered(&mut self, pos: u64) -> u64 {
         // Clamp the seekable position to within the bounds of the ScopedStream.
-        self.inner.seek_buffered(pos.clamp(self.start, self.start + self.len))
+        let pos = self.inner.seek_buffered(pos.clamp(self.start, self.start + self.len));
+        self.read = pos - self.start;
+        pos
     }
 
     #[inline(always)]
@@ -186,6 +237,57 @@ impl<B: ReadBytes + SeekBuffered> SeekBuffered for ScopedStream<B> {
         // within the bounds of the ScopedStream.
         let max_back = self.read.min(isize::MAX as u64) as isize;
         let max_forward = (self.len - self.read).min(isize::MAX as u64) as isize;
-        self.inner.seek_buffered_rel(delta.clamp(-max_back, max_forward))
+        let pos = self.inner.seek_buffered_rel(delta.clamp(-max_back, max_forward));
+        self.read = pos - self.start;
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{is_out_of_bounds_error, ScopedStream};
+    use crate::io::{FiniteStream, MediaSourceStream, ReadBytes, SeekBuffered};
+
+    fn stream(buf: &[u8]) -> MediaSourceStream {
+        MediaSourceStream::new(Box::new(Cursor::new(buf.to_vec())), Default::default())
+    }
+
+    #[test]
+    fn verify_scoped_stream() {
+        let buf = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        let mut reader = ScopedStream::new(stream(&buf), 8);
+        assert_eq!(reader.read_byte().unwrap(), 0);
+
+        // A nested scope may not exceed its parent.
+        assert!(is_out_of_bounds_error(&reader.scope(8).err().unwrap()));
+
+        // Reads from a nested scope count towards its parent.
+        {
+            let mut nested = reader.scope(4).unwrap();
+            assert_eq!(nested.read_double_bytes().unwrap(), [1, 2]);
+            assert_eq!(nested.bytes_available(), 2);
+
+            // An over-read fails without consuming any bytes.
+            let err = nested.read_triple_bytes().unwrap_err();
+            assert!(is_out_of_bounds_error(&err));
+            assert_eq!(nested.bytes_available(), 2);
+
+            // Buffered seeks are accounted for.
+            assert_eq!(nested.seek_buffered_rel(-1), 2);
+            assert_eq!(nested.bytes_available(), 3);
+            nested.ignore().unwrap();
+        }
+
+        assert_eq!(reader.bytes_read(), 5);
+        assert_eq!(reader.read_buf(&mut [0; 8]).unwrap(), 3);
+        assert!(is_out_of_bounds_error(&reader.read_buf(&mut [0; 1]).unwrap_err()));
+
+        // Reaching the end of the inner stream is not an out-of-bounds error.
+        let mut reader = ScopedStream::new(stream(&buf), 16);
+        reader.ignore_bytes(10).unwrap();
+        assert!(!is_out_of_bounds_error(&reader.read_byte().unwrap_err()));
     }
 }
@@ -0,0 +1,199 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::MediaSource;
+
+/// A `Segment` is one part of the stream read by a [`SegmentedSource`], such as a media segment
+/// of an HLS playlist.
+pub struct Segment {
+    /// The data of the segment.
+    pub reader: Box<dyn io::Read + Send + Sync>,
+    /// If true, the segment does not continue the stream of the previous segment. The timestamps,
+    /// codec parameters, or even the container may change. This is equivalent to the
+    /// `EXT-X-DISCONTINUITY` tag of an HLS playlist.
+    pub discontinuity: bool,
+}
+
+impl Segment {
+    /// Instantiate a new `Segment` that continues the stream of the previous segment.
+    pub fn new<R: io::Read + Send + Sync + 'static>(reader: R) -> Self {
+        Segment { reader: Box::new(reader), discontinuity: false }
+    }
+
+    /// Instantiate a new `Segment` that starts a new stream after a discontinuity.
+    pub fn new_discontinuity<R: io::Read + Send + Sync + 'static>(reader: R) -> Self {
+        Segment { reader: Box::new(reader), discontinuity: true }
+    }
+}
+
+/// `SegmentProvider` provides the segments of a [`SegmentedSource`] in order.
+///
+/// A provider may fetch each segment lazily, for example, from the network as the playlist is
+/// refreshed.
+pub trait SegmentProvider: Send + Sync {
+    /// Gets the next segment, or `None` if there are no more segments.
+    fn next_segment(&mut self) -> io::Result<Option<Segment>>;
+}
+
+impl SegmentProvider for VecDeque<Segment> {
+    fn next_segment(&mut self) -> io::Result<Option<Segment>> {
+        Ok(self.pop_front())
+    }
+}
+
+/// `SegmentedSourceHandle` is a handle to a [`SegmentedSource`] that remains accessible after the
+/// source is moved into a `MediaSourceStream`.
+#[derive(Clone, Default)]
+pub struct SegmentedSourceHandle {
+    at_discontinuity: Arc<AtomicBool>,
+}
+
+impl SegmentedSourceHandle {
+    /// Returns if the source has stopped at a discontinuity.
+    pub fn is_at_discontinuity(&self) -> bool {
+        self.at_discontinuity.load(Ordering::Acquire)
+    }
+
+    /// Resumes reading the segments following the discontinuity the source has stopped at.
+    pub fn resume(&self) {
+        self.at_discontinuity.store(false, Ordering::Release);
+    }
+}
+
+/// `SegmentedSource` is an unseekable [`MediaSource`] that concatenates an ordered series of
+/// segments into a single stream, such as the segments of an HLS playlist.
+///
+/// Segments that continue the stream of the previous segment are read as one stream. For
+/// example, the MPEG-TS segments of an HLS playlist, or a fragmented MP4 initialization segment
+/// followed by its media segments, may be read by the MPEG-TS or ISO/MP4 format readers
+/// respectively.
+///
+/// At a discontinuity, the source stops, and signals the end of the stream, so that the format
+/// reader reading it reaches the end of the stream. If the handle of the source is then at a
+/// discontinuity, call [`SegmentedSourceHandle::resume`], and instantiate a new format reader,
+/// and decoder, with the `MediaSourceStream` returned by `FormatReader::into_inner` to read the
+/// segments following the discontinuity. For fragmented MP4, the segments following a
+/// discontinuity must start with an initialization segment.
+pub struct SegmentedSource {
+    provider: Box<dyn SegmentProvider>,
+    /// The segment being read.
+    current: Option<Box<dyn io::Read + Send + Sync>>,
+    /// The segment following the discontinuity the source has stopped at.
+    pending: Option<Box<dyn io::Read + Send + Sync>>,
+    handle: SegmentedSourceHandle,
+}
+
+impl SegmentedSource {
+    /// Instantiate a new `SegmentedSource` that reads the segments from the provider.
+    pub fn new(provider: Box<dyn SegmentProvider>) -> Self {
+        SegmentedSource { provider, current: None, pending: None, handle: Default::default() }
+    }
+
+    /// Gets a handle to the source.
+    pub fn handle(&self) -> SegmentedSourceHandle {
+        self.handle.clone()
+    }
+}
+
+impl MediaSource for SegmentedSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl io::Read for SegmentedSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Remain at the end of the stream until resumed.
+        if self.handle.is_at_discontinuity() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.current.is_none() {
+                self.current = self.pending.take();
+            }
+
+            if let Some(reader) = self.current.as_mut() {
+                let len = reader.read(buf)?;
+
+                if len > 0 {
+                    return Ok(len);
+                }
+
+                // The segment has ended.
+                self.current = None;
+            }
+
+            match self.provider.next_segment()? {
+                Some(segment) if segment.discontinuity => {
+                    self.pending = Some(segment.reader);
+                    self.handle.at_discontinuity.store(true, Ordering::Release);
+
+                    return Ok(0);
+                }
+                Some(segment) => self.current = Some(segment.reader),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+impl io::Seek for SegmentedSource {
+    fn seek(&mut self, _: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Other, "source does not support seeking"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn verify_segmented_source() {
+        let segments: VecDeque<Segment> = vec![
+            Segment::new(&b"ab"[..]),
+            Segment::new(&b""[..]),
+            Segment::new(&b"cd"[..]),
+            Segment::new_discontinuity(&b"ef"[..]),
+            Segment::new(&b"g"[..]),
+        ]
+        .into();
+
+        let mut source = SegmentedSource::new(Box::new(segments));
+        let handle = source.handle();
+
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abcd");
+        assert!(handle.is_at_discontinuity());
+
+        // The source remains at the end of the stream until resumed.
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abcd");
+
+        handle.resume();
+
+        buf.clear();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"efg");
+        assert!(!handle.is_at_discontinuity());
+    }
+}
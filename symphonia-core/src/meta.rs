@@ -121,6 +121,7 @@ pub enum StandardTagKey {
     EncodingDate,
     Engineer,
     Ensemble,
+    GaplessPlayback,
     Genre,
     IdentAsin,
     IdentBarcode,
@@ -130,6 +131,7 @@ pub enum StandardTagKey {
     IdentPn,
     IdentPodcast,
     IdentUpc,
+    InvolvedPeople,
     Label,
     Language,
     License,
@@ -141,6 +143,7 @@ pub enum StandardTagKey {
     Mood,
     MovementName,
     MovementNumber,
+    MovementTotal,
     MusicBrainzAlbumArtistId,
     MusicBrainzAlbumId,
     MusicBrainzArtistId,
@@ -156,6 +159,7 @@ pub enum StandardTagKey {
     MusicBrainzReleaseType,
     MusicBrainzTrackId,
     MusicBrainzWorkId,
+    MusicianCredits,
     Opus,
     OriginalAlbum,
     OriginalArtist,
@@ -207,9 +211,89 @@ pub enum StandardTagKey {
     UrlPurchase,
     UrlSource,
     Version,
+    Work,
     Writer,
 }
 
+/// The type of content of synchronized `Lyrics`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LyricsContentType {
+    /// Other content.
+    Other,
+    /// Lyrics.
+    Lyrics,
+    /// A transcription of the spoken text.
+    Transcription,
+    /// Movement, or part, names.
+    PartName,
+    /// Events (e.g., "Don Quijote enters the stage").
+    Events,
+    /// Chords (e.g., "Bb F Fsus").
+    Chords,
+    /// Trivia, or "pop-up" information.
+    Trivia,
+    /// URLs to webpages.
+    WebpageUrls,
+    /// URLs to images.
+    ImageUrls,
+}
+
+/// The unit of the timestamps of synchronized `Lyrics`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LyricsTimeUnit {
+    /// Timestamps are in milliseconds from the start of the stream.
+    Milliseconds,
+    /// Timestamps are in MPEG audio frames from the start of the stream.
+    MpegFrames,
+}
+
+/// A line, or syllable, of synchronized `Lyrics`.
+#[derive(Clone, Debug)]
+pub struct LyricsLine {
+    /// The time at which the text starts, in units of the parent `Lyrics`' `time_unit`.
+    pub timestamp: u64,
+    /// The text. A line feed at the start of the text indicates the start of a new line.
+    pub text: String,
+}
+
+/// `Lyrics` are lyrics, or other text, synchronized with the audio.
+#[derive(Clone, Debug)]
+pub struct Lyrics {
+    /// The ISO-639-2 language code of the text, if known.
+    pub language: Option<String>,
+    /// A description of the content.
+    pub description: String,
+    /// The type of content.
+    pub content_type: LyricsContentType,
+    /// The unit of the timestamps of the lines.
+    pub time_unit: LyricsTimeUnit,
+    /// The lines, or syllables, in order of their timestamps.
+    pub lines: Vec<LyricsLine>,
+}
+
+impl fmt::Display for Lyrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Print each line on its own line prefixed by its timestamp.
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+
+            match self.time_unit {
+                LyricsTimeUnit::Milliseconds => {
+                    let ms = line.timestamp;
+                    write!(f, "[{:0>2}:{:0>2}.{:0>3}] ", ms / 60_000, (ms / 1000) % 60, ms % 1000)?
+                }
+                LyricsTimeUnit::MpegFrames => write!(f, "[frame {}] ", line.timestamp)?,
+            }
+
+            f.write_str(line.text.trim_matches('\n'))?;
+        }
+
+        Ok(())
+    }
+}
+
 /// A `Tag` value.
 ///
 /// Note: The data types in this enumeration are a generalization. Depending on the particular tag
@@ -394,6 +478,7 @@ pub struct MetadataRevision {
     tags: Vec<Tag>,
     visuals: Vec<Visual>,
     vendor_data: Vec<VendorData>,
+    lyrics: Vec<Lyrics>,
 }
 
 impl MetadataRevision {
@@ -414,6 +499,13 @@ impl MetadataRevision {
     pub fn vendor_data(&self) -> &[VendorData] {
         &self.vendor_data
     }
+
+    /// Gets an immutable slice to the synchronized `Lyrics` in this revision.
+    ///
+    /// A textual rendering of each is also provided as a `Tag`.
+    pub fn lyrics(&self) -> &[Lyrics] {
+        &self.lyrics
+    }
 }
 
 /// `MetadataBuilder` is the builder for `Metadata` revisions.
@@ -446,6 +538,12 @@ impl MetadataBuilder {
         self
     }
 
+    /// Add synchronized `Lyrics` to the metadata.
+    pub fn add_lyrics(&mut self, lyrics: Lyrics) -> &mut Self {
+        self.metadata.lyrics.push(lyrics);
+        self
+    }
+
     /// Yield the constructed `Metadata` revision.
     pub fn metadata(self) -> MetadataRevision {
         self.metadata
@@ -122,6 +122,9 @@ pub trait QueryDescriptor {
 
     /// Using the provided context buffer, score calculate and returns a value between 0 and 255
     /// indicating the confidence of the reader in decoding or parsing the source stream.
+    ///
+    /// A score of 0 indicates that the reader cannot decode or parse the source stream despite the
+    /// marker matching, and the reader will not be instantiated.
     fn score(context: &[u8]) -> u8;
 }
 
@@ -264,33 +267,43 @@ impl Probe {
                     context, init_pos, count,
                 );
 
-                // Search for registered markers in the 16-byte window.
+                // Search for registered markers in the 16-byte window. If the marker is shared by
+                // several formats, then the format with the highest score for the window is used. A
+                // score of 0 is not a match.
+                let mut best: Option<(u8, &Descriptor)> = None;
+
                 for registered in &self.registered {
                     for marker in registered.markers {
                         let len = marker.len();
 
-                        // If a match is found, return the instantiate.
                         if context[0..len] == **marker {
-                            // Re-align the stream to the start of the marker.
-                            mss.seek_buffered_rev(16);
-
-                            // TODO: Implement scoring.
+                            let score = (registered.score)(&context);
 
-                            debug!(
-                                "found the format marker {:x?} @ {}+{} bytes.",
-                                &context[0..len],
-                                init_pos,
-                                count,
-                            );
-
-                            return Ok(registered.inst);
+                            if score > 0 && best.map_or(true, |(best_score, _)| score > best_score)
+                            {
+                                best = Some((score, registered));
+                            }
+                            break;
                         }
                     }
                 }
 
-                // If no registered markers were matched, then the bloom filter returned a false
-                // positive. Re-align the stream to the end of the 2-byte window and continue the
-                // search.
+                // If a match is found, return the instantiate.
+                if let Some((_, registered)) = best {
+                    // Re-align the stream to the start of the marker.
+                    mss.seek_buffered_rev(16);
+
+                    debug!(
+                        "found the format marker {:x?} of {} @ {}+{} bytes.",
+                        &context, registered.short_name, init_pos, count,
+                    );
+
+                    return Ok(registered.inst);
+                }
+
+                // If no registered markers were matched, then either the bloom filter returned a
+                // false positive, or all matching formats rejected the window. Re-align the stream
+                // to the end of the 2-byte window and continue the search.
                 mss.seek_buffered_rev(16 - 2);
             }
         }
@@ -377,3 +390,93 @@ macro_rules! support_metadata {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{Descriptor, Instantiate, Probe};
+    use crate::errors::{unsupported_error, Error};
+    use crate::io::{MediaSourceStream, ReadBytes};
+
+    fn make_descriptor(short_name: &'static str, score: fn(&[u8]) -> u8) -> Descriptor {
+        Descriptor {
+            short_name,
+            long_name: short_name,
+            extensions: &[],
+            mime_types: &[],
+            markers: &[b"RIFF"],
+            score,
+            inst: match short_name {
+                "wave" => Instantiate::Format(|_, _| unsupported_error("wave")),
+                _ => Instantiate::Format(|_, _| unsupported_error("avi")),
+            },
+        }
+    }
+
+    fn make_probe() -> Probe {
+        let mut probe = Probe::default();
+        probe.register(&make_descriptor("wave", |ctx| {
+            if &ctx[8..12] == b"WAVE" {
+                255
+            }
+            else {
+                0
+            }
+        }));
+        probe.register(&make_descriptor("avi", |ctx| {
+            if &ctx[8..12] == b"AVI " {
+                255
+            }
+            else {
+                0
+            }
+        }));
+        probe
+    }
+
+    fn make_riff(form: &[u8; 4]) -> Vec<u8> {
+        let mut buf = b"RIFF\x04\0\0\0".to_vec();
+        buf.extend_from_slice(form);
+        buf.extend_from_slice(&[0; 12]);
+        buf
+    }
+
+    fn probe_next(probe: &Probe, buf: Vec<u8>) -> (&'static str, u64) {
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let name = match probe.next(&mut mss).unwrap() {
+            Instantiate::Format(inst) => {
+                let empty =
+                    MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default());
+
+                match inst(empty, &Default::default()) {
+                    Err(Error::Unsupported(name)) => name,
+                    _ => panic!("unexpected instantiation result"),
+                }
+            }
+            _ => panic!("expected a format reader"),
+        };
+
+        (name, mss.pos())
+    }
+
+    #[test]
+    fn verify_probe_shared_marker() {
+        let probe = make_probe();
+
+        assert_eq!(probe_next(&probe, make_riff(b"WAVE")), ("wave", 0));
+        assert_eq!(probe_next(&probe, make_riff(b"AVI ")), ("avi", 0));
+    }
+
+    #[test]
+    fn verify_probe_zero_score() {
+        let probe = make_probe();
+
+        // No format accepts the first RIFF form, so the search continues to the second.
+        let mut buf = make_riff(b"ACON");
+        buf.extend(make_riff(b"WAVE"));
+
+        assert_eq!(probe_next(&probe, buf), ("wave", 24));
+    }
+}
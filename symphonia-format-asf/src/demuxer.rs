@@ -0,0 +1,536 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use crate::objects::*;
+use crate::packet::{read_data_packet, Payload};
+
+/// The length of the data object body preceding the data packets.
+const DATA_OBJECT_HEADER_LEN: u64 = 26;
+
+/// A media object being reassembled from payloads.
+struct MediaObject {
+    num: u32,
+    pres_time: u32,
+    len: usize,
+    data: Vec<u8>,
+}
+
+/// The state of an audio stream.
+struct StreamState {
+    stream_num: u8,
+    spread: Option<AudioSpread>,
+    object: Option<MediaObject>,
+    /// The last media object read in full. Its duration is unknown until the next media object is
+    /// read.
+    pending: Option<Packet>,
+}
+
+impl StreamState {
+    fn reset(&mut self) {
+        self.object = None;
+        self.pending = None;
+    }
+
+    /// Add a payload to the media object being reassembled. Returns the pending packet if the
+    /// media object was completed.
+    fn push_payload(&mut self, payload: &Payload, data: &[u8], preroll: u64) -> Option<Packet> {
+        if payload.offset == 0 {
+            if self.object.is_some() {
+                debug!("discarding incomplete media object of stream {}", self.stream_num);
+            }
+
+            self.object = Some(MediaObject {
+                num: payload.object_num,
+                pres_time: payload.pres_time,
+                len: payload.object_len as usize,
+                data: Vec::with_capacity(payload.object_len as usize),
+            });
+        }
+
+        match &mut self.object {
+            Some(object)
+                if object.num == payload.object_num
+                    && object.data.len() == payload.offset as usize =>
+            {
+                object.data.extend_from_slice(data);
+
+                if object.data.len() < object.len {
+                    return None;
+                }
+            }
+            _ => {
+                // The payload is part of a media object with a start that was not read.
+                self.object = None;
+                return None;
+            }
+        }
+
+        let object = self.object.take().unwrap();
+
+        let data = match self.spread.and_then(|spread| spread.descramble(&object.data)) {
+            Some(data) => data,
+            None => object.data.into_boxed_slice(),
+        };
+
+        let ts = u64::from(object.pres_time).saturating_sub(preroll);
+
+        let packet = Packet::new_from_boxed_slice(u32::from(self.stream_num), ts, 0, data);
+
+        // The duration of the pending packet is the difference of the presentation times.
+        self.pending.replace(packet).map(|mut pending| {
+            pending.dur = ts.saturating_sub(pending.ts);
+            pending
+        })
+    }
+}
+
+/// Advanced Systems Format (ASF) format reader.
+///
+/// `AsfReader` implements a demuxer for ASF files, such as Windows Media Audio files. Only audio
+/// streams are exposed as tracks, with a track ID equal to the stream number. Each packet contains
+/// a single media object, and the timestamps are presentation times in milliseconds.
+pub struct AsfReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    streams: Vec<StreamState>,
+    /// The length of every data packet.
+    packet_len: u32,
+    /// The number of data packets, or 0 if unknown.
+    num_packets: u64,
+    /// The offset of all presentation times in milliseconds.
+    preroll: u64,
+    /// The duration of the file in milliseconds, or 0 if unknown.
+    duration: u64,
+    data_start_pos: u64,
+    data_end_pos: Option<u64>,
+    next_packet_num: u64,
+    index: Vec<IndexEntry>,
+    /// The packets read, but not yet returned.
+    packets: VecDeque<Packet>,
+}
+
+impl QueryDescriptor for AsfReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "asf",
+            "Advanced Systems Format",
+            &["asf", "wma"],
+            &["audio/x-ms-wma", "video/x-ms-asf"],
+            &[&HEADER_OBJECT]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl AsfReader {
+    /// Read the next data packet, and queue the packets of the media objects it completes.
+    /// Returns false at the end of the data packets.
+    fn read_next_data_packet(&mut self) -> Result<bool> {
+        if self.num_packets > 0 && self.next_packet_num >= self.num_packets {
+            return Ok(false);
+        }
+
+        if let Some(data_end_pos) = self.data_end_pos {
+            if self.reader.pos() + u64::from(self.packet_len) > data_end_pos {
+                return Ok(false);
+            }
+        }
+
+        let buf = self.reader.read_boxed_slice_exact(self.packet_len as usize)?;
+
+        self.next_packet_num += 1;
+
+        let data_packet = read_data_packet(&buf)?;
+
+        for payload in &data_packet.payloads {
+            let stream =
+                self.streams.iter_mut().find(|stream| stream.stream_num == payload.stream_num);
+
+            if let Some(stream) = stream {
+                let data = &buf[payload.data.clone()];
+
+                if let Some(packet) = stream.push_payload(payload, data, self.preroll) {
+                    self.packets.push_back(packet);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Move the reader to a data packet, and discard all partially read media objects.
+    fn seek_to_data_packet(&mut self, packet_num: u64) -> Result<()> {
+        let pos = self.data_start_pos + packet_num * u64::from(self.packet_len);
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        self.next_packet_num = packet_num;
+        self.packets.clear();
+
+        for stream in self.streams.iter_mut() {
+            stream.reset();
+        }
+
+        Ok(())
+    }
+
+    /// Get the next packet of a track while seeking.
+    fn next_track_packet(&mut self, track_id: u32) -> Result<Packet> {
+        loop {
+            match self.next_packet() {
+                Ok(packet) if packet.track_id() == track_id => return Ok(packet),
+                Ok(_) => (),
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return seek_error(SeekErrorKind::OutOfRange)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Get the send time of a data packet.
+    fn read_send_time(&mut self, packet_num: u64) -> Result<u32> {
+        let pos = self.data_start_pos + packet_num * u64::from(self.packet_len);
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        let buf = self.reader.read_boxed_slice_exact(self.packet_len as usize)?;
+
+        Ok(read_data_packet(&buf)?.send_time)
+    }
+
+    /// Find the last data packet sent before a presentation time, excluding the preroll.
+    fn find_packet(&mut self, ts: u64) -> Result<u64> {
+        // Use the index if available.
+        if !self.index.is_empty() {
+            let ts = ts + self.preroll;
+            let idx = self.index.partition_point(|entry| entry.ts <= ts).saturating_sub(1);
+            return Ok(self.index[idx].packet_num);
+        }
+
+        let num_packets = match (self.num_packets, self.data_end_pos) {
+            (0, Some(end)) => (end - self.data_start_pos) / u64::from(self.packet_len),
+            (num_packets, _) => num_packets,
+        };
+
+        // Data packets are usually sent one preroll before they are presented, so use a binary
+        // search for the last data packet with a send time not after the presentation time.
+        let mut low = 0;
+        let mut high = num_packets;
+
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+
+            if u64::from(self.read_send_time(mid)?) <= ts {
+                low = mid;
+            }
+            else {
+                high = mid;
+            }
+        }
+
+        Ok(low)
+    }
+}
+
+impl FormatReader for AsfReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let header = ObjectHeader::read(&mut source)?;
+
+        if header.guid != HEADER_OBJECT || header.len < 6 {
+            return unsupported_error("asf: missing header object");
+        }
+
+        // Skip the number of header objects, and the reserved fields.
+        source.ignore_bytes(6)?;
+
+        let mut reader = ScopedStream::new(&mut source, header.len - 6);
+
+        let mut props = None;
+        let mut streams = Vec::new();
+        let mut builder = MetadataBuilder::new();
+
+        while reader.bytes_available() >= OBJECT_HEADER_LEN {
+            let header = ObjectHeader::read(&mut reader)?;
+
+            let mut object = reader.scope(header.len)?;
+
+            match header.guid {
+                FILE_PROPERTIES_OBJECT => props = Some(read_file_properties(&mut object)?),
+                STREAM_PROPERTIES_OBJECT => {
+                    if let Some(stream) = read_stream_properties(&mut object, header.len)? {
+                        streams.push(stream);
+                    }
+                }
+                CONTENT_DESCRIPTION_OBJECT => read_content_description(&mut object, &mut builder)?,
+                EXTENDED_CONTENT_DESCRIPTION_OBJECT => {
+                    read_extended_content_description(&mut object, &mut builder)?
+                }
+                HEADER_EXTENSION_OBJECT => read_header_extension(&mut object, &mut builder)?,
+                _ => debug!("skipping header object {:x?}", header.guid),
+            }
+
+            object.ignore()?;
+        }
+
+        reader.ignore()?;
+
+        let props = match props {
+            Some(props) => props,
+            None => return decode_error("asf: missing file properties object"),
+        };
+
+        if streams.is_empty() {
+            return unsupported_error("asf: no audio streams");
+        }
+
+        // Skip any objects preceding the data object.
+        let data_len = loop {
+            let header = ObjectHeader::read(&mut source)?;
+
+            if header.guid == DATA_OBJECT {
+                break header.len;
+            }
+
+            debug!("skipping object {:x?}", header.guid);
+            source.ignore_bytes(header.len)?;
+        };
+
+        // Skip the file ID.
+        source.ignore_bytes(16)?;
+
+        let num_packets = match source.read_u64()? {
+            0 => props.num_packets,
+            num_packets => num_packets,
+        };
+
+        let _reserved = source.read_u16()?;
+
+        let data_start_pos = source.pos();
+
+        // A data object without a size is being broadcast, and ends with the stream.
+        let data_end_pos = if data_len >= DATA_OBJECT_HEADER_LEN {
+            Some(data_start_pos + data_len - DATA_OBJECT_HEADER_LEN)
+        }
+        else {
+            None
+        };
+
+        // The optional index objects follow the data object.
+        let mut index = Vec::new();
+
+        if let (Some(data_end_pos), true) = (data_end_pos, source.is_seekable()) {
+            source.seek(SeekFrom::Start(data_end_pos))?;
+
+            while let Ok(header) = ObjectHeader::read(&mut source) {
+                let mut object = ScopedStream::new(&mut source, header.len);
+
+                let entries = match header.guid {
+                    INDEX_OBJECT => read_index(&mut object, props.packet_len),
+                    SIMPLE_INDEX_OBJECT if index.is_empty() => read_simple_index(&mut object),
+                    _ => Ok(Vec::new()),
+                };
+
+                match entries {
+                    Ok(entries) if !entries.is_empty() => index = entries,
+                    Ok(_) => (),
+                    Err(err) => warn!("failed to read index: {}", err),
+                }
+
+                if object.ignore().is_err() {
+                    break;
+                }
+            }
+
+            source.seek(SeekFrom::Start(data_start_pos))?;
+        }
+
+        let duration = props.duration.saturating_sub(props.preroll);
+
+        let mut tracks = Vec::new();
+        let mut states = Vec::new();
+
+        for stream in streams {
+            let mut params = stream.params;
+
+            if duration > 0 {
+                params.with_n_frames(duration);
+            }
+
+            // A media object of PCM audio does not exceed a data packet.
+            if is_pcm(params.codec) && stream.block_align > 0 {
+                let max_frames = props.packet_len / u32::from(stream.block_align);
+                params.with_max_frames_per_packet(u64::from(max_frames.max(1)));
+            }
+
+            tracks.push(Track::new(u32::from(stream.stream_num), params));
+
+            states.push(StreamState {
+                stream_num: stream.stream_num,
+                spread: stream.spread,
+                object: None,
+                pending: None,
+            });
+        }
+
+        let mut metadata: MetadataLog = Default::default();
+        metadata.push(builder.metadata());
+
+        Ok(AsfReader {
+            reader: source,
+            tracks,
+            cues: Vec::new(),
+            metadata,
+            streams: states,
+            packet_len: props.packet_len,
+            num_packets,
+            preroll: props.preroll,
+            duration,
+            data_start_pos,
+            data_end_pos,
+            next_packet_num: 0,
+            index,
+            packets: VecDeque::new(),
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        loop {
+            if let Some(packet) = self.packets.pop_front() {
+                return Ok(packet);
+            }
+
+            if !self.read_next_data_packet()? {
+                // The last media object of every stream lasts until the end of the file.
+                for stream in self.streams.iter_mut() {
+                    if let Some(mut packet) = stream.pending.take() {
+                        packet.dur = self.duration.saturating_sub(packet.ts);
+                        self.packets.push_back(packet);
+                    }
+                }
+
+                if self.packets.is_empty() {
+                    return end_of_stream_error();
+                }
+            }
+        }
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let (track_id, required_ts) = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, track_id } => (track_id, ts),
+            // Time value given, timestamps are in milliseconds.
+            SeekTo::Time { time, track_id } => {
+                let ts = TimeBase::new(1, 1000).calc_timestamp(time);
+                (track_id.unwrap_or(self.tracks[0].id), ts)
+            }
+        };
+
+        let stream = match self.streams.iter().find(|s| u32::from(s.stream_num) == track_id) {
+            Some(stream) => stream,
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        if self.duration > 0 && required_ts >= self.duration {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        if self.reader.is_seekable() {
+            let mut packet_num = self.find_packet(required_ts)?;
+            let mut step = 1;
+
+            // A media object may start in a data packet sent before the data packet found, so step
+            // back until the first packet of the track does not follow the required timestamp.
+            loop {
+                self.seek_to_data_packet(packet_num)?;
+
+                // No packet of the track may follow if the last media object started earlier.
+                match self.next_track_packet(track_id) {
+                    Ok(packet) if packet.ts <= required_ts || packet_num == 0 => {
+                        self.packets.push_front(packet);
+                        break;
+                    }
+                    Err(err) if packet_num == 0 => return Err(err),
+                    _ => (),
+                }
+
+                packet_num = packet_num.saturating_sub(step);
+                step *= 2;
+            }
+        }
+        else if stream.pending.as_ref().map_or(false, |packet| packet.ts > required_ts) {
+            // If the reader is not seekable then only forward seeks are possible.
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // Read packets until the packet of the track containing the required timestamp.
+        let actual_ts = loop {
+            let packet = self.next_track_packet(track_id)?;
+
+            if packet.ts + packet.dur.max(1) > required_ts {
+                let ts = packet.ts;
+                self.packets.push_front(packet);
+                break ts;
+            }
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+/// Returns true if the codec is a PCM codec.
+fn is_pcm(codec: CodecType) -> bool {
+    matches!(
+        codec,
+        CODEC_TYPE_PCM_U8
+            | CODEC_TYPE_PCM_S16LE
+            | CODEC_TYPE_PCM_S24LE
+            | CODEC_TYPE_PCM_S32LE
+            | CODEC_TYPE_PCM_F32LE
+            | CODEC_TYPE_PCM_F64LE
+            | CODEC_TYPE_PCM_ALAW
+            | CODEC_TYPE_PCM_MULAW
+    )
+}
@@ -0,0 +1,516 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, FiniteStream, ReadBytes, ScopedStream};
+use symphonia_core::meta::{MetadataBuilder, Tag, Value};
+use symphonia_core::units::TimeBase;
+
+use symphonia_metadata::asf;
+
+use log::debug;
+
+/// A GUID, in the mixed-endian byte order it is stored in.
+pub type Guid = [u8; 16];
+
+/// Get the stored form of a GUID.
+const fn guid(d1: u32, d2: u16, d3: u16, d4: [u8; 8]) -> Guid {
+    let a = d1.to_le_bytes();
+    let b = d2.to_le_bytes();
+    let c = d3.to_le_bytes();
+
+    [
+        a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d4[0], d4[1], d4[2], d4[3], d4[4], d4[5],
+        d4[6], d4[7],
+    ]
+}
+
+pub const HEADER_OBJECT: Guid =
+    guid(0x75b2_2630, 0x668e, 0x11cf, [0xa6, 0xd9, 0x00, 0xaa, 0x00, 0x62, 0xce, 0x6c]);
+pub const DATA_OBJECT: Guid =
+    guid(0x75b2_2636, 0x668e, 0x11cf, [0xa6, 0xd9, 0x00, 0xaa, 0x00, 0x62, 0xce, 0x6c]);
+pub const SIMPLE_INDEX_OBJECT: Guid =
+    guid(0x3300_0890, 0xe5b1, 0x11cf, [0x89, 0xf4, 0x00, 0xa0, 0xc9, 0x03, 0x49, 0xcb]);
+pub const INDEX_OBJECT: Guid =
+    guid(0xd6e2_29d3, 0x35da, 0x11d1, [0x90, 0x34, 0x00, 0xa0, 0xc9, 0x03, 0x49, 0xbe]);
+
+pub const FILE_PROPERTIES_OBJECT: Guid =
+    guid(0x8cab_dca1, 0xa947, 0x11cf, [0x8e, 0xe4, 0x00, 0xc0, 0x0c, 0x20, 0x53, 0x65]);
+pub const STREAM_PROPERTIES_OBJECT: Guid =
+    guid(0xb7dc_0791, 0xa9b7, 0x11cf, [0x8e, 0xe6, 0x00, 0xc0, 0x0c, 0x20, 0x53, 0x65]);
+pub const HEADER_EXTENSION_OBJECT: Guid =
+    guid(0x5fbf_03b5, 0xa92e, 0x11cf, [0x8e, 0xe3, 0x00, 0xc0, 0x0c, 0x20, 0x53, 0x65]);
+pub const CONTENT_DESCRIPTION_OBJECT: Guid =
+    guid(0x75b2_2633, 0x668e, 0x11cf, [0xa6, 0xd9, 0x00, 0xaa, 0x00, 0x62, 0xce, 0x6c]);
+pub const EXTENDED_CONTENT_DESCRIPTION_OBJECT: Guid =
+    guid(0xd2d0_a440, 0xe307, 0x11d2, [0x97, 0xf0, 0x00, 0xa0, 0xc9, 0x5e, 0xa8, 0x50]);
+pub const METADATA_OBJECT: Guid =
+    guid(0xc5f8_cbea, 0x5baf, 0x4877, [0x84, 0x67, 0xaa, 0x8c, 0x44, 0xfa, 0x4c, 0xca]);
+pub const METADATA_LIBRARY_OBJECT: Guid =
+    guid(0x4423_1c94, 0x9498, 0x49d1, [0xa1, 0x41, 0x1d, 0x13, 0x4e, 0x45, 0x70, 0x54]);
+
+const AUDIO_MEDIA: Guid =
+    guid(0xf869_9e40, 0x5b4d, 0x11cf, [0xa8, 0xfd, 0x00, 0x80, 0x5f, 0x5c, 0x44, 0x2b]);
+const AUDIO_SPREAD: Guid =
+    guid(0xbfc3_cd50, 0x618f, 0x11cf, [0x8b, 0xb2, 0x00, 0xaa, 0x00, 0xb4, 0xe2, 0x20]);
+
+/// The length of an object header.
+pub const OBJECT_HEADER_LEN: u64 = 24;
+
+/// An object header.
+pub struct ObjectHeader {
+    pub guid: Guid,
+    /// The length of the object body.
+    pub len: u64,
+}
+
+impl ObjectHeader {
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ObjectHeader> {
+        let mut guid = [0; 16];
+        reader.read_buf_exact(&mut guid)?;
+
+        // The object size includes the object header.
+        let len = match reader.read_u64()?.checked_sub(OBJECT_HEADER_LEN) {
+            Some(len) => len,
+            None => return decode_error("asf: invalid object size"),
+        };
+
+        Ok(ObjectHeader { guid, len })
+    }
+}
+
+/// The file properties of a file properties object.
+pub struct FileProperties {
+    /// The number of data packets, or 0 if unknown.
+    pub num_packets: u64,
+    /// The play duration in milliseconds, including the preroll.
+    pub duration: u64,
+    /// The time in milliseconds to buffer before playback, by which all presentation times are
+    /// offset.
+    pub preroll: u64,
+    /// The length of every data packet.
+    pub packet_len: u32,
+}
+
+/// Read a file properties object body.
+pub fn read_file_properties<B: ReadBytes>(reader: &mut B) -> Result<FileProperties> {
+    // Skip the file ID, file size, and creation date.
+    reader.ignore_bytes(32)?;
+
+    let num_packets = reader.read_u64()?;
+    // The play and send durations are in units of 100 ns.
+    let duration = reader.read_u64()? / 10_000;
+    let _send_duration = reader.read_u64()?;
+    let preroll = reader.read_u64()?;
+    let flags = reader.read_u32()?;
+    let min_packet_len = reader.read_u32()?;
+    let max_packet_len = reader.read_u32()?;
+    let _max_bitrate = reader.read_u32()?;
+
+    // For a broadcast the number of packets and durations are invalid.
+    let (num_packets, duration) = if flags & 0x1 != 0 { (0, 0) } else { (num_packets, duration) };
+
+    if min_packet_len != max_packet_len || min_packet_len == 0 {
+        return unsupported_error("asf: variable length data packets are not supported");
+    }
+
+    Ok(FileProperties { num_packets, duration, preroll, packet_len: min_packet_len })
+}
+
+/// The audio spread error correction parameters of an audio stream. If the span is greater than
+/// 1, the media objects of the stream are interleaved in chunks, and must be descrambled.
+#[derive(Clone, Copy)]
+pub struct AudioSpread {
+    pub span: usize,
+    pub packet_len: usize,
+    pub chunk_len: usize,
+}
+
+impl AudioSpread {
+    /// Descramble a media object.
+    pub fn descramble(&self, data: &[u8]) -> Option<Box<[u8]>> {
+        if self.chunk_len == 0
+            || self.packet_len % self.chunk_len != 0
+            || data.len() != self.packet_len * self.span
+        {
+            return None;
+        }
+
+        let chunks_per_packet = self.packet_len / self.chunk_len;
+
+        let mut out = vec![0; data.len()];
+
+        for (i, chunk) in out.chunks_exact_mut(self.chunk_len).enumerate() {
+            let row = i / self.span;
+            let col = i % self.span;
+            let idx = row + col * chunks_per_packet;
+
+            chunk.copy_from_slice(&data[idx * self.chunk_len..(idx + 1) * self.chunk_len]);
+        }
+
+        Some(out.into_boxed_slice())
+    }
+}
+
+/// An audio stream of a stream properties object.
+pub struct AudioStream {
+    pub stream_num: u8,
+    pub params: CodecParameters,
+    /// The length of a block of every channel in bytes.
+    pub block_align: u16,
+    pub spread: Option<AudioSpread>,
+}
+
+/// Get the codec parameters, and block alignment, of a WAVEFORMATEX structure.
+fn read_wave_format(buf: &[u8]) -> Result<(CodecParameters, u16)> {
+    let mut reader = BufReader::new(buf);
+
+    let format_tag = reader.read_u16()?;
+    let num_channels = reader.read_u16()?;
+    let sample_rate = reader.read_u32()?;
+    let _avg_bytes_per_sec = reader.read_u32()?;
+    let block_align = reader.read_u16()?;
+    let bits_per_sample = reader.read_u16()?;
+
+    let extra_data = match reader.read_u16() {
+        Ok(len) => {
+            let len = usize::from(len).min(buf.len() - 18);
+            Some(reader.read_boxed_slice_exact(len)?)
+        }
+        Err(_) => None,
+    };
+
+    let codec = match (format_tag, bits_per_sample) {
+        (0x0001, 8) => CODEC_TYPE_PCM_U8,
+        (0x0001, 16) => CODEC_TYPE_PCM_S16LE,
+        (0x0001, 24) => CODEC_TYPE_PCM_S24LE,
+        (0x0001, 32) => CODEC_TYPE_PCM_S32LE,
+        (0x0003, 32) => CODEC_TYPE_PCM_F32LE,
+        (0x0003, 64) => CODEC_TYPE_PCM_F64LE,
+        (0x0006, _) => CODEC_TYPE_PCM_ALAW,
+        (0x0007, _) => CODEC_TYPE_PCM_MULAW,
+        (0x0050, _) => CODEC_TYPE_MP2,
+        (0x0055, _) => CODEC_TYPE_MP3,
+        (0x00ff, _) | (0x1610, _) => CODEC_TYPE_AAC,
+        // Windows Media Audio versions 1 and 2. The version may be determined by the length of the
+        // extra data.
+        (0x0160, _) | (0x0161, _) => CODEC_TYPE_WMA,
+        (0x2001, _) => CODEC_TYPE_DCA,
+        _ => {
+            debug!("unsupported audio format tag {:#06x}", format_tag);
+            CODEC_TYPE_NULL
+        }
+    };
+
+    if num_channels == 0 || num_channels > 32 {
+        return decode_error("asf: invalid channel count");
+    }
+
+    if sample_rate == 0 {
+        return decode_error("asf: invalid sample rate");
+    }
+
+    let channels = match num_channels {
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        _ => Channels::from_bits_truncate(((1u64 << num_channels) - 1) as u32),
+    };
+
+    let mut params = CodecParameters::new();
+
+    params.for_codec(codec).with_sample_rate(sample_rate).with_channels(channels);
+
+    if bits_per_sample != 0 {
+        params.with_bits_per_coded_sample(u32::from(bits_per_sample));
+    }
+
+    match codec {
+        CODEC_TYPE_PCM_U8 | CODEC_TYPE_PCM_S16LE | CODEC_TYPE_PCM_S24LE | CODEC_TYPE_PCM_S32LE
+        | CODEC_TYPE_PCM_F32LE | CODEC_TYPE_PCM_F64LE => {
+            params.with_bits_per_sample(u32::from(bits_per_sample));
+        }
+        CODEC_TYPE_PCM_ALAW | CODEC_TYPE_PCM_MULAW => {
+            params.with_bits_per_sample(8);
+        }
+        _ => (),
+    }
+
+    if let Some(extra_data) = extra_data.filter(|data| !data.is_empty()) {
+        params.with_extra_data(extra_data);
+    }
+
+    Ok((params, block_align))
+}
+
+/// Read a stream properties object body. Returns `None` if the stream is not an audio stream.
+pub fn read_stream_properties<B: ReadBytes>(
+    reader: &mut B,
+    len: u64,
+) -> Result<Option<AudioStream>> {
+    let mut reader = ScopedStream::new(reader, len);
+
+    let mut stream_type = [0; 16];
+    reader.read_buf_exact(&mut stream_type)?;
+
+    let mut error_correction_type = [0; 16];
+    reader.read_buf_exact(&mut error_correction_type)?;
+
+    let _time_offset = reader.read_u64()?;
+    let type_specific_len = reader.read_u32()?;
+    let error_correction_len = reader.read_u32()?;
+    let flags = reader.read_u16()?;
+    let _reserved = reader.read_u32()?;
+
+    let type_specific = reader.read_boxed_slice_exact(type_specific_len as usize)?;
+    let error_correction = reader.read_boxed_slice_exact(error_correction_len as usize)?;
+
+    reader.ignore()?;
+
+    let stream_num = (flags & 0x7f) as u8;
+
+    if stream_type != AUDIO_MEDIA {
+        debug!("ignoring non-audio stream {}", stream_num);
+        return Ok(None);
+    }
+
+    if flags & 0x8000 != 0 {
+        return unsupported_error("asf: encrypted streams are not supported");
+    }
+
+    let (mut params, block_align) = read_wave_format(&type_specific)?;
+
+    // All timestamps are presentation times in milliseconds.
+    params.with_time_base(TimeBase::new(1, 1000));
+
+    let spread = if error_correction_type == AUDIO_SPREAD && error_correction.len() >= 5 {
+        let spread = AudioSpread {
+            span: usize::from(error_correction[0]),
+            packet_len: usize::from(u16::from_le_bytes([error_correction[1], error_correction[2]])),
+            chunk_len: usize::from(u16::from_le_bytes([error_correction[3], error_correction[4]])),
+        };
+
+        if spread.span > 1 {
+            Some(spread)
+        }
+        else {
+            None
+        }
+    }
+    else {
+        None
+    };
+
+    Ok(Some(AudioStream { stream_num, params, block_align, spread }))
+}
+
+/// Read a content description object body.
+pub fn read_content_description<B: ReadBytes>(
+    reader: &mut B,
+    builder: &mut MetadataBuilder,
+) -> Result<()> {
+    let mut lens = [0; 5];
+
+    for len in lens.iter_mut() {
+        *len = reader.read_u16()?;
+    }
+
+    let names = ["Title", "Author", "Copyright", "Description", "Rating"];
+
+    for (name, len) in names.iter().zip(lens) {
+        let buf = reader.read_boxed_slice_exact(usize::from(len))?;
+        let value = asf::decode_utf16le(&buf);
+
+        if !value.is_empty() {
+            builder.add_tag(Tag::new(asf::std_key_from_tag(name), name, Value::from(value)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Read an extended content description object body.
+pub fn read_extended_content_description<B: ReadBytes>(
+    reader: &mut B,
+    builder: &mut MetadataBuilder,
+) -> Result<()> {
+    let num_descriptors = reader.read_u16()?;
+
+    for _ in 0..num_descriptors {
+        let name_len = reader.read_u16()?;
+        let name = asf::decode_utf16le(&reader.read_boxed_slice_exact(usize::from(name_len))?);
+
+        let data_type = reader.read_u16()?;
+        let data_len = reader.read_u16()?;
+        let data = reader.read_boxed_slice_exact(usize::from(data_len))?;
+
+        asf::add_attribute(builder, &name, data_type, &data);
+    }
+
+    Ok(())
+}
+
+/// Read a metadata, or metadata library, object body.
+pub fn read_metadata<B: ReadBytes>(reader: &mut B, builder: &mut MetadataBuilder) -> Result<()> {
+    let num_records = reader.read_u16()?;
+
+    for _ in 0..num_records {
+        let _language_idx = reader.read_u16()?;
+        let _stream_num = reader.read_u16()?;
+        let name_len = reader.read_u16()?;
+        let data_type = reader.read_u16()?;
+        let data_len = reader.read_u32()?;
+
+        let name = asf::decode_utf16le(&reader.read_boxed_slice_exact(usize::from(name_len))?);
+        let data = reader.read_boxed_slice_exact(data_len as usize)?;
+
+        asf::add_attribute(builder, &name, data_type, &data);
+    }
+
+    Ok(())
+}
+
+/// Read the objects of a header extension object body.
+pub fn read_header_extension<B: ReadBytes>(
+    reader: &mut B,
+    builder: &mut MetadataBuilder,
+) -> Result<()> {
+    // Skip the reserved fields.
+    reader.ignore_bytes(18)?;
+
+    let len = u64::from(reader.read_u32()?);
+
+    let mut reader = ScopedStream::new(reader, len);
+
+    while reader.bytes_available() >= OBJECT_HEADER_LEN {
+        let header = ObjectHeader::read(&mut reader)?;
+
+        let mut object = reader.scope(header.len)?;
+
+        match header.guid {
+            METADATA_OBJECT | METADATA_LIBRARY_OBJECT => read_metadata(&mut object, builder)?,
+            _ => (),
+        }
+
+        object.ignore()?;
+    }
+
+    reader.ignore()?;
+
+    Ok(())
+}
+
+/// An entry of an index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The presentation time in milliseconds, including the preroll.
+    pub ts: u64,
+    /// The number of the first data packet to read.
+    pub packet_num: u64,
+}
+
+/// Read a simple index object body.
+pub fn read_simple_index<B: ReadBytes>(reader: &mut B) -> Result<Vec<IndexEntry>> {
+    // Skip the file ID.
+    reader.ignore_bytes(16)?;
+
+    // The time interval is in units of 100 ns.
+    let interval = reader.read_u64()? / 10_000;
+    let _max_packet_count = reader.read_u32()?;
+    let num_entries = reader.read_u32()?;
+
+    let mut entries = Vec::new();
+
+    for i in 0..u64::from(num_entries) {
+        let packet_num = u64::from(reader.read_u32()?);
+        let _packet_count = reader.read_u16()?;
+
+        entries.push(IndexEntry { ts: i * interval, packet_num });
+    }
+
+    Ok(entries)
+}
+
+/// Read an index object body.
+pub fn read_index<B: ReadBytes>(reader: &mut B, packet_len: u32) -> Result<Vec<IndexEntry>> {
+    let interval = u64::from(reader.read_u32()?);
+    let num_specifiers = usize::from(reader.read_u16()?);
+    let num_blocks = reader.read_u32()?;
+
+    if num_specifiers == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Skip the stream numbers and index types of the specifiers.
+    reader.ignore_bytes(4 * num_specifiers as u64)?;
+
+    let mut entries = Vec::new();
+
+    for _ in 0..num_blocks {
+        let num_entries = reader.read_u32()?;
+
+        let mut positions = Vec::with_capacity(num_specifiers);
+
+        for _ in 0..num_specifiers {
+            positions.push(reader.read_u64()?);
+        }
+
+        for _ in 0..num_entries {
+            // Use the earliest offset of all streams.
+            let mut offset = u64::MAX;
+
+            for position in &positions {
+                offset = offset.min(position + u64::from(reader.read_u32()?));
+            }
+
+            let ts = entries.len() as u64 * interval;
+
+            entries.push(IndexEntry { ts, packet_num: offset / u64::from(packet_len) });
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_wave_format() {
+        // A stereo 44.1 kHz WMA version 2 stream.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&0x0161u16.to_le_bytes());
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        buf.extend_from_slice(&44100u32.to_le_bytes());
+        buf.extend_from_slice(&16000u32.to_le_bytes());
+        buf.extend_from_slice(&2973u16.to_le_bytes());
+        buf.extend_from_slice(&16u16.to_le_bytes());
+        buf.extend_from_slice(&10u16.to_le_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 0, 0x17, 0, 0, 0, 0, 0]);
+
+        let (params, block_align) = read_wave_format(&buf).unwrap();
+
+        assert_eq!(params.codec, CODEC_TYPE_WMA);
+        assert_eq!(params.sample_rate, Some(44100));
+        assert_eq!(block_align, 2973);
+        assert_eq!(params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+        assert_eq!(params.extra_data.as_deref().map(|data| data.len()), Some(10));
+    }
+
+    #[test]
+    fn verify_descramble() {
+        // A span of 2, with 2 chunks of 2 bytes per packet.
+        let spread = AudioSpread { span: 2, packet_len: 4, chunk_len: 2 };
+
+        let data = spread.descramble(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert_eq!(&data[..], &[0, 1, 4, 5, 2, 3, 6, 7]);
+
+        assert!(spread.descramble(&[0, 1, 2, 3]).is_none());
+    }
+}
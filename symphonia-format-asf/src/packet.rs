@@ -0,0 +1,220 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::ops::Range;
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+/// A payload of a data packet, containing all or part of a media object.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Payload {
+    pub stream_num: u8,
+    pub object_num: u32,
+    /// The offset of the payload into the media object.
+    pub offset: u32,
+    /// The length of the media object, or 0 if unknown.
+    pub object_len: u32,
+    /// The presentation time of the media object in milliseconds.
+    pub pres_time: u32,
+    /// The range of the data packet containing the payload data.
+    pub data: Range<usize>,
+}
+
+/// A data packet.
+pub struct DataPacket {
+    /// The send time of the data packet in milliseconds.
+    pub send_time: u32,
+    pub payloads: Vec<Payload>,
+}
+
+/// Read a field with a length given by a 2-bit length type.
+fn read_field<B: ReadBytes>(reader: &mut B, len_type: u8) -> Result<u32> {
+    match len_type & 0x3 {
+        0 => Ok(0),
+        1 => Ok(u32::from(reader.read_u8()?)),
+        2 => Ok(u32::from(reader.read_u16()?)),
+        _ => Ok(reader.read_u32()?),
+    }
+}
+
+/// Read a data packet.
+pub fn read_data_packet(buf: &[u8]) -> Result<DataPacket> {
+    let mut reader = BufReader::new(buf);
+
+    let mut flags = reader.read_u8()?;
+
+    // The error correction data, if present, precedes the payload parsing information.
+    if flags & 0x80 != 0 {
+        reader.ignore_bytes(u64::from(flags & 0xf))?;
+        flags = reader.read_u8()?;
+    }
+
+    let property_flags = reader.read_u8()?;
+
+    let packet_len = read_field(&mut reader, flags >> 5)? as usize;
+    let _sequence = read_field(&mut reader, flags >> 1)?;
+    let padding_len = read_field(&mut reader, flags >> 3)? as usize;
+
+    let send_time = reader.read_u32()?;
+    let _duration = reader.read_u16()?;
+
+    // A packet shorter than the fixed length is padded.
+    let packet_len = if packet_len > 0 { packet_len.min(buf.len()) } else { buf.len() };
+
+    let end = match packet_len.checked_sub(padding_len) {
+        Some(end) if end >= reader.pos() as usize => end,
+        _ => return decode_error("asf: invalid padding length"),
+    };
+
+    let replicated_len_type = property_flags;
+    let offset_len_type = property_flags >> 2;
+    let object_num_len_type = property_flags >> 4;
+
+    let (num_payloads, payload_len_type) = if flags & 0x1 != 0 {
+        let payload_flags = reader.read_u8()?;
+        (payload_flags & 0x3f, Some(payload_flags >> 6))
+    }
+    else {
+        (1, None)
+    };
+
+    let mut payloads = Vec::new();
+
+    for _ in 0..num_payloads {
+        let stream_num = reader.read_u8()? & 0x7f;
+        let object_num = read_field(&mut reader, object_num_len_type)?;
+        let offset = read_field(&mut reader, offset_len_type)?;
+        let replicated_len = read_field(&mut reader, replicated_len_type)?;
+
+        // A replicated data length of 1 signals compressed payload data, consisting of many
+        // small media objects, and the offset is then the presentation time.
+        let (object_len, pres_time, pres_time_delta) = match replicated_len {
+            1 => (0, offset, Some(reader.read_u8()?)),
+            0 => (0, 0, None),
+            _ => {
+                if replicated_len < 8 {
+                    return decode_error("asf: invalid replicated data length");
+                }
+
+                let object_len = reader.read_u32()?;
+                let pres_time = reader.read_u32()?;
+
+                reader.ignore_bytes(u64::from(replicated_len) - 8)?;
+
+                (object_len, pres_time, None)
+            }
+        };
+
+        let len = match payload_len_type {
+            Some(len_type) => read_field(&mut reader, len_type)? as usize,
+            None => end.saturating_sub(reader.pos() as usize),
+        };
+
+        let start = reader.pos() as usize;
+
+        if start + len > end {
+            return decode_error("asf: invalid payload length");
+        }
+
+        match pres_time_delta {
+            Some(delta) => {
+                // Each sub-payload is a complete media object, preceded by its length.
+                let mut pos = start;
+                let mut object_num = object_num;
+                let mut pres_time = pres_time;
+
+                while pos < start + len {
+                    let sub_len = usize::from(buf[pos]);
+
+                    if pos + 1 + sub_len > start + len {
+                        return decode_error("asf: invalid sub-payload length");
+                    }
+
+                    payloads.push(Payload {
+                        stream_num,
+                        object_num,
+                        offset: 0,
+                        object_len: sub_len as u32,
+                        pres_time,
+                        data: pos + 1..pos + 1 + sub_len,
+                    });
+
+                    pos += 1 + sub_len;
+                    object_num = object_num.wrapping_add(1);
+                    pres_time = pres_time.wrapping_add(u32::from(delta));
+                }
+            }
+            None => {
+                payloads.push(Payload {
+                    stream_num,
+                    object_num,
+                    offset,
+                    object_len,
+                    pres_time,
+                    data: start..start + len,
+                });
+            }
+        }
+
+        reader.ignore_bytes(len as u64)?;
+    }
+
+    Ok(DataPacket { send_time, payloads })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_data_packet() {
+        // A packet with error correction data, a padding length byte, and multiple payloads with
+        // byte length fields.
+        let mut buf = vec![0x82, 0, 0, 0x09, 0x55];
+        buf.push(8);
+        buf.extend_from_slice(&1000u32.to_le_bytes());
+        buf.extend_from_slice(&100u16.to_le_bytes());
+        buf.push(0x42);
+
+        // The first payload is the end of a media object of 10 bytes.
+        buf.extend_from_slice(&[0x81, 3, 6, 8]);
+        buf.extend_from_slice(&10u32.to_le_bytes());
+        buf.extend_from_slice(&1500u32.to_le_bytes());
+        buf.push(4);
+        buf.extend_from_slice(&[6, 7, 8, 9]);
+
+        // The second payload is compressed, containing two media objects.
+        buf.extend_from_slice(&[1, 4, 200, 1, 50, 5, 2, 1, 2, 1, 3]);
+        buf.extend_from_slice(&[0; 8]);
+
+        let packet = read_data_packet(&buf).unwrap();
+
+        assert_eq!(packet.send_time, 1000);
+        assert_eq!(packet.payloads.len(), 3);
+        assert_eq!(
+            packet.payloads[0],
+            Payload {
+                stream_num: 1,
+                object_num: 3,
+                offset: 6,
+                object_len: 10,
+                pres_time: 1500,
+                data: 26..30,
+            }
+        );
+        assert_eq!(packet.payloads[1].pres_time, 200);
+        assert_eq!(packet.payloads[1].data, 37..39);
+        assert_eq!(packet.payloads[2].object_num, 5);
+        assert_eq!(packet.payloads[2].pres_time, 250);
+        assert_eq!(packet.payloads[2].data, 40..41);
+
+        // The payloads may not extend into the padding.
+        buf[5] = 9;
+        assert!(read_data_packet(&buf).is_err());
+    }
+}
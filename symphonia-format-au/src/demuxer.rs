@@ -0,0 +1,392 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, StandardTagKey, Tag, Value};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+/// The length of the fixed part of the header.
+const HEADER_LEN: usize = 24;
+
+/// The data size of a stream of unknown length.
+const UNKNOWN_DATA_SIZE: u32 = 0xffff_ffff;
+
+/// The maximum length of an annotation that will be read. Longer annotations are skipped.
+const MAX_ANNOTATION_LEN: u64 = 64 * 1024;
+
+/// The maximum number of frames in a packet.
+const MAX_FRAMES_PER_PACKET: u64 = 1152;
+
+/// Sun/NeXT Audio (AU) format reader.
+///
+/// `AuReader` implements a demuxer for AU (SND) files containing linear PCM, floating point PCM,
+/// µ-law, or A-law audio. The annotation of the header is exposed as metadata.
+pub struct AuReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    data_start_pos: u64,
+    /// The position of the end of the audio data, or `u64::MAX` if unknown.
+    data_end_pos: u64,
+    /// The length of a frame in bytes.
+    frame_len: u64,
+}
+
+impl QueryDescriptor for AuReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("au", "Sun/NeXT Audio", &["au", "snd"], &["audio/basic"], &[b".snd"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// The stream described by an AU header.
+struct StreamInfo {
+    params: CodecParameters,
+    data_offset: u64,
+    data_size: Option<u64>,
+    frame_len: u64,
+}
+
+/// Read the stream information from an AU header.
+fn read_au_header(buf: &[u8; HEADER_LEN]) -> Result<StreamInfo> {
+    let field = |i: usize| u32::from_be_bytes([buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]);
+
+    if &buf[..4] != b".snd" {
+        return decode_error("au: missing .snd marker");
+    }
+
+    let data_offset = field(4);
+    let data_size = field(8);
+    let encoding = field(12);
+    let sample_rate = field(16);
+    let num_channels = field(20);
+
+    if (data_offset as usize) < HEADER_LEN {
+        return decode_error("au: invalid data offset");
+    }
+
+    if sample_rate == 0 {
+        return decode_error("au: invalid sample rate");
+    }
+
+    let channels = match num_channels {
+        0 => return decode_error("au: invalid channel count"),
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3..=32 => Channels::from_bits_truncate(((1u64 << num_channels) - 1) as u32),
+        _ => return unsupported_error("au: too many channels"),
+    };
+
+    let (codec, bits_per_sample) = match encoding {
+        1 => (CODEC_TYPE_PCM_MULAW, 8),
+        2 => (CODEC_TYPE_PCM_S8, 8),
+        3 => (CODEC_TYPE_PCM_S16BE, 16),
+        4 => (CODEC_TYPE_PCM_S24BE, 24),
+        5 => (CODEC_TYPE_PCM_S32BE, 32),
+        6 => (CODEC_TYPE_PCM_F32BE, 32),
+        7 => (CODEC_TYPE_PCM_F64BE, 64),
+        27 => (CODEC_TYPE_PCM_ALAW, 8),
+        _ => return unsupported_error("au: unsupported encoding"),
+    };
+
+    let mut params = CodecParameters::new();
+
+    params
+        .for_codec(codec)
+        .with_sample_rate(sample_rate)
+        .with_time_base(TimeBase::new(1, sample_rate))
+        .with_channels(channels)
+        .with_max_frames_per_packet(MAX_FRAMES_PER_PACKET)
+        .with_frames_per_block(1);
+
+    // The bits per sample of the companded codecs is implicit.
+    if codec != CODEC_TYPE_PCM_MULAW && codec != CODEC_TYPE_PCM_ALAW {
+        params.with_bits_per_sample(bits_per_sample).with_bits_per_coded_sample(bits_per_sample);
+    }
+
+    let data_size = match data_size {
+        UNKNOWN_DATA_SIZE => None,
+        data_size => Some(u64::from(data_size)),
+    };
+
+    let frame_len = u64::from(bits_per_sample / 8) * u64::from(num_channels);
+
+    Ok(StreamInfo { params, data_offset: u64::from(data_offset), data_size, frame_len })
+}
+
+/// Read the annotation of the header. If the annotation is a list of `key=value` entries, each
+/// entry becomes a tag. Otherwise, the annotation is a comment.
+fn read_annotation(buf: &[u8]) -> Vec<Tag> {
+    // The annotation is null-terminated, and may be padded with further nulls.
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+
+    let text = String::from_utf8_lossy(&buf[..len]);
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let entries: Vec<&str> =
+        text.split(|c| c == '\n' || c == ';').map(str::trim).filter(|e| !e.is_empty()).collect();
+
+    let is_key_value = entries.iter().all(|entry| match entry.split_once('=') {
+        Some((key, _)) => !key.is_empty() && !key.contains(char::is_whitespace),
+        None => false,
+    });
+
+    if !is_key_value {
+        return vec![Tag::new(Some(StandardTagKey::Comment), "", Value::from(text))];
+    }
+
+    entries
+        .iter()
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| Tag::new(annotation_key_to_std_key(key), key, Value::from(value)))
+        .collect()
+}
+
+/// Maps the keys of an annotation to standard tag keys.
+fn annotation_key_to_std_key(key: &str) -> Option<StandardTagKey> {
+    let std_key = match key.to_ascii_lowercase().as_str() {
+        "album" => StandardTagKey::Album,
+        "artist" => StandardTagKey::Artist,
+        "comment" => StandardTagKey::Comment,
+        "copyright" => StandardTagKey::Copyright,
+        "date" => StandardTagKey::Date,
+        "genre" => StandardTagKey::Genre,
+        "title" => StandardTagKey::TrackTitle,
+        "track" => StandardTagKey::TrackNumber,
+        _ => return None,
+    };
+
+    Some(std_key)
+}
+
+impl FormatReader for AuReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut header = [0; HEADER_LEN];
+        source.read_buf_exact(&mut header)?;
+
+        let StreamInfo { mut params, data_offset, data_size, frame_len } = read_au_header(&header)?;
+
+        let mut metadata: MetadataLog = Default::default();
+
+        // The annotation fills the remainder of the header up to the audio data.
+        let annotation_len = data_offset - HEADER_LEN as u64;
+
+        if annotation_len > MAX_ANNOTATION_LEN {
+            warn!("skipping annotation of {} bytes", annotation_len);
+            source.ignore_bytes(annotation_len)?;
+        }
+        else if annotation_len > 0 {
+            let annotation = source.read_boxed_slice_exact(annotation_len as usize)?;
+
+            let tags = read_annotation(&annotation);
+
+            if !tags.is_empty() {
+                let mut builder = MetadataBuilder::new();
+
+                for tag in tags {
+                    builder.add_tag(tag);
+                }
+
+                metadata.push(builder.metadata());
+            }
+        }
+
+        let data_start_pos = source.pos();
+
+        // The data size may be unknown, or exceed the length of a truncated file.
+        let data_end_pos = match (data_size, source.byte_len()) {
+            (Some(data_size), Some(byte_len)) => byte_len.min(data_start_pos + data_size),
+            (Some(data_size), None) => data_start_pos + data_size,
+            (None, Some(byte_len)) => byte_len,
+            (None, None) => u64::MAX,
+        };
+
+        if data_end_pos != u64::MAX {
+            params.with_n_frames(data_end_pos.saturating_sub(data_start_pos) / frame_len);
+        }
+
+        Ok(AuReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            data_start_pos,
+            data_end_pos,
+            frame_len,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let pos = self.reader.pos();
+
+        let num_frames_left = self.data_end_pos.saturating_sub(pos) / self.frame_len;
+
+        let max_len = (num_frames_left.min(MAX_FRAMES_PER_PACKET) * self.frame_len) as usize;
+
+        if max_len == 0 {
+            return end_of_stream_error();
+        }
+
+        let buf = if self.data_end_pos == u64::MAX {
+            // If the length of the audio data is unknown, the stream ends wherever the media
+            // source ends, so the final packet may be shorter than requested.
+            let mut buf = vec![0; max_len];
+            let mut len = 0;
+
+            while len < max_len {
+                match self.reader.read_buf(&mut buf[len..]) {
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                    Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+
+            buf.truncate(len - len % self.frame_len as usize);
+
+            if buf.is_empty() {
+                return end_of_stream_error();
+            }
+
+            buf.into_boxed_slice()
+        }
+        else {
+            self.reader.read_boxed_slice_exact(max_len)?
+        };
+
+        let ts = (pos - self.data_start_pos) / self.frame_len;
+        let dur = buf.len() as u64 / self.frame_len;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // AU is not packetized. Seek to a packet boundary so that packets have the same timestamps
+        // regardless if the stream was seeked or not.
+        let actual_ts = required_ts / MAX_FRAMES_PER_PACKET * MAX_FRAMES_PER_PACKET;
+
+        let seek_pos = self.data_start_pos + actual_ts * self.frame_len;
+
+        if self.reader.is_seekable() {
+            self.reader.seek(SeekFrom::Start(seek_pos))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let current_pos = self.reader.pos();
+
+            if seek_pos < current_pos {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes(seek_pos - current_pos)?;
+        }
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_au_header() {
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(b".snd");
+
+        // A µ-law mono stream at 8 kHz, with a 32 byte header and unknown data size.
+        for (i, field) in [32, UNKNOWN_DATA_SIZE, 1, 8000, 1].iter().enumerate() {
+            header[4 + 4 * i..8 + 4 * i].copy_from_slice(&field.to_be_bytes());
+        }
+
+        let info = read_au_header(&header).unwrap();
+
+        assert_eq!(info.params.codec, CODEC_TYPE_PCM_MULAW);
+        assert_eq!(info.params.sample_rate, Some(8000));
+        assert_eq!(info.data_offset, 32);
+        assert_eq!(info.data_size, None);
+        assert_eq!(info.frame_len, 1);
+
+        // G.721 ADPCM is not supported.
+        header[12..16].copy_from_slice(&23u32.to_be_bytes());
+        assert!(read_au_header(&header).is_err());
+    }
+
+    #[test]
+    fn verify_read_annotation() {
+        let tags = read_annotation(b"title=Tone\nartist=Test;track=1\0\0\0");
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[2].value.to_string(), "1");
+
+        let tags = read_annotation(b"Recorded in the lab\0");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::Comment));
+    }
+}
@@ -0,0 +1,244 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+use symphonia_utils_mpeg::frame::EsCodec;
+
+use log::debug;
+
+/// The length of an entry of the legacy index.
+const INDEX_ENTRY_LEN: usize = 16;
+
+/// Gets the stream number of a chunk of the `movi` list, or an entry of the legacy index, from
+/// the chunk ID. The stream number is encoded by the first two characters of the ID in decimal.
+pub fn stream_number(id: [u8; 4]) -> Option<u32> {
+    if id[0].is_ascii_digit() && id[1].is_ascii_digit() {
+        Some(u32::from(id[0] - b'0') * 10 + u32::from(id[1] - b'0'))
+    }
+    else {
+        None
+    }
+}
+
+/// A stream header (`strh`) chunk.
+#[derive(Copy, Clone, Debug)]
+pub struct StreamHeader {
+    pub fcc_type: [u8; 4],
+    /// The time base of the stream is `scale / rate` seconds.
+    pub scale: u32,
+    pub rate: u32,
+    /// The start time of the stream in units of the time base.
+    pub start: u32,
+    /// The length of the stream in units of the time base.
+    pub length: u32,
+    /// The length of a sample in bytes, or 0 if every chunk is one sample.
+    pub sample_size: u32,
+}
+
+impl StreamHeader {
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let mut reader = BufReader::new(buf);
+
+        let fcc_type = reader.read_quad_bytes()?;
+        let _fcc_handler = reader.read_quad_bytes()?;
+        let _flags = reader.read_u32()?;
+        let _priority = reader.read_u16()?;
+        let _language = reader.read_u16()?;
+        let _initial_frames = reader.read_u32()?;
+        let scale = reader.read_u32()?;
+        let rate = reader.read_u32()?;
+        let start = reader.read_u32()?;
+        let length = reader.read_u32()?;
+        let _suggested_buffer_size = reader.read_u32()?;
+        let _quality = reader.read_u32()?;
+        let sample_size = reader.read_u32()?;
+
+        if scale == 0 || rate == 0 {
+            return decode_error("avi: invalid stream time base");
+        }
+
+        Ok(StreamHeader { fcc_type, scale, rate, start, length, sample_size })
+    }
+
+    /// Converts a number of units of the time base to a number of samples at the sample rate.
+    pub fn units_to_ts(&self, units: u64, sample_rate: u32) -> u64 {
+        let ts = u128::from(units) * u128::from(self.scale) * u128::from(sample_rate)
+            / u128::from(self.rate);
+        ts as u64
+    }
+}
+
+/// The packetization of the data of an audio stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Blocks of PCM samples of the given length in bytes.
+    Pcm(u16),
+    /// An elementary stream of frames that may span chunks.
+    Frames(EsCodec),
+    /// One raw AAC frame per chunk.
+    Aac,
+}
+
+/// The format of an audio stream, read from a stream format (`strf`) chunk.
+pub struct WaveFormat {
+    pub params: CodecParameters,
+    pub framing: Framing,
+}
+
+/// Reads a WAVEFORMATEX, or WAVEFORMATEXTENSIBLE, structure. Returns `None` if the codec is not
+/// supported.
+pub fn read_wave_format(buf: &[u8]) -> Result<Option<WaveFormat>> {
+    let mut reader = BufReader::new(buf);
+
+    let mut format_tag = reader.read_u16()?;
+    let num_channels = reader.read_u16()?;
+    let sample_rate = reader.read_u32()?;
+    let _avg_bytes_per_sec = reader.read_u32()?;
+    let block_align = reader.read_u16()?;
+
+    // The bits per sample field is absent in the older WAVEFORMAT structure.
+    let bits_per_sample = reader.read_u16().unwrap_or(0);
+
+    let extra_data = match reader.read_u16() {
+        Ok(len) => Some(reader.read_boxed_slice_exact(usize::from(len).min(buf.len() - 18))?),
+        Err(_) => None,
+    };
+
+    if num_channels == 0 || num_channels > 32 {
+        return decode_error("avi: invalid channel count");
+    }
+
+    if sample_rate == 0 {
+        return decode_error("avi: invalid sample rate");
+    }
+
+    let mut channels = match num_channels {
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        _ => Channels::from_bits_truncate(((1u64 << num_channels) - 1) as u32),
+    };
+
+    // WAVEFORMATEXTENSIBLE stores the actual format tag in the first two bytes of the sub-format
+    // GUID, and the channel mask.
+    if format_tag == 0xfffe {
+        match extra_data.as_deref() {
+            Some(ext) if ext.len() >= 22 => {
+                let mask = u32::from_le_bytes([ext[2], ext[3], ext[4], ext[5]]);
+
+                if mask.count_ones() == u32::from(num_channels) {
+                    channels = Channels::from_bits_truncate(mask);
+                }
+
+                format_tag = u16::from_le_bytes([ext[6], ext[7]]);
+            }
+            _ => return decode_error("avi: invalid extensible wave format"),
+        }
+    }
+
+    let (codec, framing) = match (format_tag, bits_per_sample) {
+        (0x0001, 8) => (CODEC_TYPE_PCM_U8, Framing::Pcm(block_align)),
+        (0x0001, 16) => (CODEC_TYPE_PCM_S16LE, Framing::Pcm(block_align)),
+        (0x0001, 24) => (CODEC_TYPE_PCM_S24LE, Framing::Pcm(block_align)),
+        (0x0001, 32) => (CODEC_TYPE_PCM_S32LE, Framing::Pcm(block_align)),
+        (0x0003, 32) => (CODEC_TYPE_PCM_F32LE, Framing::Pcm(block_align)),
+        (0x0003, 64) => (CODEC_TYPE_PCM_F64LE, Framing::Pcm(block_align)),
+        (0x0006, 8) => (CODEC_TYPE_PCM_ALAW, Framing::Pcm(block_align)),
+        (0x0007, 8) => (CODEC_TYPE_PCM_MULAW, Framing::Pcm(block_align)),
+        (0x0050, _) => (CODEC_TYPE_MP2, Framing::Frames(EsCodec::Mpa)),
+        (0x0055, _) => (CODEC_TYPE_MP3, Framing::Frames(EsCodec::Mpa)),
+        (0x00ff, _) => (CODEC_TYPE_AAC, Framing::Aac),
+        (0x2000, _) => (CODEC_TYPE_EAC3, Framing::Frames(EsCodec::Ac3)),
+        (0x2001, _) => (CODEC_TYPE_DCA, Framing::Frames(EsCodec::Dts)),
+        _ => {
+            debug!("unsupported audio format tag {:#06x}", format_tag);
+            return Ok(None);
+        }
+    };
+
+    if let Framing::Pcm(block_align) = framing {
+        if u32::from(block_align) != u32::from(num_channels) * u32::from(bits_per_sample) / 8 {
+            return decode_error("avi: invalid pcm block alignment");
+        }
+    }
+
+    let mut params = CodecParameters::new();
+
+    params.for_codec(codec).with_sample_rate(sample_rate).with_channels(channels);
+
+    if let Framing::Pcm(_) = framing {
+        params
+            .with_bits_per_sample(u32::from(bits_per_sample))
+            .with_bits_per_coded_sample(u32::from(bits_per_sample))
+            .with_frames_per_block(1);
+    }
+
+    if let Framing::Aac = framing {
+        if let Some(extra_data) = extra_data.filter(|data| !data.is_empty()) {
+            params.with_extra_data(extra_data);
+        }
+    }
+
+    Ok(Some(WaveFormat { params, framing }))
+}
+
+/// An entry of the legacy index (`idx1`) chunk.
+#[derive(Copy, Clone, Debug)]
+pub struct IndexEntry {
+    pub id: [u8; 4],
+    /// The offset of the chunk header, relative to either the start of the file, or the `movi`
+    /// list type.
+    pub offset: u32,
+    pub len: u32,
+}
+
+/// Reads the entries of the legacy index chunk.
+pub fn read_index(buf: &[u8]) -> Vec<IndexEntry> {
+    buf.chunks_exact(INDEX_ENTRY_LEN)
+        .map(|entry| IndexEntry {
+            id: [entry[0], entry[1], entry[2], entry[3]],
+            offset: u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]),
+            len: u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_wave_format() {
+        // A stereo 48 kHz 24-bit WAVEFORMATEXTENSIBLE stream with a channel mask.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&0xfffeu16.to_le_bytes());
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        buf.extend_from_slice(&48000u32.to_le_bytes());
+        buf.extend_from_slice(&288000u32.to_le_bytes());
+        buf.extend_from_slice(&6u16.to_le_bytes());
+        buf.extend_from_slice(&24u16.to_le_bytes());
+        buf.extend_from_slice(&22u16.to_le_bytes());
+        buf.extend_from_slice(&24u16.to_le_bytes());
+        buf.extend_from_slice(&0x3u32.to_le_bytes());
+        buf.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00]);
+        buf.extend_from_slice(&[0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71]);
+
+        let format = read_wave_format(&buf).unwrap().unwrap();
+
+        assert_eq!(format.framing, Framing::Pcm(6));
+        assert_eq!(format.params.codec, CODEC_TYPE_PCM_S24LE);
+        assert_eq!(format.params.sample_rate, Some(48000));
+        assert_eq!(format.params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+
+        // An unsupported codec.
+        buf[..2].copy_from_slice(&0x0161u16.to_le_bytes());
+        assert!(read_wave_format(&buf).unwrap().is_none());
+    }
+}
@@ -0,0 +1,695 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{self, Seek, SeekFrom};
+
+use symphonia_core::codecs::CodecParameters;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_format_riff::chunks::{sub_chunks, ByteOrder, ChunkHeader};
+use symphonia_metadata::riff;
+use symphonia_utils_mpeg::frame::{read_aac_config, Frame, FrameParser};
+
+use log::{debug, info, warn};
+
+use crate::chunks::{read_index, read_wave_format, stream_number, Framing};
+use crate::chunks::{IndexEntry, StreamHeader};
+
+/// The maximum length of the header list.
+const MAX_HEADER_LIST_LEN: u32 = 16 * 1024 * 1024;
+
+/// The maximum length of the legacy index.
+const MAX_INDEX_LEN: u32 = 256 * 1024 * 1024;
+
+/// The maximum number of bytes of the `movi` list to read to find the codec parameters of streams
+/// that are determined from their frames.
+const MAX_PROBE_LEN: u64 = 1024 * 1024;
+
+/// The maximum number of samples per channel in a packet of PCM samples.
+const MAX_PCM_FRAMES_PER_PACKET: u64 = 4096;
+
+/// Converts an unexpected end of stream into `None`.
+fn or_eof<T>(result: io::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A point of the index of a stream.
+struct IndexPoint {
+    /// The position of the chunk header.
+    pos: u64,
+    /// The number of bytes, and chunks, of the stream preceding the chunk.
+    bytes: u64,
+    chunks: u64,
+}
+
+/// The parser of the chunks of an audio stream.
+enum Parser {
+    /// PCM samples, the length of a block, and the samples of an incomplete packet.
+    Pcm(u16, Vec<u8>),
+    Frames(FrameParser),
+    /// Raw AAC frames, and the number of samples per channel in a frame.
+    Aac(u64),
+}
+
+/// The state of an audio stream.
+struct AudioStream {
+    number: u32,
+    header: StreamHeader,
+    params: CodecParameters,
+    parser: Parser,
+    sample_rate: u32,
+    /// The number of bytes, and chunks, of the stream read.
+    bytes: u64,
+    chunks: u64,
+    /// The timestamp of the next packet.
+    next_ts: u64,
+    /// If true, the timestamp of the next packet is taken from the position of its chunk.
+    resync: bool,
+    index: Vec<IndexPoint>,
+}
+
+impl AudioStream {
+    fn new(number: u32, header: StreamHeader, params: CodecParameters, framing: Framing) -> Self {
+        let parser = match framing {
+            Framing::Pcm(block_align) => Parser::Pcm(block_align, Vec::new()),
+            Framing::Frames(codec) => Parser::Frames(FrameParser::new(codec)),
+            Framing::Aac => {
+                let frame_len = match params.extra_data.as_deref().map(read_aac_config) {
+                    Some(Ok((_, _, frame_len))) => frame_len,
+                    _ => 1024,
+                };
+                Parser::Aac(frame_len)
+            }
+        };
+
+        AudioStream {
+            number,
+            header,
+            sample_rate: params.sample_rate.unwrap(),
+            params,
+            parser,
+            bytes: 0,
+            chunks: 0,
+            next_ts: 0,
+            resync: true,
+            index: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        match &mut self.parser {
+            Parser::Pcm(_, buf) => buf.clear(),
+            Parser::Frames(parser) => parser.reset(),
+            Parser::Aac(_) => (),
+        }
+        self.resync = true;
+    }
+
+    /// Returns true if the codec parameters of the stream are known.
+    fn is_ready(&self) -> bool {
+        match &self.parser {
+            Parser::Frames(parser) => parser.info.is_some(),
+            _ => true,
+        }
+    }
+
+    /// Gets the timestamp of the given number of bytes, and chunks, of the stream.
+    fn position_ts(&self, bytes: u64, chunks: u64) -> u64 {
+        let units = match self.header.sample_size {
+            0 => chunks,
+            sample_size => bytes / u64::from(sample_size),
+        };
+
+        self.header.units_to_ts(units + u64::from(self.header.start), self.sample_rate)
+    }
+
+    /// Adds the data of a chunk, and queues the frames completed by it.
+    fn push(&mut self, data: &[u8], frames: &mut VecDeque<(u32, Frame)>) -> Result<()> {
+        let pts = self.position_ts(self.bytes, self.chunks);
+
+        match &mut self.parser {
+            Parser::Pcm(block_align, buf) => {
+                let block_align = usize::from(*block_align);
+
+                // The timestamp applies to the first sample of the chunk, but the buffer may start
+                // with samples of the previous chunk.
+                let mut ts = pts.saturating_sub((buf.len() / block_align) as u64);
+
+                buf.extend_from_slice(data);
+
+                let max_len = MAX_PCM_FRAMES_PER_PACKET as usize * block_align;
+
+                while buf.len() >= block_align {
+                    let len = (buf.len() - buf.len() % block_align).min(max_len);
+                    let dur = (len / block_align) as u64;
+
+                    let frame = Frame { data: buf.drain(..len).collect(), pts: Some(ts), dur };
+                    frames.push_back((self.number, frame));
+
+                    ts += dur;
+                }
+            }
+            Parser::Frames(parser) => {
+                parser.push(data, Some(pts));
+
+                while let Some(frame) = parser.next_frame(false)? {
+                    frames.push_back((self.number, frame));
+                }
+            }
+            Parser::Aac(frame_len) => {
+                let frame = Frame { data: Box::from(data), pts: Some(pts), dur: *frame_len };
+                frames.push_back((self.number, frame));
+            }
+        }
+
+        self.bytes += data.len() as u64;
+        self.chunks += 1;
+
+        Ok(())
+    }
+
+    /// Queues the remaining frames at the end of the stream.
+    fn flush(&mut self, frames: &mut VecDeque<(u32, Frame)>) -> Result<()> {
+        match &mut self.parser {
+            Parser::Pcm(_, buf) if !buf.is_empty() => {
+                warn!("avi: discarding {} bytes of an incomplete pcm block", buf.len());
+                buf.clear();
+            }
+            Parser::Frames(parser) => {
+                while let Some(frame) = parser.next_frame(true)? {
+                    frames.push_back((self.number, frame));
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Gets the timestamp of a frame. Frames may span chunks, therefore, the timestamp of the
+    /// chunk is only used after a discontinuity.
+    fn timestamp(&self, frame: &Frame) -> u64 {
+        match frame.pts {
+            Some(pts) if self.resync => pts,
+            _ => self.next_ts,
+        }
+    }
+
+    /// Gets the packet of a frame.
+    fn packet(&mut self, frame: Frame) -> Packet {
+        let ts = self.timestamp(&frame);
+
+        if frame.pts.is_some() {
+            self.resync = false;
+        }
+
+        self.next_ts = ts + frame.dur;
+
+        Packet::new_from_boxed_slice(self.number, ts, frame.dur, frame.data)
+    }
+}
+
+/// Audio Video Interleave (AVI) format reader.
+///
+/// `AviReader` implements a demuxer for the audio streams of an AVI file, including OpenDML files
+/// larger than 1 GiB. PCM, MPEG audio, AAC, AC-3, and DTS streams are supported. Each audio stream
+/// is exposed as a track with a track ID equal to its stream number, and a time base of one
+/// sample. Seeking uses the legacy index (`idx1`) if present.
+pub struct AviReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    streams: Vec<AudioStream>,
+    /// The buffer of a chunk.
+    buf: Vec<u8>,
+    /// The position of the first chunk of the `movi` list.
+    movi_pos: u64,
+    /// The frames read, but not yet returned, and the numbers of their streams.
+    frames: VecDeque<(u32, Frame)>,
+}
+
+impl QueryDescriptor for AviReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "avi",
+            "Audio Video Interleave",
+            &["avi", "divx"],
+            &["video/avi", "video/msvideo", "video/x-msvideo"],
+            &[b"RIFF"]
+        )]
+    }
+
+    fn score(context: &[u8]) -> u8 {
+        // The RIFF marker is shared with WAVE files.
+        if context.len() >= 12 && &context[8..12] == b"AVI " {
+            255
+        }
+        else {
+            0
+        }
+    }
+}
+
+impl AviReader {
+    /// Reads the next chunk, and queues the frames completed by it. Returns false at the end of
+    /// the stream.
+    fn read_chunk(&mut self) -> Result<bool> {
+        let header = match or_eof(ChunkHeader::read(&mut self.reader, ByteOrder::LittleEndian))? {
+            Some(header) => header,
+            None => return Ok(false),
+        };
+
+        if &header.id == b"RIFF" || &header.id == b"LIST" {
+            let list_type = match or_eof(self.reader.read_quad_bytes())? {
+                Some(list_type) => list_type,
+                None => return Ok(false),
+            };
+
+            // The streams are stored in the movi lists of the first RIFF chunk, and the AVIX RIFF
+            // chunks that extend OpenDML files, and in rec lists within them.
+            if !matches!(&list_type, b"AVIX" | b"movi" | b"rec ") {
+                let len = header.padded_len().saturating_sub(4);
+                return Ok(or_eof(self.reader.ignore_bytes(len))?.is_some());
+            }
+
+            return Ok(true);
+        }
+
+        let stream_idx = stream_number(header.id)
+            .and_then(|number| self.streams.iter().position(|stream| stream.number == number));
+
+        let stream_idx = match stream_idx {
+            Some(stream_idx) => stream_idx,
+            None => return Ok(or_eof(self.reader.ignore_bytes(header.padded_len()))?.is_some()),
+        };
+
+        self.buf.resize(header.len as usize, 0);
+
+        // A chunk cut short by the end of the stream is discarded.
+        if or_eof(self.reader.read_buf_exact(&mut self.buf))?.is_none() {
+            return Ok(false);
+        }
+
+        if let Err(err) = self.streams[stream_idx].push(&self.buf, &mut self.frames) {
+            warn!("{}", err);
+        }
+
+        if header.len & 1 == 1 && or_eof(self.reader.read_u8())?.is_none() {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Gets the next frame, or `None` at the end of the stream.
+    fn next_frame(&mut self) -> Result<Option<(u32, Frame)>> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+
+            if !self.read_chunk()? {
+                // Any frames remaining at the end of the stream are complete.
+                for stream in self.streams.iter_mut() {
+                    stream.flush(&mut self.frames)?;
+                }
+
+                if self.frames.is_empty() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Reads the legacy index following the `movi` list, if the stream is seekable.
+    fn read_index(&mut self, movi_end: u64) -> Result<()> {
+        if !self.reader.is_seekable() {
+            return Ok(());
+        }
+
+        self.reader.seek(SeekFrom::Start(movi_end))?;
+
+        let mut entries = Vec::new();
+
+        while let Some(header) =
+            or_eof(ChunkHeader::read(&mut self.reader, ByteOrder::LittleEndian))?
+        {
+            if &header.id == b"idx1" {
+                if header.len > MAX_INDEX_LEN {
+                    warn!("avi: ignoring index of {} bytes", header.len);
+                    break;
+                }
+
+                let mut buf = vec![0; header.len as usize];
+
+                if or_eof(self.reader.read_buf_exact(&mut buf))?.is_some() {
+                    entries = read_index(&buf);
+                }
+                break;
+            }
+
+            if or_eof(self.reader.ignore_bytes(header.padded_len()))?.is_none() {
+                break;
+            }
+        }
+
+        // The offsets of the index entries are usually relative to the movi list type, but may
+        // be relative to the start of the file.
+        let base = match entries.first() {
+            Some(entry) if self.is_index_base(self.movi_pos - 4, entry)? => self.movi_pos - 4,
+            Some(_) => 0,
+            None => {
+                debug!("no index found");
+                return Ok(());
+            }
+        };
+
+        // The number of bytes, and chunks, of every stream preceding the current entry.
+        let mut counts = vec![(0, 0); self.streams.len()];
+
+        for entry in entries {
+            let stream_idx = stream_number(entry.id)
+                .and_then(|number| self.streams.iter().position(|stream| stream.number == number));
+
+            if let Some(stream_idx) = stream_idx {
+                let (bytes, chunks) = counts[stream_idx];
+
+                let pos = base + u64::from(entry.offset);
+                self.streams[stream_idx].index.push(IndexPoint { pos, bytes, chunks });
+
+                counts[stream_idx] = (bytes + u64::from(entry.len), chunks + 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the offset of an index entry, relative to a base position, is the position
+    /// of the chunk the entry refers to.
+    fn is_index_base(&mut self, base: u64, entry: &IndexEntry) -> Result<bool> {
+        self.reader.seek(SeekFrom::Start(base + u64::from(entry.offset)))?;
+        Ok(or_eof(self.reader.read_quad_bytes())? == Some(entry.id))
+    }
+}
+
+impl FormatReader for AviReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let riff = ChunkHeader::read(&mut source, ByteOrder::LittleEndian)?;
+
+        if &riff.id != b"RIFF" {
+            return unsupported_error("avi: missing riff chunk");
+        }
+
+        if &source.read_quad_bytes()? != b"AVI " {
+            return unsupported_error("avi: not an avi file");
+        }
+
+        let mut metadata: MetadataLog = Default::default();
+        let mut streams = Vec::new();
+
+        // Read the top-level chunks up-to the movi list.
+        let movi_len = loop {
+            let header = ChunkHeader::read(&mut source, ByteOrder::LittleEndian)?;
+
+            if &header.id != b"LIST" || header.len < 4 {
+                source.ignore_bytes(header.padded_len())?;
+                continue;
+            }
+
+            let list_type = source.read_quad_bytes()?;
+
+            if &list_type == b"movi" {
+                break header.len - 4;
+            }
+
+            if header.len > MAX_HEADER_LIST_LEN {
+                source.ignore_bytes(header.padded_len() - 4)?;
+                continue;
+            }
+
+            let mut buf = vec![0; header.padded_len() as usize - 4];
+            source.read_buf_exact(&mut buf)?;
+
+            match &list_type {
+                b"hdrl" => streams = read_streams(&buf)?,
+                b"INFO" => {
+                    let mut builder = MetadataBuilder::new();
+
+                    for (id, value) in sub_chunks(&buf, ByteOrder::LittleEndian) {
+                        builder.add_tag(riff::parse(id, value));
+                    }
+
+                    metadata.push(builder.metadata());
+                }
+                _ => info!("ignoring list: {}", String::from_utf8_lossy(&list_type)),
+            }
+        };
+
+        if streams.is_empty() {
+            return unsupported_error("avi: no supported audio streams");
+        }
+
+        let movi_pos = source.pos();
+
+        let mut reader = AviReader {
+            reader: source,
+            tracks: Vec::new(),
+            cues: Vec::new(),
+            metadata,
+            streams,
+            buf: Vec::new(),
+            movi_pos,
+            frames: VecDeque::new(),
+        };
+
+        reader.read_index(movi_pos + u64::from(movi_len) + u64::from(movi_len & 1))?;
+
+        if reader.reader.is_seekable() {
+            reader.reader.seek(SeekFrom::Start(movi_pos))?;
+        }
+
+        // The codec parameters of some codecs are determined from their frames.
+        while !reader.streams.iter().all(|stream| stream.is_ready()) {
+            if reader.reader.pos() - movi_pos > MAX_PROBE_LEN || !reader.read_chunk()? {
+                break;
+            }
+        }
+
+        reader.streams.retain(|stream| {
+            if !stream.is_ready() {
+                warn!("avi: ignoring stream {} without frames", stream.number);
+            }
+            stream.is_ready()
+        });
+
+        if reader.streams.is_empty() {
+            return unsupported_error("avi: no supported audio streams");
+        }
+
+        for stream in reader.streams.iter_mut() {
+            if let Parser::Frames(parser) = &stream.parser {
+                let info = parser.info.as_ref().unwrap();
+
+                stream.params.for_codec(info.codec).with_sample_rate(info.sample_rate);
+
+                if let Some(channels) = info.channels {
+                    stream.params.with_channels(channels);
+                }
+
+                stream.sample_rate = info.sample_rate;
+            }
+
+            if let Parser::Pcm(..) = stream.parser {
+                stream.params.with_max_frames_per_packet(MAX_PCM_FRAMES_PER_PACKET);
+            }
+
+            stream.params.with_time_base(TimeBase::new(1, stream.sample_rate));
+
+            if stream.header.length > 0 {
+                let end = u64::from(stream.header.start) + u64::from(stream.header.length);
+                stream.params.with_n_frames(stream.header.units_to_ts(end, stream.sample_rate));
+            }
+
+            reader.tracks.push(Track::new(stream.number, stream.params.clone()));
+        }
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (number, frame) = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        let stream = self.streams.iter_mut().find(|stream| stream.number == number).unwrap();
+
+        Ok(stream.packet(frame))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let track_id = match to {
+            SeekTo::TimeStamp { track_id, .. } => track_id,
+            SeekTo::Time { track_id, .. } => track_id.unwrap_or(self.tracks[0].id),
+        };
+
+        let stream_idx = match self.streams.iter().position(|stream| stream.number == track_id) {
+            Some(stream_idx) => stream_idx,
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.streams[stream_idx].sample_rate).calc_timestamp(time)
+            }
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        if self.reader.is_seekable() {
+            let stream = &self.streams[stream_idx];
+
+            // Find the last indexed chunk of the track starting at, or before, the timestamp.
+            // Frames may span chunks, therefore, the chunk preceding it is used for those
+            // streams. Without an index, the track is read from the start.
+            let idx = stream
+                .index
+                .iter()
+                .rposition(|point| stream.position_ts(point.bytes, point.chunks) <= required_ts);
+
+            let pos = match idx {
+                Some(idx) if matches!(stream.parser, Parser::Frames(_)) => {
+                    stream.index[idx.saturating_sub(1)].pos
+                }
+                Some(idx) => stream.index[idx].pos,
+                None => self.movi_pos,
+            };
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            self.frames.clear();
+
+            // The first chunk of every stream read after the position is the first indexed chunk
+            // of the stream at, or after, the position.
+            for stream in self.streams.iter_mut() {
+                let (bytes, chunks) = match stream.index.iter().find(|point| point.pos >= pos) {
+                    Some(point) => (point.bytes, point.chunks),
+                    None if pos == self.movi_pos => (0, 0),
+                    None => (stream.bytes, stream.chunks),
+                };
+
+                stream.bytes = bytes;
+                stream.chunks = chunks;
+                stream.reset();
+            }
+        }
+        else if self.streams[stream_idx].next_ts > required_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // Read frames until the frame of the track containing the required timestamp.
+        let actual_ts = loop {
+            let (number, frame) = match self.next_frame()? {
+                Some(frame) => frame,
+                None => return seek_error(SeekErrorKind::OutOfRange),
+            };
+
+            let stream = self.streams.iter_mut().find(|stream| stream.number == number).unwrap();
+
+            if number == track_id {
+                let ts = stream.timestamp(&frame);
+
+                if ts + frame.dur.max(1) > required_ts {
+                    self.frames.push_front((number, frame));
+                    break ts;
+                }
+            }
+
+            // Frames preceding the required timestamp are discarded.
+            stream.packet(frame);
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+/// Reads the stream lists of the header list, and returns the supported audio streams.
+fn read_streams(buf: &[u8]) -> Result<Vec<AudioStream>> {
+    let mut streams = Vec::new();
+    let mut number = 0;
+
+    for (id, data) in sub_chunks(buf, ByteOrder::LittleEndian) {
+        if &id != b"LIST" || data.len() < 4 || &data[..4] != b"strl" {
+            continue;
+        }
+
+        let mut header = None;
+        let mut format = None;
+
+        for (id, data) in sub_chunks(&data[4..], ByteOrder::LittleEndian) {
+            match &id {
+                b"strh" => header = Some(StreamHeader::read(data)?),
+                b"strf" => format = Some(data),
+                _ => (),
+            }
+        }
+
+        match (header, format) {
+            (Some(header), Some(format)) if &header.fcc_type == b"auds" => {
+                match read_wave_format(format)? {
+                    Some(format) => {
+                        debug!("found audio stream {}", number);
+                        streams.push(AudioStream::new(
+                            number,
+                            header,
+                            format.params,
+                            format.framing,
+                        ));
+                    }
+                    None => warn!("avi: ignoring stream {} with an unsupported codec", number),
+                }
+            }
+            (Some(_), _) => (),
+            (None, _) => return decode_error("avi: missing stream header"),
+        }
+
+        number += 1;
+    }
+
+    Ok(streams)
+}
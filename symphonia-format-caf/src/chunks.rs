@@ -12,6 +12,7 @@ use symphonia_core::{
     codecs::*,
     errors::{decode_error, unsupported_error, Error, Result},
     io::{MediaSourceStream, ReadBytes},
+    meta::{StandardTagKey, Tag, Value},
 };
 
 #[derive(Debug)]
@@ -21,6 +22,7 @@ pub enum Chunk {
     ChannelLayout(ChannelLayout),
     PacketTable(PacketTable),
     MagicCookie(Box<[u8]>),
+    Information(Vec<Tag>),
     Free,
 }
 
@@ -57,6 +59,7 @@ impl Chunk {
                     return invalid_chunk_size_error("Magic Cookie", chunk_size);
                 }
             }
+            b"info" => Chunk::Information(read_information(reader, chunk_size)?),
             b"free" => {
                 if chunk_size < 0 {
                     return invalid_chunk_size_error("Free", chunk_size);
@@ -158,14 +161,14 @@ impl AudioDescription {
                     }
                 }
             }
-            AppleIMA4 => CODEC_TYPE_ADPCM_IMA_WAV,
+            AppleIMA4 => CODEC_TYPE_ADPCM_IMA_QT,
             MPEG4AAC => CODEC_TYPE_AAC,
             ULaw => CODEC_TYPE_PCM_MULAW,
             ALaw => CODEC_TYPE_PCM_ALAW,
             MPEGLayer1 => CODEC_TYPE_MP1,
             MPEGLayer2 => CODEC_TYPE_MP2,
             MPEGLayer3 => CODEC_TYPE_MP3,
-            AppleLossless => CODEC_TYPE_ALAC,
+            AppleLossless { .. } => CODEC_TYPE_ALAC,
             Flac => CODEC_TYPE_FLAC,
             Opus => CODEC_TYPE_OPUS,
             unsupported => {
@@ -177,9 +180,23 @@ impl AudioDescription {
         Ok(result)
     }
 
+    /// Get the number of bits per sample, if known.
+    pub fn bits_per_sample(&self) -> Option<u32> {
+        match self.format_id {
+            AudioDescriptionFormatId::AppleLossless { bits_per_sample } => bits_per_sample,
+            _ if self.bits_per_channel > 0 => Some(self.bits_per_channel),
+            _ => None,
+        }
+    }
+
     pub fn format_is_compressed(&self) -> bool {
         self.bits_per_channel == 0
     }
+
+    /// Returns true if every packet has the same number of bytes and frames.
+    pub fn format_is_constant_bit_rate(&self) -> bool {
+        self.bytes_per_packet > 0 && self.frames_per_packet > 0
+    }
 }
 
 #[derive(Debug)]
@@ -223,7 +240,7 @@ pub enum AudioDescriptionFormatId {
     MPEGLayer1,
     MPEGLayer2,
     MPEGLayer3,
-    AppleLossless,
+    AppleLossless { bits_per_sample: Option<u32> },
     Flac,
     Opus,
 }
@@ -256,7 +273,17 @@ impl AudioDescriptionFormatId {
             b".mp1" => MPEGLayer1,
             b".mp2" => MPEGLayer2,
             b".mp3" => MPEGLayer3,
-            b"alac" => AppleLossless,
+            b"alac" => {
+                // The format flags indicate the bit depth of the source data.
+                let bits_per_sample = match format_flags {
+                    1 => Some(16),
+                    2 => Some(20),
+                    3 => Some(24),
+                    4 => Some(32),
+                    _ => None,
+                };
+                return Ok(AppleLossless { bits_per_sample });
+            }
             // Additional formats from CoreAudioBaseTypes.h
             b"flac" => Flac,
             b"opus" => Opus,
@@ -430,6 +457,13 @@ impl PacketTable {
 
         let priming_frames = reader.read_be_i32()?;
         let remainder_frames = reader.read_be_i32()?;
+        if priming_frames < 0 || remainder_frames < 0 {
+            error!(
+                "invalid priming or remainder frames in the packet table ({}, {})",
+                priming_frames, remainder_frames
+            );
+            return decode_error("caf: invalid priming or remainder frames in the packet table");
+        }
 
         let mut packets = Vec::with_capacity(total_packets as usize);
         let mut current_frame = 0;
@@ -532,6 +566,59 @@ fn invalid_chunk_size_error<T>(chunk_type: &str, chunk_size: i64) -> Result<T> {
     decode_error("caf: invalid chunk size")
 }
 
+/// Reads the entries of an Information chunk, and converts them into tags.
+fn read_information(reader: &mut MediaSourceStream, chunk_size: i64) -> Result<Vec<Tag>> {
+    let buf = match usize::try_from(chunk_size) {
+        Ok(chunk_size) if chunk_size >= 4 => reader.read_boxed_slice_exact(chunk_size)?,
+        _ => return invalid_chunk_size_error("Information", chunk_size),
+    };
+
+    let num_entries = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+
+    // Each entry is a pair of null-terminated UTF-8 strings, the key and the value.
+    let mut strings = buf[4..].split(|&b| b == 0).map(String::from_utf8_lossy);
+
+    let mut tags = Vec::new();
+
+    for _ in 0..num_entries {
+        let (key, value) = match (strings.next(), strings.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => {
+                warn!("information chunk is truncated");
+                break;
+            }
+        };
+
+        tags.push(Tag::new(info_key_to_std_key(&key), &key, Value::from(value)));
+    }
+
+    Ok(tags)
+}
+
+/// Maps the keys of an Information chunk defined by the CAF specification to standard tag keys.
+fn info_key_to_std_key(key: &str) -> Option<StandardTagKey> {
+    let std_key = match key {
+        "album" => StandardTagKey::Album,
+        "artist" => StandardTagKey::Artist,
+        "comments" => StandardTagKey::Comment,
+        "composer" => StandardTagKey::Composer,
+        "copyright" => StandardTagKey::Copyright,
+        "encoding application" => StandardTagKey::Encoder,
+        "genre" => StandardTagKey::Genre,
+        "ISRC" => StandardTagKey::IdentIsrc,
+        "lyricist" => StandardTagKey::Lyricist,
+        "recorded date" => StandardTagKey::Date,
+        "subtitle" => StandardTagKey::TrackSubtitle,
+        "tempo" => StandardTagKey::Bpm,
+        "title" => StandardTagKey::TrackTitle,
+        "track number" => StandardTagKey::TrackNumber,
+        "year" => StandardTagKey::Date,
+        _ => return None,
+    };
+
+    Some(std_key)
+}
+
 fn read_variable_length_integer(reader: &mut MediaSourceStream) -> Result<u64> {
     let mut result = 0;
 
@@ -14,9 +14,12 @@ use symphonia_core::{
     errors::{
         decode_error, end_of_stream_error, seek_error, unsupported_error, Result, SeekErrorKind,
     },
-    formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track},
+    formats::{
+        util::trim_packet, Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo,
+        Track,
+    },
     io::{MediaSource, MediaSourceStream, ReadBytes},
-    meta::{Metadata, MetadataLog},
+    meta::{Metadata, MetadataBuilder, MetadataLog},
     probe::{Descriptor, Instantiate, QueryDescriptor},
     support_format,
     units::{TimeBase, TimeStamp},
@@ -29,6 +32,7 @@ const MAX_FRAMES_PER_PACKET: u64 = 1152;
 /// `CafReader` implements a demuxer for Core Audio Format containers.
 pub struct CafReader {
     reader: MediaSourceStream,
+    options: FormatOptions,
     tracks: Vec<Track>,
     cues: Vec<Cue>,
     metadata: MetadataLog,
@@ -40,6 +44,7 @@ pub struct CafReader {
 enum PacketInfo {
     Unknown,
     Uncompressed { bytes_per_frame: u32 },
+    ConstantBitRate { bytes_per_packet: u32, frames_per_packet: u32 },
     Compressed { packets: Vec<CafPacket>, current_packet_index: usize },
 }
 
@@ -54,9 +59,10 @@ impl QueryDescriptor for CafReader {
 }
 
 impl FormatReader for CafReader {
-    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+    fn try_new(source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
         let mut reader = Self {
             reader: source,
+            options: *options,
             tracks: vec![],
             cues: vec![],
             metadata: MetadataLog::default(),
@@ -99,11 +105,34 @@ impl FormatReader for CafReader {
                 let buffer = self.reader.read_boxed_slice(bytes_to_read as usize)?;
                 Ok(Packet::new_from_boxed_slice(0, packet_timestamp, packet_duration, buffer))
             }
+            PacketInfo::ConstantBitRate { bytes_per_packet, frames_per_packet } => {
+                let data_pos = self.reader.pos() - self.data_start_pos;
+
+                let bytes_per_packet = *bytes_per_packet as u64;
+
+                if let Some(data_len) = self.data_len {
+                    if data_len - data_pos < bytes_per_packet {
+                        return end_of_stream_error();
+                    }
+                }
+
+                let packet_timestamp = data_pos / bytes_per_packet * *frames_per_packet as u64;
+                let buffer = self.reader.read_boxed_slice_exact(bytes_per_packet as usize)?;
+                let packet = Packet::new_from_boxed_slice(
+                    0,
+                    packet_timestamp,
+                    *frames_per_packet as u64,
+                    buffer,
+                );
+                Ok(self.trim_packet(packet))
+            }
             PacketInfo::Compressed { packets, ref mut current_packet_index } => {
                 if let Some(packet) = packets.get(*current_packet_index) {
                     *current_packet_index += 1;
                     let buffer = self.reader.read_boxed_slice(packet.size as usize)?;
-                    Ok(Packet::new_from_boxed_slice(0, packet.start_frame, packet.frames, buffer))
+                    let packet =
+                        Packet::new_from_boxed_slice(0, packet.start_frame, packet.frames, buffer);
+                    Ok(self.trim_packet(packet))
                 }
                 else if *current_packet_index == packets.len() {
                     end_of_stream_error()
@@ -128,7 +157,7 @@ impl FormatReader for CafReader {
         &self.tracks
     }
 
-    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+    fn seek(&mut self, mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
         let required_ts = match to {
             SeekTo::TimeStamp { ts, .. } => ts,
             SeekTo::Time { time, .. } => {
@@ -141,6 +170,9 @@ impl FormatReader for CafReader {
             }
         };
 
+        // Packet timestamps include the priming frames if gapless playback is enabled.
+        let delay = self.delay();
+
         match &mut self.packet_info {
             PacketInfo::Uncompressed { bytes_per_frame } => {
                 // Packetization for PCM data is performed by chunking the stream into
@@ -172,26 +204,60 @@ impl FormatReader for CafReader {
 
                 Ok(SeekedTo { track_id: 0, actual_ts, required_ts })
             }
-            PacketInfo::Compressed { packets, current_packet_index } => {
-                let current_ts = if let Some(packet) = packets.get(*current_packet_index) {
-                    TimeStamp::from(packet.start_frame)
+            PacketInfo::ConstantBitRate { bytes_per_packet, frames_per_packet } => {
+                let ts = required_ts + delay;
+
+                let mut packet_index = ts / *frames_per_packet as u64;
+
+                // Start decoding from the preceding packet for accurate seeks, since the packet
+                // containing the required timestamp may depend on it.
+                if let SeekMode::Accurate = mode {
+                    packet_index = packet_index.saturating_sub(1);
                 }
-                else {
-                    error!("invalid packet index: {}", current_packet_index);
-                    return decode_error("caf: invalid packet index");
-                };
 
-                let search_range = if current_ts < required_ts {
-                    *current_packet_index..packets.len()
+                let seek_pos = self.data_start_pos + packet_index * (*bytes_per_packet as u64);
+
+                if self.reader.is_seekable() {
+                    self.reader.seek(SeekFrom::Start(seek_pos))?;
                 }
                 else {
-                    0..*current_packet_index
-                };
+                    let current_pos = self.reader.pos();
+                    if seek_pos >= current_pos {
+                        self.reader.ignore_bytes(seek_pos - current_pos)?;
+                    }
+                    else {
+                        return seek_error(SeekErrorKind::ForwardOnly);
+                    }
+                }
+
+                let actual_ts = (packet_index * *frames_per_packet as u64).saturating_sub(delay);
+
+                debug!(
+                    "seek required_ts: {}, actual_ts: {}, (difference: {}, packet: {})",
+                    required_ts,
+                    actual_ts,
+                    actual_ts as i64 - required_ts as i64,
+                    packet_index,
+                );
+
+                Ok(SeekedTo { track_id: 0, actual_ts, required_ts })
+            }
+            PacketInfo::Compressed { packets, current_packet_index } => {
+                let ts = required_ts + delay;
 
-                let packet_after_ts = packets[search_range]
-                    .partition_point(|packet| packet.start_frame < required_ts);
-                let seek_packet_index = packet_after_ts.saturating_sub(1);
-                let seek_packet = &packets[seek_packet_index];
+                let packet_after_ts = packets.partition_point(|packet| packet.start_frame <= ts);
+                let mut seek_packet_index = packet_after_ts.saturating_sub(1);
+
+                // Start decoding from the preceding packet for accurate seeks, since the packet
+                // containing the required timestamp may depend on it.
+                if let SeekMode::Accurate = mode {
+                    seek_packet_index = seek_packet_index.saturating_sub(1);
+                }
+
+                let seek_packet = match packets.get(seek_packet_index) {
+                    Some(packet) => packet,
+                    None => return seek_error(SeekErrorKind::OutOfRange),
+                };
 
                 let seek_pos = self.data_start_pos + seek_packet.data_offset;
 
@@ -208,8 +274,8 @@ impl FormatReader for CafReader {
                     }
                 }
 
+                let actual_ts = TimeStamp::from(seek_packet.start_frame).saturating_sub(delay);
                 *current_packet_index = seek_packet_index;
-                let actual_ts = TimeStamp::from(seek_packet.start_frame);
 
                 debug!(
                     "seek required_ts: {}, actual_ts: {}, (difference: {}, packet: {})",
@@ -237,6 +303,25 @@ impl CafReader {
         })
     }
 
+    /// Get the number of priming frames to trim, if gapless playback is enabled.
+    fn delay(&self) -> u64 {
+        if self.options.enable_gapless {
+            self.tracks.first().and_then(|track| track.codec_params.delay).unwrap_or(0).into()
+        }
+        else {
+            0
+        }
+    }
+
+    /// Trim the priming and remainder frames from a packet, if gapless playback is enabled.
+    fn trim_packet(&self, mut packet: Packet) -> Packet {
+        if self.options.enable_gapless {
+            let params = &self.tracks[0].codec_params;
+            trim_packet(&mut packet, params.delay.unwrap_or(0), params.n_frames);
+        }
+        packet
+    }
+
     fn check_file_header(&mut self) -> Result<()> {
         let file_type = self.reader.read_quad_bytes()?;
         if file_type != *b"caff" {
@@ -264,9 +349,12 @@ impl CafReader {
             .for_codec(desc.codec_type()?)
             .with_sample_rate(desc.sample_rate as u32)
             .with_time_base(TimeBase::new(1, desc.sample_rate as u32))
-            .with_bits_per_sample(desc.bits_per_channel)
             .with_bits_per_coded_sample((desc.bytes_per_packet * 8) / desc.channels_per_frame);
 
+        if let Some(bits_per_sample) = desc.bits_per_sample() {
+            codec_params.with_bits_per_sample(bits_per_sample);
+        }
+
         match desc.channels_per_frame {
             0 => {
                 // A channel count of zero should have been rejected by the AudioDescription parser
@@ -292,7 +380,17 @@ impl CafReader {
             }
         }
 
-        if desc.format_is_compressed() {
+        if desc.format_is_compressed() && desc.format_is_constant_bit_rate() {
+            // Every packet has the same size and duration, so a packet table is not required.
+            codec_params
+                .with_max_frames_per_packet(desc.frames_per_packet as u64)
+                .with_frames_per_block(desc.frames_per_packet as u64);
+            self.packet_info = PacketInfo::ConstantBitRate {
+                bytes_per_packet: desc.bytes_per_packet,
+                frames_per_packet: desc.frames_per_packet,
+            };
+        }
+        else if desc.format_is_compressed() {
             self.packet_info =
                 PacketInfo::Compressed { packets: Vec::new(), current_packet_index: 0 };
         }
@@ -309,6 +407,7 @@ impl CafReader {
 
         let mut codec_params = CodecParameters::new();
         let mut audio_description = None;
+        let mut packet_table = None;
 
         loop {
             match Chunk::read(&mut self.reader, &audio_description)? {
@@ -322,10 +421,11 @@ impl CafReader {
                 Some(AudioData(data)) => {
                     self.data_start_pos = data.start_pos;
                     self.data_len = data.data_len;
-                    if let Some(data_len) = self.data_len {
-                        if let PacketInfo::Uncompressed { bytes_per_frame } = &self.packet_info {
-                            codec_params.with_n_frames(data_len / *bytes_per_frame as u64);
-                        }
+
+                    // If the size of the Audio Data chunk is unknown, it is the last chunk, and the
+                    // reader is positioned at the start of the audio data.
+                    if self.data_len.is_none() {
+                        break;
                     }
                 }
                 Some(ChannelLayout(layout)) => {
@@ -339,15 +439,22 @@ impl CafReader {
                         info!("couldn't convert the channel layout into a channel bitmap");
                     }
                 }
-                Some(PacketTable(table)) => {
+                Some(PacketTable(mut table)) => {
                     if let PacketInfo::Compressed { ref mut packets, .. } = &mut self.packet_info {
-                        codec_params.with_n_frames(table.valid_frames as u64);
-                        *packets = table.packets;
+                        *packets = std::mem::take(&mut table.packets);
                     }
+                    packet_table = Some(table);
                 }
                 Some(MagicCookie(data)) => {
                     codec_params.with_extra_data(data);
                 }
+                Some(Information(tags)) => {
+                    let mut builder = MetadataBuilder::new();
+                    for tag in tags {
+                        builder.add_tag(tag);
+                    }
+                    self.metadata.push(builder.metadata());
+                }
                 Some(Free) | None => {}
             }
 
@@ -368,6 +475,37 @@ impl CafReader {
             }
         }
 
+        // The packet table, if present, gives the number of priming and remainder frames, and the
+        // number of frames excluding them. Otherwise, the number of frames is derived from the size
+        // of the audio data.
+        if let Some(table) = packet_table {
+            let priming_frames = table.priming_frames as u32;
+            let remainder_frames = table.remainder_frames as u32;
+
+            codec_params.with_delay(priming_frames).with_padding(remainder_frames);
+
+            let n_frames = if self.options.enable_gapless {
+                table.valid_frames as u64
+            }
+            else {
+                table.valid_frames as u64 + u64::from(priming_frames) + u64::from(remainder_frames)
+            };
+
+            codec_params.with_n_frames(n_frames);
+        }
+        else if let Some(data_len) = self.data_len {
+            match &self.packet_info {
+                PacketInfo::Uncompressed { bytes_per_frame } => {
+                    codec_params.with_n_frames(data_len / *bytes_per_frame as u64);
+                }
+                PacketInfo::ConstantBitRate { bytes_per_packet, frames_per_packet } => {
+                    let n_packets = data_len / *bytes_per_packet as u64;
+                    codec_params.with_n_frames(n_packets * *frames_per_packet as u64);
+                }
+                _ => (),
+            }
+        }
+
         Ok(codec_params)
     }
 }
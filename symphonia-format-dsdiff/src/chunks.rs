@@ -0,0 +1,304 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{FiniteStream, ReadBytes, ScopedStream};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+use log::debug;
+
+/// The length of a chunk header.
+pub const CHUNK_HEADER_LEN: u64 = 12;
+
+/// A chunk header.
+pub struct ChunkHeader {
+    pub id: [u8; 4],
+    /// The length of the chunk body, excluding the pad byte.
+    pub len: u64,
+}
+
+impl ChunkHeader {
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ChunkHeader> {
+        let mut id = [0; 4];
+        reader.read_buf_exact(&mut id)?;
+
+        let len = reader.read_be_u64()?;
+
+        Ok(ChunkHeader { id, len })
+    }
+
+    /// Get the length of the chunk body, including the pad byte of a chunk of odd length.
+    pub fn padded_len(&self) -> u64 {
+        self.len + (self.len & 1)
+    }
+}
+
+/// The compression type of the sound data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Dsd,
+    Dst,
+}
+
+/// The sound properties of a PROP chunk.
+pub struct SoundProperties {
+    pub sample_rate: u32,
+    pub channels: Channels,
+    pub compression: Compression,
+}
+
+/// Get the channels of the channel identifiers of a CHNL chunk. If a channel is unknown, or the
+/// channels are not in the order of `Channels`, then the first channels of `Channels` are assumed.
+fn read_channels(ids: &[[u8; 4]]) -> Channels {
+    let mut channels = Channels::empty();
+
+    for id in ids {
+        let channel = match id {
+            b"SLFT" | b"MLFT" => Channels::FRONT_LEFT,
+            b"SRGT" | b"MRGT" => Channels::FRONT_RIGHT,
+            b"C   " => Channels::FRONT_CENTRE,
+            b"LFE " => Channels::LFE1,
+            b"LS  " => Channels::REAR_LEFT,
+            b"RS  " => Channels::REAR_RIGHT,
+            _ => Channels::empty(),
+        };
+
+        if channel.is_empty() || channel.bits() <= channels.bits() {
+            return Channels::from_bits_truncate(((1u64 << ids.len()) - 1) as u32);
+        }
+
+        channels |= channel;
+    }
+
+    channels
+}
+
+/// Read the sound properties of a PROP chunk body.
+pub fn read_prop_chunk<B: ReadBytes>(reader: &mut B, len: u64) -> Result<SoundProperties> {
+    let mut reader = ScopedStream::new(reader, len);
+
+    let mut prop_type = [0; 4];
+    reader.read_buf_exact(&mut prop_type)?;
+
+    if &prop_type != b"SND " {
+        return unsupported_error("dsdiff: unsupported property type");
+    }
+
+    let mut sample_rate = None;
+    let mut channels = None;
+    let mut compression = None;
+
+    while reader.bytes_available() >= CHUNK_HEADER_LEN {
+        let header = ChunkHeader::read(&mut reader)?;
+
+        match &header.id {
+            b"FS  " if header.len >= 4 => {
+                sample_rate = Some(reader.read_be_u32()?);
+                reader.ignore_bytes(header.padded_len() - 4)?;
+            }
+            b"CHNL" if header.len >= 2 => {
+                let num_channels = u64::from(reader.read_be_u16()?);
+
+                if num_channels == 0 || num_channels > 32 || header.len < 2 + 4 * num_channels {
+                    return decode_error("dsdiff: invalid channel count");
+                }
+
+                let mut ids = vec![[0; 4]; num_channels as usize];
+
+                for id in ids.iter_mut() {
+                    reader.read_buf_exact(id)?;
+                }
+
+                channels = Some(read_channels(&ids));
+                reader.ignore_bytes(header.padded_len() - 2 - 4 * num_channels)?;
+            }
+            b"CMPR" if header.len >= 4 => {
+                let mut compression_type = [0; 4];
+                reader.read_buf_exact(&mut compression_type)?;
+
+                compression = match &compression_type {
+                    b"DSD " => Some(Compression::Dsd),
+                    b"DST " => Some(Compression::Dst),
+                    _ => return unsupported_error("dsdiff: unsupported compression type"),
+                };
+
+                reader.ignore_bytes(header.padded_len() - 4)?;
+            }
+            _ => {
+                debug!("skipping property chunk {:?}", header.id);
+                reader.ignore_bytes(header.padded_len())?;
+            }
+        }
+    }
+
+    reader.ignore()?;
+
+    match (sample_rate, channels, compression) {
+        (Some(sample_rate), Some(channels), Some(compression)) if sample_rate > 0 => {
+            Ok(SoundProperties { sample_rate, channels, compression })
+        }
+        _ => decode_error("dsdiff: missing sound properties"),
+    }
+}
+
+/// Read a text preceded by its length.
+fn read_text<B: ReadBytes>(reader: &mut B) -> Result<String> {
+    let len = reader.read_be_u32()?;
+    let text = reader.read_boxed_slice_exact(len as usize)?;
+
+    // Texts of odd length are padded.
+    if len & 1 != 0 {
+        reader.read_u8()?;
+    }
+
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+/// Read the comments of a COMT chunk body.
+pub fn read_comt_chunk<B: ReadBytes>(reader: &mut B, len: u64) -> Result<Vec<Tag>> {
+    let mut reader = ScopedStream::new(reader, len);
+
+    let num_comments = reader.read_be_u16()?;
+
+    let mut tags = Vec::new();
+
+    for _ in 0..num_comments {
+        // Skip the time stamp, comment type, and comment reference.
+        reader.ignore_bytes(10)?;
+
+        let text = read_text(&mut reader)?;
+
+        tags.push(Tag::new(Some(StandardTagKey::Comment), "COMT", Value::from(text)));
+    }
+
+    reader.ignore()?;
+
+    Ok(tags)
+}
+
+/// The type of a marker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerType {
+    TrackStart,
+    TrackStop,
+    ProgramStart,
+    Index,
+    Other,
+}
+
+/// A marker of the edited master.
+pub struct Marker {
+    pub marker_type: MarkerType,
+    /// The timestamp of the marker in DSD samples.
+    pub ts: u64,
+    pub text: String,
+}
+
+/// The edited master information of a DIIN chunk.
+#[derive(Default)]
+pub struct EditedMaster {
+    pub markers: Vec<Marker>,
+    pub tags: Vec<Tag>,
+}
+
+/// Read a MARK chunk body.
+fn read_marker<B: ReadBytes>(reader: &mut B, sample_rate: u32) -> Result<Marker> {
+    let hours = u64::from(reader.read_be_u16()?);
+    let minutes = u64::from(reader.read_u8()?);
+    let seconds = u64::from(reader.read_u8()?);
+    let samples = u64::from(reader.read_be_u32()?);
+    let offset = reader.read_be_i32()?;
+
+    let marker_type = match reader.read_be_u16()? {
+        0 => MarkerType::TrackStart,
+        1 => MarkerType::TrackStop,
+        2 => MarkerType::ProgramStart,
+        4 => MarkerType::Index,
+        _ => MarkerType::Other,
+    };
+
+    // Skip the channel and track flags.
+    reader.ignore_bytes(4)?;
+
+    let text = read_text(reader)?;
+
+    let ts = ((hours * 60 + minutes) * 60 + seconds) * u64::from(sample_rate) + samples;
+    let ts = if offset < 0 {
+        ts.saturating_sub(u64::from(offset.unsigned_abs()))
+    }
+    else {
+        ts + offset as u64
+    };
+
+    Ok(Marker { marker_type, ts, text })
+}
+
+/// Read the edited master information of a DIIN chunk body.
+pub fn read_diin_chunk<B: ReadBytes>(
+    reader: &mut B,
+    len: u64,
+    sample_rate: u32,
+) -> Result<EditedMaster> {
+    let mut reader = ScopedStream::new(reader, len);
+
+    let mut info: EditedMaster = Default::default();
+
+    while reader.bytes_available() >= CHUNK_HEADER_LEN {
+        let header = ChunkHeader::read(&mut reader)?;
+
+        let mut chunk = reader.scope(header.padded_len())?;
+
+        match &header.id {
+            b"MARK" => info.markers.push(read_marker(&mut chunk, sample_rate)?),
+            b"DIAR" => {
+                let text = read_text(&mut chunk)?;
+                info.tags.push(Tag::new(Some(StandardTagKey::Artist), "DIAR", Value::from(text)));
+            }
+            b"DITI" => {
+                let text = read_text(&mut chunk)?;
+                info.tags.push(Tag::new(
+                    Some(StandardTagKey::TrackTitle),
+                    "DITI",
+                    Value::from(text),
+                ));
+            }
+            _ => debug!("skipping edited master chunk {:?}", header.id),
+        }
+
+        chunk.ignore()?;
+    }
+
+    reader.ignore()?;
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    #[test]
+    fn verify_read_prop_chunk() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"SND ");
+        buf.extend_from_slice(b"FS  \0\0\0\0\0\0\0\x04\0\x2b\x11\0");
+        buf.extend_from_slice(b"CHNL\0\0\0\0\0\0\0\x0a\0\x02SLFTSRGT");
+        buf.extend_from_slice(b"CMPR\0\0\0\0\0\0\0\x14DST \x0fDST Encoded\0\0\0\0\0");
+
+        let props = read_prop_chunk(&mut BufReader::new(&buf), buf.len() as u64).unwrap();
+
+        assert_eq!(props.sample_rate, 2_822_400);
+        assert_eq!(props.channels, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        assert_eq!(props.compression, Compression::Dst);
+
+        // Channels out of order, or unknown channels, are the first channels of `Channels`.
+        let channels = read_channels(&[*b"SRGT", *b"SLFT", *b"C001"]);
+        assert_eq!(channels.bits(), 0x7);
+    }
+}
@@ -0,0 +1,438 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_DSD_MSBF, CODEC_TYPE_DST};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::CuePoint;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, StandardTagKey, Tag, Value};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::id3v2;
+
+use log::{debug, warn};
+
+use crate::chunks::*;
+
+/// The number of DSD bytes per channel in a packet of uncompressed sound data.
+const DSD_PACKET_LEN: u64 = 4096;
+
+/// The number of DST frames per second.
+const DST_FRAME_RATE: u32 = 75;
+
+/// The location of the sound data.
+struct SoundData {
+    /// The position of the first byte, or first DST frame chunk, of the sound data.
+    start_pos: u64,
+    end_pos: u64,
+    /// The number of DST frames.
+    num_frames: Option<u64>,
+}
+
+/// DSD Interchange File Format (DSDIFF) format reader.
+///
+/// `DsdiffReader` implements a demuxer for DSDIFF files containing uncompressed DSD audio, or DST
+/// compressed DSD audio. Each packet contains either 4096 bytes of every channel, or a single DST
+/// frame. The timestamps, and the sample rate of the codec parameters, are in DSD samples.
+///
+/// The track start markers of the edited master are exposed as cues, and the index markers as
+/// points of those cues.
+pub struct DsdiffReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    compression: Compression,
+    data_start_pos: u64,
+    data_end_pos: u64,
+    /// The length of a packet of uncompressed sound data in bytes.
+    packet_len: u64,
+    /// The maximum number of DSD samples per channel in a packet.
+    packet_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for DsdiffReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "dsdiff",
+            "DSD Interchange File Format",
+            &["dff", "dsdiff"],
+            &["audio/x-dff"],
+            &[b"FRM8"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Get the cues of the markers of an edited master.
+fn markers_to_cues(markers: &[Marker]) -> Vec<Cue> {
+    let mut cues: Vec<Cue> = Vec::new();
+
+    for marker in markers {
+        match marker.marker_type {
+            MarkerType::TrackStart => {
+                let mut tags = Vec::new();
+
+                if !marker.text.is_empty() {
+                    let value = Value::from(marker.text.as_str());
+                    tags.push(Tag::new(Some(StandardTagKey::TrackTitle), "MARK", value));
+                }
+
+                let index = cues.len() as u32 + 1;
+
+                cues.push(Cue { index, start_ts: marker.ts, tags, points: Vec::new() });
+            }
+            MarkerType::Index => {
+                // An index marker is a point within the preceding track.
+                if let Some(cue) = cues.last_mut() {
+                    if marker.ts >= cue.start_ts {
+                        let start_offset_ts = marker.ts - cue.start_ts;
+                        cue.points.push(CuePoint { start_offset_ts, tags: Vec::new() });
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    cues
+}
+
+impl DsdiffReader {
+    /// Get the length of the next DST frame chunk, or `None` at the end of the sound data.
+    fn next_dst_frame_len(&mut self) -> Result<Option<u64>> {
+        while self.reader.pos() + CHUNK_HEADER_LEN <= self.data_end_pos {
+            let header = ChunkHeader::read(&mut self.reader)?;
+
+            if &header.id == b"DSTF" {
+                return Ok(Some(header.len));
+            }
+
+            // Skip the CRC chunks.
+            self.reader.ignore_bytes(header.padded_len())?;
+        }
+
+        Ok(None)
+    }
+}
+
+impl FormatReader for DsdiffReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let header = ChunkHeader::read(&mut source)?;
+
+        let mut form_type = [0; 4];
+        source.read_buf_exact(&mut form_type)?;
+
+        if &header.id != b"FRM8" || &form_type != b"DSD " {
+            return unsupported_error("dsdiff: missing frm8 chunk");
+        }
+
+        let form_end_pos = source.pos() - 4 + header.len;
+
+        let mut props = None;
+        let mut sound = None;
+        let mut markers = Vec::new();
+        let mut tags = Vec::new();
+        let mut metadata: MetadataLog = Default::default();
+
+        while source.pos() + CHUNK_HEADER_LEN <= form_end_pos {
+            let header = ChunkHeader::read(&mut source)?;
+
+            let end_pos = source.pos() + header.len;
+
+            match &header.id {
+                b"PROP" => {
+                    props = Some(read_prop_chunk(&mut source, header.len)?);
+                }
+                b"DSD " | b"DST " => {
+                    let num_frames = if &header.id == b"DST " {
+                        // The sound data starts with the frame information.
+                        let info = ChunkHeader::read(&mut source)?;
+
+                        if &info.id != b"FRTE" || info.len < 6 {
+                            return decode_error("dsdiff: missing dst frame information");
+                        }
+
+                        let num_frames = source.read_be_u32()?;
+                        let frame_rate = source.read_be_u16()?;
+
+                        if u32::from(frame_rate) != DST_FRAME_RATE {
+                            return unsupported_error("dsdiff: unsupported dst frame rate");
+                        }
+
+                        source.ignore_bytes(info.padded_len() - 6)?;
+
+                        Some(u64::from(num_frames))
+                    }
+                    else {
+                        None
+                    };
+
+                    sound = Some(SoundData { start_pos: source.pos(), end_pos, num_frames });
+
+                    // The chunks following the sound data can only be read if the source is
+                    // seekable.
+                    if !source.is_seekable() {
+                        break;
+                    }
+
+                    source.seek(SeekFrom::Start(end_pos))?;
+                }
+                b"COMT" => tags.extend(read_comt_chunk(&mut source, header.len)?),
+                b"DIIN" => {
+                    let sample_rate = match &props {
+                        Some(props) => props.sample_rate,
+                        None => return decode_error("dsdiff: missing property chunk"),
+                    };
+
+                    let info = read_diin_chunk(&mut source, header.len, sample_rate)?;
+
+                    markers.extend(info.markers);
+                    tags.extend(info.tags);
+                }
+                b"ID3 " => {
+                    let mut scoped = ScopedStream::new(&mut source, header.len);
+                    let mut builder = MetadataBuilder::new();
+
+                    match id3v2::read_id3v2(&mut scoped, &mut builder) {
+                        Ok(_) => metadata.push(builder.metadata()),
+                        Err(err) => warn!("failed to read id3 chunk: {}", err),
+                    }
+
+                    // Skip any data following the tag.
+                    scoped.ignore()?;
+                }
+                _ => {
+                    debug!("skipping chunk {:?}", header.id);
+                    source.ignore_bytes(header.len)?;
+                }
+            }
+
+            // Skip the pad byte of chunks of odd length.
+            source.ignore_bytes(header.padded_len() - header.len)?;
+        }
+
+        let props = match props {
+            Some(props) => props,
+            None => return decode_error("dsdiff: missing property chunk"),
+        };
+
+        let sound = match sound {
+            Some(sound) => sound,
+            None => return decode_error("dsdiff: missing sound data"),
+        };
+
+        if source.pos() != sound.start_pos {
+            source.seek(SeekFrom::Start(sound.start_pos))?;
+        }
+
+        if !tags.is_empty() {
+            let mut builder = MetadataBuilder::new();
+
+            for tag in tags {
+                builder.add_tag(tag);
+            }
+
+            metadata.push(builder.metadata());
+        }
+
+        let num_channels = props.channels.count() as u64;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .with_sample_rate(props.sample_rate)
+            .with_time_base(TimeBase::new(1, props.sample_rate))
+            .with_bits_per_sample(1)
+            .with_channels(props.channels);
+
+        let (packet_len, packet_dur) = match (props.compression, sound.num_frames) {
+            (Compression::Dsd, None) => {
+                let n_frames = (sound.end_pos - sound.start_pos) / num_channels * 8;
+
+                params
+                    .for_codec(CODEC_TYPE_DSD_MSBF)
+                    .with_n_frames(n_frames)
+                    .with_max_frames_per_packet(DSD_PACKET_LEN * 8);
+
+                (DSD_PACKET_LEN * num_channels, DSD_PACKET_LEN * 8)
+            }
+            (Compression::Dst, Some(num_frames)) => {
+                let frame_dur = u64::from(props.sample_rate / DST_FRAME_RATE);
+
+                params
+                    .for_codec(CODEC_TYPE_DST)
+                    .with_n_frames(num_frames * frame_dur)
+                    .with_max_frames_per_packet(frame_dur);
+
+                (0, frame_dur)
+            }
+            _ => return decode_error("dsdiff: sound data does not match the compression type"),
+        };
+
+        Ok(DsdiffReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: markers_to_cues(&markers),
+            metadata,
+            compression: props.compression,
+            data_start_pos: sound.start_pos,
+            data_end_pos: sound.end_pos,
+            packet_len,
+            packet_dur,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = match self.compression {
+            Compression::Dsd => {
+                let num_channels = self.tracks[0].codec_params.channels.unwrap().count() as u64;
+
+                let len = self.data_end_pos.saturating_sub(self.reader.pos()).min(self.packet_len);
+                let len = len - len % num_channels;
+
+                if len == 0 {
+                    return end_of_stream_error();
+                }
+
+                self.reader.read_boxed_slice_exact(len as usize)?
+            }
+            Compression::Dst => {
+                let len = match self.next_dst_frame_len()? {
+                    Some(len) => len,
+                    None => return end_of_stream_error(),
+                };
+
+                let buf = self.reader.read_boxed_slice_exact(len as usize)?;
+
+                // Skip the pad byte of frames of odd length.
+                self.reader.ignore_bytes(len & 1)?;
+
+                buf
+            }
+        };
+
+        let ts = self.next_packet_ts;
+
+        let dur = match self.compression {
+            Compression::Dsd => {
+                let num_channels = self.tracks[0].codec_params.channels.unwrap().count();
+                (buf.len() / num_channels) as u64 * 8
+            }
+            Compression::Dst => self.packet_dur,
+        };
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Every packet has the same duration.
+        let packet_idx = required_ts / self.packet_dur;
+        let mut cur_packet_idx = self.next_packet_ts / self.packet_dur;
+
+        match self.compression {
+            Compression::Dsd => {
+                // Every packet also has the same length.
+                if self.reader.is_seekable() {
+                    let pos = self.data_start_pos + packet_idx * self.packet_len;
+                    self.reader.seek(SeekFrom::Start(pos))?;
+                }
+                else if packet_idx >= cur_packet_idx {
+                    self.reader.ignore_bytes((packet_idx - cur_packet_idx) * self.packet_len)?;
+                }
+                else {
+                    return seek_error(SeekErrorKind::ForwardOnly);
+                }
+            }
+            Compression::Dst => {
+                // DST frames vary in length, so they must be skipped one by one. If seeking
+                // backwards, start over from the first frame.
+                if packet_idx < cur_packet_idx {
+                    if !self.reader.is_seekable() {
+                        return seek_error(SeekErrorKind::ForwardOnly);
+                    }
+
+                    self.reader.seek(SeekFrom::Start(self.data_start_pos))?;
+                    cur_packet_idx = 0;
+                }
+
+                while cur_packet_idx < packet_idx {
+                    match self.next_dst_frame_len()? {
+                        Some(len) => self.reader.ignore_bytes(len + (len & 1))?,
+                        None => return seek_error(SeekErrorKind::OutOfRange),
+                    }
+
+                    cur_packet_idx += 1;
+                }
+            }
+        }
+
+        self.next_packet_ts = packet_idx * self.packet_dur;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,368 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::CODEC_TYPE_DSD_MSBF_PLANAR;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_DSD_LSBF_PLANAR};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::id3v2;
+
+use log::{debug, warn};
+
+/// The length of the DSD chunk.
+const DSD_CHUNK_LEN: u64 = 28;
+
+/// The minimum length of the fmt chunk.
+const FMT_CHUNK_LEN: u64 = 52;
+
+/// The length of a chunk header.
+const CHUNK_HEADER_LEN: u64 = 12;
+
+/// The length of a block of DSD bytes for one channel.
+const BLOCK_LEN: u32 = 4096;
+
+/// DSD Stream File (DSF) format reader.
+///
+/// `DsfReader` implements a demuxer for DSF files containing raw DSD audio. Each packet contains
+/// one block of every channel. The timestamps, and the sample rate of the codec parameters, are in
+/// DSD samples.
+pub struct DsfReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    data_start_pos: u64,
+    data_end_pos: u64,
+    /// The length of a packet in bytes.
+    packet_len: u64,
+    /// The number of DSD samples per channel in a packet.
+    packet_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for DsfReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("dsf", "DSD Stream File", &["dsf"], &["audio/x-dsf"], &[b"DSD "])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// The stream described by a fmt chunk.
+struct StreamInfo {
+    params: CodecParameters,
+    /// The length of a block of one channel in bytes.
+    block_len: u64,
+}
+
+/// Get the channels of a stream from its channel type.
+fn channels_from_type(channel_type: u32) -> Result<Channels> {
+    let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    let rear = Channels::REAR_LEFT | Channels::REAR_RIGHT;
+
+    let channels = match channel_type {
+        1 => Channels::FRONT_LEFT,
+        2 => stereo,
+        3 => stereo | Channels::FRONT_CENTRE,
+        4 => stereo | rear,
+        5 => stereo | Channels::FRONT_CENTRE | Channels::LFE1,
+        6 => stereo | Channels::FRONT_CENTRE | rear,
+        7 => stereo | Channels::FRONT_CENTRE | Channels::LFE1 | rear,
+        _ => return decode_error("dsf: invalid channel type"),
+    };
+
+    Ok(channels)
+}
+
+/// Read the stream information from a fmt chunk, excluding the chunk header.
+fn read_fmt_chunk<B: ReadBytes>(reader: &mut B) -> Result<StreamInfo> {
+    let version = reader.read_u32()?;
+    let format_id = reader.read_u32()?;
+    let channel_type = reader.read_u32()?;
+    let num_channels = reader.read_u32()?;
+    let sample_rate = reader.read_u32()?;
+    let bits_per_sample = reader.read_u32()?;
+    let n_frames = reader.read_u64()?;
+    let block_len = reader.read_u32()?;
+    let _reserved = reader.read_u32()?;
+
+    if version != 1 {
+        return unsupported_error("dsf: unsupported version");
+    }
+
+    // Only raw DSD is defined.
+    if format_id != 0 {
+        return unsupported_error("dsf: unsupported format");
+    }
+
+    let channels = channels_from_type(channel_type)?;
+
+    if channels.count() != num_channels as usize {
+        return decode_error("dsf: channel count does not match the channel type");
+    }
+
+    if sample_rate == 0 {
+        return decode_error("dsf: invalid sample rate");
+    }
+
+    // The bits of each byte are either ordered least- or most-significant bit first.
+    let codec = match bits_per_sample {
+        1 => CODEC_TYPE_DSD_LSBF_PLANAR,
+        8 => CODEC_TYPE_DSD_MSBF_PLANAR,
+        _ => return decode_error("dsf: invalid bits per sample"),
+    };
+
+    if block_len != BLOCK_LEN {
+        return decode_error("dsf: invalid block size");
+    }
+
+    let mut params = CodecParameters::new();
+
+    params
+        .for_codec(codec)
+        .with_sample_rate(sample_rate)
+        .with_time_base(TimeBase::new(1, sample_rate))
+        .with_bits_per_sample(1)
+        .with_channels(channels)
+        .with_n_frames(n_frames)
+        .with_max_frames_per_packet(u64::from(block_len) * 8);
+
+    Ok(StreamInfo { params, block_len: u64::from(block_len) })
+}
+
+/// Read a chunk header, and return the chunk identifier and the length of the chunk body.
+fn read_chunk_header<B: ReadBytes>(reader: &mut B) -> Result<([u8; 4], u64)> {
+    let mut id = [0; 4];
+    reader.read_buf_exact(&mut id)?;
+
+    // The chunk size includes the chunk header.
+    let len = match reader.read_u64()?.checked_sub(CHUNK_HEADER_LEN) {
+        Some(len) => len,
+        _ => return decode_error("dsf: invalid chunk size"),
+    };
+
+    Ok((id, len))
+}
+
+impl FormatReader for DsfReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let (id, len) = read_chunk_header(&mut source)?;
+
+        if &id != b"DSD " || len + CHUNK_HEADER_LEN != DSD_CHUNK_LEN {
+            return unsupported_error("dsf: missing dsd chunk");
+        }
+
+        let _file_len = source.read_u64()?;
+        let mut metadata_pos = source.read_u64()?;
+
+        let (id, len) = read_chunk_header(&mut source)?;
+
+        if &id != b"fmt " || len + CHUNK_HEADER_LEN < FMT_CHUNK_LEN {
+            return decode_error("dsf: missing fmt chunk");
+        }
+
+        let StreamInfo { params, block_len } = read_fmt_chunk(&mut source)?;
+
+        source.ignore_bytes(len + CHUNK_HEADER_LEN - FMT_CHUNK_LEN)?;
+
+        // Skip any chunks preceding the data chunk.
+        let data_len = loop {
+            let (id, len) = read_chunk_header(&mut source)?;
+
+            if &id == b"data" {
+                break len;
+            }
+
+            debug!("skipping chunk {:?}", id);
+            source.ignore_bytes(len)?;
+        };
+
+        let data_start_pos = source.pos();
+
+        let mut metadata: MetadataLog = Default::default();
+
+        // Some writers append the ID3v2 tag without setting the pointer to the metadata chunk. In
+        // this case, the tag may still be located by its footer.
+        if metadata_pos == 0 && source.is_seekable() {
+            if let Some(len) = source.byte_len() {
+                match id3v2::find_appended_id3v2(&mut source, len) {
+                    Ok(Some(tag_len)) => metadata_pos = len - tag_len,
+                    Ok(None) => (),
+                    Err(err) => warn!("failed to locate metadata chunk: {}", err),
+                }
+
+                source.seek(SeekFrom::Start(data_start_pos))?;
+            }
+        }
+
+        // The optional metadata chunk is an ID3v2 tag that follows the audio data.
+        if metadata_pos != 0 && source.is_seekable() {
+            source.seek(SeekFrom::Start(metadata_pos))?;
+
+            let mut builder = MetadataBuilder::new();
+
+            match id3v2::read_id3v2(&mut source, &mut builder) {
+                Ok(_) => metadata.push(builder.metadata()),
+                Err(err) => warn!("failed to read metadata chunk: {}", err),
+            }
+
+            source.seek(SeekFrom::Start(data_start_pos))?;
+        }
+
+        let num_channels = params.channels.unwrap().count() as u64;
+
+        Ok(DsfReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            data_start_pos,
+            data_end_pos: data_start_pos + data_len,
+            packet_len: block_len * num_channels,
+            packet_dur: block_len * 8,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let n_frames = self.tracks[0].codec_params.n_frames.unwrap_or(u64::MAX);
+
+        // The final block is padded, so the number of samples determines the end of the stream.
+        if self.next_packet_ts >= n_frames
+            || self.reader.pos() + self.packet_len > self.data_end_pos
+        {
+            return end_of_stream_error();
+        }
+
+        let buf = self.reader.read_boxed_slice_exact(self.packet_len as usize)?;
+
+        let ts = self.next_packet_ts;
+        let dur = self.packet_dur.min(n_frames - ts);
+
+        self.next_packet_ts += self.packet_dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Every packet has the same length and duration.
+        let packet_idx = required_ts / self.packet_dur;
+
+        if self.reader.is_seekable() {
+            let pos = self.data_start_pos + packet_idx * self.packet_len;
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let cur_packet_idx = self.next_packet_ts / self.packet_dur;
+
+            if packet_idx < cur_packet_idx {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes((packet_idx - cur_packet_idx) * self.packet_len)?;
+        }
+
+        self.next_packet_ts = packet_idx * self.packet_dur;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_fmt_chunk() {
+        // A stereo DSD64 stream, LSB-first, with blocks of 4096 bytes.
+        let mut buf = Vec::new();
+
+        for field in [1u32, 0, 2, 2, 2_822_400, 1] {
+            buf.extend_from_slice(&field.to_le_bytes());
+        }
+
+        buf.extend_from_slice(&5_644_800u64.to_le_bytes());
+        buf.extend_from_slice(&4096u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+
+        let info = read_fmt_chunk(&mut BufReader::new(&buf)).unwrap();
+
+        assert_eq!(info.params.codec, CODEC_TYPE_DSD_LSBF_PLANAR);
+        assert_eq!(info.params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+        assert_eq!(info.params.n_frames, Some(5_644_800));
+        assert_eq!(info.block_len, 4096);
+
+        // The channel count must match the channel type.
+        buf[12] = 3;
+        assert!(read_fmt_chunk(&mut BufReader::new(&buf)).is_err());
+        buf[12] = 2;
+
+        // Any block size other than 4096 bytes is invalid.
+        buf[32..36].copy_from_slice(&0x4000_0000u32.to_le_bytes());
+        assert!(read_fmt_chunk(&mut BufReader::new(&buf)).is_err());
+    }
+}
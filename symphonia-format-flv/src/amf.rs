@@ -0,0 +1,151 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+/// The maximum nesting depth of objects and arrays.
+const MAX_DEPTH: usize = 16;
+
+/// An AMF0 value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AmfValue {
+    Number(f64),
+    Boolean(bool),
+    String(String),
+    /// An object, or an ECMA array, of named values.
+    Object(Vec<(String, AmfValue)>),
+    /// A strict array.
+    Array(Vec<AmfValue>),
+    /// A date, in milliseconds since the Unix epoch.
+    Date(f64),
+    /// A null, or undefined, value.
+    Null,
+}
+
+fn read_string(reader: &mut BufReader<'_>, len: usize) -> Result<String> {
+    Ok(String::from_utf8_lossy(&reader.read_boxed_slice_exact(len)?).into_owned())
+}
+
+/// Reads the named values of an object until the object end marker.
+fn read_properties(reader: &mut BufReader<'_>, depth: usize) -> Result<Vec<(String, AmfValue)>> {
+    let mut properties = Vec::new();
+
+    loop {
+        let len = usize::from(reader.read_be_u16()?);
+
+        // The object end marker is an empty name followed by the object end type.
+        if len == 0 && reader.read_u8()? == 0x09 {
+            break;
+        }
+
+        let name = read_string(reader, len)?;
+        let value = read_value(reader, depth + 1)?;
+
+        properties.push((name, value));
+    }
+
+    Ok(properties)
+}
+
+/// Reads an AMF0 value.
+pub fn read_value(reader: &mut BufReader<'_>, depth: usize) -> Result<AmfValue> {
+    if depth > MAX_DEPTH {
+        return decode_error("flv: amf values nested too deeply");
+    }
+
+    let value = match reader.read_u8()? {
+        0x00 => AmfValue::Number(reader.read_be_f64()?),
+        0x01 => AmfValue::Boolean(reader.read_u8()? != 0),
+        0x02 => {
+            let len = usize::from(reader.read_be_u16()?);
+            AmfValue::String(read_string(reader, len)?)
+        }
+        0x03 => AmfValue::Object(read_properties(reader, depth)?),
+        0x05 | 0x06 => AmfValue::Null,
+        0x08 => {
+            // The number of entries of an ECMA array is only a hint.
+            let _count = reader.read_be_u32()?;
+            AmfValue::Object(read_properties(reader, depth)?)
+        }
+        0x0a => {
+            let count = reader.read_be_u32()?;
+
+            let mut values = Vec::new();
+
+            for _ in 0..count {
+                values.push(read_value(reader, depth + 1)?);
+            }
+
+            AmfValue::Array(values)
+        }
+        0x0b => {
+            let date = reader.read_be_f64()?;
+            let _time_zone = reader.read_be_u16()?;
+            AmfValue::Date(date)
+        }
+        0x0c => {
+            let len = reader.read_be_u32()? as usize;
+            AmfValue::String(read_string(reader, len)?)
+        }
+        _ => return decode_error("flv: unsupported amf value type"),
+    };
+
+    Ok(value)
+}
+
+/// Reads the properties of the `onMetaData` script data object, if the script data is the
+/// `onMetaData` object.
+pub fn read_on_meta_data(buf: &[u8]) -> Result<Option<Vec<(String, AmfValue)>>> {
+    let mut reader = BufReader::new(buf);
+
+    if read_value(&mut reader, 0)? != AmfValue::String("onMetaData".to_string()) {
+        return Ok(None);
+    }
+
+    match read_value(&mut reader, 0)? {
+        AmfValue::Object(properties) => Ok(Some(properties)),
+        _ => decode_error("flv: invalid onMetaData object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_on_meta_data() {
+        let mut buf = vec![0x02, 0x00, 0x0a];
+        buf.extend_from_slice(b"onMetaData");
+
+        // An ECMA array with a number, a string, and a strict array.
+        buf.extend_from_slice(&[0x08, 0x00, 0x00, 0x00, 0x03]);
+        buf.extend_from_slice(&[0x00, 0x08]);
+        buf.extend_from_slice(b"duration");
+        buf.push(0x00);
+        buf.extend_from_slice(&12.5f64.to_be_bytes());
+        buf.extend_from_slice(&[0x00, 0x07]);
+        buf.extend_from_slice(b"encoder");
+        buf.extend_from_slice(&[0x02, 0x00, 0x04]);
+        buf.extend_from_slice(b"Lavf");
+        buf.extend_from_slice(&[0x00, 0x05]);
+        buf.extend_from_slice(b"times");
+        buf.extend_from_slice(&[0x0a, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01]);
+        buf.extend_from_slice(&[0x00, 0x00, 0x09]);
+
+        let properties = read_on_meta_data(&buf).unwrap().unwrap();
+
+        assert_eq!(
+            properties,
+            vec![
+                ("duration".to_string(), AmfValue::Number(12.5)),
+                ("encoder".to_string(), AmfValue::String("Lavf".to_string())),
+                ("times".to_string(), AmfValue::Array(vec![AmfValue::Boolean(true)])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,699 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AAC};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, StandardTagKey, Tag, Value};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_utils_mpeg::frame::{read_aac_config, EsCodec, Frame, FrameParser, StreamInfo};
+
+use log::{debug, warn};
+
+use crate::amf::{read_on_meta_data, AmfValue};
+use crate::tag::{or_eof, read_tag, sync_tag, TagHeader};
+use crate::tag::{PREV_TAG_SIZE_LEN, TAG_HEADER_LEN, TAG_TYPE_AUDIO, TAG_TYPE_SCRIPT};
+
+/// The length of the FLV file header.
+const FLV_HEADER_LEN: u32 = 9;
+
+/// The sound format of MP3 audio.
+const SOUND_FORMAT_MP3: u8 = 2;
+
+/// The sound format of AAC audio.
+const SOUND_FORMAT_AAC: u8 = 10;
+
+/// The sound format of MP3 audio sampled at 8 kHz.
+const SOUND_FORMAT_MP3_8K: u8 = 14;
+
+/// The AAC packet type of the AudioSpecificConfig.
+const AAC_SEQUENCE_HEADER: u8 = 0;
+
+/// The AAC packet type of a raw AAC frame.
+const AAC_RAW: u8 = 1;
+
+/// The maximum number of bytes to read to find the first audio frame.
+const MAX_PROBE_LEN: u64 = 4 * 1024 * 1024;
+
+/// The maximum number of bytes at the end of the stream to search for the last audio tag.
+const DURATION_SCAN_LEN: u64 = 4 * 1024 * 1024;
+
+/// The granularity, in bytes, of the search for the position to seek to.
+const SEEK_GRANULARITY: u64 = 4096;
+
+/// Maps the names of the properties of the `onMetaData` object to standard tag keys.
+const META_DATA_KEYS: &[(&str, StandardTagKey)] = &[
+    ("album", StandardTagKey::Album),
+    ("artist", StandardTagKey::Artist),
+    ("comment", StandardTagKey::Comment),
+    ("copyright", StandardTagKey::Copyright),
+    ("creationdate", StandardTagKey::Date),
+    ("date", StandardTagKey::Date),
+    ("encoder", StandardTagKey::Encoder),
+    ("genre", StandardTagKey::Genre),
+    ("title", StandardTagKey::TrackTitle),
+];
+
+/// The parser of the audio tags of a stream.
+enum Parser {
+    /// Raw AAC frames, the codec parameters once the AudioSpecificConfig has been read, and the
+    /// number of samples per channel in a frame.
+    Aac(Option<StreamInfo>, u64),
+    /// MPEG audio frames.
+    Mpa(FrameParser),
+}
+
+/// The state of the audio stream.
+struct AudioStream {
+    sound_format: u8,
+    parser: Parser,
+    /// The frames parsed, but not yet returned. The presentation timestamp of a frame is the
+    /// timestamp of its tag in milliseconds.
+    frames: VecDeque<Frame>,
+    sample_rate: u32,
+    /// The timestamp of the start of the stream in milliseconds.
+    start_ms: u32,
+    /// The timestamp of the next packet.
+    next_ts: u64,
+    /// If true, the timestamp of the next packet is taken from the timestamp of its tag.
+    resync: bool,
+}
+
+impl AudioStream {
+    fn new(sound_format: u8) -> Option<Self> {
+        let parser = match sound_format {
+            SOUND_FORMAT_AAC => Parser::Aac(None, 0),
+            SOUND_FORMAT_MP3 | SOUND_FORMAT_MP3_8K => Parser::Mpa(FrameParser::new(EsCodec::Mpa)),
+            _ => return None,
+        };
+
+        Some(AudioStream {
+            sound_format,
+            parser,
+            frames: VecDeque::new(),
+            sample_rate: 0,
+            start_ms: 0,
+            next_ts: 0,
+            resync: true,
+        })
+    }
+
+    fn reset(&mut self) {
+        if let Parser::Mpa(parser) = &mut self.parser {
+            parser.reset();
+        }
+        self.frames.clear();
+        self.resync = true;
+    }
+
+    fn info(&self) -> Option<&StreamInfo> {
+        match &self.parser {
+            Parser::Aac(info, _) => info.as_ref(),
+            Parser::Mpa(parser) => parser.info.as_ref(),
+        }
+    }
+
+    /// Adds the data of an audio tag, and queues the frames completed by it.
+    fn push(&mut self, data: &[u8], timestamp: u32) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if data[0] >> 4 != self.sound_format {
+            warn!("flv: ignoring audio tag with a different sound format");
+            return Ok(());
+        }
+
+        match &mut self.parser {
+            Parser::Aac(info, frame_len) => {
+                if data.len() < 2 {
+                    return decode_error("flv: aac tag too short");
+                }
+
+                match data[1] {
+                    AAC_SEQUENCE_HEADER => {
+                        let (sample_rate, channels, samples) = read_aac_config(&data[2..])?;
+
+                        match info {
+                            Some(info) if info.extra_data.as_deref() != Some(&data[2..]) => {
+                                warn!("flv: ignoring changed audio specific config");
+                            }
+                            Some(_) => (),
+                            None => {
+                                *info = Some(StreamInfo {
+                                    codec: CODEC_TYPE_AAC,
+                                    sample_rate,
+                                    channels,
+                                    extra_data: Some(Box::from(&data[2..])),
+                                });
+                                *frame_len = samples;
+                            }
+                        }
+                    }
+                    AAC_RAW => {
+                        if info.is_none() {
+                            warn!("flv: ignoring aac frame preceding the audio specific config");
+                        }
+                        else {
+                            self.frames.push_back(Frame {
+                                data: Box::from(&data[2..]),
+                                pts: Some(u64::from(timestamp)),
+                                dur: *frame_len,
+                            });
+                        }
+                    }
+                    _ => warn!("flv: ignoring aac tag with an unknown packet type"),
+                }
+            }
+            Parser::Mpa(parser) => {
+                parser.push(&data[1..], Some(u64::from(timestamp)));
+
+                while let Some(frame) = parser.next_frame(false)? {
+                    self.frames.push_back(frame);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queues the remaining frames at the end of the stream.
+    fn flush(&mut self) -> Result<()> {
+        if let Parser::Mpa(parser) = &mut self.parser {
+            while let Some(frame) = parser.next_frame(true)? {
+                self.frames.push_back(frame);
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a tag timestamp in milliseconds to a timestamp in samples relative to the start of
+    /// the stream. Timestamps preceding the start are clamped to 0.
+    fn ms_to_ts(&self, ms: u32) -> u64 {
+        let delta = i64::from(ms) - i64::from(self.start_ms);
+        delta.max(0) as u64 * u64::from(self.sample_rate) / 1000
+    }
+
+    /// Gets the timestamp of a frame.
+    fn timestamp(&self, frame: &Frame) -> u64 {
+        match frame.pts {
+            Some(ms) => {
+                let ts = self.ms_to_ts(ms as u32);
+
+                // Tag timestamps are rounded to milliseconds, therefore, small deviations from the
+                // expected timestamp are ignored.
+                let delta = if ts > self.next_ts { ts - self.next_ts } else { self.next_ts - ts };
+
+                if self.resync || delta > frame.dur / 2 {
+                    ts
+                }
+                else {
+                    self.next_ts
+                }
+            }
+            None => self.next_ts,
+        }
+    }
+
+    /// Gets the packet of a frame.
+    fn packet(&mut self, frame: Frame) -> Packet {
+        let ts = self.timestamp(&frame);
+
+        if frame.pts.is_some() {
+            self.resync = false;
+        }
+
+        self.next_ts = ts + frame.dur;
+
+        Packet::new_from_boxed_slice(0, ts, frame.dur, frame.data)
+    }
+}
+
+/// Gets the tags of the properties of the `onMetaData` object that are strings, numbers, or
+/// booleans.
+fn read_meta_data_tags(properties: Vec<(String, AmfValue)>) -> Vec<Tag> {
+    let mut tags = Vec::new();
+
+    for (name, value) in properties {
+        let value = match value {
+            AmfValue::String(value) if !value.is_empty() => Value::from(value),
+            AmfValue::Number(value) => Value::from(value),
+            AmfValue::Boolean(value) => Value::from(value),
+            _ => continue,
+        };
+
+        let std_key = META_DATA_KEYS
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+            .map(|&(_, std_key)| std_key);
+
+        tags.push(Tag::new(std_key, &name, value));
+    }
+
+    tags
+}
+
+/// Flash Video (FLV) format reader.
+///
+/// `FlvReader` implements a demuxer for the audio stream of an FLV file, or a stream of FLV tags
+/// such as an RTMP dump. AAC and MP3 audio are supported. The audio stream is exposed as a track
+/// with a track ID of 0, and a time base of one sample. Timestamps are measured from the first
+/// audio frame.
+pub struct FlvReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    stream: Option<AudioStream>,
+    /// The buffer of a tag.
+    buf: Vec<u8>,
+    /// The position of the size of the previous tag preceding the first tag.
+    data_start_pos: u64,
+    /// The duration of the stream in seconds from the `onMetaData` object, if any.
+    meta_duration: Option<f64>,
+}
+
+impl QueryDescriptor for FlvReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("flv", "Flash Video", &["flv"], &["video/x-flv"], &[b"FLV"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FlvReader {
+    fn stream(&mut self) -> &mut AudioStream {
+        self.stream.as_mut().unwrap()
+    }
+
+    /// Handles a script data tag.
+    fn read_script_data(&mut self) {
+        match read_on_meta_data(&self.buf) {
+            Ok(Some(properties)) => {
+                for (name, value) in properties.iter() {
+                    if let ("duration", AmfValue::Number(duration)) = (name.as_str(), value) {
+                        self.meta_duration = Some(*duration);
+                    }
+                }
+
+                let tags = read_meta_data_tags(properties);
+
+                if !tags.is_empty() {
+                    let mut builder = MetadataBuilder::new();
+
+                    for tag in tags {
+                        builder.add_tag(tag);
+                    }
+
+                    self.metadata.push(builder.metadata());
+                }
+            }
+            Ok(None) => (),
+            Err(err) => warn!("{}", err),
+        }
+    }
+
+    /// Gets the next frame, or `None` at the end of the stream.
+    fn next_frame(&mut self) -> Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.stream().frames.pop_front() {
+                return Ok(Some(frame));
+            }
+
+            match read_tag(&mut self.reader, &mut self.buf)? {
+                Some(header) if header.tag_type == TAG_TYPE_AUDIO && !header.filtered => {
+                    let stream = self.stream.as_mut().unwrap();
+
+                    if let Err(err) = stream.push(&self.buf, header.timestamp) {
+                        warn!("{}", err);
+                    }
+                }
+                Some(_) => (),
+                None => {
+                    // Any frames remaining at the end of the stream are complete.
+                    self.stream().flush()?;
+
+                    if self.stream().frames.is_empty() {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads tags until the codec parameters of the audio stream are known, and its first frame
+    /// has been read.
+    fn read_stream(&mut self) -> Result<()> {
+        loop {
+            if self.reader.pos() - self.data_start_pos > MAX_PROBE_LEN {
+                break;
+            }
+
+            let header = match read_tag(&mut self.reader, &mut self.buf)? {
+                Some(header) => header,
+                None => {
+                    if let Some(stream) = self.stream.as_mut() {
+                        stream.flush()?;
+                    }
+                    break;
+                }
+            };
+
+            match header.tag_type {
+                TAG_TYPE_SCRIPT => self.read_script_data(),
+                TAG_TYPE_AUDIO if header.filtered => {
+                    return unsupported_error("flv: encrypted audio is not supported");
+                }
+                TAG_TYPE_AUDIO if !self.buf.is_empty() => {
+                    if self.stream.is_none() {
+                        let sound_format = self.buf[0] >> 4;
+
+                        debug!("found audio stream with sound format {}", sound_format);
+
+                        match AudioStream::new(sound_format) {
+                            Some(stream) => self.stream = Some(stream),
+                            None => return unsupported_error("flv: unsupported audio codec"),
+                        }
+                    }
+
+                    let stream = self.stream.as_mut().unwrap();
+
+                    if let Err(err) = stream.push(&self.buf, header.timestamp) {
+                        warn!("{}", err);
+                    }
+
+                    if stream.info().is_some() && !stream.frames.is_empty() {
+                        return Ok(());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        match &self.stream {
+            Some(stream) if stream.info().is_some() && !stream.frames.is_empty() => Ok(()),
+            _ => unsupported_error("flv: no supported audio stream"),
+        }
+    }
+
+    /// Gets the timestamp in milliseconds of the last audio tag, if the stream is seekable. The
+    /// tags are read backwards from the end of the stream using the size of the previous tag that
+    /// follows every tag.
+    fn read_last_timestamp(&mut self) -> Result<Option<u32>> {
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) if self.reader.is_seekable() => byte_len,
+            _ => return Ok(None),
+        };
+
+        let pos = self.reader.pos();
+
+        let mut tag_end = byte_len;
+        let mut last = None;
+
+        while last.is_none() && byte_len - tag_end < DURATION_SCAN_LEN {
+            if tag_end < self.data_start_pos + (PREV_TAG_SIZE_LEN + TAG_HEADER_LEN) as u64 {
+                break;
+            }
+
+            self.reader.seek(SeekFrom::Start(tag_end - PREV_TAG_SIZE_LEN as u64))?;
+
+            let prev_tag_size = match or_eof(self.reader.read_be_u32())? {
+                Some(prev_tag_size) => u64::from(prev_tag_size),
+                None => break,
+            };
+
+            if prev_tag_size < TAG_HEADER_LEN as u64
+                || prev_tag_size + (PREV_TAG_SIZE_LEN as u64) > tag_end - self.data_start_pos
+            {
+                break;
+            }
+
+            let tag_start = tag_end - PREV_TAG_SIZE_LEN as u64 - prev_tag_size;
+
+            self.reader.seek(SeekFrom::Start(tag_start))?;
+
+            let mut header_buf = [0; TAG_HEADER_LEN];
+
+            if or_eof(self.reader.read_buf_exact(&mut header_buf))?.is_none() {
+                break;
+            }
+
+            let header = match TagHeader::parse(&header_buf) {
+                Some(header)
+                    if u64::from(header.data_len) + TAG_HEADER_LEN as u64 == prev_tag_size =>
+                {
+                    header
+                }
+                _ => break,
+            };
+
+            if header.tag_type == TAG_TYPE_AUDIO {
+                last = Some(header.timestamp);
+            }
+
+            tag_end = tag_start;
+        }
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        Ok(last)
+    }
+
+    /// Gets the timestamp of the first audio frame of a tag starting between two positions.
+    fn read_tag_ts(&mut self, pos: u64, end: u64) -> Result<Option<u64>> {
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        if !sync_tag(&mut self.reader)? {
+            return Ok(None);
+        }
+
+        while self.reader.pos() < end {
+            let header = match read_tag(&mut self.reader, &mut self.buf)? {
+                Some(header) => header,
+                None => break,
+            };
+
+            if header.tag_type != TAG_TYPE_AUDIO || self.buf.len() < 2 {
+                continue;
+            }
+
+            // The AudioSpecificConfig of AAC streams may be repeated, but is not a frame.
+            if self.buf[0] >> 4 == SOUND_FORMAT_AAC && self.buf[1] != AAC_RAW {
+                continue;
+            }
+
+            return Ok(Some(self.stream().ms_to_ts(header.timestamp)));
+        }
+
+        Ok(None)
+    }
+
+    /// Gets a position such that the first audio frame of a tag after it starts at, or before, a
+    /// timestamp.
+    fn find_seek_pos(&mut self, ts: u64) -> Result<u64> {
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) => byte_len,
+            None => return Ok(self.data_start_pos),
+        };
+
+        let mut lo = 0;
+        let mut hi = byte_len.saturating_sub(self.data_start_pos) / SEEK_GRANULARITY;
+
+        // Bisect the stream. The first audio frame after position `lo` starts at, or before, the
+        // timestamp, while the first audio frame after position `hi` does not.
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+
+            let pos = self.data_start_pos + mid * SEEK_GRANULARITY;
+            let end = self.data_start_pos + hi * SEEK_GRANULARITY;
+
+            match self.read_tag_ts(pos, end)? {
+                Some(tag_ts) if tag_ts <= ts => lo = mid,
+                _ => hi = mid,
+            }
+        }
+
+        Ok(self.data_start_pos + lo * SEEK_GRANULARITY)
+    }
+}
+
+impl FormatReader for FlvReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut header = [0; FLV_HEADER_LEN as usize];
+        source.read_buf_exact(&mut header)?;
+
+        if &header[..3] != b"FLV" {
+            return unsupported_error("flv: missing flv signature");
+        }
+
+        if header[3] != 1 {
+            return unsupported_error("flv: unsupported flv version");
+        }
+
+        // The header may be extended in future versions.
+        let header_len = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+
+        if header_len < FLV_HEADER_LEN {
+            return decode_error("flv: invalid header length");
+        }
+
+        source.ignore_bytes(u64::from(header_len - FLV_HEADER_LEN))?;
+
+        let data_start_pos = source.pos();
+
+        // The size of the previous tag preceding the first tag is always 0.
+        let _prev_tag_size = source.read_be_u32()?;
+
+        let mut reader = FlvReader {
+            reader: source,
+            tracks: Vec::new(),
+            cues: Vec::new(),
+            metadata: Default::default(),
+            stream: None,
+            buf: Vec::new(),
+            data_start_pos,
+            meta_duration: None,
+        };
+
+        reader.read_stream()?;
+
+        let stream = reader.stream.as_mut().unwrap();
+
+        let info = stream.info().unwrap().clone();
+        let sample_rate = info.sample_rate;
+
+        let first = stream.frames.front().unwrap();
+
+        stream.sample_rate = sample_rate;
+        stream.start_ms = first.pts.unwrap_or(0) as u32;
+
+        let frame_dur = first.dur;
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(info.codec)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate));
+
+        if let Some(channels) = info.channels {
+            params.with_channels(channels);
+        }
+
+        if let Some(extra_data) = info.extra_data {
+            params.with_extra_data(extra_data);
+        }
+
+        // The duration is estimated from the timestamp of the last audio tag, or the duration of
+        // the onMetaData object.
+        let n_frames = match reader.read_last_timestamp()? {
+            Some(last_ms) => Some(reader.stream().ms_to_ts(last_ms) + frame_dur),
+            None => reader
+                .meta_duration
+                .filter(|duration| duration.is_finite() && *duration > 0.0)
+                .map(|duration| (duration * f64::from(sample_rate)) as u64),
+        };
+
+        if let Some(n_frames) = n_frames {
+            params.with_n_frames(n_frames);
+        }
+
+        reader.tracks.push(Track::new(0, params));
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        match self.next_frame()? {
+            Some(frame) => Ok(self.stream().packet(frame)),
+            None => end_of_stream_error(),
+        }
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let track_id = match to {
+            SeekTo::TimeStamp { track_id, .. } => track_id,
+            SeekTo::Time { track_id, .. } => track_id.unwrap_or(0),
+        };
+
+        if track_id != 0 {
+            return seek_error(SeekErrorKind::InvalidTrack);
+        }
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.stream().sample_rate).calc_timestamp(time)
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if self.reader.is_seekable() {
+            let pos = self.find_seek_pos(required_ts)?;
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            sync_tag(&mut self.reader)?;
+
+            self.stream().reset();
+        }
+        else if self.stream().next_ts > required_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // Read frames until the frame containing the required timestamp.
+        let actual_ts = loop {
+            let frame = match self.next_frame()? {
+                Some(frame) => frame,
+                None => return seek_error(SeekErrorKind::OutOfRange),
+            };
+
+            let stream = self.stream();
+
+            let ts = stream.timestamp(&frame);
+
+            if ts + frame.dur.max(1) > required_ts {
+                stream.frames.push_front(frame);
+                break ts;
+            }
+
+            // Frames preceding the required timestamp are discarded.
+            stream.packet(frame);
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,174 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io;
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::{MediaSourceStream, ReadBytes, SeekBuffered};
+
+use log::warn;
+
+/// The tag type of audio tags.
+pub const TAG_TYPE_AUDIO: u8 = 8;
+
+/// The tag type of video tags.
+const TAG_TYPE_VIDEO: u8 = 9;
+
+/// The tag type of script data tags.
+pub const TAG_TYPE_SCRIPT: u8 = 18;
+
+/// The length of a tag header.
+pub const TAG_HEADER_LEN: usize = 11;
+
+/// The length of the size of the previous tag that follows every tag.
+pub const PREV_TAG_SIZE_LEN: usize = 4;
+
+/// The header of a tag.
+#[derive(Copy, Clone, Debug)]
+pub struct TagHeader {
+    pub tag_type: u8,
+    /// If true, the tag data is encrypted.
+    pub filtered: bool,
+    pub data_len: u32,
+    /// The timestamp of the tag in milliseconds.
+    pub timestamp: u32,
+}
+
+impl TagHeader {
+    /// Parses a tag header. Returns `None` if the header is invalid.
+    pub fn parse(buf: &[u8; TAG_HEADER_LEN]) -> Option<Self> {
+        // The reserved bits, and the stream ID, must be 0.
+        if buf[0] & 0xc0 != 0 || buf[8..11] != [0, 0, 0] {
+            return None;
+        }
+
+        let tag_type = buf[0] & 0x1f;
+
+        if !matches!(tag_type, TAG_TYPE_AUDIO | TAG_TYPE_VIDEO | TAG_TYPE_SCRIPT) {
+            return None;
+        }
+
+        let data_len = u32::from_be_bytes([0, buf[1], buf[2], buf[3]]);
+
+        // The lower 24 bits of the timestamp are followed by the upper 8 bits.
+        let timestamp = u32::from_be_bytes([buf[7], buf[4], buf[5], buf[6]]);
+
+        Some(TagHeader { tag_type, filtered: buf[0] & 0x20 != 0, data_len, timestamp })
+    }
+}
+
+/// Converts an unexpected end of stream into `None`.
+pub fn or_eof<T>(result: io::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Synchronizes to the next valid tag header preceded by a plausible previous tag size. Returns
+/// false at the end of the stream.
+pub fn sync_tag(reader: &mut MediaSourceStream) -> Result<bool> {
+    let mut window = [0; PREV_TAG_SIZE_LEN + TAG_HEADER_LEN];
+    let mut len = 0;
+
+    loop {
+        let byte = match or_eof(reader.read_byte())? {
+            Some(byte) => byte,
+            None => return Ok(false),
+        };
+
+        window.copy_within(1.., 0);
+        window[window.len() - 1] = byte;
+        len += 1;
+
+        if len < window.len() {
+            continue;
+        }
+
+        let prev_tag_size = u32::from_be_bytes([window[0], window[1], window[2], window[3]]);
+
+        // The previous tag size includes the header of the previous tag.
+        if prev_tag_size != 0 && prev_tag_size < TAG_HEADER_LEN as u32 {
+            continue;
+        }
+
+        let mut header = [0; TAG_HEADER_LEN];
+        header.copy_from_slice(&window[PREV_TAG_SIZE_LEN..]);
+
+        if TagHeader::parse(&header).is_some() {
+            if len > window.len() {
+                warn!("flv: skipped {} bytes to synchronize", len - window.len());
+            }
+
+            reader.seek_buffered_rev(TAG_HEADER_LEN);
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads the next tag into the buffer, and returns its header. Returns `None` at the end of the
+/// stream.
+pub fn read_tag(reader: &mut MediaSourceStream, buf: &mut Vec<u8>) -> Result<Option<TagHeader>> {
+    loop {
+        let mut header_buf = [0; TAG_HEADER_LEN];
+
+        if or_eof(reader.read_buf_exact(&mut header_buf))?.is_none() {
+            return Ok(None);
+        }
+
+        let header = match TagHeader::parse(&header_buf) {
+            Some(header) => header,
+            None => {
+                warn!("flv: invalid tag header");
+
+                reader.seek_buffered_rev(TAG_HEADER_LEN - 1);
+
+                if !sync_tag(reader)? {
+                    return Ok(None);
+                }
+
+                continue;
+            }
+        };
+
+        buf.clear();
+        buf.resize(header.data_len as usize, 0);
+
+        // A tag cut short by the end of the stream is discarded.
+        if or_eof(reader.read_buf_exact(buf))?.is_none() {
+            return Ok(None);
+        }
+
+        // The size of the tag follows the tag, but is not required.
+        let _prev_tag_size = or_eof(reader.read_be_u32())?;
+
+        return Ok(Some(header));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_parse_tag_header() {
+        let header =
+            TagHeader::parse(&[0x08, 0x00, 0x01, 0x02, 0x12, 0x34, 0x56, 0x78, 0, 0, 0]).unwrap();
+
+        assert_eq!(header.tag_type, TAG_TYPE_AUDIO);
+        assert!(!header.filtered);
+        assert_eq!(header.data_len, 0x102);
+        assert_eq!(header.timestamp, 0x7812_3456);
+
+        // A non-zero stream ID.
+        assert!(TagHeader::parse(&[0x08, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1]).is_none());
+
+        // An unknown tag type.
+        assert!(TagHeader::parse(&[0x07, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]).is_none());
+    }
+}
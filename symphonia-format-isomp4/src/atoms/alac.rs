@@ -39,9 +39,10 @@ impl Atom for AlacAtom {
             return decode_error("isomp4 (alac): invalid alac atom length");
         }
 
-        // The ALAC magic cookie (aka extra data) is either 24 or 48 bytes long.
+        // The ALAC magic cookie (aka extra data) is either 24 or 48 bytes long, but some muxers
+        // append additional atoms. These are passed to the decoder which will ignore them.
         let magic_len = match header.data_len - AtomHeader::EXTRA_DATA_SIZE {
-            len @ 24 | len @ 48 => len as usize,
+            len if len >= 24 => len as usize,
             _ => return decode_error("isomp4 (alac): invalid magic cookie length"),
         };
 
@@ -0,0 +1,67 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::ReadBytes;
+
+use crate::atoms::{Atom, AtomHeader};
+
+/// The minimum length of a chapter entry.
+const MIN_ENTRY_LEN: u64 = 9;
+
+/// A chapter entry of a Nero chapter list.
+#[derive(Debug)]
+pub struct ChplEntry {
+    /// The start time of the chapter in units of 100 nanoseconds.
+    pub start: u64,
+    /// The title of the chapter.
+    pub title: String,
+}
+
+/// Nero chapter list atom.
+#[derive(Debug)]
+pub struct ChplAtom {
+    /// Atom header.
+    header: AtomHeader,
+    /// Chapter entries.
+    pub entries: Vec<ChplEntry>,
+}
+
+impl Atom for ChplAtom {
+    fn header(&self) -> AtomHeader {
+        self.header
+    }
+
+    fn read<B: ReadBytes>(reader: &mut B, header: AtomHeader) -> Result<Self> {
+        let (version, _) = AtomHeader::read_extra(reader)?;
+
+        // Version 1 adds a reserved byte, and widens the entry count to 32 bits.
+        let (entry_count, entries_len) = match version {
+            0 => (u32::from(reader.read_u8()?), header.data_len.saturating_sub(5)),
+            _ => {
+                reader.read_u8()?;
+                (reader.read_be_u32()?, header.data_len.saturating_sub(9))
+            }
+        };
+
+        // Do not trust the entry count to allocate the table.
+        let entry_count = u64::from(entry_count).min(entries_len / MIN_ENTRY_LEN);
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+
+        for _ in 0..entry_count {
+            let start = reader.read_be_u64()?;
+
+            let title_len = reader.read_u8()?;
+            let title = reader.read_boxed_slice_exact(usize::from(title_len))?;
+
+            entries.push(ChplEntry { start, title: String::from_utf8_lossy(&title).to_string() });
+        }
+
+        Ok(ChplAtom { header, entries })
+    }
+}
@@ -12,7 +12,7 @@ use symphonia_core::meta::{
 };
 use symphonia_core::meta::{Value, Visual};
 use symphonia_core::util::bits;
-use symphonia_metadata::{id3v1, itunes};
+use symphonia_metadata::{id3v1, itunes, rating};
 
 use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType};
 
@@ -274,6 +274,30 @@ fn add_generic_tag<B: ReadBytes>(
     Ok(())
 }
 
+fn add_rating_tag<B: ReadBytes>(
+    iter: &mut AtomIterator<B>,
+    builder: &mut MetadataBuilder,
+) -> Result<()> {
+    let tag = iter.read_atom::<MetaTagAtom>()?;
+
+    for value_atom in tag.values.iter() {
+        if let Some(value) = parse_tag_value(value_atom.data_type, &value_atom.data) {
+            // The scale of the rating depends on the application, so add the rating normalized
+            // along with the raw rating.
+            let normalized = rating::from_value(&value);
+
+            for tag in rating::make_tags(Some(StandardTagKey::Rating), "rate", value, normalized) {
+                builder.add_tag(tag);
+            }
+        }
+        else {
+            warn!("unsupported data type {:?} for rating tag", value_atom.data_type);
+        }
+    }
+
+    Ok(())
+}
+
 fn add_var_unsigned_int_tag<B: ReadBytes>(
     iter: &mut AtomIterator<B>,
     builder: &mut MetadataBuilder,
@@ -410,6 +434,9 @@ fn add_media_type_tag<B: ReadBytes>(
                 9 => "Short Film",
                 10 => "TV Show",
                 11 => "Booklet",
+                14 => "Ringtone",
+                21 => "Podcast",
+                23 => "iTunes U",
                 _ => "Unknown",
             };
 
@@ -452,16 +479,24 @@ fn add_freeform_tag<B: ReadBytes>(
 ) -> Result<()> {
     let tag = iter.read_atom::<MetaTagAtom>()?;
 
-    // A user-defined tag should only have 1 value.
+    // A free-form tag without a name can not be identified.
+    if tag.name.is_none() {
+        warn!("free-form tag is missing a name");
+        return Ok(());
+    }
+
+    // Gets the fully qualified tag name.
+    let full_name = tag.full_name();
+
+    // Try to map iTunes free-form tags to standard tag keys. Unknown free-form tags are kept with
+    // their fully qualified name as the key.
+    let std_key = itunes::std_key_from_tag(&full_name);
+
+    // Multi-valued tags, such as those written by MusicBrainz Picard, store one data atom per
+    // value.
     for value_atom in tag.values.iter() {
         // Parse the value atom data into a string, if possible.
         if let Some(value) = parse_tag_value(value_atom.data_type, &value_atom.data) {
-            // Gets the fully qualified tag name.
-            let full_name = tag.full_name();
-
-            // Try to map iTunes freeform tags to standard tag keys.
-            let std_key = itunes::std_key_from_tag(&full_name);
-
             builder.add_tag(Tag::new(std_key, &full_name, value));
         }
         else {
@@ -675,8 +710,7 @@ impl Atom for IlstAtom {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Encoder))?
                 }
                 AtomType::GaplessPlaybackTag => {
-                    // TODO: Need standard tag key for gapless playback.
-                    // add_boolean_tag(&mut iter, &mut mb, )?
+                    add_boolean_tag(&mut iter, &mut mb, StandardTagKey::GaplessPlayback)?
                 }
                 AtomType::GenreTag => add_id3v1_genre_tag(&mut iter, &mut mb)?,
                 AtomType::GroupingTag => {
@@ -696,6 +730,15 @@ impl Atom for IlstAtom {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Lyrics))?
                 }
                 AtomType::MediaTypeTag => add_media_type_tag(&mut iter, &mut mb)?,
+                AtomType::MovementNameTag => {
+                    add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::MovementName))?
+                }
+                AtomType::MovementNumberTag => {
+                    add_var_signed_int_tag(&mut iter, &mut mb, StandardTagKey::MovementNumber)?
+                }
+                AtomType::MovementTotalTag => {
+                    add_var_signed_int_tag(&mut iter, &mut mb, StandardTagKey::MovementTotal)?
+                }
                 AtomType::OwnerTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Owner))?
                 }
@@ -705,9 +748,7 @@ impl Atom for IlstAtom {
                 AtomType::PurchaseDateTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::PurchaseDate))?
                 }
-                AtomType::RatingTag => {
-                    add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Rating))?
-                }
+                AtomType::RatingTag => add_rating_tag(&mut iter, &mut mb)?,
                 AtomType::SortAlbumArtistTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::SortAlbumArtist))?
                 }
@@ -753,6 +794,9 @@ impl Atom for IlstAtom {
                 AtomType::UrlPodcastTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::UrlPodcast))?
                 }
+                AtomType::WorkTag => {
+                    add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Work))?
+                }
                 AtomType::FreeFormTag => add_freeform_tag(&mut iter, &mut mb)?,
                 _ => (),
             }
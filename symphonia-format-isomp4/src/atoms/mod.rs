@@ -9,6 +9,7 @@ use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
 
 pub(crate) mod alac;
+pub(crate) mod chpl;
 pub(crate) mod co64;
 pub(crate) mod ctts;
 pub(crate) mod edts;
@@ -42,6 +43,7 @@ pub(crate) mod tfhd;
 pub(crate) mod tkhd;
 pub(crate) mod traf;
 pub(crate) mod trak;
+pub(crate) mod tref;
 pub(crate) mod trex;
 pub(crate) mod trun;
 pub(crate) mod udta;
@@ -49,6 +51,7 @@ pub(crate) mod wave;
 
 pub use self::meta::MetaAtom;
 pub use alac::AlacAtom;
+pub use chpl::ChplAtom;
 pub use co64::Co64Atom;
 #[allow(unused_imports)]
 pub use ctts::CttsAtom;
@@ -83,6 +86,7 @@ pub use tfhd::TfhdAtom;
 pub use tkhd::TkhdAtom;
 pub use traf::TrafAtom;
 pub use trak::TrakAtom;
+pub use tref::TrefAtom;
 pub use trex::TrexAtom;
 pub use trun::TrunAtom;
 pub use udta::UdtaAtom;
@@ -92,6 +96,8 @@ pub use wave::WaveAtom;
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AtomType {
     Ac3,
+    AmrNb,
+    AmrWb,
     AdvisoryTag,
     Alac,
     ALaw,
@@ -100,6 +106,8 @@ pub enum AtomType {
     ArtistLowerTag,
     ArtistTag,
     CategoryTag,
+    ChapterList,
+    ChapterTrackReference,
     ChunkOffset,
     ChunkOffset64,
     CommentTag,
@@ -112,6 +120,8 @@ pub enum AtomType {
     DateTag,
     DescriptionTag,
     DiskNumberTag,
+    Dts,
+    Eac3,
     Edit,
     EditList,
     EncodedByTag,
@@ -144,6 +154,9 @@ pub enum AtomType {
     MetaTagData,
     MetaTagMeaning,
     MetaTagName,
+    MovementNameTag,
+    MovementNumberTag,
+    MovementTotalTag,
     Movie,
     MovieExtends,
     MovieExtendsHeader,
@@ -186,7 +199,9 @@ pub enum AtomType {
     TrackFragmentRun,
     TrackHeader,
     TrackNumberTag,
+    TrackReference,
     TrackTitleTag,
+    TrueHd,
     TvEpisodeNameTag,
     TvEpisodeNumberTag,
     TvNetworkNameTag,
@@ -195,6 +210,7 @@ pub enum AtomType {
     U8SampleEntry,
     UrlPodcastTag,
     UserData,
+    WorkTag,
     Other([u8; 4]),
 }
 
@@ -203,13 +219,19 @@ impl From<[u8; 4]> for AtomType {
         match &val {
             b".mp3" => AtomType::Mp3,
             b"ac-3" => AtomType::Ac3,
+            b"samr" => AtomType::AmrNb,
+            b"sawb" => AtomType::AmrWb,
             b"alac" => AtomType::Alac,
             b"alaw" => AtomType::ALaw,
+            b"chap" => AtomType::ChapterTrackReference,
+            b"chpl" => AtomType::ChapterList,
             b"co64" => AtomType::ChunkOffset64,
             b"ctts" => AtomType::CompositionTimeToSample,
             b"data" => AtomType::MetaTagData,
             b"dfLa" => AtomType::FlacDsConfig,
             b"dOps" => AtomType::OpusDsConfig,
+            b"dtsc" | b"dtse" | b"dtsh" | b"dtsl" => AtomType::Dts,
+            b"ec-3" => AtomType::Eac3,
             b"edts" => AtomType::Edit,
             b"elst" => AtomType::EditList,
             b"esds" => AtomType::Esds,
@@ -233,6 +255,7 @@ impl From<[u8; 4]> for AtomType {
             b"minf" => AtomType::MediaInfo,
             b"moof" => AtomType::MovieFragment,
             b"moov" => AtomType::Movie,
+            b"mlpa" => AtomType::TrueHd,
             b"mp4a" => AtomType::Mp4a,
             b"mvex" => AtomType::MovieExtends,
             b"mvhd" => AtomType::MovieHeader,
@@ -253,6 +276,7 @@ impl From<[u8; 4]> for AtomType {
             b"tfhd" => AtomType::TrackFragmentHeader,
             b"tkhd" => AtomType::TrackHeader,
             b"traf" => AtomType::TrackFragment,
+            b"tref" => AtomType::TrackReference,
             b"trak" => AtomType::Track,
             b"trex" => AtomType::TrackExtends,
             b"trun" => AtomType::TrackFragmentRun,
@@ -303,8 +327,12 @@ impl From<[u8; 4]> for AtomType {
             b"\xa9gen" => AtomType::CustomGenreTag,
             b"\xa9grp" => AtomType::GroupingTag,
             b"\xa9lyr" => AtomType::LyricsTag,
+            b"\xa9mvc" => AtomType::MovementTotalTag,
+            b"\xa9mvi" => AtomType::MovementNumberTag,
+            b"\xa9mvn" => AtomType::MovementNameTag,
             b"\xa9nam" => AtomType::TrackTitleTag,
             b"\xa9too" => AtomType::EncoderTag,
+            b"\xa9wrk" => AtomType::WorkTag,
             b"\xa9wrt" => AtomType::ComposerTag,
             _ => AtomType::Other(val),
         }
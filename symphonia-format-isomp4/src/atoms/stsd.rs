@@ -7,6 +7,8 @@
 
 use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, CodecType, CODEC_TYPE_MP3, CODEC_TYPE_NULL};
+use symphonia_core::codecs::{CODEC_TYPE_AMR_NB, CODEC_TYPE_AMR_WB};
+use symphonia_core::codecs::{CODEC_TYPE_DCA, CODEC_TYPE_EAC3, CODEC_TYPE_TRUEHD};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F32LE};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_F64BE, CODEC_TYPE_PCM_F64LE};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S16LE};
@@ -59,6 +61,12 @@ impl Atom for StsdAtom {
             | AtomType::Flac
             | AtomType::Opus
             | AtomType::Mp3
+            | AtomType::Ac3
+            | AtomType::AmrNb
+            | AtomType::AmrWb
+            | AtomType::Eac3
+            | AtomType::Dts
+            | AtomType::TrueHd
             | AtomType::Lpcm
             | AtomType::QtWave
             | AtomType::ALaw
@@ -105,6 +113,23 @@ impl StsdAtom {
                 Some(AudioCodecSpecific::Mp3) => {
                     codec_params.for_codec(CODEC_TYPE_MP3);
                 }
+                Some(AudioCodecSpecific::Ac3) => {
+                    codec_params.for_codec(CODEC_TYPE_EAC3);
+                }
+                // In 3GP files the channel count of an AMR sample entry is fixed at 2, but the
+                // stream is always mono.
+                Some(AudioCodecSpecific::AmrNb) => {
+                    codec_params.for_codec(CODEC_TYPE_AMR_NB).with_channels(Channels::FRONT_LEFT);
+                }
+                Some(AudioCodecSpecific::AmrWb) => {
+                    codec_params.for_codec(CODEC_TYPE_AMR_WB).with_channels(Channels::FRONT_LEFT);
+                }
+                Some(AudioCodecSpecific::Dts) => {
+                    codec_params.for_codec(CODEC_TYPE_DCA);
+                }
+                Some(AudioCodecSpecific::TrueHd) => {
+                    codec_params.for_codec(CODEC_TYPE_TRUEHD);
+                }
                 Some(AudioCodecSpecific::Pcm(ref pcm)) => {
                     // PCM codecs.
                     codec_params
@@ -141,6 +166,16 @@ pub enum AudioCodecSpecific {
     Opus(OpusAtom),
     /// MP3.
     Mp3,
+    /// AC-3 (Dolby Digital), or E-AC-3 (Dolby Digital Plus).
+    Ac3,
+    /// Adaptive Multi-Rate Narrowband (AMR-NB).
+    AmrNb,
+    /// Adaptive Multi-Rate Wideband (AMR-WB).
+    AmrWb,
+    /// DTS Coherent Acoustics, and DTS-HD.
+    Dts,
+    /// Dolby TrueHD.
+    TrueHd,
     /// PCM codecs.
     Pcm(Pcm),
 }
@@ -529,6 +564,48 @@ fn read_audio_sample_entry<B: ReadBytes>(
         codec_specific = Some(AudioCodecSpecific::Mp3);
     }
 
+    // The AC-3 and E-AC-3 specific atoms duplicate the bit stream information present in every
+    // frame, so they are not required.
+    if header.atype == AtomType::Ac3 || header.atype == AtomType::Eac3 {
+        if codec_specific.is_some() {
+            return decode_error("isomp4: invalid sample entry");
+        }
+
+        codec_specific = Some(AudioCodecSpecific::Ac3);
+    }
+
+    // The AMR specific atom only describes the encoder, and the modes in use.
+    if header.atype == AtomType::AmrNb || header.atype == AtomType::AmrWb {
+        if codec_specific.is_some() {
+            return decode_error("isomp4: invalid sample entry");
+        }
+
+        codec_specific = Some(if header.atype == AtomType::AmrNb {
+            AudioCodecSpecific::AmrNb
+        }
+        else {
+            AudioCodecSpecific::AmrWb
+        });
+    }
+
+    // The DTS specific atom is not required since every frame is self-describing.
+    if header.atype == AtomType::Dts {
+        if codec_specific.is_some() {
+            return decode_error("isomp4: invalid sample entry");
+        }
+
+        codec_specific = Some(AudioCodecSpecific::Dts);
+    }
+
+    // The TrueHD specific atom duplicates the major sync present in the stream.
+    if header.atype == AtomType::TrueHd {
+        if codec_specific.is_some() {
+            return decode_error("isomp4: invalid sample entry");
+        }
+
+        codec_specific = Some(AudioCodecSpecific::TrueHd);
+    }
+
     Ok(SampleEntry::Audio(AudioSampleEntry {
         num_channels,
         sample_size,
@@ -536,3 +613,62 @@ fn read_audio_sample_entry<B: ReadBytes>(
         codec_specific,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    /// Build a sample description atom with a single version 0 audio sample entry of the given
+    /// type, sample rate, and nested atoms.
+    fn make_stsd(atype: &[u8; 4], sample_rate: u16, nested: &[u8]) -> Vec<u8> {
+        let mut entry = vec![0; 6];
+        // Data reference index, version, revision, and vendor.
+        entry.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // Channel count, sample size, compression ID, and packet size.
+        entry.extend_from_slice(&[0, 2, 0, 16, 0, 0, 0, 0]);
+        entry.extend_from_slice(&sample_rate.to_be_bytes());
+        entry.extend_from_slice(&[0, 0]);
+        entry.extend_from_slice(nested);
+
+        let mut buf = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        buf.extend_from_slice(&(entry.len() as u32 + 8).to_be_bytes());
+        buf.extend_from_slice(atype);
+        buf.extend_from_slice(&entry);
+        buf
+    }
+
+    fn read_codec_params(buf: &[u8]) -> CodecParameters {
+        let header = AtomHeader {
+            atype: AtomType::SampleDescription,
+            atom_len: 0,
+            data_len: buf.len() as u64,
+        };
+
+        let stsd = StsdAtom::read(&mut BufReader::new(buf), header).unwrap();
+
+        let mut params = CodecParameters::new();
+        stsd.fill_codec_params(&mut params);
+        params
+    }
+
+    #[test]
+    fn verify_amr_sample_entries() {
+        // An AMR-NB sample entry from a 3GP file, with its AMR specific atom.
+        let damr =
+            [0, 0, 0, 17, b'd', b'a', b'm', b'r', b'F', b'F', b'M', b'P', 0, 0x81, 0xff, 0, 1];
+
+        let params = read_codec_params(&make_stsd(b"samr", 8000, &damr));
+
+        assert_eq!(params.codec, CODEC_TYPE_AMR_NB);
+        assert_eq!(params.sample_rate, Some(8000));
+        assert_eq!(params.channels, Some(Channels::FRONT_LEFT));
+
+        // An AMR-WB sample entry.
+        let params = read_codec_params(&make_stsd(b"sawb", 16000, &[]));
+
+        assert_eq!(params.codec, CODEC_TYPE_AMR_WB);
+        assert_eq!(params.sample_rate, Some(16000));
+        assert_eq!(params.channels, Some(Channels::FRONT_LEFT));
+    }
+}
@@ -14,9 +14,12 @@ use crate::atoms::{Atom, AtomHeader};
 pub struct SampleDurationEntry {
     pub sample_count: u32,
     pub sample_delta: u32,
+    /// The number of the first sample of the entry.
+    pub first_sample: u32,
+    /// The timestamp of the first sample of the entry.
+    pub first_ts: u64,
 }
 
-/// Time-to-sample atom.
 #[derive(Debug)]
 pub struct SttsAtom {
     /// Atom header.
@@ -27,51 +30,41 @@ pub struct SttsAtom {
 
 impl SttsAtom {
     /// Get the timestamp and duration for the sample indicated by `sample_num`. Note, `sample_num`
-    /// is indexed relative to the `SttsAtom`. Complexity of this function in O(N).
+    /// is indexed relative to the `SttsAtom`. Complexity of this function in O(log N).
     pub fn find_timing_for_sample(&self, sample_num: u32) -> Option<(u64, u32)> {
-        let mut ts = 0;
-        let mut next_entry_first_sample = 0;
+        // Find the last entry starting at, or before, the sample. Entries with a sample count of 0
+        // start at the same sample as the following entry, and are therefore skipped.
+        let idx = self.entries.partition_point(|entry| entry.first_sample <= sample_num);
 
-        // The Stts atom compactly encodes a mapping between number of samples and sample duration.
-        // Iterate through each entry until the entry containing the next sample is found. The next
-        // packet timestamp is then the sum of the product of sample count and sample duration for
-        // the n-1 iterated entries, plus the product of the number of consumed samples in the n-th
-        // iterated entry and sample duration.
-        for entry in &self.entries {
-            next_entry_first_sample += entry.sample_count;
+        let entry = self.entries.get(idx.checked_sub(1)?)?;
 
-            if sample_num < next_entry_first_sample {
-                let entry_sample_offset = sample_num + entry.sample_count - next_entry_first_sample;
-                ts += u64::from(entry.sample_delta) * u64::from(entry_sample_offset);
+        let entry_sample_offset = sample_num - entry.first_sample;
 
-                return Some((ts, entry.sample_delta));
-            }
-
-            ts += u64::from(entry.sample_count) * u64::from(entry.sample_delta);
+        if entry_sample_offset >= entry.sample_count {
+            return None;
         }
 
-        None
+        let ts = entry.first_ts + u64::from(entry.sample_delta) * u64::from(entry_sample_offset);
+
+        Some((ts, entry.sample_delta))
     }
 
     /// Get the sample that contains the timestamp indicated by `ts`. Note, the returned `sample_num`
-    /// is indexed relative to the `SttsAtom`. Complexity of this function in O(N).
+    /// is indexed relative to the `SttsAtom`. Complexity of this function in O(log N).
     pub fn find_sample_for_timestamp(&self, ts: u64) -> Option<u32> {
-        let mut ts_accum = 0;
-        let mut sample_num = 0;
+        // Find the last entry starting at, or before, the timestamp. Entries with a total duration
+        // of 0 start at the same timestamp as the following entry, and are therefore skipped.
+        let idx = self.entries.partition_point(|entry| entry.first_ts <= ts);
 
-        for entry in &self.entries {
-            let delta = u64::from(entry.sample_delta) * u64::from(entry.sample_count);
+        let entry = self.entries.get(idx.checked_sub(1)?)?;
 
-            if ts_accum + delta > ts {
-                sample_num += ((ts - ts_accum) / u64::from(entry.sample_delta)) as u32;
-                return Some(sample_num);
-            }
+        let entry_dur = u64::from(entry.sample_delta) * u64::from(entry.sample_count);
 
-            ts_accum += delta;
-            sample_num += entry.sample_count;
+        if ts - entry.first_ts >= entry_dur {
+            return None;
         }
 
-        None
+        Some(entry.first_sample + ((ts - entry.first_ts) / u64::from(entry.sample_delta)) as u32)
     }
 }
 
@@ -86,6 +79,7 @@ impl Atom for SttsAtom {
         let entry_count = reader.read_be_u32()?;
 
         let mut total_duration = 0;
+        let mut first_sample = 0u32;
 
         // TODO: Limit table length.
         let mut entries = Vec::with_capacity(entry_count as usize);
@@ -94,11 +88,58 @@ impl Atom for SttsAtom {
             let sample_count = reader.read_be_u32()?;
             let sample_delta = reader.read_be_u32()?;
 
-            total_duration += u64::from(sample_count) * u64::from(sample_delta);
+            entries.push(SampleDurationEntry {
+                sample_count,
+                sample_delta,
+                first_sample,
+                first_ts: total_duration,
+            });
 
-            entries.push(SampleDurationEntry { sample_count, sample_delta });
+            total_duration += u64::from(sample_count) * u64::from(sample_delta);
+            first_sample = first_sample.saturating_add(sample_count);
         }
 
         Ok(SttsAtom { header, entries, total_duration })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atoms::AtomType;
+    use symphonia_core::io::BufReader;
+
+    #[test]
+    fn verify_stts_lookup() {
+        // 3 samples of 10, an empty entry, 2 samples of 0, and 2 samples of 5.
+        let entries: [(u32, u32); 4] = [(3, 10), (0, 20), (2, 0), (2, 5)];
+
+        let mut buf = vec![0, 0, 0, 0];
+        buf.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+
+        for (count, delta) in entries {
+            buf.extend_from_slice(&count.to_be_bytes());
+            buf.extend_from_slice(&delta.to_be_bytes());
+        }
+
+        let header =
+            AtomHeader { atype: AtomType::TimeToSample, atom_len: 0, data_len: buf.len() as u64 };
+
+        let stts = SttsAtom::read(&mut BufReader::new(&buf), header).unwrap();
+
+        assert_eq!(stts.total_duration, 40);
+
+        assert_eq!(stts.find_timing_for_sample(0), Some((0, 10)));
+        assert_eq!(stts.find_timing_for_sample(2), Some((20, 10)));
+        assert_eq!(stts.find_timing_for_sample(3), Some((30, 0)));
+        assert_eq!(stts.find_timing_for_sample(5), Some((30, 5)));
+        assert_eq!(stts.find_timing_for_sample(6), Some((35, 5)));
+        assert_eq!(stts.find_timing_for_sample(7), None);
+
+        assert_eq!(stts.find_sample_for_timestamp(0), Some(0));
+        assert_eq!(stts.find_sample_for_timestamp(29), Some(2));
+        assert_eq!(stts.find_sample_for_timestamp(30), Some(5));
+        assert_eq!(stts.find_sample_for_timestamp(39), Some(6));
+        assert_eq!(stts.find_sample_for_timestamp(40), None);
+    }
+}
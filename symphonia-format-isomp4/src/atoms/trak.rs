@@ -8,7 +8,9 @@
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
 
-use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType, EdtsAtom, MdiaAtom, TkhdAtom};
+use crate::atoms::{
+    Atom, AtomHeader, AtomIterator, AtomType, EdtsAtom, MdiaAtom, TkhdAtom, TrefAtom,
+};
 
 /// Track atom.
 #[allow(dead_code)]
@@ -20,6 +22,8 @@ pub struct TrakAtom {
     pub tkhd: TkhdAtom,
     /// Optional, edit list atom.
     pub edts: Option<EdtsAtom>,
+    /// Optional, track reference atom.
+    pub tref: Option<TrefAtom>,
     /// Media atom.
     pub mdia: MdiaAtom,
 }
@@ -34,6 +38,7 @@ impl Atom for TrakAtom {
 
         let mut tkhd = None;
         let mut edts = None;
+        let mut tref = None;
         let mut mdia = None;
 
         while let Some(header) = iter.next()? {
@@ -44,6 +49,9 @@ impl Atom for TrakAtom {
                 AtomType::Edit => {
                     edts = Some(iter.read_atom::<EdtsAtom>()?);
                 }
+                AtomType::TrackReference => {
+                    tref = Some(iter.read_atom::<TrefAtom>()?);
+                }
                 AtomType::Media => {
                     mdia = Some(iter.read_atom::<MdiaAtom>()?);
                 }
@@ -59,6 +67,6 @@ impl Atom for TrakAtom {
             return decode_error("isomp4: missing mdia atom");
         }
 
-        Ok(TrakAtom { header, tkhd: tkhd.unwrap(), edts, mdia: mdia.unwrap() })
+        Ok(TrakAtom { header, tkhd: tkhd.unwrap(), edts, tref, mdia: mdia.unwrap() })
     }
 }
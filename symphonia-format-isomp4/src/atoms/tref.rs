@@ -0,0 +1,47 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::ReadBytes;
+
+use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType};
+
+/// Track reference atom.
+#[derive(Debug)]
+pub struct TrefAtom {
+    /// Atom header.
+    header: AtomHeader,
+    /// The IDs of the tracks containing the chapters of the track.
+    pub chapter_track_ids: Vec<u32>,
+}
+
+impl Atom for TrefAtom {
+    fn header(&self) -> AtomHeader {
+        self.header
+    }
+
+    #[allow(clippy::single_match)]
+    fn read<B: ReadBytes>(reader: &mut B, header: AtomHeader) -> Result<Self> {
+        let mut iter = AtomIterator::new(reader, header);
+
+        let mut chapter_track_ids = Vec::new();
+
+        while let Some(header) = iter.next()? {
+            match header.atype {
+                AtomType::ChapterTrackReference => {
+                    // A track reference atom is a list of track IDs.
+                    for _ in 0..header.data_len / 4 {
+                        chapter_track_ids.push(iter.inner_mut().read_be_u32()?);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(TrefAtom { header, chapter_track_ids })
+    }
+}
@@ -9,7 +9,7 @@ use symphonia_core::errors::Result;
 use symphonia_core::io::ReadBytes;
 use symphonia_core::meta::MetadataRevision;
 
-use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType, MetaAtom};
+use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType, ChplAtom, MetaAtom};
 
 /// User data atom.
 #[derive(Debug)]
@@ -18,6 +18,8 @@ pub struct UdtaAtom {
     header: AtomHeader,
     /// Metadata atom.
     pub meta: Option<MetaAtom>,
+    /// Nero chapter list atom.
+    pub chpl: Option<ChplAtom>,
 }
 
 impl UdtaAtom {
@@ -32,21 +34,24 @@ impl Atom for UdtaAtom {
         self.header
     }
 
-    #[allow(clippy::single_match)]
     fn read<B: ReadBytes>(reader: &mut B, header: AtomHeader) -> Result<Self> {
         let mut iter = AtomIterator::new(reader, header);
 
         let mut meta = None;
+        let mut chpl = None;
 
         while let Some(header) = iter.next()? {
             match header.atype {
                 AtomType::Meta => {
                     meta = Some(iter.read_atom::<MetaAtom>()?);
                 }
+                AtomType::ChapterList => {
+                    chpl = Some(iter.read_atom::<ChplAtom>()?);
+                }
                 _ => (),
             }
         }
 
-        Ok(UdtaAtom { header, meta })
+        Ok(UdtaAtom { header, meta, chpl })
     }
 }
@@ -0,0 +1,124 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::errors::Result;
+use symphonia_core::formats::Cue;
+use symphonia_core::io::{MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+use symphonia_core::units::{Time, TimeBase};
+
+use crate::atoms::ChplAtom;
+use crate::stream::StreamSegment;
+
+/// The maximum length of a text sample, including the length prefix.
+const MAX_TEXT_SAMPLE_LEN: u32 = 2 + u16::MAX as u32;
+
+/// A chapter.
+pub struct Chapter {
+    /// The start time of the chapter.
+    pub start: Time,
+    /// The title of the chapter.
+    pub title: String,
+}
+
+/// Gets the chapters from a Nero chapter list atom.
+pub fn nero_chapters(chpl: &ChplAtom) -> Vec<Chapter> {
+    // Start times are in units of 100 nanoseconds.
+    let tb = TimeBase::new(1, 10_000_000);
+
+    chpl.entries
+        .iter()
+        .map(|entry| Chapter { start: tb.calc_time(entry.start), title: entry.title.clone() })
+        .collect()
+}
+
+/// Reads the chapters from a QuickTime chapter track. Each sample of a chapter track is a text
+/// sample containing the title of the chapter starting at the timestamp of the sample.
+pub fn read_chapter_track(
+    reader: &mut MediaSourceStream,
+    seg: &dyn StreamSegment,
+    track_num: usize,
+    tb: TimeBase,
+) -> Result<Vec<Chapter>> {
+    let mut chapters = Vec::new();
+
+    for sample_num in seg.track_sample_range(track_num) {
+        let timing = match seg.sample_timing(track_num, sample_num)? {
+            Some(timing) => timing,
+            None => break,
+        };
+
+        let desc = seg.sample_data(track_num, sample_num, true)?;
+
+        reader.seek(SeekFrom::Start(desc.base_pos + desc.offset.unwrap_or(0)))?;
+
+        let buf = reader.read_boxed_slice_exact(desc.size.min(MAX_TEXT_SAMPLE_LEN) as usize)?;
+
+        if let Some(title) = parse_text_sample(&buf) {
+            chapters.push(Chapter { start: tb.calc_time(timing.ts), title });
+        }
+    }
+
+    Ok(chapters)
+}
+
+/// Parses a text sample. A text sample is a length-prefixed string, optionally followed by
+/// modifier atoms. The string is UTF-16 if it starts with a byte order mark, otherwise UTF-8.
+fn parse_text_sample(buf: &[u8]) -> Option<String> {
+    let len = u16::from_be_bytes([*buf.first()?, *buf.get(1)?]);
+    let text = buf.get(2..2 + usize::from(len))?;
+
+    let text = match text {
+        [0xfe, 0xff, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+        [0xff, 0xfe, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+        _ => String::from_utf8_lossy(text).to_string(),
+    };
+
+    Some(text)
+}
+
+fn decode_utf16(buf: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = buf.chunks_exact(2).map(|unit| from_bytes([unit[0], unit[1]]));
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+/// Converts chapters into cues. The start of each cue is a timestamp in the given time base.
+pub fn chapters_to_cues(mut chapters: Vec<Chapter>, tb: TimeBase) -> Vec<Cue> {
+    chapters.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+
+    chapters
+        .into_iter()
+        .enumerate()
+        .map(|(index, chapter)| Cue {
+            index: index as u32,
+            start_ts: tb.calc_timestamp(chapter.start),
+            tags: vec![Tag::new(Some(StandardTagKey::TrackTitle), "", Value::from(chapter.title))],
+            points: Vec::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_text_sample;
+
+    #[test]
+    fn verify_parse_text_sample() {
+        // A UTF-8 sample followed by an encoding modifier atom.
+        let buf = b"\x00\x05Intro\x00\x00\x00\x0cencd\x00\x00\x01\x00";
+        assert_eq!(parse_text_sample(buf).as_deref(), Some("Intro"));
+
+        // A big-endian UTF-16 sample.
+        let buf = b"\x00\x06\xfe\xff\x00O\x00K";
+        assert_eq!(parse_text_sample(buf).as_deref(), Some("OK"));
+
+        // A truncated sample.
+        assert_eq!(parse_text_sample(b"\x00\x05Int"), None);
+    }
+}
@@ -18,8 +18,10 @@ use symphonia_core::units::Time;
 use std::io::{Seek, SeekFrom};
 use std::sync::Arc;
 
+use crate::atoms::hdlr::HandlerType;
 use crate::atoms::{AtomIterator, AtomType};
 use crate::atoms::{FtypAtom, MetaAtom, MoofAtom, MoovAtom, MvexAtom, SidxAtom, TrakAtom};
+use crate::chapters::{chapters_to_cues, nero_chapters, read_chapter_track};
 use crate::stream::*;
 
 use log::{debug, info, trace, warn};
@@ -83,6 +85,9 @@ struct SampleDataInfo {
 /// ISO Base Media File Format (MP4, M4A, MOV, etc.) demultiplexer.
 ///
 /// `IsoMp4Reader` implements a demuxer for the ISO Base Media File Format.
+///
+/// Chapters, as found in audiobooks, are exposed as cues. Both QuickTime chapter tracks and Nero
+/// chapter lists are supported.
 pub struct IsoMp4Reader {
     iter: AtomIterator<MediaSourceStream>,
     tracks: Vec<Track>,
@@ -305,13 +310,30 @@ impl IsoMp4Reader {
     }
 }
 
+/// Finds the first track that references a chapter track. Returns the track numbers of the track,
+/// and the chapter track.
+fn find_chapter_track(moov: &MoovAtom) -> Option<(usize, usize)> {
+    moov.traks.iter().enumerate().find_map(|(track_num, trak)| {
+        let id = *trak.tref.as_ref()?.chapter_track_ids.first()?;
+
+        let chapter_track_num = moov.traks.iter().position(|trak| trak.tkhd.id == id)?;
+
+        // Only text tracks can be chapter tracks.
+        match moov.traks[chapter_track_num].mdia.hdlr.handler_type {
+            HandlerType::Text | HandlerType::Subtitle => Some((track_num, chapter_track_num)),
+            HandlerType::Other(hdlr) if &hdlr == b"sbtl" => Some((track_num, chapter_track_num)),
+            _ => None,
+        }
+    })
+}
+
 impl QueryDescriptor for IsoMp4Reader {
     fn query() -> &'static [Descriptor] {
         &[support_format!(
             "isomp4",
             "ISO Base Media File Format",
-            &["mp4", "m4a", "m4p", "m4b", "m4r", "m4v", "mov"],
-            &["video/mp4", "audio/m4a"],
+            &["mp4", "m4a", "m4p", "m4b", "m4r", "m4v", "mov", "3gp", "3g2"],
+            &["video/mp4", "audio/m4a", "audio/3gpp", "audio/3gpp2"],
             &[b"ftyp"] // Top-level atoms
         )]
     }
@@ -479,17 +501,52 @@ impl FormatReader for IsoMp4Reader {
             }
         }
 
+        // Chapters may be stored in a Nero chapter list, or in a QuickTime chapter track referenced
+        // by the audio track. The latter is preferred if both are present since it is the format
+        // written by Apple for audiobooks.
+        let nero = moov.udta.as_ref().and_then(|udta| udta.chpl.as_ref()).map(nero_chapters);
+
+        let chapter_track = find_chapter_track(&moov);
+
+        // Chapter start times are converted into timestamps of the audio track.
+        let cue_track_num = match chapter_track {
+            Some((track_num, _)) => track_num,
+            None => moov
+                .traks
+                .iter()
+                .position(|trak| trak.mdia.hdlr.handler_type == HandlerType::Sound)
+                .unwrap_or(0),
+        };
+
         let segs: Vec<Box<dyn StreamSegment>> = vec![Box::new(MoovSegment::new(moov))];
 
-        Ok(IsoMp4Reader {
-            iter,
-            tracks,
-            cues: Default::default(),
-            metadata,
-            track_states,
-            segs,
-            mvex,
-        })
+        let mut chapters = None;
+
+        // The titles of the chapters in a chapter track are stored in the media data, and can only
+        // be read if the stream is seekable.
+        if let Some((_, chapter_track_num)) = chapter_track.filter(|_| is_seekable) {
+            let tb = track_states[chapter_track_num].codec_params.time_base.unwrap();
+
+            let reader = iter.inner_mut();
+            let pos = reader.pos();
+
+            match read_chapter_track(reader, segs[0].as_ref(), chapter_track_num, tb) {
+                Ok(track_chapters) if !track_chapters.is_empty() => chapters = Some(track_chapters),
+                Ok(_) => (),
+                Err(err) => warn!("ignoring chapter track: {}", err),
+            }
+
+            reader.seek(SeekFrom::Start(pos))?;
+        }
+
+        let cues = match (chapters.or(nero), track_states.get(cue_track_num)) {
+            (Some(chapters), Some(state)) => {
+                chapters_to_cues(chapters, state.codec_params.time_base.unwrap())
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(IsoMp4Reader { iter, tracks, cues, metadata, track_states, segs, mvex })
     }
 
     fn next_packet(&mut self) -> Result<Packet> {
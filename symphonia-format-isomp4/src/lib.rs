@@ -14,6 +14,7 @@
 #![allow(clippy::manual_range_contains)]
 
 mod atoms;
+mod chapters;
 mod demuxer;
 mod fourcc;
 mod fp;
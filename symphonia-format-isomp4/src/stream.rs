@@ -327,7 +327,7 @@ impl StreamSegment for MoovSegment {
 
         let trak = &self.moov.traks[track_num];
 
-        // Find the sample timing. Note, complexity of O(N).
+        // Find the sample timing. Note, complexity of O(log N).
         let timing = trak.mdia.minf.stbl.stts.find_timing_for_sample(sample_num);
 
         if let Some((ts, dur)) = timing {
@@ -344,7 +344,7 @@ impl StreamSegment for MoovSegment {
 
         let trak = &self.moov.traks[track_num];
 
-        // Find the sample timestamp. Note, complexity of O(N).
+        // Find the sample timestamp. Note, complexity of O(log N).
         Ok(trak.mdia.minf.stbl.stts.find_sample_for_timestamp(ts))
     }
 
@@ -17,6 +17,10 @@ pub(crate) fn codec_id_to_type(track: &TrackElement) -> Option<CodecType> {
         "A_MPEG/L1" => Some(codecs::CODEC_TYPE_MP1),
         "A_MPEG/L2" => Some(codecs::CODEC_TYPE_MP2),
         "A_MPEG/L3" => Some(codecs::CODEC_TYPE_MP3),
+        "A_AC3" | "A_EAC3" => Some(codecs::CODEC_TYPE_EAC3),
+        "A_DTS" | "A_DTS/EXPRESS" | "A_DTS/LOSSLESS" => Some(codecs::CODEC_TYPE_DCA),
+        "A_MLP" => Some(codecs::CODEC_TYPE_MLP),
+        "A_TRUEHD" => Some(codecs::CODEC_TYPE_TRUEHD),
         "A_FLAC" => Some(codecs::CODEC_TYPE_FLAC),
         "A_OPUS" => Some(codecs::CODEC_TYPE_OPUS),
         "A_VORBIS" => Some(codecs::CODEC_TYPE_VORBIS),
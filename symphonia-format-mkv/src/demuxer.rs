@@ -12,7 +12,8 @@ use std::io::{Seek, SeekFrom};
 use symphonia_core::audio::Layout;
 use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_FLAC, CODEC_TYPE_VORBIS};
 use symphonia_core::errors::{
-    decode_error, end_of_stream_error, seek_error, unsupported_error, Error, Result, SeekErrorKind,
+    decode_error, end_of_stream_error, reset_error, seek_error, unsupported_error, Error, Result,
+    SeekErrorKind,
 };
 use symphonia_core::formats::{
     Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track,
@@ -48,6 +49,12 @@ pub struct TrackState {
 /// Matroska (MKV) and WebM demultiplexer.
 ///
 /// `MkvReader` implements a demuxer for the Matroska and WebM formats.
+///
+/// Live streams, such as those of the WebM live profile, are supported. In a live stream, the
+/// segment and its clusters are of unknown size, and there are no cues. A stream joined in the
+/// middle of a cluster is read from the next cluster. If the timestamps of the clusters go
+/// backwards, or a new segment is started, `next_packet` returns `ResetRequired`, after which the
+/// tracks must be re-examined.
 pub struct MkvReader {
     /// Iterator over EBML element headers
     iter: ElementIterator<MediaSourceStream>,
@@ -59,6 +66,12 @@ pub struct MkvReader {
     frames: VecDeque<Frame>,
     timestamp_scale: u64,
     clusters: Vec<ClusterElement>,
+    /// The position of the first cluster of the segment, if known.
+    first_cluster_pos: Option<u64>,
+    /// The timestamp of the last cluster read.
+    last_cluster_ts: Option<u64>,
+    /// If true, the segment is of unknown size, as in a live stream.
+    is_live: bool,
 }
 
 #[derive(Debug)]
@@ -137,6 +150,93 @@ fn flac_extra_data_from_codec_private(codec_private: &[u8]) -> Result<Box<[u8]>>
     }
 }
 
+/// Builds the tracks of a segment.
+fn build_tracks(
+    segment_tracks: TracksElement,
+    info: &InfoElement,
+) -> Result<(Vec<Track>, HashMap<u32, TrackState>)> {
+    // TODO: remove this unwrap?
+    let time_base = TimeBase::new(u32::try_from(info.timestamp_scale).unwrap(), 1_000_000_000);
+
+    let mut tracks = Vec::new();
+    let mut states = HashMap::new();
+    for track in segment_tracks.tracks.into_vec() {
+        let codec_type = codec_id_to_type(&track);
+
+        let mut codec_params = CodecParameters::new();
+        codec_params.with_time_base(time_base);
+
+        if let Some(duration) = info.duration {
+            codec_params.with_n_frames(duration as u64);
+        }
+
+        if let Some(audio) = track.audio {
+            codec_params.with_sample_rate(audio.sampling_frequency.round() as u32);
+
+            let format = audio.bit_depth.and_then(|bits| match bits {
+                8 => Some(SampleFormat::S8),
+                16 => Some(SampleFormat::S16),
+                24 => Some(SampleFormat::S24),
+                32 => Some(SampleFormat::S32),
+                _ => None,
+            });
+
+            if let Some(format) = format {
+                codec_params.with_sample_format(format);
+            }
+
+            if let Some(bits) = audio.bit_depth {
+                codec_params.with_bits_per_sample(bits as u32);
+            }
+
+            let layout = match audio.channels {
+                1 => Some(Layout::Mono),
+                2 => Some(Layout::Stereo),
+                3 => Some(Layout::TwoPointOne),
+                6 => Some(Layout::FivePointOne),
+                other => {
+                    log::warn!("track #{} has custom number of channels: {}", track.number, other);
+                    None
+                }
+            };
+
+            if let Some(layout) = layout {
+                codec_params.with_channel_layout(layout);
+            }
+
+            if let Some(codec_type) = codec_type {
+                codec_params.for_codec(codec_type);
+                if let Some(codec_private) = track.codec_private {
+                    let extra_data = match codec_type {
+                        CODEC_TYPE_VORBIS => vorbis_extra_data_from_codec_private(&codec_private)?,
+                        CODEC_TYPE_FLAC => flac_extra_data_from_codec_private(&codec_private)?,
+                        _ => codec_private,
+                    };
+                    codec_params.with_extra_data(extra_data);
+                }
+            }
+        }
+
+        let track_id = track.number as u32;
+        tracks.push(Track {
+            id: track_id,
+            codec_params: codec_params.clone(),
+            language: track.language,
+        });
+
+        states.insert(
+            track_id,
+            TrackState {
+                codec_params,
+                track_num: track_id,
+                default_frame_duration: track.default_duration,
+            },
+        );
+    }
+
+    Ok((tracks, states))
+}
+
 impl MkvReader {
     fn seek_track_by_ts_forward(&mut self, track_id: u32, ts: u64) -> Result<SeekedTo> {
         let actual_ts = 'out: loop {
@@ -156,7 +256,30 @@ impl MkvReader {
     }
 
     fn seek_track_by_ts(&mut self, track_id: u32, ts: u64) -> Result<SeekedTo> {
+        let is_seekable = self.iter.is_seekable();
+
+        // Without cues, a stream that is not seekable, such as a live stream, can only be seeked
+        // forward.
+        let is_behind = self.last_cluster_ts.map_or(false, |last_ts| ts < last_ts);
+
+        if self.clusters.is_empty() && !is_seekable && is_behind {
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // The cluster timestamps are expected to go backwards after a seek.
+        self.last_cluster_ts = None;
+
         if self.clusters.is_empty() {
+            if let Some(pos) = self.first_cluster_pos.filter(|_| is_seekable) {
+                // Seeking restarts from the first cluster, unless the timestamp is known to be
+                // ahead.
+                if self.frames.front().map_or(true, |frame| ts < frame.timestamp) {
+                    self.iter.seek(pos)?;
+                    self.current_cluster = None;
+                    self.frames.clear();
+                }
+            }
+
             self.seek_track_by_ts_forward(track_id, ts)
         }
         else {
@@ -207,21 +330,37 @@ impl MkvReader {
         // Each Cluster is being read incrementally so we need to keep track of
         // which cluster we are currently in.
 
-        let header = match self.iter.read_child_header()? {
-            Some(header) => header,
-            None => {
+        let header = match self.iter.read_child_header() {
+            Ok(Some(header)) if !self.iter.is_past_end(&header) => header,
+            Ok(Some(_)) | Err(Error::DecodeError(_)) => return self.resync(),
+            Ok(None) => {
                 // If we reached here, it must be an end of stream.
                 return end_of_stream_error();
             }
+            Err(err) => return Err(err),
         };
 
         match header.etype {
+            ElementType::Ebml => {
+                // A new segment is started when a live stream is restarted, or chained.
+                self.read_chained_segment()?;
+                return reset_error();
+            }
             ElementType::Cluster => {
                 self.current_cluster = Some(ClusterState { timestamp: None, end: header.end() });
             }
             ElementType::Timestamp => match self.current_cluster.as_mut() {
                 Some(cluster) => {
-                    cluster.timestamp = Some(self.iter.read_u64()?);
+                    let ts = self.iter.read_u64()?;
+
+                    cluster.timestamp = Some(ts);
+
+                    let last_ts = self.last_cluster_ts.replace(ts);
+
+                    if self.is_live && last_ts.map_or(false, |last_ts| ts < last_ts) {
+                        log::warn!("cluster timestamp discontinuity");
+                        return reset_error();
+                    }
                 }
                 None => {
                     self.iter.ignore_data()?;
@@ -285,7 +424,12 @@ impl MkvReader {
                 self.current_cluster = None;
             }
             _ if header.etype.is_top_level() => {
+                // Other top-level elements were read when the segment was started.
                 self.current_cluster = None;
+                self.iter.ignore_data()?;
+            }
+            _ if is_invalid_element(&header, self.current_cluster.is_some(), self.is_live) => {
+                return self.resync();
             }
             other => {
                 log::debug!("ignored element {:?}", other);
@@ -295,6 +439,113 @@ impl MkvReader {
 
         Ok(())
     }
+
+    /// Skips invalid, or missing, data by resynchronizing to the next cluster.
+    fn resync(&mut self) -> Result<()> {
+        log::warn!("invalid element, skipping to the next cluster");
+
+        self.current_cluster = None;
+
+        if !self.iter.resync_cluster()? {
+            return end_of_stream_error();
+        }
+
+        Ok(())
+    }
+
+    /// Reads the header elements of a new segment, and replaces the tracks.
+    fn read_chained_segment(&mut self) -> Result<()> {
+        let ebml = self.iter.read_element_data::<EbmlElement>()?;
+
+        if !matches!(ebml.header.doc_type.as_str(), "matroska" | "webm") {
+            return unsupported_error("mkv: not a matroska / webm segment");
+        }
+
+        let segment = match self.iter.read_child_header()? {
+            Some(header) if header.etype == ElementType::Segment => header,
+            _ => return decode_error("mkv: missing segment element"),
+        };
+
+        let mut segment_tracks = None;
+        let mut info = None;
+
+        self.current_cluster = None;
+        self.first_cluster_pos = None;
+        self.is_live = segment.data_len == 0;
+
+        loop {
+            let header = match self.iter.read_child_header() {
+                Ok(Some(header)) if !is_invalid_element(&header, false, self.is_live) => header,
+                Ok(Some(_)) | Err(Error::DecodeError(_)) => {
+                    log::warn!("invalid element, skipping to the next cluster");
+
+                    if self.iter.resync_cluster()? {
+                        continue;
+                    }
+                    break;
+                }
+                Ok(None) => break,
+                Err(err) => return Err(err),
+            };
+
+            match header.etype {
+                ElementType::Tracks => {
+                    segment_tracks = Some(self.iter.read_element_data::<TracksElement>()?);
+                }
+                ElementType::Info => {
+                    info = Some(self.iter.read_element_data::<InfoElement>()?);
+                }
+                ElementType::Tags => {
+                    let tags = self.iter.read_element_data::<TagsElement>()?;
+                    self.metadata.push(tags.to_metadata());
+                }
+                ElementType::Cluster => {
+                    self.current_cluster =
+                        Some(ClusterState { timestamp: None, end: header.end() });
+                    self.first_cluster_pos = Some(header.pos);
+                    break;
+                }
+                other => {
+                    self.iter.ignore_data()?;
+                    log::debug!("ignored element {:?}", other);
+                }
+            }
+        }
+
+        let segment_tracks =
+            segment_tracks.ok_or(Error::DecodeError("mkv: missing Tracks element"))?;
+
+        let info = info.ok_or(Error::DecodeError("mkv: missing Info element"))?;
+
+        let (tracks, states) = build_tracks(segment_tracks, &info)?;
+
+        self.tracks = tracks;
+        self.track_states = states;
+        self.timestamp_scale = info.timestamp_scale;
+        self.clusters.clear();
+        self.last_cluster_ts = None;
+
+        Ok(())
+    }
+}
+
+/// Returns true if the element cannot be a top-level element, or a child of a cluster if in a
+/// cluster, of the segment. Such an element is most likely the result of invalid, or missing, data.
+fn is_invalid_element(header: &ElementHeader, in_cluster: bool, is_live: bool) -> bool {
+    match header.etype {
+        ElementType::Void | ElementType::Crc32 => false,
+        ElementType::Timestamp
+        | ElementType::SilentTracks
+        | ElementType::Position
+        | ElementType::PrevSize
+        | ElementType::SimpleBlock
+        | ElementType::BlockGroup
+        | ElementType::EncryptedBlock => !in_cluster,
+        // The IDs of top-level elements are 4 octets long. Unknown top-level elements are not
+        // expected in a live stream.
+        ElementType::Unknown => is_live || header.tag < 0x1000_0000,
+        etype => !etype.is_top_level(),
+    }
 }
 
 impl FormatReader for MkvReader {
@@ -323,8 +574,10 @@ impl FormatReader for MkvReader {
             return unsupported_error("mkv: not a matroska / webm file");
         }
 
-        let segment_pos = match it.read_child_header()? {
-            Some(ElementHeader { etype: ElementType::Segment, data_pos, .. }) => data_pos,
+        let (segment_pos, is_live) = match it.read_child_header()? {
+            Some(ElementHeader { etype: ElementType::Segment, data_pos, data_len, .. }) => {
+                (data_pos, data_len == 0)
+            }
             _ => return unsupported_error("mkv: missing segment element"),
         };
 
@@ -333,9 +586,28 @@ impl FormatReader for MkvReader {
         let mut clusters = Vec::new();
         let mut metadata = MetadataLog::default();
         let mut current_cluster = None;
+        let mut first_cluster_pos = None;
 
         let mut seek_positions = Vec::new();
-        while let Ok(Some(header)) = it.read_child_header() {
+        loop {
+            let header = match it.read_child_header() {
+                Ok(Some(header))
+                    if !is_invalid_element(&header, false, is_live) && !it.is_past_end(&header) =>
+                {
+                    header
+                }
+                Ok(Some(_)) | Err(Error::DecodeError(_)) => {
+                    // A live stream may be joined in the middle of a cluster.
+                    log::warn!("invalid element, skipping to the next cluster");
+
+                    if it.resync_cluster()? {
+                        continue;
+                    }
+                    break;
+                }
+                _ => break,
+            };
+
             match header.etype {
                 ElementType::SeekHead => {
                     let seek_head = it.read_element_data::<SeekHeadElement>()?;
@@ -372,6 +644,7 @@ impl FormatReader for MkvReader {
                 ElementType::Cluster => {
                     // Set state for current cluster for the first call of `next_element`.
                     current_cluster = Some(ClusterState { timestamp: None, end: header.end() });
+                    first_cluster_pos = Some(header.pos);
 
                     // Don't look forward into the stream since
                     // we can't be sure that we'll find anything useful.
@@ -432,90 +705,7 @@ impl FormatReader for MkvReader {
 
         let info = info.ok_or(Error::DecodeError("mkv: missing Info element"))?;
 
-        // TODO: remove this unwrap?
-        let time_base = TimeBase::new(u32::try_from(info.timestamp_scale).unwrap(), 1_000_000_000);
-
-        let mut tracks = Vec::new();
-        let mut states = HashMap::new();
-        for track in segment_tracks.tracks.into_vec() {
-            let codec_type = codec_id_to_type(&track);
-
-            let mut codec_params = CodecParameters::new();
-            codec_params.with_time_base(time_base);
-
-            if let Some(duration) = info.duration {
-                codec_params.with_n_frames(duration as u64);
-            }
-
-            if let Some(audio) = track.audio {
-                codec_params.with_sample_rate(audio.sampling_frequency.round() as u32);
-
-                let format = audio.bit_depth.and_then(|bits| match bits {
-                    8 => Some(SampleFormat::S8),
-                    16 => Some(SampleFormat::S16),
-                    24 => Some(SampleFormat::S24),
-                    32 => Some(SampleFormat::S32),
-                    _ => None,
-                });
-
-                if let Some(format) = format {
-                    codec_params.with_sample_format(format);
-                }
-
-                if let Some(bits) = audio.bit_depth {
-                    codec_params.with_bits_per_sample(bits as u32);
-                }
-
-                let layout = match audio.channels {
-                    1 => Some(Layout::Mono),
-                    2 => Some(Layout::Stereo),
-                    3 => Some(Layout::TwoPointOne),
-                    6 => Some(Layout::FivePointOne),
-                    other => {
-                        log::warn!(
-                            "track #{} has custom number of channels: {}",
-                            track.number,
-                            other
-                        );
-                        None
-                    }
-                };
-
-                if let Some(layout) = layout {
-                    codec_params.with_channel_layout(layout);
-                }
-
-                if let Some(codec_type) = codec_type {
-                    codec_params.for_codec(codec_type);
-                    if let Some(codec_private) = track.codec_private {
-                        let extra_data = match codec_type {
-                            CODEC_TYPE_VORBIS => {
-                                vorbis_extra_data_from_codec_private(&codec_private)?
-                            }
-                            CODEC_TYPE_FLAC => flac_extra_data_from_codec_private(&codec_private)?,
-                            _ => codec_private,
-                        };
-                        codec_params.with_extra_data(extra_data);
-                    }
-                }
-            }
-
-            let track_id = track.number as u32;
-            tracks.push(Track {
-                id: track_id,
-                codec_params: codec_params.clone(),
-                language: track.language,
-            });
-
-            states.insert(
-                track_id,
-                TrackState {
-                    codec_params,
-                    track_num: track_id,
-                    default_frame_duration: track.default_duration,
-                },
-            );
-        }
+        let (tracks, states) = build_tracks(segment_tracks, &info)?;
 
         Ok(Self {
             iter: it,
@@ -527,6 +717,9 @@ impl FormatReader for MkvReader {
             frames: VecDeque::new(),
             timestamp_scale: info.timestamp_scale,
             clusters,
+            first_cluster_pos,
+            last_cluster_ts: None,
+            is_live,
         })
     }
 
@@ -5,10 +5,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::io::SeekFrom;
+use std::io::{ErrorKind, SeekFrom};
 
 use symphonia_core::errors::{decode_error, seek_error, Error, Result, SeekErrorKind};
-use symphonia_core::io::{MediaSource, ReadBytes};
+use symphonia_core::io::{MediaSource, ReadBytes, SeekBuffered};
 use symphonia_core::util::bits::sign_extend_leq64_to_i64;
 
 use crate::element_ids::{ElementType, Type, ELEMENTS};
@@ -55,7 +55,9 @@ pub(crate) fn read_tag<R: ReadBytes>(mut reader: R) -> Result<(u32, u32, bool)>
 
 pub(crate) fn read_size<R: ReadBytes>(reader: R) -> Result<Option<u64>> {
     let (size, len) = read_vint(reader)?;
-    if size == u64::MAX && len == 1 {
+    // A size with all of its data bits set indicates an unknown size. Live streams commonly use
+    // the 8 octet form.
+    if size == u64::MAX || size == (1 << (7 * len)) - 1 {
         return Ok(None);
     }
     Ok(Some(size))
@@ -86,6 +88,10 @@ fn read_vint<R: ReadBytes>(mut reader: R) -> Result<(u64, u32)> {
     }
 
     let vint_width = byte.leading_zeros();
+    if vint_width > 7 {
+        return decode_error("mkv: invalid variable size integer");
+    }
+
     let mut vint = u64::from(byte);
     // Clear VINT_MARKER bit
     vint ^= 1 << (7 - vint_width);
@@ -101,9 +107,12 @@ fn read_vint<R: ReadBytes>(mut reader: R) -> Result<(u64, u32)> {
 
 #[cfg(test)]
 mod tests {
-    use symphonia_core::io::BufReader;
+    use std::io::Cursor;
+
+    use symphonia_core::io::{BufReader, MediaSourceStream};
 
-    use super::{read_signed_vint, read_tag, read_unsigned_vint};
+    use super::{read_signed_vint, read_size, read_tag, read_unsigned_vint, ElementIterator};
+    use crate::element_ids::ElementType;
 
     #[test]
     fn element_tag_parsing() {
@@ -144,6 +153,40 @@ mod tests {
         assert_eq!(read_signed_vint(BufReader::new(&[0x80])).unwrap(), -63);
         assert_eq!(read_signed_vint(BufReader::new(&[0x40, 0x00])).unwrap(), -8191);
     }
+
+    #[test]
+    fn element_size_parsing() {
+        assert_eq!(read_size(BufReader::new(&[0x82])).unwrap(), Some(2));
+        assert_eq!(read_size(BufReader::new(&[0x40, 0x7f])).unwrap(), Some(127));
+        assert_eq!(read_size(BufReader::new(&[0xff])).unwrap(), None);
+        assert_eq!(read_size(BufReader::new(&[0x7f, 0xff])).unwrap(), None);
+        assert_eq!(
+            read_size(BufReader::new(&[0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])).unwrap(),
+            None
+        );
+        assert!(read_size(BufReader::new(&[0x00])).is_err());
+    }
+
+    #[test]
+    fn cluster_resync() {
+        // Invalid data, a cluster tag not followed by a timestamp, and a cluster of unknown size.
+        let data = vec![
+            0xa3, 0x42, 0x1f, 0x43, 0xb6, 0x75, 0x81, 0xa3, 0x1f, 0x43, 0xb6, 0x75, 0xff, 0xe7,
+            0x81, 0x00,
+        ];
+
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+        let mut it = ElementIterator::new(mss, None);
+
+        assert!(it.resync_cluster().unwrap());
+
+        let header = it.read_child_header().unwrap().unwrap();
+        assert_eq!(header.etype, ElementType::Cluster);
+        assert_eq!(header.pos, 8);
+        assert_eq!(header.end(), None);
+
+        assert!(!it.resync_cluster().unwrap());
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -270,6 +313,68 @@ impl<R: ReadBytes> ElementIterator<R> {
         Ok(())
     }
 
+    /// Scans the stream for the next `Cluster` element, and moves to its header. Only clusters
+    /// starting with a `Timestamp`, or `CRC-32`, element are accepted. Returns `false` if the end
+    /// of the stream was reached.
+    pub(crate) fn resync_cluster(&mut self) -> Result<bool>
+    where
+        R: SeekBuffered,
+    {
+        const CLUSTER_TAG: u32 = 0x1F43B675;
+
+        let mut tag = 0u32;
+
+        loop {
+            tag = match self.reader.read_byte() {
+                Ok(byte) => (tag << 8) | u32::from(byte),
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+                Err(err) => return Err(err.into()),
+            };
+
+            if tag != CLUSTER_TAG {
+                continue;
+            }
+
+            let start = self.reader.pos();
+
+            let first_child =
+                read_size(&mut self.reader).and_then(|_| Ok(self.reader.read_byte()?));
+
+            let is_cluster = match first_child {
+                Ok(child_tag) => child_tag == 0xE7 || child_tag == 0xBF,
+                Err(Error::DecodeError(_)) => false,
+                Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return Ok(false)
+                }
+                Err(err) => return Err(err),
+            };
+
+            let len = (self.reader.pos() - start) as usize;
+
+            if is_cluster {
+                self.reader.seek_buffered_rev(len + 4);
+                self.current = None;
+                self.next_pos = self.reader.pos();
+                return Ok(true);
+            }
+
+            self.reader.seek_buffered_rev(len);
+        }
+    }
+
+    /// Returns true if the element extends past the end of the parent element, or stream.
+    pub(crate) fn is_past_end(&self, header: &ElementHeader) -> bool {
+        self.end.map_or(false, |end| header.data_pos + header.data_len > end)
+    }
+
+    /// Returns true if the underlying stream is seekable.
+    pub(crate) fn is_seekable(&self) -> bool
+    where
+        R: MediaSource,
+    {
+        self.reader.is_seekable()
+    }
+
     /// Consumes this iterator and return the original stream.
     pub(crate) fn into_inner(self) -> R {
         self.reader
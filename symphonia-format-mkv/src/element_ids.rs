@@ -46,10 +46,14 @@ pub enum ElementType {
     WritingApp,
     Cluster,
     Timestamp,
+    SilentTracks,
+    SilentTrackNumber,
+    Position,
     PrevSize,
     SimpleBlock,
     BlockGroup,
     Block,
+    EncryptedBlock,
     BlockAdditions,
     BlockMore,
     BlockAddId,
@@ -211,10 +215,14 @@ lazy_static! {
         elems.insert(0x5741, (Type::String, ElementType::WritingApp));
         elems.insert(0x1F43B675, (Type::Master, ElementType::Cluster));
         elems.insert(0xE7, (Type::Unsigned, ElementType::Timestamp));
+        elems.insert(0x5854, (Type::Master, ElementType::SilentTracks));
+        elems.insert(0x58D7, (Type::Unsigned, ElementType::SilentTrackNumber));
+        elems.insert(0xA7, (Type::Unsigned, ElementType::Position));
         elems.insert(0xAB, (Type::Unsigned, ElementType::PrevSize));
         elems.insert(0xA3, (Type::Binary, ElementType::SimpleBlock));
         elems.insert(0xA0, (Type::Master, ElementType::BlockGroup));
         elems.insert(0xA1, (Type::Binary, ElementType::Block));
+        elems.insert(0xAF, (Type::Binary, ElementType::EncryptedBlock));
         elems.insert(0x75A1, (Type::Master, ElementType::BlockAdditions));
         elems.insert(0xA6, (Type::Master, ElementType::BlockMore));
         elems.insert(0xEE, (Type::Unsigned, ElementType::BlockAddId));
@@ -0,0 +1,637 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::CodecParameters;
+use symphonia_core::errors::{end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_utils_mpeg::frame::{EsCodec, Frame, FrameParser};
+use symphonia_utils_mpeg::pes::{pts_to_ts, read_pes_packet};
+
+use log::{debug, warn};
+
+use crate::lpcm::LpcmParser;
+use crate::pack::{read_unit, sync_pack, Unit, PACK_START_CODE};
+
+/// The stream ID of private stream 1, which carries sub-streams identified by the first byte of
+/// the payload.
+const PRIVATE_STREAM_1: u8 = 0xbd;
+
+/// The length of the header of the audio sub-streams of private stream 1: the sub-stream ID, the
+/// number of frames starting in the packet, and the offset of the first frame.
+const PRIVATE_HEADER_LEN: usize = 4;
+
+/// The minimum number of bytes to read to find the audio streams.
+const MIN_PROBE_LEN: u64 = 512 * 1024;
+
+/// The maximum number of bytes to read to find the audio streams, and their codec parameters.
+const MAX_PROBE_LEN: u64 = 4 * 1024 * 1024;
+
+/// The number of bytes at the end of the stream to search for the last presentation timestamps.
+const DURATION_SCAN_LEN: u64 = 512 * 1024;
+
+/// The granularity, in bytes, of the search for the position to seek to.
+const SEEK_GRANULARITY: u64 = 2048;
+
+/// Gets the codec of an audio stream from its stream ID, or its sub-stream ID for private stream
+/// 1.
+fn stream_codec(id: u8) -> Option<EsCodec> {
+    match id {
+        0x80..=0x87 => Some(EsCodec::Ac3),
+        0x88..=0x8f | 0x98..=0x9f => Some(EsCodec::Dts),
+        0xc0..=0xdf => Some(EsCodec::Mpa),
+        _ => None,
+    }
+}
+
+/// Returns true if the sub-stream ID of private stream 1 is a DVD LPCM stream.
+fn is_lpcm(id: u8) -> bool {
+    matches!(id, 0xa0..=0xa7)
+}
+
+/// Gets the ID of the stream carried by the payload of a PES packet, and the payload of the
+/// stream, if the stream is an audio stream.
+fn stream_payload(stream_id: u8, payload: &[u8]) -> Option<(u8, &[u8])> {
+    match stream_id {
+        0xc0..=0xdf => Some((stream_id, payload)),
+        PRIVATE_STREAM_1 => {
+            let id = *payload.first()?;
+
+            if stream_codec(id).is_some() || is_lpcm(id) {
+                Some((id, payload.get(PRIVATE_HEADER_LEN..)?))
+            }
+            else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The parser of the payloads of an audio stream.
+enum Parser {
+    Frames(FrameParser),
+    Lpcm(LpcmParser),
+}
+
+/// The state of an audio stream.
+struct EsState {
+    id: u8,
+    parser: Parser,
+    sample_rate: u32,
+    /// The timestamp of the next packet.
+    next_ts: u64,
+    /// If true, the timestamp of the next packet is taken from its presentation timestamp.
+    resync: bool,
+}
+
+impl EsState {
+    fn new(id: u8) -> Option<Self> {
+        let parser = match stream_codec(id) {
+            Some(codec) => Parser::Frames(FrameParser::new(codec)),
+            None if is_lpcm(id) => Parser::Lpcm(LpcmParser::new()),
+            None => return None,
+        };
+
+        Some(EsState { id, parser, sample_rate: 0, next_ts: 0, resync: true })
+    }
+
+    fn reset(&mut self) {
+        match &mut self.parser {
+            Parser::Frames(parser) => parser.reset(),
+            Parser::Lpcm(parser) => parser.reset(),
+        }
+        self.resync = true;
+    }
+
+    /// Returns true if the codec parameters of the stream are known.
+    fn is_ready(&self) -> bool {
+        match &self.parser {
+            Parser::Frames(parser) => parser.info.is_some(),
+            Parser::Lpcm(parser) => parser.format.is_some(),
+        }
+    }
+
+    fn push(&mut self, data: &[u8], pts: Option<u64>) -> Result<()> {
+        match &mut self.parser {
+            Parser::Frames(parser) => parser.push(data, pts),
+            Parser::Lpcm(parser) => parser.push(data, pts)?,
+        }
+        Ok(())
+    }
+
+    fn next_frame(&mut self, eos: bool) -> Result<Option<Frame>> {
+        match &mut self.parser {
+            Parser::Frames(parser) => parser.next_frame(eos),
+            Parser::Lpcm(parser) => Ok(parser.next_frame(eos)),
+        }
+    }
+
+    fn codec_params(&self) -> CodecParameters {
+        let mut params = CodecParameters::new();
+
+        match &self.parser {
+            Parser::Frames(parser) => {
+                let info = parser.info.as_ref().unwrap();
+
+                params.for_codec(info.codec).with_sample_rate(info.sample_rate);
+
+                if let Some(channels) = info.channels {
+                    params.with_channels(channels);
+                }
+
+                if let Some(extra_data) = &info.extra_data {
+                    params.with_extra_data(extra_data.clone());
+                }
+            }
+            Parser::Lpcm(parser) => {
+                let format = parser.format.unwrap();
+
+                params
+                    .for_codec(format.codec())
+                    .with_sample_rate(format.sample_rate)
+                    .with_channels(format.channels())
+                    .with_bits_per_sample(format.bits_per_sample)
+                    .with_bits_per_coded_sample(format.bits_per_coded_sample())
+                    .with_max_frames_per_packet(format.max_frames_per_packet())
+                    .with_frames_per_block(1);
+            }
+        }
+
+        params.with_time_base(TimeBase::new(1, self.sample_rate));
+
+        params
+    }
+
+    /// Gets the timestamp of a frame.
+    fn timestamp(&self, frame: &Frame, start_pts: u64) -> u64 {
+        match frame.pts {
+            Some(pts) => {
+                let ts = pts_to_ts(pts, start_pts, self.sample_rate);
+
+                // The presentation timestamps are rounded to 90 kHz units, therefore, small
+                // deviations from the expected timestamp are ignored.
+                let delta = if ts > self.next_ts { ts - self.next_ts } else { self.next_ts - ts };
+
+                if self.resync || delta > frame.dur / 2 {
+                    ts
+                }
+                else {
+                    self.next_ts
+                }
+            }
+            None => self.next_ts,
+        }
+    }
+
+    /// Gets the packet of a frame.
+    fn packet(&mut self, frame: Frame, start_pts: u64) -> Packet {
+        let ts = self.timestamp(&frame, start_pts);
+
+        if frame.pts.is_some() {
+            self.resync = false;
+        }
+
+        self.next_ts = ts + frame.dur;
+
+        Packet::new_from_boxed_slice(u32::from(self.id), ts, frame.dur, frame.data)
+    }
+}
+
+/// Adds the payload of a PES packet to the audio stream it belongs to, if any, and queues the
+/// frames completed by it.
+fn push_es(
+    streams: &mut [EsState],
+    frames: &mut VecDeque<(u8, Frame)>,
+    stream_id: u8,
+    packet: &[u8],
+) -> Result<()> {
+    let payload = match read_pes_packet(packet) {
+        Ok(payload) => payload,
+        Err(err) => {
+            warn!("{}", err);
+            return Ok(());
+        }
+    };
+
+    let (id, data) = match stream_payload(stream_id, payload.data) {
+        Some(stream) => stream,
+        None => return Ok(()),
+    };
+
+    let stream = match streams.iter_mut().find(|stream| stream.id == id) {
+        Some(stream) => stream,
+        None => return Ok(()),
+    };
+
+    if let Err(err) = stream.push(data, payload.pts) {
+        warn!("{}", err);
+    }
+
+    while let Some(frame) = stream.next_frame(false)? {
+        frames.push_back((stream.id, frame));
+    }
+
+    Ok(())
+}
+
+/// MPEG program stream format reader.
+///
+/// `MpegPsReader` implements a demuxer for the audio streams of an MPEG-1 or MPEG-2 program
+/// stream, including DVD VOB files: MPEG audio, and the AC-3, DTS, and LPCM sub-streams of private
+/// stream 1. Each audio stream is exposed as a track with a track ID equal to its stream ID, or its
+/// sub-stream ID for the sub-streams of private stream 1, and a time base of one sample. The
+/// timestamps of all tracks are measured from the earliest presentation timestamp.
+pub struct MpegPsReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    streams: Vec<EsState>,
+    /// The buffer of a PES packet.
+    buf: Vec<u8>,
+    data_start_pos: u64,
+    /// The presentation timestamp of the start of the stream.
+    start_pts: u64,
+    /// The frames read, but not yet returned, and the IDs of their streams.
+    frames: VecDeque<(u8, Frame)>,
+}
+
+impl QueryDescriptor for MpegPsReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "mpegps",
+            "MPEG Program Stream",
+            &["mpg", "mpeg", "vob", "m2p"],
+            &["video/mpeg", "video/mp2p"],
+            &[&[0x00, 0x00, 0x01, PACK_START_CODE]]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl MpegPsReader {
+    /// Queues the remaining frames of every stream at the end of the stream.
+    fn flush(&mut self) -> Result<()> {
+        for stream in self.streams.iter_mut() {
+            while let Some(frame) = stream.next_frame(true)? {
+                self.frames.push_back((stream.id, frame));
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the next frame, or `None` at the end of the stream.
+    fn next_frame(&mut self) -> Result<Option<(u8, Frame)>> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+
+            match read_unit(&mut self.reader, &mut self.buf)? {
+                Some(Unit::Pes(stream_id)) => {
+                    push_es(&mut self.streams, &mut self.frames, stream_id, &self.buf)?
+                }
+                Some(_) => (),
+                None => {
+                    // Any frames remaining at the end of the stream are complete.
+                    self.flush()?;
+
+                    if self.frames.is_empty() {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the start of the stream to find the audio streams, and the first frames of every
+    /// audio stream to determine its codec parameters. Returns the first system clock reference,
+    /// if any.
+    fn read_streams(&mut self) -> Result<Option<u64>> {
+        let mut first_scr = None;
+
+        loop {
+            let len = self.reader.pos() - self.data_start_pos;
+
+            if len > MAX_PROBE_LEN {
+                warn!("mpegps: stopped searching for audio streams");
+                break;
+            }
+
+            // Streams may start anywhere, therefore, a minimum amount of the stream is read
+            // before all streams are assumed to be found.
+            if len > MIN_PROBE_LEN && self.streams.iter().all(|stream| stream.is_ready()) {
+                break;
+            }
+
+            let stream_id = match read_unit(&mut self.reader, &mut self.buf)? {
+                Some(Unit::Pes(stream_id)) => stream_id,
+                Some(Unit::Pack(scr)) => {
+                    first_scr = first_scr.or(scr);
+                    continue;
+                }
+                Some(Unit::Other) => continue,
+                None => {
+                    self.flush()?;
+                    break;
+                }
+            };
+
+            // Add the stream if it is a newly found audio stream.
+            if let Ok(payload) = read_pes_packet(&self.buf) {
+                if let Some((id, _)) = stream_payload(stream_id, payload.data) {
+                    if !self.streams.iter().any(|stream| stream.id == id) {
+                        debug!("found audio stream {:#x}", id);
+                        self.streams.extend(EsState::new(id));
+                    }
+                }
+            }
+
+            push_es(&mut self.streams, &mut self.frames, stream_id, &self.buf)?;
+        }
+
+        // Streams without a frame cannot be decoded.
+        self.streams.retain(|stream| {
+            if !stream.is_ready() {
+                warn!("mpegps: ignoring stream {:#x} without frames", stream.id);
+            }
+            stream.is_ready()
+        });
+
+        if self.streams.is_empty() {
+            return unsupported_error("mpegps: no supported audio streams");
+        }
+
+        // Keep the order of the tracks stable regardless of the order the streams were found in.
+        self.streams.sort_by_key(|stream| stream.id);
+
+        Ok(first_scr)
+    }
+
+    /// Gets the presentation timestamp of the next PES packet of an audio stream before the end
+    /// position, and the ID of its stream.
+    fn read_next_pts(&mut self, end: u64) -> Result<Option<(u8, u64)>> {
+        while self.reader.pos() < end {
+            let stream_id = match read_unit(&mut self.reader, &mut self.buf)? {
+                Some(Unit::Pes(stream_id)) => stream_id,
+                Some(_) => continue,
+                None => break,
+            };
+
+            let payload = match read_pes_packet(&self.buf) {
+                Ok(payload) => payload,
+                Err(_) => continue,
+            };
+
+            if let (Some((id, _)), Some(pts)) =
+                (stream_payload(stream_id, payload.data), payload.pts)
+            {
+                return Ok(Some((id, pts)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the last presentation timestamp of every stream, if the stream is seekable.
+    fn read_last_pts(&mut self) -> Result<Vec<(u8, u64)>> {
+        let mut last = Vec::new();
+
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) if self.reader.is_seekable() => byte_len,
+            _ => return Ok(last),
+        };
+
+        let pos = self.reader.pos();
+
+        self.reader.seek(SeekFrom::Start(byte_len.saturating_sub(DURATION_SCAN_LEN).max(pos)))?;
+
+        if sync_pack(&mut self.reader)? {
+            while let Some((id, pts)) = self.read_next_pts(byte_len)? {
+                match last.iter_mut().find(|(last_id, _)| *last_id == id) {
+                    Some((_, last_pts)) => *last_pts = pts,
+                    None => last.push((id, pts)),
+                }
+            }
+        }
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        Ok(last)
+    }
+
+    /// Gets the timestamp of the first PES packet of a stream starting in a pack between two
+    /// positions.
+    fn read_pes_ts(&mut self, stream_idx: usize, pos: u64, end: u64) -> Result<Option<u64>> {
+        let id = self.streams[stream_idx].id;
+        let sample_rate = self.streams[stream_idx].sample_rate;
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        if !sync_pack(&mut self.reader)? {
+            return Ok(None);
+        }
+
+        while let Some((pes_id, pts)) = self.read_next_pts(end)? {
+            if pes_id == id {
+                return Ok(Some(pts_to_ts(pts, self.start_pts, sample_rate)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets a position such that the first PES packet of a stream starting in a pack after it
+    /// starts at, or before, a timestamp.
+    fn find_seek_pos(&mut self, stream_idx: usize, ts: u64) -> Result<u64> {
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) => byte_len,
+            None => return Ok(self.data_start_pos),
+        };
+
+        let mut lo = 0;
+        let mut hi = byte_len.saturating_sub(self.data_start_pos) / SEEK_GRANULARITY;
+
+        // Bisect the stream. The first PES packet after position `lo` starts at, or before, the
+        // timestamp, while the first PES packet after position `hi` does not.
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+
+            let pos = self.data_start_pos + mid * SEEK_GRANULARITY;
+            let end = self.data_start_pos + hi * SEEK_GRANULARITY;
+
+            match self.read_pes_ts(stream_idx, pos, end)? {
+                Some(pes_ts) if pes_ts <= ts => lo = mid,
+                _ => hi = mid,
+            }
+        }
+
+        Ok(self.data_start_pos + lo * SEEK_GRANULARITY)
+    }
+}
+
+impl FormatReader for MpegPsReader {
+    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut reader = MpegPsReader {
+            data_start_pos: source.pos(),
+            reader: source,
+            tracks: Vec::new(),
+            cues: Vec::new(),
+            metadata: Default::default(),
+            streams: Vec::new(),
+            buf: Vec::new(),
+            start_pts: 0,
+            frames: VecDeque::new(),
+        };
+
+        let first_scr = reader.read_streams()?;
+
+        // The stream starts at the earliest presentation timestamp of the first frame of any
+        // stream, or the first system clock reference if there are none.
+        let start_pts = reader
+            .streams
+            .iter()
+            .filter_map(|stream| {
+                reader.frames.iter().find(|(id, _)| *id == stream.id).and_then(|(_, f)| f.pts)
+            })
+            .min();
+
+        reader.start_pts = start_pts.or(first_scr).unwrap_or(0);
+
+        for stream in reader.streams.iter_mut() {
+            stream.sample_rate = match &stream.parser {
+                Parser::Frames(parser) => parser.info.as_ref().unwrap().sample_rate,
+                Parser::Lpcm(parser) => parser.format.unwrap().sample_rate,
+            };
+        }
+
+        let last_pts = reader.read_last_pts()?;
+
+        for stream in reader.streams.iter() {
+            let mut params = stream.codec_params();
+
+            // The duration is estimated from the presentation timestamp of the last PES packet.
+            if let Some(&(_, pts)) = last_pts.iter().find(|(id, _)| *id == stream.id) {
+                let n_frames = pts_to_ts(pts, reader.start_pts, stream.sample_rate);
+
+                if n_frames > 0 {
+                    params.with_n_frames(n_frames);
+                }
+            }
+
+            reader.tracks.push(Track::new(u32::from(stream.id), params));
+        }
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (id, frame) = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        let stream = self.streams.iter_mut().find(|stream| stream.id == id).unwrap();
+
+        Ok(stream.packet(frame, self.start_pts))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let track_id = match to {
+            SeekTo::TimeStamp { track_id, .. } => track_id,
+            SeekTo::Time { track_id, .. } => track_id.unwrap_or(self.tracks[0].id),
+        };
+
+        let stream_idx = match self.streams.iter().position(|s| u32::from(s.id) == track_id) {
+            Some(stream_idx) => stream_idx,
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.streams[stream_idx].sample_rate).calc_timestamp(time)
+            }
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        if self.reader.is_seekable() {
+            let pos = self.find_seek_pos(stream_idx, required_ts)?;
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            if pos > self.data_start_pos {
+                sync_pack(&mut self.reader)?;
+            }
+
+            self.frames.clear();
+
+            for stream in self.streams.iter_mut() {
+                stream.reset();
+            }
+        }
+        else if self.streams[stream_idx].next_ts > required_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // Read frames until the frame of the track containing the required timestamp.
+        let actual_ts = loop {
+            let (id, frame) = match self.next_frame()? {
+                Some(frame) => frame,
+                None => return seek_error(SeekErrorKind::OutOfRange),
+            };
+
+            let stream = self.streams.iter_mut().find(|stream| stream.id == id).unwrap();
+
+            if u32::from(id) == track_id {
+                let ts = stream.timestamp(&frame, self.start_pts);
+
+                if ts + frame.dur.max(1) > required_ts {
+                    self.frames.push_front((id, frame));
+                    break ts;
+                }
+            }
+
+            // Frames preceding the required timestamp are discarded.
+            stream.packet(frame, self.start_pts);
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,241 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecType, CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S24BE};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+
+use symphonia_utils_mpeg::frame::Frame;
+use symphonia_utils_mpeg::pes::{PTS_FREQ, PTS_MASK};
+
+use log::warn;
+
+/// The length of the header preceding the samples of every LPCM PES packet.
+const LPCM_HEADER_LEN: usize = 3;
+
+/// The maximum length of the payload of a PES packet.
+const MAX_PAYLOAD_LEN: usize = 65536;
+
+/// The format of DVD LPCM audio.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LpcmFormat {
+    pub sample_rate: u32,
+    pub num_channels: u32,
+    pub bits_per_sample: u32,
+}
+
+impl LpcmFormat {
+    /// Reads the format from the header of an LPCM PES packet.
+    fn read(header: &[u8]) -> Result<Self> {
+        let bits_per_sample = match header[1] >> 6 {
+            0 => 16,
+            1 => 20,
+            2 => 24,
+            _ => return decode_error("mpegps: invalid lpcm quantization"),
+        };
+
+        let sample_rate = [48000, 96000, 44100, 32000][usize::from((header[1] >> 4) & 0x3)];
+        let num_channels = u32::from(header[1] & 0x7) + 1;
+
+        Ok(LpcmFormat { sample_rate, num_channels, bits_per_sample })
+    }
+
+    /// The codec the samples are converted to. Samples of 20 and 24 bits are converted to 24-bit
+    /// samples.
+    pub fn codec(&self) -> CodecType {
+        if self.bits_per_sample == 16 {
+            CODEC_TYPE_PCM_S16BE
+        }
+        else {
+            CODEC_TYPE_PCM_S24BE
+        }
+    }
+
+    pub fn bits_per_coded_sample(&self) -> u32 {
+        if self.bits_per_sample == 16 {
+            16
+        }
+        else {
+            24
+        }
+    }
+
+    pub fn channels(&self) -> Channels {
+        Channels::from_bits_truncate(((1u64 << self.num_channels) - 1) as u32)
+    }
+
+    /// The number of samples per channel in a block. Samples of 20 and 24 bits are stored in
+    /// groups spanning several samples per channel.
+    fn block_frames(&self) -> usize {
+        match self.bits_per_sample {
+            16 => 1,
+            _ if self.num_channels % 2 == 0 => 2,
+            _ => 4,
+        }
+    }
+
+    /// The length of a block in bytes.
+    fn block_len(&self) -> usize {
+        self.block_frames() * (self.num_channels * self.bits_per_sample) as usize / 8
+    }
+
+    /// The maximum number of samples per channel of a packet.
+    pub fn max_frames_per_packet(&self) -> u64 {
+        ((MAX_PAYLOAD_LEN / self.block_len() + 1) * self.block_frames()) as u64
+    }
+}
+
+/// Converts blocks of 20 or 24-bit samples to 24-bit big-endian samples. A group of 2 samples for
+/// mono streams, or 4 samples otherwise, stores the upper 16 bits of each sample, followed by the
+/// remaining bits of each sample.
+fn convert_samples(format: &LpcmFormat, buf: &[u8]) -> Box<[u8]> {
+    let group_samples = if format.num_channels == 1 { 2 } else { 4 };
+
+    let group_len = match format.bits_per_sample {
+        20 => group_samples * 5 / 2,
+        _ => group_samples * 3,
+    };
+
+    let mut out = Vec::with_capacity(buf.len() / group_len * group_samples * 3);
+
+    for group in buf.chunks_exact(group_len) {
+        let (msbs, lsbs) = group.split_at(2 * group_samples);
+
+        for (i, msb) in msbs.chunks_exact(2).enumerate() {
+            let lsb = match format.bits_per_sample {
+                20 => (lsbs[i / 2] << (4 * (i % 2))) & 0xf0,
+                _ => lsbs[i],
+            };
+
+            out.extend_from_slice(&[msb[0], msb[1], lsb]);
+        }
+    }
+
+    out.into_boxed_slice()
+}
+
+/// A parser of the payloads of the PES packets of a DVD LPCM stream.
+pub struct LpcmParser {
+    buf: Vec<u8>,
+    /// The presentation timestamp of the start of the buffer, if known.
+    pts: Option<u64>,
+    /// The format of the stream, once a PES packet has been read.
+    pub format: Option<LpcmFormat>,
+}
+
+impl LpcmParser {
+    pub fn new() -> Self {
+        LpcmParser { buf: Vec::new(), pts: None, format: None }
+    }
+
+    /// Discards all buffered data.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.pts = None;
+    }
+
+    /// Adds the payload of a PES packet, without the private stream 1 header.
+    pub fn push(&mut self, data: &[u8], pts: Option<u64>) -> Result<()> {
+        if data.len() < LPCM_HEADER_LEN {
+            return decode_error("mpegps: lpcm packet too short");
+        }
+
+        let format = LpcmFormat::read(&data[..LPCM_HEADER_LEN])?;
+
+        match self.format {
+            Some(current) if current != format => {
+                return unsupported_error("mpegps: lpcm format changes are not supported");
+            }
+            Some(_) => (),
+            None => self.format = Some(format),
+        }
+
+        if let Some(pts) = pts {
+            // The timestamp applies to the first sample of the packet, but the buffer may start
+            // with the remainder of the previous packet.
+            let bits_per_frame = (format.num_channels * format.bits_per_sample) as u64;
+            let buffered = 8 * self.buf.len() as u64 / bits_per_frame;
+            let delta = buffered * PTS_FREQ / u64::from(format.sample_rate);
+
+            self.pts = Some(pts.wrapping_sub(delta) & PTS_MASK);
+        }
+
+        self.buf.extend_from_slice(&data[LPCM_HEADER_LEN..]);
+
+        Ok(())
+    }
+
+    /// Gets the next packet. If `eos` is true, then no more data will be added.
+    pub fn next_frame(&mut self, eos: bool) -> Option<Frame> {
+        let format = self.format?;
+
+        let block_len = format.block_len();
+        let num_blocks = self.buf.len() / block_len;
+
+        if num_blocks == 0 {
+            if eos && !self.buf.is_empty() {
+                warn!("mpegps: discarding {} bytes of an incomplete lpcm block", self.buf.len());
+                self.reset();
+            }
+            return None;
+        }
+
+        let len = num_blocks * block_len;
+
+        let data = match format.bits_per_sample {
+            16 => Box::from(&self.buf[..len]),
+            _ => convert_samples(&format, &self.buf[..len]),
+        };
+
+        self.buf.drain(..len);
+
+        let dur = (num_blocks * format.block_frames()) as u64;
+
+        Some(Frame { data, pts: self.pts.take(), dur })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_lpcm_parser() {
+        // 20-bit, 48 kHz, stereo.
+        let format = LpcmFormat { sample_rate: 48000, num_channels: 2, bits_per_sample: 20 };
+        assert_eq!(format.block_len(), 10);
+
+        let mut parser = LpcmParser::new();
+
+        // A block of 2 stereo samples, and half of the next block.
+        let data = [
+            0x00, 0x41, 0x80, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xab, 0xcd, 0xff, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        parser.push(&data, Some(9000)).unwrap();
+
+        assert_eq!(parser.format, Some(format));
+
+        let frame = parser.next_frame(false).unwrap();
+        assert_eq!(frame.pts, Some(9000));
+        assert_eq!(frame.dur, 2);
+        assert_eq!(
+            &frame.data[..],
+            &[0x12, 0x34, 0xc0, 0x56, 0x78, 0xd0, 0x9a, 0xbc, 0xf0, 0xde, 0xab, 0xf0]
+        );
+
+        assert!(parser.next_frame(false).is_none());
+
+        // The timestamp of the next packet is adjusted for the buffered sample.
+        parser.push(&[0x00, 0x41, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00], Some(9000)).unwrap();
+
+        let frame = parser.next_frame(true).unwrap();
+        assert_eq!(frame.pts, Some(8999));
+        assert_eq!(frame.dur, 2);
+        assert!(parser.next_frame(true).is_none());
+    }
+}
@@ -0,0 +1,168 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io;
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::{MediaSourceStream, ReadBytes, SeekBuffered};
+
+use symphonia_utils_mpeg::pes::read_timestamp;
+
+use log::warn;
+
+/// The start code of a pack header.
+pub const PACK_START_CODE: u8 = 0xba;
+
+/// The start code of a system header.
+const SYSTEM_HEADER_START_CODE: u8 = 0xbb;
+
+/// The start code marking the end of a program stream.
+const PROGRAM_END_CODE: u8 = 0xb9;
+
+/// The length of the start code prefix, and the start code.
+const START_CODE_LEN: usize = 4;
+
+/// A unit of a program stream.
+pub enum Unit {
+    /// A pack header, and the base of its system clock reference in 90 kHz units, if valid.
+    Pack(Option<u64>),
+    /// A PES packet, and its stream ID. The entire packet is read into the buffer.
+    Pes(u8),
+    /// A system header, or the end of the program stream.
+    Other,
+}
+
+/// Converts an unexpected end of stream into `None`.
+fn or_eof<T>(result: io::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Reads up-to, and including, the next start code, and returns the start code. Returns `None` at
+/// the end of the stream.
+fn read_start_code(reader: &mut MediaSourceStream) -> Result<Option<u8>> {
+    let mut word = u32::MAX;
+    let mut len = 0;
+
+    loop {
+        let byte = match or_eof(reader.read_byte())? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+
+        word = (word << 8) | u32::from(byte);
+        len += 1;
+
+        if word & 0xffff_ff00 == 0x0000_0100 && byte >= PROGRAM_END_CODE {
+            if len > START_CODE_LEN {
+                warn!("mpegps: skipped {} bytes to synchronize", len - START_CODE_LEN);
+            }
+
+            return Ok(Some(byte));
+        }
+    }
+}
+
+/// Synchronizes to the next pack header. Returns false at the end of the stream.
+pub fn sync_pack(reader: &mut MediaSourceStream) -> Result<bool> {
+    let mut word = u32::MAX;
+
+    loop {
+        let byte = match or_eof(reader.read_byte())? {
+            Some(byte) => byte,
+            None => return Ok(false),
+        };
+
+        word = (word << 8) | u32::from(byte);
+
+        if word == 0x0000_0100 | u32::from(PACK_START_CODE) {
+            reader.seek_buffered_rev(START_CODE_LEN);
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads the remainder of a pack header.
+fn read_pack_header(reader: &mut MediaSourceStream) -> Result<Option<Option<u64>>> {
+    let mut buf = [0; 10];
+
+    if or_eof(reader.read_buf_exact(&mut buf[..8]))?.is_none() {
+        return Ok(None);
+    }
+
+    // MPEG-2 pack headers start with the bits '01', while MPEG-1 pack headers start with '0010'.
+    if buf[0] & 0xc0 == 0x40 {
+        if or_eof(reader.read_buf_exact(&mut buf[8..]))?.is_none() {
+            return Ok(None);
+        }
+
+        // The system clock reference base is 33 bits with marker bits, followed by a 9-bit
+        // extension.
+        let scr = (u64::from((buf[0] >> 3) & 0x7) << 30)
+            | (u64::from(buf[0] & 0x3) << 28)
+            | (u64::from(buf[1]) << 20)
+            | (u64::from(buf[2] >> 3) << 15)
+            | (u64::from(buf[2] & 0x3) << 13)
+            | (u64::from(buf[3]) << 5)
+            | u64::from(buf[4] >> 3);
+
+        let stuffing_len = buf[9] & 0x7;
+
+        if or_eof(reader.ignore_bytes(u64::from(stuffing_len)))?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Some(scr)))
+    }
+    else if buf[0] & 0xf0 == 0x20 {
+        Ok(Some(Some(read_timestamp(&buf[..5]))))
+    }
+    else {
+        warn!("mpegps: invalid pack header");
+        Ok(Some(None))
+    }
+}
+
+/// Reads the next unit of a program stream. Returns `None` at the end of the stream.
+pub fn read_unit(reader: &mut MediaSourceStream, buf: &mut Vec<u8>) -> Result<Option<Unit>> {
+    let start_code = match read_start_code(reader)? {
+        Some(start_code) => start_code,
+        None => return Ok(None),
+    };
+
+    match start_code {
+        PACK_START_CODE => Ok(read_pack_header(reader)?.map(Unit::Pack)),
+        PROGRAM_END_CODE => Ok(Some(Unit::Other)),
+        _ => {
+            // The system header, and PES packets, start with their length.
+            let len = match or_eof(reader.read_be_u16())? {
+                Some(len) => usize::from(len),
+                None => return Ok(None),
+            };
+
+            buf.clear();
+            buf.extend_from_slice(&[0x00, 0x00, 0x01, start_code]);
+            buf.extend_from_slice(&(len as u16).to_be_bytes());
+            buf.resize(START_CODE_LEN + 2 + len, 0);
+
+            // A packet cut short by the end of the stream is discarded.
+            if or_eof(reader.read_buf_exact(&mut buf[START_CODE_LEN + 2..]))?.is_none() {
+                return Ok(None);
+            }
+
+            if start_code == SYSTEM_HEADER_START_CODE {
+                Ok(Some(Unit::Other))
+            }
+            else {
+                Ok(Some(Unit::Pes(start_code)))
+            }
+        }
+    }
+}
@@ -0,0 +1,664 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::CodecParameters;
+use symphonia_core::errors::{end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_utils_mpeg::frame::{EsCodec, Frame, FrameParser};
+use symphonia_utils_mpeg::pes::{pts_to_ts, read_pes_pts, PesReader};
+
+use log::{debug, warn};
+
+use crate::packet::{detect_packet_len, read_ts_packet, TsPacket, SYNC_BYTE, TS_PACKET_LEN};
+use crate::psi::{read_pat, read_pmt, SectionReader};
+
+/// The PID of the packets carrying the program association table.
+const PAT_PID: u16 = 0x0000;
+
+/// The number of bytes read to detect the packet length.
+const DETECT_LEN: usize = 4 * 204;
+
+/// The maximum number of bytes to read to find the audio streams, and their codec parameters.
+const MAX_PROBE_LEN: u64 = 4 * 1024 * 1024;
+
+/// The number of bytes at the end of the stream to search for the last presentation timestamps.
+const DURATION_SCAN_LEN: u64 = 512 * 1024;
+
+/// The state of an audio elementary stream.
+struct EsState {
+    pid: u16,
+    pes: PesReader,
+    parser: FrameParser,
+    language: Option<String>,
+    sample_rate: u32,
+    /// The continuity counter of the last packet, if any.
+    continuity_counter: Option<u8>,
+    /// The timestamp of the next packet.
+    next_ts: u64,
+    /// If true, the timestamp of the next packet is taken from its presentation timestamp.
+    resync: bool,
+}
+
+impl EsState {
+    fn new(pid: u16, codec: EsCodec, language: Option<String>) -> Self {
+        EsState {
+            pid,
+            pes: PesReader::new(),
+            parser: FrameParser::new(codec),
+            language,
+            sample_rate: 0,
+            continuity_counter: None,
+            next_ts: 0,
+            resync: true,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pes.reset();
+        self.parser.reset();
+        self.continuity_counter = None;
+        self.resync = true;
+    }
+
+    /// Gets the timestamp of a frame.
+    fn timestamp(&self, frame: &Frame, start_pts: u64) -> u64 {
+        match frame.pts {
+            Some(pts) => {
+                let ts = pts_to_ts(pts, start_pts, self.sample_rate);
+
+                // The presentation timestamps are rounded to 90 kHz units, therefore, small
+                // deviations from the expected timestamp are ignored.
+                let delta = if ts > self.next_ts { ts - self.next_ts } else { self.next_ts - ts };
+
+                if self.resync || delta > frame.dur / 2 {
+                    ts
+                }
+                else {
+                    self.next_ts
+                }
+            }
+            None => self.next_ts,
+        }
+    }
+
+    /// Gets the packet of a frame.
+    fn packet(&mut self, frame: Frame, start_pts: u64) -> Packet {
+        let ts = self.timestamp(&frame, start_pts);
+
+        if frame.pts.is_some() {
+            self.resync = false;
+        }
+
+        self.next_ts = ts + frame.dur;
+
+        Packet::new_from_boxed_slice(u32::from(self.pid), ts, frame.dur, frame.data)
+    }
+}
+
+/// Reads the next transport stream packet into the buffer, synchronizing to the next
+/// synchronization byte if required, and skips the bytes following it up-to the next packet.
+/// Returns false at the end of the stream.
+fn read_packet(reader: &mut MediaSourceStream, buf: &mut [u8], packet_len: usize) -> Result<bool> {
+    let mut filled = 0;
+
+    loop {
+        match reader.read_buf_exact(&mut buf[filled..]) {
+            Ok(_) => (),
+            // A partial packet at the end of the stream is discarded.
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+
+        if buf[0] == SYNC_BYTE {
+            // The time code of the next M2TS packet, or the parity bytes, may be missing at the
+            // end of the stream.
+            return match reader.ignore_bytes((packet_len - TS_PACKET_LEN) as u64) {
+                Ok(_) => Ok(true),
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(true),
+                Err(err) => Err(err.into()),
+            };
+        }
+
+        let skip = buf[1..].iter().position(|&b| b == SYNC_BYTE).map_or(buf.len(), |pos| pos + 1);
+
+        warn!("mpegts: skipping {} bytes to synchronize", skip);
+
+        buf.copy_within(skip.., 0);
+        filled = buf.len() - skip;
+    }
+}
+
+/// Adds the payload of a transport stream packet to the audio elementary stream it belongs to, if
+/// any, and queues the frames completed by it.
+fn push_es(
+    streams: &mut [EsState],
+    frames: &mut VecDeque<(u16, Frame)>,
+    packet: &TsPacket<'_>,
+) -> Result<()> {
+    let stream = match streams.iter_mut().find(|stream| stream.pid == packet.pid) {
+        Some(stream) => stream,
+        None => return Ok(()),
+    };
+
+    if packet.discontinuity {
+        stream.resync = true;
+    }
+
+    // The continuity counter is only incremented by packets with a payload.
+    if packet.payload.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(counter) = stream.continuity_counter {
+        // A duplicate packet is sent at most once after the original.
+        if packet.continuity_counter == counter {
+            return Ok(());
+        }
+
+        if packet.continuity_counter != (counter + 1) & 0xf && !packet.discontinuity {
+            warn!("mpegts: packets lost on pid {}", stream.pid);
+
+            stream.reset();
+        }
+    }
+
+    stream.continuity_counter = Some(packet.continuity_counter);
+
+    match stream.pes.push(packet.payload, packet.payload_start) {
+        Ok(payload) => stream.parser.push(payload.data, payload.pts),
+        Err(err) => warn!("{}", err),
+    }
+
+    while let Some(frame) = stream.parser.next_frame(false)? {
+        frames.push_back((stream.pid, frame));
+    }
+
+    Ok(())
+}
+
+/// MPEG transport stream format reader.
+///
+/// `MpegTsReader` implements a demuxer for the audio elementary streams of an MPEG transport
+/// stream: MPEG audio, AAC in ADTS or LATM frames, and AC-3 or E-AC-3. Each audio elementary
+/// stream is exposed as a track with a track ID equal to its PID, and a time base of one sample.
+/// The timestamps of all tracks are measured from the earliest presentation timestamp.
+pub struct MpegTsReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    streams: Vec<EsState>,
+    /// The buffer of a transport stream packet.
+    buf: [u8; TS_PACKET_LEN],
+    /// The length of a packet, including any bytes between transport stream packets.
+    packet_len: usize,
+    data_start_pos: u64,
+    /// The presentation timestamp of the start of the stream.
+    start_pts: u64,
+    /// The frames read, but not yet returned, and the PIDs of their streams.
+    frames: VecDeque<(u16, Frame)>,
+}
+
+impl QueryDescriptor for MpegTsReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "mpegts",
+            "MPEG Transport Stream",
+            &["ts", "m2ts", "mts"],
+            &["video/mp2t", "audio/mp2t"],
+            &[&[SYNC_BYTE, 0x40, 0x00]]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl MpegTsReader {
+    /// Gets the next frame, or `None` at the end of the stream.
+    fn next_frame(&mut self) -> Result<Option<(u16, Frame)>> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+
+            if !read_packet(&mut self.reader, &mut self.buf, self.packet_len)? {
+                // Any frames remaining at the end of the stream are complete.
+                for stream in self.streams.iter_mut() {
+                    while let Some(frame) = stream.parser.next_frame(true)? {
+                        self.frames.push_back((stream.pid, frame));
+                    }
+                }
+
+                if self.frames.is_empty() {
+                    return Ok(None);
+                }
+
+                continue;
+            }
+
+            match read_ts_packet(&self.buf) {
+                Ok(Some(packet)) => push_es(&mut self.streams, &mut self.frames, &packet)?,
+                Ok(None) => (),
+                Err(err) => warn!("{}", err),
+            }
+        }
+    }
+
+    /// Reads the program specific information, and the first frames of every audio elementary
+    /// stream to determine its codec parameters. Returns the first program clock reference of
+    /// any program, if any.
+    fn read_streams(&mut self) -> Result<Option<u64>> {
+        let mut pat = SectionReader::default();
+        let mut is_pat_read = false;
+
+        // The PIDs of the packets carrying the program clock references of the programs.
+        let mut pcr_pids = Vec::new();
+        let mut first_pcr = None;
+
+        // The program map sections, their PIDs, and if they were read.
+        let mut pmts: Vec<(u16, SectionReader, bool)> = Vec::new();
+
+        loop {
+            if self.reader.pos() - self.data_start_pos > MAX_PROBE_LEN {
+                warn!("mpegts: stopped searching for audio streams");
+                break;
+            }
+
+            if !read_packet(&mut self.reader, &mut self.buf, self.packet_len)? {
+                for stream in self.streams.iter_mut() {
+                    while let Some(frame) = stream.parser.next_frame(true)? {
+                        self.frames.push_back((stream.pid, frame));
+                    }
+                }
+                break;
+            }
+
+            let packet = match read_ts_packet(&self.buf) {
+                Ok(Some(packet)) => packet,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!("{}", err);
+                    continue;
+                }
+            };
+
+            if packet.pid == PAT_PID {
+                for section in pat.push(packet.payload, packet.payload_start) {
+                    let programs = match read_pat(&section) {
+                        Ok(programs) => programs,
+                        Err(err) => {
+                            warn!("{}", err);
+                            continue;
+                        }
+                    };
+
+                    for (program, pid) in programs {
+                        if !pmts.iter().any(|pmt| pmt.0 == pid) {
+                            debug!("program {} has a program map on pid {}", program, pid);
+                            pmts.push((pid, Default::default(), false));
+                        }
+                    }
+
+                    is_pat_read = true;
+                }
+            }
+            else if let Some(pmt) = pmts.iter_mut().find(|pmt| pmt.0 == packet.pid) {
+                for section in pmt.1.push(packet.payload, packet.payload_start) {
+                    let pmt_info = match read_pmt(&section) {
+                        Ok(pmt_info) => pmt_info,
+                        Err(err) => {
+                            warn!("{}", err);
+                            continue;
+                        }
+                    };
+
+                    for es in pmt_info.streams {
+                        if self.streams.iter().any(|stream| stream.pid == es.pid) {
+                            continue;
+                        }
+
+                        match es.codec {
+                            Some(codec) => {
+                                debug!(
+                                    "program {} has a {:?} stream on pid {}",
+                                    pmt_info.program, codec, es.pid
+                                );
+
+                                self.streams.push(EsState::new(es.pid, codec, es.language));
+                            }
+                            None => debug!(
+                                "ignoring stream type {:#x} on pid {}",
+                                es.stream_type, es.pid
+                            ),
+                        }
+                    }
+
+                    pcr_pids.push(pmt_info.pcr_pid);
+                    pmt.2 = true;
+                }
+            }
+            else {
+                if first_pcr.is_none() && pcr_pids.contains(&packet.pid) {
+                    first_pcr = packet.pcr;
+                }
+
+                push_es(&mut self.streams, &mut self.frames, &packet)?;
+            }
+
+            let is_complete = is_pat_read
+                && pmts.iter().all(|pmt| pmt.2)
+                && self.streams.iter().all(|stream| stream.parser.info.is_some());
+
+            if is_complete {
+                break;
+            }
+        }
+
+        // Streams without a frame cannot be decoded.
+        self.streams.retain(|stream| {
+            if stream.parser.info.is_none() {
+                warn!("mpegts: ignoring stream on pid {} without frames", stream.pid);
+            }
+            stream.parser.info.is_some()
+        });
+
+        if self.streams.is_empty() {
+            return unsupported_error("mpegts: no supported audio streams");
+        }
+
+        Ok(first_pcr)
+    }
+
+    /// Gets the last presentation timestamp of every stream, if the stream is seekable.
+    fn read_last_pts(&mut self) -> Result<Vec<(u16, u64)>> {
+        let mut last = Vec::new();
+
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) if self.reader.is_seekable() => byte_len,
+            _ => return Ok(last),
+        };
+
+        let packet_len = self.packet_len as u64;
+        let data_len = byte_len.saturating_sub(self.data_start_pos);
+        let offset = data_len.saturating_sub(DURATION_SCAN_LEN) / packet_len * packet_len;
+
+        let pos = self.reader.pos();
+
+        self.reader.seek(SeekFrom::Start(self.data_start_pos + offset))?;
+
+        while read_packet(&mut self.reader, &mut self.buf, self.packet_len)? {
+            let packet = match read_ts_packet(&self.buf) {
+                Ok(Some(packet)) if packet.payload_start => packet,
+                _ => continue,
+            };
+
+            if !self.streams.iter().any(|stream| stream.pid == packet.pid) {
+                continue;
+            }
+
+            if let Some(pts) = read_pes_pts(packet.payload) {
+                match last.iter_mut().find(|(pid, _)| *pid == packet.pid) {
+                    Some((_, last_pts)) => *last_pts = pts,
+                    None => last.push((packet.pid, pts)),
+                }
+            }
+        }
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        Ok(last)
+    }
+
+    /// Gets the timestamp of the first PES packet of a stream starting between two positions.
+    fn read_pes_ts(&mut self, stream_idx: usize, pos: u64, end: u64) -> Result<Option<u64>> {
+        let pid = self.streams[stream_idx].pid;
+        let sample_rate = self.streams[stream_idx].sample_rate;
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        while self.reader.pos() < end
+            && read_packet(&mut self.reader, &mut self.buf, self.packet_len)?
+        {
+            let packet = match read_ts_packet(&self.buf) {
+                Ok(Some(packet)) if packet.pid == pid && packet.payload_start => packet,
+                _ => continue,
+            };
+
+            if let Some(pts) = read_pes_pts(packet.payload) {
+                return Ok(Some(pts_to_ts(pts, self.start_pts, sample_rate)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the position of a packet such that the first PES packet of a stream starting after it
+    /// starts at, or before, a timestamp.
+    fn find_seek_pos(&mut self, stream_idx: usize, ts: u64) -> Result<u64> {
+        let byte_len = match self.reader.byte_len() {
+            Some(byte_len) => byte_len,
+            None => return Ok(self.data_start_pos),
+        };
+
+        let packet_len = self.packet_len as u64;
+
+        let mut lo = 0;
+        let mut hi = byte_len.saturating_sub(self.data_start_pos) / packet_len;
+
+        // Bisect the packets. The first PES packet after packet `lo` starts at, or before, the
+        // timestamp, while the first PES packet after packet `hi` does not.
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+
+            let pos = self.data_start_pos + mid * packet_len;
+            let end = self.data_start_pos + hi * packet_len;
+
+            match self.read_pes_ts(stream_idx, pos, end)? {
+                Some(pes_ts) if pes_ts <= ts => lo = mid,
+                _ => hi = mid,
+            }
+        }
+
+        Ok(self.data_start_pos + lo * packet_len)
+    }
+}
+
+impl FormatReader for MpegTsReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // The stream may be shorter than the number of bytes required to detect the packet
+        // length.
+        let mut buf = vec![0; DETECT_LEN];
+        let mut len = 0;
+
+        while len < buf.len() {
+            match source.read_buf(&mut buf[len..]) {
+                Ok(read) => len += read,
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        source.seek_buffered_rev(len);
+        buf.truncate(len);
+
+        let packet_len = match detect_packet_len(&buf) {
+            Some(packet_len) => packet_len,
+            None => return unsupported_error("mpegts: missing transport stream packets"),
+        };
+
+        debug!("packet length is {} bytes", packet_len);
+
+        let mut reader = MpegTsReader {
+            data_start_pos: source.pos(),
+            reader: source,
+            tracks: Vec::new(),
+            cues: Vec::new(),
+            metadata: Default::default(),
+            streams: Vec::new(),
+            buf: [0; TS_PACKET_LEN],
+            packet_len,
+            start_pts: 0,
+            frames: VecDeque::new(),
+        };
+
+        let first_pcr = reader.read_streams()?;
+
+        // The stream starts at the earliest presentation timestamp of the first frame of any
+        // stream, or the first program clock reference if there are none.
+        let start_pts = reader
+            .streams
+            .iter()
+            .filter_map(|stream| {
+                reader.frames.iter().find(|(pid, _)| *pid == stream.pid).and_then(|(_, f)| f.pts)
+            })
+            .min();
+
+        reader.start_pts = start_pts.or(first_pcr).unwrap_or(0);
+
+        for stream in reader.streams.iter_mut() {
+            stream.sample_rate = stream.parser.info.as_ref().unwrap().sample_rate;
+        }
+
+        let last_pts = reader.read_last_pts()?;
+
+        for stream in reader.streams.iter() {
+            let info = stream.parser.info.as_ref().unwrap();
+
+            let mut params = CodecParameters::new();
+
+            params
+                .for_codec(info.codec)
+                .with_sample_rate(info.sample_rate)
+                .with_time_base(TimeBase::new(1, info.sample_rate));
+
+            if let Some(channels) = info.channels {
+                params.with_channels(channels);
+            }
+
+            if let Some(extra_data) = &info.extra_data {
+                params.with_extra_data(extra_data.clone());
+            }
+
+            // The duration is estimated from the presentation timestamp of the last PES packet.
+            if let Some(&(_, pts)) = last_pts.iter().find(|(pid, _)| *pid == stream.pid) {
+                let n_frames = pts_to_ts(pts, reader.start_pts, stream.sample_rate);
+
+                if n_frames > 0 {
+                    params.with_n_frames(n_frames);
+                }
+            }
+
+            let mut track = Track::new(u32::from(stream.pid), params);
+            track.language = stream.language.clone();
+
+            reader.tracks.push(track);
+        }
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (pid, frame) = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        let stream = self.streams.iter_mut().find(|stream| stream.pid == pid).unwrap();
+
+        Ok(stream.packet(frame, self.start_pts))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let track_id = match to {
+            SeekTo::TimeStamp { track_id, .. } => track_id,
+            SeekTo::Time { track_id, .. } => track_id.unwrap_or(self.tracks[0].id),
+        };
+
+        let stream_idx = match self.streams.iter().position(|s| u32::from(s.pid) == track_id) {
+            Some(stream_idx) => stream_idx,
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.streams[stream_idx].sample_rate).calc_timestamp(time)
+            }
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        if self.reader.is_seekable() {
+            let pos = self.find_seek_pos(stream_idx, required_ts)?;
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            self.frames.clear();
+
+            for stream in self.streams.iter_mut() {
+                stream.reset();
+            }
+        }
+        else if self.streams[stream_idx].next_ts > required_ts {
+            // If the reader is not seekable then only forward seeks are possible.
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        // Read frames until the frame of the track containing the required timestamp.
+        let actual_ts = loop {
+            let (pid, frame) = match self.next_frame()? {
+                Some(frame) => frame,
+                None => return seek_error(SeekErrorKind::OutOfRange),
+            };
+
+            let stream = self.streams.iter_mut().find(|stream| stream.pid == pid).unwrap();
+
+            if u32::from(pid) == track_id {
+                let ts = stream.timestamp(&frame, self.start_pts);
+
+                if ts + frame.dur.max(1) > required_ts {
+                    self.frames.push_front((pid, frame));
+                    break ts;
+                }
+            }
+
+            // Frames preceding the required timestamp are discarded.
+            stream.packet(frame, self.start_pts);
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,136 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+/// The length of a transport stream packet.
+pub const TS_PACKET_LEN: usize = 188;
+
+/// The synchronization byte at the start of every transport stream packet.
+pub const SYNC_BYTE: u8 = 0x47;
+
+/// The lengths of the packets of the supported packet formats: plain transport stream packets,
+/// M2TS (Blu-ray, AVCHD) packets with a 4-byte time code, and packets followed by 16 bytes of
+/// Reed-Solomon parity.
+const PACKET_LENS: [usize; 3] = [188, 192, 204];
+
+/// The number of consecutive synchronization bytes required to detect the packet length.
+const NUM_SYNC_PACKETS: usize = 4;
+
+/// A transport stream packet.
+pub struct TsPacket<'a> {
+    pub pid: u16,
+    /// If true, a PES packet or PSI section starts in the payload.
+    pub payload_start: bool,
+    pub continuity_counter: u8,
+    /// If true, the continuity counter or the timebase is discontinuous at this packet.
+    pub discontinuity: bool,
+    /// The base of the program clock reference in 90 kHz units, if present.
+    pub pcr: Option<u64>,
+    pub payload: &'a [u8],
+}
+
+/// Reads a transport stream packet. Returns `None` if the packet has the transport error
+/// indicator set.
+pub fn read_ts_packet(buf: &[u8]) -> Result<Option<TsPacket<'_>>> {
+    if buf.len() < TS_PACKET_LEN || buf[0] != SYNC_BYTE {
+        return decode_error("mpegts: missing sync byte");
+    }
+
+    let buf = &buf[..TS_PACKET_LEN];
+
+    if buf[1] & 0x80 != 0 {
+        return Ok(None);
+    }
+
+    let payload_start = buf[1] & 0x40 != 0;
+    let pid = (u16::from(buf[1] & 0x1f) << 8) | u16::from(buf[2]);
+    let adaptation_field_control = (buf[3] >> 4) & 0x3;
+    let continuity_counter = buf[3] & 0xf;
+
+    let mut discontinuity = false;
+    let mut pcr = None;
+    let mut pos = 4;
+
+    if adaptation_field_control & 0x2 != 0 {
+        let len = usize::from(buf[4]);
+
+        if 5 + len > TS_PACKET_LEN {
+            return decode_error("mpegts: invalid adaptation field length");
+        }
+
+        if len > 0 {
+            let flags = buf[5];
+
+            discontinuity = flags & 0x80 != 0;
+
+            // The program clock reference base is 33 bits, followed by a 9-bit extension.
+            if flags & 0x10 != 0 && len >= 7 {
+                let b = &buf[6..11];
+
+                pcr = Some(
+                    (u64::from(b[0]) << 25)
+                        | (u64::from(b[1]) << 17)
+                        | (u64::from(b[2]) << 9)
+                        | (u64::from(b[3]) << 1)
+                        | (u64::from(b[4]) >> 7),
+                );
+            }
+        }
+
+        pos += 1 + len;
+    }
+
+    let payload = if adaptation_field_control & 0x1 != 0 { &buf[pos..] } else { &buf[..0] };
+
+    Ok(Some(TsPacket { pid, payload_start, continuity_counter, discontinuity, pcr, payload }))
+}
+
+/// Detects the length of the packets of a transport stream from a buffer starting with a
+/// synchronization byte. Returns `None` if the synchronization bytes do not repeat. A buffer
+/// shorter than a few packets, such as a very short stream, is checked as far as it goes.
+pub fn detect_packet_len(buf: &[u8]) -> Option<usize> {
+    PACKET_LENS.iter().copied().find(|&len| {
+        (0..NUM_SYNC_PACKETS).all(|i| buf.get(i * len).map_or(i > 0, |&b| b == SYNC_BYTE))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_ts_packet() {
+        let mut buf = [0xff; TS_PACKET_LEN];
+
+        // PID 0x101, payload start, adaptation field with a PCR, and a payload.
+        buf[..4].copy_from_slice(&[0x47, 0x41, 0x01, 0x37]);
+        buf[4..12].copy_from_slice(&[7, 0x10, 0x00, 0x00, 0x01, 0x00, 0xfe, 0x00]);
+
+        let packet = read_ts_packet(&buf).unwrap().unwrap();
+
+        assert_eq!(packet.pid, 0x101);
+        assert!(packet.payload_start);
+        assert_eq!(packet.continuity_counter, 7);
+        assert_eq!(packet.pcr, Some(0x201));
+        assert_eq!(packet.payload.len(), TS_PACKET_LEN - 12);
+
+        // The transport error indicator is set.
+        buf[1] |= 0x80;
+        assert!(read_ts_packet(&buf).unwrap().is_none());
+
+        let mut stream = vec![0; 192 * NUM_SYNC_PACKETS];
+
+        for packet in stream.chunks_exact_mut(192) {
+            packet[0] = SYNC_BYTE;
+        }
+
+        assert_eq!(detect_packet_len(&stream), Some(192));
+        assert_eq!(detect_packet_len(&stream[1..]), None);
+        assert_eq!(detect_packet_len(&stream[..400]), Some(192));
+    }
+}
@@ -0,0 +1,302 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::checksum::Crc32;
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::Monitor;
+
+use symphonia_utils_mpeg::frame::EsCodec;
+
+use log::warn;
+
+/// The table ID of a program association section.
+const PAT_TABLE_ID: u8 = 0x00;
+
+/// The table ID of a program map section.
+const PMT_TABLE_ID: u8 = 0x02;
+
+/// The length of the header of a long-form section.
+const SECTION_HEADER_LEN: usize = 8;
+
+/// The length of the CRC at the end of a long-form section.
+const SECTION_CRC_LEN: usize = 4;
+
+/// A reassembler of the program specific information (PSI) sections carried by the packets of a
+/// PID.
+#[derive(Default)]
+pub struct SectionReader {
+    /// The bytes of the sections being reassembled. If empty, no section is in progress.
+    buf: Vec<u8>,
+}
+
+impl SectionReader {
+    /// Adds the payload of a packet, and returns the complete sections with a valid CRC.
+    pub fn push(&mut self, payload: &[u8], payload_start: bool) -> Vec<Box<[u8]>> {
+        let mut sections = Vec::new();
+
+        if payload_start {
+            // The pointer field is the number of bytes of the previous section preceding the start
+            // of a new section.
+            let pointer = match payload.first() {
+                Some(&pointer) => usize::from(pointer),
+                None => return sections,
+            };
+
+            if 1 + pointer > payload.len() {
+                self.buf.clear();
+                return sections;
+            }
+
+            if !self.buf.is_empty() {
+                self.buf.extend_from_slice(&payload[1..1 + pointer]);
+                self.take_sections(&mut sections);
+            }
+
+            self.buf.clear();
+            self.buf.extend_from_slice(&payload[1 + pointer..]);
+        }
+        else if !self.buf.is_empty() {
+            self.buf.extend_from_slice(payload);
+        }
+
+        self.take_sections(&mut sections);
+
+        sections
+    }
+
+    /// Removes the complete sections from the start of the buffer.
+    fn take_sections(&mut self, sections: &mut Vec<Box<[u8]>>) {
+        loop {
+            // The sections of a packet may be followed by stuffing bytes.
+            if self.buf.first() == Some(&0xff) {
+                self.buf.clear();
+            }
+
+            if self.buf.len() < 3 {
+                break;
+            }
+
+            let len = 3 + ((usize::from(self.buf[1] & 0xf) << 8) | usize::from(self.buf[2]));
+
+            if self.buf.len() < len {
+                break;
+            }
+
+            let section = self.buf.drain(..len).collect::<Box<[u8]>>();
+
+            // The CRC of a long-form section, calculated over the entire section including the
+            // CRC, is 0.
+            if section[1] & 0x80 != 0 {
+                let mut crc = Crc32::new(0xffff_ffff);
+                crc.process_buf_bytes(&section);
+
+                if crc.crc() != 0 {
+                    warn!("mpegts: section crc mismatch");
+                    continue;
+                }
+            }
+
+            sections.push(section);
+        }
+    }
+}
+
+/// Gets the body of a long-form section with the given table ID, excluding the header and CRC.
+fn section_body(section: &[u8], table_id: u8) -> Result<&[u8]> {
+    if section.len() < SECTION_HEADER_LEN + SECTION_CRC_LEN || section[0] != table_id {
+        return decode_error("mpegts: invalid section");
+    }
+
+    // Sections that are not yet applicable should be ignored.
+    if section[5] & 0x1 == 0 {
+        return decode_error("mpegts: section is not current");
+    }
+
+    Ok(&section[SECTION_HEADER_LEN..section.len() - SECTION_CRC_LEN])
+}
+
+/// Reads a program association section, and returns the program numbers and PIDs of the program
+/// map sections.
+pub fn read_pat(section: &[u8]) -> Result<Vec<(u16, u16)>> {
+    let body = section_body(section, PAT_TABLE_ID)?;
+
+    let programs = body
+        .chunks_exact(4)
+        .map(|entry| {
+            let program = u16::from_be_bytes([entry[0], entry[1]]);
+            let pid = u16::from_be_bytes([entry[2] & 0x1f, entry[3]]);
+            (program, pid)
+        })
+        // Program 0 is the network information table.
+        .filter(|&(program, _)| program != 0)
+        .collect();
+
+    Ok(programs)
+}
+
+/// An elementary stream of a program.
+pub struct EsInfo {
+    pub stream_type: u8,
+    pub pid: u16,
+    /// The codec of the stream, if it is a supported audio stream.
+    pub codec: Option<EsCodec>,
+    /// The ISO 639 language code of the stream.
+    pub language: Option<String>,
+}
+
+/// A program map section.
+pub struct Pmt {
+    pub program: u16,
+    /// The PID of the packets carrying the program clock reference.
+    pub pcr_pid: u16,
+    pub streams: Vec<EsInfo>,
+}
+
+/// Gets the codec of an elementary stream from its stream type and descriptors.
+fn es_codec(stream_type: u8, descriptors: &[(u8, &[u8])]) -> Option<EsCodec> {
+    match stream_type {
+        0x03 | 0x04 => Some(EsCodec::Mpa),
+        0x0f => Some(EsCodec::Adts),
+        0x11 => Some(EsCodec::Latm),
+        // ATSC AC-3, and E-AC-3.
+        0x81 | 0x87 => Some(EsCodec::Ac3),
+        // Private data is identified by its descriptors. DVB uses the AC-3 (0x6a) and enhanced
+        // AC-3 (0x7a) descriptors, otherwise a registration descriptor (0x05) may be present.
+        0x06 => descriptors
+            .iter()
+            .find(|(tag, data)| match tag {
+                0x6a | 0x7a => true,
+                0x05 => data.starts_with(b"AC-3") || data.starts_with(b"EAC3"),
+                _ => false,
+            })
+            .map(|_| EsCodec::Ac3),
+        _ => None,
+    }
+}
+
+/// Reads the descriptors of a descriptor loop.
+fn read_descriptors(mut buf: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut descriptors = Vec::new();
+
+    while buf.len() >= 2 {
+        let tag = buf[0];
+        let len = usize::from(buf[1]).min(buf.len() - 2);
+
+        descriptors.push((tag, &buf[2..2 + len]));
+        buf = &buf[2 + len..];
+    }
+
+    descriptors
+}
+
+/// Reads a program map section.
+pub fn read_pmt(section: &[u8]) -> Result<Pmt> {
+    let body = section_body(section, PMT_TABLE_ID)?;
+
+    let program = u16::from_be_bytes([section[3], section[4]]);
+
+    if body.len() < 4 {
+        return decode_error("mpegts: invalid program map section");
+    }
+
+    let pcr_pid = u16::from_be_bytes([body[0] & 0x1f, body[1]]);
+    let program_info_len = usize::from(u16::from_be_bytes([body[2] & 0xf, body[3]]));
+
+    let mut buf = body.get(4 + program_info_len..).unwrap_or(&[]);
+
+    let mut streams = Vec::new();
+
+    while buf.len() >= 5 {
+        let stream_type = buf[0];
+        let pid = u16::from_be_bytes([buf[1] & 0x1f, buf[2]]);
+        let es_info_len = usize::from(u16::from_be_bytes([buf[3] & 0xf, buf[4]]));
+
+        let es_info = match buf.get(5..5 + es_info_len) {
+            Some(es_info) => es_info,
+            None => return decode_error("mpegts: invalid elementary stream info length"),
+        };
+
+        let descriptors = read_descriptors(es_info);
+
+        // The ISO 639 language descriptor.
+        let language = descriptors
+            .iter()
+            .find(|(tag, data)| *tag == 0x0a && data.len() >= 3)
+            .map(|(_, data)| String::from_utf8_lossy(&data[..3]).into_owned());
+
+        streams.push(EsInfo {
+            stream_type,
+            pid,
+            codec: es_codec(stream_type, &descriptors),
+            language,
+        });
+
+        buf = &buf[5 + es_info_len..];
+    }
+
+    Ok(Pmt { program, pcr_pid, streams })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends the CRC of a section to it.
+    fn with_crc(mut section: Vec<u8>) -> Vec<u8> {
+        let mut crc = Crc32::new(0xffff_ffff);
+        crc.process_buf_bytes(&section);
+        section.extend_from_slice(&crc.crc().to_be_bytes());
+        section
+    }
+
+    #[test]
+    fn verify_read_pmt() {
+        #[rustfmt::skip]
+        let pmt = with_crc(vec![
+            0x02, 0xb0, 0x25, 0x00, 0x01, 0xc1, 0x00, 0x00,
+            // PCR PID 0x100, no program info.
+            0xe1, 0x00, 0xf0, 0x00,
+            // MPEG-1 audio, PID 0x101, with a language descriptor.
+            0x03, 0xe1, 0x01, 0xf0, 0x06, 0x0a, 0x04, b'e', b'n', b'g', 0x00,
+            // Private data, PID 0x102, with an AC-3 descriptor.
+            0x06, 0xe1, 0x02, 0xf0, 0x03, 0x6a, 0x01, 0x00,
+            // H.264 video, PID 0x103.
+            0x1b, 0xe1, 0x03, 0xf0, 0x00,
+        ]);
+
+        // Split the section over two packets, followed by stuffing.
+        let mut first = vec![0x00];
+        first.extend_from_slice(&pmt[..20]);
+
+        let mut second = pmt[20..].to_vec();
+        second.extend_from_slice(&[0xff; 8]);
+
+        let mut reader = SectionReader::default();
+
+        assert!(reader.push(&first, true).is_empty());
+
+        let sections = reader.push(&second, false);
+        assert_eq!(sections.len(), 1);
+
+        let pmt = read_pmt(&sections[0]).unwrap();
+
+        assert_eq!(pmt.program, 1);
+        assert_eq!(pmt.pcr_pid, 0x100);
+        assert_eq!(pmt.streams.len(), 3);
+        assert_eq!(pmt.streams[0].codec, Some(EsCodec::Mpa));
+        assert_eq!(pmt.streams[0].language.as_deref(), Some("eng"));
+        assert_eq!(pmt.streams[1].codec, Some(EsCodec::Ac3));
+        assert_eq!(pmt.streams[2].codec, None);
+
+        // A corrupted section is rejected.
+        let mut corrupt = first.clone();
+        corrupt[10] ^= 0x1;
+
+        assert!(reader.push(&corrupt, true).is_empty());
+        assert!(reader.push(&second, false).is_empty());
+    }
+}
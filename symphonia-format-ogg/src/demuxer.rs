@@ -11,6 +11,7 @@ use std::io::{Seek, SeekFrom};
 use symphonia_core::errors::{reset_error, seek_error, unsupported_error};
 use symphonia_core::errors::{Error, Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::util::SeekSearchResult;
 use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes, SeekBuffered};
 use symphonia_core::meta::{Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
@@ -45,6 +46,9 @@ pub struct OggReader {
 
 impl OggReader {
     fn read_page(&mut self) -> Result<()> {
+        // The position from which the next page is read.
+        let pos = self.reader.pos();
+
         // Try reading pages until a page is successfully read, or an IO error.
         loop {
             match self.pages.try_next_page(&mut self.reader) {
@@ -65,6 +69,11 @@ impl OggReader {
         }
 
         if let Some(stream) = self.streams.get_mut(&page.header.serial) {
+            // If the reader is seekable, index the page to speed up later seeks.
+            if self.reader.is_seekable() {
+                stream.index_page(&page, pos);
+            }
+
             // TODO: Process side data.
             let _side_data = stream.read_page(&page)?;
         }
@@ -123,11 +132,41 @@ impl OggReader {
             let mut start_byte_pos = self.phys_byte_range_start;
             let mut end_byte_pos = self.phys_byte_range_end.unwrap();
 
+            // Narrow the byte range using the pages indexed by previous seeks and reads. An
+            // indexed page that starts after the required timestamp is an upper bound, while an
+            // indexed page that starts at, or before, the required timestamp is a lower bound.
+            match stream.index().search(required_ts) {
+                SeekSearchResult::Upper(upper) => {
+                    end_byte_pos = upper.byte_offset.min(end_byte_pos);
+                }
+                SeekSearchResult::Lower(lower) => {
+                    start_byte_pos = lower.byte_offset.max(start_byte_pos);
+                }
+                SeekSearchResult::Range(lower, upper) => {
+                    start_byte_pos = lower.byte_offset.max(start_byte_pos);
+                    end_byte_pos = upper.byte_offset.min(end_byte_pos);
+                }
+                SeekSearchResult::Stream => (),
+            }
+
+            // Start the bisection at the byte position interpolated from the index, if possible.
+            let mut interpolated_pos = stream.index().interpolate(required_ts);
+
+            if start_byte_pos > end_byte_pos {
+                // The index is inconsistent with the byte range, ignore it.
+                start_byte_pos = self.phys_byte_range_start;
+                end_byte_pos = self.phys_byte_range_end.unwrap();
+                interpolated_pos = None;
+            }
+
             // Bisect the stream while the byte range is large. For smaller ranges, a linear scan is
             // faster than having the the binary search converge.
             while end_byte_pos - start_byte_pos > 2 * OGG_PAGE_MAX_SIZE as u64 {
                 // Find the middle of the upper and lower byte search range.
-                let mid_byte_pos = (start_byte_pos + end_byte_pos) / 2;
+                let mid_byte_pos = interpolated_pos
+                    .take()
+                    .filter(|&pos| pos > start_byte_pos && pos < end_byte_pos)
+                    .unwrap_or((start_byte_pos + end_byte_pos) / 2);
 
                 // Seek to the middle of the byte range.
                 self.reader.seek(SeekFrom::Start(mid_byte_pos))?;
@@ -147,8 +186,8 @@ impl OggReader {
                     }
                 }
 
-                // Probe the page to get the start and end timestamp.
-                let (start_ts, end_ts) = stream.inspect_page(&self.pages.page());
+                // Probe the page to get the start and end timestamp, and index it.
+                let (start_ts, end_ts) = stream.index_page(&self.pages.page(), mid_byte_pos);
 
                 debug!(
                     "seek: bisect step: page={{ start_ts={}, end_ts={} }} byte_range=[{}, {}, {}]",
@@ -489,3 +528,114 @@ impl FormatReader for OggReader {
         self.reader
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::OggReader;
+    use crate::page::OGG_PAGE_MAX_SIZE;
+    use symphonia_core::checksum::Crc32;
+    use symphonia_core::formats::{FormatReader, SeekMode, SeekTo};
+    use symphonia_core::io::{MediaSourceStream, Monitor};
+
+    const SERIAL: u32 = 0x1234;
+
+    /// Builds an OGG page containing the provided packets. Each packet must be less than 255 bytes.
+    fn ogg_page(header_type: u8, absgp: u64, sequence: u32, packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+
+        page.push(0);
+        page.push(header_type);
+        page.extend_from_slice(&absgp.to_le_bytes());
+        page.extend_from_slice(&SERIAL.to_le_bytes());
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(packets.len() as u8);
+        page.extend(packets.iter().map(|packet| packet.len() as u8));
+
+        for packet in packets {
+            page.extend_from_slice(packet);
+        }
+
+        // The CRC is calculated with the CRC field zeroed.
+        let mut crc32 = Crc32::new(0);
+        crc32.process_buf_bytes(&page);
+        page[22..26].copy_from_slice(&crc32.crc().to_le_bytes());
+
+        page
+    }
+
+    /// Builds a mono OGG Opus stream with 100 pages of 50 packets. Each packet is a single 20 ms
+    /// frame of varying length.
+    fn ogg_opus_stream() -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 1, 0, 0, 0x80, 0xbb, 0, 0, 0, 0, 0]);
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&[0; 8]);
+
+        let mut file = ogg_page(0x02, 0, 0, &[head]);
+        file.extend(ogg_page(0x00, 0, 1, &[tags]));
+
+        for i in 0..100 {
+            let packets: Vec<Vec<u8>> = (0..50)
+                .map(|j| {
+                    let n = 50 * i + j;
+                    let mut packet = vec![0x08];
+                    packet.resize(40 + (n * 13) % 200, n as u8);
+                    packet
+                })
+                .collect();
+
+            let header_type = if i == 99 { 0x04 } else { 0x00 };
+            let absgp = 960 * 50 * (i as u64 + 1);
+
+            file.extend(ogg_page(header_type, absgp, i as u32 + 2, &packets));
+        }
+
+        file
+    }
+
+    #[test]
+    fn verify_accurate_seek_from_index() {
+        let file = ogg_opus_stream();
+
+        // The stream must be long enough for the seek to bisect the stream.
+        assert!(file.len() > 2 * OGG_PAGE_MAX_SIZE);
+
+        let new_reader = || {
+            let data = std::io::Cursor::new(file.clone());
+            let source = MediaSourceStream::new(Box::new(data), Default::default());
+            OggReader::try_new(source, &Default::default()).unwrap()
+        };
+
+        // Read the first 3000 packets to populate the seek index of the indexed reader.
+        let mut indexed = new_reader();
+
+        let track_id = indexed.tracks()[0].id;
+
+        for _ in 0..3000 {
+            indexed.next_packet().unwrap();
+        }
+
+        for &ts in
+            &[1500 * 960 + 100, 0, 2999 * 960 + 1, 3750 * 960 + 5, 500 * 960 + 959, 4999 * 960 + 1]
+        {
+            let to = || SeekTo::TimeStamp { ts, track_id };
+
+            // A fresh reader has nothing indexed.
+            let mut linear = new_reader();
+
+            let indexed_seeked_to = indexed.seek(SeekMode::Accurate, to()).unwrap();
+            let linear_seeked_to = linear.seek(SeekMode::Accurate, to()).unwrap();
+
+            assert_eq!(indexed_seeked_to.actual_ts, ts - ts % 960);
+            assert_eq!(indexed_seeked_to.actual_ts, linear_seeked_to.actual_ts);
+
+            let indexed_packet = indexed.next_packet().unwrap();
+            let linear_packet = linear.next_packet().unwrap();
+
+            assert_eq!(indexed_packet.ts(), linear_packet.ts());
+            assert_eq!(indexed_packet.buf(), linear_packet.buf());
+        }
+    }
+}
@@ -9,6 +9,7 @@ use std::collections::VecDeque;
 
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::formats::util::SeekIndex;
 use symphonia_core::formats::Packet;
 
 use super::common::SideData;
@@ -48,10 +49,13 @@ pub struct LogicalStream {
     start_bound: Option<Bound>,
     end_bound: Option<Bound>,
     gapless: bool,
+    /// An index of the pages inspected so far.
+    index: SeekIndex,
 }
 
 impl LogicalStream {
     const MAX_PACKET_LEN: usize = 16 * 1024 * 1024;
+    const MAX_INDEX_LEN: usize = 4096;
 
     pub fn new(mapper: Box<dyn Mapper>, gapless: bool) -> Self {
         LogicalStream {
@@ -63,6 +67,7 @@ impl LogicalStream {
             start_bound: None,
             end_bound: None,
             gapless,
+            index: SeekIndex::with_max_len(Self::MAX_INDEX_LEN),
         }
     }
 
@@ -74,6 +79,25 @@ impl LogicalStream {
         self.mapper.reset();
     }
 
+    /// Get the index of the pages inspected so far. The byte offset of each seek point is a
+    /// position from which the next page of the logical stream is the indexed page.
+    pub fn index(&self) -> &SeekIndex {
+        &self.index
+    }
+
+    /// Examine a page, found by reading the next page of the logical stream from `pos`, and add it
+    /// to the index. Returns the start and end timestamps of the page as a tuple.
+    pub fn index_page(&mut self, page: &Page<'_>, pos: u64) -> (u64, u64) {
+        let (start_ts, end_ts) = self.inspect_page(page);
+
+        if !page.header.is_continuation {
+            let n_frames = (end_ts - start_ts).min(u64::from(u32::MAX)) as u32;
+            self.index.insert(start_ts, pos, n_frames);
+        }
+
+        (start_ts, end_ts)
+    }
+
     /// Returns true if the stream is ready.
     pub fn is_ready(&self) -> bool {
         self.mapper.is_ready()
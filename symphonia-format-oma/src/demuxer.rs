@@ -0,0 +1,309 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::CODEC_TYPE_PCM_S16BE;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_ATRAC3, CODEC_TYPE_ATRAC3PLUS};
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use symphonia_metadata::id3v2;
+
+use log::debug;
+
+/// The length of the EA3 header.
+const EA3_HEADER_LEN: usize = 96;
+
+/// Sample rates in units of 100 Hz, indexed by the sample rate field of the codec parameters.
+const SAMPLE_RATES: [u32; 8] = [320, 441, 480, 882, 960, 0, 0, 0];
+
+/// The codec identifiers of the EA3 header.
+const CODEC_ID_ATRAC3: u8 = 0;
+const CODEC_ID_ATRAC3PLUS: u8 = 1;
+const CODEC_ID_MP3: u8 = 3;
+const CODEC_ID_LPCM: u8 = 4;
+
+/// The encryption identifiers of unencrypted files.
+const EID_NONE: [u16; 2] = [0xffff, 0xff80];
+
+/// OpenMG Audio (OMA) format reader.
+///
+/// `OmaReader` implements a demuxer for unencrypted OMA and AA3 files containing ATRAC3, ATRAC3+,
+/// or linear PCM audio. Each packet is a single frame of the stream.
+pub struct OmaReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    first_frame_pos: u64,
+    /// The length of a frame in bytes.
+    frame_len: u64,
+    /// The number of samples per channel in a frame.
+    frame_dur: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for OmaReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "oma",
+            "OpenMG Audio",
+            &["oma", "omg", "aa3"],
+            &["audio/x-oma"],
+            &[b"ea3\x03", b"ea3\x04", b"EA3\x01", b"EA3\x02"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// The stream described by an EA3 header.
+struct StreamInfo {
+    params: CodecParameters,
+    frame_len: u64,
+    frame_dur: u64,
+}
+
+/// Get the channels of an ATRAC3+ stream from its channel configuration.
+fn atrac3plus_channels(channel_id: u32) -> Result<Channels> {
+    let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+    let rear = Channels::REAR_LEFT | Channels::REAR_RIGHT;
+
+    let channels = match channel_id {
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3 => front,
+        4 => front | Channels::REAR_CENTRE,
+        5 => front | Channels::LFE1 | rear,
+        6 => front | Channels::LFE1 | rear | Channels::REAR_CENTRE,
+        7 => front | Channels::LFE1 | rear | Channels::SIDE_LEFT | Channels::SIDE_RIGHT,
+        _ => return decode_error("oma: invalid atrac3+ channel configuration"),
+    };
+
+    Ok(channels)
+}
+
+/// Read the stream information from an EA3 header.
+fn read_ea3_header(buf: &[u8; EA3_HEADER_LEN]) -> Result<StreamInfo> {
+    if &buf[..3] != b"EA3" || buf[4] != 0 || usize::from(buf[5]) != EA3_HEADER_LEN {
+        return decode_error("oma: missing ea3 header");
+    }
+
+    let eid = u16::from_be_bytes([buf[6], buf[7]]);
+
+    if !EID_NONE.contains(&eid) {
+        return unsupported_error("oma: encrypted files are not supported");
+    }
+
+    let codec_params = u32::from_be_bytes([0, buf[33], buf[34], buf[35]]);
+
+    let mut sample_rate = 100 * SAMPLE_RATES[((codec_params >> 13) & 0x7) as usize];
+
+    let mut params = CodecParameters::new();
+
+    let (frame_len, frame_dur) = match buf[32] {
+        CODEC_ID_ATRAC3 => {
+            params
+                .for_codec(CODEC_TYPE_ATRAC3)
+                .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+                .with_frames_per_block(1024);
+
+            (u64::from(codec_params & 0x3ff) * 8, 1024)
+        }
+        CODEC_ID_ATRAC3PLUS => {
+            params
+                .for_codec(CODEC_TYPE_ATRAC3PLUS)
+                .with_channels(atrac3plus_channels((codec_params >> 10) & 0x7)?)
+                .with_frames_per_block(2048);
+
+            (u64::from(codec_params & 0x3ff) * 8 + 8, 2048)
+        }
+        CODEC_ID_LPCM => {
+            // Linear PCM is always 16-bit big-endian stereo at 44.1 kHz, in frames of 1024 bytes.
+            params
+                .for_codec(CODEC_TYPE_PCM_S16BE)
+                .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+                .with_bits_per_sample(16)
+                .with_bits_per_coded_sample(16);
+
+            sample_rate = 44100;
+
+            (1024, 256)
+        }
+        CODEC_ID_MP3 => return unsupported_error("oma: mp3 is not supported"),
+        _ => return unsupported_error("oma: unsupported codec"),
+    };
+
+    if sample_rate == 0 {
+        return decode_error("oma: invalid sample rate");
+    }
+
+    if frame_len == 0 {
+        return decode_error("oma: invalid frame size");
+    }
+
+    params
+        .with_sample_rate(sample_rate)
+        .with_time_base(TimeBase::new(1, sample_rate))
+        .with_max_frames_per_packet(frame_dur);
+
+    Ok(StreamInfo { params, frame_len, frame_dur })
+}
+
+impl FormatReader for OmaReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut metadata: MetadataLog = Default::default();
+
+        // The EA3 header may be preceded by an ea3 tag.
+        let mut marker = [0; 3];
+        source.read_buf_exact(&mut marker)?;
+        source.seek_buffered_rev(3);
+
+        if &marker == b"ea3" {
+            let mut builder = MetadataBuilder::new();
+            id3v2::read_ea3(&mut source, &mut builder)?;
+            metadata.push(builder.metadata());
+        }
+
+        let mut header = [0; EA3_HEADER_LEN];
+        source.read_buf_exact(&mut header)?;
+
+        let StreamInfo { mut params, frame_len, frame_dur } = read_ea3_header(&header)?;
+
+        let first_frame_pos = source.pos();
+
+        if let Some(byte_len) = source.byte_len() {
+            let n_frames = byte_len.saturating_sub(first_frame_pos) / frame_len;
+            params.with_n_frames(n_frames * frame_dur);
+        }
+
+        Ok(OmaReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            first_frame_pos,
+            frame_len,
+            frame_dur,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = self.reader.read_boxed_slice_exact(self.frame_len as usize)?;
+
+        let ts = self.next_packet_ts;
+
+        self.next_packet_ts += self.frame_dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, self.frame_dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        // Get the timestamp of the desired audio frame.
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = self.tracks[0].codec_params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = self.tracks[0].codec_params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Every frame has the same length and duration.
+        let frame_idx = required_ts / self.frame_dur;
+
+        if self.reader.is_seekable() {
+            let pos = self.first_frame_pos + frame_idx * self.frame_len;
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let cur_frame_idx = self.next_packet_ts / self.frame_dur;
+
+            if frame_idx < cur_frame_idx {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes((frame_idx - cur_frame_idx) * self.frame_len)?;
+        }
+
+        self.next_packet_ts = frame_idx * self.frame_dur;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts as i64 - self.next_packet_ts as i64
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_ea3_header() {
+        let mut header = [0; EA3_HEADER_LEN];
+        header[..8].copy_from_slice(&[b'E', b'A', b'3', 0x01, 0x00, 0x60, 0xff, 0xff]);
+
+        // An ATRAC3 joint-stereo stream at 44.1 kHz, with frames of 192 bytes.
+        header[32..36].copy_from_slice(&[CODEC_ID_ATRAC3, 0x02, 0x20, 0x18]);
+
+        let info = read_ea3_header(&header).unwrap();
+
+        assert_eq!(info.params.codec, CODEC_TYPE_ATRAC3);
+        assert_eq!(info.params.sample_rate, Some(44100));
+        assert_eq!(info.frame_len, 192);
+        assert_eq!(info.frame_dur, 1024);
+
+        // Encrypted files are not supported.
+        header[6..8].copy_from_slice(&[0x00, 0x01]);
+        assert!(read_ea3_header(&header).is_err());
+    }
+}
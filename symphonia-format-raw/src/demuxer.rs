@@ -0,0 +1,335 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::sample::SampleFormat;
+
+use log::debug;
+
+/// The maximum number of frames in a packet.
+const MAX_FRAMES_PER_PACKET: u64 = 1152;
+
+/// The byte order of the samples of a raw stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// The encoding of the samples of a raw stream.
+#[derive(Copy, Clone, Debug)]
+pub enum RawEncoding {
+    /// Linear PCM samples of the given format.
+    Pcm(SampleFormat),
+    /// G.711 A-law companded samples.
+    ALaw,
+    /// G.711 µ-law companded samples.
+    MuLaw,
+}
+
+/// The parameters of a raw stream. Since a raw stream has no header, these must be provided by
+/// the caller.
+#[derive(Copy, Clone, Debug)]
+pub struct RawParams {
+    /// The sample rate in Hz.
+    pub sample_rate: u32,
+    /// The number of interleaved channels.
+    pub num_channels: u32,
+    /// The encoding of the samples.
+    pub encoding: RawEncoding,
+    /// The byte order of the samples. Ignored for 8-bit and companded samples. Default:
+    /// `Endianness::Little`.
+    pub endianness: Endianness,
+    /// The number of bytes to skip before the first sample, such as an unsupported header.
+    /// Default: `0`.
+    pub offset: u64,
+    /// The length of the audio data in bytes, or `None` if the audio data extends to the end of
+    /// the stream. Default: `None`.
+    pub len: Option<u64>,
+}
+
+impl RawParams {
+    /// Instantiate parameters for a little-endian stream that starts at the beginning of the
+    /// media source and extends to its end.
+    pub fn new(sample_rate: u32, num_channels: u32, encoding: RawEncoding) -> Self {
+        RawParams {
+            sample_rate,
+            num_channels,
+            encoding,
+            endianness: Endianness::Little,
+            offset: 0,
+            len: None,
+        }
+    }
+}
+
+/// Get the codec, and the number of bits per sample, of a raw stream.
+fn raw_codec(encoding: RawEncoding, endianness: Endianness) -> (CodecType, u32) {
+    let le = endianness == Endianness::Little;
+
+    let pcm = |le_codec, be_codec, bits| (if le { le_codec } else { be_codec }, bits);
+
+    match encoding {
+        RawEncoding::Pcm(SampleFormat::U8) => (CODEC_TYPE_PCM_U8, 8),
+        RawEncoding::Pcm(SampleFormat::S8) => (CODEC_TYPE_PCM_S8, 8),
+        RawEncoding::Pcm(SampleFormat::U16) => pcm(CODEC_TYPE_PCM_U16LE, CODEC_TYPE_PCM_U16BE, 16),
+        RawEncoding::Pcm(SampleFormat::S16) => pcm(CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S16BE, 16),
+        RawEncoding::Pcm(SampleFormat::U24) => pcm(CODEC_TYPE_PCM_U24LE, CODEC_TYPE_PCM_U24BE, 24),
+        RawEncoding::Pcm(SampleFormat::S24) => pcm(CODEC_TYPE_PCM_S24LE, CODEC_TYPE_PCM_S24BE, 24),
+        RawEncoding::Pcm(SampleFormat::U32) => pcm(CODEC_TYPE_PCM_U32LE, CODEC_TYPE_PCM_U32BE, 32),
+        RawEncoding::Pcm(SampleFormat::S32) => pcm(CODEC_TYPE_PCM_S32LE, CODEC_TYPE_PCM_S32BE, 32),
+        RawEncoding::Pcm(SampleFormat::F32) => pcm(CODEC_TYPE_PCM_F32LE, CODEC_TYPE_PCM_F32BE, 32),
+        RawEncoding::Pcm(SampleFormat::F64) => pcm(CODEC_TYPE_PCM_F64LE, CODEC_TYPE_PCM_F64BE, 64),
+        RawEncoding::ALaw => (CODEC_TYPE_PCM_ALAW, 8),
+        RawEncoding::MuLaw => (CODEC_TYPE_PCM_MULAW, 8),
+    }
+}
+
+/// Raw PCM format reader.
+///
+/// `RawReader` implements a demuxer for headerless streams of interleaved PCM, floating point
+/// PCM, µ-law, or A-law samples, such as `.pcm`, `.raw`, or `.sw` captures. Since such streams
+/// cannot be probed, the reader must be instantiated with `RawReader::try_new_with_params`.
+pub struct RawReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    data_start_pos: u64,
+    /// The position of the end of the audio data, or `u64::MAX` if unknown.
+    data_end_pos: u64,
+    /// The length of a frame in bytes.
+    frame_len: u64,
+}
+
+impl RawReader {
+    /// Instantiate a `RawReader` for a raw stream with the given parameters.
+    pub fn try_new_with_params(
+        mut source: MediaSourceStream,
+        _options: &FormatOptions,
+        raw_params: &RawParams,
+    ) -> Result<Self> {
+        if raw_params.sample_rate == 0 {
+            return decode_error("raw: invalid sample rate");
+        }
+
+        let channels = match raw_params.num_channels {
+            0 => return decode_error("raw: invalid channel count"),
+            1 => Channels::FRONT_LEFT,
+            2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            n @ 3..=32 => Channels::from_bits_truncate(((1u64 << n) - 1) as u32),
+            _ => return unsupported_error("raw: too many channels"),
+        };
+
+        let (codec, bits_per_sample) = raw_codec(raw_params.encoding, raw_params.endianness);
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(codec)
+            .with_sample_rate(raw_params.sample_rate)
+            .with_time_base(TimeBase::new(1, raw_params.sample_rate))
+            .with_channels(channels)
+            .with_max_frames_per_packet(MAX_FRAMES_PER_PACKET)
+            .with_frames_per_block(1);
+
+        // The bits per sample of the companded codecs is implicit.
+        if codec != CODEC_TYPE_PCM_MULAW && codec != CODEC_TYPE_PCM_ALAW {
+            params
+                .with_bits_per_sample(bits_per_sample)
+                .with_bits_per_coded_sample(bits_per_sample);
+        }
+
+        let frame_len = u64::from(bits_per_sample / 8) * u64::from(raw_params.num_channels);
+
+        source.ignore_bytes(raw_params.offset)?;
+
+        let data_start_pos = source.pos();
+
+        // The length may be unknown, or exceed the length of a truncated file.
+        let data_end_pos = match (raw_params.len, source.byte_len()) {
+            (Some(len), Some(byte_len)) => byte_len.min(data_start_pos.saturating_add(len)),
+            (Some(len), None) => data_start_pos.saturating_add(len),
+            (None, Some(byte_len)) => byte_len,
+            (None, None) => u64::MAX,
+        };
+
+        if data_end_pos != u64::MAX {
+            params.with_n_frames(data_end_pos.saturating_sub(data_start_pos) / frame_len);
+        }
+
+        Ok(RawReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            data_start_pos,
+            data_end_pos,
+            frame_len,
+        })
+    }
+}
+
+impl FormatReader for RawReader {
+    fn try_new(_source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // A raw stream has no header, so its parameters cannot be determined.
+        unsupported_error("raw: stream parameters are required")
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let pos = self.reader.pos();
+
+        let num_frames_left = self.data_end_pos.saturating_sub(pos) / self.frame_len;
+
+        let max_len = (num_frames_left.min(MAX_FRAMES_PER_PACKET) * self.frame_len) as usize;
+
+        if max_len == 0 {
+            return end_of_stream_error();
+        }
+
+        let buf = if self.data_end_pos == u64::MAX {
+            // If the length of the audio data is unknown, the stream ends wherever the media
+            // source ends, so the final packet may be shorter than requested.
+            let mut buf = vec![0; max_len];
+            let mut len = 0;
+
+            while len < max_len {
+                match self.reader.read_buf(&mut buf[len..]) {
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                    Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+
+            buf.truncate(len - len % self.frame_len as usize);
+
+            if buf.is_empty() {
+                return end_of_stream_error();
+            }
+
+            buf.into_boxed_slice()
+        }
+        else {
+            self.reader.read_boxed_slice_exact(max_len)?
+        };
+
+        let ts = (pos - self.data_start_pos) / self.frame_len;
+        let dur = buf.len() as u64 / self.frame_len;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // The sample rate is always known.
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, params.sample_rate.unwrap()).calc_timestamp(time)
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Seek to a packet boundary so that packets have the same timestamps regardless if the
+        // stream was seeked or not.
+        let actual_ts = required_ts / MAX_FRAMES_PER_PACKET * MAX_FRAMES_PER_PACKET;
+
+        let seek_pos = self.data_start_pos + actual_ts * self.frame_len;
+
+        if self.reader.is_seekable() {
+            self.reader.seek(SeekFrom::Start(seek_pos))?;
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let current_pos = self.reader.pos();
+
+            if seek_pos < current_pos {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes(seek_pos - current_pos)?;
+        }
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn verify_raw_reader() {
+        // A 16-byte header, 3000 big-endian 16-bit stereo frames, and a 4-byte trailer.
+        let mut buf = vec![0xff; 16];
+        buf.extend((0..6000u16).flat_map(|s| s.to_be_bytes()));
+        buf.extend_from_slice(b"TAIL");
+
+        let mut raw_params = RawParams::new(8000, 2, RawEncoding::Pcm(SampleFormat::S16));
+        raw_params.endianness = Endianness::Big;
+        raw_params.offset = 16;
+        raw_params.len = Some(12000);
+
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let mut reader =
+            RawReader::try_new_with_params(mss, &Default::default(), &raw_params).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_PCM_S16BE);
+        assert_eq!(params.n_frames, Some(3000));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts, packet.dur), (0, 1152));
+        assert_eq!(&packet.data[..4], &[0, 0, 0, 1]);
+
+        let seeked = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 2500, track_id: 0 });
+        assert_eq!(seeked.unwrap().actual_ts, 2304);
+
+        // The final packet ends before the trailer.
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts, packet.dur), (2304, 696));
+        assert!(reader.next_packet().is_err());
+    }
+}
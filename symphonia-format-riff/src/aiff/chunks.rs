@@ -8,16 +8,17 @@
 use std::fmt;
 
 use symphonia_core::codecs::{
-    CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F64BE, CODEC_TYPE_PCM_MULAW,
-    CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24BE, CODEC_TYPE_PCM_S32BE,
-    CODEC_TYPE_PCM_S8,
+    CODEC_TYPE_ADPCM_IMA_QT, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F64BE,
+    CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24BE,
+    CODEC_TYPE_PCM_S32BE, CODEC_TYPE_PCM_S8,
 };
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::{MediaSourceStream, ReadBytes};
 
+use crate::chunks::{ChunkParser, ParseChunk, ParseChunkTag};
 use crate::common::{
-    try_channel_count_to_mask, ChunkParser, FormatALaw, FormatData, FormatIeeeFloat, FormatMuLaw,
-    FormatPcm, PacketInfo, ParseChunk, ParseChunkTag,
+    try_channel_count_to_mask, FormatALaw, FormatAdpcm, FormatData, FormatIeeeFloat, FormatMuLaw,
+    FormatPcm, PacketInfo,
 };
 
 use extended::Extended;
@@ -104,6 +105,18 @@ impl CommonChunk {
         Ok(FormatData::Pcm(FormatPcm { bits_per_sample, channels, codec }))
     }
 
+    fn read_ima4_fmt(n_channels: u16) -> Result<FormatData> {
+        // The sample size of the common chunk is that of the decoded samples, the coded samples
+        // are always 4 bits.
+        let channels = try_channel_count_to_mask(n_channels)?;
+        Ok(FormatData::Adpcm(FormatAdpcm {
+            bits_per_sample: 4,
+            channels,
+            codec: CODEC_TYPE_ADPCM_IMA_QT,
+            extra_data: None,
+        }))
+    }
+
     pub fn packet_info(&self) -> Result<PacketInfo> {
         match &self.format_data {
             FormatData::Pcm(_) => {
@@ -127,9 +140,17 @@ impl CommonChunk {
             FormatData::Extensible(_) => {
                 unsupported_error("aiff: packet info not implemented for format Extensible")
             }
+            FormatData::Adpcm(FormatAdpcm { codec, .. }) if *codec == CODEC_TYPE_ADPCM_IMA_QT => {
+                // Each block contains 34 bytes per channel that decode to 64 samples.
+                let block_align = 34 * self.n_channels;
+                PacketInfo::with_blocks(block_align as u16, 64)
+            }
             FormatData::Adpcm(_) => {
                 unsupported_error("aiff: packet info not implemented for format Adpcm")
             }
+            FormatData::Gsm(_) => {
+                unsupported_error("aiff: packet info not implemented for format Gsm")
+            }
         }
     }
 }
@@ -172,7 +193,7 @@ impl fmt::Display for CommonChunk {
                 writeln!(f, "\t\tcodec: {},", pcm.codec)?;
             }
             FormatData::ALaw(ref alaw) => {
-                writeln!(f, "\tformat_data: MuLaw {{")?;
+                writeln!(f, "\tformat_data: ALaw {{")?;
                 writeln!(f, "\t\tchannels: {},", alaw.channels)?;
                 writeln!(f, "\t\tcodec: {},", alaw.codec)?;
             }
@@ -192,6 +213,9 @@ impl fmt::Display for CommonChunk {
             FormatData::Adpcm(_) => {
                 writeln!(f, "\tformat_data: Adpcm DISPLAY UNSUPPORTED {{")?;
             }
+            FormatData::Gsm(_) => {
+                writeln!(f, "\tformat_data: Gsm DISPLAY UNSUPPORTED {{")?;
+            }
         };
 
         writeln!(f, "\t}}")?;
@@ -237,6 +261,7 @@ impl CommonChunkParser for ChunkParser<CommonChunk> {
             b"fl32" | b"fl64" => CommonChunk::read_ieee_fmt(sample_size as u16, n_channels as u16),
             b"sowt" | b"SOWT" => CommonChunk::read_sowt_fmt(sample_size as u16, n_channels as u16),
             b"twos" | b"TWOS" => CommonChunk::read_twos_fmt(sample_size as u16, n_channels as u16),
+            b"ima4" => CommonChunk::read_ima4_fmt(n_channels as u16),
             _ => return unsupported_error("aifc: Compression type not implemented"),
         };
 
@@ -16,10 +16,11 @@ use symphonia_core::meta::{Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_format;
 
-use log::debug;
+use log::{debug, warn};
 
+use crate::chunks::{ByteOrder, ChunksReader};
 use crate::common::{
-    append_data_params, append_format_params, next_packet, ByteOrder, ChunksReader, PacketInfo,
+    append_data_params, append_format_params, next_packet, read_appended_id3v2, PacketInfo,
 };
 mod chunks;
 use chunks::*;
@@ -81,7 +82,7 @@ impl FormatReader for AiffReader {
 
         let mut codec_params = CodecParameters::new();
         //TODO: Chunks such as marker contain metadata, get it.
-        let metadata: MetadataLog = Default::default();
+        let mut metadata: MetadataLog = Default::default();
         let mut packet_info = PacketInfo::without_blocks(0);
 
         loop {
@@ -126,6 +127,19 @@ impl FormatReader for AiffReader {
                     // Append Sound chunk fields to codec parameters.
                     append_data_params(&mut codec_params, data.len as u64, &packet_info);
 
+                    // Some taggers append an ID3v2 tag after the FORM chunk. If the source is
+                    // seekable, read it, and then return to the start of the audio data.
+                    let has_trailing_data = source.is_seekable()
+                        && source.byte_len().map_or(false, |len| data_end_pos < len);
+
+                    if has_trailing_data {
+                        if let Err(err) = read_appended_id3v2(&mut source, &mut metadata) {
+                            warn!("aiff: ignoring appended id3v2 tag: {}", err);
+                        }
+
+                        source.seek(SeekFrom::Start(data_start_pos))?;
+                    }
+
                     // Add a new track using the collected codec parameters.
                     return Ok(AiffReader {
                         reader: source,
@@ -205,7 +219,8 @@ impl FormatReader for AiffReader {
         let actual_ts = self.packet_info.get_actual_ts(ts);
 
         // Calculate the absolute byte offset of the desired audio frame.
-        let seek_pos = self.data_start_pos + (actual_ts * self.packet_info.block_size);
+        let seek_pos = self.data_start_pos
+            + (actual_ts / self.packet_info.frames_per_block * self.packet_info.block_size);
 
         // If the reader supports seeking we can seek directly to the frame's offset wherever it may
         // be.
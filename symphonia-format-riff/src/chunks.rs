@@ -0,0 +1,224 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generic parsing of the chunks of RIFF (little-endian) and IFF (big-endian) files.
+//!
+//! Chunks are padded to an even length. A list chunk (`RIFF`, `LIST`, or `FORM`) starts with a
+//! 4-byte list type followed by its sub-chunks, which may be read with a `ChunksReader` of the
+//! list length less the list type, or with `sub_chunks` if the list was read into a buffer.
+
+use std::io;
+use std::marker::PhantomData;
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::ReadBytes;
+
+use log::{debug, info};
+
+/// The byte order of the chunk lengths. RIFF is little-endian, IFF is big-endian.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+/// The header of a chunk.
+#[derive(Copy, Clone, Debug)]
+pub struct ChunkHeader {
+    pub id: [u8; 4],
+    pub len: u32,
+}
+
+impl ChunkHeader {
+    pub fn read<B: ReadBytes>(reader: &mut B, byte_order: ByteOrder) -> io::Result<Self> {
+        let id = reader.read_quad_bytes()?;
+
+        let len = match byte_order {
+            ByteOrder::LittleEndian => reader.read_u32()?,
+            ByteOrder::BigEndian => reader.read_be_u32()?,
+        };
+
+        Ok(ChunkHeader { id, len })
+    }
+
+    /// The length of the chunk data, including the pad byte of chunks with an odd length.
+    pub fn padded_len(&self) -> u64 {
+        u64::from(self.len) + u64::from(self.len & 1)
+    }
+}
+
+/// Splits the data of a list, already read into a buffer, into its chunks. A chunk cut short by
+/// the end of the list is truncated.
+pub fn sub_chunks(mut buf: &[u8], byte_order: ByteOrder) -> Vec<([u8; 4], &[u8])> {
+    let mut chunks = Vec::new();
+
+    while buf.len() >= 8 {
+        let id = [buf[0], buf[1], buf[2], buf[3]];
+
+        let len = match byte_order {
+            ByteOrder::LittleEndian => u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
+            ByteOrder::BigEndian => u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        };
+
+        let data = &buf[8..];
+        let len = (len as usize).min(data.len());
+
+        chunks.push((id, &data[..len]));
+
+        buf = &data[(len + (len & 1)).min(data.len())..];
+    }
+
+    chunks
+}
+
+/// `ParseChunkTag` implements `parse_tag` to map between the 4-byte chunk identifier and the
+/// enumeration
+pub trait ParseChunkTag: Sized {
+    fn parse_tag(tag: [u8; 4], len: u32) -> Option<Self>;
+}
+
+pub enum NullChunks {}
+
+impl ParseChunkTag for NullChunks {
+    fn parse_tag(_tag: [u8; 4], _len: u32) -> Option<Self> {
+        None
+    }
+}
+
+/// `ChunksReader` reads chunks from a `ByteStream`. It is generic across a type, usually an enum,
+/// implementing the `ParseChunkTag` trait. When a new chunk is encountered in the stream,
+/// `parse_tag` on T is called to return an object capable of parsing/reading that chunk or `None`.
+/// This makes reading the actual chunk data lazy in that the  chunk is not read until the object is
+/// consumed.
+pub struct ChunksReader<T: ParseChunkTag> {
+    len: u32,
+    byte_order: ByteOrder,
+    consumed: u32,
+    phantom: PhantomData<T>,
+}
+
+impl<T: ParseChunkTag> ChunksReader<T> {
+    pub fn new(len: u32, byte_order: ByteOrder) -> Self {
+        ChunksReader { len, byte_order, consumed: 0, phantom: PhantomData }
+    }
+
+    pub fn next<B: ReadBytes>(&mut self, reader: &mut B) -> Result<Option<T>> {
+        // Loop until a chunk is recognized and returned, or the end of stream is reached.
+        loop {
+            // Align to the next 2-byte boundary if not currently aligned.
+            if self.consumed & 0x1 == 1 {
+                reader.read_u8()?;
+                self.consumed += 1;
+            }
+
+            // Check if there are enough bytes for another chunk, if not, there are no more chunks.
+            if self.consumed + 8 > self.len {
+                return Ok(None);
+            }
+
+            // Read tag and len, the chunk header.
+            let ChunkHeader { id: tag, len } = ChunkHeader::read(reader, self.byte_order)?;
+
+            self.consumed += 8;
+
+            // Check if the ChunkReader has enough unread bytes to fully read the chunk.
+            //
+            // Warning: the formulation of this conditional is critical because len is untrusted
+            // input, it may overflow when if added to anything.
+            if self.len - self.consumed < len {
+                // When ffmpeg encodes wave to stdout the riff (parent) and data chunk lengths are
+                // (2^32)-1 since the size can't be known ahead of time.
+                if !(self.len == len && len == u32::MAX) {
+                    debug!(
+                        "chunk length of {} exceeds parent (list) chunk length",
+                        String::from_utf8_lossy(&tag)
+                    );
+                    return decode_error("riff: chunk length exceeds parent (list) chunk length");
+                }
+            }
+
+            // The length of the chunk has been validated, so "consume" the chunk.
+            self.consumed = self.consumed.saturating_add(len);
+
+            match T::parse_tag(tag, len) {
+                Some(chunk) => return Ok(Some(chunk)),
+                None => {
+                    // As per the RIFF spec, unknown chunks are to be ignored.
+                    info!(
+                        "ignoring unknown chunk: tag={}, len={}.",
+                        String::from_utf8_lossy(&tag),
+                        len
+                    );
+
+                    reader.ignore_bytes(u64::from(len))?
+                }
+            }
+        }
+    }
+    pub fn finish<B: ReadBytes>(&mut self, reader: &mut B) -> Result<()> {
+        // If data is remaining in this chunk, skip it.
+        if self.consumed < self.len {
+            let remaining = self.len - self.consumed;
+            reader.ignore_bytes(u64::from(remaining))?;
+            self.consumed += remaining;
+        }
+
+        // Pad the chunk to the next 2-byte boundary.
+        if self.len & 0x1 == 1 {
+            reader.read_u8()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Common trait implemented for all chunks that are parsed by a `ChunkParser`.
+pub trait ParseChunk: Sized {
+    fn parse<B: ReadBytes>(reader: &mut B, tag: [u8; 4], len: u32) -> Result<Self>;
+}
+
+/// `ChunkParser` is a utility struct for unifying the parsing of chunks.
+pub struct ChunkParser<P: ParseChunk> {
+    tag: [u8; 4],
+    pub len: u32,
+    phantom: PhantomData<P>,
+}
+
+impl<P: ParseChunk> ChunkParser<P> {
+    pub fn new(tag: [u8; 4], len: u32) -> Self {
+        ChunkParser { tag, len, phantom: PhantomData }
+    }
+
+    pub fn parse<B: ReadBytes>(&self, reader: &mut B) -> Result<P> {
+        P::parse(reader, self.tag, self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    #[test]
+    fn verify_sub_chunks() {
+        // An odd length chunk followed by its pad byte, and a chunk cut short by the end.
+        let buf =
+            [b'a', b'b', b'c', b'd', 0, 0, 0, 3, 1, 2, 3, 0, b'e', b'f', b'g', b'h', 0, 0, 0, 9, 4];
+
+        let chunks = sub_chunks(&buf, ByteOrder::BigEndian);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], (*b"abcd", &[1, 2, 3][..]));
+        assert_eq!(chunks[1], (*b"efgh", &[4][..]));
+
+        let mut reader = BufReader::new(&buf);
+        let header = ChunkHeader::read(&mut reader, ByteOrder::BigEndian).unwrap();
+
+        assert_eq!(header.id, *b"abcd");
+        assert_eq!(header.padded_len(), 4);
+    }
+}
@@ -7,40 +7,23 @@
 
 /// `PacketInfo` helps to simulate packetization over a number of blocks of data.
 /// In case the codec is blockless the block size equals one full audio frame in bytes.
-use std::marker::PhantomData;
+use std::io;
 
 use symphonia_core::audio::Channels;
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::codecs::CodecType;
 use symphonia_core::errors::{decode_error, end_of_stream_error, Error, Result};
 use symphonia_core::formats::prelude::*;
-use symphonia_core::io::{MediaSourceStream, ReadBytes};
+use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, MetadataLog};
+use symphonia_metadata::id3v2;
 
-use log::{debug, info};
-
-pub enum ByteOrder {
-    LittleEndian,
-    BigEndian,
-}
+use log::info;
 
 /// The maximum number of frames that will be in a packet.
 /// Since there are no real packets in AIFF, this is arbitrary, used same value as MP3.
 const MAX_FRAMES_PER_PACKET: u64 = 1152;
 
-/// `ParseChunkTag` implements `parse_tag` to map between the 4-byte chunk identifier and the
-/// enumeration
-pub trait ParseChunkTag: Sized {
-    fn parse_tag(tag: [u8; 4], len: u32) -> Option<Self>;
-}
-
-pub enum NullChunks {}
-
-impl ParseChunkTag for NullChunks {
-    fn parse_tag(_tag: [u8; 4], _len: u32) -> Option<Self> {
-        None
-    }
-}
-
 pub fn fix_channel_mask(mut channel_mask: u32, n_channels: u16) -> u32 {
     let channel_diff = n_channels as i32 - channel_mask.count_ones() as i32;
 
@@ -98,120 +81,6 @@ fn test_try_channel_count_to_mask() {
     }
 }
 
-/// `ChunksReader` reads chunks from a `ByteStream`. It is generic across a type, usually an enum,
-/// implementing the `ParseChunkTag` trait. When a new chunk is encountered in the stream,
-/// `parse_tag` on T is called to return an object capable of parsing/reading that chunk or `None`.
-/// This makes reading the actual chunk data lazy in that the  chunk is not read until the object is
-/// consumed.
-pub struct ChunksReader<T: ParseChunkTag> {
-    len: u32,
-    byte_order: ByteOrder,
-    consumed: u32,
-    phantom: PhantomData<T>,
-}
-
-impl<T: ParseChunkTag> ChunksReader<T> {
-    pub fn new(len: u32, byte_order: ByteOrder) -> Self {
-        ChunksReader { len, byte_order, consumed: 0, phantom: PhantomData }
-    }
-
-    pub fn next<B: ReadBytes>(&mut self, reader: &mut B) -> Result<Option<T>> {
-        // Loop until a chunk is recognized and returned, or the end of stream is reached.
-        loop {
-            // Align to the next 2-byte boundary if not currently aligned.
-            if self.consumed & 0x1 == 1 {
-                reader.read_u8()?;
-                self.consumed += 1;
-            }
-
-            // Check if there are enough bytes for another chunk, if not, there are no more chunks.
-            if self.consumed + 8 > self.len {
-                return Ok(None);
-            }
-
-            // Read tag and len, the chunk header.
-            let tag = reader.read_quad_bytes()?;
-
-            let len = match self.byte_order {
-                ByteOrder::LittleEndian => reader.read_u32()?,
-                ByteOrder::BigEndian => reader.read_be_u32()?,
-            };
-
-            self.consumed += 8;
-
-            // Check if the ChunkReader has enough unread bytes to fully read the chunk.
-            //
-            // Warning: the formulation of this conditional is critical because len is untrusted
-            // input, it may overflow when if added to anything.
-            if self.len - self.consumed < len {
-                // When ffmpeg encodes wave to stdout the riff (parent) and data chunk lengths are
-                // (2^32)-1 since the size can't be known ahead of time.
-                if !(self.len == len && len == u32::MAX) {
-                    debug!(
-                        "chunk length of {} exceeds parent (list) chunk length",
-                        String::from_utf8_lossy(&tag)
-                    );
-                    return decode_error("riff: chunk length exceeds parent (list) chunk length");
-                }
-            }
-
-            // The length of the chunk has been validated, so "consume" the chunk.
-            self.consumed = self.consumed.saturating_add(len);
-
-            match T::parse_tag(tag, len) {
-                Some(chunk) => return Ok(Some(chunk)),
-                None => {
-                    // As per the RIFF spec, unknown chunks are to be ignored.
-                    info!(
-                        "ignoring unknown chunk: tag={}, len={}.",
-                        String::from_utf8_lossy(&tag),
-                        len
-                    );
-
-                    reader.ignore_bytes(u64::from(len))?
-                }
-            }
-        }
-    }
-    pub fn finish<B: ReadBytes>(&mut self, reader: &mut B) -> Result<()> {
-        // If data is remaining in this chunk, skip it.
-        if self.consumed < self.len {
-            let remaining = self.len - self.consumed;
-            reader.ignore_bytes(u64::from(remaining))?;
-            self.consumed += remaining;
-        }
-
-        // Pad the chunk to the next 2-byte boundary.
-        if self.len & 0x1 == 1 {
-            reader.read_u8()?;
-        }
-
-        Ok(())
-    }
-}
-
-/// Common trait implemented for all chunks that are parsed by a `ChunkParser`.
-pub trait ParseChunk: Sized {
-    fn parse<B: ReadBytes>(reader: &mut B, tag: [u8; 4], len: u32) -> Result<Self>;
-}
-
-/// `ChunkParser` is a utility struct for unifying the parsing of chunks.
-pub struct ChunkParser<P: ParseChunk> {
-    tag: [u8; 4],
-    pub len: u32,
-    phantom: PhantomData<P>,
-}
-
-impl<P: ParseChunk> ChunkParser<P> {
-    pub fn new(tag: [u8; 4], len: u32) -> Self {
-        ChunkParser { tag, len, phantom: PhantomData }
-    }
-
-    pub fn parse<B: ReadBytes>(&self, reader: &mut B) -> Result<P> {
-        P::parse(reader, self.tag, self.len)
-    }
-}
-
 pub enum FormatData {
     Pcm(FormatPcm),
     Adpcm(FormatAdpcm),
@@ -219,6 +88,7 @@ pub enum FormatData {
     Extensible(FormatExtensible),
     ALaw(FormatALaw),
     MuLaw(FormatMuLaw),
+    Gsm(FormatGsm),
 }
 
 pub struct FormatPcm {
@@ -237,6 +107,8 @@ pub struct FormatAdpcm {
     pub channels: Channels,
     /// Codec type.
     pub codec: CodecType,
+    /// Codec specific extra data.
+    pub extra_data: Option<Box<[u8]>>,
 }
 
 pub struct FormatIeeeFloat {
@@ -275,6 +147,13 @@ pub struct FormatMuLaw {
     pub codec: CodecType,
 }
 
+pub struct FormatGsm {
+    /// Channel bitmask.
+    pub channels: Channels,
+    /// Codec type.
+    pub codec: CodecType,
+}
+
 pub struct PacketInfo {
     pub block_size: u64,
     pub frames_per_block: u64,
@@ -344,11 +223,22 @@ pub fn next_packet(
 
     let blocks_per_packet = num_blocks_left.min(packet_info.max_blocks_per_packet);
 
-    let dur = blocks_per_packet * packet_info.frames_per_block;
     let packet_len = blocks_per_packet * packet_info.block_size;
 
-    // Copy the frames.
-    let packet_buf = reader.read_boxed_slice(packet_len as usize)?;
+    // Copy the frames. If the length of the data chunk is unknown, the stream ends wherever the
+    // media source ends, so the final packet may be shorter than requested.
+    let packet_buf = if data_end_pos == u64::MAX {
+        read_unbounded_packet(reader, packet_len as usize, packet_info.block_size as usize)?
+    }
+    else {
+        reader.read_boxed_slice(packet_len as usize)?
+    };
+
+    if packet_buf.is_empty() {
+        return end_of_stream_error();
+    }
+
+    let dur = packet_buf.len() as u64 / packet_info.block_size * packet_info.frames_per_block;
 
     // The packet timestamp is the position of the first byte of the first frame in the
     // packet relative to the start of the data chunk divided by the length per frame.
@@ -357,6 +247,48 @@ pub fn next_packet(
     Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf))
 }
 
+/// Reads the ID3v2 tag appended to the end of a seekable stream, after the RIFF chunk, if present.
+pub fn read_appended_id3v2(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<()> {
+    let len = match reader.byte_len() {
+        Some(len) => len,
+        None => return Ok(()),
+    };
+
+    if id3v2::find_appended_id3v2(reader, len)?.is_some() {
+        let mut builder = MetadataBuilder::new();
+        id3v2::read_id3v2(reader, &mut builder)?;
+        metadata.push(builder.metadata());
+    }
+
+    Ok(())
+}
+
+/// Reads up to `max_len` bytes of audio data, stopping early if the end of the stream is reached.
+/// Any trailing partial block is discarded.
+fn read_unbounded_packet(
+    reader: &mut MediaSourceStream,
+    max_len: usize,
+    block_size: usize,
+) -> Result<Box<[u8]>> {
+    let mut buf = vec![0; max_len];
+    let mut len = 0;
+
+    while len < max_len {
+        match reader.read_buf(&mut buf[len..]) {
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    buf.truncate(len - len % block_size);
+
+    Ok(buf.into_boxed_slice())
+}
+
 /// TODO: format here refers to format chunk in Wave terminology, but the data being handled here is generic - find a better name, or combine with append_data_params
 pub fn append_format_params(
     codec_params: &mut CodecParameters,
@@ -375,6 +307,10 @@ pub fn append_format_params(
         }
         FormatData::Adpcm(adpcm) => {
             codec_params.for_codec(adpcm.codec).with_channels(adpcm.channels);
+
+            if let Some(extra_data) = &adpcm.extra_data {
+                codec_params.with_extra_data(extra_data.clone());
+            }
         }
         FormatData::IeeeFloat(ieee) => {
             codec_params.for_codec(ieee.codec).with_channels(ieee.channels);
@@ -392,6 +328,9 @@ pub fn append_format_params(
         FormatData::MuLaw(mulaw) => {
             codec_params.for_codec(mulaw.codec).with_channels(mulaw.channels);
         }
+        FormatData::Gsm(gsm) => {
+            codec_params.for_codec(gsm.codec).with_channels(gsm.channels);
+        }
     }
 }
 
@@ -14,6 +14,8 @@
 #![allow(clippy::identity_op)]
 #![allow(clippy::manual_range_contains)]
 
+pub mod chunks;
+#[cfg(any(feature = "aiff", feature = "wav"))]
 mod common;
 
 #[cfg(feature = "aiff")]
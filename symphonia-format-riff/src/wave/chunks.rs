@@ -11,21 +11,39 @@ use symphonia_core::audio::Channels;
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::codecs::CodecType;
 use symphonia_core::codecs::{
-    CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32LE,
-    CODEC_TYPE_PCM_F64LE, CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24LE,
-    CODEC_TYPE_PCM_S32LE, CODEC_TYPE_PCM_U8,
+    CODEC_TYPE_ADPCM_G722, CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS, CODEC_TYPE_ADPCM_YAMAHA,
+    CODEC_TYPE_GSM_MS, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32LE, CODEC_TYPE_PCM_F64LE,
+    CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24LE, CODEC_TYPE_PCM_S32LE,
+    CODEC_TYPE_PCM_U8,
 };
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
-use symphonia_core::io::{MediaSourceStream, ReadBytes};
-use symphonia_core::meta::{MetadataBuilder, MetadataRevision, Tag};
-use symphonia_metadata::riff;
+use symphonia_core::formats::{Cue, CuePoint};
+use symphonia_core::io::{MediaSourceStream, ReadBytes, ScopedStream};
+use symphonia_core::meta::{MetadataBuilder, MetadataRevision, Tag, Value};
+use symphonia_metadata::{id3v2, riff};
 
+use log::warn;
+
+use crate::chunks::{ByteOrder, ChunkParser, ChunksReader, NullChunks, ParseChunk, ParseChunkTag};
 use crate::common::{
-    fix_channel_mask, try_channel_count_to_mask, ByteOrder, ChunkParser, ChunksReader, FormatALaw,
-    FormatAdpcm, FormatData, FormatExtensible, FormatIeeeFloat, FormatMuLaw, FormatPcm, NullChunks,
-    PacketInfo, ParseChunk, ParseChunkTag,
+    fix_channel_mask, try_channel_count_to_mask, FormatALaw, FormatAdpcm, FormatData,
+    FormatExtensible, FormatGsm, FormatIeeeFloat, FormatMuLaw, FormatPcm, PacketInfo,
 };
 
+/// The maximum length of a variable length text field, such as the coding history of a bext chunk,
+/// that is read. The remainder of a longer field is skipped.
+const MAX_TEXT_FIELD_LEN: u32 = 64 * 1024;
+
+/// Reads a variable length text field of `len` bytes, of which at most `MAX_TEXT_FIELD_LEN` bytes
+/// are returned.
+fn read_text_field<B: ReadBytes>(reader: &mut B, len: u32) -> Result<Box<[u8]>> {
+    let buf = reader.read_boxed_slice_exact(len.min(MAX_TEXT_FIELD_LEN) as usize)?;
+
+    reader.ignore_bytes(u64::from(len.saturating_sub(MAX_TEXT_FIELD_LEN)))?;
+
+    Ok(buf)
+}
+
 pub struct WaveFormatChunk {
     /// The number of channels.
     pub n_channels: u16,
@@ -111,19 +129,27 @@ impl WaveFormatChunk {
 
         let extra_size = reader.read_u16()? as u64;
 
-        match codec {
+        let extra_data = match codec {
             CODEC_TYPE_ADPCM_MS if extra_size < 32 => {
                 return decode_error("wav: malformed fmt_adpcm chunk");
             }
+            CODEC_TYPE_ADPCM_MS => {
+                // The Microsoft ADPCM extension contains the number of frames per block followed
+                // by the table of predictor coefficients the encoder used. Pass it on to the
+                // decoder since the coefficients may differ from the standard set.
+                Some(reader.read_boxed_slice_exact(extra_size as usize)?)
+            }
             CODEC_TYPE_ADPCM_IMA_WAV if extra_size != 2 => {
                 return decode_error("wav: malformed fmt_adpcm chunk");
             }
-            _ => (),
-        }
-        reader.ignore_bytes(extra_size)?;
+            _ => {
+                reader.ignore_bytes(extra_size)?;
+                None
+            }
+        };
 
         let channels = try_channel_count_to_mask(n_channels)?;
-        Ok(FormatData::Adpcm(FormatAdpcm { bits_per_sample, channels, codec }))
+        Ok(FormatData::Adpcm(FormatAdpcm { bits_per_sample, channels, codec, extra_data }))
     }
 
     fn read_ieee_fmt<B: ReadBytes>(
@@ -303,42 +329,143 @@ impl WaveFormatChunk {
         }))
     }
 
+    fn read_g711_fmt_ext<B: ReadBytes>(reader: &mut B, len: u32) -> Result<()> {
+        // G.711 writers disagree on whether the WaveFormat is extended. Some omit the extension
+        // data length field entirely, while others pad the chunk beyond the extension data.
+        match len {
+            16 => Ok(()),
+            17 => decode_error("wav: malformed fmt_g711 chunk"),
+            _ => {
+                let extra_size = reader.read_u16()?;
+
+                if u32::from(extra_size) > len - 18 {
+                    return decode_error("wav: malformed fmt_g711 chunk");
+                }
+
+                reader.ignore_bytes(u64::from(len - 18))?;
+                Ok(())
+            }
+        }
+    }
+
     fn read_alaw_pcm_fmt<B: ReadBytes>(
         reader: &mut B,
         n_channels: u16,
         len: u32,
     ) -> Result<FormatData> {
-        if len != 18 {
-            return decode_error("wav: malformed fmt_alaw chunk");
+        Self::read_g711_fmt_ext(reader, len)?;
+
+        let channels = try_channel_count_to_mask(n_channels)?;
+        Ok(FormatData::ALaw(FormatALaw { codec: CODEC_TYPE_PCM_ALAW, channels }))
+    }
+
+    fn read_mulaw_pcm_fmt<B: ReadBytes>(
+        reader: &mut B,
+        n_channels: u16,
+        len: u32,
+    ) -> Result<FormatData> {
+        Self::read_g711_fmt_ext(reader, len)?;
+
+        let channels = try_channel_count_to_mask(n_channels)?;
+        Ok(FormatData::MuLaw(FormatMuLaw { codec: CODEC_TYPE_PCM_MULAW, channels }))
+    }
+
+    fn read_gsm_fmt<B: ReadBytes>(reader: &mut B, n_channels: u16, len: u32) -> Result<FormatData> {
+        // WaveFormatEx with extension data length field present and with atleast samples per
+        // block data.
+        if len < 20 {
+            return decode_error("wav: malformed fmt_gsm chunk");
         }
 
         let extra_size = reader.read_u16()?;
 
-        if extra_size > 0 {
-            reader.ignore_bytes(u64::from(extra_size))?;
+        if extra_size < 2 {
+            return decode_error("wav: malformed fmt_gsm chunk");
+        }
+
+        // Microsoft GSM always packs two frames of 160 samples into each block.
+        let samples_per_block = reader.read_u16()?;
+
+        if samples_per_block != 320 {
+            return unsupported_error("wav: unsupported samples per block for fmt_gsm");
+        }
+
+        reader.ignore_bytes(u64::from(extra_size) - 2)?;
+
+        // GSM is only defined for mono.
+        if n_channels != 1 {
+            return unsupported_error("wav: fmt_gsm must be mono");
         }
 
         let channels = try_channel_count_to_mask(n_channels)?;
-        Ok(FormatData::ALaw(FormatALaw { codec: CODEC_TYPE_PCM_ALAW, channels }))
+        Ok(FormatData::Gsm(FormatGsm { codec: CODEC_TYPE_GSM_MS, channels }))
     }
 
-    fn read_mulaw_pcm_fmt<B: ReadBytes>(
+    fn read_g722_fmt<B: ReadBytes>(
         reader: &mut B,
+        bits_per_sample: u16,
         n_channels: u16,
         len: u32,
     ) -> Result<FormatData> {
-        if len != 18 {
-            return decode_error("wav: malformed fmt_mulaw chunk");
+        // The WaveFormat may optionally be extended, but G.722 does not define any extension data.
+        if len > 16 {
+            if len < 18 {
+                return decode_error("wav: malformed fmt_g722 chunk");
+            }
+
+            let extra_size = reader.read_u16()?;
+            reader.ignore_bytes(u64::from(extra_size))?;
         }
 
-        let extra_size = reader.read_u16()?;
+        // G.722 is only defined for mono.
+        if n_channels != 1 {
+            return unsupported_error("wav: fmt_g722 must be mono");
+        }
+
+        let channels = try_channel_count_to_mask(n_channels)?;
 
-        if extra_size > 0 {
+        Ok(FormatData::Adpcm(FormatAdpcm {
+            bits_per_sample,
+            channels,
+            codec: CODEC_TYPE_ADPCM_G722,
+            extra_data: None,
+        }))
+    }
+
+    fn read_yamaha_fmt<B: ReadBytes>(
+        reader: &mut B,
+        bits_per_sample: u16,
+        n_channels: u16,
+        len: u32,
+    ) -> Result<FormatData> {
+        // The WaveFormat may optionally be extended, but Yamaha ADPCM does not define any
+        // extension data.
+        if len > 16 {
+            if len < 18 {
+                return decode_error("wav: malformed fmt_yamaha chunk");
+            }
+
+            let extra_size = reader.read_u16()?;
             reader.ignore_bytes(u64::from(extra_size))?;
         }
 
+        if bits_per_sample != 4 {
+            return decode_error("wav: bits per sample for fmt_yamaha must be 4");
+        }
+
+        // Yamaha ADPCM is only defined for mono and stereo.
+        if n_channels != 1 && n_channels != 2 {
+            return unsupported_error("wav: fmt_yamaha must be mono or stereo");
+        }
+
         let channels = try_channel_count_to_mask(n_channels)?;
-        Ok(FormatData::MuLaw(FormatMuLaw { codec: CODEC_TYPE_PCM_MULAW, channels }))
+
+        Ok(FormatData::Adpcm(FormatAdpcm {
+            bits_per_sample,
+            channels,
+            codec: CODEC_TYPE_ADPCM_YAMAHA,
+            extra_data: None,
+        }))
     }
 
     pub(crate) fn packet_info(&self) -> Result<PacketInfo> {
@@ -360,6 +487,20 @@ impl WaveFormatChunk {
                     + 1) as u64;
                 PacketInfo::with_blocks(self.block_align, frames_per_block)
             }
+            FormatData::Adpcm(FormatAdpcm { codec, .. }) if codec == CODEC_TYPE_ADPCM_G722 => {
+                // Each byte is a code that decodes to two samples.
+                PacketInfo::with_blocks(self.n_channels, 2)
+            }
+            FormatData::Adpcm(FormatAdpcm { codec, .. }) if codec == CODEC_TYPE_ADPCM_YAMAHA => {
+                // Each byte contains two samples, either of one channel, or one of each channel.
+                PacketInfo::with_blocks(1, 2 / u64::from(self.n_channels))
+            }
+            FormatData::Gsm(_) => {
+                if self.block_align != 65 {
+                    return decode_error("wav: invalid block align for fmt_gsm");
+                }
+                PacketInfo::with_blocks(self.block_align, 320)
+            }
             _ => Ok(PacketInfo::without_blocks(self.block_align)),
         }
     }
@@ -388,6 +529,10 @@ impl ParseChunk for WaveFormatChunk {
         const WAVE_FORMAT_ALAW: u16 = 0x0006;
         const WAVE_FORMAT_MULAW: u16 = 0x0007;
         const WAVE_FORMAT_ADPCM_IMA: u16 = 0x0011;
+        const WAVE_FORMAT_YAMAHA_ADPCM: u16 = 0x0020;
+        const WAVE_FORMAT_GSM610: u16 = 0x0031;
+        const WAVE_FORMAT_G722_ADPCM: u16 = 0x0065;
+        const WAVE_FORMAT_G722: u16 = 0x028f;
         const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
 
         let format_data = match format {
@@ -413,6 +558,17 @@ impl ParseChunk for WaveFormatChunk {
                 len,
                 CODEC_TYPE_ADPCM_IMA_WAV,
             ),
+            // The Yamaha ADPCM Format
+            WAVE_FORMAT_YAMAHA_ADPCM => {
+                Self::read_yamaha_fmt(reader, bits_per_sample, n_channels, len)
+            }
+            // The Microsoft GSM 6.10 Format
+            WAVE_FORMAT_GSM610 => Self::read_gsm_fmt(reader, n_channels, len),
+            // The G.722 Format. Both the registered and the commonly written identifiers are
+            // accepted.
+            WAVE_FORMAT_G722_ADPCM | WAVE_FORMAT_G722 => {
+                Self::read_g722_fmt(reader, bits_per_sample, n_channels, len)
+            }
             // Unsupported format.
             _ => return unsupported_error("wav: unsupported wave format"),
         }?;
@@ -465,6 +621,11 @@ impl fmt::Display for WaveFormatChunk {
                 writeln!(f, "\t\tchannels: {},", mulaw.channels)?;
                 writeln!(f, "\t\tcodec: {},", mulaw.codec)?;
             }
+            FormatData::Gsm(ref gsm) => {
+                writeln!(f, "\tformat_data: Gsm {{")?;
+                writeln!(f, "\t\tchannels: {},", gsm.channels)?;
+                writeln!(f, "\t\tcodec: {},", gsm.codec)?;
+            }
         };
 
         writeln!(f, "\t}}")?;
@@ -542,6 +703,498 @@ impl ParseChunk for InfoChunk {
     }
 }
 
+/// The Broadcast Wave Format (BWF) broadcast audio extension chunk as defined in EBU Tech 3285.
+pub struct BextChunk {
+    /// A free-text description of the sound sequence.
+    pub description: String,
+    /// The name of the originator (producer) of the audio file.
+    pub originator: String,
+    /// An unambiguous reference allocated by the originating organisation.
+    pub originator_reference: String,
+    /// The date of creation of the audio sequence formatted as `yyyy-mm-dd`.
+    pub origination_date: String,
+    /// The time of creation of the audio sequence formatted as `hh:mm:ss`.
+    pub origination_time: String,
+    /// The sample count since midnight of the first sample of the audio sequence. This is the
+    /// position of the audio sequence on a timeline.
+    pub time_reference: u64,
+    /// The version of the bext chunk.
+    pub version: u16,
+    /// The SMPTE unique material identifier (UMID). Only valid if version >= 1.
+    pub umid: Option<[u8; 64]>,
+    /// The integrated loudness in LUFS. Only valid if version >= 2.
+    pub loudness_value: Option<f32>,
+    /// The loudness range in LU. Only valid if version >= 2.
+    pub loudness_range: Option<f32>,
+    /// The maximum true peak level in dBTP. Only valid if version >= 2.
+    pub max_true_peak_level: Option<f32>,
+    /// The highest value of the momentary loudness in LUFS. Only valid if version >= 2.
+    pub max_momentary_loudness: Option<f32>,
+    /// The highest value of the short-term loudness in LUFS. Only valid if version >= 2.
+    pub max_short_term_loudness: Option<f32>,
+    /// The coding history of the audio.
+    pub coding_history: String,
+}
+
+impl BextChunk {
+    /// The length of the bext chunk excluding the variable length coding history.
+    const MIN_LEN: u32 = 602;
+
+    /// Read a fixed-length, NUL padded, ASCII string field.
+    fn read_string<B: ReadBytes>(reader: &mut B, len: usize) -> Result<String> {
+        let mut buf = vec![0u8; len];
+        reader.read_buf_exact(&mut buf)?;
+        Ok(bext_string(&buf))
+    }
+
+    /// Read a loudness field. Loudness values are stored as 100 times the actual value. A value of
+    /// 0x7fff indicates the value was not calculated.
+    fn read_loudness<B: ReadBytes>(reader: &mut B, version: u16) -> Result<Option<f32>> {
+        let value = reader.read_u16()? as i16;
+
+        match version {
+            0 | 1 => Ok(None),
+            _ if value == 0x7fff => Ok(None),
+            _ => Ok(Some(f32::from(value) / 100.0)),
+        }
+    }
+
+    /// Generate a metadata revision from the bext chunk.
+    pub fn metadata(&self) -> MetadataRevision {
+        let mut builder = MetadataBuilder::new();
+
+        let strings = [
+            ("Description", &self.description),
+            ("Originator", &self.originator),
+            ("OriginatorReference", &self.originator_reference),
+            ("OriginationDate", &self.origination_date),
+            ("OriginationTime", &self.origination_time),
+            ("CodingHistory", &self.coding_history),
+        ];
+
+        for (field, value) in strings.iter() {
+            if !value.is_empty() {
+                builder.add_tag(riff::parse_bext(field, value));
+            }
+        }
+
+        builder.add_tag(Tag::new(None, "TimeReference", Value::from(self.time_reference)));
+        builder.add_tag(Tag::new(None, "Version", Value::from(self.version)));
+
+        if let Some(umid) = &self.umid {
+            builder.add_tag(Tag::new(None, "UMID", Value::from(&umid[..])));
+        }
+
+        let loudness = [
+            ("LoudnessValue", self.loudness_value),
+            ("LoudnessRange", self.loudness_range),
+            ("MaxTruePeakLevel", self.max_true_peak_level),
+            ("MaxMomentaryLoudness", self.max_momentary_loudness),
+            ("MaxShortTermLoudness", self.max_short_term_loudness),
+        ];
+
+        for (key, value) in loudness.iter() {
+            if let Some(value) = value {
+                builder.add_tag(Tag::new(None, key, Value::from(*value)));
+            }
+        }
+
+        builder.metadata()
+    }
+}
+
+impl ParseChunk for BextChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<BextChunk> {
+        if len < BextChunk::MIN_LEN {
+            return decode_error("wav: malformed bext chunk");
+        }
+
+        let description = Self::read_string(reader, 256)?;
+        let originator = Self::read_string(reader, 32)?;
+        let originator_reference = Self::read_string(reader, 32)?;
+        let origination_date = Self::read_string(reader, 10)?;
+        let origination_time = Self::read_string(reader, 8)?;
+
+        // The time reference is stored as two 32-bit words, low word first.
+        let time_reference_low = reader.read_u32()?;
+        let time_reference_high = reader.read_u32()?;
+        let time_reference = (u64::from(time_reference_high) << 32) | u64::from(time_reference_low);
+
+        let version = reader.read_u16()?;
+
+        // The UMID was added in version 1. An all zero UMID indicates it is not present.
+        let mut umid = [0u8; 64];
+        reader.read_buf_exact(&mut umid)?;
+
+        let umid = if version >= 1 && umid.iter().any(|&b| b != 0) { Some(umid) } else { None };
+
+        // The loudness fields were added in version 2.
+        let loudness_value = Self::read_loudness(reader, version)?;
+        let loudness_range = Self::read_loudness(reader, version)?;
+        let max_true_peak_level = Self::read_loudness(reader, version)?;
+        let max_momentary_loudness = Self::read_loudness(reader, version)?;
+        let max_short_term_loudness = Self::read_loudness(reader, version)?;
+
+        // Reserved for future extensions.
+        reader.ignore_bytes(180)?;
+
+        // The coding history occupies the remainder of the chunk.
+        let coding_history = bext_string(&read_text_field(reader, len - BextChunk::MIN_LEN)?);
+
+        Ok(BextChunk {
+            description,
+            originator,
+            originator_reference,
+            origination_date,
+            origination_time,
+            time_reference,
+            version,
+            umid,
+            loudness_value,
+            loudness_range,
+            max_true_peak_level,
+            max_momentary_loudness,
+            max_short_term_loudness,
+            coding_history,
+        })
+    }
+}
+
+impl fmt::Display for BextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BextChunk {{")?;
+        writeln!(f, "\tdescription: {},", self.description)?;
+        writeln!(f, "\toriginator: {},", self.originator)?;
+        writeln!(f, "\toriginator_reference: {},", self.originator_reference)?;
+        writeln!(f, "\torigination_date: {},", self.origination_date)?;
+        writeln!(f, "\torigination_time: {},", self.origination_time)?;
+        writeln!(f, "\ttime_reference: {},", self.time_reference)?;
+        writeln!(f, "\tversion: {},", self.version)?;
+        writeln!(f, "}}")
+    }
+}
+
+/// Converts a NUL padded string field of a bext chunk into a `String`.
+fn bext_string(buf: &[u8]) -> String {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).trim_end().to_string()
+}
+
+/// A single cue point in a cue chunk.
+pub struct CuePointEntry {
+    /// The unique identifier of the cue point.
+    pub id: u32,
+    /// The sample offset of the cue point in the data chunk.
+    pub sample_offset: u32,
+}
+
+/// The cue chunk lists the positions of markers within the audio data.
+pub struct CueChunk {
+    pub points: Vec<CuePointEntry>,
+}
+
+impl ParseChunk for CueChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<CueChunk> {
+        if len < 4 {
+            return decode_error("wav: malformed cue chunk");
+        }
+
+        let n_points = reader.read_u32()?;
+
+        // Each cue point is 24 bytes long.
+        if u64::from(n_points) * 24 > u64::from(len - 4) {
+            return decode_error("wav: invalid number of cue points");
+        }
+
+        let mut points = Vec::with_capacity(n_points as usize);
+
+        for _ in 0..n_points {
+            let id = reader.read_u32()?;
+            // The play order position, data chunk identifier, chunk start, and block start are
+            // only relevant for wave lists which are not supported.
+            let _position = reader.read_u32()?;
+            let _data_chunk_id = reader.read_quad_bytes()?;
+            let _chunk_start = reader.read_u32()?;
+            let _block_start = reader.read_u32()?;
+            let sample_offset = reader.read_u32()?;
+
+            points.push(CuePointEntry { id, sample_offset });
+        }
+
+        // Skip any remaining bytes in the chunk.
+        reader.ignore_bytes(u64::from(len - 4 - 24 * n_points))?;
+
+        Ok(CueChunk { points })
+    }
+}
+
+/// A sample loop in a smpl chunk.
+pub struct SampleLoop {
+    /// The identifier of the cue point associated with the loop.
+    pub cue_point_id: u32,
+    /// The loop type. 0 is forward, 1 is alternating (ping-pong), and 2 is backward.
+    pub loop_type: u32,
+    /// The sample offset of the first sample of the loop.
+    pub start: u32,
+    /// The sample offset of the last sample of the loop.
+    pub end: u32,
+    /// The number of times to play the loop. 0 indicates an infinite loop.
+    pub play_count: u32,
+}
+
+/// The sampler chunk describes how a sampler should play the audio data.
+pub struct SmplChunk {
+    pub manufacturer: u32,
+    pub product: u32,
+    /// The duration of one sample in nanoseconds.
+    pub sample_period: u32,
+    /// The MIDI note number that plays the audio data at its original pitch.
+    pub midi_unity_note: u32,
+    /// The fraction of a semitone up from the unity note.
+    pub midi_pitch_fraction: u32,
+    pub smpte_format: u32,
+    pub smpte_offset: u32,
+    pub loops: Vec<SampleLoop>,
+}
+
+impl SmplChunk {
+    /// Generate a metadata revision from the sampler chunk.
+    pub fn metadata(&self) -> MetadataRevision {
+        let mut builder = MetadataBuilder::new();
+
+        if self.manufacturer != 0 {
+            builder.add_tag(Tag::new(None, "Manufacturer", Value::from(self.manufacturer)));
+        }
+        if self.product != 0 {
+            builder.add_tag(Tag::new(None, "Product", Value::from(self.product)));
+        }
+        if self.sample_period != 0 {
+            builder.add_tag(Tag::new(None, "SamplePeriod", Value::from(self.sample_period)));
+        }
+
+        builder.add_tag(Tag::new(None, "MIDIUnityNote", Value::from(self.midi_unity_note)));
+        builder.add_tag(Tag::new(None, "MIDIPitchFraction", Value::from(self.midi_pitch_fraction)));
+
+        if self.smpte_format != 0 {
+            builder.add_tag(Tag::new(None, "SMPTEFormat", Value::from(self.smpte_format)));
+            builder.add_tag(Tag::new(None, "SMPTEOffset", Value::from(self.smpte_offset)));
+        }
+
+        builder.metadata()
+    }
+}
+
+impl ParseChunk for SmplChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<SmplChunk> {
+        if len < 36 {
+            return decode_error("wav: malformed smpl chunk");
+        }
+
+        let manufacturer = reader.read_u32()?;
+        let product = reader.read_u32()?;
+        let sample_period = reader.read_u32()?;
+        let midi_unity_note = reader.read_u32()?;
+        let midi_pitch_fraction = reader.read_u32()?;
+        let smpte_format = reader.read_u32()?;
+        let smpte_offset = reader.read_u32()?;
+        let n_loops = reader.read_u32()?;
+        let _sampler_data_len = reader.read_u32()?;
+
+        // Each sample loop is 24 bytes long.
+        if u64::from(n_loops) * 24 > u64::from(len - 36) {
+            return decode_error("wav: invalid number of sample loops");
+        }
+
+        let mut loops = Vec::with_capacity(n_loops as usize);
+
+        for _ in 0..n_loops {
+            let cue_point_id = reader.read_u32()?;
+            let loop_type = reader.read_u32()?;
+            let start = reader.read_u32()?;
+            let end = reader.read_u32()?;
+            // The fractional sample offset of the loop end is not used.
+            let _fraction = reader.read_u32()?;
+            let play_count = reader.read_u32()?;
+
+            loops.push(SampleLoop { cue_point_id, loop_type, start, end, play_count });
+        }
+
+        // Skip the sampler specific data.
+        reader.ignore_bytes(u64::from(len - 36 - 24 * n_loops))?;
+
+        Ok(SmplChunk {
+            manufacturer,
+            product,
+            sample_period,
+            midi_unity_note,
+            midi_pitch_fraction,
+            smpte_format,
+            smpte_offset,
+            loops,
+        })
+    }
+}
+
+/// An associated data list entry attaching text to a cue point.
+pub struct AdtlChunk {
+    /// The identifier of the cue point the text is associated with.
+    pub cue_point_id: u32,
+    /// The tag key for the text.
+    pub key: &'static str,
+    /// The text.
+    pub text: String,
+    /// For labeled text, the number of samples in the region starting at the cue point.
+    pub sample_len: Option<u32>,
+}
+
+impl ParseChunk for AdtlChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, tag: [u8; 4], len: u32) -> Result<AdtlChunk> {
+        if len < 4 {
+            return decode_error("wav: malformed adtl chunk");
+        }
+
+        let cue_point_id = reader.read_u32()?;
+
+        let (key, sample_len, text_len) = match &tag {
+            b"labl" => ("Label", None, len - 4),
+            b"note" => ("Note", None, len - 4),
+            b"ltxt" => {
+                if len < 20 {
+                    return decode_error("wav: malformed ltxt chunk");
+                }
+
+                let sample_len = reader.read_u32()?;
+                // The purpose, country, language, dialect, and code page fields are not used.
+                reader.ignore_bytes(12)?;
+
+                ("Text", Some(sample_len), len - 20)
+            }
+            _ => return decode_error("wav: unknown adtl chunk"),
+        };
+
+        let buf = read_text_field(reader, text_len)?;
+
+        let text_len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        let text = String::from_utf8_lossy(&buf[..text_len]).to_string();
+
+        Ok(AdtlChunk { cue_point_id, key, text, sample_len })
+    }
+}
+
+/// `CueInfo` collects the marker related chunks of a WAVE file. These chunks may appear in any
+/// order, and so must all be read before the cues can be generated.
+#[derive(Default)]
+pub struct CueInfo {
+    pub cue: Option<CueChunk>,
+    pub adtl: Vec<AdtlChunk>,
+    pub smpl: Option<SmplChunk>,
+}
+
+impl CueInfo {
+    /// Generate a list of `Cue`s, ordered by timestamp, from the marker related chunks.
+    ///
+    /// Every cue point becomes a `Cue` with its associated labels, notes, and labeled text as tags.
+    /// If labeled text specifies a region, a `CuePoint` tagged `RegionEnd` marks the end of the
+    /// region. Every sample loop is attached to the `Cue` of its cue point, or a new `Cue` if there
+    /// is no such cue point, with the loop type and play count as tags, and a `CuePoint` tagged
+    /// `LoopEnd` marking the last frame of the loop.
+    pub fn into_cues(self) -> Vec<Cue> {
+        let mut cues = Vec::new();
+
+        if let Some(cue) = self.cue {
+            for point in cue.points {
+                cues.push(Cue {
+                    index: point.id,
+                    start_ts: u64::from(point.sample_offset),
+                    tags: Vec::new(),
+                    points: Vec::new(),
+                });
+            }
+        }
+
+        for adtl in self.adtl {
+            if let Some(cue) = cues.iter_mut().find(|cue| cue.index == adtl.cue_point_id) {
+                cue.tags.push(Tag::new(None, adtl.key, Value::from(adtl.text)));
+
+                if let Some(sample_len) = adtl.sample_len.filter(|&len| len > 0) {
+                    cue.points.push(CuePoint {
+                        start_offset_ts: u64::from(sample_len),
+                        tags: vec![Tag::new(None, "RegionEnd", Value::Flag)],
+                    });
+                }
+            }
+        }
+
+        if let Some(smpl) = self.smpl {
+            for sample_loop in smpl.loops {
+                let loop_tags = vec![
+                    Tag::new(None, "LoopType", Value::from(sample_loop.loop_type)),
+                    Tag::new(None, "LoopPlayCount", Value::from(sample_loop.play_count)),
+                ];
+
+                let loop_end = CuePoint {
+                    start_offset_ts: u64::from(sample_loop.end.saturating_sub(sample_loop.start)),
+                    tags: vec![Tag::new(None, "LoopEnd", Value::Flag)],
+                };
+
+                // Attach the loop to its cue point if the cue point starts at the start of the
+                // loop.
+                let cue = cues.iter_mut().find(|cue| {
+                    cue.index == sample_loop.cue_point_id
+                        && cue.start_ts == u64::from(sample_loop.start)
+                });
+
+                if let Some(cue) = cue {
+                    cue.tags.extend(loop_tags);
+                    cue.points.push(loop_end);
+                }
+                else {
+                    // Use the index following the greatest cue index. Cue indices are read from
+                    // the file, so if the greatest is the maximum, then the loop is dropped.
+                    let index = match cues.iter().map(|cue| cue.index).max() {
+                        Some(index) => index.checked_add(1),
+                        None => Some(0),
+                    };
+
+                    if let Some(index) = index {
+                        cues.push(Cue {
+                            index,
+                            start_ts: u64::from(sample_loop.start),
+                            tags: loop_tags,
+                            points: vec![loop_end],
+                        });
+                    }
+                    else {
+                        warn!("wav: no cue index is available for a sample loop, dropping it");
+                    }
+                }
+            }
+        }
+
+        cues.sort_by_key(|cue| cue.start_ts);
+        cues
+    }
+}
+
+/// An ID3v2 tag embedded in a chunk.
+pub struct Id3Chunk {
+    pub metadata: MetadataRevision,
+}
+
+impl ParseChunk for Id3Chunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<Id3Chunk> {
+        let mut scoped = ScopedStream::new(reader, u64::from(len));
+
+        let mut builder = MetadataBuilder::new();
+        id3v2::read_id3v2(&mut scoped, &mut builder)?;
+
+        // Skip any data following the tag.
+        scoped.ignore()?;
+
+        Ok(Id3Chunk { metadata: builder.metadata() })
+    }
+}
+
 pub struct DataChunk {
     pub len: u32,
 }
@@ -556,6 +1209,10 @@ pub enum RiffWaveChunks {
     Format(ChunkParser<WaveFormatChunk>),
     List(ChunkParser<ListChunk>),
     Fact(ChunkParser<FactChunk>),
+    Bext(ChunkParser<BextChunk>),
+    Cue(ChunkParser<CueChunk>),
+    Smpl(ChunkParser<SmplChunk>),
+    Id3(ChunkParser<Id3Chunk>),
     Data(ChunkParser<DataChunk>),
 }
 
@@ -571,6 +1228,10 @@ impl ParseChunkTag for RiffWaveChunks {
             b"fmt " => parser!(RiffWaveChunks::Format, WaveFormatChunk, tag, len),
             b"LIST" => parser!(RiffWaveChunks::List, ListChunk, tag, len),
             b"fact" => parser!(RiffWaveChunks::Fact, FactChunk, tag, len),
+            b"bext" => parser!(RiffWaveChunks::Bext, BextChunk, tag, len),
+            b"cue " => parser!(RiffWaveChunks::Cue, CueChunk, tag, len),
+            b"smpl" => parser!(RiffWaveChunks::Smpl, SmplChunk, tag, len),
+            b"id3 " | b"ID3 " => parser!(RiffWaveChunks::Id3, Id3Chunk, tag, len),
             b"data" => parser!(RiffWaveChunks::Data, DataChunk, tag, len),
             _ => None,
         }
@@ -591,6 +1252,19 @@ impl ParseChunkTag for RiffInfoListChunks {
     }
 }
 
+pub enum RiffAdtlListChunks {
+    Adtl(ChunkParser<AdtlChunk>),
+}
+
+impl ParseChunkTag for RiffAdtlListChunks {
+    fn parse_tag(tag: [u8; 4], len: u32) -> Option<Self> {
+        match &tag {
+            b"labl" | b"note" | b"ltxt" => parser!(RiffAdtlListChunks::Adtl, AdtlChunk, tag, len),
+            _ => None,
+        }
+    }
+}
+
 pub fn append_fact_params(codec_params: &mut CodecParameters, fact: &FactChunk) {
     codec_params.with_n_frames(u64::from(fact.n_frames));
 }
@@ -616,3 +1290,241 @@ pub fn read_info_chunk(source: &mut MediaSourceStream, len: u32) -> Result<Metad
 
     Ok(metadata_builder.metadata())
 }
+
+pub fn read_adtl_chunk(source: &mut MediaSourceStream, len: u32) -> Result<Vec<AdtlChunk>> {
+    let mut adtl_list = ChunksReader::<RiffAdtlListChunks>::new(len, ByteOrder::LittleEndian);
+
+    let mut entries = Vec::new();
+
+    while let Some(RiffAdtlListChunks::Adtl(adtl)) = adtl_list.next(source)? {
+        entries.push(adtl.parse(source)?);
+    }
+
+    adtl_list.finish(source)?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    /// Makes a bext chunk with a description, version, the first two loudness fields, and a
+    /// coding history.
+    fn make_bext(version: u16, loudness: [u16; 2], coding_history: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0u8; BextChunk::MIN_LEN as usize];
+
+        buf[..9].copy_from_slice(b"Interview");
+        // The time reference, low word first.
+        buf[338..346].copy_from_slice(&0x1_0000_0002u64.to_le_bytes());
+        buf[346..348].copy_from_slice(&version.to_le_bytes());
+        // The UMID.
+        buf[348] = 0x06;
+        buf[412..414].copy_from_slice(&loudness[0].to_le_bytes());
+        buf[414..416].copy_from_slice(&loudness[1].to_le_bytes());
+        // The remaining loudness fields are unset.
+        for field in buf[416..422].chunks_exact_mut(2) {
+            field.copy_from_slice(&0x7fffu16.to_le_bytes());
+        }
+
+        buf.extend_from_slice(coding_history);
+        buf
+    }
+
+    fn parse_bext(buf: &[u8]) -> Result<BextChunk> {
+        BextChunk::parse(&mut BufReader::new(buf), *b"bext", buf.len() as u32)
+    }
+
+    #[test]
+    fn verify_bext_chunk() {
+        // Version 2 has the UMID and loudness fields. The loudness fields are signed, and 0x7fff
+        // indicates an unset field.
+        let bext =
+            parse_bext(&make_bext(2, [(-2300i16) as u16, 0x7fff], b"A=PCM\r\n\0\0")).unwrap();
+
+        assert_eq!(bext.description, "Interview");
+        assert_eq!(bext.time_reference, 0x1_0000_0002);
+        assert_eq!(bext.version, 2);
+        assert_eq!(bext.umid.map(|umid| umid[0]), Some(0x06));
+        assert_eq!(bext.loudness_value, Some(-23.0));
+        assert_eq!(bext.loudness_range, None);
+        assert_eq!(bext.max_true_peak_level, None);
+        assert_eq!(bext.coding_history, "A=PCM");
+
+        // Version 1 has the UMID, but not the loudness fields.
+        let bext = parse_bext(&make_bext(1, [100, 200], b"")).unwrap();
+
+        assert!(bext.umid.is_some());
+        assert_eq!(bext.loudness_value, None);
+        assert_eq!(bext.loudness_range, None);
+
+        // Version 0 has neither.
+        let bext = parse_bext(&make_bext(0, [100, 200], b"")).unwrap();
+
+        assert!(bext.umid.is_none());
+        assert_eq!(bext.loudness_value, None);
+        assert_eq!(bext.coding_history, "");
+
+        // The chunk must be atleast 602 bytes long.
+        assert!(parse_bext(&make_bext(2, [0, 0], b"")[..601]).is_err());
+    }
+
+    fn cue_point(id: u32, sample_offset: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&[0; 8]);
+        buf.extend_from_slice(&sample_offset.to_le_bytes());
+        buf
+    }
+
+    fn make_cue(points: &[(u32, u32)]) -> CueChunk {
+        let mut buf = (points.len() as u32).to_le_bytes().to_vec();
+
+        for &(id, sample_offset) in points {
+            buf.extend_from_slice(&cue_point(id, sample_offset));
+        }
+
+        CueChunk::parse(&mut BufReader::new(&buf), *b"cue ", buf.len() as u32).unwrap()
+    }
+
+    /// Makes a smpl chunk with the given sample loops of cue point identifier, start, and end.
+    fn make_smpl(loops: &[(u32, u32, u32)]) -> SmplChunk {
+        let mut buf = vec![0u8; 28];
+        // The number of sample loops, and the length of the sampler specific data.
+        buf.extend_from_slice(&(loops.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&4u32.to_le_bytes());
+
+        for &(id, start, end) in loops {
+            for field in [id, 1, start, end, 0, 3].iter() {
+                buf.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+
+        buf.extend_from_slice(&[0xff; 4]);
+
+        SmplChunk::parse(&mut BufReader::new(&buf), *b"smpl", buf.len() as u32).unwrap()
+    }
+
+    fn parse_adtl(tag: [u8; 4], buf: &[u8]) -> Result<AdtlChunk> {
+        AdtlChunk::parse(&mut BufReader::new(buf), tag, buf.len() as u32)
+    }
+
+    #[test]
+    fn verify_cue_chunk() {
+        let cue = make_cue(&[(1, 100), (2, 50)]);
+
+        assert_eq!(cue.points.len(), 2);
+        assert_eq!((cue.points[1].id, cue.points[1].sample_offset), (2, 50));
+
+        // The number of cue points must fit in the chunk.
+        let mut buf = 2u32.to_le_bytes().to_vec();
+        buf.extend_from_slice(&cue_point(1, 100));
+
+        let result = CueChunk::parse(&mut BufReader::new(&buf), *b"cue ", buf.len() as u32);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_smpl_chunk() {
+        let smpl = make_smpl(&[(7, 10, 20)]);
+
+        assert_eq!(smpl.loops.len(), 1);
+        assert_eq!(smpl.loops[0].cue_point_id, 7);
+        assert_eq!(smpl.loops[0].loop_type, 1);
+        assert_eq!((smpl.loops[0].start, smpl.loops[0].end), (10, 20));
+        assert_eq!(smpl.loops[0].play_count, 3);
+
+        // The chunk must be atleast 36 bytes long.
+        let result = SmplChunk::parse(&mut BufReader::new(&[0; 32]), *b"smpl", 32);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_adtl_chunk() {
+        let adtl = parse_adtl(*b"labl", b"\x02\0\0\0Intro\0").unwrap();
+
+        assert_eq!(adtl.cue_point_id, 2);
+        assert_eq!(adtl.key, "Label");
+        assert_eq!(adtl.text, "Intro");
+        assert_eq!(adtl.sample_len, None);
+
+        let mut buf = vec![2, 0, 0, 0, 0x10, 0, 0, 0];
+        buf.extend_from_slice(&[0; 12]);
+        buf.extend_from_slice(b"Verse");
+
+        let adtl = parse_adtl(*b"ltxt", &buf).unwrap();
+
+        assert_eq!(adtl.key, "Text");
+        assert_eq!(adtl.text, "Verse");
+        assert_eq!(adtl.sample_len, Some(0x10));
+
+        // Labeled text must be atleast 20 bytes long, and unknown chunks are an error.
+        assert!(parse_adtl(*b"ltxt", &buf[..19]).is_err());
+        assert!(parse_adtl(*b"abcd", b"\x02\0\0\0Intro").is_err());
+    }
+
+    #[test]
+    fn verify_cue_info_into_cues() {
+        let info = CueInfo {
+            cue: Some(make_cue(&[(1, 100), (2, 50)])),
+            adtl: vec![
+                parse_adtl(*b"labl", b"\x01\0\0\0Chorus").unwrap(),
+                parse_adtl(*b"labl", b"\x09\0\0\0Orphan").unwrap(),
+            ],
+            smpl: Some(make_smpl(&[(1, 100, 150), (5, 300, 400)])),
+        };
+
+        let cues = info.into_cues();
+
+        assert_eq!(cues.iter().map(|cue| cue.start_ts).collect::<Vec<_>>(), [50, 100, 300]);
+
+        // The label, and the loop starting at the cue point, are attached to cue point 1.
+        assert_eq!(cues[1].index, 1);
+        assert_eq!(cues[1].tags.len(), 3);
+        assert_eq!(cues[1].points[0].start_offset_ts, 50);
+
+        // The loop that matches no cue point gets the next cue index.
+        assert_eq!(cues[2].index, 3);
+        assert_eq!(cues[2].points[0].start_offset_ts, 100);
+    }
+
+    #[test]
+    fn verify_cue_info_into_cues_max_index() {
+        // If the greatest cue index is the maximum, a loop that matches no cue point is dropped.
+        let info = CueInfo {
+            cue: Some(make_cue(&[(u32::MAX, 100)])),
+            adtl: Vec::new(),
+            smpl: Some(make_smpl(&[(5, 300, 400)])),
+        };
+
+        let cues = info.into_cues();
+
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].index, u32::MAX);
+    }
+
+    #[test]
+    fn verify_adtl_chunk_long_text() {
+        // Only the first 64 kB of the text is read, and the rest is skipped.
+        let mut buf = vec![1, 0, 0, 0];
+        buf.extend_from_slice(&vec![b'A'; MAX_TEXT_FIELD_LEN as usize + 10]);
+
+        assert_eq!(parse_adtl(*b"note", &buf).unwrap().text.len(), MAX_TEXT_FIELD_LEN as usize);
+    }
+
+    #[test]
+    fn verify_bext_chunk_long_coding_history() {
+        // Only the first 64 kB of the coding history is read, and the rest is skipped.
+        let mut buf = make_bext(0, [0, 0], &vec![b'A'; MAX_TEXT_FIELD_LEN as usize + 10]);
+        buf.extend_from_slice(b"next");
+
+        let mut reader = BufReader::new(&buf);
+        let bext = BextChunk::parse(&mut reader, *b"bext", buf.len() as u32 - 4).unwrap();
+
+        assert_eq!(bext.coding_history.len(), MAX_TEXT_FIELD_LEN as usize);
+        assert_eq!(reader.read_quad_bytes().unwrap(), *b"next");
+    }
+}
@@ -16,10 +16,11 @@ use symphonia_core::meta::{Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_format;
 
-use log::{debug, error};
+use log::{debug, error, warn};
 
+use crate::chunks::{ByteOrder, ChunksReader};
 use crate::common::{
-    append_data_params, append_format_params, next_packet, ByteOrder, ChunksReader, PacketInfo,
+    append_data_params, append_format_params, next_packet, read_appended_id3v2, PacketInfo,
 };
 mod chunks;
 use chunks::*;
@@ -56,8 +57,14 @@ impl QueryDescriptor for WavReader {
         ]
     }
 
-    fn score(_context: &[u8]) -> u8 {
-        255
+    fn score(context: &[u8]) -> u8 {
+        // The RIFF marker is shared with other RIFF forms, such as AVI.
+        if context.len() >= 12 && context[8..12] != WAVE_RIFF_FORM {
+            0
+        }
+        else {
+            255
+        }
     }
 }
 
@@ -83,12 +90,20 @@ impl FormatReader for WavReader {
             return unsupported_error("wav: riff form is not wave");
         }
 
-        let mut riff_chunks =
-            ChunksReader::<RiffWaveChunks>::new(riff_len, ByteOrder::LittleEndian);
+        // Writers that stream their output, and therefore cannot go back to fill in the length of
+        // the RIFF chunk once it is known, set it to either 0 or (2^32)-1. In either case, treat
+        // the RIFF chunk as extending to the end of the stream.
+        let is_streamed = riff_len == 0 || riff_len == u32::MAX;
+
+        let mut riff_chunks = ChunksReader::<RiffWaveChunks>::new(
+            if is_streamed { u32::MAX } else { riff_len },
+            ByteOrder::LittleEndian,
+        );
 
         let mut codec_params = CodecParameters::new();
         let mut metadata: MetadataLog = Default::default();
         let mut packet_info = PacketInfo::without_blocks(0);
+        let mut cue_info: CueInfo = Default::default();
 
         loop {
             let chunk = riff_chunks.next(&mut source)?;
@@ -123,37 +138,74 @@ impl FormatReader for WavReader {
                     // Append Fact chunk fields to codec parameters.
                     append_fact_params(&mut codec_params, &fact);
                 }
-                RiffWaveChunks::List(lst) => {
-                    let list = lst.parse(&mut source)?;
-
-                    // Riff Lists can have many different forms, but WavReader only supports Info
-                    // lists.
-                    match &list.form {
-                        b"INFO" => metadata.push(read_info_chunk(&mut source, list.len)?),
-                        _ => list.skip(&mut source)?,
-                    }
-                }
                 RiffWaveChunks::Data(dat) => {
                     let data = dat.parse(&mut source)?;
 
                     // Record the bounds of the data chunk.
                     let data_start_pos = source.pos();
-                    let data_end_pos = data_start_pos + u64::from(data.len);
+
+                    // A streamed data chunk also has an unknown length, and ends wherever the
+                    // stream ends. If the length of the media source is known, the end of the data
+                    // chunk can be inferred from it. Otherwise, the number of frames is unknown.
+                    let is_data_len_unknown =
+                        data.len == u32::MAX || (data.len == 0 && is_streamed);
+
+                    let data_end_pos = if is_data_len_unknown {
+                        source.byte_len().filter(|_| source.is_seekable()).unwrap_or(u64::MAX)
+                    }
+                    else {
+                        data_start_pos + u64::from(data.len)
+                    };
 
                     // Append Data chunk fields to codec parameters.
-                    append_data_params(&mut codec_params, data.len as u64, &packet_info);
+                    if data_end_pos != u64::MAX {
+                        append_data_params(
+                            &mut codec_params,
+                            data_end_pos - data_start_pos,
+                            &packet_info,
+                        );
+                    }
+
+                    // Many writers place metadata chunks after the data chunk. If the source is
+                    // seekable, skip over the audio data to read them, and then return to the
+                    // start of the audio data.
+                    let has_trailing_chunks = source.is_seekable()
+                        && source.byte_len().map_or(false, |len| data_end_pos < len);
+
+                    if has_trailing_chunks {
+                        source.seek(SeekFrom::Start(data_end_pos))?;
+
+                        // The chunks following the data chunk are not required to play the
+                        // stream, therefore, errors encountered while reading them are not fatal.
+                        if let Err(err) = read_trailing_chunks(
+                            &mut source,
+                            &mut riff_chunks,
+                            &mut metadata,
+                            &mut cue_info,
+                        ) {
+                            warn!("wav: ignoring chunks after the data chunk: {}", err);
+                        }
+
+                        // Some taggers append an ID3v2 tag after the RIFF chunk.
+                        if let Err(err) = read_appended_id3v2(&mut source, &mut metadata) {
+                            warn!("wav: ignoring appended id3v2 tag: {}", err);
+                        }
+
+                        source.seek(SeekFrom::Start(data_start_pos))?;
+                    }
 
                     // Add a new track using the collected codec parameters.
                     return Ok(WavReader {
                         reader: source,
                         tracks: vec![Track::new(0, codec_params)],
-                        cues: Vec::new(),
+                        cues: cue_info.into_cues(),
                         metadata,
                         packet_info,
                         data_start_pos,
                         data_end_pos,
                     });
                 }
+                chunk => read_metadata_chunk(&mut source, chunk, &mut metadata, &mut cue_info)?,
             }
         }
     }
@@ -222,7 +274,8 @@ impl FormatReader for WavReader {
         let actual_ts = self.packet_info.get_actual_ts(ts);
 
         // Calculate the absolute byte offset of the desired audio frame.
-        let seek_pos = self.data_start_pos + (actual_ts * self.packet_info.block_size);
+        let seek_pos = self.data_start_pos
+            + (actual_ts / self.packet_info.frames_per_block * self.packet_info.block_size);
 
         // If the reader supports seeking we can seek directly to the frame's offset wherever it may
         // be.
@@ -250,3 +303,54 @@ impl FormatReader for WavReader {
         self.reader
     }
 }
+
+/// Reads a chunk that may contain metadata or markers. Chunks that contain neither are skipped.
+fn read_metadata_chunk(
+    source: &mut MediaSourceStream,
+    chunk: RiffWaveChunks,
+    metadata: &mut MetadataLog,
+    cue_info: &mut CueInfo,
+) -> Result<()> {
+    match chunk {
+        RiffWaveChunks::List(lst) => {
+            let list = lst.parse(source)?;
+
+            // Riff Lists can have many different forms, but WavReader only supports Info and
+            // associated data lists.
+            match &list.form {
+                b"INFO" => metadata.push(read_info_chunk(source, list.len)?),
+                b"adtl" => cue_info.adtl.extend(read_adtl_chunk(source, list.len)?),
+                _ => list.skip(source)?,
+            }
+        }
+        RiffWaveChunks::Bext(bxt) => {
+            let bext = bxt.parse(source)?;
+            metadata.push(bext.metadata());
+        }
+        RiffWaveChunks::Cue(cue) => cue_info.cue = Some(cue.parse(source)?),
+        RiffWaveChunks::Smpl(smpl) => {
+            let smpl = smpl.parse(source)?;
+            metadata.push(smpl.metadata());
+            cue_info.smpl = Some(smpl);
+        }
+        RiffWaveChunks::Id3(id3) => metadata.push(id3.parse(source)?.metadata),
+        RiffWaveChunks::Format(fmt) => source.ignore_bytes(u64::from(fmt.len))?,
+        RiffWaveChunks::Fact(fct) => source.ignore_bytes(u64::from(fct.len))?,
+        RiffWaveChunks::Data(dat) => source.ignore_bytes(u64::from(dat.len))?,
+    }
+    Ok(())
+}
+
+/// Reads the chunks following the data chunk. Only metadata chunks are read, all other chunks are
+/// skipped.
+fn read_trailing_chunks(
+    source: &mut MediaSourceStream,
+    riff_chunks: &mut ChunksReader<RiffWaveChunks>,
+    metadata: &mut MetadataLog,
+    cue_info: &mut CueInfo,
+) -> Result<()> {
+    while let Some(chunk) = riff_chunks.next(source)? {
+        read_metadata_chunk(source, chunk, metadata, cue_info)?;
+    }
+    Ok(())
+}
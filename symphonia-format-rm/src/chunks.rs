@@ -0,0 +1,474 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+use log::debug;
+
+/// The length of a chunk header.
+pub const CHUNK_HEADER_LEN: u64 = 10;
+
+/// The sub-packet lengths of the Sipro flavors.
+const SIPR_BLOCK_LENS: [usize; 4] = [29, 19, 37, 20];
+
+/// A chunk header.
+pub struct ChunkHeader {
+    pub id: [u8; 4],
+    /// The length of the chunk body.
+    pub len: u64,
+}
+
+impl ChunkHeader {
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ChunkHeader> {
+        let mut id = [0; 4];
+        reader.read_buf_exact(&mut id)?;
+
+        // The chunk size includes the chunk header.
+        let len = match u64::from(reader.read_be_u32()?).checked_sub(CHUNK_HEADER_LEN) {
+            Some(len) => len,
+            None => return decode_error("rm: invalid chunk size"),
+        };
+
+        let _version = reader.read_be_u16()?;
+
+        Ok(ChunkHeader { id, len })
+    }
+}
+
+/// Decode an ISO-8859-1 string, excluding any terminating null characters.
+fn decode_str(buf: &[u8]) -> String {
+    let text: String = buf.iter().map(|&b| char::from(b)).collect();
+    text.trim_end_matches('\0').to_string()
+}
+
+/// Read the title, author, copyright, and comment strings, each preceded by a length of
+/// `len_bytes` bytes, as tags.
+fn read_content<B: ReadBytes>(reader: &mut B, len_bytes: usize) -> Result<Vec<Tag>> {
+    let fields = [
+        ("Title", StandardTagKey::TrackTitle),
+        ("Author", StandardTagKey::Artist),
+        ("Copyright", StandardTagKey::Copyright),
+        ("Comment", StandardTagKey::Comment),
+    ];
+
+    let mut tags = Vec::new();
+
+    for (name, std_key) in fields {
+        let len = match len_bytes {
+            1 => usize::from(reader.read_u8()?),
+            _ => usize::from(reader.read_be_u16()?),
+        };
+
+        let value = decode_str(&reader.read_boxed_slice_exact(len)?);
+
+        if !value.is_empty() {
+            tags.push(Tag::new(Some(std_key), name, Value::from(value)));
+        }
+    }
+
+    Ok(tags)
+}
+
+/// The file properties of a PROP chunk.
+pub struct Properties {
+    /// The duration of the file in milliseconds.
+    pub duration: u32,
+    /// The offset of the first INDX chunk from the start of the file, or 0 if there is no index.
+    pub index_offset: u32,
+}
+
+/// Read a PROP chunk body.
+pub fn read_prop<B: ReadBytes>(reader: &mut B) -> Result<Properties> {
+    // Skip the bit rates, packet sizes, and number of packets.
+    reader.ignore_bytes(20)?;
+
+    let duration = reader.read_be_u32()?;
+    let _preroll = reader.read_be_u32()?;
+    let index_offset = reader.read_be_u32()?;
+
+    Ok(Properties { duration, index_offset })
+}
+
+/// Read a CONT chunk body.
+pub fn read_cont<B: ReadBytes>(reader: &mut B) -> Result<Vec<Tag>> {
+    read_content(reader, 2)
+}
+
+/// The media properties of a MDPR chunk.
+pub struct MediaProperties {
+    pub stream_num: u16,
+    /// The duration of the stream in milliseconds.
+    pub duration: u32,
+    /// The type specific data describing the stream.
+    pub type_specific: Box<[u8]>,
+}
+
+/// Read a MDPR chunk body.
+pub fn read_mdpr<B: ReadBytes>(reader: &mut B) -> Result<MediaProperties> {
+    let stream_num = reader.read_be_u16()?;
+
+    // Skip the bit rates, packet sizes, start time, and preroll.
+    reader.ignore_bytes(24)?;
+
+    let duration = reader.read_be_u32()?;
+
+    let name_len = reader.read_u8()?;
+    reader.ignore_bytes(u64::from(name_len))?;
+
+    let mime_type_len = reader.read_u8()?;
+    reader.ignore_bytes(u64::from(mime_type_len))?;
+
+    let type_specific_len = reader.read_be_u32()?;
+    let type_specific = reader.read_boxed_slice_exact(type_specific_len as usize)?;
+
+    Ok(MediaProperties { stream_num, duration, type_specific })
+}
+
+/// An entry of an INDX chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The timestamp in milliseconds.
+    pub ts: u32,
+    /// The offset of the data packet from the start of the file.
+    pub offset: u32,
+}
+
+/// An index of the data packets of a stream.
+pub struct Index {
+    pub stream_num: u16,
+    /// The offset of the next INDX chunk from the start of the file, or 0 if this is the last.
+    pub next_offset: u32,
+    pub entries: Vec<IndexEntry>,
+}
+
+/// Read an INDX chunk body.
+pub fn read_indx<B: ReadBytes>(reader: &mut B) -> Result<Index> {
+    let num_entries = reader.read_be_u32()?;
+    let stream_num = reader.read_be_u16()?;
+    let next_offset = reader.read_be_u32()?;
+
+    let mut entries = Vec::new();
+
+    for _ in 0..num_entries {
+        let _version = reader.read_be_u16()?;
+        let ts = reader.read_be_u32()?;
+        let offset = reader.read_be_u32()?;
+        let _packet_num = reader.read_be_u32()?;
+
+        entries.push(IndexEntry { ts, offset });
+    }
+
+    Ok(Index { stream_num, next_offset, entries })
+}
+
+/// The interleaving scheme of an audio stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interleaver {
+    /// Each packet contains a single block.
+    None,
+    /// Frames are interleaved over rows of a superblock.
+    Int4,
+    /// Sub-packets are interleaved over rows of a superblock.
+    Genr,
+    /// The nibbles of a superblock of rows are reordered.
+    Sipr,
+    /// Each packet contains variable length blocks, preceded by their lengths.
+    Vbr,
+}
+
+/// An audio stream.
+pub struct AudioStream {
+    pub params: CodecParameters,
+    pub interleaver: Interleaver,
+    /// The number of rows of a superblock.
+    pub sub_packet_h: usize,
+    /// The length of a row of a superblock.
+    pub frame_len: usize,
+    /// The length of a frame interleaved by `Interleaver::Int4`.
+    pub coded_frame_len: usize,
+    /// The length of a sub-packet interleaved by `Interleaver::Genr`.
+    pub sub_packet_len: usize,
+    /// The length of a block of a deinterleaved superblock.
+    pub block_len: usize,
+    /// The number of bytes per minute, or 0 if unknown.
+    pub bytes_per_minute: u32,
+    /// If true, the 16-bit words of each block are byte-swapped.
+    pub swap_bytes: bool,
+    pub tags: Vec<Tag>,
+}
+
+/// Read a string preceded by a 1 byte length.
+fn read_str8<B: ReadBytes>(reader: &mut B) -> Result<Box<[u8]>> {
+    let len = reader.read_u8()?;
+    Ok(reader.read_boxed_slice_exact(usize::from(len))?)
+}
+
+/// Read a RealAudio header, following the ".ra\xfd" marker. If `is_file` is true, the header is
+/// the header of a RealAudio file, otherwise it is the type specific data of a MDPR chunk.
+pub fn read_ra_header<B: ReadBytes>(reader: &mut B, is_file: bool) -> Result<AudioStream> {
+    let version = reader.read_be_u16()?;
+
+    if version == 3 {
+        // Version 3 streams are always 8 kHz mono RealAudio 1.0 (14.4).
+        let header_len = reader.read_be_u16()?;
+
+        let buf = reader.read_boxed_slice_exact(usize::from(header_len))?;
+        let mut reader = BufReader::new(&buf);
+
+        reader.ignore_bytes(8)?;
+        let bytes_per_minute = u32::from(reader.read_be_u16()?);
+        reader.ignore_bytes(4)?;
+
+        let tags = read_content(&mut reader, 1)?;
+
+        let mut params = CodecParameters::new();
+        params.with_sample_rate(8000).with_channels(Channels::FRONT_LEFT);
+
+        return Ok(AudioStream {
+            params,
+            interleaver: Interleaver::None,
+            sub_packet_h: 1,
+            frame_len: 0,
+            coded_frame_len: 0,
+            sub_packet_len: 0,
+            block_len: 0,
+            bytes_per_minute,
+            swap_bytes: false,
+            tags,
+        });
+    }
+
+    if version != 4 && version != 5 {
+        return unsupported_error("rm: unsupported realaudio version");
+    }
+
+    // Skip the unused field, the ".ra4" or ".ra5" marker, the data size, the version, and the
+    // header size.
+    reader.ignore_bytes(16)?;
+
+    let flavor = reader.read_be_u16()?;
+    let coded_frame_len = reader.read_be_u32()? as usize;
+    reader.ignore_bytes(4)?;
+    let bytes_per_minute = reader.read_be_u32()?;
+    reader.ignore_bytes(4)?;
+    let sub_packet_h = usize::from(reader.read_be_u16()?);
+    let frame_len = usize::from(reader.read_be_u16()?);
+    let sub_packet_len = usize::from(reader.read_be_u16()?);
+    reader.ignore_bytes(2)?;
+
+    if version == 5 {
+        reader.ignore_bytes(6)?;
+    }
+
+    let sample_rate = u32::from(reader.read_be_u16()?);
+    reader.ignore_bytes(4)?;
+    let num_channels = reader.read_be_u16()?;
+
+    let (interleaver_id, fourcc) = if version == 5 {
+        let mut interleaver_id = [0; 4];
+        reader.read_buf_exact(&mut interleaver_id)?;
+
+        let mut fourcc = [0; 4];
+        reader.read_buf_exact(&mut fourcc)?;
+
+        (interleaver_id.to_vec(), fourcc.to_vec())
+    }
+    else {
+        (read_str8(reader)?.to_vec(), read_str8(reader)?.to_vec())
+    };
+
+    let codec = match &fourcc[..] {
+        b"cook" => CODEC_TYPE_COOK,
+        b"sipr" => CODEC_TYPE_SIPR,
+        b"atrc" => CODEC_TYPE_ATRAC3,
+        b"raac" | b"racp" => CODEC_TYPE_AAC,
+        b"dnet" => CODEC_TYPE_EAC3,
+        _ => {
+            debug!("unsupported realaudio codec {:?}", decode_str(&fourcc));
+            CODEC_TYPE_NULL
+        }
+    };
+
+    let mut tags = Vec::new();
+    let mut extra_data = None;
+
+    if is_file {
+        // A RealAudio file has no codec data, and is followed by the content strings.
+        reader.ignore_bytes(3)?;
+        tags = read_content(reader, 1)?;
+    }
+    else if matches!(
+        codec,
+        CODEC_TYPE_COOK | CODEC_TYPE_SIPR | CODEC_TYPE_ATRAC3 | CODEC_TYPE_AAC
+    ) {
+        reader.ignore_bytes(if version == 5 { 4 } else { 3 })?;
+
+        let mut len = reader.read_be_u32()? as usize;
+
+        // The codec data of AAC is preceded by a byte.
+        if codec == CODEC_TYPE_AAC && len > 0 {
+            reader.read_u8()?;
+            len -= 1;
+        }
+
+        extra_data = Some(reader.read_boxed_slice_exact(len)?);
+    }
+
+    let block_len = match &fourcc[..] {
+        b"sipr" => match SIPR_BLOCK_LENS.get(usize::from(flavor)) {
+            Some(&len) => len,
+            None => return decode_error("rm: invalid sipro flavor"),
+        },
+        b"cook" | b"atrc" => sub_packet_len,
+        b"28_8" => coded_frame_len,
+        _ => frame_len,
+    };
+
+    let interleaver = match &interleaver_id[..] {
+        b"Int4" => Interleaver::Int4,
+        b"genr" => Interleaver::Genr,
+        b"sipr" => Interleaver::Sipr,
+        b"vbrs" | b"vbrf" => Interleaver::Vbr,
+        b"Int0" => Interleaver::None,
+        _ => return unsupported_error("rm: unsupported interleaver"),
+    };
+
+    // Verify the superblock of an interleaved stream may be deinterleaved into blocks.
+    let is_valid = match interleaver {
+        Interleaver::Int4 => {
+            coded_frame_len <= frame_len
+                && sub_packet_h > 1
+                && coded_frame_len * sub_packet_h <= (2 + (sub_packet_h & 1)) * frame_len
+        }
+        Interleaver::Genr => {
+            sub_packet_len > 0 && sub_packet_len <= frame_len && frame_len % sub_packet_len == 0
+        }
+        _ => true,
+    };
+
+    let is_interleaved =
+        matches!(interleaver, Interleaver::Int4 | Interleaver::Genr | Interleaver::Sipr);
+
+    if !is_valid || (is_interleaved && (block_len == 0 || frame_len * sub_packet_h < block_len)) {
+        return decode_error("rm: invalid interleaving parameters");
+    }
+
+    if num_channels == 0 || num_channels > 32 {
+        return decode_error("rm: invalid channel count");
+    }
+
+    let mut params = CodecParameters::new();
+
+    params
+        .for_codec(codec)
+        .with_sample_rate(sample_rate)
+        .with_channels(Channels::from_bits_truncate(((1u64 << num_channels) - 1) as u32));
+
+    if let Some(extra_data) = extra_data.filter(|data| !data.is_empty()) {
+        params.with_extra_data(extra_data);
+    }
+
+    Ok(AudioStream {
+        params,
+        interleaver,
+        sub_packet_h,
+        frame_len,
+        coded_frame_len,
+        sub_packet_len,
+        block_len,
+        bytes_per_minute,
+        swap_bytes: &fourcc[..] == b"dnet",
+        tags,
+    })
+}
+
+/// Read the type specific data of a MDPR chunk. Returns `None` if the stream is not a supported
+/// audio stream.
+pub fn read_type_specific(buf: &[u8]) -> Result<Option<AudioStream>> {
+    match buf.get(..4) {
+        Some(b".ra\xfd") => {
+            let mut reader = BufReader::new(&buf[4..]);
+            Ok(Some(read_ra_header(&mut reader, false)?))
+        }
+        Some(b"LSD:") => {
+            // The RealAudio Lossless header is the codec data.
+            if buf.len() < 24 {
+                return decode_error("rm: invalid ralf header");
+            }
+
+            let num_channels = u16::from_be_bytes([buf[8], buf[9]]);
+            let sample_rate = u32::from_be_bytes([buf[12], buf[13], buf[14], buf[15]]);
+
+            if num_channels == 0 || num_channels > 32 {
+                return decode_error("rm: invalid channel count");
+            }
+
+            let mut params = CodecParameters::new();
+
+            params
+                .for_codec(CODEC_TYPE_RALF)
+                .with_sample_rate(sample_rate)
+                .with_channels(Channels::from_bits_truncate(((1u64 << num_channels) - 1) as u32))
+                .with_extra_data(Box::from(buf));
+
+            Ok(Some(AudioStream {
+                params,
+                interleaver: Interleaver::None,
+                sub_packet_h: 1,
+                frame_len: 0,
+                coded_frame_len: 0,
+                sub_packet_len: 0,
+                block_len: 0,
+                bytes_per_minute: 0,
+                swap_bytes: false,
+                tags: Vec::new(),
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_ra_header() {
+        // A version 5 Cook stream, interleaved by sub-packets of 93 bytes over 16 rows.
+        let mut buf = vec![0, 5, 0, 0];
+        buf.extend_from_slice(b".ra5");
+        buf.extend_from_slice(&[0; 10]);
+        buf.extend_from_slice(&5u16.to_be_bytes());
+        buf.extend_from_slice(&1488u32.to_be_bytes());
+        buf.extend_from_slice(&[0; 12]);
+        buf.extend_from_slice(&16u16.to_be_bytes());
+        buf.extend_from_slice(&744u16.to_be_bytes());
+        buf.extend_from_slice(&93u16.to_be_bytes());
+        buf.extend_from_slice(&[0; 8]);
+        buf.extend_from_slice(&44100u16.to_be_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        buf.extend_from_slice(&2u16.to_be_bytes());
+        buf.extend_from_slice(b"genrcook");
+        buf.extend_from_slice(&[0; 4]);
+        buf.extend_from_slice(&8u32.to_be_bytes());
+        buf.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+
+        let stream = read_ra_header(&mut BufReader::new(&buf), false).unwrap();
+
+        assert_eq!(stream.params.codec, CODEC_TYPE_COOK);
+        assert_eq!(stream.params.sample_rate, Some(44100));
+        assert_eq!(stream.params.extra_data.as_deref().map(|data| data.len()), Some(8));
+        assert_eq!(stream.interleaver, Interleaver::Genr);
+        assert_eq!(stream.sub_packet_h, 16);
+        assert_eq!(stream.frame_len, 744);
+        assert_eq!(stream.block_len, 93);
+    }
+}
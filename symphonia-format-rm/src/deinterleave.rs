@@ -0,0 +1,314 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+use crate::chunks::{AudioStream, Interleaver};
+
+/// The length of a packet of a RealAudio file with unknown frame lengths.
+const RAW_PACKET_LEN: usize = 1000;
+
+/// The pairs of nibble groups swapped by the Sipro interleaver.
+const SIPR_SWAPS: [(usize, usize); 38] = [
+    (0, 63),
+    (1, 22),
+    (2, 44),
+    (3, 90),
+    (5, 81),
+    (7, 31),
+    (8, 86),
+    (9, 58),
+    (10, 36),
+    (12, 68),
+    (13, 39),
+    (14, 73),
+    (15, 53),
+    (16, 69),
+    (17, 57),
+    (19, 88),
+    (20, 34),
+    (21, 71),
+    (24, 46),
+    (25, 94),
+    (26, 54),
+    (28, 75),
+    (29, 50),
+    (32, 70),
+    (33, 92),
+    (35, 74),
+    (38, 85),
+    (40, 56),
+    (42, 87),
+    (43, 65),
+    (45, 59),
+    (48, 79),
+    (49, 93),
+    (51, 89),
+    (55, 95),
+    (61, 76),
+    (67, 83),
+    (77, 80),
+];
+
+/// Reorder the nibbles of a superblock of Sipro audio.
+fn reorder_sipr(buf: &mut [u8], sub_packet_h: usize, frame_len: usize) {
+    // The superblock is split into 96 groups of nibbles.
+    let group_len = sub_packet_h * frame_len * 2 / 96;
+
+    let get = |buf: &[u8], n: usize| (buf[n >> 1] >> (4 * (n & 1))) & 0xf;
+
+    let set = |buf: &mut [u8], n: usize, x: u8| {
+        let shift = 4 * (n & 1);
+        buf[n >> 1] = (buf[n >> 1] & !(0xf << shift)) | (x << shift);
+    };
+
+    for &(a, b) in SIPR_SWAPS.iter() {
+        for j in 0..group_len {
+            let i = a * group_len + j;
+            let o = b * group_len + j;
+
+            let x = get(buf, i);
+            let y = get(buf, o);
+
+            set(buf, o, x);
+            set(buf, i, y);
+        }
+    }
+}
+
+/// A block of audio, consisting of one or more codec packets, starting at a timestamp.
+pub struct Block {
+    /// The timestamp in milliseconds.
+    pub ts: u64,
+    pub packets: Vec<Box<[u8]>>,
+}
+
+/// A deinterleaver of the packets of an audio stream into blocks.
+pub struct Deinterleaver {
+    interleaver: Interleaver,
+    sub_packet_h: usize,
+    frame_len: usize,
+    coded_frame_len: usize,
+    sub_packet_len: usize,
+    block_len: usize,
+    swap_bytes: bool,
+    /// The superblock being deinterleaved.
+    buf: Vec<u8>,
+    /// The next row of the superblock.
+    row: usize,
+    /// The timestamp of the superblock.
+    ts: u64,
+}
+
+impl Deinterleaver {
+    pub fn new(stream: &AudioStream) -> Self {
+        let is_interleaved =
+            matches!(stream.interleaver, Interleaver::Int4 | Interleaver::Genr | Interleaver::Sipr);
+
+        let buf_len = if is_interleaved { stream.frame_len * stream.sub_packet_h } else { 0 };
+
+        Deinterleaver {
+            interleaver: stream.interleaver,
+            sub_packet_h: stream.sub_packet_h,
+            frame_len: stream.frame_len,
+            coded_frame_len: stream.coded_frame_len,
+            sub_packet_len: stream.sub_packet_len,
+            block_len: stream.block_len,
+            swap_bytes: stream.swap_bytes,
+            buf: vec![0; buf_len],
+            row: 0,
+            ts: 0,
+        }
+    }
+
+    /// Discard the superblock being deinterleaved.
+    pub fn reset(&mut self) {
+        self.row = 0;
+    }
+
+    /// Get the length of a row of a superblock, or of a packet if the stream is not interleaved.
+    pub fn row_len(&self) -> usize {
+        match self.interleaver {
+            Interleaver::Int4 => self.coded_frame_len * (self.sub_packet_h / 2),
+            Interleaver::Genr | Interleaver::Sipr => self.frame_len,
+            _ if self.coded_frame_len > 0 => self.coded_frame_len,
+            _ => RAW_PACKET_LEN,
+        }
+    }
+
+    /// Get the number of rows of a superblock, or 1 if the stream is not interleaved.
+    pub fn rows(&self) -> usize {
+        if self.buf.is_empty() {
+            1
+        }
+        else {
+            self.sub_packet_h
+        }
+    }
+
+    /// Add the data of a packet. A keyframe packet starts a superblock. Returns a block if one was
+    /// completed.
+    pub fn push(&mut self, data: &[u8], ts: u64, is_keyframe: bool) -> Result<Option<Block>> {
+        let mut packets = match self.interleaver {
+            Interleaver::None => vec![Box::from(data)],
+            Interleaver::Vbr => split_vbr(data)?,
+            _ => {
+                if is_keyframe {
+                    self.row = 0;
+                }
+
+                if self.row == 0 {
+                    self.ts = ts;
+                }
+
+                if data.len() < self.row_len() {
+                    return decode_error("rm: packet too short for interleaver");
+                }
+
+                let h = self.sub_packet_h;
+                let w = self.frame_len;
+                let y = self.row;
+
+                match self.interleaver {
+                    Interleaver::Int4 => {
+                        let cfs = self.coded_frame_len;
+
+                        for (x, frame) in data.chunks_exact(cfs).take(h / 2).enumerate() {
+                            let pos = x * 2 * w + y * cfs;
+                            self.buf[pos..pos + cfs].copy_from_slice(frame);
+                        }
+                    }
+                    Interleaver::Genr => {
+                        let sps = self.sub_packet_len;
+
+                        for (x, sub_packet) in data.chunks_exact(sps).take(w / sps).enumerate() {
+                            let pos = sps * (h * x + ((h + 1) / 2) * (y & 1) + (y >> 1));
+                            self.buf[pos..pos + sps].copy_from_slice(sub_packet);
+                        }
+                    }
+                    _ => self.buf[y * w..(y + 1) * w].copy_from_slice(&data[..w]),
+                }
+
+                self.row += 1;
+
+                if self.row < h {
+                    return Ok(None);
+                }
+
+                self.row = 0;
+
+                if self.interleaver == Interleaver::Sipr {
+                    reorder_sipr(&mut self.buf, h, w);
+                }
+
+                let packets = self.buf.chunks_exact(self.block_len).map(Box::from).collect();
+
+                return Ok(Some(Block { ts: self.ts, packets }));
+            }
+        };
+
+        if self.swap_bytes {
+            for packet in packets.iter_mut() {
+                for word in packet.chunks_exact_mut(2) {
+                    word.swap(0, 1);
+                }
+            }
+        }
+
+        Ok(Some(Block { ts, packets }))
+    }
+}
+
+/// Split the data of a packet with variable length blocks into blocks.
+fn split_vbr(data: &[u8]) -> Result<Vec<Box<[u8]>>> {
+    if data.len() < 2 {
+        return decode_error("rm: packet too short");
+    }
+
+    // The block lengths are preceded by their total length in bits.
+    let num_blocks = usize::from(u16::from_be_bytes([data[0], data[1]]) >> 4);
+
+    let mut pos = 2 + 2 * num_blocks;
+
+    if pos > data.len() {
+        return decode_error("rm: packet too short");
+    }
+
+    let mut packets = Vec::with_capacity(num_blocks);
+
+    for i in 0..num_blocks {
+        let len = usize::from(u16::from_be_bytes([data[2 + 2 * i], data[3 + 2 * i]]));
+
+        if pos + len > data.len() {
+            return decode_error("rm: invalid block length");
+        }
+
+        packets.push(Box::from(&data[pos..pos + len]));
+        pos += len;
+    }
+
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::codecs::CodecParameters;
+
+    fn stream(interleaver: Interleaver, sub_packet_h: usize, frame_len: usize) -> AudioStream {
+        AudioStream {
+            params: CodecParameters::new(),
+            interleaver,
+            sub_packet_h,
+            frame_len,
+            coded_frame_len: 2,
+            sub_packet_len: 2,
+            block_len: 2,
+            bytes_per_minute: 0,
+            swap_bytes: false,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn verify_deinterleave_genr() {
+        // 4 rows of 2 sub-packets. The sub-packets of even rows fill the first half of each
+        // column, and those of odd rows the second half.
+        let mut deint = Deinterleaver::new(&stream(Interleaver::Genr, 4, 4));
+
+        assert!(deint.push(&[0, 0, 4, 4], 100, true).unwrap().is_none());
+        assert!(deint.push(&[2, 2, 6, 6], 110, false).unwrap().is_none());
+        assert!(deint.push(&[1, 1, 5, 5], 120, false).unwrap().is_none());
+
+        let block = deint.push(&[3, 3, 7, 7], 130, false).unwrap().unwrap();
+
+        assert_eq!(block.ts, 100);
+        assert_eq!(block.packets.len(), 8);
+
+        for (i, packet) in block.packets.iter().enumerate() {
+            assert_eq!(&packet[..], &[i as u8, i as u8]);
+        }
+
+        // A keyframe restarts the superblock.
+        assert!(deint.push(&[0, 0, 4, 4], 140, false).unwrap().is_none());
+        assert!(deint.push(&[0, 0, 4, 4], 150, true).unwrap().is_none());
+        assert_eq!(deint.row, 1);
+    }
+
+    #[test]
+    fn verify_split_vbr() {
+        let data = [0x00, 0x20, 0x00, 0x02, 0x00, 0x01, 1, 2, 3];
+
+        let packets = split_vbr(&data).unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(&packets[0][..], &[1, 2]);
+        assert_eq!(&packets[1][..], &[3]);
+
+        assert!(split_vbr(&data[..8]).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, Tag};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+use crate::chunks::*;
+use crate::deinterleave::{Block, Deinterleaver};
+
+/// The length of the DATA chunk body preceding the data packets.
+const DATA_CHUNK_HEADER_LEN: u64 = 8;
+
+/// The state of an audio stream.
+struct StreamState {
+    stream_num: u16,
+    deinterleaver: Deinterleaver,
+    /// The last block read. Its duration is unknown until the next block is read.
+    pending: Option<Block>,
+    /// The duration of the stream in milliseconds, or 0 if unknown.
+    duration: u64,
+    /// The duration of the last packet returned.
+    last_dur: u64,
+    index: Vec<IndexEntry>,
+}
+
+impl StreamState {
+    /// Queue the packets of the pending block, which last until `end_ts`, or the end of the
+    /// stream if `None`.
+    fn flush(&mut self, end_ts: Option<u64>, packets: &mut VecDeque<Packet>) {
+        let block = match self.pending.take() {
+            Some(block) => block,
+            None => return,
+        };
+
+        let num_packets = block.packets.len() as u64;
+
+        let block_dur = match end_ts {
+            Some(end_ts) => end_ts.saturating_sub(block.ts),
+            None if self.duration > block.ts => self.duration - block.ts,
+            None => self.last_dur * num_packets,
+        };
+
+        // The packets of a block evenly divide the duration of the block.
+        for (i, buf) in block.packets.into_iter().enumerate() {
+            let i = i as u64;
+            let ts = block.ts + block_dur * i / num_packets;
+            let dur = block.ts + block_dur * (i + 1) / num_packets - ts;
+
+            packets.push_back(Packet::new_from_boxed_slice(
+                u32::from(self.stream_num),
+                ts,
+                dur,
+                buf,
+            ));
+
+            self.last_dur = dur;
+        }
+    }
+
+    /// Add a block, and queue the packets of the pending block.
+    fn push_block(&mut self, block: Block, packets: &mut VecDeque<Packet>) {
+        self.flush(Some(block.ts), packets);
+        self.pending = Some(block);
+    }
+
+    fn reset(&mut self) {
+        self.deinterleaver.reset();
+        self.pending = None;
+    }
+}
+
+/// RealMedia format reader.
+///
+/// `RmReader` implements a demuxer for RealMedia files, and RealAudio files. Only audio streams
+/// are exposed as tracks, with a track ID equal to the stream number. The superblocks of
+/// interleaved streams are deinterleaved, and each packet contains a single codec packet. The
+/// timestamps are in milliseconds.
+pub struct RmReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    streams: Vec<StreamState>,
+    /// The position of the start of the file.
+    file_pos: u64,
+    data_start_pos: u64,
+    data_end_pos: Option<u64>,
+    /// If true, the file is a RealAudio file consisting of a header followed by the audio data.
+    is_ra_file: bool,
+    /// The number of bytes per minute of a RealAudio file, or 0 if unknown.
+    bytes_per_minute: u64,
+    /// If true, the next packet of a RealAudio file starts a superblock.
+    is_ra_keyframe: bool,
+    /// The packets read, but not yet returned.
+    packets: VecDeque<Packet>,
+}
+
+impl QueryDescriptor for RmReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "rm",
+            "RealMedia",
+            &["rm", "rmvb", "ra", "ram"],
+            &["application/vnd.rn-realmedia", "audio/x-pn-realaudio"],
+            &[b".RMF", b".ra\xfd"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl RmReader {
+    /// Read the next data packet, and queue the packets of the blocks it completes. Returns false
+    /// at the end of the data packets.
+    fn read_next_data_packet(&mut self) -> Result<bool> {
+        if self.is_ra_file {
+            return self.read_next_ra_packet();
+        }
+
+        if let Some(data_end_pos) = self.data_end_pos {
+            if self.reader.pos() + 12 > data_end_pos {
+                return Ok(false);
+            }
+        }
+
+        let mut marker = [0; 4];
+        self.reader.read_buf_exact(&mut marker)?;
+
+        // Without a data size, the data packets end at the index.
+        if &marker == b"INDX" {
+            return Ok(false);
+        }
+
+        let version = u16::from_be_bytes([marker[0], marker[1]]);
+        let len = u16::from_be_bytes([marker[2], marker[3]]);
+
+        let stream_num = self.reader.read_be_u16()?;
+        let ts = u64::from(self.reader.read_be_u32()?);
+
+        let (header_len, flags) = match version {
+            0 => {
+                let _packet_group = self.reader.read_u8()?;
+                (12, self.reader.read_u8()?)
+            }
+            1 => {
+                let _asm_rule = self.reader.read_be_u16()?;
+                (13, self.reader.read_u8()?)
+            }
+            _ => return decode_error("rm: invalid data packet version"),
+        };
+
+        let data_len = match len.checked_sub(header_len) {
+            Some(data_len) => usize::from(data_len),
+            None => return decode_error("rm: invalid data packet length"),
+        };
+
+        let stream = match self.streams.iter_mut().find(|s| s.stream_num == stream_num) {
+            Some(stream) => stream,
+            None => {
+                self.reader.ignore_bytes(data_len as u64)?;
+                return Ok(true);
+            }
+        };
+
+        let buf = self.reader.read_boxed_slice_exact(data_len)?;
+
+        if let Some(block) = stream.deinterleaver.push(&buf, ts, flags & 0x2 != 0)? {
+            stream.push_block(block, &mut self.packets);
+        }
+
+        Ok(true)
+    }
+
+    /// Read the next packet of a RealAudio file.
+    fn read_next_ra_packet(&mut self) -> Result<bool> {
+        let stream = &mut self.streams[0];
+
+        let mut buf = vec![0; stream.deinterleaver.row_len()];
+
+        let pos = self.reader.pos() - self.data_start_pos;
+
+        match self.reader.read_buf_exact(&mut buf) {
+            Ok(_) => (),
+            // A partial packet at the end of the file is discarded.
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+
+        // The timestamps are calculated from the position in the audio data.
+        let ts = (pos * 60_000).checked_div(self.bytes_per_minute).unwrap_or(0);
+
+        let is_keyframe = std::mem::take(&mut self.is_ra_keyframe);
+
+        if let Some(block) = stream.deinterleaver.push(&buf, ts, is_keyframe)? {
+            stream.push_block(block, &mut self.packets);
+        }
+
+        Ok(true)
+    }
+
+    /// Get the position of the data packet to start reading from to seek a stream to a timestamp.
+    fn find_data_packet(&self, stream_idx: usize, ts: u64) -> u64 {
+        let stream = &self.streams[stream_idx];
+
+        if self.is_ra_file {
+            // Seek to the start of the superblock containing the timestamp.
+            let superblock_len =
+                (stream.deinterleaver.row_len() * stream.deinterleaver.rows()) as u64;
+
+            let offset = ts * self.bytes_per_minute / 60_000;
+
+            return self.data_start_pos + offset / superblock_len * superblock_len;
+        }
+
+        let idx = stream.index.partition_point(|entry| u64::from(entry.ts) <= ts);
+
+        match idx.checked_sub(1) {
+            Some(idx) => self.file_pos + u64::from(stream.index[idx].offset),
+            None => self.data_start_pos,
+        }
+    }
+
+    /// Get the next packet of a track while seeking.
+    fn next_track_packet(&mut self, track_id: u32) -> Result<Packet> {
+        loop {
+            match self.next_packet() {
+                Ok(packet) if packet.track_id() == track_id => return Ok(packet),
+                Ok(_) => (),
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return seek_error(SeekErrorKind::OutOfRange)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Read the index chunks of a RealMedia file.
+fn read_indexes(
+    reader: &mut MediaSourceStream,
+    file_pos: u64,
+    index_offset: u32,
+    streams: &mut [StreamState],
+) -> Result<()> {
+    let mut offset = index_offset;
+
+    while offset != 0 {
+        reader.seek(SeekFrom::Start(file_pos + u64::from(offset)))?;
+
+        let header = ChunkHeader::read(reader)?;
+
+        if &header.id != b"INDX" {
+            return decode_error("rm: missing index chunk");
+        }
+
+        let index = read_indx(&mut ScopedStream::new(&mut *reader, header.len))?;
+
+        if let Some(stream) = streams.iter_mut().find(|s| s.stream_num == index.stream_num) {
+            stream.index = index.entries;
+        }
+
+        offset = index.next_offset;
+    }
+
+    Ok(())
+}
+
+impl FormatReader for RmReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let file_pos = source.pos();
+
+        let mut marker = [0; 4];
+        source.read_buf_exact(&mut marker)?;
+
+        let mut tags = Vec::new();
+        let mut tracks = Vec::new();
+        let mut streams = Vec::new();
+
+        let mut add_stream = |stream_num: u16, stream: AudioStream, duration: u64| {
+            let mut params = stream.params.clone();
+
+            params.with_time_base(TimeBase::new(1, 1000));
+
+            if duration > 0 {
+                params.with_n_frames(duration);
+            }
+
+            tracks.push(Track::new(u32::from(stream_num), params));
+
+            streams.push(StreamState {
+                stream_num,
+                deinterleaver: Deinterleaver::new(&stream),
+                pending: None,
+                duration,
+                last_dur: 0,
+                index: Vec::new(),
+            });
+
+            stream
+        };
+
+        if &marker == b".ra\xfd" {
+            let stream = read_ra_header(&mut source, true)?;
+
+            let bytes_per_minute = u64::from(stream.bytes_per_minute);
+
+            let stream = add_stream(0, stream, 0);
+
+            let metadata = metadata_from_tags(stream.tags);
+
+            return Ok(RmReader {
+                data_start_pos: source.pos(),
+                reader: source,
+                tracks,
+                cues: Vec::new(),
+                metadata,
+                streams,
+                file_pos,
+                data_end_pos: None,
+                is_ra_file: true,
+                bytes_per_minute,
+                is_ra_keyframe: true,
+                packets: VecDeque::new(),
+            });
+        }
+
+        if &marker != b".RMF" {
+            return unsupported_error("rm: missing file header");
+        }
+
+        // Skip the rest of the file header chunk.
+        let len = u64::from(source.read_be_u32()?);
+        source.ignore_bytes(len.saturating_sub(8))?;
+
+        let mut props = None;
+
+        let data_len = loop {
+            let header = ChunkHeader::read(&mut source)?;
+
+            if &header.id == b"DATA" {
+                break header.len;
+            }
+
+            let mut chunk = ScopedStream::new(&mut source, header.len);
+
+            match &header.id {
+                b"PROP" => props = Some(read_prop(&mut chunk)?),
+                b"CONT" => tags.extend(read_cont(&mut chunk)?),
+                b"MDPR" => {
+                    let mdpr = read_mdpr(&mut chunk)?;
+
+                    match read_type_specific(&mdpr.type_specific)? {
+                        Some(stream) => {
+                            let duration = match mdpr.duration {
+                                0 => props.as_ref().map_or(0, |props| props.duration),
+                                duration => duration,
+                            };
+
+                            let stream = add_stream(mdpr.stream_num, stream, u64::from(duration));
+                            tags.extend(stream.tags);
+                        }
+                        None => debug!("ignoring stream {}", mdpr.stream_num),
+                    }
+                }
+                _ => debug!("skipping chunk {:?}", String::from_utf8_lossy(&header.id)),
+            }
+
+            chunk.ignore()?;
+        };
+
+        if streams.is_empty() {
+            return unsupported_error("rm: no audio streams");
+        }
+
+        let _num_packets = source.read_be_u32()?;
+        let _next_data_offset = source.read_be_u32()?;
+
+        let data_start_pos = source.pos();
+
+        let data_end_pos = if data_len > DATA_CHUNK_HEADER_LEN {
+            Some(data_start_pos + data_len - DATA_CHUNK_HEADER_LEN)
+        }
+        else {
+            None
+        };
+
+        let index_offset = props.map_or(0, |props| props.index_offset);
+
+        if index_offset != 0 && source.is_seekable() {
+            if let Err(err) = read_indexes(&mut source, file_pos, index_offset, &mut streams) {
+                warn!("failed to read index: {}", err);
+            }
+
+            source.seek(SeekFrom::Start(data_start_pos))?;
+        }
+
+        let metadata = metadata_from_tags(tags);
+
+        Ok(RmReader {
+            reader: source,
+            tracks,
+            cues: Vec::new(),
+            metadata,
+            streams,
+            file_pos,
+            data_start_pos,
+            data_end_pos,
+            is_ra_file: false,
+            bytes_per_minute: 0,
+            is_ra_keyframe: false,
+            packets: VecDeque::new(),
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        loop {
+            if let Some(packet) = self.packets.pop_front() {
+                return Ok(packet);
+            }
+
+            if !self.read_next_data_packet()? {
+                // The last block of every stream lasts until the end of the stream.
+                for stream in self.streams.iter_mut() {
+                    stream.flush(None, &mut self.packets);
+                }
+
+                if self.packets.is_empty() {
+                    return end_of_stream_error();
+                }
+            }
+        }
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let (track_id, required_ts) = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, track_id } => (track_id, ts),
+            // Time value given, timestamps are in milliseconds.
+            SeekTo::Time { time, track_id } => {
+                let ts = TimeBase::new(1, 1000).calc_timestamp(time);
+                (track_id.unwrap_or(self.tracks[0].id), ts)
+            }
+        };
+
+        let stream_idx = match self.tracks.iter().position(|track| track.id == track_id) {
+            Some(idx) => idx,
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        let duration = self.streams[stream_idx].duration;
+
+        if duration > 0 && required_ts >= duration {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        if self.reader.is_seekable() {
+            let pos = self.find_data_packet(stream_idx, required_ts);
+
+            self.reader.seek(SeekFrom::Start(pos))?;
+
+            self.packets.clear();
+            self.is_ra_keyframe = true;
+
+            for stream in self.streams.iter_mut() {
+                stream.reset();
+            }
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible.
+            let pending = self.streams[stream_idx].pending.as_ref();
+
+            if pending.map_or(false, |block| block.ts > required_ts) {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        // Read packets until the packet of the track containing the required timestamp.
+        let actual_ts = loop {
+            let packet = self.next_track_packet(track_id)?;
+
+            if packet.ts + packet.dur.max(1) > required_ts {
+                let ts = packet.ts;
+                self.packets.push_front(packet);
+                break ts;
+            }
+        };
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+/// Get a metadata log with a revision of the tags, if any.
+fn metadata_from_tags(tags: Vec<Tag>) -> MetadataLog {
+    let mut metadata: MetadataLog = Default::default();
+
+    if !tags.is_empty() {
+        let mut builder = MetadataBuilder::new();
+
+        for tag in tags {
+            builder.add_tag(tag);
+        }
+
+        metadata.push(builder.metadata());
+    }
+
+    metadata
+}
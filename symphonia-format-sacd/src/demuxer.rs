@@ -0,0 +1,377 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_DSD_MSBF, CODEC_TYPE_DST};
+use symphonia_core::errors::SeekErrorKind;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, Result};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+use crate::sector::read_audio_sector;
+use crate::toc::*;
+
+/// Super Audio CD (SACD) image format reader.
+///
+/// `SacdReader` implements a demuxer for SACD ISO images. The 2-channel area is read if present,
+/// otherwise the multi-channel area is read. Each track of the area is exposed as a track, with a
+/// track ID equal to the track number. Packets are read from the tracks in order, and each packet
+/// contains a single frame of uncompressed DSD audio, or a DST coded frame. The timestamps, and
+/// the sample rate of the codec parameters, are in DSD samples.
+///
+/// The text of each track is exposed as the tags of a cue, starting at the position of the track
+/// within the area.
+pub struct SacdReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    area: AreaToc,
+    /// The position of the first sector of the image.
+    image_pos: u64,
+    /// The number of DSD samples per channel in a frame.
+    frame_dur: u64,
+    next_sector: u32,
+    /// The frame number, and data, of the frame being read.
+    frame: Option<(u32, Vec<u8>)>,
+    /// The frames read in full, but not yet returned.
+    frames: VecDeque<(u32, Box<[u8]>)>,
+    /// Frames preceding this frame number are discarded.
+    min_frame: u32,
+}
+
+impl QueryDescriptor for SacdReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!("sacd", "Super Audio CD Image", &["iso"], &[], &[b"SACDMTOC"])]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+/// Move a reader to the start of a sector of the image.
+fn seek_to_sector(reader: &mut MediaSourceStream, image_pos: u64, sector: u64) -> Result<()> {
+    let pos = image_pos + sector * SECTOR_LEN as u64;
+
+    if reader.is_seekable() {
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+    else if pos >= reader.pos() {
+        reader.ignore_bytes(pos - reader.pos())?;
+    }
+    else {
+        return seek_error(SeekErrorKind::ForwardOnly);
+    }
+
+    Ok(())
+}
+
+/// Read a sector of the image.
+fn read_sector(reader: &mut MediaSourceStream, image_pos: u64, sector: u64) -> Result<Vec<u8>> {
+    seek_to_sector(reader, image_pos, sector)?;
+
+    let mut buf = vec![0; SECTOR_LEN];
+    reader.read_buf_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+impl SacdReader {
+    /// Read the next audio sector, and queue the frames it completes.
+    fn read_next_sector(&mut self) -> Result<()> {
+        let mut buf = [0; SECTOR_LEN];
+        self.reader.read_buf_exact(&mut buf)?;
+
+        self.next_sector += 1;
+
+        let sector = read_audio_sector(&buf)?;
+
+        let mut frames = sector.frames.iter();
+
+        for packet in sector.packets.iter().filter(|packet| packet.is_audio) {
+            if packet.frame_start {
+                self.finish_frame();
+
+                // The number of time codes was verified when reading the sector.
+                let frame_num = *frames.next().unwrap();
+
+                self.frame = Some((frame_num, Vec::new()));
+            }
+
+            // Data that does not belong to a started frame is the end of a frame that preceded a
+            // seek.
+            if let Some((_, data)) = &mut self.frame {
+                data.extend_from_slice(&buf[packet.range.clone()]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queue the frame being read.
+    fn finish_frame(&mut self) {
+        if let Some((frame_num, data)) = self.frame.take() {
+            self.frames.push_back((frame_num, data.into_boxed_slice()));
+        }
+    }
+
+    /// Get the frame number of the first frame starting at, or after, a sector.
+    fn first_frame_from(&mut self, sector: u32) -> Result<Option<u32>> {
+        seek_to_sector(&mut self.reader, self.image_pos, u64::from(sector))?;
+
+        let mut buf = [0; SECTOR_LEN];
+
+        for _ in sector..=self.area.end_sector {
+            self.reader.read_buf_exact(&mut buf)?;
+
+            if let Some(&frame_num) = read_audio_sector(&buf)?.frames.first() {
+                return Ok(Some(frame_num));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the sector in which a frame starts.
+    fn find_frame_sector(&mut self, frame_num: u32) -> Result<u32> {
+        // The frame numbers increase with the sectors, so use a binary search for the last sector
+        // from which the first starting frame does not follow the frame.
+        let mut low = self.area.start_sector;
+        let mut high = self.area.end_sector + 1;
+
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+
+            match self.first_frame_from(mid)? {
+                Some(first_frame_num) if first_frame_num <= frame_num => low = mid,
+                _ => high = mid,
+            }
+        }
+
+        Ok(low)
+    }
+}
+
+impl FormatReader for SacdReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let master_toc_pos = MASTER_TOC_SECTOR * SECTOR_LEN as u64;
+
+        // If the image was probed, the source is positioned at the master TOC, otherwise it is
+        // positioned at the start of the image.
+        let image_pos = source.pos().saturating_sub(master_toc_pos);
+
+        let buf = read_sector(&mut source, image_pos, MASTER_TOC_SECTOR)?;
+        let master = read_master_toc(&buf)?;
+
+        let mut tags = master.tags;
+
+        let buf = read_sector(&mut source, image_pos, MASTER_TEXT_SECTOR)?;
+        read_master_text(&buf, master.charset, &mut tags);
+
+        let toc_sector = match master.stereo_toc_sector.or(master.multi_channel_toc_sector) {
+            Some(sector) => u64::from(sector),
+            None => return decode_error("sacd: no audio area"),
+        };
+
+        // The first sector of the area TOC contains the number of sectors of the TOC.
+        let mut buf = read_sector(&mut source, image_pos, toc_sector)?;
+
+        if !is_area_toc(&buf) {
+            return decode_error("sacd: missing area toc");
+        }
+
+        let toc_len = usize::from(u16::from_be_bytes([buf[10], buf[11]])).max(1);
+
+        buf.resize(toc_len * SECTOR_LEN, 0);
+        source.read_buf_exact(&mut buf[SECTOR_LEN..])?;
+
+        let mut area = read_area_toc(&buf)?;
+
+        let frame_dur = u64::from(area.sample_rate / FRAMES_PER_SECOND);
+
+        let codec = match area.frame_format {
+            FrameFormat::Dsd => CODEC_TYPE_DSD_MSBF,
+            FrameFormat::Dst => CODEC_TYPE_DST,
+        };
+
+        let mut tracks = Vec::new();
+        let mut cues = Vec::new();
+
+        for (i, info) in area.tracks.iter_mut().enumerate() {
+            let mut params = CodecParameters::new();
+
+            params
+                .for_codec(codec)
+                .with_sample_rate(area.sample_rate)
+                .with_time_base(TimeBase::new(1, area.sample_rate))
+                .with_bits_per_sample(1)
+                .with_channels(area.channels)
+                .with_n_frames(u64::from(info.duration) * frame_dur)
+                .with_max_frames_per_packet(frame_dur);
+
+            let track_num = i as u32 + 1;
+
+            tracks.push(Track::new(track_num, params));
+
+            cues.push(Cue {
+                index: track_num,
+                start_ts: u64::from(info.start) * frame_dur,
+                tags: std::mem::take(&mut info.tags),
+                points: Vec::new(),
+            });
+        }
+
+        if tracks.is_empty() {
+            return decode_error("sacd: no tracks");
+        }
+
+        let mut metadata: MetadataLog = Default::default();
+
+        if !tags.is_empty() {
+            let mut builder = MetadataBuilder::new();
+
+            for tag in tags {
+                builder.add_tag(tag);
+            }
+
+            metadata.push(builder.metadata());
+        }
+
+        let start_sector = area.start_sector;
+
+        seek_to_sector(&mut source, image_pos, u64::from(start_sector))?;
+
+        Ok(SacdReader {
+            reader: source,
+            tracks,
+            cues,
+            metadata,
+            area,
+            image_pos,
+            frame_dur,
+            next_sector: start_sector,
+            frame: None,
+            frames: VecDeque::new(),
+            min_frame: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        loop {
+            while let Some((frame_num, buf)) = self.frames.pop_front() {
+                if frame_num < self.min_frame {
+                    continue;
+                }
+
+                // Frames between tracks are discarded.
+                let track = self.area.tracks.iter().position(|info| {
+                    frame_num >= info.start && frame_num - info.start < info.duration
+                });
+
+                if let Some(idx) = track {
+                    let ts = u64::from(frame_num - self.area.tracks[idx].start) * self.frame_dur;
+
+                    return Ok(Packet::new_from_boxed_slice(
+                        idx as u32 + 1,
+                        ts,
+                        self.frame_dur,
+                        buf,
+                    ));
+                }
+            }
+
+            if self.next_sector > self.area.end_sector {
+                // The final frame ends with the audio data.
+                if self.frame.is_none() {
+                    return end_of_stream_error();
+                }
+
+                self.finish_frame();
+            }
+            else {
+                self.read_next_sector()?;
+            }
+        }
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let (track_id, required_ts) = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, track_id } => (track_id, ts),
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, track_id } => {
+                let ts = TimeBase::new(1, self.area.sample_rate).calc_timestamp(time);
+                (track_id.unwrap_or(1), ts)
+            }
+        };
+
+        let (start, duration) = match self.area.tracks.get((track_id as usize).wrapping_sub(1)) {
+            Some(info) => (info.start, info.duration),
+            None => return seek_error(SeekErrorKind::InvalidTrack),
+        };
+
+        debug!("seeking to ts={} of track={}", required_ts, track_id);
+
+        if required_ts >= u64::from(duration) * self.frame_dur {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        let frame_num = start + (required_ts / self.frame_dur) as u32;
+
+        if self.reader.is_seekable() {
+            let sector = self.find_frame_sector(frame_num)?;
+
+            seek_to_sector(&mut self.reader, self.image_pos, u64::from(sector))?;
+
+            self.next_sector = sector;
+            self.frame = None;
+            self.frames.clear();
+        }
+        else {
+            // If the reader is not seekable then only forward seeks are possible. The frames
+            // preceding the frame are read and discarded.
+            let cur_frame = self.frames.front().map(|frame| frame.0);
+            let cur_frame = cur_frame.or_else(|| self.frame.as_ref().map(|frame| frame.0));
+
+            if cur_frame.map_or(false, |cur_frame| frame_num < cur_frame) {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        self.min_frame = frame_num;
+
+        let actual_ts = u64::from(frame_num - start) * self.frame_dur;
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts - actual_ts);
+
+        Ok(SeekedTo { track_id, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
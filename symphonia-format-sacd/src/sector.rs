@@ -0,0 +1,118 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::ops::Range;
+
+use symphonia_core::errors::{decode_error, Result};
+
+use crate::toc::{read_time_code, SECTOR_LEN};
+
+/// The data type of an audio packet.
+const DATA_TYPE_AUDIO: u8 = 2;
+
+/// A packet of an audio sector.
+pub struct AudioPacket {
+    /// If true, the packet is the first packet of a frame.
+    pub frame_start: bool,
+    /// If true, the packet contains audio data, otherwise it contains supplementary data or
+    /// padding.
+    pub is_audio: bool,
+    /// The range of the sector containing the data of the packet.
+    pub range: Range<usize>,
+}
+
+/// An audio sector.
+pub struct AudioSector {
+    pub packets: Vec<AudioPacket>,
+    /// The frame numbers of the frames starting in the sector.
+    pub frames: Vec<u32>,
+}
+
+/// Read an audio sector.
+pub fn read_audio_sector(buf: &[u8]) -> Result<AudioSector> {
+    debug_assert!(buf.len() == SECTOR_LEN);
+
+    let header = buf[0];
+
+    let is_dst = header & 0x80 != 0;
+    let num_frames = usize::from((header >> 3) & 0x7);
+    let num_packets = usize::from(header & 0x7);
+
+    let mut pos = 1;
+
+    let mut packet_infos = Vec::with_capacity(num_packets);
+
+    for _ in 0..num_packets {
+        let info = u16::from_be_bytes([buf[pos], buf[pos + 1]]);
+        packet_infos.push(info);
+        pos += 2;
+    }
+
+    // Each frame starting in the sector has a time code. The time codes of DST coded frames are
+    // followed by the number of sectors of the frame.
+    let mut frames = Vec::with_capacity(num_frames);
+
+    for _ in 0..num_frames {
+        frames.push(read_time_code(&buf[pos..]));
+        pos += if is_dst { 4 } else { 3 };
+    }
+
+    let mut packets = Vec::with_capacity(num_packets);
+
+    for info in packet_infos {
+        let len = usize::from(info & 0x7ff);
+
+        if pos + len > SECTOR_LEN {
+            return decode_error("sacd: invalid audio packet length");
+        }
+
+        packets.push(AudioPacket {
+            frame_start: info & 0x8000 != 0,
+            is_audio: (info >> 11) & 0x7 == u16::from(DATA_TYPE_AUDIO),
+            range: pos..pos + len,
+        });
+
+        pos += len;
+    }
+
+    if packets.iter().filter(|packet| packet.frame_start && packet.is_audio).count() > frames.len()
+    {
+        return decode_error("sacd: missing frame time code");
+    }
+
+    Ok(AudioSector { packets, frames })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_audio_sector() {
+        let mut buf = vec![0; SECTOR_LEN];
+
+        // One DSD frame start, and two packets: the end of the previous frame, followed by the
+        // start of the next frame at 00:02:10.
+        buf[0] = (1 << 3) | 2;
+        buf[1..3].copy_from_slice(&(0x1000u16 | 100).to_be_bytes());
+        buf[3..5].copy_from_slice(&(0x9000u16 | 1000).to_be_bytes());
+        buf[5..8].copy_from_slice(&[0, 2, 10]);
+
+        let sector = read_audio_sector(&buf).unwrap();
+
+        assert_eq!(sector.frames, [160]);
+        assert_eq!(sector.packets.len(), 2);
+        assert!(!sector.packets[0].frame_start && sector.packets[0].is_audio);
+        assert_eq!(sector.packets[0].range, 8..108);
+        assert!(sector.packets[1].frame_start && sector.packets[1].is_audio);
+        assert_eq!(sector.packets[1].range, 108..1108);
+
+        // Packets may not extend past the end of the sector.
+        buf[3..5].copy_from_slice(&(0x9000u16 | 2000).to_be_bytes());
+        assert!(read_audio_sector(&buf).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+/// The length of a sector in bytes.
+pub const SECTOR_LEN: usize = 2048;
+
+/// The sector of the master TOC.
+pub const MASTER_TOC_SECTOR: u64 = 510;
+
+/// The sector of the first master text.
+pub const MASTER_TEXT_SECTOR: u64 = 511;
+
+/// The number of frames per second.
+pub const FRAMES_PER_SECOND: u32 = 75;
+
+fn be_u16(buf: &[u8], pos: usize) -> u16 {
+    u16::from_be_bytes([buf[pos], buf[pos + 1]])
+}
+
+fn be_u32(buf: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]])
+}
+
+/// Get the frame number of a time code of minutes, seconds, and frames.
+pub fn read_time_code(buf: &[u8]) -> u32 {
+    (u32::from(buf[0]) * 60 + u32::from(buf[1])) * FRAMES_PER_SECOND + u32::from(buf[2])
+}
+
+/// Read a null-terminated text at a position of a buffer. Texts of the ISO 646 and ISO 8859-1
+/// character sets are decoded exactly, all others are assumed to be UTF-8.
+fn read_text(buf: &[u8], pos: usize, charset: u8) -> Option<String> {
+    let text = buf.get(pos..)?;
+    let text = &text[..text.iter().position(|&b| b == 0).unwrap_or(text.len())];
+
+    if text.is_empty() {
+        return None;
+    }
+
+    match charset {
+        1 | 2 => Some(text.iter().map(|&b| char::from(b)).collect()),
+        _ => Some(String::from_utf8_lossy(text).into_owned()),
+    }
+}
+
+/// The master TOC of a disc.
+pub struct MasterToc {
+    /// The first sector of the 2-channel area TOC, if present.
+    pub stereo_toc_sector: Option<u32>,
+    /// The first sector of the multi-channel area TOC, if present.
+    pub multi_channel_toc_sector: Option<u32>,
+    /// The character set of the first text channel.
+    pub charset: u8,
+    pub tags: Vec<Tag>,
+}
+
+/// Read the master TOC sector.
+pub fn read_master_toc(buf: &[u8]) -> Result<MasterToc> {
+    if &buf[0..8] != b"SACDMTOC" {
+        return unsupported_error("sacd: missing master toc");
+    }
+
+    let mut tags = Vec::new();
+
+    let set_size = be_u16(buf, 16);
+    let seq_num = be_u16(buf, 18);
+
+    if set_size > 1 {
+        let disc = Value::from(u32::from(seq_num));
+        let total = Value::from(u32::from(set_size));
+
+        tags.push(Tag::new(Some(StandardTagKey::DiscNumber), "ALBUM_SEQUENCE_NUMBER", disc));
+        tags.push(Tag::new(Some(StandardTagKey::DiscTotal), "ALBUM_SET_SIZE", total));
+    }
+
+    let year = be_u16(buf, 120);
+
+    if year != 0 {
+        let date = format!("{:04}-{:02}-{:02}", year, buf[122], buf[123]);
+        tags.push(Tag::new(Some(StandardTagKey::Date), "DISC_DATE", Value::from(date)));
+    }
+
+    let toc_sector = |pos| match be_u32(buf, pos) {
+        0 => None,
+        sector => Some(sector),
+    };
+
+    Ok(MasterToc {
+        stereo_toc_sector: toc_sector(64),
+        multi_channel_toc_sector: toc_sector(72),
+        charset: buf[138],
+        tags,
+    })
+}
+
+/// Read the master text sector, and append its tags.
+pub fn read_master_text(buf: &[u8], charset: u8, tags: &mut Vec<Tag>) {
+    if &buf[0..8] != b"SACDText" {
+        return;
+    }
+
+    // The position of each text, followed by the key it is mapped to.
+    let fields = [
+        (16, Some(StandardTagKey::Album), "ALBUM_TITLE"),
+        (18, Some(StandardTagKey::AlbumArtist), "ALBUM_ARTIST"),
+        (20, Some(StandardTagKey::Label), "ALBUM_PUBLISHER"),
+        (22, Some(StandardTagKey::Copyright), "ALBUM_COPYRIGHT"),
+        (32, Some(StandardTagKey::DiscSubtitle), "DISC_TITLE"),
+        (34, Some(StandardTagKey::Artist), "DISC_ARTIST"),
+        (36, None, "DISC_PUBLISHER"),
+        (38, None, "DISC_COPYRIGHT"),
+    ];
+
+    for (pos, std_key, key) in fields {
+        let text_pos = usize::from(be_u16(buf, pos));
+
+        if text_pos == 0 {
+            continue;
+        }
+
+        if let Some(text) = read_text(buf, text_pos, charset) {
+            tags.push(Tag::new(std_key, key, Value::from(text)));
+        }
+    }
+}
+
+/// The frame format of an area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// DST coded frames.
+    Dst,
+    /// Uncompressed DSD frames.
+    Dsd,
+}
+
+/// A track of an area.
+pub struct TrackInfo {
+    /// The frame number of the start of the track.
+    pub start: u32,
+    /// The duration of the track in frames.
+    pub duration: u32,
+    pub tags: Vec<Tag>,
+}
+
+/// The TOC of a 2-channel or multi-channel area.
+pub struct AreaToc {
+    pub sample_rate: u32,
+    pub frame_format: FrameFormat,
+    pub channels: Channels,
+    /// The first sector of the audio data.
+    pub start_sector: u32,
+    /// The last sector of the audio data.
+    pub end_sector: u32,
+    pub tracks: Vec<TrackInfo>,
+}
+
+/// Get the channels of an area from its channel count.
+fn read_channels(num_channels: u8) -> Result<Channels> {
+    let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    let rear = Channels::REAR_LEFT | Channels::REAR_RIGHT;
+
+    let channels = match num_channels {
+        2 => stereo,
+        3 => stereo | Channels::FRONT_CENTRE,
+        4 => stereo | rear,
+        5 => stereo | Channels::FRONT_CENTRE | rear,
+        6 => stereo | Channels::FRONT_CENTRE | Channels::LFE1 | rear,
+        _ => return decode_error("sacd: invalid channel count"),
+    };
+
+    Ok(channels)
+}
+
+/// Read the track text of a track at a position of the track text sectors.
+fn read_track_text(buf: &[u8], pos: usize, charset: u8) -> Vec<Tag> {
+    let mut tags = Vec::new();
+
+    let num_items = match buf.get(pos) {
+        Some(&num_items) => num_items,
+        None => return tags,
+    };
+
+    let mut pos = pos + 4;
+
+    for _ in 0..num_items {
+        let text_type = match buf.get(pos) {
+            Some(&text_type) => text_type,
+            None => break,
+        };
+
+        // The text type is followed by a padding byte.
+        pos += 2;
+
+        let (std_key, key) = match text_type {
+            0x01 => (Some(StandardTagKey::TrackTitle), "TITLE"),
+            0x02 => (Some(StandardTagKey::Artist), "PERFORMER"),
+            0x03 => (Some(StandardTagKey::Lyricist), "SONGWRITER"),
+            0x04 => (Some(StandardTagKey::Composer), "COMPOSER"),
+            0x05 => (Some(StandardTagKey::Arranger), "ARRANGER"),
+            0x06 => (Some(StandardTagKey::Comment), "MESSAGE"),
+            0x07 => (None, "EXTRA_MESSAGE"),
+            _ => (None, ""),
+        };
+
+        if let Some(text) = read_text(buf, pos, charset) {
+            if !key.is_empty() {
+                tags.push(Tag::new(std_key, key, Value::from(text)));
+            }
+        }
+
+        // Skip the text, and the null bytes that pad it.
+        while buf.get(pos).map_or(false, |&b| b != 0) {
+            pos += 1;
+        }
+
+        while buf.get(pos) == Some(&0) {
+            pos += 1;
+        }
+    }
+
+    tags
+}
+
+/// Returns true if the sector is the first sector of an area TOC.
+pub fn is_area_toc(buf: &[u8]) -> bool {
+    &buf[0..8] == b"TWOCHTOC" || &buf[0..8] == b"MULCHTOC"
+}
+
+/// Read all the sectors of an area TOC.
+pub fn read_area_toc(buf: &[u8]) -> Result<AreaToc> {
+    if !is_area_toc(buf) {
+        return decode_error("sacd: missing area toc");
+    }
+
+    // Only a sample rate of 64 times 44.1 kHz is defined.
+    let sample_rate = match buf[20] {
+        4 => 2_822_400,
+        _ => return unsupported_error("sacd: unsupported sample rate"),
+    };
+
+    let frame_format = match buf[21] & 0xf {
+        0 => FrameFormat::Dst,
+        2 | 3 => FrameFormat::Dsd,
+        _ => return unsupported_error("sacd: unsupported frame format"),
+    };
+
+    let channels = read_channels(buf[32])?;
+
+    let num_tracks = usize::from(buf[69]);
+    let start_sector = be_u32(buf, 72);
+    let end_sector = be_u32(buf, 76);
+
+    if end_sector < start_sector {
+        return decode_error("sacd: invalid audio data sectors");
+    }
+
+    let charset = buf[90];
+
+    let mut tracks: Vec<TrackInfo> =
+        (0..num_tracks).map(|_| TrackInfo { start: 0, duration: 0, tags: Vec::new() }).collect();
+
+    let mut has_times = false;
+    let mut has_text = false;
+
+    // The remaining sectors of the TOC are identified by their signature.
+    for start in (SECTOR_LEN..buf.len()).step_by(SECTOR_LEN) {
+        let sector = &buf[start..start + SECTOR_LEN];
+
+        match &sector[0..8] {
+            b"SACDTRL2" if !has_times => {
+                for (i, track) in tracks.iter_mut().enumerate() {
+                    track.start = read_time_code(&sector[8 + 4 * i..]);
+                    track.duration = read_time_code(&sector[1028 + 4 * i..]);
+                }
+                has_times = true;
+            }
+            b"SACDTTxt" if !has_text => {
+                // The text of a track may continue into the following sectors.
+                for (i, track) in tracks.iter_mut().enumerate() {
+                    let pos = usize::from(be_u16(sector, 8 + 2 * i));
+
+                    if pos != 0 {
+                        track.tags = read_track_text(&buf[start..], pos, charset);
+                    }
+                }
+                has_text = true;
+            }
+            _ => (),
+        }
+    }
+
+    if !has_times {
+        return decode_error("sacd: missing track times");
+    }
+
+    Ok(AreaToc { sample_rate, frame_format, channels, start_sector, end_sector, tracks })
+}
@@ -0,0 +1,709 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{self, Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::*;
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, StandardTagKey, Tag, Value};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::{debug, warn};
+
+/// The signature at the start of the file header.
+const VOC_SIGNATURE: &[u8; 20] = b"Creative Voice File\x1a";
+
+/// The length of the file header.
+const HEADER_LEN: u16 = 26;
+
+/// The maximum number of frames in a packet.
+const MAX_FRAMES_PER_PACKET: u64 = 1152;
+
+/// The block types.
+const BLOCK_TERMINATOR: u8 = 0;
+const BLOCK_SOUND_DATA: u8 = 1;
+const BLOCK_SOUND_CONTINUE: u8 = 2;
+const BLOCK_SILENCE: u8 = 3;
+const BLOCK_MARKER: u8 = 4;
+const BLOCK_TEXT: u8 = 5;
+const BLOCK_REPEAT_START: u8 = 6;
+const BLOCK_REPEAT_END: u8 = 7;
+const BLOCK_EXTENDED: u8 = 8;
+const BLOCK_NEW_SOUND_DATA: u8 = 9;
+
+/// The codec identifiers.
+const CODEC_PCM_U8: u16 = 0;
+const CODEC_ADPCM_4: u16 = 1;
+const CODEC_ADPCM_3: u16 = 2;
+const CODEC_ADPCM_2: u16 = 3;
+const CODEC_PCM_S16LE: u16 = 4;
+const CODEC_ALAW: u16 = 6;
+const CODEC_MULAW: u16 = 7;
+
+/// The format of the audio data of a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SoundFormat {
+    sample_rate: u32,
+    num_channels: u32,
+    codec: u16,
+}
+
+impl SoundFormat {
+    fn is_adpcm(&self) -> bool {
+        matches!(self.codec, CODEC_ADPCM_4 | CODEC_ADPCM_3 | CODEC_ADPCM_2)
+    }
+
+    /// Get the number of ADPCM samples coded in a byte.
+    fn samples_per_byte(&self) -> u64 {
+        match self.codec {
+            CODEC_ADPCM_4 => 2,
+            CODEC_ADPCM_3 => 3,
+            _ => 4,
+        }
+    }
+
+    /// Get the length of a PCM frame in bytes.
+    fn frame_len(&self) -> u64 {
+        let sample_len = if self.codec == CODEC_PCM_S16LE { 2 } else { 1 };
+        sample_len * u64::from(self.num_channels)
+    }
+
+    /// Get the maximum length of a packet, excluding the ADPCM reference bytes.
+    fn max_packet_len(&self) -> u64 {
+        if self.is_adpcm() {
+            MAX_FRAMES_PER_PACKET * u64::from(self.num_channels) / self.samples_per_byte()
+        }
+        else {
+            MAX_FRAMES_PER_PACKET * self.frame_len()
+        }
+    }
+
+    /// Get the number of frames coded in `len` bytes of audio data. The audio data of an ADPCM
+    /// sound starts with a reference byte per channel.
+    fn num_frames(&self, len: u64, has_reference: bool) -> u64 {
+        let num_channels = u64::from(self.num_channels);
+
+        if !self.is_adpcm() {
+            len / self.frame_len()
+        }
+        else if !has_reference {
+            len * self.samples_per_byte() / num_channels
+        }
+        else if len >= num_channels {
+            1 + (len - num_channels) * self.samples_per_byte() / num_channels
+        }
+        else {
+            0
+        }
+    }
+
+    /// Get the byte value of a silent sample.
+    fn silent_sample(&self) -> u8 {
+        match self.codec {
+            CODEC_PCM_U8 => 0x80,
+            CODEC_ALAW => 0xd5,
+            CODEC_MULAW => 0xff,
+            _ => 0,
+        }
+    }
+
+    /// Get the codec parameters of the audio data.
+    fn codec_params(&self) -> Result<CodecParameters> {
+        let (codec, bits_per_sample) = match self.codec {
+            CODEC_PCM_U8 => (CODEC_TYPE_PCM_U8, Some(8)),
+            CODEC_ADPCM_4 => (CODEC_TYPE_ADPCM_SBPRO_4, None),
+            CODEC_ADPCM_3 => (CODEC_TYPE_ADPCM_SBPRO_3, None),
+            CODEC_ADPCM_2 => (CODEC_TYPE_ADPCM_SBPRO_2, None),
+            CODEC_PCM_S16LE => (CODEC_TYPE_PCM_S16LE, Some(16)),
+            CODEC_ALAW => (CODEC_TYPE_PCM_ALAW, None),
+            CODEC_MULAW => (CODEC_TYPE_PCM_MULAW, None),
+            _ => return unsupported_error("voc: unsupported codec"),
+        };
+
+        let channels = match self.num_channels {
+            1 => Channels::FRONT_LEFT,
+            2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            _ => return unsupported_error("voc: unsupported channel count"),
+        };
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(codec)
+            .with_sample_rate(self.sample_rate)
+            .with_time_base(TimeBase::new(1, self.sample_rate))
+            .with_channels(channels)
+            .with_frames_per_block(1);
+
+        if let Some(bits_per_sample) = bits_per_sample {
+            params
+                .with_bits_per_sample(bits_per_sample)
+                .with_bits_per_coded_sample(bits_per_sample);
+        }
+
+        // The first packet of an ADPCM sound is one frame longer due to the reference bytes.
+        if self.is_adpcm() {
+            params.with_max_frames_per_packet(MAX_FRAMES_PER_PACKET + 1);
+        }
+        else {
+            params.with_max_frames_per_packet(MAX_FRAMES_PER_PACKET);
+        }
+
+        Ok(params)
+    }
+}
+
+/// A block of a VOC file.
+enum Block {
+    /// The start of a new sound. The audio data follows.
+    Sound { format: SoundFormat, len: u64 },
+    /// A continuation of the previous sound. The audio data follows.
+    Continuation { len: u64 },
+    /// A period of silence.
+    Silence { frames: u64, sample_rate: u32 },
+    /// A marker.
+    Marker(u16),
+    /// A text annotation.
+    Text(String),
+    /// A block that is ignored.
+    Other,
+    /// The end of the file.
+    Terminator,
+}
+
+/// Get the sample rate from the time constant of a sound data or silence block.
+fn sample_rate_from_divisor(divisor: u8) -> u32 {
+    1_000_000 / (256 - u32::from(divisor))
+}
+
+/// Read a block header and the fields of the block. The reader is positioned at the audio data of
+/// sound blocks, or after the block otherwise.
+fn read_block<B: ReadBytes>(reader: &mut B) -> Result<Block> {
+    // A file may end without a terminator block.
+    let block_type = match reader.read_u8() {
+        Ok(block_type) => block_type,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(Block::Terminator),
+        Err(err) => return Err(err.into()),
+    };
+
+    if block_type == BLOCK_TERMINATOR {
+        return Ok(Block::Terminator);
+    }
+
+    let len = u64::from(reader.read_u24()?);
+
+    let block = match block_type {
+        BLOCK_SOUND_DATA if len >= 2 => {
+            let divisor = reader.read_u8()?;
+            let codec = reader.read_u8()?;
+
+            let format = SoundFormat {
+                sample_rate: sample_rate_from_divisor(divisor),
+                num_channels: 1,
+                codec: u16::from(codec),
+            };
+
+            Block::Sound { format, len: len - 2 }
+        }
+        BLOCK_SOUND_CONTINUE => Block::Continuation { len },
+        BLOCK_SILENCE if len == 3 => {
+            let frames = u64::from(reader.read_u16()?) + 1;
+            let sample_rate = sample_rate_from_divisor(reader.read_u8()?);
+
+            Block::Silence { frames, sample_rate }
+        }
+        BLOCK_MARKER if len == 2 => Block::Marker(reader.read_u16()?),
+        BLOCK_TEXT => {
+            let buf = reader.read_boxed_slice_exact(len as usize)?;
+
+            let text = buf.split(|&b| b == 0).next().unwrap_or_default();
+
+            Block::Text(String::from_utf8_lossy(text).into_owned())
+        }
+        BLOCK_EXTENDED if len == 4 => {
+            // An extended block overrides the format of the sound data block that follows it.
+            let time_constant = u32::from(reader.read_u16()?);
+            let codec = u16::from(reader.read_u8()?);
+            let num_channels = u32::from(reader.read_u8()?) + 1;
+
+            let block = read_block(reader)?;
+
+            match block {
+                Block::Sound { len, .. } => {
+                    let sample_rate = 256_000_000 / (num_channels * (65536 - time_constant));
+                    Block::Sound { format: SoundFormat { sample_rate, num_channels, codec }, len }
+                }
+                _ => block,
+            }
+        }
+        BLOCK_NEW_SOUND_DATA if len >= 12 => {
+            let sample_rate = reader.read_u32()?;
+            let _bits_per_sample = reader.read_u8()?;
+            let num_channels = u32::from(reader.read_u8()?);
+            let codec = reader.read_u16()?;
+            reader.ignore_bytes(4)?;
+
+            if sample_rate == 0 {
+                return decode_error("voc: invalid sample rate");
+            }
+
+            Block::Sound { format: SoundFormat { sample_rate, num_channels, codec }, len: len - 12 }
+        }
+        BLOCK_SOUND_DATA | BLOCK_SILENCE | BLOCK_MARKER | BLOCK_EXTENDED | BLOCK_NEW_SOUND_DATA => {
+            return decode_error("voc: invalid block length");
+        }
+        _ => {
+            // Loops are played once. Unknown blocks are skipped.
+            if block_type != BLOCK_REPEAT_START && block_type != BLOCK_REPEAT_END {
+                debug!("skipping unknown block type {}", block_type);
+            }
+
+            reader.ignore_bytes(len)?;
+
+            Block::Other
+        }
+    };
+
+    Ok(block)
+}
+
+/// The position within the blocks of a VOC file.
+enum State {
+    /// Between blocks.
+    Idle,
+    /// Within the audio data of a sound block that ends at `end_pos`. If `has_reference` is set,
+    /// the audio data starts with ADPCM reference bytes.
+    Sound { end_pos: u64, has_reference: bool },
+    /// Within a period of silence.
+    Silence { frames: u64 },
+}
+
+/// A block that playback may start from.
+#[derive(Clone, Copy)]
+struct SeekPoint {
+    ts: u64,
+    pos: u64,
+}
+
+/// Creative Voice (VOC) format reader.
+///
+/// `VocReader` implements a demuxer for VOC files containing 8-bit or 16-bit PCM, µ-law, A-law, or
+/// Creative 8-bit ADPCM audio. The format of the first sound determines the format of the track,
+/// and sounds of any other format are skipped. Markers are exposed as cues, and text blocks as
+/// metadata. Loops are played once.
+///
+/// Seeking, and cues beyond the first sound, require a seekable source.
+pub struct VocReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    format: SoundFormat,
+    state: State,
+    /// If set, the continuation blocks of a skipped sound are skipped.
+    skip_continuation: bool,
+    /// If set, markers and text blocks are collected as they are read.
+    collect: bool,
+    tags: Vec<Tag>,
+    seek_points: Vec<SeekPoint>,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for VocReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "voc",
+            "Creative Voice",
+            &["voc"],
+            &["audio/x-voc"],
+            &[b"Creative Voice F"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl VocReader {
+    /// Read the next block, and update the state if it contains audio.
+    fn read_next_block(&mut self) -> Result<bool> {
+        let block = read_block(&mut self.reader)?;
+
+        match block {
+            Block::Sound { format, len } => {
+                let end_pos = self.reader.pos() + len;
+
+                if format == self.format {
+                    self.state = State::Sound { end_pos, has_reference: format.is_adpcm() };
+                    self.skip_continuation = false;
+                }
+                else {
+                    warn!("skipping sound with a different format {:?}", format);
+                    self.reader.ignore_bytes(len)?;
+                    self.skip_continuation = true;
+                }
+            }
+            Block::Continuation { len } => {
+                if self.skip_continuation {
+                    self.reader.ignore_bytes(len)?;
+                }
+                else {
+                    let end_pos = self.reader.pos() + len;
+                    self.state = State::Sound { end_pos, has_reference: false };
+                }
+            }
+            Block::Silence { frames, sample_rate } => {
+                // Silence can only be synthesized for PCM codecs.
+                if !self.format.is_adpcm() {
+                    let frames =
+                        frames * u64::from(self.format.sample_rate) / u64::from(sample_rate);
+                    self.state = State::Silence { frames };
+                }
+            }
+            Block::Marker(index) => {
+                if self.collect {
+                    let cue = Cue {
+                        index: u32::from(index),
+                        start_ts: self.next_packet_ts,
+                        tags: Vec::new(),
+                        points: Vec::new(),
+                    };
+                    self.cues.push(cue);
+                }
+            }
+            Block::Text(text) => {
+                if self.collect {
+                    self.tags.push(Tag::new(Some(StandardTagKey::Comment), "", Value::from(text)));
+                }
+            }
+            Block::Other => (),
+            Block::Terminator => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Push the collected text blocks as metadata.
+    fn push_tags(&mut self) {
+        if !self.tags.is_empty() {
+            let mut builder = MetadataBuilder::new();
+
+            for tag in self.tags.drain(..) {
+                builder.add_tag(tag);
+            }
+
+            self.metadata.push(builder.metadata());
+        }
+    }
+
+    /// Read all blocks to find the markers, text blocks, seek points, and the total number of
+    /// frames.
+    fn scan(&mut self) -> Result<()> {
+        loop {
+            let pos = self.reader.pos();
+
+            if !self.read_next_block()? {
+                break;
+            }
+
+            let ts = self.next_packet_ts;
+
+            match self.state {
+                State::Sound { end_pos, has_reference } => {
+                    // Decoding of ADPCM audio can only start at a reference byte.
+                    if has_reference || !self.format.is_adpcm() {
+                        self.seek_points.push(SeekPoint { ts, pos });
+                    }
+
+                    let len = end_pos - self.reader.pos();
+                    self.next_packet_ts += self.format.num_frames(len, has_reference);
+                    self.reader.ignore_bytes(len)?;
+                }
+                State::Silence { frames } => {
+                    self.seek_points.push(SeekPoint { ts, pos });
+                    self.next_packet_ts += frames;
+                }
+                State::Idle => (),
+            }
+
+            self.state = State::Idle;
+        }
+
+        Ok(())
+    }
+}
+
+impl FormatReader for VocReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut signature = [0; 20];
+        source.read_buf_exact(&mut signature)?;
+
+        if &signature != VOC_SIGNATURE {
+            return unsupported_error("voc: missing voc signature");
+        }
+
+        let header_len = source.read_u16()?;
+        let _version = source.read_u16()?;
+        let _checksum = source.read_u16()?;
+
+        if header_len < HEADER_LEN {
+            return decode_error("voc: invalid header length");
+        }
+
+        source.ignore_bytes(u64::from(header_len - HEADER_LEN))?;
+
+        let first_block_pos = source.pos();
+
+        // The format of the first sound is the format of the track.
+        let mut tags = Vec::new();
+        let mut cues = Vec::new();
+
+        let (format, len) = loop {
+            match read_block(&mut source)? {
+                Block::Sound { format, len } => break (format, len),
+                Block::Continuation { len } => source.ignore_bytes(len)?,
+                Block::Text(text) => {
+                    tags.push(Tag::new(Some(StandardTagKey::Comment), "", Value::from(text)))
+                }
+                Block::Marker(index) => cues.push(Cue {
+                    index: u32::from(index),
+                    start_ts: 0,
+                    tags: Vec::new(),
+                    points: Vec::new(),
+                }),
+                Block::Silence { .. } | Block::Other => (),
+                Block::Terminator => return decode_error("voc: no sound data"),
+            }
+        };
+
+        debug!("sound format {:?}", format);
+
+        let params = format.codec_params()?;
+
+        let mut reader = VocReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            format,
+            state: State::Idle,
+            skip_continuation: false,
+            collect: true,
+            tags: Vec::new(),
+            seek_points: Vec::new(),
+            next_packet_ts: 0,
+        };
+
+        if reader.reader.is_seekable() {
+            // Scan the whole file, then start playback from the first block.
+            reader.reader.seek(SeekFrom::Start(first_block_pos))?;
+            reader.scan()?;
+            reader.reader.seek(SeekFrom::Start(first_block_pos))?;
+
+            reader.tracks[0].codec_params.with_n_frames(reader.next_packet_ts);
+            reader.next_packet_ts = 0;
+            reader.collect = false;
+        }
+        else {
+            // Otherwise, start playback from the first sound. Markers and text blocks are
+            // collected as they are read.
+            let end_pos = reader.reader.pos() + len;
+
+            reader.state = State::Sound { end_pos, has_reference: format.is_adpcm() };
+            reader.tags = tags;
+            reader.cues = cues;
+        }
+
+        reader.push_tags();
+
+        Ok(reader)
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        loop {
+            match self.state {
+                State::Sound { end_pos, has_reference } => {
+                    let pos = self.reader.pos();
+
+                    let mut max_len = self.format.max_packet_len();
+
+                    if has_reference {
+                        max_len += u64::from(self.format.num_channels);
+                    }
+
+                    let len = end_pos.saturating_sub(pos).min(max_len);
+
+                    let dur = self.format.num_frames(len, has_reference);
+
+                    if dur == 0 {
+                        // Skip any trailing bytes of the sound.
+                        self.reader.ignore_bytes(end_pos.saturating_sub(pos))?;
+                        self.state = State::Idle;
+                        continue;
+                    }
+
+                    let buf = self.reader.read_boxed_slice_exact(len as usize)?;
+
+                    self.state = State::Sound { end_pos, has_reference: false };
+
+                    let ts = self.next_packet_ts;
+                    self.next_packet_ts += dur;
+
+                    return Ok(Packet::new_from_boxed_slice(0, ts, dur, buf));
+                }
+                State::Silence { frames } => {
+                    if frames == 0 {
+                        self.state = State::Idle;
+                        continue;
+                    }
+
+                    let dur = frames.min(MAX_FRAMES_PER_PACKET);
+
+                    let len = (dur * self.format.frame_len()) as usize;
+                    let buf = vec![self.format.silent_sample(); len].into_boxed_slice();
+
+                    self.state = State::Silence { frames: frames - dur };
+
+                    let ts = self.next_packet_ts;
+                    self.next_packet_ts += dur;
+
+                    return Ok(Packet::new_from_boxed_slice(0, ts, dur, buf));
+                }
+                State::Idle => {
+                    let has_block = self.read_next_block()?;
+
+                    self.push_tags();
+
+                    if !has_block {
+                        return end_of_stream_error();
+                    }
+                }
+            }
+        }
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        if self.seek_points.is_empty() {
+            return seek_error(SeekErrorKind::Unseekable);
+        }
+
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        debug!("seeking to ts={}", required_ts);
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        // Find the last block that starts before the required timestamp.
+        let idx = self.seek_points.partition_point(|point| point.ts <= required_ts);
+        let SeekPoint { ts, pos } = self.seek_points[idx.saturating_sub(1)];
+
+        self.next_packet_ts = ts;
+        self.state = State::Idle;
+        self.skip_continuation = false;
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        // ADPCM audio must be decoded from the start of the sound. PCM audio can start at any
+        // packet within the block.
+        if !self.format.is_adpcm() && required_ts > ts {
+            self.read_next_block()?;
+
+            let delta = (required_ts - ts) / MAX_FRAMES_PER_PACKET * MAX_FRAMES_PER_PACKET;
+
+            match self.state {
+                State::Sound { end_pos, .. } => {
+                    let pos = self.reader.pos();
+                    let len = (delta * self.format.frame_len()).min(end_pos - pos);
+
+                    self.reader.ignore_bytes(len)?;
+                    self.next_packet_ts += len / self.format.frame_len();
+                }
+                State::Silence { frames } => {
+                    let delta = delta.min(frames);
+
+                    self.state = State::Silence { frames: frames - delta };
+                    self.next_packet_ts += delta;
+                }
+                State::Idle => (),
+            }
+        }
+
+        let actual_ts = self.next_packet_ts;
+
+        debug!("seeked to ts={} (delta={})", actual_ts, required_ts as i64 - actual_ts as i64);
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_block() {
+        // An extended block for 4-bit ADPCM stereo at 8 kHz, followed by a sound data block.
+        let buf = [8, 4, 0, 0, 0x80, 0xc1, 1, 1, 1, 6, 0, 0, 0xd3, 0, 0x80, 0x80, 0x12, 0x34];
+
+        let mut reader = BufReader::new(&buf);
+
+        match read_block(&mut reader).unwrap() {
+            Block::Sound { format, len } => {
+                assert_eq!(format, SoundFormat { sample_rate: 8000, num_channels: 2, codec: 1 });
+                assert_eq!(len, 4);
+                assert_eq!(format.num_frames(len, true), 3);
+            }
+            _ => panic!("expected a sound block"),
+        }
+
+        // A file may end without a terminator.
+        reader.ignore_bytes(4).unwrap();
+        assert!(matches!(read_block(&mut reader).unwrap(), Block::Terminator));
+    }
+}
@@ -115,8 +115,14 @@ impl QueryDescriptor for WavReader {
         ]
     }
 
-    fn score(_context: &[u8]) -> u8 {
-        255
+    fn score(context: &[u8]) -> u8 {
+        // The RIFF marker is shared with other RIFF forms, such as AVI.
+        if context.len() >= 12 && context[8..12] != WAVE_RIFF_FORM {
+            0
+        }
+        else {
+            255
+        }
     }
 }
 
@@ -0,0 +1,596 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! An APEv1 and APEv2 metadata reader.
+//!
+//! APE tags are found at the end, and less commonly the start, of Monkey's Audio, Musepack, TAK,
+//! WavPack, and MP3 files. A tag consists of a list of items, and a footer, optionally preceded by
+//! a header with the same contents as the footer.
+
+use std::collections::HashMap;
+use std::io::{Seek, SeekFrom};
+
+use lazy_static::lazy_static;
+use log::warn;
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+use symphonia_core::meta::{
+    MetadataBuilder, StandardTagKey, StandardVisualKey, Tag, Value, Visual,
+};
+
+/// The APE tag preamble: "APETAGEX" in ASCII.
+const APE_TAG_PREAMBLE: [u8; 8] = *b"APETAGEX";
+
+/// The length of an APE tag header or footer.
+pub const APE_TAG_HEADER_LEN: u64 = 32;
+
+/// The maximum number of items in an APE tag that will be read.
+const APE_TAG_MAX_ITEMS: u32 = 1024;
+
+/// The maximum length of an APE tag (items and footer) that will be read.
+const APE_TAG_MAX_LEN: u32 = 16 * 1024 * 1024;
+
+lazy_static! {
+    static ref APE_TAG_MAP: HashMap<&'static str, StandardTagKey> = {
+        let mut m = HashMap::new();
+        m.insert("acoustid_fingerprint", StandardTagKey::AcoustidFingerprint);
+        m.insert("acoustid_id", StandardTagKey::AcoustidId);
+        m.insert("album artist", StandardTagKey::AlbumArtist);
+        m.insert("album", StandardTagKey::Album);
+        m.insert("albumartist", StandardTagKey::AlbumArtist);
+        m.insert("albumartistsort", StandardTagKey::SortAlbumArtist);
+        m.insert("albumsort", StandardTagKey::SortAlbum);
+        m.insert("arranger", StandardTagKey::Arranger);
+        m.insert("artist", StandardTagKey::Artist);
+        m.insert("artistsort", StandardTagKey::SortArtist);
+        m.insert("asin", StandardTagKey::IdentAsin);
+        m.insert("barcode", StandardTagKey::IdentBarcode);
+        m.insert("bpm", StandardTagKey::Bpm);
+        m.insert("catalog", StandardTagKey::IdentCatalogNumber);
+        m.insert("catalognumber", StandardTagKey::IdentCatalogNumber);
+        m.insert("comment", StandardTagKey::Comment);
+        m.insert("compilation", StandardTagKey::Compilation);
+        m.insert("composer", StandardTagKey::Composer);
+        m.insert("composersort", StandardTagKey::SortComposer);
+        m.insert("conductor", StandardTagKey::Conductor);
+        m.insert("copyright", StandardTagKey::Copyright);
+        m.insert("disc", StandardTagKey::DiscNumber);
+        m.insert("discnumber", StandardTagKey::DiscNumber);
+        m.insert("discsubtitle", StandardTagKey::DiscSubtitle);
+        m.insert("djmixer", StandardTagKey::MixDj);
+        m.insert("ean/upc", StandardTagKey::IdentEanUpn);
+        m.insert("encodedby", StandardTagKey::EncodedBy);
+        m.insert("encoder", StandardTagKey::Encoder);
+        m.insert("engineer", StandardTagKey::Engineer);
+        m.insert("genre", StandardTagKey::Genre);
+        m.insert("grouping", StandardTagKey::ContentGroup);
+        m.insert("isrc", StandardTagKey::IdentIsrc);
+        m.insert("label", StandardTagKey::Label);
+        m.insert("language", StandardTagKey::Language);
+        m.insert("lyricist", StandardTagKey::Lyricist);
+        m.insert("lyrics", StandardTagKey::Lyrics);
+        m.insert("media", StandardTagKey::MediaFormat);
+        m.insert("mixer", StandardTagKey::MixEngineer);
+        m.insert("mood", StandardTagKey::Mood);
+        m.insert("movement", StandardTagKey::MovementNumber);
+        m.insert("movementname", StandardTagKey::MovementName);
+        m.insert("movementtotal", StandardTagKey::MovementTotal);
+        m.insert("musicbrainz_albumartistid", StandardTagKey::MusicBrainzAlbumArtistId);
+        m.insert("musicbrainz_albumid", StandardTagKey::MusicBrainzAlbumId);
+        m.insert("musicbrainz_albumstatus", StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("musicbrainz_albumtype", StandardTagKey::MusicBrainzReleaseType);
+        m.insert("musicbrainz_artistid", StandardTagKey::MusicBrainzArtistId);
+        m.insert("musicbrainz_discid", StandardTagKey::MusicBrainzDiscId);
+        m.insert("musicbrainz_releasegroupid", StandardTagKey::MusicBrainzReleaseGroupId);
+        m.insert("musicbrainz_releasetrackid", StandardTagKey::MusicBrainzReleaseTrackId);
+        m.insert("musicbrainz_trackid", StandardTagKey::MusicBrainzRecordingId);
+        m.insert("musicbrainz_workid", StandardTagKey::MusicBrainzWorkId);
+        m.insert("originaldate", StandardTagKey::OriginalDate);
+        m.insert("performer", StandardTagKey::Performer);
+        m.insert("producer", StandardTagKey::Producer);
+        m.insert("publisher", StandardTagKey::Label);
+        m.insert("record date", StandardTagKey::Date);
+        m.insert("releasecountry", StandardTagKey::ReleaseCountry);
+        m.insert("remixer", StandardTagKey::Remixer);
+        m.insert("replaygain_album_gain", StandardTagKey::ReplayGainAlbumGain);
+        m.insert("replaygain_album_peak", StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("replaygain_track_gain", StandardTagKey::ReplayGainTrackGain);
+        m.insert("replaygain_track_peak", StandardTagKey::ReplayGainTrackPeak);
+        m.insert("script", StandardTagKey::Script);
+        m.insert("subtitle", StandardTagKey::TrackSubtitle);
+        m.insert("title", StandardTagKey::TrackTitle);
+        m.insert("titlesort", StandardTagKey::SortTrackTitle);
+        m.insert("track", StandardTagKey::TrackNumber);
+        m.insert("upc", StandardTagKey::IdentUpc);
+        m.insert("work", StandardTagKey::Work);
+        m.insert("writer", StandardTagKey::Writer);
+        m.insert("year", StandardTagKey::Date);
+        m
+    };
+}
+
+lazy_static! {
+    static ref APE_COVER_ART_MAP: HashMap<&'static str, StandardVisualKey> = {
+        let mut m = HashMap::new();
+        m.insert("cover art (artist)", StandardVisualKey::ArtistPerformer);
+        m.insert("cover art (back)", StandardVisualKey::BackCover);
+        m.insert("cover art (band logo)", StandardVisualKey::BandArtistLogo);
+        m.insert("cover art (band)", StandardVisualKey::BandOrchestra);
+        m.insert("cover art (composer)", StandardVisualKey::Composer);
+        m.insert("cover art (conductor)", StandardVisualKey::Conductor);
+        m.insert("cover art (front)", StandardVisualKey::FrontCover);
+        m.insert("cover art (icon)", StandardVisualKey::FileIcon);
+        m.insert("cover art (illustration)", StandardVisualKey::Illustration);
+        m.insert("cover art (lead artist)", StandardVisualKey::LeadArtistPerformerSoloist);
+        m.insert("cover art (leaflet)", StandardVisualKey::Leaflet);
+        m.insert("cover art (lyricist)", StandardVisualKey::Lyricist);
+        m.insert("cover art (media)", StandardVisualKey::Media);
+        m.insert("cover art (movie scene)", StandardVisualKey::ScreenCapture);
+        m.insert("cover art (other icon)", StandardVisualKey::OtherIcon);
+        m.insert("cover art (performance)", StandardVisualKey::Performance);
+        m.insert("cover art (publisher logo)", StandardVisualKey::PublisherStudioLogo);
+        m.insert("cover art (recording)", StandardVisualKey::RecordingSession);
+        m.insert("cover art (studio)", StandardVisualKey::RecordingLocation);
+        m
+    };
+}
+
+/// The type of value stored in an APE tag item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ItemType {
+    /// UTF-8 text. May contain multiple values seperated by a null character.
+    Text,
+    /// Binary data.
+    Binary,
+    /// A UTF-8 link to an external resource.
+    Locator,
+}
+
+/// An APE tag header or footer.
+#[derive(Copy, Clone, Debug)]
+pub struct ApeTagHeader {
+    /// The tag version. 1000 for APEv1, or 2000 for APEv2.
+    pub version: u32,
+    /// The length of the tag in bytes, including the footer, but excluding the header.
+    pub size: u32,
+    /// The number of items in the tag.
+    pub num_items: u32,
+    /// The global flags of the tag.
+    pub flags: u32,
+}
+
+impl ApeTagHeader {
+    /// Returns true if the tag has a header.
+    pub fn has_header(&self) -> bool {
+        self.flags & 0x8000_0000 != 0
+    }
+
+    /// Returns true if the tag has a footer. All APEv1 tags have a footer.
+    pub fn has_footer(&self) -> bool {
+        self.version == 1000 || self.flags & 0x4000_0000 == 0
+    }
+
+    /// Returns true if the tag is marked as read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.flags & 0x1 != 0
+    }
+
+    /// Returns true if this is the header of the tag, or false if it is the footer.
+    pub fn is_header(&self) -> bool {
+        self.flags & 0x2000_0000 != 0
+    }
+
+    /// The length of the items in the tag.
+    pub fn items_len(&self) -> u32 {
+        self.size - APE_TAG_HEADER_LEN as u32
+    }
+
+    /// The total length of the tag, including the header if present.
+    pub fn tag_len(&self) -> u64 {
+        u64::from(self.size) + if self.has_header() { APE_TAG_HEADER_LEN } else { 0 }
+    }
+}
+
+/// An APE tag item.
+#[derive(Clone, Debug)]
+pub struct ApeItem {
+    /// The key of the item. Keys are case-insensitive ASCII strings.
+    pub key: String,
+    /// The type of value stored in the item.
+    pub item_type: ItemType,
+    /// If true, the item is marked as read-only.
+    pub read_only: bool,
+    /// The value of the item. Text and locator values are UTF-8.
+    pub value: Box<[u8]>,
+}
+
+/// An APE tag.
+#[derive(Clone, Debug)]
+pub struct ApeTag {
+    /// The header, or footer, of the tag.
+    pub header: ApeTagHeader,
+    /// The items of the tag.
+    pub items: Vec<ApeItem>,
+}
+
+impl ApeTag {
+    /// Adds the items of the tag to the metadata builder.
+    pub fn add_to_metadata(&self, metadata: &mut MetadataBuilder) {
+        for item in &self.items {
+            read_item(&item.key, item.item_type, &item.value, metadata);
+        }
+    }
+}
+
+/// Read an APE tag header or footer.
+pub fn read_ape_tag_header<B: ReadBytes>(reader: &mut B) -> Result<ApeTagHeader> {
+    let mut preamble = [0; 8];
+    reader.read_buf_exact(&mut preamble)?;
+
+    if preamble != APE_TAG_PREAMBLE {
+        return unsupported_error("meta (ape): missing ape tag preamble");
+    }
+
+    let version = reader.read_u32()?;
+    let size = reader.read_u32()?;
+    let num_items = reader.read_u32()?;
+    let flags = reader.read_u32()?;
+
+    // Reserved.
+    reader.ignore_bytes(8)?;
+
+    if version != 1000 && version != 2000 {
+        return unsupported_error("meta (ape): unsupported ape tag version");
+    }
+
+    if size < APE_TAG_HEADER_LEN as u32 || size > APE_TAG_MAX_LEN {
+        return decode_error("meta (ape): invalid ape tag size");
+    }
+
+    if num_items > APE_TAG_MAX_ITEMS {
+        return decode_error("meta (ape): too many ape tag items");
+    }
+
+    Ok(ApeTagHeader { version, size, num_items, flags })
+}
+
+/// Reads an APE tag footer ending at the given position of the stream, if there is one. The
+/// footer is only returned if the entire tag is within the stream, in which case the reader is
+/// positioned at the first item of the tag.
+pub fn read_ape_tag_footer<R: ReadBytes + Seek>(
+    reader: &mut R,
+    end: u64,
+) -> Result<Option<ApeTagHeader>> {
+    if end < APE_TAG_HEADER_LEN {
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(end - APE_TAG_HEADER_LEN))?;
+
+    let footer = match read_ape_tag_header(reader) {
+        Ok(footer) if !footer.is_header() => footer,
+        _ => return Ok(None),
+    };
+
+    if footer.tag_len() > end {
+        warn!("meta (ape): ape tag exceeds the length of the stream");
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(end - u64::from(footer.size)))?;
+
+    Ok(Some(footer))
+}
+
+/// Reads an APE tag from a buffer containing the entire tag. The tag may either start with a
+/// header, or end with a footer.
+pub fn read_ape_tag(buf: &[u8]) -> Result<ApeTag> {
+    let header_len = APE_TAG_HEADER_LEN as usize;
+
+    if buf.len() < header_len {
+        return unsupported_error("meta (ape): missing ape tag preamble");
+    }
+
+    // Prefer the header, if present, since the tag may be followed by other data.
+    let (header, items) = if buf[..8] == APE_TAG_PREAMBLE {
+        let header = read_ape_tag_header(&mut BufReader::new(&buf[..header_len]))?;
+
+        if !header.is_header() {
+            return decode_error("meta (ape): expected an ape tag header");
+        }
+
+        let items_end = header_len + header.items_len() as usize;
+
+        if items_end > buf.len() {
+            return decode_error("meta (ape): ape tag exceeds the length of the buffer");
+        }
+
+        (header, &buf[header_len..items_end])
+    }
+    else {
+        let footer = read_ape_tag_header(&mut BufReader::new(&buf[buf.len() - header_len..]))?;
+
+        if footer.is_header() {
+            return decode_error("meta (ape): expected an ape tag footer");
+        }
+
+        if footer.size as usize > buf.len() {
+            return decode_error("meta (ape): ape tag exceeds the length of the buffer");
+        }
+
+        (footer, &buf[buf.len() - footer.size as usize..buf.len() - header_len])
+    };
+
+    Ok(ApeTag { header, items: parse_ape_tag_items(items, &header)? })
+}
+
+/// Parses the items of an APE tag. Items with an invalid key, or a reserved type, are skipped.
+pub fn parse_ape_tag_items(buf: &[u8], header: &ApeTagHeader) -> Result<Vec<ApeItem>> {
+    let mut items = buf;
+    let mut parsed = Vec::new();
+
+    for _ in 0..header.num_items {
+        if items.len() < 8 {
+            return decode_error("meta (ape): item header exceeds tag length");
+        }
+
+        let value_len = u32::from_le_bytes([items[0], items[1], items[2], items[3]]) as usize;
+        let item_flags = u32::from_le_bytes([items[4], items[5], items[6], items[7]]);
+
+        items = &items[8..];
+
+        // The key is a null-terminated ASCII string.
+        let key_len = match items.iter().position(|&b| b == 0) {
+            Some(len) => len,
+            None => return decode_error("meta (ape): unterminated item key"),
+        };
+
+        let key = &items[..key_len];
+
+        items = &items[key_len + 1..];
+
+        if value_len > items.len() {
+            return decode_error("meta (ape): item value exceeds tag length");
+        }
+
+        let value = &items[..value_len];
+
+        items = &items[value_len..];
+
+        // Keys must be between 2 and 255 characters in the printable ASCII range.
+        if key.len() < 2 || key.len() > 255 || key.iter().any(|&b| b < 0x20 || b > 0x7e) {
+            warn!("meta (ape): ignoring item with an invalid key");
+            continue;
+        }
+
+        // APEv1 tags only contain text items.
+        let item_type = match (header.version, (item_flags >> 1) & 0x3) {
+            (1000, _) | (_, 0) => ItemType::Text,
+            (_, 1) => ItemType::Binary,
+            (_, 2) => ItemType::Locator,
+            _ => {
+                warn!("meta (ape): ignoring item with a reserved type");
+                continue;
+            }
+        };
+
+        parsed.push(ApeItem {
+            key: String::from_utf8_lossy(key).into_owned(),
+            item_type,
+            read_only: item_flags & 0x1 != 0,
+            value: Box::from(value),
+        });
+    }
+
+    Ok(parsed)
+}
+
+/// Read the items of an APE tag. The reader must be positioned at the first item, immediately
+/// after the tag header (if present).
+pub fn read_ape_tag_items<B: ReadBytes>(
+    reader: &mut B,
+    header: &ApeTagHeader,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    let mut buf = vec![0; header.items_len() as usize];
+    reader.read_buf_exact(&mut buf)?;
+
+    let tag = ApeTag { header: *header, items: parse_ape_tag_items(&buf, header)? };
+
+    tag.add_to_metadata(metadata);
+
+    Ok(())
+}
+
+fn read_item(key: &str, item_type: ItemType, value: &[u8], metadata: &mut MetadataBuilder) {
+    let key_lower = key.to_ascii_lowercase();
+
+    match item_type {
+        ItemType::Text => {
+            let std_key = APE_TAG_MAP.get(key_lower.as_str()).copied();
+
+            // A text item may contain multiple values seperated by a null character.
+            for value in String::from_utf8_lossy(value).split('\0') {
+                metadata.add_tag(Tag::new(std_key, key, Value::from(value)));
+            }
+        }
+        ItemType::Binary => {
+            // Any binary item with a cover art key is cover art, even if its usage is unknown
+            // (e.g., "Cover Art (Other)").
+            if key_lower.starts_with("cover art") {
+                let usage = APE_COVER_ART_MAP.get(key_lower.as_str()).copied();
+                read_cover_art(key, usage, value, metadata);
+            }
+            else {
+                metadata.add_tag(Tag::new(None, key, Value::from(value)));
+            }
+        }
+        ItemType::Locator => {
+            metadata.add_tag(Tag::new(None, key, Value::from(String::from_utf8_lossy(value))));
+        }
+    }
+}
+
+/// Read a cover art item. Cover art is stored as a null-terminated file name followed by the image
+/// data. Some writers omit the file name, and store only the image data.
+fn read_cover_art(
+    key: &str,
+    usage: Option<StandardVisualKey>,
+    value: &[u8],
+    metadata: &mut MetadataBuilder,
+) {
+    let split = value.iter().position(|&b| b == 0).map(|len| (&value[..len], &value[len + 1..]));
+
+    // Prefer the file name and image data split if the image data is recognized, otherwise assume
+    // the file name was omitted if the entire value is recognized image data.
+    let (desc, data) = match split {
+        Some((name, data)) if detect_image_type(data).is_some() => (Some(name), data),
+        _ if detect_image_type(value).is_some() => (None, value),
+        Some((name, data)) => (Some(name), data),
+        None => {
+            warn!("meta (ape): ignoring invalid cover art item");
+            return;
+        }
+    };
+
+    let media_type = detect_image_type(data).unwrap_or("application/octet-stream");
+
+    let mut tags = vec![Tag::new(None, "APE_ITEM_KEY", Value::from(key))];
+
+    if let Some(desc) = desc {
+        let desc = String::from_utf8_lossy(desc);
+        tags.push(Tag::new(Some(StandardTagKey::Description), "DESCRIPTION", Value::from(desc)));
+    }
+
+    metadata.add_visual(Visual {
+        media_type: media_type.to_string(),
+        dimensions: None,
+        bits_per_pixel: None,
+        color_mode: None,
+        usage,
+        tags,
+        data: Box::from(data),
+    });
+}
+
+/// Detects the media type of image data from its signature.
+fn detect_image_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_ape_tag, read_ape_tag_header, read_ape_tag_items, ItemType};
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey, StandardVisualKey, Value};
+
+    fn header(buf: &mut Vec<u8>, items_len: usize, num_items: u32, flags: u32) {
+        buf.extend_from_slice(b"APETAGEX");
+        buf.extend_from_slice(&2000u32.to_le_bytes());
+        buf.extend_from_slice(&(items_len as u32 + 32).to_le_bytes());
+        buf.extend_from_slice(&num_items.to_le_bytes());
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(&[0; 8]);
+    }
+
+    fn item(buf: &mut Vec<u8>, flags: u32, key: &str, value: &[u8]) {
+        buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(key.as_bytes());
+        buf.push(0);
+        buf.extend_from_slice(value);
+    }
+
+    #[test]
+    fn verify_read_ape_tag() {
+        let mut items = Vec::new();
+        item(&mut items, 0, "Title", b"Title");
+        item(&mut items, 0, "Artist", b"A\0B");
+        item(&mut items, 2, "Cover Art (Front)", b"cover.png\0\x89PNG");
+        item(&mut items, 4, "Related", b"https://example.com");
+        item(&mut items, 2, "Cover Art (Other)", b"\xff\xd8\xff\xe0");
+
+        let mut tag = Vec::new();
+        tag.extend_from_slice(&items);
+        tag.extend_from_slice(b"APETAGEX");
+        tag.extend_from_slice(&2000u32.to_le_bytes());
+        tag.extend_from_slice(&(items.len() as u32 + 32).to_le_bytes());
+        tag.extend_from_slice(&5u32.to_le_bytes());
+        tag.extend_from_slice(&0u32.to_le_bytes());
+        tag.extend_from_slice(&[0; 8]);
+
+        let header = read_ape_tag_header(&mut BufReader::new(&tag[items.len()..])).unwrap();
+
+        assert!(!header.is_header());
+        assert_eq!(header.items_len() as usize, items.len());
+
+        let mut builder = MetadataBuilder::new();
+        read_ape_tag_items(&mut BufReader::new(&tag), &header, &mut builder).unwrap();
+
+        let metadata = builder.metadata();
+        let tags = metadata.tags();
+
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert!(matches!(&tags[1].value, Value::String(value) if value == "A"));
+        assert!(matches!(&tags[2].value, Value::String(value) if value == "B"));
+        assert_eq!(tags[3].key, "Related");
+
+        let visuals = metadata.visuals();
+
+        assert_eq!(visuals.len(), 2);
+        assert_eq!(visuals[0].usage, Some(StandardVisualKey::FrontCover));
+        assert_eq!(visuals[0].media_type, "image/png");
+        assert_eq!(visuals[0].data.as_ref(), b"\x89PNG");
+
+        // Cover art of an unknown usage, without a file name.
+        assert_eq!(visuals[1].usage, None);
+        assert_eq!(visuals[1].media_type, "image/jpeg");
+        assert_eq!(visuals[1].tags.len(), 1);
+        assert_eq!(visuals[1].data.as_ref(), b"\xff\xd8\xff\xe0");
+    }
+
+    #[test]
+    fn verify_read_ape_tag_buffer() {
+        let mut items = Vec::new();
+        item(&mut items, 1, "Title", b"Title");
+        item(&mut items, 2, "Cover Art (Front)", b"cover.png\0\x89PNG");
+
+        // A tag with a header and a footer, followed by trailing data.
+        let mut tag = Vec::new();
+        header(&mut tag, items.len(), 2, 0xa000_0000);
+        tag.extend_from_slice(&items);
+        header(&mut tag, items.len(), 2, 0x8000_0000);
+        tag.extend_from_slice(b"TAG");
+
+        let ape = read_ape_tag(&tag).unwrap();
+
+        assert!(ape.header.has_footer());
+        assert_eq!(ape.items.len(), 2);
+        assert_eq!(ape.items[0].key, "Title");
+        assert_eq!(ape.items[0].item_type, ItemType::Text);
+        assert!(ape.items[0].read_only);
+        assert_eq!(ape.items[1].item_type, ItemType::Binary);
+        assert!(!ape.items[1].read_only);
+
+        // A tag with only a footer.
+        let mut tag = items.clone();
+        header(&mut tag, items.len(), 2, 0);
+
+        let ape = read_ape_tag(&tag).unwrap();
+
+        assert_eq!(ape.items.len(), 2);
+        assert_eq!(ape.items[1].value.as_ref(), b"cover.png\0\x89PNG");
+    }
+}
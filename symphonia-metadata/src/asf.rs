@@ -0,0 +1,227 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ASF (Windows Media) metadata support.
+
+use std::collections::HashMap;
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value, Visual};
+
+use lazy_static::lazy_static;
+use log::warn;
+
+use crate::id3v2::util::apic_picture_type_to_visual_key;
+use crate::rating;
+
+lazy_static! {
+    static ref ASF_TAG_MAP: HashMap<&'static str, StandardTagKey> = {
+        let mut m = HashMap::new();
+        // The fields of the content description object.
+        m.insert("Author", StandardTagKey::Artist);
+        m.insert("Copyright", StandardTagKey::Copyright);
+        m.insert("Description", StandardTagKey::Comment);
+        m.insert("Rating", StandardTagKey::Rating);
+        m.insert("Title", StandardTagKey::TrackTitle);
+        // The attributes of the extended content description, metadata, and metadata library
+        // objects.
+        m.insert("Acoustid/Fingerprint", StandardTagKey::AcoustidFingerprint);
+        m.insert("Acoustid/Id", StandardTagKey::AcoustidId);
+        m.insert("MusicBrainz/Album Artist Id", StandardTagKey::MusicBrainzAlbumArtistId);
+        m.insert("MusicBrainz/Album Id", StandardTagKey::MusicBrainzAlbumId);
+        m.insert("MusicBrainz/Album Release Country", StandardTagKey::ReleaseCountry);
+        m.insert("MusicBrainz/Album Status", StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("MusicBrainz/Album Type", StandardTagKey::MusicBrainzReleaseType);
+        m.insert("MusicBrainz/Artist Id", StandardTagKey::MusicBrainzArtistId);
+        m.insert("MusicBrainz/Disc Id", StandardTagKey::MusicBrainzDiscId);
+        m.insert("MusicBrainz/Release Group Id", StandardTagKey::MusicBrainzReleaseGroupId);
+        m.insert("MusicBrainz/Release Track Id", StandardTagKey::MusicBrainzReleaseTrackId);
+        m.insert("MusicBrainz/Track Id", StandardTagKey::MusicBrainzTrackId);
+        m.insert("MusicBrainz/Work Id", StandardTagKey::MusicBrainzWorkId);
+        m.insert("WM/ARTISTS", StandardTagKey::Artist);
+        m.insert("WM/AlbumArtist", StandardTagKey::AlbumArtist);
+        m.insert("WM/AlbumArtistSortOrder", StandardTagKey::SortAlbumArtist);
+        m.insert("WM/AlbumSortOrder", StandardTagKey::SortAlbum);
+        m.insert("WM/AlbumTitle", StandardTagKey::Album);
+        m.insert("WM/ArtistSortOrder", StandardTagKey::SortArtist);
+        m.insert("WM/AuthorURL", StandardTagKey::UrlArtist);
+        m.insert("WM/Barcode", StandardTagKey::IdentBarcode);
+        m.insert("WM/BeatsPerMinute", StandardTagKey::Bpm);
+        m.insert("WM/CatalogNo", StandardTagKey::IdentCatalogNumber);
+        m.insert("WM/Composer", StandardTagKey::Composer);
+        m.insert("WM/ComposerSortOrder", StandardTagKey::SortComposer);
+        m.insert("WM/Conductor", StandardTagKey::Conductor);
+        m.insert("WM/ContentGroupDescription", StandardTagKey::ContentGroup);
+        m.insert("WM/EncodedBy", StandardTagKey::EncodedBy);
+        m.insert("WM/EncodingSettings", StandardTagKey::EncoderSettings);
+        m.insert("WM/EncodingTime", StandardTagKey::EncodingDate);
+        m.insert("WM/Genre", StandardTagKey::Genre);
+        m.insert("WM/ISRC", StandardTagKey::IdentIsrc);
+        m.insert("WM/Language", StandardTagKey::Language);
+        m.insert("WM/Lyrics", StandardTagKey::Lyrics);
+        m.insert("WM/ModifiedBy", StandardTagKey::Remixer);
+        m.insert("WM/Mood", StandardTagKey::Mood);
+        m.insert("WM/OriginalAlbumTitle", StandardTagKey::OriginalAlbum);
+        m.insert("WM/OriginalArtist", StandardTagKey::OriginalArtist);
+        m.insert("WM/OriginalFilename", StandardTagKey::OriginalFile);
+        m.insert("WM/OriginalLyricist", StandardTagKey::OriginalWriter);
+        m.insert("WM/OriginalReleaseYear", StandardTagKey::OriginalDate);
+        m.insert("WM/PartOfSet", StandardTagKey::DiscNumber);
+        m.insert("WM/Producer", StandardTagKey::Producer);
+        m.insert("WM/Publisher", StandardTagKey::Label);
+        m.insert("WM/SetSubTitle", StandardTagKey::DiscSubtitle);
+        m.insert("WM/SubTitle", StandardTagKey::TrackSubtitle);
+        m.insert("WM/TitleSortOrder", StandardTagKey::SortTrackTitle);
+        m.insert("WM/ToolName", StandardTagKey::Encoder);
+        m.insert("WM/TrackNumber", StandardTagKey::TrackNumber);
+        m.insert("WM/Writer", StandardTagKey::Lyricist);
+        m.insert("WM/Year", StandardTagKey::Date);
+        m.insert("replaygain_album_gain", StandardTagKey::ReplayGainAlbumGain);
+        m.insert("replaygain_album_peak", StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("replaygain_track_gain", StandardTagKey::ReplayGainTrackGain);
+        m.insert("replaygain_track_peak", StandardTagKey::ReplayGainTrackPeak);
+        m
+    };
+}
+
+/// Try to map the ASF attribute `name` to a `StandardTagKey`.
+pub fn std_key_from_tag(name: &str) -> Option<StandardTagKey> {
+    ASF_TAG_MAP.get(name).copied()
+}
+
+/// Decode a UTF-16LE string, excluding any terminating null characters.
+pub fn decode_utf16le(buf: &[u8]) -> String {
+    let units = buf.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+
+    let text = String::from_utf16_lossy(&units.collect::<Vec<u16>>());
+
+    text.trim_end_matches('\0').to_string()
+}
+
+/// Read a null-terminated UTF-16LE string.
+fn read_utf16le_null_terminated<B: ReadBytes>(reader: &mut B) -> Result<String> {
+    let mut buf = Vec::new();
+
+    loop {
+        let unit = reader.read_double_bytes()?;
+
+        if unit == [0, 0] {
+            break;
+        }
+
+        buf.extend_from_slice(&unit);
+    }
+
+    Ok(decode_utf16le(&buf))
+}
+
+/// Read the value of a WM/Picture attribute.
+pub fn read_wm_picture(buf: &[u8]) -> Result<Visual> {
+    let mut reader = BufReader::new(buf);
+
+    let picture_type = reader.read_u8()?;
+    let data_len = reader.read_u32()? as usize;
+
+    let media_type = read_utf16le_null_terminated(&mut reader)?;
+    let description = read_utf16le_null_terminated(&mut reader)?;
+
+    if data_len > buf.len() - reader.pos() as usize {
+        return decode_error("asf: invalid picture data length");
+    }
+
+    let data = reader.read_boxed_slice_exact(data_len)?;
+
+    let mut tags = Vec::new();
+
+    if !description.is_empty() {
+        tags.push(Tag::new(Some(StandardTagKey::Description), "", Value::from(description)));
+    }
+
+    Ok(Visual {
+        media_type,
+        dimensions: None,
+        bits_per_pixel: None,
+        color_mode: None,
+        usage: apic_picture_type_to_visual_key(u32::from(picture_type)),
+        tags,
+        data,
+    })
+}
+
+/// Add an attribute of an extended content description, metadata, or metadata library object to
+/// the metadata. Pictures are added as visuals, and all other attributes as tags.
+pub fn add_attribute(builder: &mut MetadataBuilder, name: &str, data_type: u16, buf: &[u8]) {
+    let value = match data_type {
+        // Unicode string.
+        0 => Value::from(decode_utf16le(buf)),
+        // Byte array.
+        1 if name == "WM/Picture" => {
+            match read_wm_picture(buf) {
+                Ok(visual) => {
+                    builder.add_visual(visual);
+                }
+                Err(err) => warn!("failed to read picture: {}", err),
+            }
+            return;
+        }
+        1 => Value::from(buf),
+        // Boolean, either 32-bit or 16-bit depending on the object.
+        2 => Value::from(buf.iter().any(|&b| b != 0)),
+        // 32-bit, 64-bit, and 16-bit unsigned integers.
+        3 if buf.len() >= 4 => Value::from(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])),
+        4 if buf.len() >= 8 => {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&buf[..8]);
+            Value::from(u64::from_le_bytes(bytes))
+        }
+        5 if buf.len() >= 2 => Value::from(u16::from_le_bytes([buf[0], buf[1]])),
+        // GUID, or malformed values.
+        _ => Value::from(buf),
+    };
+
+    // The rating set by Windows Media Player is added normalized along with the raw rating.
+    if name == "WM/SharedUserRating" {
+        let normalized = match value {
+            Value::UnsignedInt(rating) => rating::from_wm_shared_user_rating(rating),
+            _ => None,
+        };
+
+        for tag in rating::make_tags(Some(StandardTagKey::Rating), name, value, normalized) {
+            builder.add_tag(tag);
+        }
+        return;
+    }
+
+    builder.add_tag(Tag::new(std_key_from_tag(name), name, value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::meta::StandardVisualKey;
+
+    #[test]
+    fn verify_read_wm_picture() {
+        let mut buf = vec![3];
+        buf.extend_from_slice(&4u32.to_le_bytes());
+        buf.extend_from_slice(b"i\0m\0a\0g\0e\0/\0p\0n\0g\0\0\0");
+        buf.extend_from_slice(b"\0\0");
+        buf.extend_from_slice(&[1, 2, 3, 4]);
+
+        let visual = read_wm_picture(&buf).unwrap();
+
+        assert_eq!(visual.media_type, "image/png");
+        assert_eq!(visual.usage, Some(StandardVisualKey::FrontCover));
+        assert!(visual.tags.is_empty());
+        assert_eq!(&visual.data[..], &[1, 2, 3, 4]);
+
+        // The picture data may not extend past the value.
+        buf[1] = 5;
+        assert!(read_wm_picture(&buf).is_err());
+    }
+}
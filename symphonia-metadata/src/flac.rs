@@ -69,7 +69,12 @@ pub fn read_picture_block<B: ReadBytes>(
     let desc = String::from_utf8_lossy(&desc_buf);
 
     // Convert description bytes into a standard Vorbis DESCRIPTION tag.
-    let tags = vec![Tag::new(Some(StandardTagKey::Description), "DESCRIPTION", Value::from(desc))];
+    let mut tags =
+        vec![Tag::new(Some(StandardTagKey::Description), "DESCRIPTION", Value::from(desc))];
+
+    // Not all picture types map to a standard visual key (e.g., "other"), therefore always retain
+    // the picture type so that visuals of such types may still be distinguished.
+    tags.push(Tag::new(None, "PICTURE_TYPE", Value::from(type_enc)));
 
     // Read the width, and height of the visual.
     let width = reader.read_be_u32()?;
@@ -106,3 +111,65 @@ pub fn read_picture_block<B: ReadBytes>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::read_picture_block;
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::{MetadataBuilder, Size, StandardVisualKey, Value};
+
+    fn picture_block(type_enc: u32, desc: &str, width: u32, height: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&type_enc.to_be_bytes());
+        buf.extend_from_slice(&10u32.to_be_bytes());
+        buf.extend_from_slice(b"image/jpeg");
+        buf.extend_from_slice(&(desc.len() as u32).to_be_bytes());
+        buf.extend_from_slice(desc.as_bytes());
+        buf.extend_from_slice(&width.to_be_bytes());
+        buf.extend_from_slice(&height.to_be_bytes());
+        buf.extend_from_slice(&24u32.to_be_bytes());
+        buf.extend_from_slice(&0u32.to_be_bytes());
+        buf.extend_from_slice(&4u32.to_be_bytes());
+        buf.extend_from_slice(&[0xff, 0xd8, 0xff, 0xd9]);
+        buf
+    }
+
+    #[test]
+    fn verify_read_multiple_picture_blocks() {
+        let mut builder = MetadataBuilder::new();
+
+        for (type_enc, desc, size) in [(3, "front", 500), (4, "back", 400), (0, "other", 0)] {
+            let block = picture_block(type_enc, desc, size, size);
+            read_picture_block(&mut BufReader::new(&block), &mut builder).unwrap();
+        }
+
+        let metadata = builder.metadata();
+        let visuals = metadata.visuals();
+
+        assert_eq!(visuals.len(), 3);
+
+        assert_eq!(visuals[0].usage, Some(StandardVisualKey::FrontCover));
+        assert_eq!(visuals[1].usage, Some(StandardVisualKey::BackCover));
+        assert_eq!(visuals[2].usage, None);
+
+        let dimensions: Vec<_> = visuals
+            .iter()
+            .map(|v| v.dimensions.map(|Size { width, height }| (width, height)))
+            .collect();
+
+        assert_eq!(dimensions, [Some((500, 500)), Some((400, 400)), None]);
+
+        for (visual, (type_enc, desc)) in
+            visuals.iter().zip([(3, "front"), (4, "back"), (0, "other")])
+        {
+            assert_eq!(visual.media_type, "image/jpeg");
+            assert_eq!(visual.data.as_ref(), &[0xff, 0xd8, 0xff, 0xd9]);
+
+            let desc_tag = visual.tags.iter().find(|tag| tag.key == "DESCRIPTION").unwrap();
+            assert!(matches!(&desc_tag.value, Value::String(value) if value == desc));
+
+            let type_tag = visual.tags.iter().find(|tag| tag.key == "PICTURE_TYPE").unwrap();
+            assert!(matches!(type_tag.value, Value::UnsignedInt(value) if value == type_enc));
+        }
+    }
+}
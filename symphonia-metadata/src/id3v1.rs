@@ -209,7 +209,65 @@ const GENRES: &[&str] = &[
     "Psybient",
 ];
 
+/// The length of an ID3v1 tag.
+pub const TAG_LEN: u64 = 128;
+
+/// The length of an extended (TAG+) tag. When present, the extended tag immediately precedes the
+/// ID3v1 tag.
+pub const EXTENDED_TAG_LEN: u64 = 227;
+
+/// The extended (TAG+) tag. The extended tag extends the title, artist, and album fields of the
+/// ID3v1 tag by 60 characters each, and adds a few fields of its own.
+pub struct ExtendedTag {
+    /// The 60 characters that follow the first 30 characters of the title.
+    pub title: String,
+    /// The 60 characters that follow the first 30 characters of the artist.
+    pub artist: String,
+    /// The 60 characters that follow the first 30 characters of the album.
+    pub album: String,
+    /// The speed of the track: 0 is unset, 1 is slow, 2 is medium, 3 is fast, and 4 is hardcore.
+    pub speed: u8,
+    /// A free-text genre.
+    pub genre: String,
+    /// The start time of the music formatted as `mmm:ss`.
+    pub start_time: String,
+    /// The end time of the music formatted as `mmm:ss`.
+    pub end_time: String,
+}
+
+/// Read an extended (TAG+) tag.
+pub fn read_extended_tag<B: ReadBytes>(reader: &mut B) -> Result<ExtendedTag> {
+    // Read the "TAG+" header.
+    let marker = reader.read_quad_bytes()?;
+
+    if marker != *b"TAG+" {
+        return unsupported_error("id3v1: Not an extended ID3v1 tag");
+    }
+
+    let buf = reader.read_boxed_slice_exact(223)?;
+
+    Ok(ExtendedTag {
+        title: decode_iso8859_text(&buf[0..60]),
+        artist: decode_iso8859_text(&buf[60..120]),
+        album: decode_iso8859_text(&buf[120..180]),
+        speed: buf[180],
+        genre: decode_iso8859_text(&buf[181..211]),
+        start_time: decode_iso8859_text(&buf[211..217]),
+        end_time: decode_iso8859_text(&buf[217..223]),
+    })
+}
+
+/// Read an ID3v1 tag.
 pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    read_id3v1_with_extended(reader, None, metadata)
+}
+
+/// Read an ID3v1 tag, and merge it with the extended (TAG+) tag that preceded it, if any.
+pub fn read_id3v1_with_extended<B: ReadBytes>(
+    reader: &mut B,
+    extended: Option<&ExtendedTag>,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
     // Read the "TAG" header.
     let marker = reader.read_triple_bytes()?;
 
@@ -219,17 +277,28 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
 
     let buf = reader.read_boxed_slice_exact(125)?;
 
-    let title = decode_iso8859_text(&buf[0..30]);
+    // The extended tag continues the title, artist, and album fields where the ID3v1 tag ends.
+    let (title, artist, album) = match extended {
+        Some(extended) => (
+            extend_iso8859_text(&buf[0..30], &extended.title),
+            extend_iso8859_text(&buf[30..60], &extended.artist),
+            extend_iso8859_text(&buf[60..90], &extended.album),
+        ),
+        None => (
+            decode_iso8859_text(&buf[0..30]),
+            decode_iso8859_text(&buf[30..60]),
+            decode_iso8859_text(&buf[60..90]),
+        ),
+    };
+
     if !title.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TITLE", Value::from(title)));
     }
 
-    let artist = decode_iso8859_text(&buf[30..60]);
     if !artist.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from(artist)));
     }
 
-    let album = decode_iso8859_text(&buf[60..90]);
     if !album.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::Album), "ALBUM", Value::from(album)));
     }
@@ -239,7 +308,10 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
         metadata.add_tag(Tag::new(Some(StandardTagKey::Date), "DATE", Value::from(year)));
     }
 
-    let comment = if buf[122] == 0 {
+    // In ID3v1.1, the last 2 bytes of the comment are a NUL byte followed by the track number. A
+    // track number of 0 is not a valid track number, and is likely the padding of an ID3v1.0
+    // comment.
+    let comment = if buf[122] == 0 && buf[123] != 0 {
         let track = buf[123];
 
         metadata.add_tag(Tag::new(Some(StandardTagKey::TrackNumber), "TRACK", Value::from(track)));
@@ -266,11 +338,58 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
         ));
     }
 
+    if let Some(extended) = extended {
+        // The free-text genre is usually more specific than the genre index.
+        if !extended.genre.is_empty() {
+            metadata.add_tag(Tag::new(
+                Some(StandardTagKey::Genre),
+                "GENRE",
+                Value::from(extended.genre.as_str()),
+            ));
+        }
+
+        if extended.speed != 0 {
+            metadata.add_tag(Tag::new(None, "SPEED", Value::from(extended.speed)));
+        }
+
+        if !extended.start_time.is_empty() {
+            metadata.add_tag(Tag::new(
+                None,
+                "START_TIME",
+                Value::from(extended.start_time.as_str()),
+            ));
+        }
+
+        if !extended.end_time.is_empty() {
+            metadata.add_tag(Tag::new(None, "END_TIME", Value::from(extended.end_time.as_str())));
+        }
+    }
+
     Ok(())
 }
 
+/// Decodes a fixed-length ISO-8859-1 text field up to the first NUL character.
+fn decode_iso8859_text_untrimmed(data: &[u8]) -> String {
+    let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+
+    data[..len].iter().filter(|&b| *b > 0x1f).map(|&b| b as char).collect()
+}
+
+/// Decodes a fixed-length ISO-8859-1 text field. Fields are padded with NUL characters, or by some
+/// writers, spaces.
 fn decode_iso8859_text(data: &[u8]) -> String {
-    data.iter().filter(|&b| *b > 0x1f).map(|&b| b as char).collect()
+    decode_iso8859_text_untrimmed(data).trim_end_matches(' ').to_string()
+}
+
+/// Decodes a fixed-length ISO-8859-1 text field, and appends its continuation from the extended
+/// tag. If there is a continuation, the field is full, and any trailing spaces are significant.
+fn extend_iso8859_text(data: &[u8], continuation: &str) -> String {
+    if continuation.is_empty() {
+        decode_iso8859_text(data)
+    }
+    else {
+        decode_iso8859_text_untrimmed(data) + continuation
+    }
 }
 
 pub mod util {
@@ -281,3 +400,86 @@ pub mod util {
         GENRES.get(usize::from(index))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    fn make_tag(comment: &[u8; 30], genre: u8) -> Vec<u8> {
+        let mut buf = b"TAG".to_vec();
+        buf.extend_from_slice(b"Title\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        buf.extend_from_slice(b"Artist                        ");
+        buf.extend_from_slice(&[0; 30]);
+        buf.extend_from_slice(b"1999");
+        buf.extend_from_slice(comment);
+        buf.push(genre);
+        buf
+    }
+
+    fn tags(metadata: MetadataBuilder) -> Vec<(Option<StandardTagKey>, String)> {
+        metadata.metadata().tags().iter().map(|tag| (tag.std_key, tag.value.to_string())).collect()
+    }
+
+    #[test]
+    fn verify_read_id3v1() {
+        // An ID3v1.1 tag with a track number, and an extended genre.
+        let mut comment = [0; 30];
+        comment[..7].copy_from_slice(b"Comment");
+        comment[29] = 7;
+
+        let mut builder = MetadataBuilder::new();
+        read_id3v1(&mut BufReader::new(&make_tag(&comment, 189)), &mut builder).unwrap();
+
+        assert_eq!(
+            tags(builder),
+            vec![
+                (Some(StandardTagKey::TrackTitle), "Title".to_string()),
+                (Some(StandardTagKey::Artist), "Artist".to_string()),
+                (Some(StandardTagKey::Date), "1999".to_string()),
+                (Some(StandardTagKey::TrackNumber), "7".to_string()),
+                (Some(StandardTagKey::Comment), "Comment".to_string()),
+                (Some(StandardTagKey::Genre), "Dubstep".to_string()),
+            ]
+        );
+
+        // An ID3v1.0 tag with a NUL padded comment does not have a track number.
+        let mut builder = MetadataBuilder::new();
+        read_id3v1(&mut BufReader::new(&make_tag(&[0; 30], 255)), &mut builder).unwrap();
+
+        assert!(tags(builder).iter().all(|(key, _)| *key != Some(StandardTagKey::TrackNumber)));
+    }
+
+    #[test]
+    fn verify_read_id3v1_with_extended() {
+        let mut buf = b"TAG+".to_vec();
+        let mut title = [0; 60];
+        title[..6].copy_from_slice(b" Title");
+        buf.extend_from_slice(&title);
+        buf.extend_from_slice(&[0; 120]);
+        buf.push(3);
+        let mut genre = [0; 30];
+        genre[..9].copy_from_slice(b"Nu-Disco ");
+        buf.extend_from_slice(&genre);
+        buf.extend_from_slice(b"000:10001:20");
+
+        let extended = read_extended_tag(&mut BufReader::new(&buf)).unwrap();
+
+        let mut builder = MetadataBuilder::new();
+        let tag = make_tag(&[0; 30], 255);
+        read_id3v1_with_extended(&mut BufReader::new(&tag), Some(&extended), &mut builder).unwrap();
+
+        assert_eq!(
+            tags(builder),
+            vec![
+                (Some(StandardTagKey::TrackTitle), "Title Title".to_string()),
+                (Some(StandardTagKey::Artist), "Artist".to_string()),
+                (Some(StandardTagKey::Date), "1999".to_string()),
+                (Some(StandardTagKey::Genre), "Nu-Disco".to_string()),
+                (None, "3".to_string()),
+                (None, "000:10".to_string()),
+                (None, "001:20".to_string()),
+            ]
+        );
+    }
+}
@@ -11,14 +11,19 @@ use std::str;
 
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::{BufReader, FiniteStream, ReadBytes};
-use symphonia_core::meta::{StandardTagKey, Tag, Value, Visual};
+use symphonia_core::meta::{
+    Lyrics, LyricsContentType, LyricsLine, LyricsTimeUnit, StandardTagKey, Tag, Value, Visual,
+};
 
 use encoding_rs::UTF_16BE;
 use lazy_static::lazy_static;
-use log::warn;
+use log::{info, warn};
+
+use crate::rating;
 
 use super::unsync::{decode_unsynchronisation, read_syncsafe_leq32};
 use super::util;
+use super::{Chapter, TableOfContents};
 
 // The following is a list of all standardized ID3v2.x frames for all ID3v2 major versions and their
 // implementation status ("S" column) in Symphonia.
@@ -44,7 +49,7 @@ use super::util;
 //       ETC    ETCO                                Event timing codes
 //       GEO    GEOB                                General encapsulated object
 //              GRID                                Group identification registration
-//   x   IPL    IPLS    TIPL                        Involved people list
+//   x   IPL    IPLS    TIPL     InvolvedPeople     Involved people list
 //       LNK    LINK                                Linked information
 //   x   MCI    MCDI                                Music CD identifier
 //       MLL    MLLT                                MPEG location lookup table
@@ -59,7 +64,7 @@ use super::util;
 //       REV    RVRB                                Reverb
 //                      SEEK                        Seek frame
 //                      SIGN                        Signature frame
-//       SLT    SYLT                                Synchronized lyric/text
+//   x   SLT    SYLT             Lyrics             Synchronized lyric/text
 //       STC    SYTC                                Synchronized tempo codes
 //   x   TAL    TALB             Album              Album/Movie/Show title
 //   x   TBP    TBPM             Bpm                BPM (beats per minute)
@@ -83,7 +88,7 @@ use super::util;
 //   x   TKE    TKEY                                Initial key
 //   x   TLA    TLAN             Language           Language(s)
 //   x   TLE    TLEN                                Length
-//   x                  TMCL                        Musician credits list
+//   x                  TMCL     MusicianCredits    Musician credits list
 //   x   TMT    TMED             MediaFormat        Media type
 //   x                  TMOO     Mood               Mood
 //   x   TOT    TOAL             OriginalAlbum      Original album/movie/show title
@@ -96,7 +101,7 @@ use super::util;
 //   x   TP2    TPE2             AlbumArtist        Band/orchestra/accompaniment
 //   x   TP3    TPE3             Performer          Conductor/performer refinement
 //   x   TP4    TPE4             Remixer            Interpreted, remixed, or otherwise modified by
-//   x   TPA    TPOS             TrackNumber        Part of a set
+//   x   TPA    TPOS             DiscNumber         Part of a set
 //   x                  TPRO                        Produced notice
 //   x   TPB    TPUB             Label              Publisher
 //   x   TRK    TRCK             TrackNumber        Track number/Position in set
@@ -109,7 +114,7 @@ use super::util;
 //   x   TSI    TSIZ    n/a                         Size
 //   x   TRC    TSRC             IdentIsrc          ISRC (international standard recording code)
 //   x   TSS    TSSE             Encoder            Software/Hardware and settings used for encoding
-//   x                  TSST                        Set subtitle
+//   x                  TSST     DiscSubtitle       Set subtitle
 //   x   TYE    TYER    n/a      Date               Year
 //   x   TXX    TXXX                                User defined text information frame
 //       UFI    UFID                                Unique file identifier
@@ -124,26 +129,30 @@ use super::util;
 //   x          WPAY             UrlPayment         Payment
 //   x   WPB    WPUB             UrlLabel           Publishers official webpage
 //   x   WXX    WXXX             Url                User defined URL link frame
-//   x          GRP1                                (Apple iTunes) Grouping
-//   x          MVNM             MovementName       (Apple iTunes) Movement name
-//   x          MVIN             MovementNumber     (Apple iTunes) Movement number
-//       PCS    PCST                                (Apple iTunes) Podcast flag
-//   x          TCAT             PodcastCategory    (Apple iTunes) Podcast category
-//   x          TDES             PodcastDescription (Apple iTunes) Podcast description
-//   x          TGID             IdentPodcast       (Apple iTunes) Podcast identifier
-//   x          TKWD             PodcastKeywords    (Apple iTunes) Podcast keywords
-//   x          WFED             UrlPodcast         (Apple iTunes) Podcast url
+//   x   GP1    GRP1             ContentGroup       (Apple iTunes) Grouping
+//   x   MVN    MVNM             MovementName       (Apple iTunes) Movement name
+//   x   MVI    MVIN             MovementNumber     (Apple iTunes) Movement number
+//   x   PCS    PCST             Podcast            (Apple iTunes) Podcast flag
+//   x   TCT    TCAT             PodcastCategory    (Apple iTunes) Podcast category
+//   x   TDS    TDES             PodcastDescription (Apple iTunes) Podcast description
+//   x   TID    TGID             IdentPodcast       (Apple iTunes) Podcast identifier
+//   x   TKW    TKWD             PodcastKeywords    (Apple iTunes) Podcast keywords
+//   x   WFD    WFED             UrlPodcast         (Apple iTunes) Podcast url
 //   x   TST                     SortTrackTitle     (Apple iTunes) Title sort order
 //   x   TSP                     SortArtist         (Apple iTunes) Artist order order
 //   x   TSA                     SortAlbum          (Apple iTunes) Album sort order
 //   x   TS2    TSO2             SortAlbumArtist    (Apple iTunes) Album artist sort order
 //   x   TSC    TSOC             SortComposer       (Apple iTunes) Composer sort order
+//   x   TCP    TCMP             Compilation        (Apple iTunes) Compilation flag
+//   x          CHAP                                (Chapter addendum) Chapter
+//   x          CTOC                                (Chapter addendum) Table of contents
 //
 // Information on these frames can be found at:
 //
 //     ID3v2.2: http://id3.org/id3v2-00
 //     ID3v2.3: http://id3.org/d3v2.3.0
 //     ID3v2.4: http://id3.org/id3v2.4.0-frames
+//     Chapters: http://id3.org/id3v2-chapters-1.0
 
 /// The result of parsing a frame.
 pub enum FrameResult {
@@ -159,6 +168,13 @@ pub enum FrameResult {
     Visual(Visual),
     /// A frame was parsed and yielded many `Tag`s.
     MultipleTags(Vec<Tag>),
+    /// A frame was parsed and yielded a `Chapter`.
+    Chapter(Chapter),
+    /// A frame was parsed and yielded a `TableOfContents`.
+    TableOfContents(TableOfContents),
+    /// A frame was parsed and yielded synchronized `Lyrics`, and a `Tag` with their textual
+    /// rendering.
+    Lyrics(Tag, Lyrics),
 }
 
 /// Makes a frame result for a frame containing invalid data.
@@ -173,6 +189,12 @@ fn unsupported_frame(id: &[u8]) -> Result<FrameResult> {
 
 type FrameParser = fn(&mut BufReader<'_>, Option<StandardTagKey>, &str) -> Result<FrameResult>;
 
+const PIC_PARSER: (FrameParser, Option<StandardTagKey>) = (read_pic_frame, None);
+const CHAP_V3_PARSER: (FrameParser, Option<StandardTagKey>) = (read_chap_v3_frame, None);
+const CHAP_V4_PARSER: (FrameParser, Option<StandardTagKey>) = (read_chap_v4_frame, None);
+const CTOC_V3_PARSER: (FrameParser, Option<StandardTagKey>) = (read_ctoc_v3_frame, None);
+const CTOC_V4_PARSER: (FrameParser, Option<StandardTagKey>) = (read_ctoc_v4_frame, None);
+
 lazy_static! {
     static ref LEGACY_FRAME_MAP: HashMap<&'static [u8; 3], &'static [u8; 4]> = {
         let mut m = HashMap::new();
@@ -183,10 +205,13 @@ lazy_static! {
         m.insert(b"EQU", b"EQUA");
         m.insert(b"ETC", b"ETCO");
         m.insert(b"GEO", b"GEOB");
+        m.insert(b"GP1", b"GRP1");
         m.insert(b"IPL", b"IPLS");
         m.insert(b"LNK", b"LINK");
         m.insert(b"MCI", b"MCDI");
         m.insert(b"MLL", b"MLLT");
+        m.insert(b"MVI", b"MVIN");
+        m.insert(b"MVN", b"MVNM");
         m.insert(b"PCS", b"PCST");
         m.insert(b"PIC", b"APIC");
         m.insert(b"POP", b"POPM");
@@ -198,13 +223,18 @@ lazy_static! {
         m.insert(b"TBP", b"TBPM");
         m.insert(b"TCM", b"TCOM");
         m.insert(b"TCO", b"TCON");
+        m.insert(b"TCP", b"TCMP");
         m.insert(b"TCR", b"TCOP");
+        m.insert(b"TCT", b"TCAT");
         m.insert(b"TDA", b"TDAT");
+        m.insert(b"TDS", b"TDES");
         m.insert(b"TDY", b"TDLY");
         m.insert(b"TEN", b"TENC");
         m.insert(b"TFT", b"TFLT");
+        m.insert(b"TID", b"TGID");
         m.insert(b"TIM", b"TIME");
         m.insert(b"TKE", b"TKEY");
+        m.insert(b"TKW", b"TKWD");
         m.insert(b"TLA", b"TLAN");
         m.insert(b"TLE", b"TLEN");
         m.insert(b"TMT", b"TMED");
@@ -242,6 +272,7 @@ lazy_static! {
         m.insert(b"WAS", b"WOAS");
         m.insert(b"WCM", b"WCOM");
         m.insert(b"WCP", b"WCOP");
+        m.insert(b"WFD", b"WFED");
         m.insert(b"WPB", b"WPUB");
         m.insert(b"WXX", b"WXXX");
         m
@@ -263,7 +294,7 @@ lazy_static! {
             // m.insert(b"ETCO", read_null_frame);
             // m.insert(b"GEOB", read_null_frame);
             // m.insert(b"GRID", read_null_frame);
-            m.insert(b"IPLS", (read_text_frame, None));
+            m.insert(b"IPLS", (read_text_frame, Some(StandardTagKey::InvolvedPeople)));
             // m.insert(b"LINK", read_null_frame);
             m.insert(b"MCDI", (read_mcdi_frame, None));
             // m.insert(b"MLLT", read_null_frame);
@@ -271,6 +302,7 @@ lazy_static! {
             m.insert(b"PCNT", (read_pcnt_frame, None));
             m.insert(b"POPM", (read_popm_frame, Some(StandardTagKey::Rating)));
             // m.insert(b"POSS", read_null_frame);
+            m.insert(b"PCST", (read_pcst_frame, Some(StandardTagKey::Podcast)));
             m.insert(b"PRIV", (read_priv_frame, None));
             // m.insert(b"RBUF", read_null_frame);
             // m.insert(b"RVA2", read_null_frame);
@@ -278,10 +310,11 @@ lazy_static! {
             // m.insert(b"RVRB", read_null_frame);
             // m.insert(b"SEEK", read_null_frame);
             // m.insert(b"SIGN", read_null_frame);
-            // m.insert(b"SYLT", read_null_frame);
+            m.insert(b"SYLT", (read_sylt_frame, Some(StandardTagKey::Lyrics)));
             // m.insert(b"SYTC", read_null_frame);
             m.insert(b"TALB", (read_text_frame, Some(StandardTagKey::Album)));
             m.insert(b"TBPM", (read_text_frame, Some(StandardTagKey::Bpm)));
+            m.insert(b"TCMP", (read_text_frame, Some(StandardTagKey::Compilation)));
             m.insert(b"TCOM", (read_text_frame, Some(StandardTagKey::Composer)));
             m.insert(b"TCON", (read_text_frame, Some(StandardTagKey::Genre)));
             m.insert(b"TCOP", (read_text_frame, Some(StandardTagKey::Copyright)));
@@ -297,14 +330,14 @@ lazy_static! {
             m.insert(b"TEXT", (read_text_frame, Some(StandardTagKey::Writer)));
             m.insert(b"TFLT", (read_text_frame, None));
             m.insert(b"TIME", (read_text_frame, Some(StandardTagKey::Date)));
-            m.insert(b"TIPL", (read_text_frame, None));
+            m.insert(b"TIPL", (read_text_frame, Some(StandardTagKey::InvolvedPeople)));
             m.insert(b"TIT1", (read_text_frame, Some(StandardTagKey::ContentGroup)));
             m.insert(b"TIT2", (read_text_frame, Some(StandardTagKey::TrackTitle)));
             m.insert(b"TIT3", (read_text_frame, Some(StandardTagKey::TrackSubtitle)));
             m.insert(b"TKEY", (read_text_frame, None));
             m.insert(b"TLAN", (read_text_frame, Some(StandardTagKey::Language)));
             m.insert(b"TLEN", (read_text_frame, None));
-            m.insert(b"TMCL", (read_text_frame, None));
+            m.insert(b"TMCL", (read_text_frame, Some(StandardTagKey::MusicianCredits)));
             m.insert(b"TMED", (read_text_frame, Some(StandardTagKey::MediaFormat)));
             m.insert(b"TMOO", (read_text_frame, Some(StandardTagKey::Mood)));
             m.insert(b"TOAL", (read_text_frame, Some(StandardTagKey::OriginalAlbum)));
@@ -332,7 +365,7 @@ lazy_static! {
             m.insert(b"TSOT", (read_text_frame, Some(StandardTagKey::SortTrackTitle)));
             m.insert(b"TSRC", (read_text_frame, Some(StandardTagKey::IdentIsrc)));
             m.insert(b"TSSE", (read_text_frame, Some(StandardTagKey::Encoder)));
-            m.insert(b"TSST", (read_text_frame, None));
+            m.insert(b"TSST", (read_text_frame, Some(StandardTagKey::DiscSubtitle)));
             m.insert(b"TXXX", (read_txxx_frame, None));
             m.insert(b"TYER", (read_text_frame, Some(StandardTagKey::Date)));
             // m.insert(b"UFID", read_null_frame);
@@ -349,7 +382,7 @@ lazy_static! {
             m.insert(b"WXXX", (read_wxxx_frame, Some(StandardTagKey::Url)));
             // Apple iTunes frames
             // m.insert(b"PCST", (read_null_frame, None));
-            m.insert(b"GRP1", (read_text_frame, None));
+            m.insert(b"GRP1", (read_text_frame, Some(StandardTagKey::ContentGroup)));
             m.insert(b"MVIN", (read_text_frame, Some(StandardTagKey::MovementNumber)));
             m.insert(b"MVNM", (read_text_frame, Some(StandardTagKey::MovementName)));
             m.insert(b"TCAT", (read_text_frame, Some(StandardTagKey::PodcastCategory)));
@@ -364,23 +397,37 @@ lazy_static! {
 }
 
 lazy_static! {
+    /// Map of the uppercase descriptions of TXXX frames to standard tag keys. The descriptions are
+    /// those written by MusicBrainz Picard.
     static ref TXXX_FRAME_STD_KEYS: HashMap<&'static str, StandardTagKey> = {
         let mut m = HashMap::new();
         m.insert("ACOUSTID FINGERPRINT", StandardTagKey::AcoustidFingerprint);
         m.insert("ACOUSTID ID", StandardTagKey::AcoustidId);
+        m.insert("ARTISTS", StandardTagKey::Artist);
+        m.insert("ASIN", StandardTagKey::IdentAsin);
         m.insert("BARCODE", StandardTagKey::IdentBarcode);
         m.insert("CATALOGNUMBER", StandardTagKey::IdentCatalogNumber);
         m.insert("LICENSE", StandardTagKey::License);
+        m.insert("MOVEMENTTOTAL", StandardTagKey::MovementTotal);
         m.insert("MUSICBRAINZ ALBUM ARTIST ID", StandardTagKey::MusicBrainzAlbumArtistId);
         m.insert("MUSICBRAINZ ALBUM ID", StandardTagKey::MusicBrainzAlbumId);
+        m.insert("MUSICBRAINZ ALBUM RELEASE COUNTRY", StandardTagKey::ReleaseCountry);
+        m.insert("MUSICBRAINZ ALBUM STATUS", StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("MUSICBRAINZ ALBUM TYPE", StandardTagKey::MusicBrainzReleaseType);
         m.insert("MUSICBRAINZ ARTIST ID", StandardTagKey::MusicBrainzArtistId);
+        m.insert("MUSICBRAINZ DISC ID", StandardTagKey::MusicBrainzDiscId);
+        m.insert("MUSICBRAINZ ORIGINAL ALBUM ID", StandardTagKey::MusicBrainzOriginalAlbumId);
+        m.insert("MUSICBRAINZ ORIGINAL ARTIST ID", StandardTagKey::MusicBrainzOriginalArtistId);
         m.insert("MUSICBRAINZ RELEASE GROUP ID", StandardTagKey::MusicBrainzReleaseGroupId);
+        m.insert("MUSICBRAINZ RELEASE TRACK ID", StandardTagKey::MusicBrainzReleaseTrackId);
         m.insert("MUSICBRAINZ WORK ID", StandardTagKey::MusicBrainzWorkId);
+        m.insert("ORIGINALDATE", StandardTagKey::OriginalDate);
         m.insert("REPLAYGAIN_ALBUM_GAIN", StandardTagKey::ReplayGainAlbumGain);
         m.insert("REPLAYGAIN_ALBUM_PEAK", StandardTagKey::ReplayGainAlbumPeak);
         m.insert("REPLAYGAIN_TRACK_GAIN", StandardTagKey::ReplayGainTrackGain);
         m.insert("REPLAYGAIN_TRACK_PEAK", StandardTagKey::ReplayGainTrackPeak);
         m.insert("SCRIPT", StandardTagKey::Script);
+        m.insert("WORK", StandardTagKey::Work);
         m
     };
 }
@@ -403,6 +450,23 @@ fn validate_lang_code(code: [u8; 3]) -> bool {
     code.iter().filter(|&c| *c < b'a' || *c > b'z').count() == 0
 }
 
+/// Reads an ISO-639-2 language code. Returns `None` if the language code is unknown or invalid.
+fn read_lang_code<B: ReadBytes>(reader: &mut B) -> Result<Option<String>> {
+    let mut lang = reader.read_triple_bytes()?;
+
+    // Many files use upper-case language codes, or "XXX" for an unknown language, neither of which
+    // are valid. Since many files also don't use valid ISO-639-2 language codes at all, skip the
+    // language code if it doesn't validate. Returning an error would break far too many files to
+    // be worth it.
+    lang.make_ascii_lowercase();
+
+    if !validate_lang_code(lang) || lang == *b"xxx" {
+        return Ok(None);
+    }
+
+    Ok(Some(as_ascii_str(&lang).to_string()))
+}
+
 /// Gets a slice of ASCII bytes as a string slice.
 ///
 /// Assumes the bytes are valid ASCII characters. Panics otherwise.
@@ -410,14 +474,46 @@ fn as_ascii_str(id: &[u8]) -> &str {
     std::str::from_utf8(id).unwrap()
 }
 
+/// Decompresses a zlib DEFLATE compressed frame body of at most `max_len` bytes once decompressed.
+/// Returns `None` if the frame body could not be decompressed.
+fn inflate_frame(data: &[u8], max_len: u32) -> Option<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, max_len as usize).ok()
+}
+
 /// Finds a frame parser for "modern" ID3v2.3 or ID3v2.4 tags.
 fn find_parser(id: [u8; 4]) -> Option<&'static (FrameParser, Option<StandardTagKey>)> {
     FRAME_PARSERS.get(&id)
 }
 
+/// Finds a frame parser for an ID3v2.3 tag.
+fn find_parser_v3(id: [u8; 4]) -> Option<&'static (FrameParser, Option<StandardTagKey>)> {
+    // The sub-frames of chapter and table of contents frames are encoded the same way as the frames
+    // of the tag itself, therefore their frame parsers are version-specific.
+    match &id {
+        b"CHAP" => Some(&CHAP_V3_PARSER),
+        b"CTOC" => Some(&CTOC_V3_PARSER),
+        _ => find_parser(id),
+    }
+}
+
+/// Finds a frame parser for an ID3v2.4 tag.
+fn find_parser_v4(id: [u8; 4]) -> Option<&'static (FrameParser, Option<StandardTagKey>)> {
+    match &id {
+        b"CHAP" => Some(&CHAP_V4_PARSER),
+        b"CTOC" => Some(&CTOC_V4_PARSER),
+        _ => find_parser(id),
+    }
+}
+
 /// Finds a frame parser for a "legacy" ID3v2.2 tag by finding an equivalent "modern" ID3v2.3+ frame
 /// parser.
 fn find_parser_legacy(id: [u8; 3]) -> Option<&'static (FrameParser, Option<StandardTagKey>)> {
+    // The attached picture frame of an ID3v2.2 tag is encoded differently than its ID3v2.3+
+    // equivalent, and therefore has its own frame parser.
+    if id == *b"PIC" {
+        return Some(&PIC_PARSER);
+    }
+
     match LEGACY_FRAME_MAP.get(&id) {
         Some(id) => find_parser(**id),
         _ => None,
@@ -490,7 +586,7 @@ pub fn read_id3v2p3_frame<B: ReadBytes>(reader: &mut B) -> Result<FrameResult> {
 
     // Find a parser for the frame. If there is none, skip over the remainder of the frame as it
     // cannot be parsed.
-    let (parser, std_key) = match find_parser(id) {
+    let (parser, std_key) = match find_parser_v3(id) {
         Some(p) => p,
         None => {
             reader.ignore_bytes(size)?;
@@ -498,19 +594,27 @@ pub fn read_id3v2p3_frame<B: ReadBytes>(reader: &mut B) -> Result<FrameResult> {
         }
     };
 
-    // Frame zlib DEFLATE compression usage flag.
-    // TODO: Implement decompression if it is actually used in the real world.
-    if flags & 0x80 != 0x0 {
+    // Frame encryption usage flag. This will likely never be supported since encryption methods are
+    // vendor-specific. Skip the frame instead of failing to read the remainder of the tag.
+    if flags & 0x40 != 0x0 {
         reader.ignore_bytes(size)?;
-        return unsupported_error("id3v2: compressed frames are not supported");
+        return unsupported_frame(&id);
     }
 
-    // Frame encryption usage flag. This will likely never be supported since encryption methods are
-    // vendor-specific.
-    if flags & 0x4 != 0x0 {
-        reader.ignore_bytes(size)?;
-        return unsupported_error("id3v2: encrypted frames are not supported");
+    // Frame zlib DEFLATE compression usage flag. If set, the size of the decompressed frame body
+    // follows the frame header.
+    let decompressed_len = if flags & 0x80 != 0x0 {
+        if size < 4 {
+            reader.ignore_bytes(size)?;
+            return invalid_data(&id);
+        }
+
+        size -= 4;
+        Some(reader.read_be_u32()?)
     }
+    else {
+        None
+    };
 
     // Frame group identifier byte. Used to group a set of frames. There is no analogue in
     // Symphonia.
@@ -526,10 +630,15 @@ pub fn read_id3v2p3_frame<B: ReadBytes>(reader: &mut B) -> Result<FrameResult> {
 
     let data = reader.read_boxed_slice_exact(size as usize)?;
 
-    parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id))
+    match decompressed_len {
+        Some(len) => match inflate_frame(&data, len) {
+            Some(data) => parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id)),
+            None => invalid_data(&id),
+        },
+        None => parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id)),
+    }
 }
 
-/// Read an ID3v2.4 frame.
 pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result<FrameResult> {
     let id = reader.read_quad_bytes()?;
 
@@ -555,7 +664,7 @@ pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result
 
     // Find a parser for the frame. If there is none, skip over the remainder of the frame as it
     // cannot be parsed.
-    let (parser, std_key) = match find_parser(id) {
+    let (parser, std_key) = match find_parser_v4(id) {
         Some(p) => p,
         None => {
             reader.ignore_bytes(size)?;
@@ -563,18 +672,11 @@ pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result
         }
     };
 
-    // Frame zlib DEFLATE compression usage flag.
-    // TODO: Implement decompression if it is actually used in the real world.
-    if flags & 0x8 != 0x0 {
-        reader.ignore_bytes(size)?;
-        return unsupported_error("id3v2: compressed frames are not supported");
-    }
-
     // Frame encryption usage flag. This will likely never be supported since encryption methods are
-    // vendor-specific.
+    // vendor-specific. Skip the frame instead of failing to read the remainder of the tag.
     if flags & 0x4 != 0x0 {
         reader.ignore_bytes(size)?;
-        return unsupported_error("id3v2: encrypted frames are not supported");
+        return unsupported_frame(&id);
     }
 
     // Frame group identifier byte. Used to group a set of frames. There is no analogue in
@@ -587,9 +689,11 @@ pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result
     // The data length indicator is optional in the frame header. This field indicates the original
     // size of the frame body before compression, encryption, and/or unsynchronisation. It is
     // mandatory if encryption or compression are used, but only encouraged for unsynchronisation.
-    // It's not that helpful, so we just ignore it.
+    // It's only used to limit the size of a decompressed frame body.
+    let mut data_len = None;
+
     if size >= 4 && (flags & 0x1) != 0x0 {
-        read_syncsafe_leq32(reader, 28)?;
+        data_len = Some(read_syncsafe_leq32(reader, 28)?);
         size -= 4;
     }
 
@@ -620,16 +724,19 @@ pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result
 
     // The frame body is unsynchronised. Decode the unsynchronised data back to it's original form
     // in-place before wrapping the decoded data in a BufStream for the frame parsers.
-    if flags & 0x2 != 0x0 {
-        let unsync_data = decode_unsynchronisation(&mut raw_data);
+    let data: &[u8] =
+        if flags & 0x2 != 0x0 { decode_unsynchronisation(&mut raw_data) } else { &raw_data };
 
-        parser(&mut BufReader::new(unsync_data), *std_key, as_ascii_str(&id))
-    }
-    // The frame body has not been unsynchronised. Wrap the raw data buffer in BufStream without any
-    // additional decoding.
-    else {
-        parser(&mut BufReader::new(&raw_data), *std_key, as_ascii_str(&id))
+    // The frame body is compressed. Since compression is applied before unsynchronisation, it must
+    // be decompressed after unsynchronisation was decoded.
+    if flags & 0x8 != 0x0 {
+        return match data_len.and_then(|len| inflate_frame(data, len)) {
+            Some(data) => parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id)),
+            None => invalid_data(&id),
+        };
     }
+
+    parser(&mut BufReader::new(data), *std_key, as_ascii_str(&id))
 }
 
 /// Reads all text frames frame except for `TXXX`.
@@ -682,8 +789,9 @@ fn read_txxx_frame(
     let desc = scan_text(reader, encoding, reader.bytes_available() as usize)?;
 
     // Some TXXX frames may be mapped to standard keys. Check if a standard key exists for the
-    // description.
-    let std_key = TXXX_FRAME_STD_KEYS.get(desc.as_ref()).copied();
+    // description. Writers do not agree on the case of the description, therefore, it is matched
+    // case-insensitively.
+    let std_key = TXXX_FRAME_STD_KEYS.get(desc.to_uppercase().as_str()).copied();
 
     // Generate a key name using the description.
     let key = format!("TXXX:{}", desc);
@@ -765,6 +873,134 @@ fn read_priv_frame(
     Ok(FrameResult::Tag(tag))
 }
 
+/// Reads a `CHAP` (chapter) frame from an ID3v2.3 tag.
+fn read_chap_v3_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    read_chap_frame(reader, 3)
+}
+
+/// Reads a `CHAP` (chapter) frame from an ID3v2.4 tag.
+fn read_chap_v4_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    read_chap_frame(reader, 4)
+}
+
+/// Reads a `CHAP` (chapter) frame, including its sub-frames, from a tag of the given major version.
+fn read_chap_frame(reader: &mut BufReader<'_>, major_version: u8) -> Result<FrameResult> {
+    // Scan for the null-terminated ISO-8859-1 element identifier.
+    let id =
+        scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?.into_owned();
+
+    let start_ms = reader.read_be_u32()?;
+    let end_ms = reader.read_be_u32()?;
+
+    // A byte offset of 0xffff_ffff indicates that the offset is not set, and the times should be
+    // used instead.
+    let start_offset = Some(reader.read_be_u32()?).filter(|&offset| offset != u32::MAX);
+    let end_offset = Some(reader.read_be_u32()?).filter(|&offset| offset != u32::MAX);
+
+    // The remainder of the frame is a sequence of sub-frames describing the chapter.
+    let (tags, visuals) = read_sub_frames(reader, major_version)?;
+
+    Ok(FrameResult::Chapter(Chapter {
+        id,
+        start_ms,
+        end_ms,
+        start_offset,
+        end_offset,
+        tags,
+        visuals,
+    }))
+}
+
+/// Reads a `CTOC` (table of contents) frame from an ID3v2.3 tag.
+fn read_ctoc_v3_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    read_ctoc_frame(reader, 3)
+}
+
+/// Reads a `CTOC` (table of contents) frame from an ID3v2.4 tag.
+fn read_ctoc_v4_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    read_ctoc_frame(reader, 4)
+}
+
+/// Reads a `CTOC` (table of contents) frame, including its sub-frames, from a tag of the given
+/// major version.
+fn read_ctoc_frame(reader: &mut BufReader<'_>, major_version: u8) -> Result<FrameResult> {
+    // Scan for the null-terminated ISO-8859-1 element identifier.
+    let id =
+        scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?.into_owned();
+
+    let flags = reader.read_u8()?;
+    let n_children = reader.read_u8()?;
+
+    // The element identifiers of the children are each null-terminated ISO-8859-1 strings.
+    let mut children = Vec::with_capacity(usize::from(n_children));
+
+    for _ in 0..n_children {
+        let child = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+        children.push(child.into_owned());
+    }
+
+    // The remainder of the frame is a sequence of sub-frames describing the table of contents.
+    // Visuals are not meaningful for a table of contents, and are ignored.
+    let (tags, _) = read_sub_frames(reader, major_version)?;
+
+    Ok(FrameResult::TableOfContents(TableOfContents {
+        id,
+        is_top_level: flags & 0x2 != 0,
+        is_ordered: flags & 0x1 != 0,
+        children,
+        tags,
+    }))
+}
+
+/// Reads the embedded sub-frames of a `CHAP` or `CTOC` frame from a tag of the given major version.
+fn read_sub_frames(
+    reader: &mut BufReader<'_>,
+    major_version: u8,
+) -> Result<(Vec<Tag>, Vec<Visual>)> {
+    let mut tags = Vec::new();
+    let mut visuals = Vec::new();
+
+    while reader.bytes_available() >= 10 {
+        let frame = match major_version {
+            3 => read_id3v2p3_frame(reader),
+            4 => read_id3v2p4_frame(reader),
+            _ => unreachable!(),
+        }?;
+
+        match frame {
+            FrameResult::Padding => break,
+            FrameResult::Tag(tag) => tags.push(tag),
+            FrameResult::MultipleTags(multi_tags) => tags.extend(multi_tags),
+            FrameResult::Visual(visual) => visuals.push(visual),
+            FrameResult::UnsupportedFrame(ref id) => {
+                info!("unsupported chapter sub-frame {}", id);
+            }
+            FrameResult::InvalidData(ref id) => {
+                warn!("invalid data for {} chapter sub-frame", id);
+            }
+            _ => (),
+        }
+    }
+
+    Ok((tags, visuals))
+}
+
 /// Reads a `COMM` (comment) or `USLT` (unsynchronized comment) frame.
 fn read_comm_uslt_frame(
     reader: &mut BufReader<'_>,
@@ -777,25 +1013,18 @@ fn read_comm_uslt_frame(
         _ => return decode_error("id3v2: invalid text encoding"),
     };
 
-    // The next three bytes are the language.
-    let lang = reader.read_triple_bytes()?;
-
-    // Encode the language into the key of the comment Tag. Since many files don't use valid
-    // ISO-639-2 language codes, we'll just skip the language code if it doesn't validate. Returning
-    // an error would break far too many files to be worth it.
-    let key = if validate_lang_code(lang) {
-        format!("{}!{}", id, as_ascii_str(&lang))
-    }
-    else {
-        id.to_string()
+    // The next three bytes are the language. Encode the language into the key of the Tag.
+    let key = match read_lang_code(reader)? {
+        Some(lang) => format!("{}!{}", id, lang),
+        None => id.to_string(),
     };
 
     // Short text (content description) is next, but since there is no way to represent this in
     // Symphonia, skip it.
     scan_text(reader, encoding, reader.bytes_available() as usize)?;
 
-    // Full text (lyrics) is last.
-    let text = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    // Full text (lyrics) is last. Unlike other text, it may span multiple lines.
+    let text = scan_multiline_text(reader, encoding, reader.bytes_available() as usize)?;
 
     // Create the tag.
     let tag = Tag::new(std_key, &key, Value::from(text));
@@ -803,6 +1032,67 @@ fn read_comm_uslt_frame(
     Ok(FrameResult::Tag(tag))
 }
 
+/// Reads a `SYLT` (synchronized lyrics) frame.
+fn read_sylt_frame(
+    reader: &mut BufReader<'_>,
+    std_key: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of all text in the frame.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // The next three bytes are the language. Like comments, encode the language into the key of
+    // the Tag.
+    let language = read_lang_code(reader)?;
+
+    let key = match &language {
+        Some(lang) => format!("{}!{}", id, lang),
+        None => id.to_string(),
+    };
+
+    let time_unit = match reader.read_u8()? {
+        1 => LyricsTimeUnit::MpegFrames,
+        2 => LyricsTimeUnit::Milliseconds,
+        _ => return decode_error("id3v2: invalid synchronized lyrics timestamp format"),
+    };
+
+    let content_type = match reader.read_u8()? {
+        1 => LyricsContentType::Lyrics,
+        2 => LyricsContentType::Transcription,
+        3 => LyricsContentType::PartName,
+        4 => LyricsContentType::Events,
+        5 => LyricsContentType::Chords,
+        6 => LyricsContentType::Trivia,
+        7 => LyricsContentType::WebpageUrls,
+        8 => LyricsContentType::ImageUrls,
+        _ => LyricsContentType::Other,
+    };
+
+    let description = scan_text(reader, encoding, reader.bytes_available() as usize)?.into_owned();
+
+    // The remainder of the frame is a sequence of text and timestamp pairs.
+    let mut lines = Vec::new();
+
+    while reader.bytes_available() > 0 {
+        let text = scan_multiline_text(reader, encoding, reader.bytes_available() as usize)?;
+        let timestamp = u64::from(reader.read_be_u32()?);
+
+        lines.push(LyricsLine { timestamp, text });
+    }
+
+    // The lines should be in chronological order, but are not required to be.
+    lines.sort_by_key(|line| line.timestamp);
+
+    let lyrics = Lyrics { language, description, content_type, time_unit, lines };
+
+    let tag = Tag::new(std_key, &key, Value::from(lyrics.to_string()));
+
+    Ok(FrameResult::Lyrics(tag, lyrics))
+}
+
 /// Reads a `PCNT` (total file play count) frame.
 fn read_pcnt_frame(
     reader: &mut BufReader<'_>,
@@ -850,10 +1140,10 @@ fn read_popm_frame(
     // There's a personalized play counter here, but there is no analogue in Symphonia so don't do
     // anything with it.
 
-    // Create the tag.
-    let tag = Tag::new(std_key, &key, Value::from(rating));
+    // Create the raw and normalized rating tags.
+    let tags = rating::make_tags(std_key, &key, Value::from(rating), rating::from_popm(rating));
 
-    Ok(FrameResult::Tag(tag))
+    Ok(FrameResult::MultipleTags(tags))
 }
 
 /// Reads a `MCDI` (music CD identifier) frame.
@@ -886,6 +1176,44 @@ fn read_apic_frame(
     let media_type =
         scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?.into_owned();
 
+    read_picture(reader, encoding, media_type)
+}
+
+/// Reads a `PIC` (attached picture) frame from an ID3v2.2 tag.
+fn read_pic_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the text description.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // Unlike the APIC frame, the PIC frame has a fixed-length image format instead of a media type.
+    let mut format = reader.read_triple_bytes()?;
+    format.make_ascii_uppercase();
+
+    let media_type = match &format {
+        b"JPG" => "image/jpeg".to_string(),
+        b"PNG" => "image/png".to_string(),
+        b"GIF" => "image/gif".to_string(),
+        b"BMP" => "image/bmp".to_string(),
+        // The image data is a URL to the image.
+        b"-->" => "-->".to_string(),
+        _ => format!("image/{}", String::from_utf8_lossy(&format).to_ascii_lowercase()),
+    };
+
+    read_picture(reader, encoding, media_type)
+}
+
+/// Reads the remainder of an `APIC` or `PIC` frame following the media type.
+fn read_picture(
+    reader: &mut BufReader<'_>,
+    encoding: Encoding,
+    media_type: String,
+) -> Result<FrameResult> {
     // Image usage.
     let usage = util::apic_picture_type_to_visual_key(u32::from(reader.read_u8()?));
 
@@ -894,7 +1222,7 @@ fn read_apic_frame(
 
     let tags = vec![Tag::new(Some(StandardTagKey::Description), "", Value::from(desc))];
 
-    // The remainder of the APIC frame is the image data.
+    // The remainder of the frame is the image data.
     // TODO: Apply a limit.
     let data = Box::from(reader.read_buf_bytes_available_ref());
 
@@ -911,6 +1239,19 @@ fn read_apic_frame(
     Ok(FrameResult::Visual(visual))
 }
 
+/// Reads a `PCST` (podcast) frame.
+fn read_pcst_frame(
+    reader: &mut BufReader<'_>,
+    std_key: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The frame body should be 4 zero bytes, however the presence of the frame alone marks the file
+    // as a podcast.
+    reader.ignore_bytes(reader.bytes_available())?;
+
+    Ok(FrameResult::Tag(Tag::new(std_key, id, Value::Flag)))
+}
+
 /// Enumeration of valid encodings for text fields in ID3v2 tags
 #[derive(Copy, Clone, Debug)]
 enum Encoding {
@@ -963,6 +1304,27 @@ fn scan_text<'a>(
     Ok(decode_text(encoding, buf))
 }
 
+/// Scans for a string like `scan_text`, but preserves line breaks as permitted in the full text of
+/// comments and lyrics. Line breaks are normalized to a single line feed.
+fn scan_multiline_text(
+    reader: &mut BufReader<'_>,
+    encoding: Encoding,
+    scan_len: usize,
+) -> io::Result<String> {
+    let text = match encoding {
+        // Decoding ISO-8859-1 text removes all control characters, including line breaks.
+        Encoding::Iso8859_1 => reader
+            .scan_bytes_aligned_ref(&[0x00], 1, scan_len)?
+            .iter()
+            .filter(|&b| *b > 0x1f || *b == b'\n' || *b == b'\r')
+            .map(|&b| b as char)
+            .collect(),
+        _ => scan_text(reader, encoding, scan_len)?.into_owned(),
+    };
+
+    Ok(text.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
 /// Decodes a slice of bytes containing encoded text into a UTF-8 `str`. Trailing null terminators
 /// are removed, and any invalid characters are replaced with the [U+FFFD REPLACEMENT CHARACTER].
 fn decode_text(encoding: Encoding, data: &[u8]) -> Cow<'_, str> {
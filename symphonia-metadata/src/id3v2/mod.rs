@@ -7,9 +7,13 @@
 
 //! An ID3v2 metadata reader.
 
+use std::io::{Seek, SeekFrom};
+
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::*;
-use symphonia_core::meta::{MetadataBuilder, MetadataOptions, MetadataReader, MetadataRevision};
+use symphonia_core::meta::{
+    MetadataBuilder, MetadataOptions, MetadataReader, MetadataRevision, Tag, Visual,
+};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_metadata;
 
@@ -21,6 +25,107 @@ mod unsync;
 use frames::*;
 use unsync::{read_syncsafe_leq32, UnsyncStream};
 
+/// The length of the header, or footer, of an ID3v2 tag.
+const ID3V2_HEADER_LEN: u64 = 10;
+
+/// A chapter read from an ID3v2 `CHAP` frame.
+#[derive(Clone, Debug)]
+pub struct Chapter {
+    /// The element identifier of the chapter. Unique within the tag.
+    pub id: String,
+    /// The start time of the chapter in milliseconds.
+    pub start_ms: u32,
+    /// The end time of the chapter in milliseconds.
+    pub end_ms: u32,
+    /// The byte offset of the first audio frame of the chapter, if set.
+    pub start_offset: Option<u32>,
+    /// The byte offset of the first audio frame following the chapter, if set.
+    pub end_offset: Option<u32>,
+    /// The tags read from the sub-frames of the chapter (e.g., its title).
+    pub tags: Vec<Tag>,
+    /// The visuals read from the sub-frames of the chapter (e.g., chapter artwork).
+    pub visuals: Vec<Visual>,
+}
+
+/// A table of contents read from an ID3v2 `CTOC` frame.
+#[derive(Clone, Debug)]
+pub struct TableOfContents {
+    /// The element identifier of the table of contents. Unique within the tag.
+    pub id: String,
+    /// If true, this is the root of the table of contents hierarchy.
+    pub is_top_level: bool,
+    /// If true, the child elements are listed in playback order.
+    pub is_ordered: bool,
+    /// The element identifiers of the child elements. A child element is either a chapter, or a
+    /// nested table of contents.
+    pub children: Vec<String>,
+    /// The tags read from the sub-frames of the table of contents (e.g., its title).
+    pub tags: Vec<Tag>,
+}
+
+/// The chapters, and tables of contents, read from an ID3v2 tag.
+#[derive(Clone, Debug, Default)]
+pub struct Chapters {
+    /// The chapters in the order they appear in the tag.
+    pub chapters: Vec<Chapter>,
+    /// The tables of contents in the order they appear in the tag.
+    pub tables_of_contents: Vec<TableOfContents>,
+}
+
+impl Chapters {
+    /// Consumes the chapters and returns them in playback order.
+    ///
+    /// If there is an ordered top-level table of contents, the chapters are ordered by a
+    /// depth-first walk of the table of contents hierarchy. Any chapters not referenced by the
+    /// hierarchy, or all chapters if the order is not known, are ordered by their start time.
+    pub fn into_ordered(self) -> Vec<Chapter> {
+        let Chapters { mut chapters, tables_of_contents } = self;
+
+        // Chapter frames may appear in any order within the tag.
+        chapters.sort_by_key(|chapter| chapter.start_ms);
+
+        let root = tables_of_contents.iter().find(|toc| toc.is_top_level && toc.is_ordered);
+
+        let mut ordered = Vec::with_capacity(chapters.len());
+
+        if let Some(root) = root {
+            let mut visited = Vec::new();
+            walk_toc(root, &tables_of_contents, &mut chapters, &mut visited, &mut ordered);
+        }
+
+        ordered.append(&mut chapters);
+        ordered
+    }
+}
+
+/// Moves the chapters referenced by a table of contents, and its nested tables of contents, from
+/// `chapters` to `ordered` in the order they are listed.
+fn walk_toc(
+    toc: &TableOfContents,
+    tables_of_contents: &[TableOfContents],
+    chapters: &mut Vec<Chapter>,
+    visited: &mut Vec<String>,
+    ordered: &mut Vec<Chapter>,
+) {
+    // Guard against malformed tags where a table of contents (indirectly) references itself.
+    if visited.contains(&toc.id) {
+        return;
+    }
+
+    visited.push(toc.id.clone());
+
+    for child in &toc.children {
+        if let Some(pos) = chapters.iter().position(|chapter| chapter.id == *child) {
+            ordered.push(chapters.remove(pos));
+        }
+        else if let Some(nested) = tables_of_contents.iter().find(|toc| toc.id == *child) {
+            // A nested table of contents is walked regardless of whether it is ordered since its
+            // position within the parent is.
+            walk_toc(nested, tables_of_contents, chapters, visited, ordered);
+        }
+    }
+}
+
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 enum TagSizeRestriction {
@@ -93,11 +198,11 @@ struct ExtendedHeader {
     restrictions: Option<Restrictions>,
 }
 
-/// Read the header of an ID3v2 (verions 2.2+) tag.
-fn read_id3v2_header<B: ReadBytes>(reader: &mut B) -> Result<Header> {
+/// Read the header of an ID3v2 (verions 2.2+) tag starting with the given marker.
+fn read_id3v2_header<B: ReadBytes>(reader: &mut B, expected_marker: &[u8; 3]) -> Result<Header> {
     let marker = reader.read_triple_bytes()?;
 
-    if marker != *b"ID3" {
+    if marker != *expected_marker {
         return unsupported_error("id3v2: not an ID3v2 tag");
     }
 
@@ -276,6 +381,7 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
     reader: &mut B,
     header: &Header,
     metadata: &mut MetadataBuilder,
+    chapters: &mut Chapters,
 ) -> Result<()> {
     // If there is an extended header, read and parse it based on the major version of the tag.
     if header.has_extended_header {
@@ -319,6 +425,19 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
             FrameResult::Visual(visual) => {
                 metadata.add_visual(visual);
             }
+            // A frame was parsed into a chapter, add it to the chapter collection.
+            FrameResult::Chapter(chapter) => {
+                chapters.chapters.push(chapter);
+            }
+            // A frame was parsed into a table of contents, add it to the chapter collection.
+            FrameResult::TableOfContents(toc) => {
+                chapters.tables_of_contents.push(toc);
+            }
+            // A frame was parsed into synchronized lyrics, add them and their tag.
+            FrameResult::Lyrics(tag, lyrics) => {
+                metadata.add_tag(tag);
+                metadata.add_lyrics(lyrics);
+            }
             // An unknown frame was encountered.
             FrameResult::UnsupportedFrame(ref id) => {
                 info!("unsupported frame {}", id);
@@ -339,24 +458,49 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
 }
 
 pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    read_id3v2_with_chapters(reader, metadata, &mut Chapters::default())
+}
+
+/// Reads an ID3v2 tag like `read_id3v2`, additionally appending any chapters, and tables of
+/// contents, in the tag to `chapters`.
+pub fn read_id3v2_with_chapters<B: ReadBytes>(
+    reader: &mut B,
+    metadata: &mut MetadataBuilder,
+    chapters: &mut Chapters,
+) -> Result<()> {
+    read_tag(reader, b"ID3", metadata, chapters)
+}
+
+/// Reads a Sony OpenMG (`ea3`) tag, as found at the start of OMA files. The tag is an ID3v2 tag
+/// with the marker `ea3` instead of `ID3`.
+pub fn read_ea3<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    read_tag(reader, b"ea3", metadata, &mut Chapters::default())
+}
+
+fn read_tag<B: ReadBytes>(
+    reader: &mut B,
+    marker: &[u8; 3],
+    metadata: &mut MetadataBuilder,
+    chapters: &mut Chapters,
+) -> Result<()> {
     // Read the (sorta) version agnostic tag header.
-    let header = read_id3v2_header(reader)?;
+    let header = read_id3v2_header(reader, marker)?;
 
     // If the unsynchronisation flag is set in the header, all tag data must be passed through the
     // unsynchronisation decoder before being read for verions < 4 of ID3v2.
     let mut scoped = if header.unsynchronisation && header.major_version < 4 {
-        let mut unsync = UnsyncStream::new(ScopedStream::new(reader, u64::from(header.size)));
+        let mut unsync = UnsyncStream::new(ScopedStream::new(&mut *reader, u64::from(header.size)));
 
-        read_id3v2_body(&mut unsync, &header, metadata)?;
+        read_id3v2_body(&mut unsync, &header, metadata, chapters)?;
 
         unsync.into_inner()
     }
     // Otherwise, read the data as-is. Individual frames may be unsynchronised for major versions
     // >= 4.
     else {
-        let mut scoped = ScopedStream::new(reader, u64::from(header.size));
+        let mut scoped = ScopedStream::new(&mut *reader, u64::from(header.size));
 
-        read_id3v2_body(&mut scoped, &header, metadata)?;
+        read_id3v2_body(&mut scoped, &header, metadata, chapters)?;
 
         scoped
     };
@@ -364,9 +508,52 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     // Ignore any remaining data in the tag.
     scoped.ignore()?;
 
+    // The footer is not included in the size of the tag.
+    if header.has_footer {
+        reader.ignore_bytes(ID3V2_HEADER_LEN)?;
+    }
+
     Ok(())
 }
 
+/// Locates an ID3v2 tag appended to a stream, or a region of a stream, that ends at `end`.
+///
+/// An appended tag is only allowed by ID3v2.4, and must have a footer. The `SEEK` frame of a tag at
+/// the start of the stream may also point to an appended tag, but since the footer is mandatory,
+/// the footer alone is used to locate the tag. If a tag is found, the reader is positioned at the
+/// start of the tag, and the total length of the tag, including the header and footer, is
+/// returned. The tag may then be read using `read_id3v2`.
+pub fn find_appended_id3v2<R: ReadBytes + Seek>(reader: &mut R, end: u64) -> Result<Option<u64>> {
+    if end < 2 * ID3V2_HEADER_LEN {
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(end - ID3V2_HEADER_LEN))?;
+
+    let mut buf = [0; ID3V2_HEADER_LEN as usize];
+    reader.read_buf_exact(&mut buf)?;
+
+    if buf[..3] != *b"3DI" {
+        return Ok(None);
+    }
+
+    let footer = read_id3v2_header(&mut BufReader::new(&buf), b"3DI")?;
+
+    if !footer.has_footer {
+        return decode_error("id3v2: invalid tag footer");
+    }
+
+    let tag_len = u64::from(footer.size) + 2 * ID3V2_HEADER_LEN;
+
+    if tag_len > end {
+        return decode_error("id3v2: appended tag exceeds the length of the stream");
+    }
+
+    reader.seek(SeekFrom::Start(end - tag_len))?;
+
+    Ok(Some(tag_len))
+}
+
 pub mod util {
     use symphonia_core::meta::StandardVisualKey;
 
@@ -420,3 +607,302 @@ impl MetadataReader for Id3v2Reader {
         Ok(builder.metadata())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{find_appended_id3v2, read_id3v2, read_id3v2_with_chapters, Chapters};
+    use symphonia_core::io::{BufReader, MediaSourceStream, ReadBytes};
+    use symphonia_core::meta::{
+        LyricsContentType, MetadataBuilder, StandardTagKey, StandardVisualKey,
+    };
+
+    /// Builds an ID3v2.4 tag with a footer, and a single title frame.
+    fn tag_with_footer(title: &str) -> Vec<u8> {
+        let mut frame = b"TIT2".to_vec();
+        frame.extend_from_slice(&[0, 0, 0, title.len() as u8 + 1, 0, 0, 3]);
+        frame.extend_from_slice(title.as_bytes());
+
+        let mut tag = b"ID3\x04\x00\x10".to_vec();
+        tag.extend_from_slice(&[0, 0, 0, frame.len() as u8]);
+        tag.extend_from_slice(&frame);
+        tag.extend_from_slice(b"3DI\x04\x00\x10");
+        tag.extend_from_slice(&[0, 0, 0, frame.len() as u8]);
+        tag
+    }
+
+    #[test]
+    fn verify_find_appended_id3v2() {
+        let tag = tag_with_footer("Title");
+
+        let mut buf = vec![0xff; 64];
+        buf.extend_from_slice(&tag);
+        buf.extend_from_slice(&[0xff; 16]);
+
+        let end = buf.len() as u64 - 16;
+
+        let mut reader =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        assert_eq!(find_appended_id3v2(&mut reader, end).unwrap(), Some(tag.len() as u64));
+        assert_eq!(reader.pos(), 64);
+
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut reader, &mut builder).unwrap();
+
+        // The footer is consumed with the tag.
+        assert_eq!(reader.pos(), end);
+        assert_eq!(builder.metadata().tags()[0].std_key, Some(StandardTagKey::TrackTitle));
+
+        // No tag ends at the end of the stream.
+        assert_eq!(find_appended_id3v2(&mut reader, end + 16).unwrap(), None);
+    }
+
+    /// Builds an ID3v2.3 frame.
+    fn frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        frame_with_flags(id, 0, body)
+    }
+
+    fn frame_with_flags(id: &[u8; 4], flags: u16, body: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&flags.to_be_bytes());
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn chap_frame(id: &str, start_ms: u32, end_ms: u32) -> Vec<u8> {
+        let mut body = id.as_bytes().to_vec();
+        body.push(0);
+        body.extend_from_slice(&start_ms.to_be_bytes());
+        body.extend_from_slice(&end_ms.to_be_bytes());
+        body.extend_from_slice(&[0xff; 8]);
+        body.extend_from_slice(&frame(b"TIT2", format!("\x03{}\x00", id).as_bytes()));
+        frame(b"CHAP", &body)
+    }
+
+    fn ctoc_frame(id: &str, flags: u8, children: &[&str]) -> Vec<u8> {
+        let mut body = id.as_bytes().to_vec();
+        body.extend_from_slice(&[0, flags, children.len() as u8]);
+        for child in children {
+            body.extend_from_slice(child.as_bytes());
+            body.push(0);
+        }
+        frame(b"CTOC", &body)
+    }
+
+    fn read_tag(frames: &[Vec<u8>], builder: &mut MetadataBuilder) -> Chapters {
+        read_tag_with_flags(0, &frames.concat(), builder)
+    }
+
+    /// Reads an ID3v2.3 tag with the given header flags, and body.
+    fn read_tag_with_flags(flags: u8, body: &[u8], builder: &mut MetadataBuilder) -> Chapters {
+        let mut tag = b"ID3\x03\x00".to_vec();
+        tag.extend_from_slice(&[flags, 0, 0, (body.len() >> 7) as u8, (body.len() & 0x7f) as u8]);
+        tag.extend_from_slice(body);
+
+        let mut chapters = Chapters::default();
+        read_id3v2_with_chapters(&mut BufReader::new(&tag), builder, &mut chapters).unwrap();
+        chapters
+    }
+
+    fn read_chapters(frames: &[Vec<u8>]) -> Chapters {
+        read_tag(frames, &mut MetadataBuilder::new())
+    }
+
+    #[test]
+    fn verify_chapter_order() {
+        let ids = |chapters: Chapters| {
+            chapters.into_ordered().into_iter().map(|chapter| chapter.id).collect::<Vec<_>>()
+        };
+
+        let chapters = [chap_frame("c", 2000, 3000), chap_frame("a", 0, 1000)];
+
+        // Without a table of contents, chapters are ordered by start time.
+        let toc = read_chapters(&chapters);
+        assert_eq!(toc.chapters[0].tags[0].value.to_string(), "c");
+        assert_eq!(ids(toc), ["a", "c"]);
+
+        // An ordered top-level table of contents, with a nested table of contents, determines the
+        // order. Unreferenced chapters follow in order of start time.
+        let toc = read_chapters(&[
+            ctoc_frame("nested", 0x1, &["b", "toc"]),
+            ctoc_frame("toc", 0x3, &["c", "nested"]),
+            chap_frame("d", 3000, 4000),
+            chap_frame("b", 1000, 2000),
+            chapters.concat(),
+        ]);
+        assert!(toc.tables_of_contents[1].is_top_level);
+        assert_eq!(toc.tables_of_contents[0].children, ["b", "toc"]);
+        assert_eq!(ids(toc), ["c", "b", "a", "d"]);
+
+        // An unordered table of contents does not determine the order.
+        let toc = read_chapters(&[ctoc_frame("toc", 0x2, &["c", "a"]), chapters.concat()]);
+        assert_eq!(ids(toc), ["a", "c"]);
+    }
+
+    #[test]
+    fn verify_std_keys() {
+        let frames = [
+            // TXXX descriptions are matched case-insensitively.
+            frame(b"TXXX", b"\x00MusicBrainz Album Id\x00abcd"),
+            frame(b"TXXX", b"\x00WORK\x00Symphony No. 9"),
+            frame(b"TXXX", b"\x00Custom\x00Value"),
+            frame(b"TIPL", b"\x00producer\x00Someone"),
+            frame(b"TMCL", b"\x00piano\x00Someone"),
+        ];
+
+        let mut builder = MetadataBuilder::new();
+        read_tag(&frames, &mut builder);
+
+        let metadata = builder.metadata();
+        let keys = metadata.tags().iter().map(|tag| tag.std_key).collect::<Vec<_>>();
+
+        assert_eq!(
+            keys,
+            [
+                Some(StandardTagKey::MusicBrainzAlbumId),
+                Some(StandardTagKey::Work),
+                None,
+                Some(StandardTagKey::InvolvedPeople),
+                Some(StandardTagKey::InvolvedPeople),
+                Some(StandardTagKey::MusicianCredits),
+                Some(StandardTagKey::MusicianCredits),
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_lyrics() {
+        // Unsynchronized lyrics with an upper-case language code and CRLF line breaks.
+        let uslt = frame(b"USLT", b"\x00ENG\x00One\r\nTwo\x00");
+
+        // Synchronized lyrics with millisecond timestamps, out of order.
+        let mut body = b"\x03XXX\x02\x01Verse\x00".to_vec();
+        body.extend_from_slice(b"\nTwo\x00\x00\x00\x07\xd0");
+        body.extend_from_slice(b"One\x00\x00\x00\x03\xe8");
+        let sylt = frame(b"SYLT", &body);
+
+        let mut builder = MetadataBuilder::new();
+        read_tag(&[uslt, sylt], &mut builder);
+
+        let metadata = builder.metadata();
+        let tags = metadata.tags();
+
+        assert_eq!(tags[0].key, "USLT!eng");
+        assert_eq!(tags[0].value.to_string(), "One\nTwo");
+
+        assert_eq!(tags[1].key, "SYLT");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Lyrics));
+        assert_eq!(tags[1].value.to_string(), "[00:01.000] One\n[00:02.000] Two");
+
+        let lyrics = &metadata.lyrics()[0];
+
+        assert_eq!(lyrics.language, None);
+        assert_eq!(lyrics.description, "Verse");
+        assert_eq!(lyrics.content_type, LyricsContentType::Lyrics);
+        assert_eq!(lyrics.lines[1].text, "\nTwo");
+    }
+
+    #[test]
+    fn verify_v3_frame_flags() {
+        let mut compressed = 7u32.to_be_bytes().to_vec();
+        compressed
+            .extend_from_slice(b"\x78\x9c\x63\x0e\xc9\x2c\xc9\x49\x65\x00\x00\x07\xff\x02\x06");
+
+        let frames = [
+            // A compressed title.
+            frame_with_flags(b"TIT2", 0x0080, &compressed),
+            // An encrypted album, using encryption method 0x80.
+            frame_with_flags(b"TALB", 0x0040, b"\x80\x12\x34\x56"),
+            // A grouped artist, in group 0x90.
+            frame_with_flags(b"TPE1", 0x0020, b"\x90\x03Artist\x00"),
+        ];
+
+        let mut builder = MetadataBuilder::new();
+        read_tag(&frames, &mut builder);
+
+        let metadata = builder.metadata();
+        let tags = metadata.tags();
+
+        // The encrypted frame is skipped, but the remainder of the tag is read.
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[0].value.to_string(), "Title");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Artist));
+        assert_eq!(tags[1].value.to_string(), "Artist");
+    }
+
+    #[test]
+    fn verify_v3_unsynchronisation() {
+        // An unsupported frame containing false synchronisation followed by a title.
+        let body = [frame(b"XXXX", b"\xff\xe0\xff\x00"), frame(b"TIT2", b"\x00T\xff\x00")].concat();
+
+        // Unsynchronise the tag body by inserting a 0x00 after every 0xff.
+        let mut unsync = Vec::new();
+
+        for &byte in &body {
+            unsync.push(byte);
+
+            if byte == 0xff {
+                unsync.push(0x00);
+            }
+        }
+
+        let mut builder = MetadataBuilder::new();
+        read_tag_with_flags(0x80, &unsync, &mut builder);
+
+        let metadata = builder.metadata();
+
+        assert_eq!(metadata.tags()[0].value.to_string(), "T\u{ff}");
+    }
+
+    #[test]
+    fn verify_v2_frames() {
+        let frame = |id: &[u8; 3], body: &[u8]| {
+            let mut frame = id.to_vec();
+            frame.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            frame.extend_from_slice(body);
+            frame
+        };
+
+        let body = [
+            frame(b"TT2", b"\x00Title\x00"),
+            frame(b"TP1", b"\x00Artist\x00"),
+            frame(b"TCP", b"\x001\x00"),
+            frame(b"COM", b"\x00eng\x00Comment\x00"),
+            frame(b"PCS", b"\x00\x00\x00\x00"),
+            frame(b"PIC", b"\x00PNG\x03Cover\x00\x89PNG"),
+        ]
+        .concat();
+
+        let mut tag = b"ID3\x02\x00\x00\x00\x00".to_vec();
+        tag.extend_from_slice(&[(body.len() >> 7) as u8, (body.len() & 0x7f) as u8]);
+        tag.extend_from_slice(&body);
+
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut BufReader::new(&tag), &mut builder).unwrap();
+
+        let metadata = builder.metadata();
+
+        let std_keys = metadata.tags().iter().map(|tag| tag.std_key).collect::<Vec<_>>();
+
+        assert_eq!(
+            std_keys,
+            [
+                Some(StandardTagKey::TrackTitle),
+                Some(StandardTagKey::Artist),
+                Some(StandardTagKey::Compilation),
+                Some(StandardTagKey::Comment),
+                Some(StandardTagKey::Podcast),
+            ]
+        );
+        assert_eq!(metadata.tags()[3].key, "COM!eng");
+
+        let visual = &metadata.visuals()[0];
+
+        assert_eq!(visual.media_type, "image/png");
+        assert_eq!(visual.usage, Some(StandardVisualKey::FrontCover));
+        assert_eq!(visual.tags[0].value.to_string(), "Cover");
+        assert_eq!(&visual.data[..], b"\x89PNG");
+    }
+}
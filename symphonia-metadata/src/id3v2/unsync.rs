@@ -172,8 +172,9 @@ impl<B: ReadBytes + FiniteStream> ReadBytes for UnsyncStream<B> {
     }
 
     fn ignore_bytes(&mut self, count: u64) -> io::Result<()> {
+        // The count is the number of decoded bytes to ignore, therefore the bytes must be decoded.
         for _ in 0..count {
-            self.inner.read_byte()?;
+            self.read_byte()?;
         }
         Ok(())
     }
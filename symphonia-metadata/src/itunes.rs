@@ -14,59 +14,108 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 
 lazy_static! {
+    /// Map of the fully qualified, lowercase, names of free-form tags to standard tag keys. The
+    /// names are those written by MusicBrainz Picard.
     static ref ITUNES_TAG_MAP: HashMap<&'static str, StandardTagKey> = {
         let mut m = HashMap::new();
-        m.insert("com.apple.iTunes:ARTISTS", StandardTagKey::Artist);
-        m.insert("com.apple.iTunes:ASIN", StandardTagKey::IdentAsin);
-        m.insert("com.apple.iTunes:BARCODE", StandardTagKey::IdentBarcode);
-        m.insert("com.apple.iTunes:CATALOGNUMBER", StandardTagKey::IdentCatalogNumber);
-        m.insert("com.apple.iTunes:CONDUCTOR", StandardTagKey::Conductor);
-        m.insert("com.apple.iTunes:DISCSUBTITLE", StandardTagKey::DiscSubtitle);
-        m.insert("com.apple.iTunes:DJMIXER", StandardTagKey::MixDj);
-        m.insert("com.apple.iTunes:ENGINEER", StandardTagKey::Engineer);
-        m.insert("com.apple.iTunes:ISRC", StandardTagKey::IdentIsrc);
-        m.insert("com.apple.iTunes:LABEL", StandardTagKey::Label);
-        m.insert("com.apple.iTunes:LANGUAGE", StandardTagKey::Language);
-        m.insert("com.apple.iTunes:LICENSE", StandardTagKey::License);
-        m.insert("com.apple.iTunes:LYRICIST", StandardTagKey::Lyricist);
-        m.insert("com.apple.iTunes:MEDIA", StandardTagKey::MediaFormat);
-        m.insert("com.apple.iTunes:MIXER", StandardTagKey::MixEngineer);
-        m.insert("com.apple.iTunes:MOOD", StandardTagKey::Mood);
+        m.insert("com.apple.itunes:acoustid_fingerprint", StandardTagKey::AcoustidFingerprint);
+        m.insert("com.apple.itunes:acoustid_id", StandardTagKey::AcoustidId);
+        m.insert("com.apple.itunes:arranger", StandardTagKey::Arranger);
+        m.insert("com.apple.itunes:artists", StandardTagKey::Artist);
+        m.insert("com.apple.itunes:asin", StandardTagKey::IdentAsin);
+        m.insert("com.apple.itunes:barcode", StandardTagKey::IdentBarcode);
+        m.insert("com.apple.itunes:catalognumber", StandardTagKey::IdentCatalogNumber);
+        m.insert("com.apple.itunes:conductor", StandardTagKey::Conductor);
+        m.insert("com.apple.itunes:discsubtitle", StandardTagKey::DiscSubtitle);
+        m.insert("com.apple.itunes:djmixer", StandardTagKey::MixDj);
+        m.insert("com.apple.itunes:engineer", StandardTagKey::Engineer);
+        m.insert("com.apple.itunes:isrc", StandardTagKey::IdentIsrc);
+        m.insert("com.apple.itunes:label", StandardTagKey::Label);
+        m.insert("com.apple.itunes:language", StandardTagKey::Language);
+        m.insert("com.apple.itunes:license", StandardTagKey::License);
+        m.insert("com.apple.itunes:lyricist", StandardTagKey::Lyricist);
+        m.insert("com.apple.itunes:media", StandardTagKey::MediaFormat);
+        m.insert("com.apple.itunes:mixer", StandardTagKey::MixEngineer);
+        m.insert("com.apple.itunes:mood", StandardTagKey::Mood);
         m.insert(
-            "com.apple.iTunes:MusicBrainz Album Artist Id",
+            "com.apple.itunes:musicbrainz album artist id",
             StandardTagKey::MusicBrainzAlbumArtistId,
         );
-        m.insert("com.apple.iTunes:MusicBrainz Album Id", StandardTagKey::MusicBrainzAlbumId);
+        m.insert("com.apple.itunes:musicbrainz album id", StandardTagKey::MusicBrainzAlbumId);
         m.insert(
-            "com.apple.iTunes:MusicBrainz Album Release Country",
+            "com.apple.itunes:musicbrainz album release country",
             StandardTagKey::ReleaseCountry,
         );
         m.insert(
-            "com.apple.iTunes:MusicBrainz Album Status",
+            "com.apple.itunes:musicbrainz album status",
             StandardTagKey::MusicBrainzReleaseStatus,
         );
-        m.insert("com.apple.iTunes:MusicBrainz Album Type", StandardTagKey::MusicBrainzReleaseType);
-        m.insert("com.apple.iTunes:MusicBrainz Artist Id", StandardTagKey::MusicBrainzArtistId);
+        m.insert("com.apple.itunes:musicbrainz album type", StandardTagKey::MusicBrainzReleaseType);
+        m.insert("com.apple.itunes:musicbrainz artist id", StandardTagKey::MusicBrainzArtistId);
+        m.insert("com.apple.itunes:musicbrainz disc id", StandardTagKey::MusicBrainzDiscId);
+        m.insert("com.apple.itunes:musicbrainz label id", StandardTagKey::MusicBrainzLabelId);
         m.insert(
-            "com.apple.iTunes:MusicBrainz Release Group Id",
+            "com.apple.itunes:musicbrainz original album id",
+            StandardTagKey::MusicBrainzOriginalAlbumId,
+        );
+        m.insert(
+            "com.apple.itunes:musicbrainz original artist id",
+            StandardTagKey::MusicBrainzOriginalArtistId,
+        );
+        m.insert(
+            "com.apple.itunes:musicbrainz release group id",
             StandardTagKey::MusicBrainzReleaseGroupId,
         );
         m.insert(
-            "com.apple.iTunes:MusicBrainz Release Track Id",
+            "com.apple.itunes:musicbrainz release track id",
             StandardTagKey::MusicBrainzReleaseTrackId,
         );
-        m.insert("com.apple.iTunes:MusicBrainz Track Id", StandardTagKey::MusicBrainzTrackId);
-        m.insert("com.apple.iTunes:MusicBrainz Work Id", StandardTagKey::MusicBrainzWorkId);
-        m.insert("com.apple.iTunes:originaldate", StandardTagKey::OriginalDate);
-        m.insert("com.apple.iTunes:PRODUCER", StandardTagKey::Producer);
-        m.insert("com.apple.iTunes:REMIXER", StandardTagKey::Remixer);
-        m.insert("com.apple.iTunes:SCRIPT", StandardTagKey::Script);
-        m.insert("com.apple.iTunes:SUBTITLE", StandardTagKey::TrackSubtitle);
+        m.insert("com.apple.itunes:musicbrainz track id", StandardTagKey::MusicBrainzTrackId);
+        m.insert("com.apple.itunes:musicbrainz work id", StandardTagKey::MusicBrainzWorkId);
+        m.insert("com.apple.itunes:originaldate", StandardTagKey::OriginalDate);
+        m.insert("com.apple.itunes:producer", StandardTagKey::Producer);
+        m.insert("com.apple.itunes:remixer", StandardTagKey::Remixer);
+        m.insert("com.apple.itunes:replaygain_album_gain", StandardTagKey::ReplayGainAlbumGain);
+        m.insert("com.apple.itunes:replaygain_album_peak", StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("com.apple.itunes:replaygain_track_gain", StandardTagKey::ReplayGainTrackGain);
+        m.insert("com.apple.itunes:replaygain_track_peak", StandardTagKey::ReplayGainTrackPeak);
+        m.insert("com.apple.itunes:script", StandardTagKey::Script);
+        m.insert("com.apple.itunes:subtitle", StandardTagKey::TrackSubtitle);
+        m.insert("com.apple.itunes:writer", StandardTagKey::Writer);
         m
     };
 }
 
-/// Try to map the iTunes `tag` name to a `StandardTagKey`.
+/// Try to map the fully qualified iTunes free-form tag name (`mean:name`) to a `StandardTagKey`.
+///
+/// Writers do not agree on the case of free-form tag names, therefore, the name is matched
+/// case-insensitively.
 pub fn std_key_from_tag(key: &str) -> Option<StandardTagKey> {
-    ITUNES_TAG_MAP.get(key).copied()
+    ITUNES_TAG_MAP.get(key.to_lowercase().as_str()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::std_key_from_tag;
+    use symphonia_core::meta::StandardTagKey;
+
+    #[test]
+    fn verify_std_key_from_tag() {
+        assert_eq!(
+            std_key_from_tag("com.apple.iTunes:MusicBrainz Track Id"),
+            Some(StandardTagKey::MusicBrainzTrackId)
+        );
+        assert_eq!(
+            std_key_from_tag("com.apple.iTunes:replaygain_track_gain"),
+            Some(StandardTagKey::ReplayGainTrackGain)
+        );
+        assert_eq!(
+            std_key_from_tag("com.apple.iTunes:REPLAYGAIN_ALBUM_PEAK"),
+            Some(StandardTagKey::ReplayGainAlbumPeak)
+        );
+        assert_eq!(std_key_from_tag("com.apple.iTunes:LABEL"), Some(StandardTagKey::Label));
+        assert_eq!(std_key_from_tag("com.apple.iTunes:isrc"), Some(StandardTagKey::IdentIsrc));
+        assert_eq!(std_key_from_tag("com.apple.iTunes:Custom"), None);
+        assert_eq!(std_key_from_tag("org.example:LABEL"), None);
+    }
 }
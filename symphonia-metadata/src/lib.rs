@@ -14,9 +14,13 @@
 #![allow(clippy::identity_op)]
 #![allow(clippy::manual_range_contains)]
 
+pub mod apev2;
+pub mod asf;
 pub mod flac;
 pub mod id3v1;
 pub mod id3v2;
 pub mod itunes;
+pub mod lyrics3;
+pub mod rating;
 pub mod riff;
 pub mod vorbis;
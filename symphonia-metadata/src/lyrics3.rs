@@ -0,0 +1,261 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A Lyrics3v2 metadata reader.
+//!
+//! A Lyrics3v2 tag is found at the end of old MP3 files, between the audio data and the ID3v1 tag.
+//! The tag starts with `LYRICSBEGIN`, and is followed by a list of fields. Each field is composed
+//! of a 3 character identifier, a 5 digit length, and the field data. The tag ends with a 6 digit
+//! length, and `LYRICS200`.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, FiniteStream, ReadBytes};
+use symphonia_core::meta::{
+    Lyrics, LyricsContentType, LyricsLine, LyricsTimeUnit, MetadataBuilder, StandardTagKey, Tag,
+    Value,
+};
+
+/// The length of the Lyrics3v2 footer: a 6 digit length, and `LYRICS200`.
+const FOOTER_LEN: u64 = 15;
+
+/// The length of the `LYRICSBEGIN` marker.
+const MARKER_LEN: usize = 11;
+
+/// Locates a Lyrics3v2 tag that ends at `end`.
+///
+/// If a tag is found, the reader is positioned at the start of the tag, and the total length of
+/// the tag, including the footer, is returned. The tag may then be read using `read_lyrics3v2`.
+pub fn find_lyrics3v2<R: ReadBytes + Seek>(reader: &mut R, end: u64) -> Result<Option<u64>> {
+    if end < FOOTER_LEN + MARKER_LEN as u64 {
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(end - FOOTER_LEN))?;
+
+    let mut footer = [0; FOOTER_LEN as usize];
+    reader.read_buf_exact(&mut footer)?;
+
+    if footer[6..] != *b"LYRICS200" {
+        return Ok(None);
+    }
+
+    // The length of the tag, excluding the footer.
+    let len = match parse_decimal(&footer[..6]) {
+        Some(len) if len >= MARKER_LEN as u64 => len,
+        _ => return decode_error("lyrics3: invalid tag length"),
+    };
+
+    let tag_len = len + FOOTER_LEN;
+
+    if tag_len > end {
+        return decode_error("lyrics3: tag exceeds the length of the stream");
+    }
+
+    reader.seek(SeekFrom::Start(end - tag_len))?;
+
+    Ok(Some(tag_len))
+}
+
+/// Reads a Lyrics3v2 tag of length `tag_len`, including the footer, as returned by
+/// `find_lyrics3v2`.
+pub fn read_lyrics3v2<B: ReadBytes>(
+    reader: &mut B,
+    tag_len: u64,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    let buf = reader.read_boxed_slice_exact(tag_len as usize)?;
+
+    if buf[..MARKER_LEN] != *b"LYRICSBEGIN" {
+        return decode_error("lyrics3: missing tag marker");
+    }
+
+    let mut reader = BufReader::new(&buf[MARKER_LEN..buf.len() - FOOTER_LEN as usize]);
+
+    while reader.bytes_available() > 0 {
+        let mut header = [0; 8];
+        reader.read_buf_exact(&mut header)?;
+
+        let len = match parse_decimal(&header[3..]) {
+            Some(len) => len as usize,
+            None => return decode_error("lyrics3: invalid field length"),
+        };
+
+        let id = String::from_utf8_lossy(&header[..3]);
+        let text = decode_iso8859_text(reader.read_buf_bytes_ref(len)?);
+
+        // Empty fields carry no information.
+        if text.is_empty() {
+            continue;
+        }
+
+        let (std_key, value) = match id.as_ref() {
+            "LYR" => match parse_lyrics(&text) {
+                Some(lyrics) => {
+                    let value = Value::from(lyrics.to_string());
+                    metadata.add_lyrics(lyrics);
+                    (Some(StandardTagKey::Lyrics), value)
+                }
+                None => (Some(StandardTagKey::Lyrics), Value::from(text)),
+            },
+            "INF" => (Some(StandardTagKey::Comment), Value::from(text)),
+            "AUT" => (Some(StandardTagKey::Lyricist), Value::from(text)),
+            "EAL" => (Some(StandardTagKey::Album), Value::from(text)),
+            "EAR" => (Some(StandardTagKey::Artist), Value::from(text)),
+            "ETT" => (Some(StandardTagKey::TrackTitle), Value::from(text)),
+            _ => (None, Value::from(text)),
+        };
+
+        metadata.add_tag(Tag::new(std_key, &id, value));
+    }
+
+    Ok(())
+}
+
+/// Parses the lyrics field. If the lines of the lyrics are prefixed by `[mm:ss]` timestamps, the
+/// synchronized lyrics are returned, otherwise `None` is returned.
+fn parse_lyrics(text: &str) -> Option<Lyrics> {
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        // A line may have more than one timestamp if it is repeated.
+        let mut rest = line;
+        let mut timestamps = Vec::new();
+
+        while let Some(timestamp) = parse_timestamp(rest) {
+            timestamps.push(timestamp);
+            rest = &rest[7..];
+        }
+
+        // If any line is not synchronized, then the lyrics are not synchronized.
+        if timestamps.is_empty() {
+            return None;
+        }
+
+        for timestamp in timestamps {
+            lines.push(LyricsLine { timestamp, text: rest.to_string() });
+        }
+    }
+
+    lines.sort_by_key(|line| line.timestamp);
+
+    Some(Lyrics {
+        language: None,
+        description: String::new(),
+        content_type: LyricsContentType::Lyrics,
+        time_unit: LyricsTimeUnit::Milliseconds,
+        lines,
+    })
+}
+
+/// Parses a `[mm:ss]` timestamp at the start of the line, and returns it in milliseconds.
+fn parse_timestamp(line: &str) -> Option<u64> {
+    let buf = line.as_bytes();
+
+    if buf.len() < 7 || buf[0] != b'[' || buf[3] != b':' || buf[6] != b']' {
+        return None;
+    }
+
+    let mins = parse_decimal(&buf[1..3])?;
+    let secs = parse_decimal(&buf[4..6])?;
+
+    Some(1000 * (60 * mins + secs))
+}
+
+/// Parses a fixed-length decimal number.
+fn parse_decimal(buf: &[u8]) -> Option<u64> {
+    if !buf.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    Some(buf.iter().fold(0, |acc, &digit| 10 * acc + u64::from(digit - b'0')))
+}
+
+/// Decodes ISO-8859-1 text, and normalizes line breaks to line feeds.
+fn decode_iso8859_text(buf: &[u8]) -> String {
+    let text: String = buf.iter().map(|&b| b as char).collect();
+    text.replace("\r\n", "\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use symphonia_core::io::MediaSourceStream;
+
+    fn stream(buf: &[u8]) -> MediaSourceStream {
+        MediaSourceStream::new(Box::new(Cursor::new(buf.to_vec())), Default::default())
+    }
+
+    fn field(id: &str, data: &str) -> String {
+        format!("{}{:05}{}", id, data.len(), data)
+    }
+
+    fn make_tag(fields: &[String]) -> Vec<u8> {
+        let body = format!("LYRICSBEGIN{}", fields.concat());
+        format!("{}{:06}LYRICS200", body, body.len()).into_bytes()
+    }
+
+    fn read_tag(tag: &[u8]) -> MetadataBuilder {
+        let mut buf = b"audio".to_vec();
+        buf.extend_from_slice(tag);
+
+        let mut reader = stream(&buf);
+
+        let tag_len = find_lyrics3v2(&mut reader, buf.len() as u64).unwrap().unwrap();
+        assert_eq!(tag_len, tag.len() as u64);
+        assert_eq!(reader.pos(), 5);
+
+        let mut builder = MetadataBuilder::new();
+        read_lyrics3v2(&mut reader, tag_len, &mut builder).unwrap();
+        builder
+    }
+
+    #[test]
+    fn verify_read_lyrics3v2() {
+        let tag = make_tag(&[
+            field("IND", "11"),
+            field("LYR", "[00:01][00:05]One\r\n[00:03]Two"),
+            field("ETT", "A title longer than thirty characters"),
+            field("EAR", ""),
+        ]);
+
+        let metadata = read_tag(&tag).metadata();
+        let tags = metadata.tags();
+
+        assert_eq!(tags.len(), 3);
+
+        assert_eq!(tags[0].key, "IND");
+        assert_eq!(tags[0].std_key, None);
+
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Lyrics));
+        assert_eq!(tags[1].value.to_string(), "[00:01.000] One\n[00:03.000] Two\n[00:05.000] One");
+
+        assert_eq!(tags[2].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[2].value.to_string(), "A title longer than thirty characters");
+
+        assert_eq!(metadata.lyrics().len(), 1);
+        assert_eq!(metadata.lyrics()[0].lines[2].timestamp, 5000);
+
+        // Unsynchronized lyrics are kept as text.
+        let metadata = read_tag(&make_tag(&[field("LYR", "One\r\nTwo")])).metadata();
+        assert!(matches!(metadata.tags()[0].value, Value::String(ref s) if s == "One\nTwo"));
+        assert!(metadata.lyrics().is_empty());
+    }
+
+    #[test]
+    fn verify_find_lyrics3v2() {
+        let mut reader = stream(b"no tag at the end of this stream");
+        assert_eq!(find_lyrics3v2(&mut reader, 32).unwrap(), None);
+
+        // The length in the footer exceeds the length of the stream.
+        let mut reader = stream(b"LYRICSBEGIN999999LYRICS200");
+        assert!(find_lyrics3v2(&mut reader, 26).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Rating normalization.
+//!
+//! Every metadata format, and often every application, stores ratings on a different scale. A
+//! rating is therefore read twice: the raw rating is kept as a tag without a standard tag key, and
+//! the rating normalized to the range 0 to 100 is added as a `Rating` tag.
+
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+/// Normalizes a popularimeter (ID3v2 `POPM`) rating.
+///
+/// The rating is mapped to stars using the ranges of the Windows Media Player convention, where 1,
+/// 64, 128, 196, and 255 are 1 to 5 stars. A rating of 0 is unknown.
+pub fn from_popm(rating: u8) -> Option<u8> {
+    let stars = match rating {
+        0 => return None,
+        1..=31 => 1,
+        32..=95 => 2,
+        96..=159 => 3,
+        160..=223 => 4,
+        _ => 5,
+    };
+
+    Some(20 * stars)
+}
+
+/// Normalizes a Windows Media `WM/SharedUserRating` rating.
+///
+/// Windows Media Player stores 1, 25, 50, 75, and 99 for 1 to 5 stars. A rating of 0 is unrated.
+pub fn from_wm_shared_user_rating(rating: u64) -> Option<u8> {
+    let stars = match rating {
+        0 => return None,
+        1..=12 => 1,
+        13..=37 => 2,
+        38..=62 => 3,
+        63..=86 => 4,
+        _ => 5,
+    };
+
+    Some(20 * stars)
+}
+
+/// Normalizes a rating of an unspecified scale, such as a Vorbis comment or an MP4 `rate` atom.
+///
+/// Applications commonly write either a fraction between 0.0 and 1.0, a number of stars between 0
+/// and 5, or a percentage. A fractional rating is only assumed if the rating has a decimal point.
+pub fn from_value(value: &Value) -> Option<u8> {
+    let (rating, is_decimal) = match value {
+        Value::String(text) => {
+            let text = text.trim();
+            (text.parse::<f64>().ok()?, text.contains('.'))
+        }
+        Value::Float(rating) => (*rating, true),
+        Value::SignedInt(rating) => (*rating as f64, false),
+        Value::UnsignedInt(rating) => (*rating as f64, false),
+        _ => return None,
+    };
+
+    if !rating.is_finite() || rating < 0.0 {
+        return None;
+    }
+
+    let normalized = if is_decimal && rating <= 1.0 {
+        100.0 * rating
+    }
+    else if rating <= 5.0 {
+        20.0 * rating
+    }
+    else if rating <= 100.0 {
+        rating
+    }
+    else {
+        return None;
+    };
+
+    Some(normalized.round() as u8)
+}
+
+/// Makes the tags of a rating: the raw rating, and, if the rating could be normalized, the
+/// normalized rating. The standard tag key, normally `Rating`, is only assigned to the normalized
+/// rating.
+pub fn make_tags(
+    std_key: Option<StandardTagKey>,
+    key: &str,
+    raw: Value,
+    normalized: Option<u8>,
+) -> Vec<Tag> {
+    let mut tags = vec![Tag::new(None, key, raw)];
+
+    if let Some(normalized) = normalized {
+        tags.push(Tag::new(std_key, key, Value::from(normalized)));
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_normalize_rating() {
+        assert_eq!(from_popm(0), None);
+        assert_eq!(from_popm(1), Some(20));
+        assert_eq!(from_popm(128), Some(60));
+        assert_eq!(from_popm(196), Some(80));
+        assert_eq!(from_popm(255), Some(100));
+
+        assert_eq!(from_wm_shared_user_rating(0), None);
+        assert_eq!(from_wm_shared_user_rating(25), Some(40));
+        assert_eq!(from_wm_shared_user_rating(99), Some(100));
+
+        assert_eq!(from_value(&Value::from("0.6")), Some(60));
+        assert_eq!(from_value(&Value::from(" 4 ")), Some(80));
+        assert_eq!(from_value(&Value::from("3.5")), Some(70));
+        assert_eq!(from_value(&Value::from("85")), Some(85));
+        assert_eq!(from_value(&Value::from(1u8)), Some(20));
+        assert_eq!(from_value(&Value::from(0.25)), Some(25));
+        assert_eq!(from_value(&Value::from("101")), None);
+        assert_eq!(from_value(&Value::from("-1")), None);
+        assert_eq!(from_value(&Value::from("NaN")), None);
+        assert_eq!(from_value(&Value::from("good")), None);
+    }
+}
@@ -5,7 +5,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! A RIFF INFO metadata reader.
+//! A RIFF INFO and Broadcast Wave Format (BWF) metadata reader.
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -15,6 +15,7 @@ lazy_static! {
     static ref RIFF_INFO_MAP: HashMap<&'static str, StandardTagKey> = {
         let mut m = HashMap::new();
         m.insert("ages", StandardTagKey::Rating);
+        m.insert("ibpm", StandardTagKey::Bpm);
         m.insert("cmnt", StandardTagKey::Comment);
         // Is this the same as a cmnt?
         m.insert("comm", StandardTagKey::Comment);
@@ -26,21 +27,25 @@ lazy_static! {
         m.insert("icop", StandardTagKey::Copyright);
         m.insert("icrd", StandardTagKey::Date);
         m.insert("idit", StandardTagKey::OriginalDate);
+        m.insert("idst", StandardTagKey::Label);
         m.insert("ienc", StandardTagKey::EncodedBy);
         m.insert("ieng", StandardTagKey::Engineer);
         m.insert("ifrm", StandardTagKey::TrackTotal);
         m.insert("ignr", StandardTagKey::Genre);
         m.insert("ilng", StandardTagKey::Language);
+        m.insert("imed", StandardTagKey::MediaFormat);
         m.insert("imus", StandardTagKey::Composer);
         m.insert("inam", StandardTagKey::TrackTitle);
         m.insert("iprd", StandardTagKey::Album);
         m.insert("ipro", StandardTagKey::Producer);
         m.insert("iprt", StandardTagKey::TrackNumber);
         m.insert("irtd", StandardTagKey::Rating);
+        m.insert("isbj", StandardTagKey::Description);
         m.insert("isft", StandardTagKey::Encoder);
         m.insert("isgn", StandardTagKey::Genre);
         m.insert("isrf", StandardTagKey::MediaFormat);
         m.insert("itch", StandardTagKey::EncodedBy);
+        m.insert("itrk", StandardTagKey::TrackNumber);
         m.insert("iwri", StandardTagKey::Writer);
         m.insert("lang", StandardTagKey::Language);
         m.insert("prt1", StandardTagKey::TrackNumber);
@@ -55,15 +60,71 @@ lazy_static! {
     };
 }
 
+lazy_static! {
+    static ref BEXT_MAP: HashMap<&'static str, StandardTagKey> = {
+        let mut m = HashMap::new();
+        m.insert("CodingHistory", StandardTagKey::EncoderSettings);
+        m.insert("Description", StandardTagKey::Description);
+        m.insert("OriginationDate", StandardTagKey::Date);
+        m.insert("Originator", StandardTagKey::Producer);
+        m
+    };
+}
+
 /// Parse the RIFF INFO block into a `Tag` using the block's identifier tag and a slice
 /// containing the block's contents.
 pub fn parse(tag: [u8; 4], buf: &[u8]) -> Tag {
     // TODO: Key should be checked that it only contains ASCII characters.
     let key = String::from_utf8_lossy(&tag);
-    let value = String::from_utf8_lossy(buf);
+
+    // INFO strings are usually, but not always, NUL terminated. Some writers also pad the string
+    // with additional NUL characters. Only the content before the first NUL is used.
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let value = String::from_utf8_lossy(&buf[..len]);
 
     // Attempt to assign a standardized tag key.
     let std_tag = RIFF_INFO_MAP.get(key.to_lowercase().as_str()).copied();
 
     Tag::new(std_tag, &key, Value::from(value))
 }
+
+/// Parse a string field of a Broadcast Wave Format (BWF) bext chunk into a `Tag` using the field
+/// name as defined in EBU Tech 3285 (e.g., `Description`, `Originator`), and the field's value.
+pub fn parse_bext(field: &str, value: &str) -> Tag {
+    // Attempt to assign a standardized tag key.
+    let std_tag = BEXT_MAP.get(field).copied();
+
+    Tag::new(std_tag, field, Value::from(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_bext};
+    use symphonia_core::meta::{StandardTagKey, Value};
+
+    #[test]
+    fn verify_parse_info_tag() {
+        let tag = parse(*b"INAM", b"Title\0\0");
+        assert_eq!(tag.std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tag.key, "INAM");
+        assert!(matches!(tag.value, Value::String(ref s) if s == "Title"));
+
+        let tag = parse(*b"IART", b"Artist");
+        assert_eq!(tag.std_key, Some(StandardTagKey::Artist));
+        assert!(matches!(tag.value, Value::String(ref s) if s == "Artist"));
+    }
+
+    #[test]
+    fn verify_parse_bext_tag() {
+        let tag = parse_bext("Description", "Interview");
+        assert_eq!(tag.std_key, Some(StandardTagKey::Description));
+        assert_eq!(tag.key, "Description");
+        assert!(matches!(tag.value, Value::String(ref s) if s == "Interview"));
+
+        let tag = parse_bext("OriginationDate", "2024-01-31");
+        assert_eq!(tag.std_key, Some(StandardTagKey::Date));
+
+        let tag = parse_bext("OriginatorReference", "ABC123");
+        assert_eq!(tag.std_key, None);
+    }
+}
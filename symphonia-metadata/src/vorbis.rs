@@ -16,11 +16,13 @@ use symphonia_core::errors::Result;
 use symphonia_core::io::{BufReader, ReadBytes};
 use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
 
-use crate::flac;
+use crate::{flac, rating};
 
 lazy_static! {
     static ref VORBIS_COMMENT_MAP: HashMap<&'static str, StandardTagKey> = {
         let mut m = HashMap::new();
+        m.insert("acoustid_fingerprint"        , StandardTagKey::AcoustidFingerprint);
+        m.insert("acoustid_id"                 , StandardTagKey::AcoustidId);
         m.insert("album artist"                , StandardTagKey::AlbumArtist);
         m.insert("album"                       , StandardTagKey::Album);
         m.insert("albumartist"                 , StandardTagKey::AlbumArtist);
@@ -29,6 +31,7 @@ lazy_static! {
         m.insert("arranger"                    , StandardTagKey::Arranger);
         m.insert("artist"                      , StandardTagKey::Artist);
         m.insert("artistsort"                  , StandardTagKey::SortArtist);
+        m.insert("asin"                        , StandardTagKey::IdentAsin);
         // TODO: Is Author a synonym for Writer?
         m.insert("author"                      , StandardTagKey::Writer);
         m.insert("barcode"                     , StandardTagKey::IdentBarcode);
@@ -38,8 +41,10 @@ lazy_static! {
         m.insert("catalognumber"               , StandardTagKey::IdentCatalogNumber);
         m.insert("catalogue #"                 , StandardTagKey::IdentCatalogNumber);
         m.insert("comment"                     , StandardTagKey::Comment);
+        m.insert("compilation"                 , StandardTagKey::Compilation);
         m.insert("compileation"                , StandardTagKey::Compilation);
         m.insert("composer"                    , StandardTagKey::Composer);
+        m.insert("composersort"                , StandardTagKey::SortComposer);
         m.insert("conductor"                   , StandardTagKey::Conductor);
         m.insert("copyright"                   , StandardTagKey::Copyright);
         m.insert("date"                        , StandardTagKey::Date);
@@ -55,12 +60,15 @@ lazy_static! {
         m.insert("djmixer"                     , StandardTagKey::MixDj);
         m.insert("ean/upn"                     , StandardTagKey::IdentEanUpn);
         m.insert("encoded-by"                  , StandardTagKey::EncodedBy);
+        m.insert("encodedby"                   , StandardTagKey::EncodedBy);
         m.insert("encoder settings"            , StandardTagKey::EncoderSettings);
         m.insert("encoder"                     , StandardTagKey::Encoder);
         m.insert("encoding"                    , StandardTagKey::EncoderSettings);
         m.insert("engineer"                    , StandardTagKey::Engineer);
         m.insert("ensemble"                    , StandardTagKey::Ensemble);
+        m.insert("fmps_rating"                 , StandardTagKey::Rating);
         m.insert("genre"                       , StandardTagKey::Genre);
+        m.insert("grouping"                    , StandardTagKey::ContentGroup);
         m.insert("isrc"                        , StandardTagKey::IdentIsrc);
         m.insert("language"                    , StandardTagKey::Language);
         m.insert("label"                       , StandardTagKey::Label);
@@ -70,8 +78,13 @@ lazy_static! {
         m.insert("media"                       , StandardTagKey::MediaFormat);
         m.insert("mixer"                       , StandardTagKey::MixEngineer);
         m.insert("mood"                        , StandardTagKey::Mood);
+        m.insert("movement"                    , StandardTagKey::MovementNumber);
+        m.insert("movementname"                , StandardTagKey::MovementName);
+        m.insert("movementtotal"               , StandardTagKey::MovementTotal);
         m.insert("musicbrainz_albumartistid"   , StandardTagKey::MusicBrainzAlbumArtistId);
         m.insert("musicbrainz_albumid"         , StandardTagKey::MusicBrainzAlbumId);
+        m.insert("musicbrainz_albumstatus"     , StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("musicbrainz_albumtype"       , StandardTagKey::MusicBrainzReleaseType);
         m.insert("musicbrainz_artistid"        , StandardTagKey::MusicBrainzArtistId);
         m.insert("musicbrainz_discid"          , StandardTagKey::MusicBrainzDiscId);
         m.insert("musicbrainz_originalalbumid" , StandardTagKey::MusicBrainzOriginalAlbumId);
@@ -84,6 +97,7 @@ lazy_static! {
         m.insert("opus"                        , StandardTagKey::Opus);
         m.insert("organization"                , StandardTagKey::Label);
         m.insert("originaldate"                , StandardTagKey::OriginalDate);
+        m.insert("originalyear"                , StandardTagKey::OriginalDate);
         m.insert("part"                        , StandardTagKey::Part);
         m.insert("performer"                   , StandardTagKey::Performer);
         m.insert("producer"                    , StandardTagKey::Producer);
@@ -92,6 +106,9 @@ lazy_static! {
         m.insert("publisher"                   , StandardTagKey::Label);
         m.insert("rating"                      , StandardTagKey::Rating);
         m.insert("releasecountry"              , StandardTagKey::ReleaseCountry);
+        m.insert("releasedate"                 , StandardTagKey::ReleaseDate);
+        m.insert("releasestatus"               , StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("releasetype"                 , StandardTagKey::MusicBrainzReleaseType);
         m.insert("remixer"                     , StandardTagKey::Remixer);
         m.insert("replaygain_album_gain"       , StandardTagKey::ReplayGainAlbumGain);
         m.insert("replaygain_album_peak"       , StandardTagKey::ReplayGainAlbumPeak);
@@ -109,22 +126,38 @@ lazy_static! {
         m.insert("upc"                         , StandardTagKey::IdentUpc);
         m.insert("version"                     , StandardTagKey::Remixer);
         m.insert("version"                     , StandardTagKey::Version);
+        m.insert("website"                     , StandardTagKey::Url);
+        m.insert("work"                        , StandardTagKey::Work);
         m.insert("writer"                      , StandardTagKey::Writer);
         m.insert("year"                        , StandardTagKey::Date);
         m
     };
 }
 
-/// Parse a string containing a base64 encoded FLAC picture block into a visual.
-fn parse_base64_picture_block(encoded: &str, metadata: &mut MetadataBuilder) {
-    if let Some(data) = base64_decode(encoded) {
-        if flac::read_picture_block(&mut BufReader::new(&data), metadata).is_err() {
-            warn!("invalid picture block data");
+/// Parse a string containing a base64 encoded FLAC picture block into a visual. Returns `false` if
+/// the picture block could not be decoded.
+fn parse_base64_picture_block(encoded: &str, metadata: &mut MetadataBuilder) -> bool {
+    // Some writers wrap the base64 encoding over multiple lines, so ignore any whitespace.
+    let encoded = if encoded.bytes().any(|b| b.is_ascii_whitespace()) {
+        encoded.chars().filter(|c| !c.is_ascii_whitespace()).collect()
+    }
+    else {
+        encoded.to_string()
+    };
+
+    if let Some(data) = base64_decode(&encoded) {
+        // The visual is only added once the entire picture block was read.
+        if flac::read_picture_block(&mut BufReader::new(&data), metadata).is_ok() {
+            return true;
         }
+
+        warn!("invalid picture block data");
     }
     else {
         warn!("the base64 encoding of a picture block is invalid");
     }
+
+    false
 }
 
 /// Parse the given Vorbis Comment string into a `Tag`.
@@ -142,15 +175,31 @@ fn parse_comment(tag: &str, metadata: &mut MetadataBuilder) {
         // A comment with a key "METADATA_BLOCK_PICTURE" is a FLAC picture block encoded in base64.
         // Attempt to decode it as such. If this fails in any way, treat the comment as a regular
         // tag.
-        if key_lower == "metadata_block_picture" {
-            parse_base64_picture_block(value, metadata);
+        if key_lower == "metadata_block_picture" && parse_base64_picture_block(value, metadata) {
+            return;
         }
-        else {
-            // Attempt to assign a standardized tag key.
-            let std_tag = VORBIS_COMMENT_MAP.get(key_lower.as_str()).copied();
 
-            metadata.add_tag(Tag::new(std_tag, key, Value::from(value)));
+        // Attempt to assign a standardized tag key. Some applications write a rating per user
+        // with the key "RATING:<user>".
+        let std_tag = match VORBIS_COMMENT_MAP.get(key_lower.as_str()) {
+            Some(std_tag) => Some(*std_tag),
+            None if key_lower.starts_with("rating:") => Some(StandardTagKey::Rating),
+            None => None,
+        };
+
+        // Ratings are written on many scales, so add the rating normalized along with the raw
+        // rating.
+        if std_tag == Some(StandardTagKey::Rating) {
+            let raw = Value::from(value);
+            let normalized = rating::from_value(&raw);
+
+            for tag in rating::make_tags(std_tag, key, raw, normalized) {
+                metadata.add_tag(tag);
+            }
+            return;
         }
+
+        metadata.add_tag(Tag::new(std_tag, key, Value::from(value)));
     }
 }
 
@@ -298,4 +347,27 @@ mod tests {
         assert_eq!(None, base64_decode("ab!c").as_deref());
         assert_eq!(None, base64_decode("ab=c").as_deref());
     }
+
+    #[test]
+    fn verify_parse_picture_comment() {
+        use symphonia_core::meta::MetadataBuilder;
+
+        // A picture block of a 1x1 PNG front cover, wrapped over two lines.
+        let encoded = "AAAAAwAAAAlpbWFnZS9wbmcAAAAFQ292ZXIAAAABAAAAAQAAABgAAAAAAAAABIlQTkc=";
+        let wrapped = format!("METADATA_BLOCK_PICTURE={}\r\n{}", &encoded[..40], &encoded[40..]);
+
+        let mut builder = MetadataBuilder::new();
+        super::parse_comment(&wrapped, &mut builder);
+
+        // An invalid picture block is kept as a regular tag.
+        super::parse_comment("METADATA_BLOCK_PICTURE=AAAA", &mut builder);
+
+        let metadata = builder.metadata();
+
+        assert_eq!(metadata.visuals().len(), 1);
+        assert_eq!(metadata.visuals()[0].media_type, "image/png");
+        assert_eq!(metadata.visuals()[0].data.as_ref(), b"\x89PNG");
+        assert_eq!(metadata.tags().len(), 1);
+        assert_eq!(metadata.tags()[0].key, "METADATA_BLOCK_PICTURE");
+    }
 }
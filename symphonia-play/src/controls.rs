@@ -0,0 +1,132 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Interactive Playback Controls
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+
+use log::warn;
+
+/// The number of seconds seeked by the left and right arrow keys.
+const SEEK_SHORT_SECS: i32 = 10;
+
+/// The number of seconds seeked by the up and down arrow keys.
+const SEEK_LONG_SECS: i32 = 60;
+
+/// The help text printed when the interactive controls are enabled.
+pub const HELP: &str = "controls: [space] pause/resume, [\u{2190}/\u{2192}] seek \u{b1}10s, \
+                        [\u{2193}/\u{2191}] seek \u{b1}60s, [0-9] jump to 0-90%, \
+                        [l] mark a-b repeat, [q] quit";
+
+/// A command issued by the user.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Seek forward, or backward, by the number of seconds.
+    SeekBy(i32),
+    /// Seek to a percentage of the duration of the track.
+    SeekToPercent(u8),
+    /// Pause, or resume, playback.
+    TogglePause,
+    /// Mark the start, or end, of the section to repeat, or stop repeating the section.
+    MarkRepeat,
+    /// Stop playback.
+    Quit,
+}
+
+impl Command {
+    fn from_key(key: KeyEvent) -> Option<Self> {
+        // Raw mode disables the interrupt signal, so Ctrl-C must be handled explicitly.
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('c') => Some(Command::Quit),
+                _ => None,
+            };
+        }
+
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => Some(Command::TogglePause),
+            KeyCode::Left => Some(Command::SeekBy(-SEEK_SHORT_SECS)),
+            KeyCode::Right => Some(Command::SeekBy(SEEK_SHORT_SECS)),
+            KeyCode::Down => Some(Command::SeekBy(-SEEK_LONG_SECS)),
+            KeyCode::Up => Some(Command::SeekBy(SEEK_LONG_SECS)),
+            KeyCode::Char(c @ '0'..='9') => Some(Command::SeekToPercent(10 * (c as u8 - b'0'))),
+            KeyCode::Char('l') => Some(Command::MarkRepeat),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// `Controls` reads the key presses of the user from the terminal, and translates them into
+/// commands.
+///
+/// The terminal is in raw mode for the lifetime of `Controls`.
+pub struct Controls {
+    commands: Receiver<Command>,
+}
+
+impl Controls {
+    /// Enables raw mode on the terminal, and starts reading key presses. Returns `None` if the
+    /// standard input is not a terminal.
+    pub fn try_new() -> Option<Self> {
+        if !std::io::stdin().is_tty() {
+            warn!("interactive controls require the standard input to be a terminal");
+            return None;
+        }
+
+        if let Err(err) = terminal::enable_raw_mode() {
+            warn!("failed to enable interactive controls: {}", err);
+            return None;
+        }
+
+        let (sender, commands) = mpsc::channel();
+
+        // Reading an event blocks, so read key presses on a separate thread. The thread exits once
+        // the controls are dropped and a command fails to send.
+        thread::spawn(move || loop {
+            let command = match event::read() {
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => Command::from_key(key),
+                Ok(_) => None,
+                Err(_) => break,
+            };
+
+            if let Some(command) = command {
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Some(Controls { commands })
+    }
+
+    /// Gets the next command without blocking, or `None` if there are no pending commands.
+    pub fn try_recv(&self) -> Option<Command> {
+        match self.commands.try_recv() {
+            Ok(command) => Some(command),
+            Err(TryRecvError::Empty) => None,
+            // If the key press thread has exited, the terminal can no longer be read, so stop.
+            Err(TryRecvError::Disconnected) => Some(Command::Quit),
+        }
+    }
+
+    /// Waits for the next command.
+    pub fn recv(&self) -> Command {
+        self.commands.recv().unwrap_or(Command::Quit)
+    }
+}
+
+impl Drop for Controls {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,165 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ReplayGain and R128 Gain
+
+use std::borrow::Cow;
+
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal};
+use symphonia::core::meta::{StandardTagKey, Tag, Value};
+
+use log::{info, warn};
+
+/// The gain, in decibels, added to a R128 gain. R128 gains normalize to a loudness of -23 LUFS,
+/// whereas ReplayGain gains normalize to a loudness of approximately -18 LUFS.
+const R128_TO_REPLAYGAIN_DB: f64 = 5.0;
+
+/// The gain to apply during playback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GainMode {
+    /// Apply the track gain.
+    Track,
+    /// Apply the album gain, or the track gain if there is no album gain.
+    Album,
+    /// Do not apply a gain.
+    Off,
+}
+
+impl GainMode {
+    /// The names of the gain modes accepted by `from_name`.
+    pub const NAMES: &'static [&'static str] = &["track", "album", "off"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "track" => Some(GainMode::Track),
+            "album" => Some(GainMode::Album),
+            "off" => Some(GainMode::Off),
+            _ => None,
+        }
+    }
+}
+
+/// A gain, in decibels, and the peak sample amplitude it was calculated for.
+struct GainInfo {
+    gain_db: f64,
+    peak: Option<f64>,
+}
+
+/// Parses the value of a gain or peak tag. Gains are usually suffixed with the unit.
+fn parse_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(value) => Some(*value),
+        Value::SignedInt(value) => Some(*value as f64),
+        Value::UnsignedInt(value) => Some(*value as f64),
+        Value::String(value) => {
+            let value = value.trim();
+            let value =
+                value.strip_suffix("dB").or_else(|| value.strip_suffix("db")).unwrap_or(value);
+
+            value.trim().parse::<f64>().ok().filter(|value| value.is_finite())
+        }
+        _ => None,
+    }
+}
+
+/// Finds the ReplayGain gain and peak tags, or, if there are none, the R128 gain tag.
+fn find_gain(
+    tags: &[Tag],
+    gain_key: StandardTagKey,
+    peak_key: StandardTagKey,
+    r128_key: &str,
+) -> Option<GainInfo> {
+    let find_std = |key| tags.iter().find(|tag| tag.std_key == Some(key));
+
+    if let Some(gain_db) = find_std(gain_key).and_then(|tag| parse_value(&tag.value)) {
+        let peak =
+            find_std(peak_key).and_then(|tag| parse_value(&tag.value)).filter(|&peak| peak > 0.0);
+
+        return Some(GainInfo { gain_db, peak });
+    }
+
+    // R128 gains are stored as a Q7.8 fixed-point number of decibels, and do not have a peak.
+    tags.iter()
+        .find(|tag| tag.key.eq_ignore_ascii_case(r128_key))
+        .and_then(|tag| parse_value(&tag.value))
+        .map(|gain| GainInfo { gain_db: gain / 256.0 + R128_TO_REPLAYGAIN_DB, peak: None })
+}
+
+/// `Gain` scales the decoded audio by the gain in its ReplayGain, or R128, tags.
+pub struct Gain {
+    scale: f32,
+    buf: Option<AudioBuffer<f32>>,
+}
+
+impl Gain {
+    /// Gets the gain for the gain mode from the tags. Returns `None` if no gain should be applied.
+    pub fn from_tags(mode: GainMode, tags: &[Tag]) -> Option<Gain> {
+        let track = || {
+            find_gain(
+                tags,
+                StandardTagKey::ReplayGainTrackGain,
+                StandardTagKey::ReplayGainTrackPeak,
+                "R128_TRACK_GAIN",
+            )
+        };
+
+        let info = match mode {
+            GainMode::Track => track(),
+            GainMode::Album => find_gain(
+                tags,
+                StandardTagKey::ReplayGainAlbumGain,
+                StandardTagKey::ReplayGainAlbumPeak,
+                "R128_ALBUM_GAIN",
+            )
+            .or_else(track),
+            GainMode::Off => return None,
+        };
+
+        let info = match info {
+            Some(info) => info,
+            _ => {
+                warn!("no replaygain or r128 gain tags were found, not applying a gain");
+                return None;
+            }
+        };
+
+        let mut scale = 10f64.powf(info.gain_db / 20.0);
+
+        // If the peak is known, prevent clipping by limiting the scale such that the peak is not
+        // amplified beyond full scale.
+        if let Some(peak) = info.peak {
+            scale = scale.min(1.0 / peak);
+        }
+
+        info!("applying a gain of {:+.2} dB", 20.0 * scale.log10());
+
+        Some(Gain { scale: scale as f32, buf: None })
+    }
+
+    /// Applies the gain to the decoded audio.
+    pub fn apply<'a>(&'a mut self, decoded: AudioBufferRef<'_>) -> AudioBufferRef<'a> {
+        // Allocate a new buffer if there is no buffer, or it is not compatible with the decoded
+        // audio.
+        let is_compatible = match &self.buf {
+            Some(buf) => buf.spec() == decoded.spec() && buf.capacity() >= decoded.capacity(),
+            None => false,
+        };
+
+        if !is_compatible {
+            self.buf = Some(decoded.make_equivalent::<f32>());
+        }
+
+        let buf = self.buf.as_mut().unwrap();
+
+        decoded.convert(buf);
+
+        let scale = self.scale;
+        buf.transform(|sample| sample * scale);
+
+        AudioBufferRef::F32(Cow::Borrowed(buf))
+    }
+}
@@ -0,0 +1,200 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Machine-readable Format Dump
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use symphonia::core::formats::{Cue, Track};
+use symphonia::core::meta::{ColorMode, Lyrics, MetadataRevision, Tag, Value, Visual};
+use symphonia::core::probe::ProbeResult;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Map, Value as JsonValue};
+
+/// Dumps the tracks, tags, visuals, lyrics, and cues of the probed format as a JSON object.
+///
+/// If the visuals were dumped to files, `visuals_file_name` is the file name the visuals were
+/// dumped with, and each visual references its file instead of embedding its data.
+pub fn dump_format(
+    path: &Path,
+    probed: &mut ProbeResult,
+    visuals_file_name: Option<&OsStr>,
+) -> JsonValue {
+    let mut root = Map::new();
+
+    root.insert("path".into(), json!(path.display().to_string()));
+    root.insert("tracks".into(), probed.format.tracks().iter().map(dump_track).collect());
+
+    // Like when printing, prefer metadata that's provided in the container format, over other tags
+    // found during the probe operation.
+    let (tags, visuals, lyrics) = if let Some(metadata_rev) = probed.format.metadata().current() {
+        dump_revision(metadata_rev, visuals_file_name)
+    }
+    else if let Some(metadata_rev) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        dump_revision(metadata_rev, visuals_file_name)
+    }
+    else {
+        (JsonValue::Array(Vec::new()), JsonValue::Array(Vec::new()), JsonValue::Array(Vec::new()))
+    };
+
+    root.insert("tags".into(), tags);
+    root.insert("visuals".into(), visuals);
+    root.insert("lyrics".into(), lyrics);
+    root.insert("cues".into(), probed.format.cues().iter().map(dump_cue).collect());
+
+    JsonValue::Object(root)
+}
+
+fn dump_track(track: &Track) -> JsonValue {
+    let params = &track.codec_params;
+
+    let codec = symphonia::default::get_codecs().get_codec(params.codec);
+
+    json!({
+        "id": track.id,
+        "codec": codec.map(|codec| codec.short_name),
+        "codec_long_name": codec.map(|codec| codec.long_name),
+        "sample_rate": params.sample_rate,
+        "time_base": params.time_base.map(|tb| json!({ "numer": tb.numer, "denom": tb.denom })),
+        "start_ts": params.start_ts,
+        "n_frames": params.n_frames,
+        "duration": params.time_base.zip(params.n_frames).map(|(tb, n_frames)| {
+            let time = tb.calc_time(n_frames);
+            time.seconds as f64 + time.frac
+        }),
+        "delay": params.delay,
+        "padding": params.padding,
+        "sample_format": params.sample_format.map(|format| format!("{:?}", format)),
+        "bits_per_sample": params.bits_per_sample,
+        "channels": params.channels.map(|channels| channels.count()),
+        "channel_map": params.channels.map(|channels| {
+            channels.iter().map(|channel| format!("{:?}", channel)).collect::<Vec<_>>()
+        }),
+        "channel_layout": params.channel_layout.map(|layout| format!("{:?}", layout)),
+        "language": track.language,
+    })
+}
+
+fn dump_cue(cue: &Cue) -> JsonValue {
+    let points = cue
+        .points
+        .iter()
+        .map(|point| {
+            json!({
+                "start_offset_ts": point.start_offset_ts,
+                "tags": dump_tags(&point.tags),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "index": cue.index,
+        "start_ts": cue.start_ts,
+        "tags": dump_tags(&cue.tags),
+        "points": points,
+    })
+}
+
+fn dump_revision(
+    metadata_rev: &MetadataRevision,
+    visuals_file_name: Option<&OsStr>,
+) -> (JsonValue, JsonValue, JsonValue) {
+    (
+        dump_tags(metadata_rev.tags()),
+        dump_visuals(metadata_rev.visuals(), visuals_file_name),
+        metadata_rev.lyrics().iter().map(dump_lyrics).collect(),
+    )
+}
+
+fn dump_tags(tags: &[Tag]) -> JsonValue {
+    tags.iter()
+        .map(|tag| {
+            json!({
+                "key": tag.key,
+                "std_key": tag.std_key.map(|key| format!("{:?}", key)),
+                "value": dump_value(&tag.value),
+            })
+        })
+        .collect()
+}
+
+fn dump_value(value: &Value) -> JsonValue {
+    match value {
+        // Binary data is encoded as a base64 string.
+        Value::Binary(buf) => json!(BASE64.encode(buf)),
+        Value::Boolean(boolean) => json!(boolean),
+        // A flag carries no data, only the presence of the tag is meaningful.
+        Value::Flag => json!(true),
+        // Non-finite numbers can not be represented in JSON, and are dumped as null.
+        Value::Float(float) => json!(float),
+        Value::SignedInt(int) => json!(int),
+        Value::String(string) => json!(string),
+        Value::UnsignedInt(uint) => json!(uint),
+    }
+}
+
+fn dump_lyrics(lyrics: &Lyrics) -> JsonValue {
+    let lines = lyrics
+        .lines
+        .iter()
+        .map(|line| json!({ "timestamp": line.timestamp, "text": line.text }))
+        .collect::<Vec<_>>();
+
+    json!({
+        "language": lyrics.language,
+        "description": lyrics.description,
+        "content_type": format!("{:?}", lyrics.content_type),
+        "time_unit": format!("{:?}", lyrics.time_unit),
+        "lines": lines,
+    })
+}
+
+fn dump_visuals(visuals: &[Visual], visuals_file_name: Option<&OsStr>) -> JsonValue {
+    visuals
+        .iter()
+        .enumerate()
+        .map(|(idx, visual)| {
+            let mut obj = Map::new();
+
+            obj.insert("media_type".into(), json!(visual.media_type));
+            obj.insert("usage".into(), json!(visual.usage.map(|usage| format!("{:?}", usage))));
+            obj.insert(
+                "dimensions".into(),
+                json!(visual.dimensions.map(|size| json!({
+                    "width": size.width,
+                    "height": size.height,
+                }))),
+            );
+            obj.insert("bits_per_pixel".into(), json!(visual.bits_per_pixel.map(|bpp| bpp.get())));
+
+            let colors = match visual.color_mode {
+                Some(ColorMode::Indexed(colors)) => Some(colors.get()),
+                _ => None,
+            };
+
+            obj.insert("palette_colors".into(), json!(colors));
+            obj.insert("size".into(), json!(visual.data.len()));
+            obj.insert("tags".into(), dump_tags(&visual.tags));
+
+            // Reference the dumped file if the visuals were dumped, otherwise embed the data.
+            match visuals_file_name {
+                Some(file_name) => {
+                    let file = crate::visual_file_name(visual, file_name, idx);
+                    obj.insert("file".into(), json!(file.to_string_lossy()));
+                }
+                None => {
+                    obj.insert("data".into(), json!(BASE64.encode(&visual.data)));
+                }
+            }
+
+            JsonValue::Object(obj)
+        })
+        .collect()
+}
@@ -18,10 +18,12 @@ use std::io::Write;
 use std::path::Path;
 
 use lazy_static::lazy_static;
-use symphonia::core::codecs::{DecoderOptions, FinalizeResult, CODEC_TYPE_NULL};
+use symphonia::core::codecs::{Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_NULL};
 use symphonia::core::errors::{Error, Result};
 use symphonia::core::formats::{Cue, FormatOptions, FormatReader, SeekMode, SeekTo, Track};
-use symphonia::core::io::{MediaSource, MediaSourceStream, ReadOnlySource};
+use symphonia::core::io::{
+    MediaSource, MediaSourceStream, MediaSourceStreamOptions, ReadOnlySource,
+};
 use symphonia::core::meta::{ColorMode, MetadataOptions, MetadataRevision, Tag, Value, Visual};
 use symphonia::core::probe::{Hint, ProbeResult};
 use symphonia::core::units::{Time, TimeBase};
@@ -29,11 +31,21 @@ use symphonia::core::units::{Time, TimeBase};
 use clap::{Arg, ArgMatches};
 use log::{error, info, warn};
 
+mod controls;
+mod gain;
+mod json;
 mod output;
+mod read_ahead;
+mod speed;
+mod visualize;
+mod wav;
 
 #[cfg(not(target_os = "linux"))]
 mod resampler;
 
+/// The maximum number of consecutive errors skipped over when resyncing is enabled.
+const MAX_RESYNC_ERRORS: u32 = 100;
+
 enum SeekPosition {
     Time(f64),
     Timetamp(u64),
@@ -75,25 +87,41 @@ fn main() {
                 ]),
         )
         .arg(
-            Arg::new("track").long("track").short('t').value_name("TRACK").help("The track to use"),
+            Arg::new("track")
+                .long("track")
+                .short('t')
+                .value_name("ID")
+                .validator(|id| id.parse::<u32>())
+                .help(
+                    "The ID of the track to decode (default: the first track with a known codec)",
+                ),
         )
         .arg(
             Arg::new("decode-only")
                 .long("decode-only")
                 .help("Decode, but do not play the audio")
-                .conflicts_with_all(&["probe-only", "verify-only", "verify"]),
+                .conflicts_with_all(&["probe-only", "verify-only", "verify", "output"]),
         )
         .arg(
             Arg::new("probe-only")
                 .long("probe-only")
                 .help("Only probe the input for metadata")
-                .conflicts_with_all(&["decode-only", "verify-only"]),
+                .conflicts_with_all(&["decode-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("dump-json")
+                .long("dump-json")
+                .help("Only probe the input, and print the tracks, metadata, and cues as JSON")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
         )
         .arg(
             Arg::new("verify-only")
                 .long("verify-only")
-                .help("Verify the decoded audio is valid, but do not play the audio")
-                .conflicts_with_all(&["verify"]),
+                .help(
+                    "Verify the decoded audio of all tracks is valid, and print a report, but do \
+                     not play the audio",
+                )
+                .conflicts_with_all(&["verify", "output"]),
         )
         .arg(
             Arg::new("verify")
@@ -101,6 +129,123 @@ fn main() {
                 .short('v')
                 .help("Verify the decoded audio is valid during playback"),
         )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("FILE")
+                .help("Write the decoded audio to a WAVE file instead of playing it"),
+        )
+        .arg(
+            Arg::new("sample-format")
+                .long("sample-format")
+                .value_name("FORMAT")
+                .possible_values(wav::WavSampleFormat::NAMES)
+                .requires("output")
+                .help(
+                    "The sample format of the output file (default: closest to the decoded audio)",
+                ),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Enable keyboard controls to seek, and pause or resume, during playback")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("gain")
+                .long("gain")
+                .value_name("MODE")
+                .possible_values(gain::GainMode::NAMES)
+                .default_value("off")
+                .help("Apply the track, or album, gain from the ReplayGain or R128 tags")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
+        .arg(
+            Arg::new("host")
+                .long("host")
+                .value_name("NAME")
+                .help("The audio host to play the audio with (see --list-devices)")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("device")
+                .long("device")
+                .value_name("NAME")
+                .help("The audio output device to play the audio on (see --list-devices)")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("list-devices")
+                .long("list-devices")
+                .help("List the audio hosts and output devices, and exit")
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("loop")
+                .long("loop")
+                .help("Repeat the track until stopped")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("visualize")
+                .long("visualize")
+                .value_name("MODE")
+                .possible_values(visualize::VisualizeMode::NAMES)
+                .help("Draw peak meters, and optionally the spectrum, of the audio during playback")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only", "output"]),
+        )
+        .arg(
+            Arg::new("speed")
+                .long("speed")
+                .value_name("FACTOR")
+                .validator(|factor| match factor.parse::<f64>() {
+                    Ok(factor) if (speed::MIN_SPEED..=speed::MAX_SPEED).contains(&factor) => Ok(()),
+                    _ => Err(format!(
+                        "the speed must be a number between {} and {}",
+                        speed::MIN_SPEED,
+                        speed::MAX_SPEED
+                    )),
+                })
+                .help("Play the audio faster, or slower, by the factor (default: 1)")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
+        .arg(
+            Arg::new("keep-pitch")
+                .long("keep-pitch")
+                .requires("speed")
+                .help("Preserve the pitch of the audio when changing the speed"),
+        )
+        .arg(
+            Arg::new("buffer-size")
+                .long("buffer-size")
+                .value_name("KiB")
+                .validator(|len| match len.parse::<usize>() {
+                    Ok(len) if len >= 64 && len.is_power_of_two() => Ok(()),
+                    _ => Err("the buffer size must be a power of 2, and at least 64 KiB"),
+                })
+                .help("The size of the input buffer in KiB (default: 64)"),
+        )
+        .arg(
+            Arg::new("read-ahead")
+                .long("read-ahead")
+                .value_name("KiB")
+                .validator(|len| len.parse::<usize>().map(|_| ()))
+                .help(
+                    "Read ahead of playback, up to the number of KiB, when reading from standard \
+                     input, and buffer that much before starting playback",
+                ),
+        )
+        .arg(
+            Arg::new("resync")
+                .long("resync")
+                .help(
+                    "Skip over corrupt or undecodable data and resynchronize, instead of stopping \
+                     playback (for live streams)",
+                )
+                .conflicts_with_all(&["probe-only", "verify-only"]),
+        )
         .arg(Arg::new("no-progress").long("no-progress").help("Do not display playback progress"))
         .arg(
             Arg::new("no-gapless").long("no-gapless").help("Disable gapless decoding and playback"),
@@ -113,7 +258,7 @@ fn main() {
         .arg(
             Arg::new("INPUT")
                 .help("The input file path, or - to use standard input")
-                .required(true)
+                .required_unless_present("list-devices")
                 .index(1),
         )
         .get_matches();
@@ -131,14 +276,24 @@ fn main() {
 }
 
 fn run(args: &ArgMatches) -> Result<i32> {
+    if args.is_present("list-devices") {
+        return Ok(list_devices());
+    }
+
     let path = Path::new(args.value_of("INPUT").unwrap());
 
     // Create a hint to help the format registry guess what format reader is appropriate.
     let mut hint = Hint::new();
 
-    // If the path string is '-' then read from standard input.
+    // If the path string is '-' then read from standard input, reading ahead if requested.
     let source = if path.as_os_str() == "-" {
-        Box::new(ReadOnlySource::new(std::io::stdin())) as Box<dyn MediaSource>
+        match args.value_of("read-ahead").map(|len| len.parse::<usize>().unwrap()) {
+            Some(len) if len > 0 => {
+                let stdin = read_ahead::ReadAhead::new(std::io::stdin(), len * 1024);
+                Box::new(ReadOnlySource::new(stdin)) as Box<dyn MediaSource>
+            }
+            _ => Box::new(ReadOnlySource::new(std::io::stdin())) as Box<dyn MediaSource>,
+        }
     }
     else {
         // Othwerise, get a Path from the path string.
@@ -153,8 +308,19 @@ fn run(args: &ArgMatches) -> Result<i32> {
         Box::new(File::open(path)?)
     };
 
-    // Create the media source stream using the boxed media source from above.
-    let mss = MediaSourceStream::new(source, Default::default());
+    // Create the media source stream using the boxed media source from above. The buffer size was
+    // validated when parsed.
+    let mss_opts = match args.value_of("buffer-size") {
+        Some(len) => MediaSourceStreamOptions {
+            buffer_len: len.parse::<usize>().unwrap() * 1024,
+            ..Default::default()
+        },
+        None => Default::default(),
+    };
+
+    let mss = MediaSourceStream::new(source, mss_opts);
+
+    let resync = args.is_present("resync");
 
     // Use the default options for format readers other than for gapless playback.
     let format_opts =
@@ -163,11 +329,8 @@ fn run(args: &ArgMatches) -> Result<i32> {
     // Use the default options for metadata readers.
     let metadata_opts: MetadataOptions = Default::default();
 
-    // Get the value of the track option, if provided.
-    let track = match args.value_of("track") {
-        Some(track_str) => track_str.parse::<usize>().ok(),
-        _ => None,
-    };
+    // Get the value of the track option, if provided. The value was validated when parsed.
+    let track = args.value_of("track").map(|id| id.parse::<u32>().unwrap());
 
     let no_progress = args.is_present("no-progress");
 
@@ -175,23 +338,38 @@ fn run(args: &ArgMatches) -> Result<i32> {
     match symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts) {
         Ok(mut probed) => {
             // Dump visuals if requested.
-            if args.is_present("dump-visuals") {
+            let visuals_file_name = if args.is_present("dump-visuals") {
                 let name = match path.file_name() {
                     Some(name) if name != "-" => name,
                     _ => OsStr::new("NoName"),
                 };
 
                 dump_visuals(&mut probed, name);
+                Some(name)
             }
+            else {
+                None
+            };
 
             // Select the operating mode.
-            if args.is_present("verify-only") {
+            if args.is_present("dump-json") {
+                // Dump-JSON mode is like probe-only mode, but prints the information as JSON.
+                let dump = json::dump_format(path, &mut probed, visuals_file_name);
+
+                println!("{:#}", dump);
+                Ok(0)
+            }
+            else if args.is_present("verify-only") {
                 // Verify-only mode decodes and verifies the audio, but does not play it.
-                decode_only(probed.format, &DecoderOptions { verify: true, ..Default::default() })
+                verify_only(path, probed.format, track)
             }
             else if args.is_present("decode-only") {
                 // Decode-only mode decodes the audio, but does not play or verify it.
-                decode_only(probed.format, &DecoderOptions { verify: false, ..Default::default() })
+                decode_only(
+                    probed.format,
+                    track,
+                    &DecoderOptions { verify: false, ..Default::default() },
+                )
             }
             else if args.is_present("probe-only") {
                 // Probe-only mode only prints information about the format, tracks, metadata, etc.
@@ -215,8 +393,95 @@ fn run(args: &ArgMatches) -> Result<i32> {
                 let decode_opts =
                     DecoderOptions { verify: args.is_present("verify"), ..Default::default() };
 
+                // If an output file is provided, write the decoded audio to it instead of the audio
+                // output device.
+                let audio_output = match args.value_of("output") {
+                    Some(output_path) => {
+                        let sample_format = args
+                            .value_of("sample-format")
+                            .and_then(wav::WavSampleFormat::from_name);
+
+                        let output = wav::WavOutput::new(File::create(output_path)?, sample_format);
+
+                        Some(Box::new(output) as Box<dyn output::AudioOutput>)
+                    }
+                    None => None,
+                };
+
+                // Get the audio output device to play the audio on.
+                let device_opts = output::DeviceOptions {
+                    host: args.value_of("host").map(String::from),
+                    device: args.value_of("device").map(String::from),
+                };
+
+                // Get the gain to apply from the tags. Like when printing, prefer tags that are
+                // part of the container format.
+                let gain_mode = gain::GainMode::from_name(args.value_of("gain").unwrap()).unwrap();
+
+                let gain = if let Some(metadata_rev) = probed.format.metadata().current() {
+                    gain::Gain::from_tags(gain_mode, metadata_rev.tags())
+                }
+                else if let Some(metadata_rev) =
+                    probed.metadata.get().as_ref().and_then(|m| m.current())
+                {
+                    gain::Gain::from_tags(gain_mode, metadata_rev.tags())
+                }
+                else {
+                    gain::Gain::from_tags(gain_mode, &[])
+                };
+
+                // If requested, enable the interactive controls. The controls read the terminal, so
+                // they can not be used if the input is read from standard input.
+                let controls = if !args.is_present("interactive") {
+                    None
+                }
+                else if path.as_os_str() == "-" {
+                    warn!("interactive controls can not be used when reading from standard input");
+                    None
+                }
+                else {
+                    controls::Controls::try_new()
+                };
+
+                // The terminal is in raw mode if the controls are enabled, so carriage returns are
+                // required.
+                if controls.is_some() {
+                    print!("{}\r\n\r\n", controls::HELP);
+                }
+
+                // If present, parse the speed argument. The value was validated when parsed. A
+                // speed of 1 does not change the audio.
+                let speed = args
+                    .value_of("speed")
+                    .map(|factor| factor.parse::<f64>().unwrap())
+                    .filter(|&factor| factor != 1.0)
+                    .map(|factor| speed::SpeedOptions {
+                        factor,
+                        keep_pitch: args.is_present("keep-pitch"),
+                    });
+
                 // Play it!
-                play(probed.format, track, seek, &decode_opts, no_progress)
+                let play_opts = PlayOptions {
+                    track_id: track,
+                    seek,
+                    no_progress,
+                    repeat: args.is_present("loop"),
+                    visualize: args
+                        .value_of("visualize")
+                        .and_then(visualize::VisualizeMode::from_name),
+                    speed,
+                    resync,
+                };
+
+                play(
+                    probed.format,
+                    play_opts,
+                    &decode_opts,
+                    audio_output,
+                    controls,
+                    gain,
+                    &device_opts,
+                )
             }
         }
         Err(err) => {
@@ -227,10 +492,19 @@ fn run(args: &ArgMatches) -> Result<i32> {
     }
 }
 
-fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions) -> Result<i32> {
-    // Get the default track.
-    // TODO: Allow track selection.
-    let track = reader.default_track().unwrap();
+fn decode_only(
+    mut reader: Box<dyn FormatReader>,
+    track_id: Option<u32>,
+    decode_opts: &DecoderOptions,
+) -> Result<i32> {
+    // Select the track to decode.
+    let track = match select_track(reader.tracks(), track_id) {
+        Some(track) => track,
+        // The track selected by the user does not exist.
+        None if track_id.is_some() => return Ok(-1),
+        _ => return Ok(0),
+    };
+
     let track_id = track.id;
 
     // Create a decoder for the track.
@@ -263,27 +537,219 @@ fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions)
     do_verification(decoder.finalize())
 }
 
+/// The integrity of a track being verified.
+struct TrackIntegrity {
+    track_id: u32,
+    /// The decoder, or `None` if the codec is not supported.
+    decoder: Option<Box<dyn Decoder>>,
+    /// The number of frames declared by the container, if known.
+    declared_frames: Option<u64>,
+    /// The number of frames decoded.
+    decoded_frames: u64,
+    /// The number of packets that failed to decode.
+    decode_errors: u64,
+}
+
+fn verify_only(
+    path: &Path,
+    mut reader: Box<dyn FormatReader>,
+    track_id: Option<u32>,
+) -> Result<i32> {
+    let decode_opts = DecoderOptions { verify: true, ..Default::default() };
+
+    // Verify the track selected by the user, or all tracks with a known codec.
+    let mut tracks = Vec::new();
+
+    for track in reader.tracks() {
+        let is_selected = match track_id {
+            Some(id) => track.id == id,
+            None => track.codec_params.codec != CODEC_TYPE_NULL,
+        };
+
+        if is_selected {
+            // A track with an unsupported codec cannot be verified, but should not prevent the
+            // other tracks from being verified.
+            let decoder =
+                match symphonia::default::get_codecs().make(&track.codec_params, &decode_opts) {
+                    Ok(decoder) => Some(decoder),
+                    Err(err) => {
+                        warn!("track {}: {}", track.id, err);
+                        None
+                    }
+                };
+
+            tracks.push(TrackIntegrity {
+                track_id: track.id,
+                decoder,
+                declared_frames: track.codec_params.n_frames,
+                decoded_frames: 0,
+                decode_errors: 0,
+            });
+        }
+    }
+
+    if tracks.is_empty() {
+        return match track_id {
+            Some(id) => {
+                error!("there is no track with the id {}", id);
+                Ok(-1)
+            }
+            None => Ok(0),
+        };
+    }
+
+    // Decode all packets of the verified tracks, counting the decoded frames and decode errors.
+    let result = loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(err) => break Err(err),
+        };
+
+        // If the packet does not belong to a verified track, or the track cannot be decoded, skip
+        // over it.
+        let track = match tracks.iter_mut().find(|track| track.track_id == packet.track_id()) {
+            Some(track) => track,
+            _ => continue,
+        };
+
+        let decoder = match &mut track.decoder {
+            Some(decoder) => decoder,
+            _ => continue,
+        };
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => track.decoded_frames += decoded.frames() as u64,
+            Err(Error::DecodeError(err)) => {
+                warn!("track {}: decode error: {}", track.track_id, err);
+                track.decode_errors += 1;
+            }
+            Err(err) => break Err(err),
+        }
+    };
+
+    // Return if a fatal error occured.
+    ignore_end_of_stream_error(result)?;
+
+    Ok(print_integrity(path, tracks))
+}
+
+/// Prints the integrity report of the verified tracks. Returns the exit code, which is non-zero if
+/// any track failed verification.
+fn print_integrity(path: &Path, tracks: Vec<TrackIntegrity>) -> i32 {
+    let mut is_ok = true;
+
+    println!("+ {}", path.display());
+    println!("|");
+    println!("| // Integrity //");
+
+    for (idx, track) in tracks.into_iter().enumerate() {
+        println!("|     [{:0>2}] Track ID:        {}", idx + 1, track.track_id);
+
+        let mut decoder = match track.decoder {
+            Some(decoder) => decoder,
+            _ => {
+                println!("|          Verification:    unsupported codec");
+                continue;
+            }
+        };
+
+        // Print the verification result of the codec (e.g., a MD5 checksum of the decoded audio).
+        let verification = match decoder.finalize().verify_ok {
+            Some(true) => "passed",
+            Some(false) => {
+                is_ok = false;
+                "failed"
+            }
+            None => "unsupported",
+        };
+
+        println!("|          Verification:    {}", verification);
+
+        // Print the number of decoded frames, and if the number of frames declared by the container
+        // is known, if they match. Fewer frames than declared indicates the file is truncated or
+        // damaged. More frames than declared may only indicate the encoder delay and padding were
+        // not trimmed, therefore it is reported, but does not fail verification.
+        match track.declared_frames {
+            Some(declared) if declared == track.decoded_frames => {
+                println!("|          Frames:          {} (matches declared)", track.decoded_frames);
+            }
+            Some(declared) if declared > track.decoded_frames => {
+                is_ok = false;
+                println!(
+                    "|          Frames:          {} ({} declared, {} missing)",
+                    track.decoded_frames,
+                    declared,
+                    declared - track.decoded_frames
+                );
+            }
+            Some(declared) => {
+                println!(
+                    "|          Frames:          {} ({} declared, {} extra)",
+                    track.decoded_frames,
+                    declared,
+                    track.decoded_frames - declared
+                );
+            }
+            None => println!("|          Frames:          {}", track.decoded_frames),
+        }
+
+        if track.decode_errors > 0 {
+            is_ok = false;
+        }
+
+        println!("|          Decode Errors:   {}", track.decode_errors);
+    }
+
+    println!(":");
+    println!("verification: {}", if is_ok { "passed" } else { "failed" });
+
+    i32::from(!is_ok)
+}
+
 #[derive(Copy, Clone)]
 struct PlayTrackOptions {
     track_id: u32,
     seek_ts: u64,
+    no_progress: bool,
+    repeat: bool,
+    visualize: Option<visualize::VisualizeMode>,
+    speed: Option<speed::SpeedOptions>,
+    resync: bool,
+}
+
+struct PlayOptions {
+    /// The ID of the track to play, or `None` to play the first track with a known codec.
+    track_id: Option<u32>,
+    /// The position to start playback at.
+    seek: Option<SeekPosition>,
+    /// If true, do not display playback progress.
+    no_progress: bool,
+    /// If true, repeat the track until stopped.
+    repeat: bool,
+    /// The visualization to draw during playback, if any.
+    visualize: Option<visualize::VisualizeMode>,
+    /// The playback speed, if changed.
+    speed: Option<speed::SpeedOptions>,
+    /// If true, skip over errors reading the input instead of stopping playback.
+    resync: bool,
 }
 
 fn play(
     mut reader: Box<dyn FormatReader>,
-    track_num: Option<usize>,
-    seek: Option<SeekPosition>,
+    play_opts: PlayOptions,
     decode_opts: &DecoderOptions,
-    no_progress: bool,
+    mut audio_output: Option<Box<dyn output::AudioOutput>>,
+    controls: Option<controls::Controls>,
+    mut gain: Option<gain::Gain>,
+    device_opts: &output::DeviceOptions,
 ) -> Result<i32> {
-    // If the user provided a track number, select that track if it exists, otherwise, select the
-    // first track with a known codec.
-    let track = track_num
-        .and_then(|t| reader.tracks().get(t))
-        .or_else(|| first_supported_track(reader.tracks()));
+    let PlayOptions { track_id, seek, no_progress, repeat, visualize, speed, resync } = play_opts;
 
-    let mut track_id = match track {
+    // Select the track to play.
+    let mut track_id = match select_track(reader.tracks(), track_id) {
         Some(track) => track.id,
+        // The track selected by the user does not exist.
+        None if track_id.is_some() => return Ok(-1),
         _ => return Ok(0),
     };
 
@@ -320,13 +786,19 @@ fn play(
         0
     };
 
-    // The audio output device.
-    let mut audio_output = None;
-
-    let mut track_info = PlayTrackOptions { track_id, seek_ts };
+    let mut track_info =
+        PlayTrackOptions { track_id, seek_ts, no_progress, repeat, visualize, speed, resync };
 
     let result = loop {
-        match play_track(&mut reader, &mut audio_output, track_info, decode_opts, no_progress) {
+        match play_track(
+            &mut reader,
+            &mut audio_output,
+            track_info,
+            decode_opts,
+            controls.as_ref(),
+            gain.as_mut(),
+            device_opts,
+        ) {
             Err(Error::ResetRequired) => {
                 // The demuxer indicated that a reset is required. This is sometimes seen with
                 // streaming OGG (e.g., Icecast) wherein the entire contents of the container change
@@ -337,7 +809,7 @@ fn play(
                 // Select the first supported track since the user's selected track number might no
                 // longer be valid or make sense.
                 let track_id = first_supported_track(reader.tracks()).unwrap().id;
-                track_info = PlayTrackOptions { track_id, seek_ts: 0 };
+                track_info = PlayTrackOptions { track_id, seek_ts: 0, ..track_info };
             }
             res => break res,
         }
@@ -356,7 +828,9 @@ fn play_track(
     audio_output: &mut Option<Box<dyn output::AudioOutput>>,
     play_opts: PlayTrackOptions,
     decode_opts: &DecoderOptions,
-    no_progress: bool,
+    controls: Option<&controls::Controls>,
+    mut gain: Option<&mut gain::Gain>,
+    device_opts: &output::DeviceOptions,
 ) -> Result<i32> {
     // Get the selected track using the track ID.
     let track = match reader.tracks().iter().find(|track| track.id == play_opts.track_id) {
@@ -369,16 +843,134 @@ fn play_track(
 
     // Get the selected track's timebase and duration.
     let tb = track.codec_params.time_base;
-    let dur = track.codec_params.n_frames.map(|frames| track.codec_params.start_ts + frames);
+    let start_ts = track.codec_params.start_ts;
+    let dur = track.codec_params.n_frames.map(|frames| start_ts + frames);
+
+    // The timestamp of the first frame to play. This changes when seeking interactively.
+    let mut seek_ts = play_opts.seek_ts;
+
+    // The timestamp of the last packet played.
+    let mut last_ts = seek_ts;
+
+    // Changes the speed of the audio being played, if enabled.
+    let mut speed = play_opts.speed.map(speed::Speed::new);
+
+    // The visualizer of the audio being played, if enabled.
+    let mut visualizer = play_opts.visualize.map(visualize::Visualizer::new);
+
+    // The number of consecutive errors skipped over while resyncing.
+    let mut resync_errors = 0;
+
+    // The start (A), and end (B), timestamps of the section of the track to repeat.
+    let mut repeat_a = None;
+    let mut repeat_b = None;
 
     // Decode and play the packets belonging to the selected track.
-    let result = loop {
+    let result = 'play: loop {
+        // The timestamp to seek to before getting the next packet, if any.
+        let mut seek_to = None;
+
+        // Handle the commands issued by the user, if the interactive controls are enabled. While
+        // paused, wait for the next command.
+        if let Some(controls) = controls {
+            let mut paused = false;
+
+            while let Some(command) =
+                if paused { Some(controls.recv()) } else { controls.try_recv() }
+            {
+                // Relative seeks are relative to the last seek that is yet to be performed.
+                let cur_ts = seek_to.unwrap_or(last_ts);
+
+                match command {
+                    controls::Command::TogglePause => paused = !paused,
+                    controls::Command::Quit => break 'play Ok(()),
+                    controls::Command::SeekBy(secs) => match tb {
+                        Some(tb) => {
+                            let delta = tb.calc_timestamp(Time::from(secs.unsigned_abs()));
+
+                            let ts = if secs < 0 {
+                                cur_ts.saturating_sub(delta)
+                            }
+                            else {
+                                cur_ts.saturating_add(delta)
+                            };
+
+                            // Do not seek past the end of the track if its duration is known.
+                            seek_to = Some(dur.map_or(ts, |dur| ts.min(dur)));
+                        }
+                        None => warn!("seek error: the track does not have a time base"),
+                    },
+                    controls::Command::SeekToPercent(percent) => match dur {
+                        Some(dur) => {
+                            seek_to = Some(start_ts + (dur - start_ts) * u64::from(percent) / 100)
+                        }
+                        None => warn!("seek error: the track does not have a known duration"),
+                    },
+                    controls::Command::MarkRepeat => match (repeat_a, repeat_b) {
+                        // Mark the start of the section to repeat.
+                        (None, _) => repeat_a = Some(cur_ts),
+                        // Mark the end of the section, and start repeating it.
+                        (Some(a), None) if cur_ts > a => {
+                            repeat_b = Some(cur_ts);
+                            seek_to = Some(a);
+                        }
+                        // Stop repeating the section.
+                        _ => {
+                            repeat_a = None;
+                            repeat_b = None;
+                        }
+                    },
+                }
+            }
+        }
+
+        // If the end of the section being repeated was reached, seek back to its start.
+        if let (Some(a), Some(b)) = (repeat_a, repeat_b) {
+            if seek_to.is_none() && last_ts >= b {
+                seek_to = Some(a);
+            }
+        }
+
+        if let Some(ts) = seek_to {
+            match seek_track(reader, &mut decoder, play_opts.track_id, ts) {
+                Ok(Some(required_ts)) => {
+                    seek_ts = required_ts;
+                    last_ts = required_ts;
+                }
+                Ok(None) => (),
+                Err(err) => break Err(err),
+            }
+        }
+
         // Get the next packet from the format reader.
         let packet = match reader.next_packet() {
             Ok(packet) => packet,
+            Err(err) if play_opts.repeat && is_end_of_stream(&err) => {
+                // Repeat the track by seeking back to its start. If the seek fails, then the track
+                // cannot be repeated.
+                match seek_track(reader, &mut decoder, play_opts.track_id, start_ts) {
+                    Ok(Some(required_ts)) => {
+                        seek_ts = required_ts;
+                        last_ts = required_ts;
+                        continue;
+                    }
+                    Ok(None) => break Err(err),
+                    Err(err) => break Err(err),
+                }
+            }
+            Err(Error::DecodeError(err))
+                if play_opts.resync && resync_errors < MAX_RESYNC_ERRORS =>
+            {
+                // The format reader will attempt to resynchronize on the next call.
+                warn!("resyncing after demux error: {}", err);
+                resync_errors += 1;
+                continue;
+            }
             Err(err) => break Err(err),
         };
 
+        resync_errors = 0;
+
         // If the packet does not belong to the selected track, skip it.
         if packet.track_id() != play_opts.track_id {
             continue;
@@ -405,10 +997,20 @@ fn play_track(
                     // Get the capacity of the decoded buffer. Note that this is capacity, not
                     // length! The capacity of the decoded buffer is constant for the life of the
                     // decoder, but the length is not.
-                    let duration = decoded.capacity() as u64;
+                    let mut duration = decoded.capacity() as u64;
 
-                    // Try to open the audio output.
-                    audio_output.replace(output::try_open(spec, duration).unwrap());
+                    // Changing the speed changes the number of frames written to the audio output.
+                    if let Some(speed) = &speed {
+                        duration = speed.max_frames(&spec, duration);
+                    }
+
+                    // Try to open the audio output. If it fails to open, for example, because the
+                    // selected host or device does not exist, then playback cannot continue. The
+                    // error was already logged.
+                    match output::try_open(spec, duration, device_opts) {
+                        Ok(output) => audio_output.replace(output),
+                        Err(_) => return Ok(-1),
+                    };
                 }
                 else {
                     // TODO: Check the audio spec. and duration hasn't changed.
@@ -416,9 +1018,28 @@ fn play_track(
 
                 // Write the decoded audio samples to the audio output if the presentation timestamp
                 // for the packet is >= the seeked position (0 if not seeking).
-                if packet.ts() >= play_opts.seek_ts {
-                    if !no_progress {
-                        print_progress(packet.ts(), dur, tb);
+                if packet.ts() >= seek_ts {
+                    last_ts = packet.ts();
+
+                    // Apply the gain, and change the speed, if enabled, before visualizing and
+                    // writing to the audio output.
+                    let decoded = match gain.as_mut() {
+                        Some(gain) => gain.apply(decoded),
+                        None => decoded,
+                    };
+
+                    let decoded = match speed.as_mut() {
+                        Some(speed) => speed.apply(decoded),
+                        None => decoded,
+                    };
+
+                    // The visualization is drawn above the progress, so update it first.
+                    if let Some(visualizer) = visualizer.as_mut() {
+                        visualizer.update(&decoded);
+                    }
+
+                    if !play_opts.no_progress {
+                        print_progress(packet.ts(), dur, tb, repeat_b.is_some());
                     }
 
                     if let Some(audio_output) = audio_output {
@@ -435,8 +1056,9 @@ fn play_track(
         }
     };
 
-    if !no_progress {
-        println!();
+    if !play_opts.no_progress {
+        // The carriage return is required if the terminal is in raw mode.
+        print!("\r\n");
     }
 
     // Return if a fatal error occured.
@@ -446,20 +1068,68 @@ fn play_track(
     do_verification(decoder.finalize())
 }
 
+/// Seeks the reader to the timestamp of the track, and resets the decoder. Returns the timestamp of
+/// the first frame to play, or `None` if the seek failed.
+fn seek_track(
+    reader: &mut Box<dyn FormatReader>,
+    decoder: &mut Box<dyn Decoder>,
+    track_id: u32,
+    ts: u64,
+) -> Result<Option<u64>> {
+    match reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts, track_id }) {
+        Ok(seeked_to) => {
+            // The decoder must be reset after seeking since the packets that follow do not continue
+            // from the last decoded packet.
+            decoder.reset();
+
+            Ok(Some(seeked_to.required_ts))
+        }
+        // A reset is handled the same way as if it was returned by next_packet.
+        Err(err @ Error::ResetRequired) => Err(err),
+        Err(err) => {
+            // Don't give-up on a seek error.
+            warn!("seek error: {}", err);
+            Ok(None)
+        }
+    }
+}
+
 fn first_supported_track(tracks: &[Track]) -> Option<&Track> {
     tracks.iter().find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
 }
 
+/// Selects the track with the ID provided by the user, or, if no ID was provided, the first track
+/// with a known codec.
+fn select_track(tracks: &[Track], track_id: Option<u32>) -> Option<&Track> {
+    match track_id {
+        Some(id) => {
+            let track = tracks.iter().find(|t| t.id == id);
+
+            if track.is_none() {
+                error!("there is no track with the id {}", id);
+            }
+
+            track
+        }
+        None => first_supported_track(tracks),
+    }
+}
+
+fn is_end_of_stream(err: &Error) -> bool {
+    // An "end of stream" error is currently the only way a format reader can indicate the media is
+    // complete.
+    match err {
+        Error::IoError(err) => {
+            err.kind() == std::io::ErrorKind::UnexpectedEof && err.to_string() == "end of stream"
+        }
+        _ => false,
+    }
+}
+
 fn ignore_end_of_stream_error(result: Result<()>) -> Result<()> {
     match result {
-        Err(Error::IoError(err))
-            if err.kind() == std::io::ErrorKind::UnexpectedEof
-                && err.to_string() == "end of stream" =>
-        {
-            // Do not treat "end of stream" as a fatal error. It's the currently only way a
-            // format reader can indicate the media is complete.
-            Ok(())
-        }
+        // Do not treat "end of stream" as a fatal error.
+        Err(err) if is_end_of_stream(&err) => Ok(()),
         _ => result,
     }
 }
@@ -477,7 +1147,7 @@ fn do_verification(finalization: FinalizeResult) -> Result<i32> {
     }
 }
 
-fn dump_visual(visual: &Visual, file_name: &OsStr, index: usize) {
+fn visual_file_name(visual: &Visual, file_name: &OsStr, index: usize) -> OsString {
     let extension = match visual.media_type.to_lowercase().as_str() {
         "image/bmp" => ".bmp",
         "image/gif" => ".gif",
@@ -488,6 +1158,11 @@ fn dump_visual(visual: &Visual, file_name: &OsStr, index: usize) {
 
     let mut out_file_name = OsString::from(file_name);
     out_file_name.push(format!("-{:0>2}{}", index, extension));
+    out_file_name
+}
+
+fn dump_visual(visual: &Visual, file_name: &OsStr, index: usize) {
+    let out_file_name = visual_file_name(visual, file_name, index);
 
     if let Err(err) = File::create(out_file_name).and_then(|mut file| file.write_all(&visual.data))
     {
@@ -547,6 +1222,38 @@ fn print_update(rev: &MetadataRevision) {
     println!();
 }
 
+fn list_devices() -> i32 {
+    let hosts = match output::list_devices() {
+        Ok(hosts) => hosts,
+        // The error was already logged.
+        Err(_) => return -1,
+    };
+
+    for host in hosts {
+        println!("+ {}{}", host.name, if host.is_default { " (default)" } else { "" });
+        println!("|");
+        println!("| // Devices //");
+
+        for (idx, device) in host.devices.iter().enumerate() {
+            println!(
+                "|     [{:0>2}] {}{}",
+                idx + 1,
+                device.name,
+                if device.is_default { " (default)" } else { "" }
+            );
+
+            if let Some(description) = &device.description {
+                println!("|          {}", description);
+            }
+        }
+
+        println!(":");
+        println!();
+    }
+
+    0
+}
+
 fn print_tracks(tracks: &[Track]) {
     if !tracks.is_empty() {
         println!("|");
@@ -555,7 +1262,8 @@ fn print_tracks(tracks: &[Track]) {
         for (idx, track) in tracks.iter().enumerate() {
             let params = &track.codec_params;
 
-            print!("|     [{:0>2}] Codec:           ", idx + 1);
+            println!("|     [{:0>2}] Track ID:        {}", idx + 1, track.id);
+            print!("|          Codec:           ");
 
             if let Some(codec) = symphonia::default::get_codecs().get_codec(params.codec) {
                 println!("{} ({})", codec.long_name, codec.short_name);
@@ -780,7 +1488,7 @@ fn fmt_time(ts: u64, tb: TimeBase) -> String {
     format!("{}:{:0>2}:{:0>6.3}", hours, mins, secs)
 }
 
-fn print_progress(ts: u64, dur: Option<u64>, tb: Option<TimeBase>) {
+fn print_progress(ts: u64, dur: Option<u64>, tb: Option<TimeBase>, is_repeating: bool) {
     // Get a string slice containing a progress bar.
     fn progress_bar(ts: u64, dur: u64) -> &'static str {
         const NUM_STEPS: usize = 60;
@@ -800,6 +1508,9 @@ fn print_progress(ts: u64, dur: Option<u64>, tb: Option<TimeBase>) {
         &PROGRESS_BAR[i as usize]
     }
 
+    // Display the repeat symbol instead of the play symbol if a section is being repeated.
+    let symbol = if is_repeating { "\u{1f501}" } else { "\u{25b6}\u{fe0f}" };
+
     // Multiple print! calls would need to be made to print the progress, so instead, only lock
     // stdout once and use write! rather then print!.
     let stdout = std::io::stdout();
@@ -812,7 +1523,7 @@ fn print_progress(ts: u64, dur: Option<u64>, tb: Option<TimeBase>) {
         let mins = (t.seconds % (60 * 60)) / 60;
         let secs = f64::from((t.seconds % 60) as u32) + t.frac;
 
-        write!(output, "\r\u{25b6}\u{fe0f}  {}:{:0>2}:{:0>4.1}", hours, mins, secs).unwrap();
+        write!(output, "\r{}  {}:{:0>2}:{:0>4.1}", symbol, hours, mins, secs).unwrap();
 
         if let Some(dur) = dur {
             let d = tb.calc_time(dur.saturating_sub(ts));
@@ -826,7 +1537,7 @@ fn print_progress(ts: u64, dur: Option<u64>, tb: Option<TimeBase>) {
         }
     }
     else {
-        write!(output, "\r\u{25b6}\u{fe0f}  {}", ts).unwrap();
+        write!(output, "\r{}  {}", symbol, ts).unwrap();
     }
 
     // This extra space is a workaround for Konsole to correctly erase the previous line.
@@ -28,9 +28,35 @@ pub enum AudioOutputError {
 
 pub type Result<T> = result::Result<T, AudioOutputError>;
 
+/// The audio output device to open.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceOptions {
+    /// The name of the audio host, or `None` to use the default host.
+    pub host: Option<String>,
+    /// The name of the audio output device, or `None` to use the default device of the host.
+    pub device: Option<String>,
+}
+
+/// An audio host, and its audio output devices.
+pub struct HostInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub devices: Vec<DeviceInfo>,
+}
+
+/// An audio output device.
+pub struct DeviceInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: bool,
+}
+
 #[cfg(target_os = "linux")]
 mod pulseaudio {
-    use super::{AudioOutput, AudioOutputError, Result};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::{AudioOutput, AudioOutputError, DeviceInfo, DeviceOptions, HostInfo, Result};
 
     use symphonia::core::audio::*;
     use symphonia::core::units::Duration;
@@ -40,13 +66,27 @@ mod pulseaudio {
 
     use log::{error, warn};
 
+    /// The name of the only audio host.
+    const HOST_NAME: &str = "PulseAudio";
+
     pub struct PulseAudioOutput {
         pa: psimple::Simple,
         sample_buf: RawSampleBuffer<f32>,
     }
 
     impl PulseAudioOutput {
-        pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
+        pub fn try_open(
+            spec: SignalSpec,
+            duration: Duration,
+            device_opts: &DeviceOptions,
+        ) -> Result<Box<dyn AudioOutput>> {
+            if let Some(host) = &device_opts.host {
+                if !host.eq_ignore_ascii_case(HOST_NAME) {
+                    error!("audio output host '{}' is not available", host);
+                    return Err(AudioOutputError::OpenStreamError);
+                }
+            }
+
             // An interleaved buffer is required to send data to PulseAudio. Use a SampleBuffer to
             // move data between Symphonia AudioBuffers and the byte buffers required by PulseAudio.
             let sample_buf = RawSampleBuffer::<f32>::new(duration, spec);
@@ -78,7 +118,7 @@ mod pulseaudio {
                 None,                               // Use default server
                 "Symphonia Player",                 // Application name
                 pulse::stream::Direction::Playback, // Playback stream
-                None,                               // Default playback device
+                device_opts.device.as_deref(),      // Playback device (sink)
                 "Music",                            // Description of the stream
                 &pa_spec,                           // Signal specification
                 pa_ch_map.as_ref(),                 // Channel map
@@ -123,6 +163,107 @@ mod pulseaudio {
         }
     }
 
+    /// Iterates the main loop once, blocking until there are events to dispatch.
+    fn iterate(mainloop: &mut pulse::mainloop::standard::Mainloop) -> Result<()> {
+        match mainloop.iterate(true) {
+            pulse::mainloop::standard::IterateResult::Success(_) => Ok(()),
+            pulse::mainloop::standard::IterateResult::Quit(_) => {
+                error!("audio host main loop quit unexpectedly");
+                Err(AudioOutputError::OpenStreamError)
+            }
+            pulse::mainloop::standard::IterateResult::Err(err) => {
+                error!("audio host main loop error: {}", err);
+                Err(AudioOutputError::OpenStreamError)
+            }
+        }
+    }
+
+    /// Lists the sinks of the PulseAudio server.
+    pub fn list_devices() -> Result<Vec<HostInfo>> {
+        let mut mainloop = match pulse::mainloop::standard::Mainloop::new() {
+            Some(mainloop) => mainloop,
+            _ => {
+                error!("failed to create the audio host main loop");
+                return Err(AudioOutputError::OpenStreamError);
+            }
+        };
+
+        let mut context = match pulse::context::Context::new(&mainloop, "Symphonia Player") {
+            Some(context) => context,
+            _ => {
+                error!("failed to create the audio host context");
+                return Err(AudioOutputError::OpenStreamError);
+            }
+        };
+
+        if let Err(err) = context.connect(None, pulse::context::FlagSet::NOFLAGS, None) {
+            error!("failed to connect to the audio host: {}", err);
+            return Err(AudioOutputError::OpenStreamError);
+        }
+
+        // Wait for the connection to the server to be ready.
+        loop {
+            iterate(&mut mainloop)?;
+
+            match context.get_state() {
+                pulse::context::State::Ready => break,
+                pulse::context::State::Failed | pulse::context::State::Terminated => {
+                    error!("failed to connect to the audio host");
+                    return Err(AudioOutputError::OpenStreamError);
+                }
+                _ => (),
+            }
+        }
+
+        let default_sink = Rc::new(RefCell::new(None));
+        let devices = Rc::new(RefCell::new(Vec::new()));
+
+        let introspector = context.introspect();
+
+        let server_op = {
+            let default_sink = default_sink.clone();
+
+            introspector.get_server_info(move |info| {
+                *default_sink.borrow_mut() = info.default_sink_name.as_ref().map(|n| n.to_string());
+            })
+        };
+
+        let sinks_op = {
+            let devices = devices.clone();
+
+            introspector.get_sink_info_list(move |result| {
+                if let pulse::callbacks::ListResult::Item(info) = result {
+                    if let Some(name) = &info.name {
+                        devices.borrow_mut().push(DeviceInfo {
+                            name: name.to_string(),
+                            description: info.description.as_ref().map(|d| d.to_string()),
+                            is_default: false,
+                        });
+                    }
+                }
+            })
+        };
+
+        // Wait for both operations to complete.
+        while server_op.get_state() == pulse::operation::State::Running
+            || sinks_op.get_state() == pulse::operation::State::Running
+        {
+            iterate(&mut mainloop)?;
+        }
+
+        context.disconnect();
+
+        let mut devices = devices.take();
+
+        if let Some(default_sink) = default_sink.take() {
+            for device in devices.iter_mut() {
+                device.is_default = device.name == default_sink;
+            }
+        }
+
+        Ok(vec![HostInfo { name: HOST_NAME.to_string(), is_default: true, devices }])
+    }
+
     /// Maps a set of Symphonia `Channels` to a PulseAudio channel map.
     fn map_channels_to_pa_channelmap(channels: Channels) -> Option<pulse::channelmap::Map> {
         let mut map: pulse::channelmap::Map = Default::default();
@@ -169,7 +310,7 @@ mod pulseaudio {
 mod cpal {
     use crate::resampler::Resampler;
 
-    use super::{AudioOutput, AudioOutputError, Result};
+    use super::{AudioOutput, AudioOutputError, DeviceInfo, DeviceOptions, HostInfo, Result};
 
     use symphonia::core::audio::{AudioBufferRef, RawSample, SampleBuffer, SignalSpec};
     use symphonia::core::conv::{ConvertibleSample, IntoSample};
@@ -178,7 +319,7 @@ mod cpal {
     use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
     use rb::*;
 
-    use log::{error, info};
+    use log::{error, info, warn};
 
     pub struct CpalAudioOutput;
 
@@ -192,42 +333,189 @@ mod cpal {
     impl AudioOutputSample for u16 {}
 
     impl CpalAudioOutput {
-        pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-            // Get default host.
-            let host = cpal::default_host();
-
-            // Get the default audio output device.
-            let device = match host.default_output_device() {
-                Some(device) => device,
-                _ => {
-                    error!("failed to get default audio output device");
-                    return Err(AudioOutputError::OpenStreamError);
-                }
-            };
+        pub fn try_open(
+            spec: SignalSpec,
+            duration: Duration,
+            device_opts: &DeviceOptions,
+        ) -> Result<Box<dyn AudioOutput>> {
+            let host = get_host(device_opts.host.as_deref())?;
+            let device = get_device(&host, device_opts.device.as_deref())?;
 
-            let config = match device.default_output_config() {
-                Ok(config) => config,
-                Err(err) => {
-                    error!("failed to get default audio output device config: {}", err);
-                    return Err(AudioOutputError::OpenStreamError);
-                }
-            };
+            let config = negotiate_config(&device, spec)?;
 
             // Select proper playback routine based on sample format.
             match config.sample_format() {
                 cpal::SampleFormat::F32 => {
-                    CpalAudioOutputImpl::<f32>::try_open(spec, duration, &device)
+                    CpalAudioOutputImpl::<f32>::try_open(spec, duration, &device, config.config())
                 }
                 cpal::SampleFormat::I16 => {
-                    CpalAudioOutputImpl::<i16>::try_open(spec, duration, &device)
+                    CpalAudioOutputImpl::<i16>::try_open(spec, duration, &device, config.config())
                 }
                 cpal::SampleFormat::U16 => {
-                    CpalAudioOutputImpl::<u16>::try_open(spec, duration, &device)
+                    CpalAudioOutputImpl::<u16>::try_open(spec, duration, &device, config.config())
+                }
+            }
+        }
+    }
+
+    /// Gets the audio host with the name, or the default host if no name is provided.
+    fn get_host(name: Option<&str>) -> Result<cpal::Host> {
+        let name = match name {
+            Some(name) => name,
+            _ => return Ok(cpal::default_host()),
+        };
+
+        let id = match cpal::available_hosts()
+            .into_iter()
+            .find(|id| id.name().eq_ignore_ascii_case(name))
+        {
+            Some(id) => id,
+            _ => {
+                error!("audio output host '{}' is not available", name);
+                return Err(AudioOutputError::OpenStreamError);
+            }
+        };
+
+        cpal::host_from_id(id).map_err(|err| {
+            error!("failed to get audio output host '{}': {}", name, err);
+            AudioOutputError::OpenStreamError
+        })
+    }
+
+    /// Gets the audio output device with the name, or the default device of the host if no name is
+    /// provided.
+    fn get_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device> {
+        let device = match name {
+            Some(name) => match host.output_devices() {
+                Ok(mut devices) => {
+                    devices.find(|device| device.name().map_or(false, |n| n == name))
+                }
+                Err(err) => {
+                    error!("failed to get audio output devices: {}", err);
+                    return Err(AudioOutputError::OpenStreamError);
                 }
+            },
+            _ => host.default_output_device(),
+        };
+
+        match device {
+            Some(device) => Ok(device),
+            _ => {
+                error!("failed to get audio output device '{}'", name.unwrap_or("default"));
+                Err(AudioOutputError::OpenStreamError)
             }
         }
     }
 
+    /// Negotiates the configuration of the output stream with the device.
+    ///
+    /// A configuration with the same sample rate and number of channels as the decoded audio is
+    /// preferred. Otherwise, a configuration at the default sample rate of the device is used, and
+    /// the decoded audio is resampled.
+    fn negotiate_config(
+        device: &cpal::Device,
+        spec: SignalSpec,
+    ) -> Result<cpal::SupportedStreamConfig> {
+        let default_config = match device.default_output_config() {
+            Ok(config) => config,
+            Err(err) => {
+                error!("failed to get default audio output device config: {}", err);
+                return Err(AudioOutputError::OpenStreamError);
+            }
+        };
+
+        let num_channels = spec.channels.count() as cpal::ChannelCount;
+
+        let ranges = match device.supported_output_configs() {
+            Ok(ranges) => ranges.filter(|range| range.channels() == num_channels).collect(),
+            Err(err) => {
+                warn!("failed to get supported audio output device configs: {}", err);
+                Vec::new()
+            }
+        };
+
+        // Find a configuration that supports the sample rate, preferring the default sample format.
+        let find = |rate: cpal::SampleRate| {
+            let mut supported = ranges
+                .iter()
+                .filter(|range| range.min_sample_rate() <= rate && rate <= range.max_sample_rate());
+
+            supported
+                .clone()
+                .find(|range| range.sample_format() == default_config.sample_format())
+                .or_else(|| supported.next())
+                .map(|range| range.clone().with_sample_rate(rate))
+        };
+
+        if let Some(config) = find(cpal::SampleRate(spec.rate)) {
+            return Ok(config);
+        }
+
+        if let Some(config) = find(default_config.sample_rate()) {
+            return Ok(config);
+        }
+
+        if default_config.channels() != num_channels {
+            warn!("audio output device does not support {} channel(s)", num_channels);
+        }
+
+        Ok(default_config)
+    }
+
+    /// Lists the available audio hosts, and their audio output devices.
+    pub fn list_devices() -> Result<Vec<HostInfo>> {
+        let default_host_id = cpal::default_host().id();
+
+        let mut hosts = Vec::new();
+
+        for id in cpal::available_hosts() {
+            let host = match cpal::host_from_id(id) {
+                Ok(host) => host,
+                Err(err) => {
+                    warn!("failed to get audio output host '{}': {}", id.name(), err);
+                    continue;
+                }
+            };
+
+            let default_name = host.default_output_device().and_then(|device| device.name().ok());
+
+            let mut devices = Vec::new();
+
+            match host.output_devices() {
+                Ok(output_devices) => {
+                    for device in output_devices {
+                        let name = match device.name() {
+                            Ok(name) => name,
+                            _ => continue,
+                        };
+
+                        let description = device.default_output_config().ok().map(|config| {
+                            format!(
+                                "{} Hz, {} channel(s), {:?}",
+                                config.sample_rate().0,
+                                config.channels(),
+                                config.sample_format()
+                            )
+                        });
+
+                        let is_default = default_name.as_ref() == Some(&name);
+
+                        devices.push(DeviceInfo { name, description, is_default });
+                    }
+                }
+                Err(err) => warn!("failed to get audio output devices of '{}': {}", id.name(), err),
+            }
+
+            hosts.push(HostInfo {
+                name: id.name().to_string(),
+                is_default: id == default_host_id,
+                devices,
+            });
+        }
+
+        Ok(hosts)
+    }
+
     struct CpalAudioOutputImpl<T: AudioOutputSample>
     where
         T: AudioOutputSample,
@@ -243,25 +531,10 @@ mod cpal {
             spec: SignalSpec,
             duration: Duration,
             device: &cpal::Device,
+            config: cpal::StreamConfig,
         ) -> Result<Box<dyn AudioOutput>> {
             let num_channels = spec.channels.count();
 
-            // Output audio stream config.
-            let config = if cfg!(not(target_os = "windows")) {
-                cpal::StreamConfig {
-                    channels: num_channels as cpal::ChannelCount,
-                    sample_rate: cpal::SampleRate(spec.rate),
-                    buffer_size: cpal::BufferSize::Default,
-                }
-            }
-            else {
-                // Use the default config for Windows.
-                device
-                    .default_output_config()
-                    .expect("Failed to get the default output config.")
-                    .config()
-            };
-
             // Create a ring buffer with a capacity for up-to 200ms of audio.
             let ring_len = ((200 * config.sample_rate.0 as usize) / 1000) * num_channels;
 
@@ -358,11 +631,29 @@ mod cpal {
 }
 
 #[cfg(target_os = "linux")]
-pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-    pulseaudio::PulseAudioOutput::try_open(spec, duration)
+pub fn try_open(
+    spec: SignalSpec,
+    duration: Duration,
+    device_opts: &DeviceOptions,
+) -> Result<Box<dyn AudioOutput>> {
+    pulseaudio::PulseAudioOutput::try_open(spec, duration, device_opts)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn try_open(
+    spec: SignalSpec,
+    duration: Duration,
+    device_opts: &DeviceOptions,
+) -> Result<Box<dyn AudioOutput>> {
+    cpal::CpalAudioOutput::try_open(spec, duration, device_opts)
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_devices() -> Result<Vec<HostInfo>> {
+    pulseaudio::list_devices()
 }
 
 #[cfg(not(target_os = "linux"))]
-pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-    cpal::CpalAudioOutput::try_open(spec, duration)
+pub fn list_devices() -> Result<Vec<HostInfo>> {
+    cpal::list_devices()
 }
@@ -0,0 +1,146 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Read-ahead Buffering
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// The maximum number of bytes read from the inner reader at once.
+const READ_LEN: usize = 4 * 1024;
+
+struct Buffer {
+    data: VecDeque<u8>,
+    /// The error that ended the stream, if any.
+    error: Option<io::Error>,
+    /// If true, the inner reader has reached the end of the stream, or failed.
+    is_eof: bool,
+    /// If true, the `ReadAhead` was dropped, and the reader thread should exit.
+    is_closed: bool,
+}
+
+struct Shared {
+    buf: Mutex<Buffer>,
+    cond: Condvar,
+}
+
+/// `ReadAhead` reads ahead of its consumer on a separate thread, up to a maximum number of bytes.
+///
+/// For piped or network input, reading ahead absorbs stalls of the input that would otherwise stall
+/// playback. The first read waits until the buffer is full, or the stream ended, such that playback
+/// starts with a full buffer.
+pub struct ReadAhead {
+    shared: Arc<Shared>,
+    /// The number of bytes to buffer before the first read returns.
+    prebuffer_len: usize,
+}
+
+impl ReadAhead {
+    /// Starts reading ahead, up to `len` bytes, from the inner reader.
+    pub fn new<R: Read + Send + 'static>(mut inner: R, len: usize) -> Self {
+        let shared = Arc::new(Shared {
+            buf: Mutex::new(Buffer {
+                data: VecDeque::with_capacity(len),
+                error: None,
+                is_eof: false,
+                is_closed: false,
+            }),
+            cond: Condvar::new(),
+        });
+
+        let thread_shared = Arc::clone(&shared);
+
+        thread::spawn(move || {
+            let shared = thread_shared;
+            let mut chunk = vec![0; READ_LEN];
+
+            loop {
+                // Wait until there is space for a chunk.
+                {
+                    let mut buf = shared.buf.lock().unwrap();
+
+                    while buf.data.len() + READ_LEN > len && !buf.is_closed {
+                        buf = shared.cond.wait(buf).unwrap();
+                    }
+
+                    if buf.is_closed {
+                        break;
+                    }
+                }
+
+                // Read without holding the lock, since reading may block.
+                let result = inner.read(&mut chunk);
+
+                let mut buf = shared.buf.lock().unwrap();
+
+                match result {
+                    Ok(0) => buf.is_eof = true,
+                    Ok(n) => buf.data.extend(&chunk[..n]),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        buf.error = Some(err);
+                        buf.is_eof = true;
+                    }
+                }
+
+                shared.cond.notify_all();
+
+                if buf.is_eof {
+                    break;
+                }
+            }
+        });
+
+        ReadAhead { shared, prebuffer_len: len.saturating_sub(READ_LEN).max(1) }
+    }
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        let mut buf = self.shared.buf.lock().unwrap();
+
+        // Wait for the buffer to be prebuffered on the first read, and for at least one byte after.
+        while buf.data.len() < self.prebuffer_len && !buf.is_eof {
+            buf = self.shared.cond.wait(buf).unwrap();
+        }
+
+        self.prebuffer_len = 1;
+
+        // Return the error that ended the stream once all buffered data was read.
+        if buf.data.is_empty() {
+            return match buf.error.take() {
+                Some(err) => Err(err),
+                None => Ok(0),
+            };
+        }
+
+        let n = out.len().min(buf.data.len());
+
+        for (out, byte) in out.iter_mut().zip(buf.data.drain(..n)) {
+            *out = byte;
+        }
+
+        self.shared.cond.notify_all();
+
+        Ok(n)
+    }
+}
+
+impl Drop for ReadAhead {
+    fn drop(&mut self) {
+        // If the reader thread is blocked reading the inner reader, it will exit after the read
+        // returns.
+        self.shared.buf.lock().unwrap().is_closed = true;
+        self.shared.cond.notify_all();
+    }
+}
@@ -0,0 +1,370 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Playback Speed
+
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+
+/// The minimum playback speed.
+pub const MIN_SPEED: f64 = 0.25;
+
+/// The maximum playback speed.
+pub const MAX_SPEED: f64 = 4.0;
+
+/// The number of zero crossings on each side of the centre of the resampler's sinc kernel.
+const SINC_ZEROS: usize = 8;
+
+/// The number of entries per zero crossing in the resampler's sinc kernel table.
+const SINC_RESOLUTION: usize = 256;
+
+/// The duration, in seconds, of a WSOLA frame.
+const WSOLA_FRAME_SECS: f64 = 0.04;
+
+/// The playback speed, and how it is changed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpeedOptions {
+    /// The playback speed factor. A factor of 2 plays twice as fast.
+    pub factor: f64,
+    /// If true, preserve the pitch of the audio. Otherwise, the pitch changes with the speed, like
+    /// a tape played at a different speed.
+    pub keep_pitch: bool,
+}
+
+/// A band-limited resampler that changes the speed, and pitch, of the audio by reading it at a
+/// fractional step.
+struct Resampler {
+    /// The number of input frames stepped over per output frame.
+    step: f64,
+    /// The cutoff frequency as a fraction of the input Nyquist frequency. When speeding up, the
+    /// cutoff is lowered to prevent aliasing.
+    cutoff: f64,
+    /// The number of input frames on each side of the centre of the kernel.
+    half_width: usize,
+    /// The Blackman windowed sinc kernel, from its centre to its last zero crossing.
+    kernel: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(step: f64) -> Self {
+        let cutoff = 0.97 * (1.0 / step).min(1.0);
+
+        let half_width = (SINC_ZEROS as f64 / cutoff).ceil() as usize;
+
+        let len = SINC_ZEROS * SINC_RESOLUTION;
+
+        let kernel = (0..len + 2)
+            .map(|i| {
+                let t = i as f64 / SINC_RESOLUTION as f64;
+
+                if t >= SINC_ZEROS as f64 {
+                    return 0.0;
+                }
+
+                let sinc = if i == 0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+
+                let x = PI * t / SINC_ZEROS as f64;
+                let window = 0.42 + 0.5 * x.cos() + 0.08 * (2.0 * x).cos();
+
+                (sinc * window) as f32
+            })
+            .collect();
+
+        Resampler { step, cutoff, half_width, kernel }
+    }
+
+    /// Gets the kernel value at a distance, in input frames, from its centre.
+    fn kernel(&self, dist: f64) -> f32 {
+        let pos = (dist * self.cutoff).abs() * SINC_RESOLUTION as f64;
+
+        let i = pos as usize;
+
+        if i + 1 >= self.kernel.len() {
+            return 0.0;
+        }
+
+        let frac = (pos - i as f64) as f32;
+
+        self.kernel[i] + frac * (self.kernel[i + 1] - self.kernel[i])
+    }
+
+    /// Interpolates the samples at a fractional position.
+    fn interpolate(&self, samples: &[f32], pos: f64) -> f32 {
+        let centre = pos as usize;
+
+        let start = centre + 1 - self.half_width;
+        let end = centre + self.half_width;
+
+        let sum = (start..=end).map(|i| samples[i] * self.kernel(i as f64 - pos)).sum::<f32>();
+
+        sum * self.cutoff as f32
+    }
+}
+
+/// A waveform similarity overlap-add (WSOLA) time-stretcher that changes the speed of the audio,
+/// but preserves its pitch.
+///
+/// WSOLA overlap-adds windowed frames of the input at a fixed output hop, whereas the input hop is
+/// scaled by the speed. To prevent phase cancellation, each frame is shifted, within a tolerance,
+/// to where it best continues the previous frame.
+struct Wsola {
+    /// The number of input frames stepped over per output frame.
+    step: f64,
+    /// The length of a frame.
+    len: usize,
+    /// The maximum shift of a frame.
+    tolerance: usize,
+    /// The periodic Hann window. Overlapping windows by half of their length sum to one.
+    window: Vec<f32>,
+    /// The start of the natural continuation of the last frame in the input, if any.
+    natural: Option<usize>,
+    /// The second half of the last windowed frame, to be overlap-added with the next frame.
+    overlap: Vec<Vec<f32>>,
+}
+
+impl Wsola {
+    fn new(step: f64, sample_rate: u32, n_channels: usize) -> Self {
+        let len = ((WSOLA_FRAME_SECS * f64::from(sample_rate)) as usize).max(64) & !1;
+
+        let window = (0..len)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / len as f64).cos()) as f32)
+            .collect();
+
+        Wsola {
+            step,
+            len,
+            tolerance: len / 8,
+            window,
+            natural: None,
+            overlap: vec![vec![0.0; len / 2]; n_channels],
+        }
+    }
+
+    /// Finds the start of the frame, within the tolerance of its nominal start, that best continues
+    /// the previous frame.
+    fn find_start(&self, input: &[Vec<f32>], nominal: usize) -> usize {
+        let hop = self.len / 2;
+
+        let natural = match self.natural {
+            Some(natural) => natural,
+            None => return nominal,
+        };
+
+        // The cross-correlation of the first half of the frame with the natural continuation. For
+        // speed, only every other sample is correlated.
+        let correlate = |start: usize| {
+            input
+                .iter()
+                .map(|samples| {
+                    (0..hop)
+                        .step_by(2)
+                        .map(|i| samples[start + i] * samples[natural + i])
+                        .sum::<f32>()
+                })
+                .sum::<f32>()
+        };
+
+        let mut best = (nominal, correlate(nominal));
+
+        for start in nominal - self.tolerance..=nominal + self.tolerance {
+            let corr = correlate(start);
+
+            if corr > best.1 {
+                best = (start, corr);
+            }
+        }
+
+        best.0
+    }
+}
+
+enum Stretcher {
+    Resampler(Resampler),
+    Wsola(Wsola),
+}
+
+/// `Speed` changes the playback speed of the decoded audio.
+pub struct Speed {
+    opts: SpeedOptions,
+    stretcher: Option<Stretcher>,
+    /// The decoded audio converted to `f32` samples.
+    conv: Option<AudioBuffer<f32>>,
+    /// The input audio that is yet to be consumed.
+    input: Vec<Vec<f32>>,
+    /// The position of the next output frame in the input.
+    pos: f64,
+    /// The audio with the speed changed.
+    buf: Option<AudioBuffer<f32>>,
+}
+
+impl Speed {
+    pub fn new(opts: SpeedOptions) -> Self {
+        Speed { opts, stretcher: None, conv: None, input: Vec::new(), pos: 0.0, buf: None }
+    }
+
+    /// Gets the maximum number of frames returned by `apply` for decoded audio with the signal
+    /// specification and the maximum number of frames.
+    pub fn max_frames(&self, spec: &SignalSpec, duration: u64) -> u64 {
+        // The input that may be left over from the previous call, in addition to the decoded audio,
+        // and the number of frames output at once.
+        let (leftover, hop) = if self.opts.keep_pitch {
+            let len = Wsola::new(self.opts.factor, spec.rate, 0).len;
+            (2 * len + (len as f64 * self.opts.factor) as usize, len / 2)
+        }
+        else {
+            (2 * Resampler::new(self.opts.factor).half_width + 1, 1)
+        };
+
+        ((duration as f64 + leftover as f64) / self.opts.factor).ceil() as u64 + hop as u64
+    }
+
+    /// Changes the speed of the decoded audio. The number of frames returned depends on the speed,
+    /// and may be 0.
+    pub fn apply<'a>(&'a mut self, decoded: AudioBufferRef<'_>) -> AudioBufferRef<'a> {
+        let spec = *decoded.spec();
+        let n_channels = spec.channels.count();
+
+        // (Re)initialize if the signal specification changed.
+        let is_compatible = match &self.conv {
+            Some(conv) => conv.spec() == decoded.spec() && conv.capacity() >= decoded.capacity(),
+            None => false,
+        };
+
+        if !is_compatible {
+            let (stretcher, pos) = if self.opts.keep_pitch {
+                let wsola = Wsola::new(self.opts.factor, spec.rate, n_channels);
+                let pos = wsola.tolerance;
+                (Stretcher::Wsola(wsola), pos)
+            }
+            else {
+                let resampler = Resampler::new(self.opts.factor);
+                let pos = resampler.half_width;
+                (Stretcher::Resampler(resampler), pos)
+            };
+
+            // Prefill the input with silence such that the first frames can be read.
+            self.input = vec![vec![0.0; pos]; n_channels];
+            self.pos = pos as f64;
+            self.stretcher = Some(stretcher);
+
+            let max_frames = self.max_frames(&spec, decoded.capacity() as u64);
+
+            self.conv = Some(decoded.make_equivalent::<f32>());
+            self.buf = Some(AudioBuffer::new(max_frames, spec));
+        }
+
+        let conv = self.conv.as_mut().unwrap();
+
+        decoded.convert(conv);
+
+        for (ch, input) in self.input.iter_mut().enumerate() {
+            input.extend_from_slice(conv.chan(ch));
+        }
+
+        let buf = self.buf.as_mut().unwrap();
+        buf.clear();
+
+        let consumed = match self.stretcher.as_mut().unwrap() {
+            Stretcher::Resampler(resampler) => resample(resampler, &self.input, &mut self.pos, buf),
+            Stretcher::Wsola(wsola) => stretch(wsola, &self.input, &mut self.pos, buf),
+        };
+
+        // Remove the consumed input.
+        for input in self.input.iter_mut() {
+            input.drain(..consumed);
+        }
+
+        AudioBufferRef::F32(Cow::Borrowed(buf))
+    }
+}
+
+/// Resamples the input into the buffer, and returns the number of input frames consumed.
+fn resample(
+    resampler: &Resampler,
+    input: &[Vec<f32>],
+    pos: &mut f64,
+    buf: &mut AudioBuffer<f32>,
+) -> usize {
+    let len = input[0].len();
+    let half_width = resampler.half_width;
+
+    // Count the number of frames that can be resampled with the input available.
+    let mut n_frames = 0;
+
+    while (*pos + n_frames as f64 * resampler.step) as usize + half_width < len
+        && n_frames < buf.capacity()
+    {
+        n_frames += 1;
+    }
+
+    buf.render_reserved(Some(n_frames));
+
+    for (ch, samples) in input.iter().enumerate() {
+        for (i, out) in buf.chan_mut(ch).iter_mut().enumerate() {
+            *out = resampler.interpolate(samples, *pos + i as f64 * resampler.step);
+        }
+    }
+
+    *pos += n_frames as f64 * resampler.step;
+
+    // Keep the input required by the kernel for the next frame.
+    let consumed = (*pos as usize + 1).saturating_sub(half_width);
+    *pos -= consumed as f64;
+
+    consumed
+}
+
+/// Time-stretches the input into the buffer, and returns the number of input frames consumed.
+fn stretch(
+    wsola: &mut Wsola,
+    input: &[Vec<f32>],
+    pos: &mut f64,
+    buf: &mut AudioBuffer<f32>,
+) -> usize {
+    let len = input[0].len();
+    let hop = wsola.len / 2;
+
+    while (*pos as usize) + wsola.tolerance + wsola.len <= len
+        && buf.frames() + hop <= buf.capacity()
+    {
+        let start = wsola.find_start(input, *pos as usize);
+
+        let offset = buf.frames();
+        buf.render_reserved(Some(hop));
+
+        // Overlap-add the first half of the frame with the second half of the previous frame, and
+        // keep the second half of this frame for the next frame.
+        for (ch, samples) in input.iter().enumerate() {
+            let frame = &samples[start..start + wsola.len];
+            let overlap = &mut wsola.overlap[ch];
+
+            let out = &mut buf.chan_mut(ch)[offset..];
+
+            for i in 0..hop {
+                out[i] = overlap[i] + frame[i] * wsola.window[i];
+                overlap[i] = frame[hop + i] * wsola.window[hop + i];
+            }
+        }
+
+        wsola.natural = Some(start + hop);
+        *pos += hop as f64 * wsola.step;
+    }
+
+    // Keep the input required to search for the next frame, and to continue the last frame.
+    let mut consumed = (*pos as usize).saturating_sub(wsola.tolerance);
+
+    if let Some(natural) = wsola.natural {
+        consumed = consumed.min(natural);
+        wsola.natural = Some(natural - consumed);
+    }
+
+    *pos -= consumed as f64;
+
+    consumed
+}
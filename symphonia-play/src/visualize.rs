@@ -0,0 +1,290 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Terminal Audio Visualization
+
+use std::io::Write;
+
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal};
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+use crossterm::cursor::MoveToPreviousLine;
+use crossterm::queue;
+use crossterm::terminal::{Clear, ClearType};
+
+/// The number of times the visualization is redrawn per second of audio.
+const UPDATES_PER_SEC: u32 = 20;
+
+/// The width, in characters, of the peak meters and the spectrum. This matches the width of the
+/// progress bar.
+const WIDTH: usize = 60;
+
+/// The maximum number of channels to draw a peak meter for.
+const MAX_METERS: usize = 8;
+
+/// The level, in decibels, of an empty peak meter.
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// The amount, in decibels, the held level of a peak meter falls per update.
+const METER_FALL_DB: f32 = 1.5;
+
+/// The FFT size of the spectrum analyzer.
+const FFT_SIZE: usize = 4096;
+
+/// The height, in lines, of the spectrum.
+const SPECTRUM_HEIGHT: usize = 8;
+
+/// The level, in decibels, of an empty spectrum band.
+const SPECTRUM_FLOOR_DB: f32 = -90.0;
+
+/// The lowest frequency, in Hz, shown in the spectrum.
+const SPECTRUM_MIN_FREQ: f32 = 20.0;
+
+/// The characters used to draw a partially filled spectrum band, in eighths.
+const BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The visualization to draw during playback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VisualizeMode {
+    /// Draw a peak meter per channel.
+    Meter,
+    /// Draw a peak meter per channel, and the spectrum of the audio.
+    Spectrum,
+}
+
+impl VisualizeMode {
+    /// The names of the visualization modes accepted by `from_name`.
+    pub const NAMES: &'static [&'static str] = &["meter", "spectrum"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "meter" => Some(VisualizeMode::Meter),
+            "spectrum" => Some(VisualizeMode::Spectrum),
+            _ => None,
+        }
+    }
+}
+
+/// A spectrum analyzer of the most recent audio, mixed down to mono.
+struct Spectrum {
+    fft: Fft,
+    /// The Hann window applied before the FFT.
+    window: Vec<f32>,
+    /// A ring buffer of the most recent `FFT_SIZE` samples.
+    samples: Vec<f32>,
+    /// The write position of the ring buffer.
+    pos: usize,
+    /// The FFT input and output.
+    bins: Vec<Complex>,
+}
+
+impl Spectrum {
+    fn new() -> Self {
+        let window = (0..FFT_SIZE)
+            .map(|i| {
+                let x = std::f32::consts::PI * i as f32 / FFT_SIZE as f32;
+                x.sin() * x.sin()
+            })
+            .collect();
+
+        Spectrum {
+            fft: Fft::new(FFT_SIZE),
+            window,
+            samples: vec![0.0; FFT_SIZE],
+            pos: 0,
+            bins: vec![Complex::default(); FFT_SIZE],
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.samples[self.pos] = sample;
+        self.pos = (self.pos + 1) % FFT_SIZE;
+    }
+
+    /// Calculates the level, in decibels, of `WIDTH` logarithmically spaced frequency bands.
+    fn bands(&mut self, sample_rate: u32) -> Vec<f32> {
+        // Unwrap the ring buffer, oldest sample first, and apply the window.
+        let samples = self.samples[self.pos..].iter().chain(&self.samples[..self.pos]);
+
+        for ((bin, &sample), &w) in self.bins.iter_mut().zip(samples).zip(&self.window) {
+            *bin = Complex::new(sample * w, 0.0);
+        }
+
+        self.fft.fft_inplace(&mut self.bins);
+
+        // A full scale sine wave has a magnitude of a quarter of the FFT size after applying the
+        // Hann window.
+        let scale = 4.0 / FFT_SIZE as f32;
+
+        let nyquist = sample_rate as f32 / 2.0;
+        let hz_per_bin = sample_rate as f32 / FFT_SIZE as f32;
+        let max_bin = FFT_SIZE / 2;
+
+        let freq = |band: usize| {
+            SPECTRUM_MIN_FREQ * (nyquist / SPECTRUM_MIN_FREQ).powf(band as f32 / WIDTH as f32)
+        };
+
+        (0..WIDTH)
+            .map(|band| {
+                // The bins of the band. A band always contains at least one bin.
+                let start = ((freq(band) / hz_per_bin) as usize).min(max_bin - 1);
+                let end = ((freq(band + 1) / hz_per_bin) as usize).clamp(start + 1, max_bin);
+
+                let peak = self.bins[start..end]
+                    .iter()
+                    .map(|bin| (bin.re * bin.re + bin.im * bin.im).sqrt())
+                    .fold(0.0f32, f32::max);
+
+                20.0 * (peak * scale).max(f32::MIN_POSITIVE).log10()
+            })
+            .collect()
+    }
+}
+
+/// `Visualizer` draws peak meters, and optionally a spectrum, of the audio being played to the
+/// terminal.
+///
+/// The visualization is drawn above the progress line, and is redrawn in-place.
+pub struct Visualizer {
+    spectrum: Option<Spectrum>,
+    /// The audio converted to `f32` samples.
+    buf: Option<AudioBuffer<f32>>,
+    /// The peak sample amplitude of each channel since the last update.
+    peaks: Vec<f32>,
+    /// The level, in decibels, displayed by each peak meter.
+    levels: Vec<f32>,
+    /// The number of frames since the last update.
+    frames: u32,
+    /// The number of lines drawn by the last update.
+    lines: u16,
+}
+
+impl Visualizer {
+    pub fn new(mode: VisualizeMode) -> Self {
+        let spectrum = match mode {
+            VisualizeMode::Meter => None,
+            VisualizeMode::Spectrum => Some(Spectrum::new()),
+        };
+
+        Visualizer {
+            spectrum,
+            buf: None,
+            peaks: Vec::new(),
+            levels: Vec::new(),
+            frames: 0,
+            lines: 0,
+        }
+    }
+
+    /// Analyzes the audio about to be played, and redraws the visualization if it is time to.
+    pub fn update(&mut self, decoded: &AudioBufferRef<'_>) {
+        let spec = *decoded.spec();
+        let n_channels = spec.channels.count();
+
+        // Allocate a new buffer if there is no buffer, or it is not compatible with the decoded
+        // audio.
+        let is_compatible = match &self.buf {
+            Some(buf) => buf.spec() == decoded.spec() && buf.capacity() >= decoded.capacity(),
+            None => false,
+        };
+
+        if !is_compatible {
+            self.buf = Some(decoded.make_equivalent::<f32>());
+            self.peaks = vec![0.0; n_channels];
+            self.levels = vec![METER_FLOOR_DB; n_channels];
+        }
+
+        let buf = self.buf.as_mut().unwrap();
+
+        decoded.convert(buf);
+
+        for (ch, peak) in self.peaks.iter_mut().enumerate() {
+            *peak = buf.chan(ch).iter().fold(*peak, |peak, sample| peak.max(sample.abs()));
+        }
+
+        if let Some(spectrum) = &mut self.spectrum {
+            let scale = 1.0 / n_channels as f32;
+
+            for i in 0..buf.frames() {
+                spectrum.push(scale * (0..n_channels).map(|ch| buf.chan(ch)[i]).sum::<f32>());
+            }
+        }
+
+        self.frames += buf.frames() as u32;
+
+        if self.frames >= spec.rate / UPDATES_PER_SEC {
+            self.frames = 0;
+            self.draw(spec.rate);
+        }
+    }
+
+    fn draw(&mut self, sample_rate: u32) {
+        let mut lines = Vec::new();
+
+        // Draw a peak meter per channel. The displayed level falls slowly, rather than following
+        // the peak, so that it can be read.
+        for (ch, (peak, level)) in
+            self.peaks.iter_mut().zip(self.levels.iter_mut()).enumerate().take(MAX_METERS)
+        {
+            let peak_db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+
+            *level = peak_db.max(*level - METER_FALL_DB).clamp(METER_FLOOR_DB, 0.0);
+            *peak = 0.0;
+
+            let filled = (WIDTH as f32 * (1.0 - *level / METER_FLOOR_DB)) as usize;
+
+            lines.push(format!(
+                "{:>2} [{}{}] {:>6.1} dB",
+                ch + 1,
+                str::repeat("■", filled),
+                str::repeat(" ", WIDTH - filled),
+                peak_db.max(METER_FLOOR_DB)
+            ));
+        }
+
+        // Draw the spectrum, top line first.
+        if let Some(spectrum) = &mut self.spectrum {
+            // The level of each band in eighths of a line.
+            let heights = spectrum
+                .bands(sample_rate)
+                .iter()
+                .map(|&db| {
+                    let frac = 1.0 - db.clamp(SPECTRUM_FLOOR_DB, 0.0) / SPECTRUM_FLOOR_DB;
+                    (frac * (8 * SPECTRUM_HEIGHT) as f32) as usize
+                })
+                .collect::<Vec<_>>();
+
+            for row in (0..SPECTRUM_HEIGHT).rev() {
+                let line = heights
+                    .iter()
+                    .map(|&height| BARS[height.saturating_sub(8 * row).min(8)])
+                    .collect::<String>();
+
+                lines.push(format!("   |{}|", line));
+            }
+        }
+
+        let stdout = std::io::stdout();
+        let mut output = stdout.lock();
+
+        // Move the cursor back to the first line of the last update to draw over it.
+        if self.lines > 0 {
+            queue!(output, MoveToPreviousLine(self.lines)).unwrap();
+        }
+
+        // The carriage return is required if the terminal is in raw mode.
+        for line in &lines {
+            queue!(output, Clear(ClearType::CurrentLine)).unwrap();
+            write!(output, "{}\r\n", line).unwrap();
+        }
+
+        output.flush().unwrap();
+
+        self.lines = lines.len() as u16;
+    }
+}
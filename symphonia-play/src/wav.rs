@@ -0,0 +1,257 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! WAVE File Output
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+use symphonia::core::audio::{AudioBufferRef, SampleBuffer, SignalSpec};
+use symphonia::core::conv::ConvertibleSample;
+use symphonia::core::sample::i24;
+
+use log::error;
+
+use crate::output::{AudioOutput, AudioOutputError, Result};
+
+/// The length of the header written before the audio data.
+const HEADER_LEN: u32 = 68;
+
+/// The sample format of the audio data of a WAVE file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WavSampleFormat {
+    U8,
+    S16,
+    S24,
+    S32,
+    F32,
+}
+
+impl WavSampleFormat {
+    /// The names of the sample formats accepted by `from_name`.
+    pub const NAMES: &'static [&'static str] = &["u8", "s16", "s24", "s32", "f32"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u8" => Some(WavSampleFormat::U8),
+            "s16" => Some(WavSampleFormat::S16),
+            "s24" => Some(WavSampleFormat::S24),
+            "s32" => Some(WavSampleFormat::S32),
+            "f32" => Some(WavSampleFormat::F32),
+            _ => None,
+        }
+    }
+
+    /// Gets the sample format closest to the sample format of the decoded audio.
+    fn from_decoded(decoded: &AudioBufferRef<'_>) -> Self {
+        match decoded {
+            AudioBufferRef::U8(_) | AudioBufferRef::S8(_) => WavSampleFormat::U8,
+            AudioBufferRef::U16(_) | AudioBufferRef::S16(_) => WavSampleFormat::S16,
+            AudioBufferRef::U24(_) | AudioBufferRef::S24(_) => WavSampleFormat::S24,
+            AudioBufferRef::U32(_) | AudioBufferRef::S32(_) => WavSampleFormat::S32,
+            AudioBufferRef::F32(_) | AudioBufferRef::F64(_) => WavSampleFormat::F32,
+        }
+    }
+
+    fn bits_per_sample(&self) -> u16 {
+        match self {
+            WavSampleFormat::U8 => 8,
+            WavSampleFormat::S16 => 16,
+            WavSampleFormat::S24 => 24,
+            WavSampleFormat::S32 | WavSampleFormat::F32 => 32,
+        }
+    }
+}
+
+/// A sample that may be written to a WAVE file.
+trait WavSample: ConvertibleSample {
+    fn write_le(self, buf: &mut Vec<u8>);
+}
+
+impl WavSample for u8 {
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.push(self);
+    }
+}
+
+impl WavSample for i16 {
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl WavSample for i24 {
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.inner().to_le_bytes()[..3]);
+    }
+}
+
+impl WavSample for i32 {
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl WavSample for f32 {
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Interleaves, and converts, the decoded audio into the buffer.
+fn interleave<S: WavSample>(decoded: AudioBufferRef<'_>, buf: &mut Vec<u8>) {
+    let mut sample_buf = SampleBuffer::<S>::new(decoded.capacity() as u64, *decoded.spec());
+
+    sample_buf.copy_interleaved_ref(decoded);
+
+    for &sample in sample_buf.samples() {
+        sample.write_le(buf);
+    }
+}
+
+/// The stream being written.
+struct WavStream {
+    spec: SignalSpec,
+    sample_format: WavSampleFormat,
+    /// The number of bytes of audio data written.
+    data_len: u64,
+}
+
+/// An `AudioOutput` that writes the decoded audio to a WAVE file.
+///
+/// The header of the file is written for the signal specification of the first decoded audio
+/// buffer, and is finalized with the length of the audio data when the output is flushed.
+pub struct WavOutput {
+    writer: BufWriter<File>,
+    /// The sample format, or `None` to use the sample format closest to the decoded audio.
+    sample_format: Option<WavSampleFormat>,
+    stream: Option<WavStream>,
+    buf: Vec<u8>,
+}
+
+impl WavOutput {
+    pub fn new(file: File, sample_format: Option<WavSampleFormat>) -> Self {
+        WavOutput { writer: BufWriter::new(file), sample_format, stream: None, buf: Vec::new() }
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        let stream = self.stream.as_ref().unwrap();
+
+        let num_channels = stream.spec.channels.count() as u16;
+        let bits_per_sample = stream.sample_format.bits_per_sample();
+        let block_align = num_channels * (bits_per_sample / 8);
+
+        // The lengths of a RIFF file, and its chunks, are limited to 32 bits.
+        let data_len = stream.data_len.min(u64::from(u32::MAX - HEADER_LEN)) as u32;
+
+        let sub_format: u16 = match stream.sample_format {
+            WavSampleFormat::F32 => 0x0003,
+            _ => 0x0001,
+        };
+
+        let w = &mut self.writer;
+
+        w.write_all(b"RIFF")?;
+        w.write_all(&(HEADER_LEN - 8 + data_len + (data_len & 1)).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+
+        // A WAVEFORMATEXTENSIBLE format chunk is always written so that the channel mask is
+        // preserved.
+        w.write_all(b"fmt ")?;
+        w.write_all(&40u32.to_le_bytes())?;
+        w.write_all(&0xfffeu16.to_le_bytes())?;
+        w.write_all(&num_channels.to_le_bytes())?;
+        w.write_all(&stream.spec.rate.to_le_bytes())?;
+        w.write_all(&(stream.spec.rate * u32::from(block_align)).to_le_bytes())?;
+        w.write_all(&block_align.to_le_bytes())?;
+        w.write_all(&bits_per_sample.to_le_bytes())?;
+        w.write_all(&22u16.to_le_bytes())?;
+        w.write_all(&bits_per_sample.to_le_bytes())?;
+        w.write_all(&stream.spec.channels.bits().to_le_bytes())?;
+        w.write_all(&sub_format.to_le_bytes())?;
+        w.write_all(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00])?;
+        w.write_all(&[0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71])?;
+
+        w.write_all(b"data")?;
+        w.write_all(&data_len.to_le_bytes())?;
+
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> std::io::Result<()> {
+        let data_len = match &self.stream {
+            Some(stream) => stream.data_len,
+            None => return Ok(()),
+        };
+
+        // Pad the data chunk to an even length.
+        if data_len & 1 == 1 {
+            self.writer.write_all(&[0])?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.flush()
+    }
+}
+
+impl AudioOutput for WavOutput {
+    fn write(&mut self, decoded: AudioBufferRef<'_>) -> Result<()> {
+        // Do nothing if there are no audio frames.
+        if decoded.frames() == 0 {
+            return Ok(());
+        }
+
+        match &self.stream {
+            Some(stream) if stream.spec != *decoded.spec() => {
+                error!("the signal specification changed, but a wave file can not change it");
+                return Err(AudioOutputError::StreamClosedError);
+            }
+            Some(_) => (),
+            None => {
+                let sample_format =
+                    self.sample_format.unwrap_or_else(|| WavSampleFormat::from_decoded(&decoded));
+
+                self.stream = Some(WavStream { spec: *decoded.spec(), sample_format, data_len: 0 });
+
+                if let Err(err) = self.write_header() {
+                    error!("wave file write error: {}", err);
+                    return Err(AudioOutputError::OpenStreamError);
+                }
+            }
+        }
+
+        let stream = self.stream.as_mut().unwrap();
+
+        self.buf.clear();
+
+        match stream.sample_format {
+            WavSampleFormat::U8 => interleave::<u8>(decoded, &mut self.buf),
+            WavSampleFormat::S16 => interleave::<i16>(decoded, &mut self.buf),
+            WavSampleFormat::S24 => interleave::<i24>(decoded, &mut self.buf),
+            WavSampleFormat::S32 => interleave::<i32>(decoded, &mut self.buf),
+            WavSampleFormat::F32 => interleave::<f32>(decoded, &mut self.buf),
+        }
+
+        stream.data_len += self.buf.len() as u64;
+
+        match self.writer.write_all(&self.buf) {
+            Err(err) => {
+                error!("wave file write error: {}", err);
+
+                Err(AudioOutputError::StreamClosedError)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(err) = self.finalize() {
+            error!("wave file write error: {}", err);
+        }
+    }
+}
@@ -0,0 +1,1016 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecType, CODEC_TYPE_AAC, CODEC_TYPE_DCA, CODEC_TYPE_EAC3};
+use symphonia_core::codecs::{CODEC_TYPE_MP1, CODEC_TYPE_MP2, CODEC_TYPE_MP3};
+use symphonia_core::errors::{decode_error, unsupported_error, Error, Result};
+use symphonia_core::io::{BitReaderLtr, FiniteBitStream, ReadBitsLtr};
+
+use log::{debug, warn};
+
+/// The codec of an audio elementary stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EsCodec {
+    /// MPEG-1 or MPEG-2 audio layer 1, 2, or 3.
+    Mpa,
+    /// AAC in ADTS frames.
+    Adts,
+    /// AAC in LATM frames with the LOAS AudioSyncStream framing.
+    Latm,
+    /// AC-3 or E-AC-3.
+    Ac3,
+    /// DTS, with any DTS-HD extension substreams.
+    Dts,
+}
+
+/// The codec parameters of an audio elementary stream, as determined from its frames.
+#[derive(Clone, Debug)]
+pub struct StreamInfo {
+    pub codec: CodecType,
+    pub sample_rate: u32,
+    pub channels: Option<Channels>,
+    pub extra_data: Option<Box<[u8]>>,
+}
+
+/// A packet of an audio elementary stream.
+pub struct Frame {
+    pub data: Box<[u8]>,
+    /// The presentation timestamp of the packet in 90 kHz units, if known.
+    pub pts: Option<u64>,
+    /// The duration of the packet in samples per channel.
+    pub dur: u64,
+}
+
+/// The result of parsing the start of the buffer.
+enum Parsed {
+    /// A frame of the given length, containing the given packets, and their durations.
+    Frame(usize, Vec<(Box<[u8]>, u64)>),
+    /// More data is required to parse the frame.
+    Incomplete,
+    /// The buffer does not start with a valid frame.
+    Invalid,
+}
+
+/// A parser of the frames of an audio elementary stream.
+pub struct FrameParser {
+    codec: EsCodec,
+    buf: Vec<u8>,
+    /// The offset of the start of the buffer in the elementary stream.
+    base: u64,
+    /// The offsets in the elementary stream of PES packets with a presentation timestamp, and
+    /// their presentation timestamp.
+    markers: VecDeque<(u64, u64)>,
+    /// The packets parsed, but not yet returned.
+    frames: VecDeque<Frame>,
+    /// The stream mux configuration of a LATM stream.
+    latm_config: Option<StreamMuxConfig>,
+    /// The codec parameters, once a frame has been parsed.
+    pub info: Option<StreamInfo>,
+}
+
+impl FrameParser {
+    pub fn new(codec: EsCodec) -> Self {
+        FrameParser {
+            codec,
+            buf: Vec::new(),
+            base: 0,
+            markers: VecDeque::new(),
+            frames: VecDeque::new(),
+            latm_config: None,
+            info: None,
+        }
+    }
+
+    /// Discards all buffered data.
+    pub fn reset(&mut self) {
+        self.base += self.buf.len() as u64;
+        self.buf.clear();
+        self.markers.clear();
+        self.frames.clear();
+    }
+
+    /// Adds the payload of a PES packet. If `pts` is provided, then the payload is the start of a
+    /// PES packet with that presentation timestamp.
+    pub fn push(&mut self, data: &[u8], pts: Option<u64>) {
+        if let Some(pts) = pts {
+            self.markers.push_back((self.base + self.buf.len() as u64, pts));
+        }
+
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Gets the next packet. If `eos` is true, then no more data will be added, and any frame at
+    /// the end of the buffer is assumed to be complete.
+    pub fn next_frame(&mut self, eos: bool) -> Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+
+            if self.buf.is_empty() {
+                return Ok(None);
+            }
+
+            let parsed = match self.codec {
+                EsCodec::Mpa | EsCodec::Adts => self.parse_mpa_adts(eos),
+                EsCodec::Latm => self.parse_latm(eos),
+                EsCodec::Ac3 => self.parse_ac3(eos),
+                EsCodec::Dts => self.parse_dts(eos),
+            };
+
+            match parsed {
+                Ok(Parsed::Frame(len, packets)) => {
+                    // The presentation timestamp of a PES packet applies to the first frame
+                    // starting within it.
+                    let pts = self.take_pts(self.base);
+
+                    for (i, (data, dur)) in packets.into_iter().enumerate() {
+                        let pts = if i == 0 { pts } else { None };
+                        self.frames.push_back(Frame { data, pts, dur });
+                    }
+
+                    self.consume(len);
+                }
+                Ok(Parsed::Incomplete) => {
+                    if eos {
+                        debug!("discarding {} bytes of an incomplete frame", self.buf.len());
+                        self.consume(self.buf.len());
+                    }
+                    return Ok(None);
+                }
+                Ok(Parsed::Invalid) => self.resync(),
+                Err(Error::DecodeError(err)) => {
+                    warn!("{}", err);
+                    self.resync();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Removes the markers preceding, or at, the offset, and returns the presentation timestamp of
+    /// the last one.
+    fn take_pts(&mut self, offset: u64) -> Option<u64> {
+        let mut pts = None;
+
+        while let Some(&(marker, marker_pts)) = self.markers.front() {
+            if marker > offset {
+                break;
+            }
+
+            pts = Some(marker_pts);
+            self.markers.pop_front();
+        }
+
+        pts
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.buf.drain(..len);
+        self.base += len as u64;
+    }
+
+    /// Skips to the next byte that could be the start of a frame.
+    fn resync(&mut self) {
+        let first = match self.codec {
+            EsCodec::Mpa | EsCodec::Adts => 0xff,
+            EsCodec::Latm => 0x56,
+            EsCodec::Ac3 => 0x0b,
+            EsCodec::Dts => 0x7f,
+        };
+
+        let len = self.buf[1..].iter().position(|&b| b == first).map_or(self.buf.len(), |p| p + 1);
+
+        debug!("skipping {} bytes", len);
+
+        self.consume(len);
+    }
+
+    /// Returns true if the buffer starts with a synchronization word compatible with the first
+    /// frame, `None` if the buffer is too short to tell.
+    fn is_next_sync(&self, pos: usize, eos: bool) -> Option<bool> {
+        let next = match self.buf.get(pos..pos + 2) {
+            Some(next) => next,
+            // At the end of the stream, a frame need not be followed by another.
+            None => return if eos { Some(true) } else { None },
+        };
+
+        let is_sync = match self.codec {
+            // The version and layer must be the same.
+            EsCodec::Mpa => next[0] == 0xff && next[1] & 0xfe == self.buf[1] & 0xfe,
+            EsCodec::Adts => next[0] == 0xff && next[1] & 0xf6 == 0xf0,
+            EsCodec::Latm => next[0] == 0x56 && next[1] & 0xe0 == 0xe0,
+            EsCodec::Ac3 => next == [0x0b, 0x77],
+            EsCodec::Dts => next == [0x7f, 0xfe] || next == [0x64, 0x58],
+        };
+
+        Some(is_sync)
+    }
+
+    fn set_info(&mut self, info: StreamInfo) {
+        if self.info.is_none() {
+            debug!("{:?}", info);
+            self.info = Some(info);
+        }
+    }
+
+    fn parse_mpa_adts(&mut self, eos: bool) -> Result<Parsed> {
+        let header = match self.buf.get(..9) {
+            Some(header) => header,
+            None => return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete }),
+        };
+
+        let (len, header_len, dur, info) = match self.codec {
+            EsCodec::Mpa => match read_mpa_header(header) {
+                Some((len, dur, info)) => (len, 0, dur, info),
+                None => return Ok(Parsed::Invalid),
+            },
+            _ => match read_adts_header(header) {
+                Some((len, header_len, info)) => (len, header_len, 1024, info),
+                None => return Ok(Parsed::Invalid),
+            },
+        };
+
+        if self.buf.len() < len {
+            return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete });
+        }
+
+        // A frame must be followed by another frame to guard against false synchronization.
+        match self.is_next_sync(len, eos) {
+            Some(true) => (),
+            Some(false) => return Ok(Parsed::Invalid),
+            None => return Ok(Parsed::Incomplete),
+        }
+
+        // The number of raw data blocks of an ADTS frame.
+        if self.codec == EsCodec::Adts && self.buf[6] & 0x3 != 0 {
+            warn!("skipping adts frame with more than 1 aac frame");
+            return Ok(Parsed::Frame(len, Vec::new()));
+        }
+
+        self.set_info(info);
+
+        let packet = Box::from(&self.buf[header_len..len]);
+
+        Ok(Parsed::Frame(len, vec![(packet, dur)]))
+    }
+
+    fn parse_latm(&mut self, eos: bool) -> Result<Parsed> {
+        let header = match self.buf.get(..3) {
+            Some(header) => header,
+            None => return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete }),
+        };
+
+        // The AudioSyncStream synchronization word is 11 bits, followed by a 13-bit length.
+        if header[0] != 0x56 || header[1] & 0xe0 != 0xe0 {
+            return Ok(Parsed::Invalid);
+        }
+
+        let len = 3 + ((usize::from(header[1] & 0x1f) << 8) | usize::from(header[2]));
+
+        if self.buf.len() < len {
+            return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete });
+        }
+
+        match self.is_next_sync(len, eos) {
+            Some(true) => (),
+            Some(false) => return Ok(Parsed::Invalid),
+            None => return Ok(Parsed::Incomplete),
+        }
+
+        let payloads = read_audio_mux_element(&self.buf[3..len], &mut self.latm_config)?;
+
+        let config = self.latm_config.as_ref().unwrap();
+
+        let info = StreamInfo {
+            codec: CODEC_TYPE_AAC,
+            sample_rate: config.sample_rate,
+            channels: config.channels,
+            extra_data: Some(config.asc.clone()),
+        };
+
+        let dur = config.samples;
+
+        self.set_info(info);
+
+        Ok(Parsed::Frame(len, payloads.into_iter().map(|payload| (payload, dur)).collect()))
+    }
+
+    fn parse_ac3(&mut self, eos: bool) -> Result<Parsed> {
+        let first = match self.buf.get(..AC3_HEADER_LEN) {
+            Some(header) => match read_ac3_header(header) {
+                Some(first) => first,
+                None => return Ok(Parsed::Invalid),
+            },
+            None => return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete }),
+        };
+
+        if self.buf.len() < first.len {
+            return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete });
+        }
+
+        // A frame of another substream outside of an access unit is skipped.
+        if !first.starts_access_unit {
+            debug!("skipping frame outside of an access unit");
+            return Ok(Parsed::Frame(first.len, Vec::new()));
+        }
+
+        // An access unit consists of a frame of independent substream 0, and the frames of the
+        // other substreams that follow it.
+        let mut len = first.len;
+
+        loop {
+            let header = match self.buf.get(len..len + AC3_HEADER_LEN) {
+                Some(header) => header,
+                None if eos => break,
+                None => return Ok(Parsed::Incomplete),
+            };
+
+            match read_ac3_header(header) {
+                Some(next) if !next.starts_access_unit => {
+                    if self.buf.len() < len + next.len {
+                        if eos {
+                            break;
+                        }
+                        return Ok(Parsed::Incomplete);
+                    }
+
+                    len += next.len;
+                }
+                _ => break,
+            }
+        }
+
+        self.set_info(first.info);
+
+        let packet = Box::from(&self.buf[..len]);
+
+        Ok(Parsed::Frame(len, vec![(packet, first.dur)]))
+    }
+
+    fn parse_dts(&mut self, eos: bool) -> Result<Parsed> {
+        let (mut len, dur, info) = match self.buf.get(..DTS_HEADER_LEN) {
+            Some(header) => match read_dts_header(header) {
+                Some(header) => header,
+                None => return Ok(Parsed::Invalid),
+            },
+            None => return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete }),
+        };
+
+        if self.buf.len() < len {
+            return Ok(if eos { Parsed::Invalid } else { Parsed::Incomplete });
+        }
+
+        // A core frame is followed by the frames of the extension substreams, if any.
+        loop {
+            let header = match self.buf.get(len..len + DTS_EXSS_HEADER_LEN) {
+                Some(header) => header,
+                None if eos => break,
+                None => return Ok(Parsed::Incomplete),
+            };
+
+            match read_dts_exss_len(header) {
+                Some(exss_len) => {
+                    if self.buf.len() < len + exss_len {
+                        if eos {
+                            break;
+                        }
+                        return Ok(Parsed::Incomplete);
+                    }
+
+                    len += exss_len;
+                }
+                None => break,
+            }
+        }
+
+        match self.is_next_sync(len, eos) {
+            Some(true) => (),
+            Some(false) => return Ok(Parsed::Invalid),
+            None => return Ok(Parsed::Incomplete),
+        }
+
+        self.set_info(info);
+
+        let packet = Box::from(&self.buf[..len]);
+
+        Ok(Parsed::Frame(len, vec![(packet, dur)]))
+    }
+}
+
+/// The channels of an AAC channel configuration.
+fn aac_channels(config: u32) -> Option<Channels> {
+    let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    let surround = Channels::FRONT_CENTRE | front | Channels::SIDE_LEFT | Channels::SIDE_RIGHT;
+
+    match config {
+        1 => Some(Channels::FRONT_LEFT),
+        2 => Some(front),
+        3 => Some(Channels::FRONT_CENTRE | front),
+        4 => Some(Channels::FRONT_CENTRE | front | Channels::REAR_CENTRE),
+        5 => Some(surround),
+        6 => Some(surround | Channels::LFE1),
+        7 => {
+            Some(surround | Channels::FRONT_LEFT_WIDE | Channels::FRONT_RIGHT_WIDE | Channels::LFE1)
+        }
+        _ => None,
+    }
+}
+
+/// The sample rates of an AAC sample rate index.
+const AAC_SAMPLE_RATES: [u32; 13] =
+    [96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350];
+
+/// The bit rates, in kbps, of MPEG-1 layer 1, layer 2, and layer 3, and MPEG-2 layer 1, and
+/// layers 2 and 3.
+const MPA_BIT_RATES: [[u32; 15]; 5] = [
+    [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+    [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// Reads the header of an MPEG audio frame. Returns the length of the frame, the number of
+/// samples per channel, and the codec parameters.
+fn read_mpa_header(b: &[u8]) -> Option<(usize, u64, StreamInfo)> {
+    if b[0] != 0xff || b[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+
+    // The version is 0 for MPEG-2.5, 2 for MPEG-2, and 3 for MPEG-1. The layer is 4 - layer.
+    let version = (b[1] >> 3) & 0x3;
+    let layer = 4 - ((b[1] >> 1) & 0x3);
+    let bit_rate_idx = usize::from(b[2] >> 4);
+    let sample_rate_idx = usize::from((b[2] >> 2) & 0x3);
+    let padding = u32::from((b[2] >> 1) & 0x1);
+
+    // Free format streams are not supported.
+    if version == 1 || layer == 4 || bit_rate_idx == 0 || bit_rate_idx == 15 || sample_rate_idx == 3
+    {
+        return None;
+    }
+
+    let is_mpeg1 = version == 3;
+
+    let bit_rate = 1000
+        * match (is_mpeg1, layer) {
+            (true, _) => MPA_BIT_RATES[usize::from(layer - 1)][bit_rate_idx],
+            (false, 1) => MPA_BIT_RATES[3][bit_rate_idx],
+            (false, _) => MPA_BIT_RATES[4][bit_rate_idx],
+        };
+
+    let sample_rate = [44100, 48000, 32000][sample_rate_idx]
+        >> match version {
+            3 => 0,
+            2 => 1,
+            _ => 2,
+        };
+
+    let (len, dur, codec) = match layer {
+        1 => ((12 * bit_rate / sample_rate + padding) * 4, 384, CODEC_TYPE_MP1),
+        2 => (144 * bit_rate / sample_rate + padding, 1152, CODEC_TYPE_MP2),
+        _ if is_mpeg1 => (144 * bit_rate / sample_rate + padding, 1152, CODEC_TYPE_MP3),
+        _ => (72 * bit_rate / sample_rate + padding, 576, CODEC_TYPE_MP3),
+    };
+
+    let channels = match b[3] >> 6 {
+        3 => Channels::FRONT_LEFT,
+        _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+    };
+
+    let info = StreamInfo { codec, sample_rate, channels: Some(channels), extra_data: None };
+
+    Some((len as usize, dur, info))
+}
+
+/// Reads the header of an ADTS frame. Returns the length of the frame, the length of the header,
+/// and the codec parameters.
+fn read_adts_header(b: &[u8]) -> Option<(usize, usize, StreamInfo)> {
+    // The layer must be 0.
+    if b[0] != 0xff || b[1] & 0xf6 != 0xf0 {
+        return None;
+    }
+
+    let is_protected = b[1] & 0x1 == 0;
+    let sample_rate_idx = usize::from((b[2] >> 2) & 0xf);
+    let channel_config = u32::from(((b[2] & 0x1) << 2) | (b[3] >> 6));
+    let len = (usize::from(b[3] & 0x3) << 11) | (usize::from(b[4]) << 3) | usize::from(b[5] >> 5);
+
+    let header_len = if is_protected { 9 } else { 7 };
+
+    if sample_rate_idx >= AAC_SAMPLE_RATES.len() || len < header_len {
+        return None;
+    }
+
+    // Like the ADTS reader, the codec parameters do not include an audio specific config.
+    let info = StreamInfo {
+        codec: CODEC_TYPE_AAC,
+        sample_rate: AAC_SAMPLE_RATES[sample_rate_idx],
+        channels: aac_channels(channel_config),
+        extra_data: None,
+    };
+
+    Some((len, header_len, info))
+}
+
+/// The number of bytes required to read the header of an AC-3 or E-AC-3 frame.
+const AC3_HEADER_LEN: usize = 8;
+
+/// The bit rates, in kbps, of an AC-3 frame size code.
+const AC3_BIT_RATES: [u32; 19] =
+    [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640];
+
+/// The header of an AC-3 or E-AC-3 frame.
+struct Ac3Header {
+    len: usize,
+    /// If true, the frame is a frame of independent substream 0.
+    starts_access_unit: bool,
+    dur: u64,
+    info: StreamInfo,
+}
+
+/// Reads the header of an AC-3 or E-AC-3 frame.
+fn read_ac3_header(b: &[u8]) -> Option<Ac3Header> {
+    if b[..2] != [0x0b, 0x77] {
+        return None;
+    }
+
+    let bsid = b[5] >> 3;
+
+    let (len, starts_access_unit, dur, sample_rate, acmod, lfeon) = match bsid {
+        0..=10 => {
+            let fscod = b[4] >> 6;
+            let frmsizecod = usize::from(b[4] & 0x3f);
+
+            if fscod == 3 || frmsizecod >= 2 * AC3_BIT_RATES.len() {
+                return None;
+            }
+
+            let bit_rate = AC3_BIT_RATES[frmsizecod >> 1];
+
+            // The number of 16-bit words in the frame.
+            let words = match fscod {
+                0 => 2 * bit_rate,
+                1 => bit_rate * 320 / 147 + (frmsizecod as u32 & 0x1),
+                _ => 3 * bit_rate,
+            };
+
+            // The low frequency effects channel follows optional mix levels.
+            let acmod = b[6] >> 5;
+
+            let mut bits = 3;
+
+            if acmod & 0x1 != 0 && acmod != 1 {
+                bits += 2;
+            }
+            if acmod & 0x4 != 0 {
+                bits += 2;
+            }
+            if acmod == 2 {
+                bits += 2;
+            }
+
+            let lfeon = (u16::from_be_bytes([b[6], b[7]]) >> (15 - bits)) & 0x1 != 0;
+
+            let sample_rate = [48000, 44100, 32000][usize::from(fscod)];
+
+            (2 * words as usize, true, 1536, sample_rate, acmod, lfeon)
+        }
+        11..=16 => {
+            let strmtyp = b[2] >> 6;
+            let substreamid = (b[2] >> 3) & 0x7;
+            let words = ((usize::from(b[2] & 0x7) << 8) | usize::from(b[3])) + 1;
+            let fscod = b[4] >> 6;
+
+            if strmtyp == 3 {
+                return None;
+            }
+
+            let (sample_rate, num_blocks) = if fscod == 3 {
+                match (b[4] >> 4) & 0x3 {
+                    3 => return None,
+                    fscod2 => ([24000, 22050, 16000][usize::from(fscod2)], 6),
+                }
+            }
+            else {
+                (
+                    [48000, 44100, 32000][usize::from(fscod)],
+                    [1, 2, 3, 6][usize::from((b[4] >> 4) & 0x3)],
+                )
+            };
+
+            let acmod = (b[4] >> 1) & 0x7;
+            let lfeon = b[4] & 0x1 != 0;
+
+            let starts_access_unit = strmtyp != 1 && substreamid == 0;
+
+            (2 * words, starts_access_unit, 256 * num_blocks, sample_rate, acmod, lfeon)
+        }
+        _ => return None,
+    };
+
+    let mut channels = match acmod {
+        1 => Channels::FRONT_LEFT,
+        0 | 2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3 => Channels::FRONT_LEFT | Channels::FRONT_CENTRE | Channels::FRONT_RIGHT,
+        4 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::REAR_CENTRE,
+        5 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_CENTRE
+                | Channels::FRONT_RIGHT
+                | Channels::REAR_CENTRE
+        }
+        6 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+        }
+        _ => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_CENTRE
+                | Channels::FRONT_RIGHT
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+        }
+    };
+
+    if lfeon {
+        channels |= Channels::LFE1;
+    }
+
+    let info = StreamInfo {
+        codec: CODEC_TYPE_EAC3,
+        sample_rate,
+        channels: Some(channels),
+        extra_data: None,
+    };
+
+    Some(Ac3Header { len, starts_access_unit, dur, info })
+}
+
+/// The number of bytes required to read the header of a DTS core frame.
+const DTS_HEADER_LEN: usize = 12;
+
+/// The number of bytes required to read the length of a DTS-HD extension substream frame.
+const DTS_EXSS_HEADER_LEN: usize = 10;
+
+/// The sample rates of a DTS core frame sample rate code. A rate of 0 is invalid.
+const DTS_SAMPLE_RATES: [u32; 16] =
+    [0, 8000, 16000, 32000, 0, 0, 11025, 22050, 44100, 0, 0, 12000, 24000, 48000, 0, 0];
+
+/// Reads the header of a 16-bit big-endian DTS core frame. Returns the length of the frame, the
+/// number of samples per channel, and the codec parameters.
+fn read_dts_header(b: &[u8]) -> Option<(usize, u64, StreamInfo)> {
+    let mut bs = BitReaderLtr::new(b);
+
+    if bs.read_bits_leq32(32).ok()? != 0x7ffe_8001 {
+        return None;
+    }
+
+    // The frame type, deficit sample count, and CRC present flag.
+    bs.ignore_bits(7).ok()?;
+
+    let num_blocks = bs.read_bits_leq32(7).ok()? + 1;
+    let len = bs.read_bits_leq32(14).ok()? as usize + 1;
+    let amode = bs.read_bits_leq32(6).ok()?;
+    let sample_rate = DTS_SAMPLE_RATES[bs.read_bits_leq32(4).ok()? as usize];
+
+    // The bit rate, and the mixing, dynamic range, time stamp, auxiliary data, HDCD, and
+    // extension flags.
+    bs.ignore_bits(15).ok()?;
+
+    let lfe = bs.read_bits_leq32(2).ok()?;
+
+    if num_blocks < 6 || len < 96 || sample_rate == 0 || lfe == 3 {
+        return None;
+    }
+
+    let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    let side = Channels::SIDE_LEFT | Channels::SIDE_RIGHT;
+
+    let channels = match amode {
+        0 => Some(Channels::FRONT_CENTRE),
+        1..=4 => Some(front),
+        5 => Some(front | Channels::FRONT_CENTRE),
+        6 => Some(front | Channels::REAR_CENTRE),
+        7 => Some(front | Channels::FRONT_CENTRE | Channels::REAR_CENTRE),
+        8 => Some(front | side),
+        9 => Some(front | Channels::FRONT_CENTRE | side),
+        _ => None,
+    };
+
+    let channels = channels.map(|channels| {
+        if lfe != 0 {
+            channels | Channels::LFE1
+        }
+        else {
+            channels
+        }
+    });
+
+    let info = StreamInfo { codec: CODEC_TYPE_DCA, sample_rate, channels, extra_data: None };
+
+    Some((len, 32 * u64::from(num_blocks), info))
+}
+
+/// Reads the length of a DTS-HD extension substream frame.
+fn read_dts_exss_len(b: &[u8]) -> Option<usize> {
+    let mut bs = BitReaderLtr::new(b);
+
+    if bs.read_bits_leq32(32).ok()? != 0x6458_2025 {
+        return None;
+    }
+
+    // The user defined bits, and the extension substream index.
+    bs.ignore_bits(10).ok()?;
+
+    // A wide header has larger header and frame size fields.
+    let (header_bits, size_bits) = if bs.read_bool().ok()? { (12, 20) } else { (8, 16) };
+
+    let header_len = bs.read_bits_leq32(header_bits).ok()? as usize + 1;
+    let len = bs.read_bits_leq32(size_bits).ok()? as usize + 1;
+
+    if len < header_len {
+        return None;
+    }
+
+    Some(len)
+}
+
+/// A LATM StreamMuxConfig.
+struct StreamMuxConfig {
+    num_sub_frames: u32,
+    /// The AudioSpecificConfig of the only layer.
+    asc: Box<[u8]>,
+    sample_rate: u32,
+    channels: Option<Channels>,
+    /// The number of samples per channel in a payload.
+    samples: u64,
+}
+
+impl StreamMuxConfig {
+    fn read(bs: &mut BitReaderLtr<'_>, buf: &[u8]) -> Result<Self> {
+        let audio_mux_version = bs.read_bit()?;
+
+        let audio_mux_version_a = if audio_mux_version == 1 { bs.read_bit()? } else { 0 };
+
+        if audio_mux_version_a != 0 {
+            return unsupported_error("mpeg: latm audio mux version a is not supported");
+        }
+
+        if audio_mux_version == 1 {
+            let _tara_buffer_fullness = latm_get_value(bs)?;
+        }
+
+        let all_streams_same_time_framing = bs.read_bool()?;
+        let num_sub_frames = bs.read_bits_leq32(6)?;
+        let num_program = bs.read_bits_leq32(4)? + 1;
+        let num_layer = bs.read_bits_leq32(3)? + 1;
+
+        if num_program > 1 || num_layer > 1 || !all_streams_same_time_framing {
+            return unsupported_error("mpeg: unsupported latm stream mux config");
+        }
+
+        let asc_len = if audio_mux_version == 1 { Some(latm_get_value(bs)?) } else { None };
+
+        let asc_start = bit_pos(bs, buf);
+
+        let (sample_rate, channels, samples) = read_audio_specific_config(bs)?;
+
+        let asc_end = bit_pos(bs, buf);
+
+        if let Some(asc_len) = asc_len {
+            let read_len = asc_end - asc_start;
+
+            if u64::from(asc_len) < read_len {
+                return decode_error("mpeg: audio specific config exceeds the stated length");
+            }
+
+            bs.ignore_bits(asc_len - read_len as u32)?;
+        }
+
+        let asc = copy_bits(buf, asc_start, asc_end - asc_start)?;
+
+        if bs.read_bits_leq32(3)? != 0 {
+            return unsupported_error("mpeg: only variable length latm payloads are supported");
+        }
+
+        let _latm_buffer_fullness = bs.read_bits_leq32(8)?;
+
+        let other_data_present = bs.read_bool()?;
+
+        if other_data_present {
+            if audio_mux_version == 1 {
+                let _other_data_len_bits = latm_get_value(bs)?;
+            }
+            else {
+                loop {
+                    let other_data_len_esc = bs.read_bool()?;
+                    let _other_data_len_tmp = bs.read_bits_leq32(8)?;
+
+                    if !other_data_len_esc {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let crc_check_present = bs.read_bool()?;
+
+        if crc_check_present {
+            let _crc_check_sum = bs.read_bits_leq32(8)?;
+        }
+
+        Ok(StreamMuxConfig { num_sub_frames, asc, sample_rate, channels, samples })
+    }
+}
+
+/// Reads an AudioSpecificConfig of an AAC object type, and returns the sample rate, channels,
+/// and number of samples per channel in a frame.
+fn read_audio_specific_config(bs: &mut BitReaderLtr<'_>) -> Result<(u32, Option<Channels>, u64)> {
+    fn read_object_type(bs: &mut BitReaderLtr<'_>) -> Result<u32> {
+        match bs.read_bits_leq32(5)? {
+            31 => Ok(32 + bs.read_bits_leq32(6)?),
+            object_type => Ok(object_type),
+        }
+    }
+
+    fn read_sample_rate(bs: &mut BitReaderLtr<'_>) -> Result<u32> {
+        match bs.read_bits_leq32(4)? as usize {
+            idx if idx < AAC_SAMPLE_RATES.len() => Ok(AAC_SAMPLE_RATES[idx]),
+            15 => Ok(bs.read_bits_leq32(24)?),
+            _ => decode_error("mpeg: invalid aac sample rate"),
+        }
+    }
+
+    let mut object_type = read_object_type(bs)?;
+    let sample_rate = read_sample_rate(bs)?;
+    let channel_config = bs.read_bits_leq32(4)?;
+
+    // Explicit signalling of spectral band replication and parametric stereo.
+    if object_type == 5 || object_type == 29 {
+        let _ext_sample_rate = read_sample_rate(bs)?;
+        object_type = read_object_type(bs)?;
+    }
+
+    // Only the GASpecificConfig of the AAC Main, LC, SSR, and LTP object types is supported.
+    if !(1..=4).contains(&object_type) {
+        return unsupported_error("mpeg: unsupported aac object type");
+    }
+
+    let frame_length_flag = bs.read_bool()?;
+
+    if bs.read_bool()? {
+        let _core_coder_delay = bs.read_bits_leq32(14)?;
+    }
+
+    let extension_flag = bs.read_bool()?;
+
+    if channel_config == 0 {
+        return unsupported_error("mpeg: aac program config element is not supported");
+    }
+
+    if extension_flag {
+        let _extension_flag3 = bs.read_bool()?;
+    }
+
+    let samples = if frame_length_flag { 960 } else { 1024 };
+
+    Ok((sample_rate, aac_channels(channel_config), samples))
+}
+
+/// Reads an AudioSpecificConfig, and returns the sample rate, channels, and number of samples per
+/// channel in a frame.
+pub fn read_aac_config(buf: &[u8]) -> Result<(u32, Option<Channels>, u64)> {
+    read_audio_specific_config(&mut BitReaderLtr::new(buf))
+}
+
+/// Reads a LATM variable length value.
+fn latm_get_value(bs: &mut BitReaderLtr<'_>) -> Result<u32> {
+    let bytes_for_value = bs.read_bits_leq32(2)?;
+
+    let mut value = 0;
+
+    for _ in 0..=bytes_for_value {
+        value = (value << 8) | bs.read_bits_leq32(8)?;
+    }
+
+    Ok(value)
+}
+
+/// Gets the position of the bit reader, in bits, relative to the start of the buffer it is reading.
+fn bit_pos(bs: &BitReaderLtr<'_>, buf: &[u8]) -> u64 {
+    8 * buf.len() as u64 - bs.bits_left()
+}
+
+/// Copies `len` bits starting at bit `start` from the buffer into a new byte-aligned buffer.
+fn copy_bits(buf: &[u8], start: u64, len: u64) -> Result<Box<[u8]>> {
+    let mut bs = BitReaderLtr::new(&buf[(start >> 3) as usize..]);
+    bs.ignore_bits((start & 0x7) as u32)?;
+
+    let mut out = vec![0; ((len + 7) >> 3) as usize];
+
+    for (i, byte) in out.iter_mut().enumerate() {
+        let num_bits = (len - 8 * i as u64).min(8) as u32;
+        *byte = (bs.read_bits_leq32(num_bits)? << (8 - num_bits)) as u8;
+    }
+
+    Ok(out.into_boxed_slice())
+}
+
+/// Reads an AudioMuxElement, with an in-band StreamMuxConfig, and returns the payloads contained
+/// within.
+fn read_audio_mux_element(
+    buf: &[u8],
+    config: &mut Option<StreamMuxConfig>,
+) -> Result<Vec<Box<[u8]>>> {
+    let mut bs = BitReaderLtr::new(buf);
+
+    let use_same_stream_mux = bs.read_bool()?;
+
+    if !use_same_stream_mux {
+        *config = Some(StreamMuxConfig::read(&mut bs, buf)?);
+    }
+
+    let config = match config {
+        Some(config) => config,
+        _ => return decode_error("mpeg: missing latm stream mux config"),
+    };
+
+    let mut payloads = Vec::with_capacity(config.num_sub_frames as usize + 1);
+
+    for _ in 0..=config.num_sub_frames {
+        // PayloadLengthInfo
+        let mut len = 0;
+
+        loop {
+            let tmp = bs.read_bits_leq32(8)?;
+            len += tmp as usize;
+
+            if tmp != 255 {
+                break;
+            }
+        }
+
+        // PayloadMux
+        let mut payload = vec![0; len];
+
+        for byte in payload.iter_mut() {
+            *byte = bs.read_bits_leq32(8)? as u8;
+        }
+
+        payloads.push(payload.into_boxed_slice());
+    }
+
+    Ok(payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_frame_parser_mpa() {
+        // MPEG-1 layer 2, 48 kHz, 64 kbps, stereo: 192 bytes per frame.
+        let mut frame = vec![0; 192];
+        frame[..4].copy_from_slice(&[0xff, 0xfd, 0x44, 0x00]);
+
+        let mut parser = FrameParser::new(EsCodec::Mpa);
+
+        // Junk, followed by a PES packet with two frames, and a PES packet with one frame.
+        parser.push(&[0x12, 0xff], None);
+        parser.push(&frame, Some(1000));
+        parser.push(&frame, None);
+
+        let first = parser.next_frame(false).unwrap().unwrap();
+        assert_eq!(first.pts, Some(1000));
+        assert_eq!(first.dur, 1152);
+        assert_eq!(first.data.len(), 192);
+
+        // The second frame cannot be returned until the start of the next frame is known.
+        assert!(parser.next_frame(false).unwrap().is_none());
+
+        parser.push(&frame, Some(4456));
+
+        let second = parser.next_frame(false).unwrap().unwrap();
+        assert_eq!(second.pts, None);
+
+        let third = parser.next_frame(true).unwrap().unwrap();
+        assert_eq!(third.pts, Some(4456));
+
+        assert!(parser.next_frame(true).unwrap().is_none());
+
+        let info = parser.info.unwrap();
+        assert_eq!(info.codec, CODEC_TYPE_MP2);
+        assert_eq!(info.sample_rate, 48000);
+    }
+}
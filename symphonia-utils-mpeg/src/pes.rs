@@ -0,0 +1,268 @@
+// Symphonia
+// Copyright (c) 2019-2024 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+/// The length of the PES packet header up-to, and including, the PES packet length.
+const PES_START_LEN: usize = 6;
+
+/// The length of the PES packet header up-to, and including, the PES header data length.
+const PES_HEADER_LEN: usize = 9;
+
+/// The maximum number of stuffing bytes in the header of an MPEG-1 PES packet.
+const MAX_MPEG1_STUFFING_LEN: usize = 16;
+
+/// The frequency of presentation timestamps.
+pub const PTS_FREQ: u64 = 90_000;
+
+/// The mask of a 33-bit presentation timestamp.
+pub const PTS_MASK: u64 = (1 << 33) - 1;
+
+/// Converts a presentation timestamp to a timestamp, in samples, relative to the start of the
+/// stream. Presentation timestamps preceding the start are clamped to 0.
+pub fn pts_to_ts(pts: u64, start_pts: u64, sample_rate: u32) -> u64 {
+    let delta = pts.wrapping_sub(start_pts) & PTS_MASK;
+
+    // Timestamps wrap around, so a delta greater than half the range is negative.
+    if delta > PTS_MASK / 2 {
+        0
+    }
+    else {
+        delta * u64::from(sample_rate) / PTS_FREQ
+    }
+}
+
+/// The header of a PES packet.
+struct PesHeader {
+    /// The total length of the header.
+    len: usize,
+    /// The presentation timestamp in 90 kHz units.
+    pts: Option<u64>,
+}
+
+/// Reads the header of a PES packet. Returns `None` if the buffer does not contain the entire
+/// header.
+fn read_pes_header(buf: &[u8]) -> Result<Option<PesHeader>> {
+    if buf.len() < PES_START_LEN {
+        return Ok(None);
+    }
+
+    if buf[..3] != [0x00, 0x00, 0x01] {
+        return decode_error("mpeg: missing pes start code");
+    }
+
+    // Some streams do not have the optional PES header: the program stream map, padding, private
+    // stream 2, ECM, EMM, the program stream directory, DSMCC, and H.222.1 type E streams.
+    if matches!(buf[3], 0xbc | 0xbe | 0xbf | 0xf0 | 0xf1 | 0xf2 | 0xf8 | 0xff) {
+        return Ok(Some(PesHeader { len: PES_START_LEN, pts: None }));
+    }
+
+    // MPEG-1 PES packets, found in MPEG-1 program streams, have a different header.
+    if buf.get(6).map_or(false, |&b| b & 0xc0 != 0x80) {
+        return read_mpeg1_pes_header(buf);
+    }
+
+    if buf.len() < PES_HEADER_LEN {
+        return Ok(None);
+    }
+
+    let len = PES_HEADER_LEN + usize::from(buf[8]);
+
+    if buf.len() < len {
+        return Ok(None);
+    }
+
+    let pts = if buf[7] & 0x80 != 0 && len >= PES_HEADER_LEN + 5 {
+        Some(read_timestamp(&buf[9..14]))
+    }
+    else {
+        None
+    };
+
+    Ok(Some(PesHeader { len, pts }))
+}
+
+/// Reads the header of an MPEG-1 PES packet.
+fn read_mpeg1_pes_header(buf: &[u8]) -> Result<Option<PesHeader>> {
+    let mut pos = PES_START_LEN;
+
+    // The header may start with stuffing bytes.
+    while buf.get(pos) == Some(&0xff) {
+        pos += 1;
+    }
+
+    if pos - PES_START_LEN > MAX_MPEG1_STUFFING_LEN {
+        return decode_error("mpeg: too many pes stuffing bytes");
+    }
+
+    // The optional buffer scale and size.
+    if buf.get(pos).map_or(false, |&b| b & 0xc0 == 0x40) {
+        pos += 2;
+    }
+
+    let (len, has_pts) = match buf.get(pos) {
+        // A PTS, or a PTS and a DTS.
+        Some(&b) if b & 0xf0 == 0x20 => (5, true),
+        Some(&b) if b & 0xf0 == 0x30 => (10, true),
+        Some(&0x0f) => (1, false),
+        Some(_) => return decode_error("mpeg: invalid pes header"),
+        None => return Ok(None),
+    };
+
+    if buf.len() < pos + len {
+        return Ok(None);
+    }
+
+    let pts = if has_pts { Some(read_timestamp(&buf[pos..pos + 5])) } else { None };
+
+    Ok(Some(PesHeader { len: pos + len, pts }))
+}
+
+/// Reads a complete PES packet, and returns its payload.
+pub fn read_pes_packet(buf: &[u8]) -> Result<PesPayload<'_>> {
+    match read_pes_header(buf)? {
+        Some(header) => Ok(PesPayload { pts: header.pts, data: &buf[header.len..] }),
+        None => decode_error("mpeg: incomplete pes header"),
+    }
+}
+
+/// Reads the presentation timestamp of a PES packet starting in the payload of a transport stream
+/// packet, if the header is entirely contained within it.
+pub fn read_pes_pts(payload: &[u8]) -> Option<u64> {
+    read_pes_header(payload).ok().flatten().and_then(|header| header.pts)
+}
+
+/// Reads a 33-bit timestamp split over 5 bytes with marker bits.
+pub fn read_timestamp(b: &[u8]) -> u64 {
+    (u64::from((b[0] >> 1) & 0x7) << 30)
+        | (u64::from(b[1]) << 22)
+        | (u64::from(b[2] >> 1) << 15)
+        | (u64::from(b[3]) << 7)
+        | u64::from(b[4] >> 1)
+}
+
+/// The payload of a PES packet carried by a transport stream packet.
+pub struct PesPayload<'a> {
+    /// The presentation timestamp of the PES packet, if the payload is the start of the PES
+    /// packet.
+    pub pts: Option<u64>,
+    pub data: &'a [u8],
+}
+
+enum PesState {
+    /// Waiting for the start of a PES packet.
+    Idle,
+    /// Reading the header of a PES packet.
+    Header(Vec<u8>),
+    /// Reading the payload of a PES packet.
+    Payload,
+}
+
+/// A reader of the PES packets carried by the packets of a PID.
+pub struct PesReader {
+    state: PesState,
+}
+
+impl Default for PesReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PesReader {
+    pub fn new() -> Self {
+        PesReader { state: PesState::Idle }
+    }
+
+    /// Discards the PES packet being read. Data is ignored until the start of the next PES packet.
+    pub fn reset(&mut self) {
+        self.state = PesState::Idle;
+    }
+
+    /// Adds the payload of a transport stream packet, and returns the part of it that is the
+    /// payload of a PES packet, if any.
+    pub fn push<'a>(&mut self, payload: &'a [u8], payload_start: bool) -> Result<PesPayload<'a>> {
+        if payload_start {
+            self.state = PesState::Header(Vec::new());
+        }
+
+        match &mut self.state {
+            PesState::Idle => Ok(PesPayload { pts: None, data: &[] }),
+            PesState::Header(buf) => {
+                let prev_len = buf.len();
+
+                buf.extend_from_slice(payload);
+
+                let header = match read_pes_header(buf) {
+                    Ok(Some(header)) => header,
+                    Ok(None) => return Ok(PesPayload { pts: None, data: &[] }),
+                    Err(err) => {
+                        self.state = PesState::Idle;
+                        return Err(err);
+                    }
+                };
+
+                self.state = PesState::Payload;
+
+                Ok(PesPayload { pts: header.pts, data: &payload[header.len - prev_len..] })
+            }
+            PesState::Payload => Ok(PesPayload { pts: None, data: payload }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_pes_reader() {
+        // An audio PES packet with a PTS of 0x1_2345_6789, and a header split over two packets.
+        let header = [0x00, 0x00, 0x01, 0xc0, 0x00, 0x00, 0x80, 0x80, 0x05];
+        let pts = [0x29, 0x8d, 0x15, 0xcf, 0x13];
+
+        let mut reader = PesReader::new();
+
+        // Data preceding the start of a PES packet is ignored.
+        assert!(reader.push(&[1, 2, 3], false).unwrap().data.is_empty());
+
+        let payload = reader.push(&header, true).unwrap();
+        assert!(payload.pts.is_none());
+        assert!(payload.data.is_empty());
+
+        let mut rest = pts.to_vec();
+        rest.extend_from_slice(&[4, 5]);
+
+        let payload = reader.push(&rest, false).unwrap();
+        assert_eq!(payload.pts, Some(0x1_2345_6789));
+        assert_eq!(payload.data, &[4, 5]);
+
+        let payload = reader.push(&[6], false).unwrap();
+        assert!(payload.pts.is_none());
+        assert_eq!(payload.data, &[6]);
+    }
+
+    #[test]
+    fn verify_read_mpeg1_pes_packet() {
+        // An MPEG-1 audio PES packet with stuffing, the buffer size, and a PTS of 0x1_2345_6789.
+        let buf = [
+            0x00, 0x00, 0x01, 0xc0, 0x00, 0x0c, 0xff, 0xff, 0x40, 0x20, 0x29, 0x8d, 0x15, 0xcf,
+            0x13, 0x07, 0x08,
+        ];
+
+        let payload = read_pes_packet(&buf).unwrap();
+        assert_eq!(payload.pts, Some(0x1_2345_6789));
+        assert_eq!(payload.data, &[0x07, 0x08]);
+
+        // Without a PTS.
+        let buf = [0x00, 0x00, 0x01, 0xc0, 0x00, 0x02, 0x0f, 0x07];
+
+        let payload = read_pes_packet(&buf).unwrap();
+        assert_eq!(payload.pts, None);
+        assert_eq!(payload.data, &[0x07]);
+    }
+}
@@ -11,7 +11,7 @@ use symphonia_core::audio::Channels;
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::formats::{util::SeekIndex, Cue, CuePoint};
 use symphonia_core::io::*;
-use symphonia_core::meta::{StandardTagKey, Tag, Value, VendorData};
+use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value, VendorData};
 
 #[derive(PartialEq, Eq)]
 pub enum MetadataBlockType {
@@ -239,12 +239,19 @@ fn printable_ascii_to_string(bytes: &[u8]) -> Option<String> {
 }
 
 /// Read a cuesheet block.
-pub fn read_cuesheet_block<B: ReadBytes>(reader: &mut B, cues: &mut Vec<Cue>) -> Result<()> {
+///
+/// Every track becomes a `Cue` with its index points as `CuePoint`s. The last track is the
+/// lead-out track, and is tagged `LEAD_OUT`. The catalog number, if set, is added to `metadata`.
+pub fn read_cuesheet_block<B: ReadBytes>(
+    reader: &mut B,
+    cues: &mut Vec<Cue>,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
     // Read cuesheet catalog number. The catalog number only allows printable ASCII characters.
     let mut catalog_number_buf = vec![0u8; 128];
     reader.read_buf_exact(&mut catalog_number_buf)?;
 
-    let _catalog_number = match printable_ascii_to_string(&catalog_number_buf) {
+    let catalog_number = match printable_ascii_to_string(&catalog_number_buf) {
         Some(s) => s,
         None => return decode_error("flac: cuesheet catalog number contains invalid characters"),
     };
@@ -279,8 +286,16 @@ pub fn read_cuesheet_block<B: ReadBytes>(reader: &mut B, cues: &mut Vec<Cue>) ->
         return decode_error("flac: cuesheets for CD-DA must not have more than 100 tracks");
     }
 
-    for _ in 0..n_tracks {
-        read_cuesheet_track(reader, is_cdda, cues)?;
+    for i in 0..n_tracks {
+        read_cuesheet_track(reader, is_cdda, i == n_tracks - 1, cues)?;
+    }
+
+    if !catalog_number.is_empty() {
+        metadata.add_tag(Tag::new(
+            Some(StandardTagKey::IdentCatalogNumber),
+            "CATALOG",
+            Value::from(catalog_number),
+        ));
     }
 
     Ok(())
@@ -289,6 +304,7 @@ pub fn read_cuesheet_block<B: ReadBytes>(reader: &mut B, cues: &mut Vec<Cue>) ->
 fn read_cuesheet_track<B: ReadBytes>(
     reader: &mut B,
     is_cdda: bool,
+    is_lead_out: bool,
     cues: &mut Vec<Cue>,
 ) -> Result<()> {
     let n_offset_samples = reader.read_be_u64()?;
@@ -330,9 +346,8 @@ fn read_cuesheet_track<B: ReadBytes>(
     // in u16 chunks a minor performance improvement.
     let flags = reader.read_be_u16()?;
 
-    // These values are contained in the Cuesheet but have no analogue in Symphonia.
-    let _is_audio = (flags & 0x8000) == 0x0000;
-    let _use_pre_emphasis = (flags & 0x4000) == 0x4000;
+    let is_audio = (flags & 0x8000) == 0x0000;
+    let use_pre_emphasis = (flags & 0x4000) == 0x4000;
 
     if flags & 0x3fff != 0 {
         return decode_error("flac: cuesheet track reserved bits should be zero");
@@ -355,8 +370,23 @@ fn read_cuesheet_track<B: ReadBytes>(
     let mut cue =
         Cue { index: number, start_ts: n_offset_samples, tags: Vec::new(), points: Vec::new() };
 
-    // Push the ISRC as a tag.
-    cue.tags.push(Tag::new(Some(StandardTagKey::IdentIsrc), "ISRC", Value::from(isrc)));
+    // Push the ISRC, if set, and the track flags as tags.
+    if !isrc.is_empty() {
+        cue.tags.push(Tag::new(Some(StandardTagKey::IdentIsrc), "ISRC", Value::from(isrc)));
+    }
+
+    if !is_audio {
+        cue.tags.push(Tag::new(None, "DATA", Value::Flag));
+    }
+
+    if use_pre_emphasis {
+        cue.tags.push(Tag::new(None, "PRE_EMPHASIS", Value::Flag));
+    }
+
+    // The lead-out track marks the end of the last track, and has no index points.
+    if is_lead_out {
+        cue.tags.push(Tag::new(None, "LEAD_OUT", Value::Flag));
+    }
 
     for _ in 0..n_indicies {
         cue.points.push(read_cuesheet_track_index(reader, is_cdda)?);
@@ -384,9 +414,13 @@ fn read_cuesheet_track_index<B: ReadBytes>(reader: &mut B, is_cdda: bool) -> Res
     }
 
     // TODO: Should be 0 or 1 for the first index for CD-DA.
-    let _idx_point = ((idx_point_enc & 0xff00_0000) >> 24) as u8;
+    let idx_point = (idx_point_enc & 0xff00_0000) >> 24;
+
+    // The index point number distinguishes the pre-gap (index 0) from the start of the track
+    // (index 1), and any sub-indicies.
+    let tags = vec![Tag::new(None, "INDEX", Value::from(idx_point))];
 
-    Ok(CuePoint { start_offset_ts: n_offset_samples, tags: Vec::new() })
+    Ok(CuePoint { start_offset_ts: n_offset_samples, tags })
 }
 
 /// Read a vendor-specific application block.
@@ -27,14 +27,41 @@
 //! | Format   | Feature Flag | Gapless* | Default |
 //! |----------|--------------|----------|---------|
 //! | AIFF     | `aiff`       | Yes      | No      |
+//! | AMR‡     | `amr`        | No       | No      |
+//! | ASF      | `asf`        | No       | No      |
+//! | AU       | `au`         | No       | No      |
+//! | AVI      | `avi`        | No       | No      |
 //! | CAF      | `caf`        | No       | No      |
+//! | DSDIFF   | `dsdiff`     | No       | No      |
+//! | DSF      | `dsf`        | No       | No      |
+//! | DTS‡     | `dca`        | No       | No      |
+//! | FLV      | `flv`        | No       | No      |
+//! | G.722†   | `adpcm`      | No       | Yes     |
+//! | GSM      | `gsm`        | No       | No      |
 //! | ISO/MP4  | `isomp4`     | No       | No      |
 //! | MKV/WebM | `mkv`        | No       | Yes     |
+//! | MLP      | `mlp`        | No       | No      |
+//! | MPEG-PS  | `mpegps`     | No       | No      |
+//! | MPEG-TS  | `mpegts`     | No       | No      |
+//! | Musepack‡ | `musepack`  | Yes      | No      |
 //! | OGG      | `ogg`        | Yes      | Yes     |
+//! | OMA‡     | `oma`        | No       | No      |
+//! | Raw PCM† | `raw`        | No       | No      |
+//! | RealMedia | `rm`        | No       | No      |
+//! | SACD     | `sacd`       | No       | No      |
+//! | SBC      | `sbc`        | No       | No      |
+//! | TAK‡     | `tak`        | Yes      | No      |
+//! | VOC      | `voc`        | No       | No      |
 //! | Wave     | `wav`        | Yes      | Yes     |
 //!
 //! \* Gapless playback requires support from both the demuxer and decoder.
 //!
+//! † Raw PCM and G.722 streams cannot be probed. They must be read with
+//! `RawReader::try_new_with_params` and `G722Reader::try_new`, respectively.
+//!
+//! ‡ Only the demuxer is implemented. The decoder is still in work, so the demuxed packets cannot
+//! be decoded yet.
+//!
 //! **Tip:** All formats can be enabled with the `all-formats` feature flag.
 //!
 //! ## Codecs
@@ -44,13 +71,22 @@
 //! | Codec    | Feature Flag | Gapless | Default |
 //! |----------|--------------|---------|---------|
 //! | AAC-LC   | `aac`        | No      | No      |
+//! | AC-3     | `ac3`        | No      | No      |
+//! | E-AC-3   | `ac3`        | No      | No      |
 //! | ADPCM    | `adpcm`      | Yes     | Yes     |
 //! | ALAC     | `alac`       | Yes     | No      |
+//! | APE      | `ape`        | Yes     | No      |
+//! | DSD      | `dsd`        | No      | No      |
+//! | DST      | `dsd`        | No      | No      |
 //! | FLAC     | `flac`       | Yes     | Yes     |
+//! | GSM 6.10 | `gsm`        | No      | No      |
 //! | MP1      | `mp1`, `mpa` | No      | No      |
 //! | MP2      | `mp2`, `mpa` | No      | No      |
 //! | MP3      | `mp3`, `mpa` | Yes     | No      |
 //! | PCM      | `pcm`        | Yes     | Yes     |
+//! | SBC      | `sbc`        | No      | No      |
+//! | Shorten  | `shorten`    | Yes     | No      |
+//! | TTA      | `tta`        | Yes     | No      |
 //! | Vorbis   | `vorbis`     | Yes     | Yes     |
 //!
 //! **Tip:** All codecs can be enabled with the `all-codecs` feature flag. Similarly, all MPEG
@@ -60,6 +96,7 @@
 //!
 //! The following metadata tagging formats are supported. These are always enabled.
 //!
+//! * APEv1 & APEv2 (in APE)
 //! * ID3v1
 //! * ID3v2
 //! * ISO/MP4
@@ -80,6 +117,11 @@
 //!
 //! **Tip:** All SIMD optimizations can be enabled with the `opt-simd` feature flag.
 //!
+//! ## Segmented Streams
+//!
+//! A `SegmentedSource`, which concatenates the segments of a segmented stream such as an HLS
+//! playlist into a single media source, may be enabled with the `segmented-source` feature flag.
+//!
 //! # Usage
 //!
 //! The following steps describe a basic usage of Symphonia:
@@ -142,18 +184,34 @@ pub mod default {
     pub mod codecs {
         //! The `codecs` module re-exports all enabled Symphonia decoders.
 
+        #[cfg(feature = "ape")]
+        pub use symphonia_bundle_ape::ApeDecoder;
         #[cfg(feature = "flac")]
         pub use symphonia_bundle_flac::FlacDecoder;
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
         pub use symphonia_bundle_mp3::MpaDecoder;
+        #[cfg(feature = "shorten")]
+        pub use symphonia_bundle_shorten::ShortenDecoder;
+        #[cfg(feature = "tta")]
+        pub use symphonia_bundle_tta::TtaDecoder;
         #[cfg(feature = "aac")]
         pub use symphonia_codec_aac::AacDecoder;
+        #[cfg(feature = "ac3")]
+        pub use symphonia_codec_ac3::Ac3Decoder;
         #[cfg(feature = "adpcm")]
         pub use symphonia_codec_adpcm::AdpcmDecoder;
         #[cfg(feature = "alac")]
         pub use symphonia_codec_alac::AlacDecoder;
+        #[cfg(feature = "dsd")]
+        pub use symphonia_codec_dsd::DsdDecoder;
+        #[cfg(feature = "dsd")]
+        pub use symphonia_codec_dsd::DstDecoder;
+        #[cfg(feature = "gsm")]
+        pub use symphonia_codec_gsm::GsmDecoder;
         #[cfg(feature = "pcm")]
         pub use symphonia_codec_pcm::PcmDecoder;
+        #[cfg(feature = "sbc")]
+        pub use symphonia_codec_sbc::SbcDecoder;
         #[cfg(feature = "vorbis")]
         pub use symphonia_codec_vorbis::VorbisDecoder;
 
@@ -165,24 +223,78 @@ pub mod default {
     pub mod formats {
         //! The `formats` module re-exports all enabled Symphonia format readers.
 
+        #[cfg(feature = "ape")]
+        pub use symphonia_bundle_ape::ApeReader;
         #[cfg(feature = "flac")]
         pub use symphonia_bundle_flac::FlacReader;
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
         pub use symphonia_bundle_mp3::MpaReader;
+        #[cfg(feature = "musepack")]
+        pub use symphonia_bundle_musepack::MpcReader;
+        #[cfg(feature = "shorten")]
+        pub use symphonia_bundle_shorten::ShortenReader;
+        #[cfg(feature = "tak")]
+        pub use symphonia_bundle_tak::TakReader;
+        #[cfg(feature = "tta")]
+        pub use symphonia_bundle_tta::TtaReader;
+        #[cfg(feature = "aac")]
+        pub use symphonia_codec_aac::AdifReader;
         #[cfg(feature = "aac")]
         pub use symphonia_codec_aac::AdtsReader;
+        #[cfg(feature = "aac")]
+        pub use symphonia_codec_aac::LoasReader;
+        #[cfg(feature = "ac3")]
+        pub use symphonia_codec_ac3::Ac3Reader;
+        #[cfg(feature = "adpcm")]
+        pub use symphonia_codec_adpcm::G722Reader;
+        #[cfg(feature = "amr")]
+        pub use symphonia_codec_amr::AmrReader;
+        #[cfg(feature = "dca")]
+        pub use symphonia_codec_dca::DcaReader;
+        #[cfg(feature = "gsm")]
+        pub use symphonia_codec_gsm::GsmReader;
+        #[cfg(feature = "mlp")]
+        pub use symphonia_codec_mlp::MlpReader;
+        #[cfg(feature = "sbc")]
+        pub use symphonia_codec_sbc::SbcReader;
+        #[cfg(feature = "asf")]
+        pub use symphonia_format_asf::AsfReader;
+        #[cfg(feature = "au")]
+        pub use symphonia_format_au::AuReader;
+        #[cfg(feature = "avi")]
+        pub use symphonia_format_avi::AviReader;
         #[cfg(feature = "caf")]
         pub use symphonia_format_caf::CafReader;
+        #[cfg(feature = "dsdiff")]
+        pub use symphonia_format_dsdiff::DsdiffReader;
+        #[cfg(feature = "dsf")]
+        pub use symphonia_format_dsf::DsfReader;
+        #[cfg(feature = "flv")]
+        pub use symphonia_format_flv::FlvReader;
         #[cfg(feature = "isomp4")]
         pub use symphonia_format_isomp4::IsoMp4Reader;
         #[cfg(feature = "mkv")]
         pub use symphonia_format_mkv::MkvReader;
+        #[cfg(feature = "mpegps")]
+        pub use symphonia_format_mpegps::MpegPsReader;
+        #[cfg(feature = "mpegts")]
+        pub use symphonia_format_mpegts::MpegTsReader;
         #[cfg(feature = "ogg")]
         pub use symphonia_format_ogg::OggReader;
+        #[cfg(feature = "oma")]
+        pub use symphonia_format_oma::OmaReader;
+        #[cfg(feature = "raw")]
+        pub use symphonia_format_raw::{Endianness, RawEncoding, RawParams, RawReader};
         #[cfg(feature = "aiff")]
         pub use symphonia_format_riff::AiffReader;
         #[cfg(feature = "wav")]
         pub use symphonia_format_riff::WavReader;
+        #[cfg(feature = "rm")]
+        pub use symphonia_format_rm::RmReader;
+        #[cfg(feature = "sacd")]
+        pub use symphonia_format_sacd::SacdReader;
+        #[cfg(feature = "voc")]
+        pub use symphonia_format_voc::VocReader;
 
         #[deprecated = "use `default::formats::MpaReader` instead"]
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
@@ -239,21 +351,45 @@ pub mod default {
         #[cfg(feature = "aac")]
         registry.register_all::<codecs::AacDecoder>();
 
+        #[cfg(feature = "ac3")]
+        registry.register_all::<codecs::Ac3Decoder>();
+
         #[cfg(feature = "adpcm")]
         registry.register_all::<codecs::AdpcmDecoder>();
 
         #[cfg(feature = "alac")]
         registry.register_all::<codecs::AlacDecoder>();
 
+        #[cfg(feature = "ape")]
+        registry.register_all::<codecs::ApeDecoder>();
+
+        #[cfg(feature = "dsd")]
+        registry.register_all::<codecs::DsdDecoder>();
+
+        #[cfg(feature = "dsd")]
+        registry.register_all::<codecs::DstDecoder>();
+
         #[cfg(feature = "flac")]
         registry.register_all::<codecs::FlacDecoder>();
 
+        #[cfg(feature = "gsm")]
+        registry.register_all::<codecs::GsmDecoder>();
+
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
         registry.register_all::<codecs::MpaDecoder>();
 
         #[cfg(feature = "pcm")]
         registry.register_all::<codecs::PcmDecoder>();
 
+        #[cfg(feature = "sbc")]
+        registry.register_all::<codecs::SbcDecoder>();
+
+        #[cfg(feature = "shorten")]
+        registry.register_all::<codecs::ShortenDecoder>();
+
+        #[cfg(feature = "tta")]
+        registry.register_all::<codecs::TtaDecoder>();
+
         #[cfg(feature = "vorbis")]
         registry.register_all::<codecs::VorbisDecoder>();
     }
@@ -268,20 +404,57 @@ pub mod default {
 
         // Formats
         #[cfg(feature = "aac")]
+        probe.register_all::<formats::AdifReader>();
+        #[cfg(feature = "aac")]
         probe.register_all::<formats::AdtsReader>();
+        #[cfg(feature = "aac")]
+        probe.register_all::<formats::LoasReader>();
+
+        #[cfg(feature = "ac3")]
+        probe.register_all::<formats::Ac3Reader>();
+
+        #[cfg(feature = "amr")]
+        probe.register_all::<formats::AmrReader>();
+
+        #[cfg(feature = "ape")]
+        probe.register_all::<formats::ApeReader>();
 
         #[cfg(feature = "caf")]
         probe.register_all::<formats::CafReader>();
 
+        #[cfg(feature = "dca")]
+        probe.register_all::<formats::DcaReader>();
+
+        #[cfg(feature = "dsdiff")]
+        probe.register_all::<formats::DsdiffReader>();
+
+        #[cfg(feature = "dsf")]
+        probe.register_all::<formats::DsfReader>();
+
         #[cfg(feature = "flac")]
         probe.register_all::<formats::FlacReader>();
 
         #[cfg(feature = "isomp4")]
         probe.register_all::<formats::IsoMp4Reader>();
 
+        #[cfg(feature = "mlp")]
+        probe.register_all::<formats::MlpReader>();
+
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
         probe.register_all::<formats::MpaReader>();
 
+        #[cfg(feature = "musepack")]
+        probe.register_all::<formats::MpcReader>();
+
+        #[cfg(feature = "shorten")]
+        probe.register_all::<formats::ShortenReader>();
+
+        #[cfg(feature = "tak")]
+        probe.register_all::<formats::TakReader>();
+
+        #[cfg(feature = "tta")]
+        probe.register_all::<formats::TtaReader>();
+
         #[cfg(feature = "aiff")]
         probe.register_all::<formats::AiffReader>();
 
@@ -291,6 +464,36 @@ pub mod default {
         #[cfg(feature = "ogg")]
         probe.register_all::<formats::OggReader>();
 
+        #[cfg(feature = "oma")]
+        probe.register_all::<formats::OmaReader>();
+
+        #[cfg(feature = "asf")]
+        probe.register_all::<formats::AsfReader>();
+
+        #[cfg(feature = "au")]
+        probe.register_all::<formats::AuReader>();
+
+        #[cfg(feature = "avi")]
+        probe.register_all::<formats::AviReader>();
+
+        #[cfg(feature = "flv")]
+        probe.register_all::<formats::FlvReader>();
+
+        #[cfg(feature = "mpegps")]
+        probe.register_all::<formats::MpegPsReader>();
+
+        #[cfg(feature = "mpegts")]
+        probe.register_all::<formats::MpegTsReader>();
+
+        #[cfg(feature = "rm")]
+        probe.register_all::<formats::RmReader>();
+
+        #[cfg(feature = "sacd")]
+        probe.register_all::<formats::SacdReader>();
+
+        #[cfg(feature = "voc")]
+        probe.register_all::<formats::VocReader>();
+
         #[cfg(feature = "mkv")]
         probe.register_all::<formats::MkvReader>();
 